@@ -119,6 +119,8 @@ impl AutomationHost for CliAutomationHost {
                     ExportFormat::Mp4 => "mp4",
                     ExportFormat::Gif => "gif",
                     ExportFormat::Mov => "mov",
+                    ExportFormat::Hevc | ExportFormat::Av1 => "mp4",
+                    ExportFormat::Webm => "webm",
                 };
                 let name = project_path
                     .file_stem()
@@ -165,6 +167,35 @@ impl AutomationHost for CliAutomationHost {
                 .export(base, |_| true)
                 .await
             }
+            ExportFormat::Hevc | ExportFormat::Av1 => {
+                let settings = cap_export::mp4_codec::Mp4CodecExportSettings {
+                    fps: profile.fps,
+                    resolution_base: profile.resolution_base,
+                    compression: map_compression(profile.compression),
+                    custom_bpp: None,
+                    force_ffmpeg_decoder: false,
+                    optimize_filesize: false,
+                };
+                if profile.format == ExportFormat::Hevc {
+                    cap_export::settings::ExportSettings::Hevc(settings)
+                } else {
+                    cap_export::settings::ExportSettings::Av1(settings)
+                }
+                .export(base, |_| true)
+                .await
+            }
+            ExportFormat::Webm => {
+                cap_export::webm::WebmExportSettings {
+                    fps: profile.fps,
+                    resolution_base: profile.resolution_base,
+                    codec: Default::default(),
+                    compression: map_compression(profile.compression),
+                    custom_bpp: None,
+                    optimize_filesize: false,
+                }
+                .export(base, |_| true)
+                .await
+            }
         }
         .map_err(|e| format!("Export failed: {e}"))?;
 
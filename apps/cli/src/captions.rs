@@ -0,0 +1,327 @@
+use std::path::{Path, PathBuf};
+
+use cap_project::{
+    CaptionSegment, CaptionsData, ProjectConfiguration, RecordingMeta, SubtitleFormat,
+};
+use cap_transcription::{TranscriptionEngine, TranscriptionOptions, TranscriptionProgress};
+use clap::{Args, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{OutputFormat, finish_json, resolve_format, write_json, write_json_line};
+
+#[derive(Args)]
+pub struct CaptionsArgs {
+    #[command(subcommand)]
+    command: CaptionsCommands,
+}
+
+#[derive(Subcommand)]
+enum CaptionsCommands {
+    /// Transcribe a project with a local Whisper or Parakeet model and save the captions
+    Generate(CaptionsGenerateArgs),
+    /// Replace a project's captions with an SRT, WebVTT or ASS file
+    Import(CaptionsImportArgs),
+    /// Write a project's captions as an SRT, WebVTT or ASS file
+    Export(CaptionsExportArgs),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum CliTranscriptionEngine {
+    Whisper,
+    Parakeet,
+}
+
+impl From<CliTranscriptionEngine> for TranscriptionEngine {
+    fn from(engine: CliTranscriptionEngine) -> Self {
+        match engine {
+            CliTranscriptionEngine::Whisper => Self::Whisper,
+            CliTranscriptionEngine::Parakeet => Self::Parakeet,
+        }
+    }
+}
+
+#[derive(Args)]
+struct CaptionsGenerateArgs {
+    project_path: PathBuf,
+    /// Whisper model file (ggml .bin) or Parakeet model directory
+    #[arg(long)]
+    model: PathBuf,
+    /// Defaults to Parakeet for a model directory and Whisper for a model file
+    #[arg(long, value_enum)]
+    engine: Option<CliTranscriptionEngine>,
+    /// Spoken language code, or auto to detect it (Whisper only)
+    #[arg(long, default_value = "auto")]
+    language: String,
+    /// Name or term whose spelling the transcript should prefer; repeatable (Whisper only)
+    #[arg(long = "hint")]
+    hints: Vec<String>,
+    /// Output format. json streams newline-delimited events: {"type":"progress","stage",...} while
+    /// transcribing, then {"type":"generated","segments","words"}
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum CliSubtitleFormat {
+    Srt,
+    Vtt,
+    Ass,
+}
+
+impl From<CliSubtitleFormat> for SubtitleFormat {
+    fn from(format: CliSubtitleFormat) -> Self {
+        match format {
+            CliSubtitleFormat::Srt => Self::Srt,
+            CliSubtitleFormat::Vtt => Self::WebVtt,
+            CliSubtitleFormat::Ass => Self::Ass,
+        }
+    }
+}
+
+/// Which clock a subtitle file's cue times follow.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum CaptionTiming {
+    /// The raw recording, before any timeline edits
+    Source,
+    /// The edited timeline, as in an exported video
+    Output,
+}
+
+#[derive(Args)]
+struct CaptionsImportArgs {
+    project_path: PathBuf,
+    /// Subtitle file to import
+    file: PathBuf,
+    /// Defaults to the file's extension (.srt, .vtt, .ass or .ssa)
+    #[arg(long, value_enum)]
+    subtitle_format: Option<CliSubtitleFormat>,
+    /// Use output when the file was made from an exported video rather than the raw recording
+    #[arg(long, value_enum, default_value_t = CaptionTiming::Source)]
+    timing: CaptionTiming,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Args)]
+struct CaptionsExportArgs {
+    project_path: PathBuf,
+    /// Subtitle file to write
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Defaults to the output's extension (.srt, .vtt or .ass)
+    #[arg(long, value_enum)]
+    subtitle_format: Option<CliSubtitleFormat>,
+    /// Use output to match a video exported from the edited timeline
+    #[arg(long, value_enum, default_value_t = CaptionTiming::Source)]
+    timing: CaptionTiming,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum CaptionsEvent {
+    Progress {
+        #[serde(flatten)]
+        progress: TranscriptionProgress,
+    },
+    Generated {
+        segments: usize,
+        words: usize,
+    },
+}
+
+impl CaptionsArgs {
+    pub async fn run(self, json: bool) -> Result<(), String> {
+        match self.command {
+            CaptionsCommands::Generate(args) => {
+                let format = resolve_format(json, args.format);
+                match args.run(format).await {
+                    Ok(()) => Ok(()),
+                    Err(error) => {
+                        if format == OutputFormat::Json {
+                            let _ = write_json_line(&json!({ "error": error }));
+                        }
+                        Err(error)
+                    }
+                }
+            }
+            CaptionsCommands::Import(args) => {
+                let format = resolve_format(json, args.format);
+                finish_json(format, args.run(format))
+            }
+            CaptionsCommands::Export(args) => {
+                let format = resolve_format(json, args.format);
+                finish_json(format, args.run(format))
+            }
+        }
+    }
+}
+
+impl CaptionsGenerateArgs {
+    async fn run(self, format: OutputFormat) -> Result<(), String> {
+        if !self.model.exists() {
+            return Err(format!("Model not found at {}", self.model.display()));
+        }
+
+        let options = TranscriptionOptions {
+            engine: self.engine.map_or_else(
+                || TranscriptionEngine::for_model(&self.model),
+                TranscriptionEngine::from,
+            ),
+            language: self.language,
+            hints: self.hints,
+        };
+
+        let captions = cap_transcription::generate_project_captions(
+            &self.project_path,
+            &self.model,
+            &options,
+            move |progress| {
+                if format == OutputFormat::Json {
+                    // Progress output must never fail the transcription itself.
+                    let _ = write_json_line(&CaptionsEvent::Progress { progress });
+                }
+            },
+        )
+        .await?;
+
+        let segments = captions.segments.len();
+        let words = word_count(&captions.segments);
+
+        match format {
+            OutputFormat::Json => write_json_line(&CaptionsEvent::Generated { segments, words })?,
+            OutputFormat::Text => println!(
+                "Generated {segments} caption segments ({words} words) for {}",
+                self.project_path.display()
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+fn subtitle_format(
+    format: Option<CliSubtitleFormat>,
+    path: &Path,
+) -> Result<SubtitleFormat, String> {
+    format
+        .map(SubtitleFormat::from)
+        .or_else(|| SubtitleFormat::from_path(path))
+        .ok_or_else(|| {
+            format!(
+                "Can't tell the subtitle format of {}; pass --subtitle-format",
+                path.display()
+            )
+        })
+}
+
+fn load_project(project_path: &Path) -> Result<(RecordingMeta, ProjectConfiguration), String> {
+    let meta = RecordingMeta::load_for_project(project_path)
+        .map_err(|e| format!("Failed to load recording meta: {e}"))?;
+    let config = match ProjectConfiguration::load(project_path) {
+        Ok(config) => config,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => ProjectConfiguration::default(),
+        Err(e) => return Err(format!("Failed to load project config: {e}")),
+    };
+    Ok((meta, config))
+}
+
+fn word_count(segments: &[CaptionSegment]) -> usize {
+    segments.iter().map(|segment| segment.words.len()).sum()
+}
+
+impl CaptionsImportArgs {
+    fn run(self, format: OutputFormat) -> Result<(), String> {
+        let subtitle_format = subtitle_format(self.subtitle_format, &self.file)?;
+        let input = std::fs::read_to_string(&self.file)
+            .map_err(|e| format!("Failed to read {}: {e}", self.file.display()))?;
+        let mut captions =
+            CaptionsData::from_subtitles(&input, subtitle_format, Default::default())
+                .map_err(|e| format!("Failed to parse {}: {e}", self.file.display()))?;
+        if captions.segments.is_empty() {
+            return Err(format!("{} has no captions", self.file.display()));
+        }
+
+        if self.timing == CaptionTiming::Output {
+            let (meta, config) = load_project(&self.project_path)?;
+            if let Some(timeline) = &config.timeline {
+                let durations = cap_transcription::recording_durations(&meta)?;
+                captions.retime_from_output(timeline, &durations);
+            }
+        }
+
+        let captions =
+            cap_transcription::save_project_captions(&self.project_path, captions.segments)?;
+        let segments = captions.segments.len();
+        let words = word_count(&captions.segments);
+
+        match format {
+            OutputFormat::Json => write_json(&json!({
+                "path": self.project_path,
+                "segments": segments,
+                "words": words,
+            })),
+            OutputFormat::Text => {
+                println!(
+                    "Imported {segments} caption segments ({words} words) into {}",
+                    self.project_path.display()
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+impl CaptionsExportArgs {
+    fn run(self, format: OutputFormat) -> Result<(), String> {
+        let subtitle_format = subtitle_format(self.subtitle_format, &self.output)?;
+        let (meta, config) = load_project(&self.project_path)?;
+        let Some(captions) = config.captions.as_ref().filter(|c| !c.segments.is_empty()) else {
+            return Err(
+                "Project has no captions; create them with `cap captions generate` or `cap captions import`"
+                    .to_string(),
+            );
+        };
+
+        // Legacy captions (not source-timed) were stored in output time already.
+        let (contents, cues) = match (&config.timeline, self.timing) {
+            (Some(timeline), CaptionTiming::Output) if captions.source_timed => {
+                let durations = cap_transcription::recording_durations(&meta)?;
+                let track = captions.derive_track(timeline, &durations);
+                (
+                    subtitle_format.write(&track, &captions.settings),
+                    track.len(),
+                )
+            }
+            _ => (
+                captions.to_subtitles(subtitle_format),
+                captions.segments.len(),
+            ),
+        };
+
+        if let Some(dir) = self
+            .output
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        }
+        std::fs::write(&self.output, contents)
+            .map_err(|e| format!("Failed to write {}: {e}", self.output.display()))?;
+
+        match format {
+            OutputFormat::Json => write_json(&json!({
+                "path": self.output,
+                "subtitleFormat": subtitle_format.extension(),
+                "cues": cues,
+            })),
+            OutputFormat::Text => {
+                println!("Wrote {cues} captions to {}", self.output.display());
+                Ok(())
+            }
+        }
+    }
+}
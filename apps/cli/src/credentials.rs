@@ -10,7 +10,7 @@ use serde_json::Value;
 
 use crate::{OutputFormat, write_json};
 
-const DEFAULT_SERVER: &str = "https://cap.so";
+const DEFAULT_SERVER: &str = cap_api::DEFAULT_SERVER_URL;
 // Prod first, then the dev bundle, so a released install wins on a machine that has both.
 const DESKTOP_BUNDLE_IDS: [&str; 2] = ["so.cap.desktop", "so.cap.desktop.dev"];
 
@@ -32,6 +32,12 @@ pub struct Credentials {
     pub user_id: Option<String>,
 }
 
+impl Credentials {
+    pub fn client(&self) -> cap_api::Client {
+        cap_api::Client::new(&self.server).with_token(&self.api_key)
+    }
+}
+
 fn load_desktop_store() -> Option<Value> {
     let data_dir = dirs::data_dir()?;
     DESKTOP_BUNDLE_IDS.into_iter().find_map(|id| {
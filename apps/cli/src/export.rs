@@ -7,8 +7,14 @@ use std::{
     },
 };
 
-use cap_export::{ExporterBase, make_cursor_only_project};
-use cap_project::{RecordingMeta, RecordingMetaInner, XY};
+use cap_export::{
+    ExporterBase,
+    chapters::{apply_instant_chapters, chapters_json_path},
+    loudness::{LoudnessNormalization, LoudnessReport},
+    make_cursor_only_project,
+    subtitles::SubtitleExportOptions,
+};
+use cap_project::{ChapterMarker, RecordingMeta, RecordingMetaInner, XY};
 use clap::{Args, ValueEnum};
 use serde::{Deserialize, Serialize};
 use tracing::info;
@@ -18,6 +24,33 @@ pub enum ExportFormat {
     Mp4,
     Gif,
     Mov,
+    /// MP4 with HEVC video
+    Hevc,
+    /// MP4 with AV1 video
+    Av1,
+    /// WebM with VP9 (or AV1, see --webm-codec) video and Opus audio
+    Webm,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum WebmCodecArg {
+    Vp9,
+    Av1,
+}
+
+impl From<WebmCodecArg> for cap_export::webm::WebmVideoCodec {
+    fn from(value: WebmCodecArg) -> Self {
+        match value {
+            WebmCodecArg::Vp9 => Self::Vp9,
+            WebmCodecArg::Av1 => Self::Av1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum SubtitleSidecarArg {
+    Srt,
+    Vtt,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
@@ -42,7 +75,8 @@ impl From<QualityArg> for cap_export::mp4::ExportCompression {
 #[derive(Args)]
 #[command(long_about = "Render a '.cap' project to a video file.
 
-NOTE: here --format selects the CONTAINER (mp4/gif/mov), NOT the output mode. For machine-readable
+NOTE: here --format selects the CONTAINER/CODEC (mp4/gif/mov/webm, or hevc/av1 for MP4 files with HEVC
+or AV1 video), NOT the output mode. For machine-readable
 output pass --json (the global flag), which streams NDJSON progress + completion events to stdout.
 The NDJSON uses PascalCase type tags and snake_case fields ({\"type\":\"Progress\",\"rendered_count\":N,
 \"total_frames\":N} then {\"type\":\"Completed\",\"path\":\"...\"}); on failure a final
@@ -55,19 +89,22 @@ pub struct Export {
     /// Output file to write the export to
     #[arg(long, short = 'o')]
     output: Option<PathBuf>,
-    /// Container to export: mp4 (default), gif, or mov. NOT the output mode — use --json for JSON
+    /// Container to export: mp4 (default), gif, mov, webm, or hevc/av1 (MP4 with HEVC/AV1 video). NOT the output mode — use --json for JSON
     #[arg(long, value_enum)]
     format: Option<ExportFormat>,
+    /// Video codec for --format webm (default vp9)
+    #[arg(long, value_enum)]
+    webm_codec: Option<WebmCodecArg>,
     /// Frames per second to render
     #[arg(long)]
     fps: Option<u32>,
     /// Output resolution as WIDTHxHEIGHT, e.g. 1920x1080
     #[arg(long)]
     resolution: Option<String>,
-    /// Compression preset (mp4 only)
+    /// Compression preset (mp4/hevc/av1/webm only)
     #[arg(long, value_enum)]
     quality: Option<QualityArg>,
-    /// Optimise for smaller files using CRF (mp4 only)
+    /// Optimise for smaller files using CRF (mp4/hevc/av1/webm only)
     #[arg(long)]
     optimize_filesize: bool,
     /// Full export settings as JSON, e.g. {"format":"Mp4","fps":60,"resolution_base":{"x":1920,"y":1080},"compression":"Maximum","custom_bpp":null} (mutually exclusive with the flags above)
@@ -76,6 +113,32 @@ pub struct Export {
     /// Decode source video with FFmpeg instead of the platform hardware decoder
     #[arg(long)]
     force_ffmpeg_decoder: bool,
+    /// Mux the project's captions as a soft subtitle track (mp4/hevc/av1/webm). On by default when
+    /// the project's captions have "export with subtitles" enabled
+    #[arg(long)]
+    embed_subtitles: bool,
+    /// Also write caption sidecar files next to the output (mp4/hevc/av1/webm), e.g. --subtitle-sidecar srt,vtt
+    #[arg(long, value_enum, value_delimiter = ',')]
+    subtitle_sidecar: Vec<SubtitleSidecarArg>,
+    /// Measure the mixed audio (EBU R128) in a first pass and apply gain to hit --target-lufs
+    /// without exceeding --true-peak (mp4/hevc/av1/webm). The measurement and gain are reported in
+    /// the completion JSON
+    #[arg(long)]
+    normalize_loudness: bool,
+    /// Integrated loudness target in LUFS for --normalize-loudness (default -16)
+    #[arg(long, allow_negative_numbers = true, requires = "normalize_loudness")]
+    target_lufs: Option<f64>,
+    /// True-peak ceiling in dBTP for --normalize-loudness (default -1)
+    #[arg(long, allow_negative_numbers = true, requires = "normalize_loudness")]
+    true_peak: Option<f64>,
+    /// Keep an instant recording's separate microphone and system audio tracks in the copy instead
+    /// of mixing them into one (instant recordings with --output only)
+    #[arg(long)]
+    keep_audio_tracks: bool,
+    /// Also write the chapters marked while recording to `<output>.chapters.json` in the web
+    /// player's format. Chapters are embedded in mp4/mov/hevc/av1/webm outputs either way
+    #[arg(long)]
+    chapters_json: bool,
     /// Stream newline-delimited JSON progress events to stdout ({"type":"Progress","rendered_count":N,"total_frames":N}; also emits a terminal {"type":"Error","error":"..."} on failure). Implied by --json
     #[arg(long)]
     progress_json: bool,
@@ -87,6 +150,7 @@ pub struct Export {
 #[derive(Default)]
 pub struct ExportFlags {
     pub format: Option<ExportFormat>,
+    pub webm_codec: Option<WebmCodecArg>,
     pub fps: Option<u32>,
     pub resolution: Option<String>,
     pub quality: Option<QualityArg>,
@@ -97,6 +161,7 @@ pub struct ExportFlags {
 impl ExportFlags {
     fn is_set(&self) -> bool {
         self.format.is_some()
+            || self.webm_codec.is_some()
             || self.fps.is_some()
             || self.resolution.is_some()
             || self.quality.is_some()
@@ -113,6 +178,12 @@ pub enum CliExportSettings {
     Gif(cap_export::gif::GifExportSettings),
     #[serde(alias = "mov")]
     Mov(cap_export::mov::MovExportSettings),
+    #[serde(alias = "hevc")]
+    Hevc(cap_export::mp4_codec::Mp4CodecExportSettings),
+    #[serde(alias = "av1")]
+    Av1(cap_export::mp4_codec::Mp4CodecExportSettings),
+    #[serde(alias = "webm")]
+    Webm(cap_export::webm::WebmExportSettings),
 }
 
 impl CliExportSettings {
@@ -121,12 +192,36 @@ impl CliExportSettings {
             Self::Mp4(settings) => settings.fps,
             Self::Gif(settings) => settings.fps,
             Self::Mov(settings) => settings.fps,
+            Self::Hevc(settings) => settings.fps,
+            Self::Av1(settings) => settings.fps,
+            Self::Webm(settings) => settings.fps,
         }
     }
 
     fn force_ffmpeg_decoder(&self) -> bool {
         match self {
             Self::Mp4(settings) => settings.force_ffmpeg_decoder,
+            Self::Hevc(settings) => settings.force_ffmpeg_decoder,
+            Self::Av1(settings) => settings.force_ffmpeg_decoder,
+            Self::Webm(_) => false,
+            Self::Gif(_) | Self::Mov(_) => false,
+        }
+    }
+
+    fn supports_subtitles(&self) -> bool {
+        match self {
+            Self::Mp4(_) | Self::Hevc(_) | Self::Av1(_) | Self::Webm(_) => true,
+            Self::Gif(_) | Self::Mov(_) => false,
+        }
+    }
+
+    fn supports_chapters(&self) -> bool {
+        !matches!(self, Self::Gif(_))
+    }
+
+    fn has_audio(&self) -> bool {
+        match self {
+            Self::Mp4(_) | Self::Hevc(_) | Self::Av1(_) | Self::Webm(_) => true,
             Self::Gif(_) | Self::Mov(_) => false,
         }
     }
@@ -134,14 +229,31 @@ impl CliExportSettings {
     fn cursor_only(&self) -> bool {
         match self {
             Self::Mov(settings) => settings.cursor_only,
-            Self::Mp4(_) | Self::Gif(_) => false,
+            Self::Mp4(_) | Self::Gif(_) | Self::Hevc(_) | Self::Av1(_) | Self::Webm(_) => false,
+        }
+    }
+}
+
+impl From<CliExportSettings> for cap_export::settings::ExportSettings {
+    fn from(settings: CliExportSettings) -> Self {
+        match settings {
+            CliExportSettings::Mp4(s) => Self::Mp4(s),
+            CliExportSettings::Gif(s) => Self::Gif(s),
+            CliExportSettings::Mov(s) => Self::Mov(s),
+            CliExportSettings::Hevc(s) => Self::Hevc(s),
+            CliExportSettings::Av1(s) => Self::Av1(s),
+            CliExportSettings::Webm(s) => Self::Webm(s),
         }
     }
 }
 
 fn default_fps(format: ExportFormat) -> u32 {
     match format {
-        ExportFormat::Mp4 | ExportFormat::Mov => 60,
+        ExportFormat::Mp4
+        | ExportFormat::Mov
+        | ExportFormat::Hevc
+        | ExportFormat::Av1
+        | ExportFormat::Webm => 60,
         ExportFormat::Gif => 30,
     }
 }
@@ -175,27 +287,66 @@ pub fn settings_from_flags(flags: &ExportFlags) -> Result<CliExportSettings, Str
         None => XY::new(1920, 1080),
     };
 
+    if flags.webm_codec.is_some() && format != ExportFormat::Webm {
+        return Err("--webm-codec is only supported for --format webm".to_string());
+    }
+
+    let compression = flags
+        .quality
+        .map(Into::into)
+        .unwrap_or(cap_export::mp4::ExportCompression::Maximum);
+
     match format {
         ExportFormat::Mp4 => Ok(CliExportSettings::Mp4(cap_export::mp4::Mp4ExportSettings {
             fps,
             resolution_base,
-            compression: flags
-                .quality
-                .map(Into::into)
-                .unwrap_or(cap_export::mp4::ExportCompression::Maximum),
+            compression,
             custom_bpp: None,
             force_ffmpeg_decoder: flags.force_ffmpeg_decoder,
             optimize_filesize: flags.optimize_filesize,
         })),
+        ExportFormat::Hevc => Ok(CliExportSettings::Hevc(
+            cap_export::mp4_codec::Mp4CodecExportSettings {
+                fps,
+                resolution_base,
+                compression,
+                custom_bpp: None,
+                force_ffmpeg_decoder: flags.force_ffmpeg_decoder,
+                optimize_filesize: flags.optimize_filesize,
+            },
+        )),
+        ExportFormat::Av1 => Ok(CliExportSettings::Av1(
+            cap_export::mp4_codec::Mp4CodecExportSettings {
+                fps,
+                resolution_base,
+                compression,
+                custom_bpp: None,
+                force_ffmpeg_decoder: flags.force_ffmpeg_decoder,
+                optimize_filesize: flags.optimize_filesize,
+            },
+        )),
+        ExportFormat::Webm => Ok(CliExportSettings::Webm(
+            cap_export::webm::WebmExportSettings {
+                fps,
+                resolution_base,
+                codec: flags.webm_codec.map(Into::into).unwrap_or_default(),
+                compression,
+                custom_bpp: None,
+                optimize_filesize: flags.optimize_filesize,
+            },
+        )),
         ExportFormat::Gif => {
             if flags.quality.is_some() {
                 return Err(
-                    "--quality is only supported for --format mp4; use --settings-json for GIF quality"
+                    "--quality is only supported for --format mp4/hevc/av1/webm; use --settings-json for GIF quality"
                         .to_string(),
                 );
             }
             if flags.optimize_filesize {
-                return Err("--optimize-filesize is only supported for --format mp4".to_string());
+                return Err(
+                    "--optimize-filesize is only supported for --format mp4/hevc/av1/webm"
+                        .to_string(),
+                );
             }
             Ok(CliExportSettings::Gif(cap_export::gif::GifExportSettings {
                 fps,
@@ -205,10 +356,15 @@ pub fn settings_from_flags(flags: &ExportFlags) -> Result<CliExportSettings, Str
         }
         ExportFormat::Mov => {
             if flags.quality.is_some() {
-                return Err("--quality is only supported for --format mp4".to_string());
+                return Err(
+                    "--quality is only supported for --format mp4/hevc/av1/webm".to_string()
+                );
             }
             if flags.optimize_filesize {
-                return Err("--optimize-filesize is only supported for --format mp4".to_string());
+                return Err(
+                    "--optimize-filesize is only supported for --format mp4/hevc/av1/webm"
+                        .to_string(),
+                );
             }
             Ok(CliExportSettings::Mov(cap_export::mov::MovExportSettings {
                 fps,
@@ -219,6 +375,32 @@ pub fn settings_from_flags(flags: &ExportFlags) -> Result<CliExportSettings, Str
     }
 }
 
+fn loudness_from_flags(
+    normalize: bool,
+    target_lufs: Option<f64>,
+    true_peak: Option<f64>,
+) -> Result<Option<LoudnessNormalization>, String> {
+    if !normalize {
+        return Ok(None);
+    }
+
+    let defaults = LoudnessNormalization::default();
+    let target_lufs = target_lufs.unwrap_or(defaults.target_lufs);
+    let true_peak_dbtp = true_peak.unwrap_or(defaults.true_peak_dbtp);
+
+    if !(-70.0..0.0).contains(&target_lufs) {
+        return Err("--target-lufs must be between -70 and 0".to_string());
+    }
+    if !(-20.0..=0.0).contains(&true_peak_dbtp) {
+        return Err("--true-peak must be between -20 and 0 dBTP".to_string());
+    }
+
+    Ok(Some(LoudnessNormalization {
+        target_lufs,
+        true_peak_dbtp,
+    }))
+}
+
 #[derive(Serialize)]
 #[serde(tag = "type")]
 enum ExportProgressMessage<'a> {
@@ -228,6 +410,12 @@ enum ExportProgressMessage<'a> {
     },
     Completed {
         path: &'a std::path::Path,
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        subtitles: &'a [PathBuf],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        chapters: Option<&'a std::path::Path>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        loudness: Option<LoudnessReport>,
     },
     // Field is named `error` (not `message`) so a single `"error" in obj` predicate detects failure
     // across every JSON-emitting command. The desktop sidecar only parses Progress/Completed, so the
@@ -238,6 +426,18 @@ enum ExportProgressMessage<'a> {
 }
 
 impl Export {
+    fn subtitle_options(&self) -> SubtitleExportOptions {
+        SubtitleExportOptions {
+            embed: self.embed_subtitles,
+            srt_sidecar: self.subtitle_sidecar.contains(&SubtitleSidecarArg::Srt),
+            vtt_sidecar: self.subtitle_sidecar.contains(&SubtitleSidecarArg::Vtt),
+        }
+    }
+
+    fn loudness_options(&self) -> Result<Option<LoudnessNormalization>, String> {
+        loudness_from_flags(self.normalize_loudness, self.target_lufs, self.true_peak)
+    }
+
     fn resolve_output(&self) -> Result<Option<PathBuf>, String> {
         match (&self.output, &self.output_path) {
             (Some(_), Some(_)) => Err(
@@ -252,6 +452,7 @@ impl Export {
     fn resolve_settings(&self) -> Result<CliExportSettings, String> {
         let flags = ExportFlags {
             format: self.format,
+            webm_codec: self.webm_codec,
             fps: self.fps,
             resolution: self.resolution.clone(),
             quality: self.quality,
@@ -313,10 +514,34 @@ impl Export {
         let meta = RecordingMeta::load_for_project(&self.project_path)
             .map_err(|e| format!("Failed to load recording meta: {e}"))?;
 
+        let subtitles = self.subtitle_options();
+        let loudness = self.loudness_options()?;
+
+        if loudness.is_some() && !settings.has_audio() {
+            return Err(
+                "--normalize-loudness needs a format with audio (mp4/hevc/av1/webm)".to_string(),
+            );
+        }
+
         if matches!(&meta.inner, RecordingMetaInner::Instant(_)) {
+            if subtitles != SubtitleExportOptions::default() {
+                return Err(
+                    "--embed-subtitles/--subtitle-sidecar need a studio recording; instant recordings have no caption timeline"
+                        .to_string(),
+                );
+            }
+            if loudness.is_some() {
+                return Err(
+                    "--normalize-loudness needs a studio recording; instant recordings are copied as-is"
+                        .to_string(),
+                );
+            }
             return export_instant_project(
                 self.project_path,
                 output,
+                self.keep_audio_tracks,
+                &meta.chapters,
+                self.chapters_json,
                 &settings,
                 progress_json,
                 completion_json,
@@ -327,7 +552,10 @@ impl Export {
 
         let force_ffmpeg_decoder = self.force_ffmpeg_decoder || settings.force_ffmpeg_decoder();
         let mut builder = ExporterBase::builder(self.project_path.clone())
-            .with_force_ffmpeg_decoder(force_ffmpeg_decoder);
+            .with_force_ffmpeg_decoder(force_ffmpeg_decoder)
+            .with_subtitles(subtitles)
+            .with_chapters_json(self.chapters_json)
+            .with_loudness_normalization(loudness);
 
         if let Some(output_path) = output {
             builder = builder.with_output_path(output_path);
@@ -337,12 +565,19 @@ impl Export {
             builder = builder.with_config(make_cursor_only_project(meta.project_config()));
         }
 
-        let exporter_base = builder
+        let mut exporter_base = builder
             .build()
             .await
             .map_err(|v| format!("Exporter build error: {v}"))?;
 
         let total_frames = exporter_base.total_frames(settings.fps());
+        let subtitle_extensions = if settings.supports_subtitles() {
+            exporter_base.subtitle_sidecar_extensions()
+        } else {
+            vec![]
+        };
+        let writes_chapters_json =
+            settings.supports_chapters() && exporter_base.writes_chapters_json();
 
         if progress_json {
             emit_export_message(
@@ -354,6 +589,9 @@ impl Export {
             )?;
         }
 
+        // Run the analysis pass up front so its result can be reported; the export reuses it.
+        let loudness_report = exporter_base.analyze_loudness().await?;
+
         let rendered = Arc::new(AtomicU32::new(0));
         let progress_stdout = Arc::clone(stdout);
         let progress_rendered = Arc::clone(&rendered);
@@ -375,12 +613,10 @@ impl Export {
             true
         };
 
-        let output_path = match settings {
-            CliExportSettings::Mp4(settings) => settings.export(exporter_base, on_progress).await,
-            CliExportSettings::Gif(settings) => settings.export(exporter_base, on_progress).await,
-            CliExportSettings::Mov(settings) => settings.export(exporter_base, on_progress).await,
-        }
-        .map_err(|v| format!("Exporter error: {v}"))?;
+        let output_path = cap_export::settings::ExportSettings::from(settings)
+            .export(exporter_base, on_progress)
+            .await
+            .map_err(|v| format!("Exporter error: {v}"))?;
 
         // Defense in depth: an export that renders no frames writes an empty (~few hundred byte) file
         // but otherwise "succeeds". An agent must never silently get/upload that, so fail loudly and
@@ -392,10 +628,21 @@ impl Export {
             ));
         }
 
+        let subtitle_paths = subtitle_extensions
+            .into_iter()
+            .map(|ext| output_path.with_extension(ext))
+            .collect::<Vec<_>>();
+        let chapters_path = writes_chapters_json.then(|| chapters_json_path(&output_path));
+
         if progress_json || completion_json {
             emit_export_message(
                 stdout,
-                &ExportProgressMessage::Completed { path: &output_path },
+                &ExportProgressMessage::Completed {
+                    path: &output_path,
+                    subtitles: &subtitle_paths,
+                    chapters: chapters_path.as_deref(),
+                    loudness: loudness_report,
+                },
             )?;
         } else {
             // Default callers pass no JSON flag; the resolved path is otherwise invisible (the
@@ -461,7 +708,11 @@ fn instant_export_settings_supported(settings: &CliExportSettings) -> bool {
                 && settings.custom_bpp.is_none()
                 && !settings.optimize_filesize
         }
-        CliExportSettings::Gif(_) | CliExportSettings::Mov(_) => false,
+        CliExportSettings::Gif(_)
+        | CliExportSettings::Mov(_)
+        | CliExportSettings::Hevc(_)
+        | CliExportSettings::Av1(_)
+        | CliExportSettings::Webm(_) => false,
     }
 }
 
@@ -477,7 +728,13 @@ fn validate_instant_output_path(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn copy_instant_output(source_path: &Path, output_path: PathBuf) -> Result<PathBuf, String> {
+/// Copies the finalized instant recording to `output_path`, mixing separate audio tracks into one
+/// unless `keep_audio_tracks` is set. Exporting in place leaves the recording untouched.
+fn copy_instant_output(
+    source_path: &Path,
+    output_path: PathBuf,
+    keep_audio_tracks: bool,
+) -> Result<PathBuf, String> {
     validate_instant_output_path(&output_path)?;
 
     if source_path == output_path.as_path() {
@@ -500,7 +757,16 @@ fn copy_instant_output(source_path: &Path, output_path: PathBuf) -> Result<PathB
         })?;
     }
 
-    std::fs::copy(source_path, &output_path).map_err(|e| {
+    let copied = if keep_audio_tracks {
+        std::fs::copy(source_path, &output_path)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    } else {
+        cap_export::audio_tracks::copy_with_downmix(source_path, &output_path)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    };
+    copied.map_err(|e| {
         format!(
             "Failed to copy instant recording from {} to {}: {e}",
             source_path.display(),
@@ -514,6 +780,9 @@ fn copy_instant_output(source_path: &Path, output_path: PathBuf) -> Result<PathB
 async fn export_instant_project(
     project_path: PathBuf,
     output: Option<PathBuf>,
+    keep_audio_tracks: bool,
+    chapters: &[ChapterMarker],
+    chapters_json: bool,
     settings: &CliExportSettings,
     progress_json: bool,
     completion_json: bool,
@@ -539,7 +808,17 @@ async fn export_instant_project(
         )?;
     }
 
-    let output_path = copy_instant_output(&source_path, output_path)?;
+    let output_path = copy_instant_output(&source_path, output_path, keep_audio_tracks)?;
+    let chapters_path = {
+        let output_path = output_path.clone();
+        let chapters = chapters.to_vec();
+        tokio::task::spawn_blocking(move || {
+            apply_instant_chapters(&output_path, &chapters, chapters_json)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to write chapters: {e}"))?
+    };
 
     if progress_json {
         emit_export_message(
@@ -554,7 +833,12 @@ async fn export_instant_project(
     if progress_json || completion_json {
         emit_export_message(
             stdout,
-            &ExportProgressMessage::Completed { path: &output_path },
+            &ExportProgressMessage::Completed {
+                path: &output_path,
+                subtitles: &[],
+                chapters: chapters_path.as_deref(),
+                loudness: None,
+            },
         )?;
     } else {
         println!("Exported video to {}", output_path.display());
@@ -590,12 +874,10 @@ pub async fn export_project_default(project_path: PathBuf) -> Result<PathBuf, St
         true
     };
 
-    let output_path = match settings {
-        CliExportSettings::Mp4(settings) => settings.export(exporter_base, on_progress).await,
-        CliExportSettings::Gif(settings) => settings.export(exporter_base, on_progress).await,
-        CliExportSettings::Mov(settings) => settings.export(exporter_base, on_progress).await,
-    }
-    .map_err(|v| format!("Exporter error: {v}"))?;
+    let output_path = cap_export::settings::ExportSettings::from(settings)
+        .export(exporter_base, on_progress)
+        .await
+        .map_err(|v| format!("Exporter error: {v}"))?;
 
     // Same 0-frame guard as Export::run_inner: a recording with missing media renders an empty,
     // unplayable file that otherwise "succeeds", and `cap upload --export` would sign + upload it and
@@ -775,6 +1057,177 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn hevc_and_av1_accept_quality_and_crf() {
+        let hevc = settings_from_flags(&ExportFlags {
+            format: Some(ExportFormat::Hevc),
+            quality: Some(QualityArg::Web),
+            optimize_filesize: true,
+            ..Default::default()
+        })
+        .unwrap();
+        match hevc {
+            CliExportSettings::Hevc(s) => {
+                assert_eq!(s.fps, 60);
+                assert!(s.optimize_filesize);
+                assert!(matches!(
+                    s.compression,
+                    cap_export::mp4::ExportCompression::Web
+                ));
+            }
+            _ => panic!("expected hevc settings"),
+        }
+
+        let av1 = settings_from_flags(&ExportFlags {
+            format: Some(ExportFormat::Av1),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(av1, CliExportSettings::Av1(_)));
+    }
+
+    #[test]
+    fn settings_json_accepts_codec_format_tags() {
+        let json = r#"{"format":"hevc","fps":30,"resolution_base":{"x":1280,"y":720},"compression":"Social","custom_bpp":null}"#;
+        assert!(matches!(
+            serde_json::from_str::<CliExportSettings>(json).unwrap(),
+            CliExportSettings::Hevc(_)
+        ));
+        let av1 = json.replace("\"hevc\"", "\"Av1\"");
+        assert!(matches!(
+            serde_json::from_str::<CliExportSettings>(&av1).unwrap(),
+            CliExportSettings::Av1(_)
+        ));
+    }
+
+    #[test]
+    fn webm_defaults_to_vp9_and_rejects_stray_codec_flag() {
+        let vp9 = settings_from_flags(&ExportFlags {
+            format: Some(ExportFormat::Webm),
+            ..Default::default()
+        })
+        .unwrap();
+        match vp9 {
+            CliExportSettings::Webm(s) => {
+                assert_eq!(s.codec, cap_export::webm::WebmVideoCodec::Vp9);
+            }
+            _ => panic!("expected webm settings"),
+        }
+
+        let av1 = settings_from_flags(&ExportFlags {
+            format: Some(ExportFormat::Webm),
+            webm_codec: Some(WebmCodecArg::Av1),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(matches!(
+            av1,
+            CliExportSettings::Webm(cap_export::webm::WebmExportSettings {
+                codec: cap_export::webm::WebmVideoCodec::Av1,
+                ..
+            })
+        ));
+
+        assert!(
+            settings_from_flags(&ExportFlags {
+                format: Some(ExportFormat::Mp4),
+                webm_codec: Some(WebmCodecArg::Vp9),
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn completed_message_lists_subtitle_sidecars_only_when_written() {
+        let path = std::path::Path::new("/tmp/out.mp4");
+        let without = serde_json::to_value(ExportProgressMessage::Completed {
+            path,
+            subtitles: &[],
+            chapters: None,
+            loudness: None,
+        })
+        .unwrap();
+        assert_eq!(
+            without,
+            serde_json::json!({"type": "Completed", "path": "/tmp/out.mp4"})
+        );
+
+        let sidecars = [PathBuf::from("/tmp/out.srt"), PathBuf::from("/tmp/out.vtt")];
+        let with = serde_json::to_value(ExportProgressMessage::Completed {
+            path,
+            subtitles: &sidecars,
+            chapters: None,
+            loudness: None,
+        })
+        .unwrap();
+        assert_eq!(
+            with["subtitles"],
+            serde_json::json!(["/tmp/out.srt", "/tmp/out.vtt"])
+        );
+    }
+
+    #[test]
+    fn completed_message_lists_chapters_json_when_written() {
+        let chapters = PathBuf::from("/tmp/out.chapters.json");
+        let message = serde_json::to_value(ExportProgressMessage::Completed {
+            path: std::path::Path::new("/tmp/out.mp4"),
+            subtitles: &[],
+            chapters: Some(&chapters),
+            loudness: None,
+        })
+        .unwrap();
+        assert_eq!(
+            message["chapters"],
+            serde_json::json!("/tmp/out.chapters.json")
+        );
+    }
+
+    #[test]
+    fn completed_message_reports_loudness_normalization() {
+        let message = serde_json::to_value(ExportProgressMessage::Completed {
+            path: std::path::Path::new("/tmp/out.mp4"),
+            subtitles: &[],
+            chapters: None,
+            loudness: Some(LoudnessReport {
+                measured_lufs: Some(-27.5),
+                measured_true_peak_dbtp: Some(-9.0),
+                target_lufs: -16.0,
+                target_true_peak_dbtp: -1.0,
+                gain_db: 8.0,
+            }),
+        })
+        .unwrap();
+        assert_eq!(
+            message["loudness"],
+            serde_json::json!({
+                "measured_lufs": -27.5,
+                "measured_true_peak_dbtp": -9.0,
+                "target_lufs": -16.0,
+                "target_true_peak_dbtp": -1.0,
+                "gain_db": 8.0
+            })
+        );
+    }
+
+    #[test]
+    fn loudness_flags_default_and_validate_targets() {
+        assert_eq!(loudness_from_flags(false, None, None).unwrap(), None);
+        assert_eq!(
+            loudness_from_flags(true, None, None).unwrap(),
+            Some(LoudnessNormalization::default())
+        );
+        assert_eq!(
+            loudness_from_flags(true, Some(-23.0), Some(-2.0)).unwrap(),
+            Some(LoudnessNormalization {
+                target_lufs: -23.0,
+                true_peak_dbtp: -2.0,
+            })
+        );
+        assert!(loudness_from_flags(true, Some(3.0), None).is_err());
+        assert!(loudness_from_flags(true, None, Some(1.0)).is_err());
+    }
+
     #[test]
     fn optimize_filesize_only_for_mp4() {
         assert!(
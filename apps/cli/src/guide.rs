@@ -165,7 +165,7 @@ fn build() -> Guide {
                 ),
                 ..cmd(
                     "record start",
-                    "Start a recording. Foreground emits `started` then `stopped` (success requires recordingMetaExists:true); see notes for `--detach`.",
+                    "Start a recording. Foreground emits `started` then `stopped` (success requires recordingMetaExists:true); see notes for `--detach`. `--mode instant --stream-url <rtmp://|rtmps://|srt://|.m3u8>` also streams it live, reconnecting with backoff; stream warnings go to stderr. `--mode instant --separate-audio-tracks` keeps mic and system audio as two labelled tracks in the MP4.",
                     OutputMode::Ndjson,
                     &["started", "stopped", "error"],
                 )
@@ -176,6 +176,12 @@ fn build() -> Guide {
                 OutputMode::Ndjson,
                 &["stopped", "error"],
             ),
+            cmd(
+                "record mark",
+                "Add a chapter marker (optional --title) to a detached recording at the current point. Exports embed the chapters; `export --chapters-json` also writes them for the web player.",
+                OutputMode::Ndjson,
+                &["marked", "error"],
+            ),
             cmd(
                 "record status",
                 "List active detached recording sessions.",
@@ -186,7 +192,8 @@ fn build() -> Guide {
                 notes: Some(
                     "EXCEPTION: export NDJSON uses PascalCase `type` tags and snake_case fields \
                      (rendered_count, total_frames) for desktop compatibility. --format selects the \
-                     CONTAINER (mp4/gif/mov), NOT output mode; use --json for machine-readable output.",
+                     CONTAINER (mp4/gif/mov/webm, or hevc/av1 for MP4 with those codecs), NOT output mode; \
+                     use --json for machine-readable output.",
                 ),
                 ..cmd(
                     "export",
@@ -243,6 +250,54 @@ fn build() -> Guide {
                 OutputMode::SingleJson,
                 &[],
             ),
+            cmd(
+                "project history",
+                "List revisions of project-config.json recorded on every write (newest is `head`), with the settings each changed.",
+                OutputMode::SingleJson,
+                &[],
+            ),
+            cmd(
+                "project restore",
+                "Restore a revision from `project history`. The restore is recorded as a new revision, so nothing is lost.",
+                OutputMode::SingleJson,
+                &[],
+            ),
+            cmd(
+                "project migrate",
+                "Upgrade project files to the current schemaVersion, keeping a `<file>.v<N>.bak` copy. `--check` only reports and exits non-zero when any project needs migration.",
+                OutputMode::SingleJson,
+                &[],
+            ),
+            cmd(
+                "project suggest-zooms",
+                "Propose zoom segments from click clusters, cursor dwells and typing bursts (`--sensitivity 0..1`). `--apply` adds the ones that don't overlap an existing zoom.",
+                OutputMode::SingleJson,
+                &[],
+            ),
+            cmd(
+                "project auto-cut",
+                "Cut mic silences and caption filler words (um, uh) out of the timeline, remapping zooms, masks, text and captions. `--dry-run` prints the edit list without saving.",
+                OutputMode::SingleJson,
+                &[],
+            ),
+            cmd(
+                "captions generate",
+                "Transcribe a project with a local Whisper model file or Parakeet model directory (`--model`) and save the captions in source time, re-deriving the caption track. Streams {type:progress,stage,progress?} lines, then {type:generated,segments,words}.",
+                OutputMode::Ndjson,
+                &[],
+            ),
+            cmd(
+                "captions import",
+                "Replace a project's captions with an SRT, WebVTT (inline word timestamps kept) or ASS (\\k karaoke timings kept) file. `--timing output` for files made from an exported video. JSON emits {path,segments,words}.",
+                OutputMode::SingleJson,
+                &[],
+            ),
+            cmd(
+                "captions export",
+                "Write a project's captions to `--output` as SRT, WebVTT with word timestamps, or styled ASS. Source time by default; `--timing output` matches an exported video. JSON emits {path,subtitleFormat,cues}.",
+                OutputMode::SingleJson,
+                &[],
+            ),
             cmd(
                 "version",
                 "CLI version + execution context (distribution, bundled binaries).",
@@ -1,4 +1,5 @@
 mod automation;
+mod captions;
 mod credentials;
 mod doctor;
 mod export;
@@ -186,6 +187,8 @@ enum Commands {
     Screenshot(screenshot::Screenshot),
     /// List recordings discovered in the desktop library (or a custom directory)
     Recordings(RecordingsArgs),
+    /// Generate captions for a project with a local transcription model
+    Captions(captions::CaptionsArgs),
     /// Upload a recording or video file and get a shareable link
     Upload(upload::UploadArgs),
     /// Update Cap Desktop and the bundled CLI
@@ -238,6 +241,8 @@ enum RecordCommands {
     Start(RecordStart),
     /// Stop a detached recording started with `cap record start --detach`
     Stop(record::RecordStopArgs),
+    /// Add a chapter marker to a detached recording at the current point
+    Mark(record::RecordMarkArgs),
     /// List active and recent detached recording sessions
     Status(FormatArgs),
     /// Internal: background worker for detached recordings (do not call directly)
@@ -300,6 +305,87 @@ enum ProjectCommands {
     Validate(ProjectTarget),
     /// Read or write a project's editor configuration (project-config.json)
     Config(ProjectConfigArgs),
+    /// Upgrade project files to the current schema version, backing up the originals
+    Migrate(ProjectMigrateArgs),
+    /// List the recorded revisions of a project's editor configuration
+    History(ProjectTarget),
+    /// Restore an earlier configuration revision (recorded as a new revision, so it can be undone)
+    Restore(ProjectRestoreArgs),
+    /// Propose zoom segments from click clusters, cursor dwells and typing bursts
+    SuggestZooms(ProjectSuggestZoomsArgs),
+    /// Cut silences and filler words out of the timeline
+    AutoCut(ProjectAutoCutArgs),
+}
+
+#[derive(Args)]
+struct ProjectRestoreArgs {
+    project_path: PathBuf,
+    /// Revision id from `cap project history`
+    revision: u64,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Args)]
+struct ProjectSuggestZoomsArgs {
+    project_path: PathBuf,
+    /// Add the suggestions that don't overlap an existing zoom segment to the project
+    #[arg(long)]
+    apply: bool,
+    /// 0 zooms only on deliberate activity, 1 on single clicks and brief pauses
+    #[arg(long, default_value_t = 0.5)]
+    sensitivity: f64,
+    /// Zoom amount for the suggested segments
+    #[arg(long, default_value_t = 2.0)]
+    amount: f64,
+    /// Don't suggest zooms for click clusters
+    #[arg(long)]
+    no_clicks: bool,
+    /// Don't suggest zooms where the cursor dwells
+    #[arg(long)]
+    no_dwell: bool,
+    /// Don't suggest zooms for typing bursts
+    #[arg(long)]
+    no_typing: bool,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Args)]
+struct ProjectAutoCutArgs {
+    project_path: PathBuf,
+    /// Print the edit list without changing the project
+    #[arg(long)]
+    dry_run: bool,
+    /// Mic level, in dBFS, below which audio counts as silence
+    #[arg(long, default_value_t = -40.0, allow_hyphen_values = true)]
+    silence_threshold_db: f64,
+    /// Shortest pause, in seconds, worth cutting
+    #[arg(long, default_value_t = 0.75)]
+    min_silence: f64,
+    /// Seconds of each pause to keep on both sides of the speech
+    #[arg(long, default_value_t = 0.15)]
+    padding: f64,
+    /// Don't cut silences
+    #[arg(long)]
+    no_silences: bool,
+    /// Don't cut filler words found in the captions
+    #[arg(long)]
+    no_fillers: bool,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Args)]
+struct ProjectMigrateArgs {
+    /// Project directories, or folders of '.cap' projects such as the recordings library
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// Only report which projects would change; exits non-zero if any would
+    #[arg(long)]
+    check: bool,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
 }
 
 #[derive(Args)]
@@ -488,6 +574,7 @@ async fn run(cli: Cli) -> Result<(), String> {
         Commands::Record(RecordArgs { command, args }) => match command {
             Some(RecordCommands::Start(args)) => args.run(json).await,
             Some(RecordCommands::Stop(args)) => args.run(json).await,
+            Some(RecordCommands::Mark(args)) => args.run(json).await,
             Some(RecordCommands::Status(args)) => {
                 let format = resolve_format(json, args.format);
                 finish_json(format, record::status(format))
@@ -513,6 +600,7 @@ async fn run(cli: Cli) -> Result<(), String> {
         },
         Commands::Screenshot(s) => s.run(json).await,
         Commands::Recordings(args) => args.run(json),
+        Commands::Captions(args) => args.run(json).await,
         Commands::Upload(args) => args.run(json).await,
         Commands::Update(args) => {
             let format = resolve_format(json, args.format);
@@ -635,6 +723,52 @@ impl ProjectArgs {
                     )
                 }
             },
+            ProjectCommands::History(args) => {
+                let format = resolve_format(json, args.format);
+                finish_json(format, project::history(args.project_path, format))
+            }
+            ProjectCommands::Restore(args) => {
+                let format = resolve_format(json, args.format);
+                finish_json(
+                    format,
+                    project::restore(args.project_path, args.revision, format),
+                )
+            }
+            ProjectCommands::SuggestZooms(args) => {
+                let format = resolve_format(json, args.format);
+                let settings = cap_project::ZoomSuggestionSettings {
+                    sensitivity: args.sensitivity,
+                    amount: args.amount,
+                    clicks: !args.no_clicks,
+                    dwell: !args.no_dwell,
+                    typing: !args.no_typing,
+                };
+                finish_json(
+                    format,
+                    project::suggest_zooms(args.project_path, settings, args.apply, format),
+                )
+            }
+            ProjectCommands::AutoCut(args) => {
+                let format = resolve_format(json, args.format);
+                let settings = cap_project::AutoCutSettings {
+                    padding: args.padding,
+                    cut_silences: !args.no_silences,
+                    cut_fillers: !args.no_fillers,
+                    ..Default::default()
+                };
+                let silence = cap_audio::SilenceSettings {
+                    threshold_db: args.silence_threshold_db,
+                    min_duration: args.min_silence,
+                };
+                finish_json(
+                    format,
+                    project::auto_cut(args.project_path, settings, silence, args.dry_run, format),
+                )
+            }
+            // Like validate, migrate reports per-project errors inside its own output.
+            ProjectCommands::Migrate(args) => {
+                project::migrate(args.paths, args.check, resolve_format(json, args.format))
+            }
         }
     }
 }
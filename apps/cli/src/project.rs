@@ -1,8 +1,13 @@
 use std::path::{Path, PathBuf};
 
+use cap_audio::{AudioData, SilenceSettings, detect_silences};
 use cap_project::{
-    InstantRecordingMeta, RecordingMeta, RecordingMetaInner, StudioRecordingMeta,
-    StudioRecordingStatus,
+    AudioMeta, AutoCutPlan, AutoCutSettings, ConfigHistory, ConfigRevision, CutReason,
+    FileMigration, InstantRecordingMeta, ProjectConfiguration, RecordingMeta, RecordingMetaInner,
+    SegmentActivity, StudioRecordingMeta, StudioRecordingStatus, TimelineConfiguration,
+    TimelineSegment, VideoMeta, ZoomSuggestion, ZoomSuggestionReason, ZoomSuggestionSettings,
+    apply_zoom_suggestions, filler_cuts, migrate_project, plan_auto_cut, silence_cuts,
+    suggest_zoom_segments,
 };
 use serde::Serialize;
 
@@ -49,6 +54,74 @@ pub fn config_set(
     Ok(())
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryReport<'a> {
+    project_path: &'a Path,
+    head: Option<u64>,
+    revisions: &'a [ConfigRevision],
+}
+
+pub fn history(project_path: PathBuf, format: OutputFormat) -> Result<(), String> {
+    let history = ConfigHistory::open(&project_path)
+        .map_err(|e| format!("Failed to read project config history: {e}"))?;
+
+    match format {
+        OutputFormat::Json => write_json(&HistoryReport {
+            project_path: &project_path,
+            head: history.head(),
+            revisions: history.revisions(),
+        }),
+        OutputFormat::Text => {
+            if history.revisions().is_empty() {
+                println!("no config history for {}", project_path.display());
+                return Ok(());
+            }
+            for revision in history.revisions().iter().rev() {
+                let marker = if history.head() == Some(revision.id) {
+                    "*"
+                } else {
+                    " "
+                };
+                let time = chrono::DateTime::from_timestamp_millis(revision.created_at_ms as i64)
+                    .map(|time| {
+                        time.with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M:%S")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                let mut summary = revision.changes.join(", ");
+                if let Some(note) = &revision.note {
+                    summary = if summary.is_empty() {
+                        format!("({note})")
+                    } else {
+                        format!("({note}) {summary}")
+                    };
+                }
+                println!("{marker} {:>4}  {time}  {summary}", revision.id);
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn restore(project_path: PathBuf, revision: u64, format: OutputFormat) -> Result<(), String> {
+    let (_, new_revision) = ProjectConfiguration::restore_revision(&project_path, revision)
+        .map_err(|e| format!("Failed to restore revision {revision}: {e}"))?;
+
+    match format {
+        OutputFormat::Json => write_json(&serde_json::json!({
+            "ok": true,
+            "restored": revision,
+            "revision": new_revision,
+        })),
+        OutputFormat::Text => {
+            println!("restored revision {revision} as revision {new_revision}");
+            Ok(())
+        }
+    }
+}
+
 pub fn inspect(project_path: PathBuf, format: OutputFormat) -> Result<(), String> {
     let meta = RecordingMeta::load_for_project(&project_path)
         .map_err(|e| format!("Failed to load recording meta: {e}"))?;
@@ -324,3 +397,401 @@ pub fn validate(project_path: PathBuf, format: OutputFormat) -> Result<(), Strin
         Err("project validation failed".to_string())
     }
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectMigration {
+    project_path: PathBuf,
+    needs_migration: bool,
+    files: Vec<FileMigration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MigrationReport {
+    check: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    projects: Vec<ProjectMigration>,
+}
+
+/// Each path is either a project or a folder of `.cap` projects, eg. the recordings library.
+fn migration_targets(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut targets = Vec::new();
+
+    for path in paths {
+        if path.join("recording-meta.json").exists() || !path.is_dir() {
+            targets.push(path);
+            continue;
+        }
+
+        let mut projects: Vec<PathBuf> = std::fs::read_dir(&path)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.is_dir() && path.extension().is_some_and(|extension| extension == "cap"))
+                    .then_some(path)
+            })
+            .collect();
+        if projects.is_empty() {
+            targets.push(path);
+        } else {
+            projects.sort();
+            targets.extend(projects);
+        }
+    }
+
+    targets
+}
+
+fn migrate_one(project_path: PathBuf, check: bool) -> ProjectMigration {
+    let result = if project_path.join("recording-meta.json").exists() {
+        migrate_project(&project_path, check).map_err(|e| e.to_string())
+    } else {
+        Err("not a Cap project (missing recording-meta.json)".to_string())
+    };
+
+    match result {
+        Ok(files) => ProjectMigration {
+            needs_migration: files.iter().any(FileMigration::is_needed),
+            project_path,
+            files,
+            error: None,
+        },
+        Err(error) => ProjectMigration {
+            project_path,
+            needs_migration: false,
+            files: Vec::new(),
+            error: Some(error),
+        },
+    }
+}
+
+pub fn migrate(paths: Vec<PathBuf>, check: bool, format: OutputFormat) -> Result<(), String> {
+    let projects: Vec<ProjectMigration> = migration_targets(paths)
+        .into_iter()
+        .map(|path| migrate_one(path, check))
+        .collect();
+
+    let failed = projects.iter().filter(|p| p.error.is_some()).count();
+    let pending = projects.iter().filter(|p| p.needs_migration).count();
+    let error = if failed > 0 {
+        Some(format!("{failed} project(s) could not be migrated"))
+    } else if check && pending > 0 {
+        Some(format!("{pending} project(s) need migration"))
+    } else {
+        None
+    };
+    let report = MigrationReport {
+        check,
+        error,
+        projects,
+    };
+
+    match format {
+        OutputFormat::Json => write_json(&report)?,
+        OutputFormat::Text => {
+            for project in &report.projects {
+                let status = match (&project.error, project.needs_migration, check) {
+                    (Some(error), _, _) => format!("error: {error}"),
+                    (None, false, _) => "up to date".to_string(),
+                    (None, true, true) => "needs migration".to_string(),
+                    (None, true, false) => "migrated".to_string(),
+                };
+                println!("{}: {status}", project.project_path.display());
+                for file in project.files.iter().filter(|file| file.is_needed()) {
+                    println!(
+                        "  {} v{} -> v{}: {}",
+                        file.file.file_name(),
+                        file.from_version,
+                        file.to_version,
+                        file.steps.join("; ")
+                    );
+                    if let Some(backup) = &file.backup_path {
+                        println!("    backup: {}", backup.display());
+                    }
+                }
+            }
+        }
+    }
+
+    match report.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ZoomSuggestionReport<'a> {
+    project_path: &'a Path,
+    suggestions: &'a [ZoomSuggestion],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    applied: Option<usize>,
+}
+
+/// The timeline the editor would start from: every recording segment, uncut.
+fn default_timeline(
+    meta: &RecordingMeta,
+    studio: &StudioRecordingMeta,
+) -> Result<TimelineConfiguration, String> {
+    let displays = match studio {
+        StudioRecordingMeta::SingleSegment { segment } => vec![&segment.display],
+        StudioRecordingMeta::MultipleSegments { inner, .. } => {
+            inner.segments.iter().map(|s| &s.display).collect()
+        }
+    };
+    let segments = displays
+        .into_iter()
+        .enumerate()
+        .map(|(i, display)| {
+            let duration =
+                crate::upload::probe_video_meta(&meta.path(&display.path))?.duration_in_secs;
+            Ok(TimelineSegment {
+                recording_clip: i as u32,
+                timescale: 1.0,
+                start: 0.0,
+                end: duration,
+                ..Default::default()
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    serde_json::from_value(serde_json::json!({ "segments": segments, "zoomSegments": [] }))
+        .map_err(|e| format!("Failed to build default timeline: {e}"))
+}
+
+pub fn suggest_zooms(
+    project_path: PathBuf,
+    settings: ZoomSuggestionSettings,
+    apply: bool,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let meta = RecordingMeta::load_for_project(&project_path)
+        .map_err(|e| format!("Failed to load recording meta: {e}"))?;
+    let Some(studio) = meta.studio_meta() else {
+        return Err("zoom suggestions need a studio recording".to_string());
+    };
+
+    let mut config = match ProjectConfiguration::load(&project_path) {
+        Ok(config) => config,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::from_str("{}")
+            .map_err(|e| format!("Failed to build default project config: {e}"))?,
+        Err(e) => return Err(format!("Failed to load project config: {e}")),
+    };
+    let timeline = match config.timeline.take() {
+        Some(timeline) => timeline,
+        None => default_timeline(&meta, studio)?,
+    };
+    let timeline = config.timeline.insert(timeline);
+
+    let suggestions = suggest_zoom_segments(&SegmentActivity::load(&meta), timeline, &settings);
+    let overlapping = suggestions
+        .iter()
+        .map(|suggestion| suggestion.overlaps(&timeline.zoom_segments))
+        .collect::<Vec<_>>();
+
+    let applied = if apply {
+        let added = apply_zoom_suggestions(timeline, &suggestions);
+        if added > 0 {
+            config
+                .write(&project_path)
+                .map_err(|e| format!("Failed to write project config: {e}"))?;
+        }
+        Some(added)
+    } else {
+        None
+    };
+
+    match format {
+        OutputFormat::Json => write_json(&ZoomSuggestionReport {
+            project_path: &project_path,
+            suggestions: &suggestions,
+            applied,
+        }),
+        OutputFormat::Text => {
+            if suggestions.is_empty() {
+                println!("no zoom suggestions for {}", project_path.display());
+            }
+            for (suggestion, overlaps) in suggestions.iter().zip(overlapping) {
+                let reasons = suggestion
+                    .reasons
+                    .iter()
+                    .map(|reason| match reason {
+                        ZoomSuggestionReason::Clicks => "clicks",
+                        ZoomSuggestionReason::Dwell => "dwell",
+                        ZoomSuggestionReason::Typing => "typing",
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let note = if overlaps {
+                    "  (overlaps an existing zoom)"
+                } else {
+                    ""
+                };
+                println!(
+                    "{:>8.2}s - {:>8.2}s  {:.1}x  {reasons}{note}",
+                    suggestion.segment.start, suggestion.segment.end, suggestion.segment.amount
+                );
+            }
+            if let Some(added) = applied {
+                println!("added {added} zoom segment(s)");
+            }
+            Ok(())
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutoCutReport<'a> {
+    project_path: &'a Path,
+    #[serde(flatten)]
+    plan: &'a AutoCutPlan,
+    applied: bool,
+}
+
+/// Silences on each recording segment's mic track, in seconds from the segment's display start.
+/// Segments recorded without a mic have none.
+fn segment_silences(
+    meta: &RecordingMeta,
+    studio: &StudioRecordingMeta,
+    settings: &SilenceSettings,
+) -> Result<Vec<Vec<(f64, f64)>>, String> {
+    let tracks: Vec<(&VideoMeta, Option<&AudioMeta>)> = match studio {
+        StudioRecordingMeta::SingleSegment { segment } => {
+            vec![(&segment.display, segment.audio.as_ref())]
+        }
+        StudioRecordingMeta::MultipleSegments { inner, .. } => inner
+            .segments
+            .iter()
+            .map(|s| (&s.display, s.mic.as_ref()))
+            .collect(),
+    };
+
+    tracks
+        .into_iter()
+        .map(|(display, mic)| {
+            let Some(mic) = mic else {
+                return Ok(Vec::new());
+            };
+            let path = meta.path(&mic.path);
+            let audio = AudioData::from_file(&path)
+                .map_err(|e| format!("Failed to decode {}: {e}", path.display()))?;
+            let offset = match (mic.start_time, display.start_time) {
+                (Some(mic), Some(display)) => mic - display,
+                _ => 0.0,
+            };
+            Ok(detect_silences(&audio, settings)
+                .into_iter()
+                .map(|(start, end)| ((start + offset).max(0.0), end + offset))
+                .filter(|(start, end)| end > start)
+                .collect())
+        })
+        .collect()
+}
+
+pub fn auto_cut(
+    project_path: PathBuf,
+    settings: AutoCutSettings,
+    silence: SilenceSettings,
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let meta = RecordingMeta::load_for_project(&project_path)
+        .map_err(|e| format!("Failed to load recording meta: {e}"))?;
+    let Some(studio) = meta.studio_meta() else {
+        return Err("auto-cut needs a studio recording".to_string());
+    };
+
+    let mut config = match ProjectConfiguration::load(&project_path) {
+        Ok(config) => config,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_json::from_str("{}")
+            .map_err(|e| format!("Failed to build default project config: {e}"))?,
+        Err(e) => return Err(format!("Failed to load project config: {e}")),
+    };
+    let timeline = match config.timeline.take() {
+        Some(timeline) => timeline,
+        None => default_timeline(&meta, studio)?,
+    };
+    let timeline = config.timeline.insert(timeline);
+
+    let mut cuts = Vec::new();
+    if settings.cut_silences {
+        cuts.extend(silence_cuts(
+            &segment_silences(&meta, studio, &silence)?,
+            &settings,
+        ));
+    }
+    if settings.cut_fillers && timeline.caption_segments.is_empty() {
+        eprintln!(
+            "no captions on the timeline; add them with `cap captions generate` to cut filler words"
+        );
+    }
+    cuts.extend(filler_cuts(timeline, &settings));
+
+    let plan = plan_auto_cut(timeline, &cuts);
+    let applied = !dry_run && !plan.cuts.is_empty();
+    if applied {
+        plan.apply(timeline);
+        config
+            .write(&project_path)
+            .map_err(|e| format!("Failed to write project config: {e}"))?;
+    }
+
+    match format {
+        OutputFormat::Json => write_json(&AutoCutReport {
+            project_path: &project_path,
+            plan: &plan,
+            applied,
+        }),
+        OutputFormat::Text => {
+            if plan.cuts.is_empty() {
+                println!("nothing to cut in {}", project_path.display());
+                return Ok(());
+            }
+
+            println!("cuts (current timeline):");
+            for cut in &plan.cuts {
+                let reasons = cut
+                    .reasons
+                    .iter()
+                    .map(|reason| match reason {
+                        CutReason::Silence => "silence",
+                        CutReason::Filler => "filler",
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let words = if cut.words.is_empty() {
+                    String::new()
+                } else {
+                    format!("  \"{}\"", cut.words.join(" "))
+                };
+                println!(
+                    "{:>8.2}s - {:>8.2}s  {:>6.2}s  {reasons}{words}",
+                    cut.start,
+                    cut.end,
+                    cut.end - cut.start
+                );
+            }
+            println!("segments:");
+            for segment in &plan.segments {
+                println!(
+                    "  clip {:<3} {:>8.2}s - {:>8.2}s  x{}",
+                    segment.recording_clip, segment.start, segment.end, segment.timescale
+                );
+            }
+            println!(
+                "{:.2}s -> {:.2}s ({:.2}s removed){}",
+                plan.duration_before,
+                plan.duration_after,
+                plan.duration_before - plan.duration_after,
+                if applied { "" } else { ", dry run" }
+            );
+            Ok(())
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use cap_project::{
-    InstantRecordingMeta, Platform, ProjectConfiguration, RecordingMeta, RecordingMetaInner,
+    ChapterMarker, InstantRecordingMeta, Platform, ProjectConfiguration, RecordingMeta,
+    RecordingMetaInner,
 };
 use cap_recording::{
-    CameraFeed, MicrophoneFeed,
+    CameraFeed, LiveStreamConfig, MicrophoneFeed, PipelineHealthEvent, StreamTarget,
     feeds::{camera, microphone},
     instant_recording,
     screen_capture::ScreenCaptureTarget,
@@ -22,7 +23,7 @@ use std::{
     time::{Duration, Instant},
 };
 use tokio::io::AsyncBufReadExt;
-use tracing::debug;
+use tracing::{debug, warn};
 use uuid::Uuid;
 
 use crate::{
@@ -59,6 +60,14 @@ pub struct RecordParams {
     /// Stop automatically after N seconds
     #[arg(long)]
     duration: Option<f64>,
+    /// Also stream the recording live: rtmp(s):// or srt:// ingest URL, or an .m3u8 path/URL for a
+    /// rolling HLS playlist (instant mode only)
+    #[arg(long)]
+    stream_url: Option<String>,
+    /// Keep the microphone and system audio as separate, labelled tracks instead of mixing them
+    /// (instant mode only; `cap export` and `cap upload` mix them back down)
+    #[arg(long)]
+    separate_audio_tracks: bool,
 }
 
 impl RecordParams {
@@ -72,6 +81,22 @@ impl RecordParams {
         if self.fps == Some(0) {
             return Err("--fps must be greater than 0".to_string());
         }
+        if let Some(url) = &self.stream_url {
+            if self.mode != RecordMode::Instant {
+                return Err("--stream-url requires --mode instant".to_string());
+            }
+            StreamTarget::parse(url).map_err(|e| e.to_string())?;
+        }
+        if self.separate_audio_tracks {
+            if self.mode != RecordMode::Instant {
+                return Err("--separate-audio-tracks requires --mode instant".to_string());
+            }
+            if self.stream_url.is_some() {
+                return Err(
+                    "--separate-audio-tracks can't be combined with --stream-url".to_string(),
+                );
+            }
+        }
         Ok(())
     }
 
@@ -110,6 +135,13 @@ impl RecordParams {
             args.push("--duration".to_string());
             args.push(duration.to_string());
         }
+        if let Some(url) = &self.stream_url {
+            args.push("--stream-url".to_string());
+            args.push(url.clone());
+        }
+        if self.separate_audio_tracks {
+            args.push("--separate-audio-tracks".to_string());
+        }
         args
     }
 }
@@ -167,6 +199,22 @@ pub struct RecordStopArgs {
     format: OutputFormat,
 }
 
+#[derive(Args)]
+pub struct RecordMarkArgs {
+    /// recordingId returned by `cap record start --detach`
+    #[arg(long)]
+    id: Option<String>,
+    /// The '.cap' project path of the recording to mark (alternative to --id)
+    #[arg(long)]
+    path: Option<PathBuf>,
+    /// Chapter title; untitled chapters are numbered when exported
+    #[arg(long)]
+    title: Option<String>,
+    /// Output format for status events
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
 impl RecordStart {
     pub async fn run(self, json: bool) -> Result<(), String> {
         let format = resolve_format(json, self.format);
@@ -448,8 +496,7 @@ async fn session_worker(params: RecordParams, recording_id: &str) -> Result<(),
         error: None,
     })?;
 
-    let stop_path = session::stop_file(recording_id)?;
-    let completed = finalize(actor, params.duration, false, Some(&stop_path)).await?;
+    let completed = finalize(actor, params.duration, false, Some(recording_id)).await?;
     crate::automation::run_recording_finished(
         completed.project_path(),
         automation_mode(params.mode),
@@ -565,6 +612,53 @@ impl RecordStopArgs {
     }
 }
 
+/// How long `cap record mark` waits for the worker to pick up the request; it polls every 150ms.
+const MARK_ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl RecordMarkArgs {
+    pub async fn run(self, json: bool) -> Result<(), String> {
+        let format = resolve_format(json, self.format);
+        match self.run_inner(format).await {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                if format == OutputFormat::Json {
+                    let _ = write_json_line(&RecordEvent::Error { error: &error });
+                }
+                Err(error)
+            }
+        }
+    }
+
+    async fn run_inner(self, format: OutputFormat) -> Result<(), String> {
+        let session = resolve_session(self.id.as_deref(), self.path.as_deref())?;
+        let id = session.recording_id;
+        if session.status != SessionStatus::Recording || !session::process_alive(session.pid) {
+            return Err(format!("Recording '{id}' is not in progress"));
+        }
+
+        let request = session::request_mark(&id, self.title.as_deref())?;
+
+        let deadline = Instant::now() + MARK_ACK_TIMEOUT;
+        while request.exists() {
+            if Instant::now() >= deadline {
+                let _ = std::fs::remove_file(&request);
+                return Err(format!(
+                    "timed out waiting for recording '{id}' to add the chapter marker"
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        emit_record_event(
+            format,
+            &RecordEvent::Marked {
+                recording_id: &id,
+                title: self.title.as_deref(),
+            },
+        )
+    }
+}
+
 fn cleanup_session(id: &str, project_path: &Path) {
     if let Err(error) = session::archive_log(id, project_path) {
         debug!("{error}");
@@ -697,6 +791,14 @@ enum ActorHandle {
 }
 
 impl ActorHandle {
+    async fn add_chapter_marker(&self, title: Option<String>) -> Result<ChapterMarker, String> {
+        match self {
+            Self::Studio(actor) => actor.add_chapter_marker(title).await,
+            Self::Instant(actor) => actor.add_chapter_marker(title).await,
+        }
+        .map_err(|e| e.to_string())
+    }
+
     async fn stop(&self) -> Result<CompletedRecording, String> {
         match self {
             Self::Studio(actor) => actor
@@ -832,8 +934,13 @@ async fn start_recording(
             if let Some(fps) = params.fps {
                 builder = builder.with_max_fps(fps);
             }
+            builder = builder.with_separate_audio_tracks(params.separate_audio_tracks);
+            if let Some(url) = params.stream_url.as_deref() {
+                let target = StreamTarget::parse(url).map_err(|e| e.to_string())?;
+                builder = builder.with_live_stream(LiveStreamConfig::new(target));
+            }
 
-            builder
+            let mut handle = builder
                 .build(
                     #[cfg(target_os = "macos")]
                     Some(
@@ -841,8 +948,40 @@ async fn start_recording(
                     ),
                 )
                 .await
-                .map(ActorHandle::Instant)
-                .map_err(|e| e.to_string())
+                .map_err(|e| e.to_string())?;
+
+            if params.stream_url.is_some()
+                && let Some(health_rx) = handle.take_health_rx()
+            {
+                tokio::spawn(report_stream_health(health_rx));
+            }
+
+            Ok(ActorHandle::Instant(handle))
+        }
+    }
+}
+
+/// Stream problems don't stop the recording, so they are reported on stderr rather than as
+/// record events.
+async fn report_stream_health(mut health_rx: cap_recording::HealthReceiver) {
+    while let Some(event) = health_rx.recv().await {
+        match event {
+            PipelineHealthEvent::StreamReconnecting {
+                target,
+                attempt,
+                retry_in_ms,
+                reason,
+            } => eprintln!(
+                "warning: Live stream to {target} failed ({reason}); retry {attempt} in {:.1}s",
+                retry_in_ms as f64 / 1000.0
+            ),
+            PipelineHealthEvent::StreamReconnected { target, .. } => {
+                eprintln!("Live stream to {target} reconnected")
+            }
+            PipelineHealthEvent::StreamFailed { target, reason } => {
+                eprintln!("warning: Live stream to {target} stopped: {reason}")
+            }
+            _ => {}
         }
     }
 }
@@ -932,10 +1071,14 @@ async fn finalize(
     actor: ActorHandle,
     duration: Option<f64>,
     interactive: bool,
-    stop_file: Option<&Path>,
+    session_id: Option<&str>,
 ) -> Result<CompletedRecording, String> {
+    let stop_file = session_id.map(session::stop_file).transpose()?;
     let outcome = std::panic::AssertUnwindSafe(async {
-        wait_for_stop(duration, interactive, stop_file).await;
+        tokio::select! {
+            _ = wait_for_stop(duration, interactive, stop_file.as_deref()) => {}
+            _ = apply_mark_requests(&actor, session_id) => {}
+        }
         actor.stop().await.map_err(|e| e.to_string())
     })
     .catch_unwind()
@@ -1024,6 +1167,7 @@ fn persist_instant_recording_meta(
         sharing: None,
         inner: RecordingMetaInner::Instant(meta),
         upload: None,
+        chapters: recording.chapters.clone(),
     }
     .save_for_project()
     .map_err(|e| format!("Failed to save instant recording meta: {e}"))?;
@@ -1092,6 +1236,24 @@ fn resolve_target(params: &RecordParams) -> Result<ScreenCaptureTarget, String>
     }
 }
 
+/// Adds a chapter marker for each `cap record mark` request a detached session receives. Runs until
+/// the recording stops, so markers land within one poll of being requested.
+async fn apply_mark_requests(actor: &ActorHandle, session_id: Option<&str>) {
+    let Some(session_id) = session_id else {
+        return std::future::pending().await;
+    };
+
+    loop {
+        for title in session::take_mark_requests(session_id) {
+            match actor.add_chapter_marker(title).await {
+                Ok(marker) => debug!(time = marker.time, "Added chapter marker"),
+                Err(error) => warn!("Could not add chapter marker: {error}"),
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(150)).await;
+    }
+}
+
 /// Block until the recording should stop: the duration elapses, the user presses Enter (interactive
 /// only), the process receives SIGINT/SIGTERM, or a detached worker's stop file appears. Every branch
 /// resolves so the caller can finalize the recording gracefully instead of being killed mid-write.
@@ -1167,6 +1329,11 @@ enum RecordEvent<'a> {
         path: &'a str,
         recording_meta_exists: bool,
     },
+    Marked {
+        recording_id: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        title: Option<&'a str>,
+    },
     Error {
         error: &'a str,
     },
@@ -1190,6 +1357,13 @@ fn emit_record_event(format: OutputFormat, event: &RecordEvent<'_>) -> Result<()
                         println!("warning: recording-meta.json was not written");
                     }
                 }
+                RecordEvent::Marked {
+                    recording_id,
+                    title,
+                } => match title {
+                    Some(title) => println!("Chapter marker '{title}' added to {recording_id}"),
+                    None => println!("Chapter marker added to {recording_id}"),
+                },
                 RecordEvent::Error { error } => println!("Recording error: {error}"),
             }
             Ok(())
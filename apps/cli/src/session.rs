@@ -3,7 +3,8 @@
 //! A detached recording runs in a re-exec'd worker process. The worker writes a `<id>.json` session
 //! file describing the live recording; `cap record stop` requests a stop by creating a `<id>.stop`
 //! file (which the worker polls for, so it works on Windows where there is no SIGTERM) and waits for
-//! the worker to flip the session status. `cap record status` lists these files.
+//! the worker to flip the session status. `cap record status` lists these files. `cap record mark`
+//! drops `<id>.<n>.mark` files the worker turns into chapter markers the same way.
 
 use std::{
     path::{Path, PathBuf},
@@ -109,10 +110,77 @@ pub fn stop_requested(path: &Path) -> bool {
     path.exists()
 }
 
+#[derive(Serialize, Deserialize, Default)]
+struct MarkRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+/// Asks the worker for `id` to add a chapter marker, returning the request file, which the worker
+/// removes once the marker is added.
+pub fn request_mark(id: &str, title: Option<&str>) -> Result<PathBuf, String> {
+    let dir = sessions_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create sessions dir: {e}"))?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let path = dir.join(format!("{id}.{nanos:020}.mark"));
+    let tmp = path.with_extension("mark.tmp");
+    let body = serde_json::to_vec(&MarkRequest {
+        title: title.map(str::to_string),
+    })
+    .map_err(|e| e.to_string())?;
+    // Same temp-then-rename as the session file, so the worker never reads a half-written request.
+    std::fs::write(&tmp, body).map_err(|e| format!("Could not request marker: {e}"))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Could not request marker: {e}"))?;
+    Ok(path)
+}
+
+/// Pending mark request files for `id`, oldest first.
+fn mark_files(id: &str) -> Vec<PathBuf> {
+    let Ok(entries) =
+        sessions_dir().and_then(|dir| std::fs::read_dir(dir).map_err(|e| e.to_string()))
+    else {
+        return Vec::new();
+    };
+    let prefix = format!("{id}.");
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "mark")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Takes the pending mark requests for `id`, oldest first, as their titles. Each file is removed as
+/// it is read so a request is applied once.
+pub fn take_mark_requests(id: &str) -> Vec<Option<String>> {
+    mark_files(id)
+        .into_iter()
+        .filter_map(|path| {
+            let body = std::fs::read(&path).ok()?;
+            std::fs::remove_file(&path).ok()?;
+            Some(
+                serde_json::from_slice::<MarkRequest>(&body)
+                    .unwrap_or_default()
+                    .title,
+            )
+        })
+        .collect()
+}
+
 pub fn cleanup(id: &str) {
     for path in [session_file(id), stop_file(id), log_file(id)]
         .into_iter()
         .flatten()
+        .chain(mark_files(id))
     {
         let _ = std::fs::remove_file(path);
     }
@@ -1,13 +1,26 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
 
+use cap_api::{Client, CreateVideo, S3VideoMeta, UploadedPart};
 use cap_project::RecordingMeta;
 use clap::Args;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tokio_util::io::ReaderStream;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
-use crate::{OutputFormat, credentials, export, resolve_format, write_json};
+use crate::{OutputFormat, credentials, export, resolve_format, write_json_line};
+
+/// Parts are at least 8 MiB: S3 needs 5 MiB for every part but the last, and Drive resumable
+/// sessions need multiples of 256 KiB. Huge files get bigger parts to stay under S3's part limit.
+const MIN_PART_SIZE: u64 = 8 * 1024 * 1024;
+const PART_ALIGNMENT: u64 = 256 * 1024;
+const MAX_PARTS: u64 = 10_000;
+const MAX_PART_ATTEMPTS: u32 = 5;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MANIFEST_VERSION: u32 = 1;
 
 #[derive(Args)]
 pub struct UploadArgs {
@@ -22,6 +35,12 @@ pub struct UploadArgs {
     /// If the input is a '.cap' project with no exported video yet, export it first
     #[arg(long)]
     export: bool,
+    /// Continue an interrupted upload of this file from its last completed part. With --video-id,
+    /// the interrupted upload must be for that video
+    #[arg(long)]
+    resume: bool,
+    /// Output format. json streams newline-delimited events: {"type":"progress","id","uploaded","total"}
+    /// after each part, then {"type":"uploaded","id","link"}
     #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
     format: OutputFormat,
 }
@@ -29,14 +48,166 @@ pub struct UploadArgs {
 #[derive(Serialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 enum UploadEvent<'a> {
-    Uploaded { id: &'a str, link: &'a str },
+    Progress {
+        id: &'a str,
+        uploaded: u64,
+        total: u64,
+    },
+    Uploaded {
+        id: &'a str,
+        link: &'a str,
+    },
+}
+
+/// Sidecar written next to the file while a multipart upload is in flight, so `--resume` can skip
+/// the parts storage already has. Parts upload in order, so the completed ones are always a prefix.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct UploadManifest {
+    version: u32,
+    server: String,
+    video_id: String,
+    upload_id: String,
+    file_size: u64,
+    /// Modification time of the file in milliseconds since the epoch; a changed file can't resume.
+    file_modified_ms: u64,
+    part_size: u64,
+    parts: Vec<ManifestPart>,
 }
 
-struct VideoMeta {
-    duration_in_secs: f64,
-    width: u32,
-    height: u32,
-    fps: f64,
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ManifestPart {
+    part_number: u32,
+    etag: String,
+    size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    size: u64,
+    modified_ms: u64,
+}
+
+fn manifest_path(file: &Path) -> PathBuf {
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    file.with_file_name(format!("{name}.upload.json"))
+}
+
+fn part_size_for(total: u64) -> u64 {
+    total
+        .div_ceil(MAX_PARTS)
+        .max(MIN_PART_SIZE)
+        .div_ceil(PART_ALIGNMENT)
+        * PART_ALIGNMENT
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.min(5)).min(MAX_RETRY_DELAY)
+}
+
+fn file_stamp(path: &Path) -> Result<FileStamp, String> {
+    let metadata =
+        std::fs::metadata(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis() as u64);
+    Ok(FileStamp {
+        size: metadata.len(),
+        modified_ms,
+    })
+}
+
+impl UploadManifest {
+    fn load(path: &Path) -> Result<Option<Self>, String> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| format!("Upload manifest {} is corrupt: {e}", path.display()))
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        // Write-then-rename so a crash mid-write never leaves a truncated manifest behind.
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_vec_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&tmp, json)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+
+    fn check_resumable(
+        &self,
+        server: &str,
+        stamp: FileStamp,
+        video_id: Option<&str>,
+    ) -> Result<(), String> {
+        if self.version != MANIFEST_VERSION {
+            return Err(format!(
+                "Upload manifest version {} is not supported; upload again without --resume",
+                self.version
+            ));
+        }
+        if let Some(video_id) = video_id
+            && video_id != self.video_id
+        {
+            return Err(format!(
+                "The interrupted upload was for video {}, not {video_id}; upload again without --resume",
+                self.video_id
+            ));
+        }
+        if self.server != server {
+            return Err(format!(
+                "The interrupted upload went to {}, not {server}; upload again without --resume",
+                self.server
+            ));
+        }
+        if self.file_size != stamp.size || self.file_modified_ms != stamp.modified_ms {
+            return Err(
+                "The file changed since the upload was interrupted; upload again without --resume"
+                    .to_string(),
+            );
+        }
+        let in_order = self
+            .parts
+            .iter()
+            .enumerate()
+            .all(|(i, part)| part.part_number as usize == i + 1);
+        if !in_order || self.uploaded_bytes() > self.file_size {
+            return Err(
+                "Upload manifest parts are inconsistent; upload again without --resume".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    fn uploaded_bytes(&self) -> u64 {
+        self.parts.iter().map(|part| part.size).sum()
+    }
+
+    fn next_part_number(&self) -> u32 {
+        self.parts.len() as u32 + 1
+    }
+
+    fn uploaded_parts(&self) -> Vec<UploadedPart> {
+        self.parts
+            .iter()
+            .map(|part| UploadedPart {
+                part_number: part.part_number,
+                etag: part.etag.clone(),
+                size: part.size as usize,
+                total_size: self.file_size,
+            })
+            .collect()
+    }
 }
 
 fn rational_fps(rate: ffmpeg::Rational) -> Option<f64> {
@@ -56,7 +227,7 @@ impl UploadArgs {
             Ok(()) => Ok(()),
             Err(error) => {
                 if format == OutputFormat::Json {
-                    let _ = write_json(&json!({ "error": error }));
+                    let _ = write_json_line(&json!({ "error": error }));
                 }
                 Err(error)
             }
@@ -66,21 +237,35 @@ impl UploadArgs {
     async fn run_inner(self, format: OutputFormat) -> Result<(), String> {
         // Resolves CAP_API_KEY, else the login Cap Desktop already stored, so an agent never has to
         // fetch or paste a key when the user is signed in.
-        let creds = credentials::resolve()?;
-        let server = creds.server.clone();
+        let client = credentials::resolve()?.client();
 
-        let file_path = self.resolve_upload_file().await?;
+        let source_path = self.resolve_upload_file().await?;
+        let file_path = downmixed_for_upload(source_path.clone()).await?;
         let meta = probe_video_meta(&file_path)?;
 
-        let http = Client::new();
-        let auth = format!("Bearer {}", creds.api_key);
-
-        let video_id =
-            create_video(&http, &server, &auth, &self.name, self.video_id, &meta).await?;
-        let put_url = presign_put(&http, &server, &auth, &video_id, &meta).await?;
-        upload_file(&http, &put_url, &file_path).await?;
-
-        let link = format!("{server}/s/{video_id}");
+        let video_id = upload_mp4(
+            &client,
+            &file_path,
+            UploadOptions {
+                name: self.name.clone(),
+                video_id: self.video_id.clone(),
+                resume: self.resume,
+            },
+            meta,
+            |id, uploaded, total| {
+                if format == OutputFormat::Json {
+                    // Progress output must never fail the upload itself.
+                    let _ = write_json_line(&UploadEvent::Progress {
+                        id,
+                        uploaded,
+                        total,
+                    });
+                }
+            },
+        )
+        .await?;
+        remove_downmixed(&source_path, &file_path);
+        let link = client.share_link(&video_id);
 
         let is_project = self.file.is_dir()
             || self
@@ -92,7 +277,7 @@ impl UploadArgs {
         }
 
         match format {
-            OutputFormat::Json => write_json(&UploadEvent::Uploaded {
+            OutputFormat::Json => write_json_line(&UploadEvent::Uploaded {
                 id: &video_id,
                 link: &link,
             })?,
@@ -153,21 +338,253 @@ pub async fn upload_video_path(file_path: &Path, name: Option<String>) -> Result
         ));
     }
 
-    let creds = credentials::resolve()?;
-    let server = creds.server.clone();
+    let client = credentials::resolve()?.client();
+    let source_path = file_path;
+    let file_path = &downmixed_for_upload(source_path.to_path_buf()).await?;
     let meta = probe_video_meta(file_path)?;
 
-    let http = Client::new();
-    let auth = format!("Bearer {}", creds.api_key);
+    let video_id = upload_mp4(
+        &client,
+        file_path,
+        UploadOptions {
+            name,
+            ..Default::default()
+        },
+        meta,
+        |_, _, _| {},
+    )
+    .await?;
+    remove_downmixed(source_path, file_path);
+    Ok(client.share_link(&video_id))
+}
+
+/// Where the mixed copy of `file` is cached, keyed on a SHA-256 of its canonical path so two
+/// recordings named `output.mp4` don't share one and the name survives toolchain upgrades. Kept
+/// out of the user's folders; its resume manifest sits beside it.
+fn downmixed_path(cache_dir: &Path, file: &Path) -> PathBuf {
+    let canonical = std::fs::canonicalize(file)
+        .or_else(|_| std::path::absolute(file))
+        .unwrap_or_else(|_| file.to_path_buf());
+    let digest = Sha256::digest(canonical.as_os_str().as_encoded_bytes());
+    let key = u64::from_be_bytes(
+        digest[..8]
+            .try_into()
+            .expect("sha256 produces at least 8 bytes"),
+    );
+
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    cache_dir.join(format!("{stem}-{key:016x}.mp4"))
+}
+
+fn downmix_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cap")
+        .join("upload-mixdown")
+}
+
+/// The share page only plays a video's first audio track, so an instant recording made with
+/// separate audio tracks is uploaded as a cached copy with them mixed into one. The copy is reused
+/// while it is newer than the recording, so `--resume` still finds its manifest, and is removed by
+/// [`remove_downmixed`] once the upload finishes.
+async fn downmixed_for_upload(file: PathBuf) -> Result<PathBuf, String> {
+    let cache_dir = downmix_cache_dir();
+    let mixed = downmixed_path(&cache_dir, &file);
+    tokio::task::spawn_blocking(move || {
+        if cap_export::audio_tracks::audio_track_count(&file) < 2 {
+            return Ok(file);
+        }
+        if let (Ok(source), Ok(existing)) = (file_stamp(&file), file_stamp(&mixed))
+            && existing.modified_ms >= source.modified_ms
+        {
+            return Ok(mixed);
+        }
+        std::fs::create_dir_all(&cache_dir)
+            .map_err(|e| format!("Failed to create {}: {e}", cache_dir.display()))?;
+        cap_export::audio_tracks::copy_with_downmix(&file, &mixed)
+            .map_err(|e| format!("Failed to mix the audio tracks of {}: {e}", file.display()))?;
+        Ok(mixed)
+    })
+    .await
+    .map_err(|e| format!("audio mixdown task failed: {e}"))?
+}
+
+/// Deletes the cached mixed copy once it's uploaded; a no-op when `source` was uploaded as-is.
+fn remove_downmixed(source: &Path, uploaded: &Path) {
+    if source != uploaded {
+        let _ = std::fs::remove_file(uploaded);
+    }
+}
 
-    let video_id = create_video(&http, &server, &auth, &name, None, &meta).await?;
-    let put_url = presign_put(&http, &server, &auth, &video_id, &meta).await?;
-    upload_file(&http, &put_url, file_path).await?;
+#[derive(Default)]
+struct UploadOptions {
+    name: Option<String>,
+    video_id: Option<String>,
+    resume: bool,
+}
 
-    Ok(format!("{server}/s/{video_id}"))
+/// Uploads the MP4 as a multipart upload, one part at a time, recording each finished part in the
+/// manifest so an interrupted upload can pick up where it stopped. Returns the video id.
+async fn upload_mp4(
+    client: &Client,
+    file_path: &Path,
+    options: UploadOptions,
+    meta: S3VideoMeta,
+    mut on_progress: impl FnMut(&str, u64, u64),
+) -> Result<String, String> {
+    let manifest_path = manifest_path(file_path);
+    let stamp = file_stamp(file_path)?;
+    let existing = UploadManifest::load(&manifest_path)?;
+
+    let mut manifest = if options.resume {
+        let manifest = existing.ok_or_else(|| {
+            format!(
+                "No interrupted upload to resume for {}",
+                file_path.display()
+            )
+        })?;
+        manifest.check_resumable(client.base_url(), stamp, options.video_id.as_deref())?;
+        manifest
+    } else {
+        if let Some(stale) = existing {
+            // Starting over; free the storage the abandoned parts hold.
+            let _ = client
+                .multipart_abort(&stale.video_id, &stale.upload_id)
+                .await;
+        }
+
+        let video = client
+            .create_video(&CreateVideo {
+                video_id: options.video_id,
+                name: options.name,
+                meta: Some(meta.clone()),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        // The web player resolves a desktopMP4 video from the canonical `<id>/result.mp4` key,
+        // which is where multipart uploads land, so `cap upload` guards its input to MP4 to keep
+        // the stored bytes consistent.
+        let upload = client
+            .multipart_initiate(&video.id, "video/mp4")
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let manifest = UploadManifest {
+            version: MANIFEST_VERSION,
+            server: client.base_url().to_string(),
+            video_id: video.id,
+            upload_id: upload.upload_id,
+            file_size: stamp.size,
+            file_modified_ms: stamp.modified_ms,
+            part_size: part_size_for(stamp.size),
+            parts: vec![],
+        };
+        manifest.save(&manifest_path)?;
+        manifest
+    };
+
+    let total = manifest.file_size;
+    let mut offset = manifest.uploaded_bytes();
+    on_progress(&manifest.video_id, offset, total);
+
+    // Read one part at a time rather than the whole file — recordings can be gigabytes, and
+    // buffering them would OOM on exactly the long unattended recordings agents produce.
+    let mut file = tokio::fs::File::open(file_path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {e}", file_path.display()))?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("Failed to read {}: {e}", file_path.display()))?;
+
+    while offset < total {
+        let part_number = manifest.next_part_number();
+        let mut chunk = vec![0; manifest.part_size.min(total - offset) as usize];
+        file.read_exact(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read {}: {e}", file_path.display()))?;
+        let size = chunk.len() as u64;
+
+        let part = upload_part(client, &manifest, part_number, chunk, offset)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Upload interrupted at part {part_number} ({offset} of {total} bytes sent): {e}. \
+                     Run the same `cap upload` command with --resume to continue"
+                )
+            })?;
+
+        manifest.parts.push(ManifestPart {
+            part_number,
+            etag: part.etag,
+            size,
+        });
+        manifest.save(&manifest_path)?;
+
+        offset += size;
+        on_progress(&manifest.video_id, offset, total);
+        // Lets the share page show progress while the upload runs; purely informational.
+        let _ = client
+            .video_progress(&manifest.video_id, offset, total)
+            .await;
+    }
+
+    client
+        .multipart_complete(
+            &manifest.video_id,
+            &manifest.upload_id,
+            &manifest.uploaded_parts(),
+            Some(meta),
+        )
+        .await
+        .map_err(|e| {
+            format!("Failed to finish the upload: {e}. Run the same `cap upload` command with --resume to retry")
+        })?;
+
+    let _ = std::fs::remove_file(&manifest_path);
+    Ok(manifest.video_id)
 }
 
-fn probe_video_meta(path: &Path) -> Result<VideoMeta, String> {
+/// Uploads one part, presigning a fresh URL and backing off between attempts so a flaky connection
+/// gets a few chances to recover before the upload is reported as interrupted.
+async fn upload_part(
+    client: &Client,
+    manifest: &UploadManifest,
+    part_number: u32,
+    chunk: Vec<u8>,
+    offset: u64,
+) -> Result<UploadedPart, cap_api::Error> {
+    let mut attempt = 1;
+    loop {
+        let result = async {
+            let url = client
+                .multipart_presign_part(&manifest.video_id, &manifest.upload_id, part_number, None)
+                .await?;
+            client
+                .put_part(&url, part_number, chunk.clone(), offset, manifest.file_size)
+                .await
+        }
+        .await;
+
+        match result {
+            Ok(part) => return Ok(part),
+            Err(e @ (cap_api::Error::Unauthenticated | cap_api::Error::UpgradeRequired)) => {
+                return Err(e);
+            }
+            Err(e) if attempt >= MAX_PART_ATTEMPTS => return Err(e),
+            Err(e) => {
+                tracing::warn!(part_number, attempt, error = %e, "Part upload failed, retrying");
+                tokio::time::sleep(retry_delay(attempt)).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+pub(crate) fn probe_video_meta(path: &Path) -> Result<S3VideoMeta, String> {
     ffmpeg::init().map_err(|e| format!("Failed to initialise FFmpeg: {e}"))?;
 
     let input = ffmpeg::format::input(path)
@@ -216,139 +633,190 @@ fn probe_video_meta(path: &Path) -> Result<VideoMeta, String> {
         }
     };
 
-    Ok(VideoMeta {
+    Ok(S3VideoMeta {
         duration_in_secs,
         width,
         height,
-        fps,
+        fps: Some(fps as f32),
     })
 }
 
-async fn create_video(
-    http: &Client,
-    server: &str,
-    auth: &str,
-    name: &Option<String>,
-    video_id: Option<String>,
-    meta: &VideoMeta,
-) -> Result<String, String> {
-    #[derive(Deserialize)]
-    struct CreateResponse {
-        id: String,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    fn manifest(parts: &[u64]) -> UploadManifest {
+        UploadManifest {
+            version: MANIFEST_VERSION,
+            server: "https://cap.so".to_string(),
+            video_id: "video".to_string(),
+            upload_id: "upload".to_string(),
+            file_size: 20 * MIB,
+            file_modified_ms: 1_700_000_000_000,
+            part_size: 8 * MIB,
+            parts: parts
+                .iter()
+                .enumerate()
+                .map(|(i, size)| ManifestPart {
+                    part_number: i as u32 + 1,
+                    etag: format!("etag-{}", i + 1),
+                    size: *size,
+                })
+                .collect(),
+        }
     }
 
-    let mut params: Vec<(&str, String)> = vec![
-        ("recordingMode", "desktopMP4".to_string()),
-        ("durationInSecs", meta.duration_in_secs.to_string()),
-        ("width", meta.width.to_string()),
-        ("height", meta.height.to_string()),
-    ];
-    params.push(("fps", meta.fps.to_string()));
-    if let Some(name) = name {
-        params.push(("name", name.clone()));
-    }
-    if let Some(id) = &video_id {
-        params.push(("videoId", id.clone()));
+    fn stamp() -> FileStamp {
+        FileStamp {
+            size: 20 * MIB,
+            modified_ms: 1_700_000_000_000,
+        }
     }
 
-    let response = http
-        .get(format!("{server}/api/desktop/video/create"))
-        .header("Authorization", auth)
-        .query(&params)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to reach Cap: {e}"))?;
+    #[test]
+    fn part_size_respects_minimum_alignment_and_part_limit() {
+        assert_eq!(part_size_for(0), MIN_PART_SIZE);
+        assert_eq!(part_size_for(100 * MIB), MIN_PART_SIZE);
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("Video creation failed ({status}): {body}"));
+        let huge = 200 * 1024 * MIB;
+        let size = part_size_for(huge);
+        assert!(huge.div_ceil(size) <= MAX_PARTS);
+        assert!(size.is_multiple_of(PART_ALIGNMENT));
     }
 
-    response
-        .json::<CreateResponse>()
-        .await
-        .map(|r| r.id)
-        .map_err(|e| format!("Unexpected video-create response: {e}"))
-}
+    #[test]
+    fn manifest_sits_next_to_the_file() {
+        assert_eq!(
+            manifest_path(Path::new("/tmp/demo.cap/output/result.mp4")),
+            Path::new("/tmp/demo.cap/output/result.mp4.upload.json")
+        );
+    }
 
-async fn presign_put(
-    http: &Client,
-    server: &str,
-    auth: &str,
-    video_id: &str,
-    meta: &VideoMeta,
-) -> Result<String, String> {
-    #[derive(Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    struct SignedResponse {
-        presigned_put_data: PresignedPutData,
+    #[test]
+    fn downmixed_copy_is_cached_per_source_path() {
+        let cache = Path::new("/cache/cap/upload-mixdown");
+        let first = downmixed_path(cache, Path::new("/tmp/a.cap/content/output.mp4"));
+        let second = downmixed_path(cache, Path::new("/tmp/b.cap/content/output.mp4"));
+
+        assert_eq!(first.parent(), Some(cache));
+        assert!(
+            first
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("output-")
+        );
+        assert_ne!(first, second);
+        assert_eq!(
+            first,
+            downmixed_path(cache, Path::new("/tmp/a.cap/content/output.mp4"))
+        );
+    }
+
+    #[test]
+    fn resume_continues_after_completed_parts() {
+        let manifest = manifest(&[8 * MIB, 8 * MIB]);
+        manifest
+            .check_resumable("https://cap.so", stamp(), None)
+            .unwrap();
+        assert_eq!(manifest.uploaded_bytes(), 16 * MIB);
+        assert_eq!(manifest.next_part_number(), 3);
+
+        let parts = manifest.uploaded_parts();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1].etag, "etag-2");
+        assert_eq!(parts[1].total_size, 20 * MIB);
     }
-    #[derive(Deserialize)]
-    struct PresignedPutData {
-        url: String,
+
+    #[test]
+    fn resume_rejects_changed_file_or_server() {
+        let manifest = manifest(&[8 * MIB]);
+
+        let grown = FileStamp {
+            size: 21 * MIB,
+            ..stamp()
+        };
+        assert!(
+            manifest
+                .check_resumable("https://cap.so", grown, None)
+                .is_err()
+        );
+
+        let touched = FileStamp {
+            modified_ms: 1,
+            ..stamp()
+        };
+        assert!(
+            manifest
+                .check_resumable("https://cap.so", touched, None)
+                .is_err()
+        );
+
+        let err = manifest
+            .check_resumable("https://self-hosted.example", stamp(), None)
+            .unwrap_err();
+        assert!(err.contains("https://cap.so"), "{err}");
     }
 
-    let response = http
-        .post(format!("{server}/api/upload/signed"))
-        .header("Authorization", auth)
-        .json(&json!({
-            "videoId": video_id,
-            // The web player resolves a desktopMP4 video from the canonical `<id>/result.mp4` key, and
-            // the server only marks the upload complete when the key ends in `result.mp4` — so this is
-            // fixed, and `cap upload` guards its input to MP4 to keep the stored bytes consistent.
-            "subpath": "result.mp4",
-            "method": "put",
-            "durationInSecs": meta.duration_in_secs,
-            "width": meta.width,
-            "height": meta.height,
-            "fps": meta.fps,
-        }))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to request upload URL: {e}"))?;
+    #[test]
+    fn resume_rejects_gaps_in_parts() {
+        let mut manifest = manifest(&[8 * MIB, 8 * MIB]);
+        manifest.parts.remove(0);
+        assert!(
+            manifest
+                .check_resumable("https://cap.so", stamp(), None)
+                .is_err()
+        );
+    }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("Upload URL request failed ({status}): {body}"));
+    #[test]
+    fn resume_with_video_id_must_match_the_interrupted_upload() {
+        let manifest = manifest(&[8 * MIB]);
+        manifest
+            .check_resumable("https://cap.so", stamp(), Some("video"))
+            .unwrap();
+
+        let err = manifest
+            .check_resumable("https://cap.so", stamp(), Some("other"))
+            .unwrap_err();
+        assert!(err.contains("other"), "{err}");
     }
 
-    response
-        .json::<SignedResponse>()
-        .await
-        .map(|r| r.presigned_put_data.url)
-        .map_err(|e| format!("Unexpected upload-URL response: {e}"))
-}
+    #[test]
+    fn manifest_round_trips_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.mp4.upload.json");
+        assert_eq!(UploadManifest::load(&path).unwrap(), None);
 
-async fn upload_file(http: &Client, put_url: &str, path: &Path) -> Result<(), String> {
-    // Stream the file rather than buffering it into memory — recordings can be gigabytes, so a
-    // `tokio::fs::read` would OOM on exactly the long unattended recordings agents produce. S3 PUT
-    // needs an explicit Content-Length for a streamed body (it rejects chunked transfer encoding),
-    // mirroring the desktop's singlepart uploader.
-    let file = tokio::fs::File::open(path)
-        .await
-        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
-    let total_size = file
-        .metadata()
-        .await
-        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?
-        .len();
-    let body = reqwest::Body::wrap_stream(ReaderStream::new(file));
-
-    let response = http
-        .put(put_url)
-        .header(reqwest::header::CONTENT_LENGTH, total_size)
-        .body(body)
-        .send()
-        .await
-        .map_err(|e| format!("Upload failed: {e}"))?;
+        let manifest = manifest(&[8 * MIB]);
+        manifest.save(&path).unwrap();
+        assert_eq!(UploadManifest::load(&path).unwrap(), Some(manifest));
+        assert!(!path.with_extension("json.tmp").exists());
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("Storage upload failed ({status}): {body}"));
+        std::fs::write(&path, b"{").unwrap();
+        assert!(UploadManifest::load(&path).is_err());
     }
 
-    Ok(())
+    #[test]
+    fn retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(2), Duration::from_secs(4));
+        assert_eq!(retry_delay(10), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn progress_events_are_tagged_camel_case() {
+        let event = serde_json::to_value(UploadEvent::Progress {
+            id: "video",
+            uploaded: 8,
+            total: 20,
+        })
+        .unwrap();
+        assert_eq!(
+            event,
+            json!({ "type": "progress", "id": "video", "uploaded": 8, "total": 20 })
+        );
+    }
 }
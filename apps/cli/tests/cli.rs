@@ -490,6 +490,85 @@ fn project_config_get_without_file_returns_default() {
     assert!(json.is_object(), "expected a default project config object");
 }
 
+#[test]
+fn project_history_lists_config_writes_and_restore_reverts() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("recording.cap");
+    write_single_segment_meta(&project);
+    let project_arg = project.to_str().unwrap();
+
+    for settings in [r#"{"cursor":{"size":100}}"#, r#"{"cursor":{"size":180}}"#] {
+        let set = run(&[
+            "project",
+            "config",
+            "set",
+            project_arg,
+            "--settings-json",
+            settings,
+        ]);
+        assert!(set.status.success(), "stderr: {}", stderr(&set));
+    }
+
+    let history = run(&["project", "history", project_arg, "--format", "json"]);
+    assert!(history.status.success(), "stderr: {}", stderr(&history));
+    let json = parse_json(&history);
+    let revisions = json["revisions"].as_array().unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(json["head"], revisions[1]["id"]);
+    assert!(
+        revisions[1]["changes"]
+            .as_array()
+            .unwrap()
+            .contains(&Value::from("cursor.size"))
+    );
+
+    let first = revisions[0]["id"].as_u64().unwrap().to_string();
+    let restore = run(&["project", "restore", project_arg, &first, "--json"]);
+    assert!(restore.status.success(), "stderr: {}", stderr(&restore));
+
+    let get = run(&["project", "config", "get", project_arg, "--json"]);
+    assert_eq!(parse_json(&get)["cursor"]["size"], 100);
+
+    let missing = run(&["project", "restore", project_arg, "999", "--json"]);
+    assert!(!missing.status.success());
+    assert!(parse_json(&missing)["error"].is_string());
+}
+
+#[test]
+fn project_migrate_check_reports_then_migrate_upgrades() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("recording.cap");
+    write_single_segment_meta(&project);
+
+    let check = run(&[
+        "project",
+        "migrate",
+        dir.path().to_str().unwrap(),
+        "--check",
+        "--format",
+        "json",
+    ]);
+    assert!(
+        !check.status.success(),
+        "--check should fail when projects need migration"
+    );
+    let json = parse_json(&check);
+    assert_eq!(json["projects"][0]["needsMigration"], true);
+    assert_eq!(json["projects"][0]["files"][0]["fromVersion"], 0);
+    assert!(!project.join("recording-meta.json.v0.bak").exists());
+
+    let migrate = run(&["project", "migrate", project.to_str().unwrap()]);
+    assert!(migrate.status.success(), "stderr: {}", stderr(&migrate));
+    assert!(project.join("recording-meta.json.v0.bak").exists());
+    let meta: Value =
+        serde_json::from_slice(&std::fs::read(project.join("recording-meta.json")).unwrap())
+            .unwrap();
+    assert!(meta["schemaVersion"].is_number());
+
+    let recheck = run(&["project", "migrate", project.to_str().unwrap(), "--check"]);
+    assert!(recheck.status.success(), "stderr: {}", stderr(&recheck));
+}
+
 #[test]
 fn export_missing_project_emits_json_error_event() {
     let output = run(&["export", "/this/path/does/not/exist.cap", "--progress-json"]);
@@ -129,6 +129,7 @@ async fn load_recording(
                     end: duration,
                     timescale: 1.0,
                     name: None,
+                    transition: None,
                 }]
             }
             StudioRecordingMeta::MultipleSegments { inner } => inner
@@ -147,6 +148,7 @@ async fn load_recording(
                         end: duration,
                         timescale: 1.0,
                         name: None,
+                        transition: None,
                     })
                 })
                 .collect(),
@@ -162,6 +164,7 @@ async fn load_recording(
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
             });
         }
     }
@@ -312,6 +315,7 @@ async fn main() {
         render_constants,
         start_frame_number: 0,
         project: project_rx,
+        recordings,
         segment_medias,
         music: cap_editor::MusicTracks::new(),
         telemetry: Some(telemetry),
@@ -2,7 +2,6 @@
 //! This will come part of the EffectTS rewrite work.
 
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use specta::Type;
 use std::collections::HashMap;
 use tauri::AppHandle;
@@ -10,42 +9,21 @@ use tracing::{instrument, trace};
 
 use crate::web_api::{AuthedApiError, ManagerExt};
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct MultipartUploadInitiateResponse {
-    pub upload_id: String,
-    pub provider: Option<String>,
-}
+pub use cap_api::{
+    MultipartUploadInitiateResponse, PresignedS3PutRequest, PresignedS3PutRequestMethod,
+    S3VideoMeta, SignedUploadTarget, UploadedPart,
+};
 
 #[instrument(skip(app))]
 pub async fn upload_multipart_initiate(
     app: &AppHandle,
     video_id: &str,
 ) -> Result<MultipartUploadInitiateResponse, AuthedApiError> {
-    let resp = app
-        .authed_api_request("/api/upload/multipart/initiate", |c, url| {
-            c.post(url)
-                .header("Content-Type", "application/json")
-                .json(&serde_json::json!({
-                    "videoId": video_id,
-                    "contentType": "video/mp4"
-                }))
-        })
-        .await
-        .map_err(|err| format!("api/upload_multipart_initiate/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/upload_multipart_initiate/{status}: {error_body}").into());
-    }
-
-    resp.json::<MultipartUploadInitiateResponse>()
-        .await
-        .map_err(|err| format!("api/upload_multipart_initiate/response: {err}").into())
+    Ok(app
+        .api_client()
+        .await?
+        .multipart_initiate(video_id, "video/mp4")
+        .await?)
 }
 
 #[instrument(skip(app, upload_id))]
@@ -56,65 +34,11 @@ pub async fn upload_multipart_presign_part(
     part_number: u32,
     md5_sum: Option<&str>,
 ) -> Result<String, AuthedApiError> {
-    #[derive(Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    pub struct Response {
-        presigned_url: String,
-    }
-
-    let mut body = serde_json::Map::from_iter([
-        ("videoId".to_string(), json!(video_id)),
-        ("uploadId".to_string(), json!(upload_id)),
-        ("partNumber".to_string(), json!(part_number)),
-    ]);
-
-    if let Some(md5_sum) = md5_sum {
-        body.insert("md5Sum".to_string(), json!(md5_sum));
-    }
-
-    let resp = app
-        .authed_api_request("/api/upload/multipart/presign-part", |c, url| {
-            c.post(url)
-                .header("Content-Type", "application/json")
-                .json(&serde_json::json!(body))
-        })
-        .await
-        .map_err(|err| format!("api/upload_multipart_presign_part/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/upload_multipart_presign_part/{status}: {error_body}").into());
-    }
-
-    resp.json::<Response>()
-        .await
-        .map_err(|err| format!("api/upload_multipart_presign_part/response: {err}").into())
-        .map(|data| data.presigned_url)
-}
-
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct UploadedPart {
-    pub part_number: u32,
-    pub etag: String,
-    pub size: usize,
-    #[serde(skip)]
-    pub total_size: u64,
-}
-
-#[derive(Serialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct S3VideoMeta {
-    #[serde(rename = "durationInSecs")]
-    pub duration_in_secs: f64,
-    pub width: u32,
-    pub height: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub fps: Option<f32>,
+    Ok(app
+        .api_client()
+        .await?
+        .multipart_presign_part(video_id, upload_id, part_number, md5_sum)
+        .await?)
 }
 
 #[instrument(skip_all)]
@@ -125,76 +49,13 @@ pub async fn upload_multipart_complete(
     parts: &[UploadedPart],
     meta: Option<S3VideoMeta>,
 ) -> Result<Option<String>, AuthedApiError> {
-    #[derive(Serialize)]
-    #[serde(rename_all = "camelCase")]
-    pub struct MultipartCompleteRequest<'a> {
-        video_id: &'a str,
-        upload_id: &'a str,
-        parts: &'a [UploadedPart],
-        #[serde(flatten)]
-        meta: Option<S3VideoMeta>,
-    }
-
-    #[derive(Deserialize)]
-    pub struct Response {
-        location: Option<String>,
-    }
-
     trace!("Completing multipart upload");
 
-    let resp = app
-        .authed_api_request("/api/upload/multipart/complete", |c, url| {
-            c.post(url)
-                .header("Content-Type", "application/json")
-                .json(&MultipartCompleteRequest {
-                    video_id,
-                    upload_id,
-                    parts,
-                    meta,
-                })
-        })
-        .await
-        .map_err(|err| format!("api/upload_multipart_complete/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/upload_multipart_complete/{status}: {error_body}").into());
-    }
-
-    resp.json::<Response>()
-        .await
-        .map_err(|err| format!("api/upload_multipart_complete/response: {err}").into())
-        .map(|data| data.location)
-}
-
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "lowercase")]
-pub enum PresignedS3PutRequestMethod {
-    #[allow(unused)]
-    Post,
-    Put,
-}
-
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PresignedS3PutRequest {
-    pub video_id: String,
-    pub subpath: String,
-    pub method: PresignedS3PutRequestMethod,
-    #[serde(flatten)]
-    pub meta: Option<S3VideoMeta>,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct SignedUploadTarget {
-    pub url: String,
-    #[serde(default)]
-    pub headers: HashMap<String, String>,
+    Ok(app
+        .api_client()
+        .await?
+        .multipart_complete(video_id, upload_id, parts, meta)
+        .await?)
 }
 
 #[instrument(skip(app))]
@@ -202,32 +63,7 @@ pub async fn upload_signed(
     app: &AppHandle,
     body: PresignedS3PutRequest,
 ) -> Result<SignedUploadTarget, AuthedApiError> {
-    #[derive(Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    pub struct Response {
-        presigned_put_data: SignedUploadTarget,
-    }
-
-    let resp = app
-        .authed_api_request("/api/upload/signed", |client, url| {
-            client.post(url).json(&body)
-        })
-        .await
-        .map_err(|err| format!("api/upload_signed/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/upload_signed/{status}: {error_body}").into());
-    }
-
-    resp.json::<Response>()
-        .await
-        .map_err(|err| format!("api/upload_signed/response: {err}").into())
-        .map(|data| data.presigned_put_data)
+    Ok(app.api_client().await?.presign_put(&body).await?)
 }
 
 #[instrument(skip(app))]
@@ -235,35 +71,12 @@ pub async fn upload_signed_batch(
     app: &AppHandle,
     video_id: &str,
     subpaths: &[String],
-) -> Result<std::collections::HashMap<String, String>, AuthedApiError> {
-    #[derive(Deserialize)]
-    struct Response {
-        urls: std::collections::HashMap<String, String>,
-    }
-
-    let resp = app
-        .authed_api_request("/api/upload/signed/batch", |client, url| {
-            client.post(url).json(&serde_json::json!({
-                "videoId": video_id,
-                "subpaths": subpaths,
-            }))
-        })
-        .await
-        .map_err(|err| format!("api/upload_signed_batch/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/upload_signed_batch/{status}: {error_body}").into());
-    }
-
-    resp.json::<Response>()
-        .await
-        .map_err(|err| format!("api/upload_signed_batch/response: {err}").into())
-        .map(|data| data.urls)
+) -> Result<HashMap<String, String>, AuthedApiError> {
+    Ok(app
+        .api_client()
+        .await?
+        .presign_batch(video_id, subpaths)
+        .await?)
 }
 
 #[instrument(skip(app))]
@@ -273,28 +86,11 @@ pub async fn desktop_video_progress(
     uploaded: u64,
     total: u64,
 ) -> Result<(), AuthedApiError> {
-    let resp = app
-        .authed_api_request("/api/desktop/video/progress", |client, url| {
-            client.post(url).json(&json!({
-                "videoId": video_id,
-                "uploaded": uploaded,
-                "total": total,
-                "updatedAt": chrono::Utc::now().to_rfc3339()
-            }))
-        })
-        .await
-        .map_err(|err| format!("api/desktop_video_progress/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/desktop_video_progress/{status}: {error_body}").into());
-    }
-
-    Ok(())
+    Ok(app
+        .api_client()
+        .await?
+        .video_progress(video_id, uploaded, total)
+        .await?)
 }
 
 #[derive(Serialize, Deserialize, Type, Debug, Clone, Default)]
@@ -330,46 +126,9 @@ pub async fn signal_recording_complete(
     app: &AppHandle,
     video_id: &str,
 ) -> Result<(), AuthedApiError> {
-    let resp = app
-        .authed_api_request("/api/upload/recording-complete", |client, url| {
-            client
-                .post(url)
-                .header("Content-Type", "application/json")
-                .json(&serde_json::json!({
-                    "videoId": video_id,
-                }))
-        })
-        .await
-        .map_err(|err| format!("api/signal_recording_complete/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/signal_recording_complete/{status}: {error_body}").into());
-    }
-
-    Ok(())
+    Ok(app.api_client().await?.recording_complete(video_id).await?)
 }
 
 pub async fn fetch_organizations(app: &AppHandle) -> Result<Vec<Organization>, AuthedApiError> {
-    let resp = app
-        .authed_api_request("/api/desktop/organizations", |client, url| client.get(url))
-        .await
-        .map_err(|err| format!("api/fetch_organizations/request: {err}"))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status().as_u16();
-        let error_body = resp
-            .text()
-            .await
-            .unwrap_or_else(|_| "<no response body>".to_string());
-        return Err(format!("api/fetch_organizations/{status}: {error_body}").into());
-    }
-
-    resp.json()
-        .await
-        .map_err(|err| format!("api/fetch_organizations/response: {err}").into())
+    Ok(app.api_client().await?.organizations().await?)
 }
@@ -71,27 +71,17 @@ impl AuthStore {
 
         let mut auth = auth;
 
-        match app
-            .authed_api_request("/api/desktop/plan", |client, url| client.get(url))
-            .await
-        {
-            Ok(response) if response.status().is_success() => {
-                #[derive(Deserialize)]
-                struct PlanResponse {
-                    upgraded: bool,
-                }
-                match response.json::<PlanResponse>().await {
-                    Ok(plan_response) => {
-                        auth.plan = Some(Plan {
-                            upgraded: plan_response.upgraded,
-                            last_checked: chrono::Utc::now().timestamp() as i32,
-                            manual: auth.plan.as_ref().is_some_and(|p| p.manual),
-                        });
-                    }
-                    Err(e) => tracing::warn!("Failed to parse plan response: {e}"),
+        match app.api_client().await {
+            Ok(client) => match client.user_plan().await {
+                Ok(plan_response) => {
+                    auth.plan = Some(Plan {
+                        upgraded: plan_response.upgraded,
+                        last_checked: chrono::Utc::now().timestamp() as i32,
+                        manual: auth.plan.as_ref().is_some_and(|p| p.manual),
+                    });
                 }
-            }
-            Ok(response) => tracing::warn!("Plan fetch returned {}", response.status()),
+                Err(e) => tracing::warn!("Failed to fetch plan: {e}"),
+            },
             Err(e) => tracing::warn!("Failed to fetch plan: {e}"),
         }
 
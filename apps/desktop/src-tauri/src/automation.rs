@@ -146,6 +146,8 @@ impl AutomationHost for DesktopAutomationHost {
                     ExportFormat::Mp4 => "mp4",
                     ExportFormat::Gif => "gif",
                     ExportFormat::Mov => "mov",
+                    ExportFormat::Hevc | ExportFormat::Av1 => "mp4",
+                    ExportFormat::Webm => "webm",
                 };
                 let name = project_path
                     .file_stem()
@@ -163,12 +165,10 @@ impl AutomationHost for DesktopAutomationHost {
 
         let base = builder.build().await.map_err(|e| format!("{e}"))?;
 
-        let result_path = match settings {
-            crate::export::ExportSettings::Mp4(s) => s.export(base, |_| true).await,
-            crate::export::ExportSettings::Gif(s) => s.export(base, |_| true).await,
-            crate::export::ExportSettings::Mov(s) => s.export(base, |_| true).await,
-        }
-        .map_err(|e| format!("Export failed: {e}"))?;
+        let result_path = settings
+            .export(base, |_| true)
+            .await
+            .map_err(|e| format!("Export failed: {e}"))?;
 
         info!(output = %result_path.display(), "Automation: export complete");
         Ok(())
@@ -584,6 +584,36 @@ fn build_desktop_export_settings(profile: &ExportProfile) -> crate::export::Expo
                 cursor_only: false,
             })
         }
+        ExportFormat::Hevc => {
+            crate::export::ExportSettings::Hevc(cap_export::mp4_codec::Mp4CodecExportSettings {
+                fps: profile.fps,
+                resolution_base: profile.resolution_base,
+                compression,
+                custom_bpp: None,
+                force_ffmpeg_decoder: false,
+                optimize_filesize: false,
+            })
+        }
+        ExportFormat::Av1 => {
+            crate::export::ExportSettings::Av1(cap_export::mp4_codec::Mp4CodecExportSettings {
+                fps: profile.fps,
+                resolution_base: profile.resolution_base,
+                compression,
+                custom_bpp: None,
+                force_ffmpeg_decoder: false,
+                optimize_filesize: false,
+            })
+        }
+        ExportFormat::Webm => {
+            crate::export::ExportSettings::Webm(cap_export::webm::WebmExportSettings {
+                fps: profile.fps,
+                resolution_base: profile.resolution_base,
+                codec: Default::default(),
+                compression,
+                custom_bpp: None,
+                optimize_filesize: false,
+            })
+        }
     }
 }
 
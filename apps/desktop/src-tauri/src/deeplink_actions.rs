@@ -26,6 +26,10 @@ pub enum DeepLinkAction {
         mode: RecordingMode,
     },
     StopRecording,
+    AddChapterMarker {
+        #[serde(default)]
+        title: Option<String>,
+    },
     OpenEditor {
         project_path: PathBuf,
     },
@@ -192,6 +196,9 @@ impl DeepLinkAction {
             DeepLinkAction::StopRecording => {
                 crate::recording::stop_recording(app.clone(), app.state()).await
             }
+            DeepLinkAction::AddChapterMarker { title } => {
+                crate::recording::add_chapter_marker(app.clone(), app.state(), title).await
+            }
             DeepLinkAction::OpenEditor { project_path } => {
                 crate::open_project_from_path(Path::new(&project_path), app.clone())
             }
@@ -276,6 +283,27 @@ mod tests {
         assert!(capture_system_audio);
     }
 
+    #[test]
+    fn parses_add_chapter_marker_action_with_and_without_title() {
+        let titled = serde_json::json!({ "add_chapter_marker": { "title": "Demo" } }).to_string();
+        let url = Url::parse_with_params("cap-desktop://action", &[("value", titled)]).unwrap();
+
+        assert_eq!(
+            DeepLinkAction::try_from(&url),
+            Ok(DeepLinkAction::AddChapterMarker {
+                title: Some("Demo".to_string())
+            })
+        );
+
+        let untitled = serde_json::json!({ "add_chapter_marker": {} }).to_string();
+        let url = Url::parse_with_params("cap-desktop://action", &[("value", untitled)]).unwrap();
+
+        assert_eq!(
+            DeepLinkAction::try_from(&url),
+            Ok(DeepLinkAction::AddChapterMarker { title: None })
+        );
+    }
+
     #[test]
     fn rejects_non_action_host() {
         let url = Url::parse("cap-desktop://login?value=%22stop_recording%22").unwrap();
@@ -1,5 +1,6 @@
 use crate::editor_window::{OptionalWindowEditorInstance, WindowEditorInstance};
 use crate::{FramesRendered, get_video_metadata};
+pub use cap_export::settings::ExportSettings;
 use cap_export::{ExporterBase, make_cursor_only_project};
 use cap_project::{RecordingMeta, XY};
 use cap_rendering::{
@@ -869,49 +870,6 @@ async fn wait_for_export_preview_idle_or_cancel(
     Ok(())
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, Type)]
-#[serde(tag = "format")]
-pub enum ExportSettings {
-    Mp4(cap_export::mp4::Mp4ExportSettings),
-    Gif(cap_export::gif::GifExportSettings),
-    Mov(cap_export::mov::MovExportSettings),
-}
-
-impl ExportSettings {
-    fn fps(&self) -> u32 {
-        match self {
-            ExportSettings::Mp4(settings) => settings.fps,
-            ExportSettings::Gif(settings) => settings.fps,
-            ExportSettings::Mov(settings) => settings.fps,
-        }
-    }
-
-    fn force_ffmpeg_decoder(&self) -> bool {
-        match self {
-            ExportSettings::Mp4(settings) => settings.force_ffmpeg_decoder,
-            ExportSettings::Gif(_) | ExportSettings::Mov(_) => false,
-        }
-    }
-
-    fn cursor_only(&self) -> bool {
-        match self {
-            ExportSettings::Mov(settings) => settings.cursor_only,
-            _ => false,
-        }
-    }
-}
-
-fn export_project_config(
-    project_config: cap_project::ProjectConfiguration,
-    cursor_only: bool,
-) -> cap_project::ProjectConfiguration {
-    if cursor_only {
-        make_cursor_only_project(project_config)
-    } else {
-        project_config
-    }
-}
-
 async fn do_export(
     project_path: &Path,
     settings: &ExportSettings,
@@ -943,56 +901,18 @@ async fn do_export(
         return Err("Export cancelled".to_string());
     }
 
-    match settings {
-        ExportSettings::Mp4(mp4_settings) => {
-            let progress = progress.clone();
-            let cancel_token = cancel_token.clone();
-            mp4_settings
-                .export(exporter_base, move |frame_index| {
-                    if cancel_token.is_cancelled() {
-                        return false;
-                    }
-
-                    progress.send(FramesRendered {
-                        rendered_count: (frame_index + 1).min(total_frames),
-                        total_frames,
-                    })
-                })
-                .await
-        }
-        ExportSettings::Gif(gif_settings) => {
-            let progress = progress.clone();
-            let cancel_token = cancel_token.clone();
-            gif_settings
-                .export(exporter_base, move |frame_index| {
-                    if cancel_token.is_cancelled() {
-                        return false;
-                    }
-
-                    progress.send(FramesRendered {
-                        rendered_count: (frame_index + 1).min(total_frames),
-                        total_frames,
-                    })
-                })
-                .await
-        }
-        ExportSettings::Mov(mov_settings) => {
-            let progress = progress.clone();
-            let cancel_token = cancel_token.clone();
-            mov_settings
-                .export(exporter_base, move |frame_index| {
-                    if cancel_token.is_cancelled() {
-                        return false;
-                    }
+    settings
+        .export(exporter_base, move |frame_index| {
+            if cancel_token.is_cancelled() {
+                return false;
+            }
 
-                    progress.send(FramesRendered {
-                        rendered_count: (frame_index + 1).min(total_frames),
-                        total_frames,
-                    })
-                })
-                .await
-        }
-    }
+            progress.send(FramesRendered {
+                rendered_count: (frame_index + 1).min(total_frames),
+                total_frames,
+            })
+        })
+        .await
 }
 
 fn is_frame_decode_error(error: &str) -> bool {
@@ -1337,12 +1257,16 @@ pub async fn get_export_estimates(
         ExportSettings::Mp4(s) => (s.resolution_base, s.fps),
         ExportSettings::Gif(s) => (s.resolution_base, s.fps),
         ExportSettings::Mov(s) => (s.resolution_base, s.fps),
+        ExportSettings::Hevc(s) => (s.resolution_base, s.fps),
+        ExportSettings::Av1(s) => (s.resolution_base, s.fps),
+        ExportSettings::Webm(s) => (s.resolution_base, s.fps),
     };
 
     let (width, height) = (resolution.x, resolution.y);
     let total_pixels = (width * height) as f64;
     let fps_f64 = fps as f64;
     let total_frames = (duration_seconds * fps_f64).ceil();
+    let video_bpp = settings.effective_bpp().unwrap_or_default() as f64;
 
     let (estimated_size_mb, estimated_time_seconds) = match &settings {
         ExportSettings::Mp4(mp4_settings) => {
@@ -1363,6 +1287,33 @@ pub async fn get_export_estimates(
 
             (size_mb, time_estimate)
         }
+        ExportSettings::Hevc(_) => estimate_codec_export(
+            total_pixels,
+            fps_f64,
+            duration_seconds,
+            total_frames,
+            video_bpp,
+            120.0,
+        ),
+        ExportSettings::Av1(_) => estimate_codec_export(
+            total_pixels,
+            fps_f64,
+            duration_seconds,
+            total_frames,
+            video_bpp,
+            45.0,
+        ),
+        ExportSettings::Webm(webm_settings) => estimate_codec_export(
+            total_pixels,
+            fps_f64,
+            duration_seconds,
+            total_frames,
+            video_bpp,
+            match webm_settings.codec {
+                cap_export::webm::WebmVideoCodec::Vp9 => 60.0,
+                cap_export::webm::WebmVideoCodec::Av1 => 45.0,
+            },
+        ),
         ExportSettings::Gif(_) => {
             let bytes_per_frame = total_pixels * 0.5;
             let gif_efficiency = 0.07;
@@ -1420,6 +1371,23 @@ fn estimate_cursor_only_size_mb(total_pixels: f64, total_frames: f64) -> f64 {
     (bytes_per_frame * total_frames) / (1024.0 * 1024.0)
 }
 
+fn estimate_codec_export(
+    total_pixels: f64,
+    fps: f64,
+    duration_seconds: f64,
+    total_frames: f64,
+    bits_per_pixel: f64,
+    effective_render_fps: f64,
+) -> (f64, f64) {
+    let effective_fps = ((fps - 30.0).max(0.0) * 0.6) + fps.min(30.0);
+    let video_bitrate = total_pixels * bits_per_pixel * effective_fps;
+    let total_bitrate = video_bitrate + 192_000.0;
+    let encoder_efficiency = 0.5;
+    let size_mb = (total_bitrate * encoder_efficiency * duration_seconds) / (8.0 * 1024.0 * 1024.0);
+
+    (size_mb, total_frames / effective_render_fps)
+}
+
 fn bpp_to_jpeg_quality(bpp: f32) -> u8 {
     ((bpp - 0.04) / (0.3 - 0.04) * (95.0 - 40.0) + 40.0).clamp(40.0, 95.0) as u8
 }
@@ -56,6 +56,7 @@ pub enum HotkeyAction {
     StopRecording,
     RestartRecording,
     TogglePauseRecording,
+    AddChapterMarker,
     CycleRecordingMode,
     OpenRecordingPicker,
     OpenRecordingPickerDisplay,
@@ -159,6 +160,9 @@ async fn handle_hotkey(app: AppHandle, action: HotkeyAction) -> Result<(), Strin
         HotkeyAction::TogglePauseRecording => {
             recording::toggle_pause_recording(app.clone(), app.state()).await
         }
+        HotkeyAction::AddChapterMarker => {
+            recording::add_chapter_marker(app.clone(), app.state(), None).await
+        }
         HotkeyAction::CycleRecordingMode => {
             let current = RecordingSettingsStore::get(&app)
                 .ok()
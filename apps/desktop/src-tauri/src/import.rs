@@ -322,6 +322,7 @@ fn full_timeline_for_segments(
                 start: 0.0,
                 end: duration,
                 name: None,
+                transition: None,
             })
         })
         .collect()
@@ -355,6 +356,7 @@ fn full_timeline_for_source_segments(
                 start: 0.0,
                 end: duration,
                 name: None,
+                transition: None,
             })
         })
         .collect()
@@ -375,6 +377,7 @@ fn ensure_project_timeline<'a>(
             caption_segments: Vec::new(),
             keyboard_segments: Vec::new(),
             audio_segments: Vec::new(),
+            annotation_segments: Vec::new(),
         });
     }
 
@@ -908,6 +911,7 @@ fn source_timeline_segments_for_import(
             start,
             end,
             name: None,
+            transition: None,
         });
     }
 
@@ -1426,6 +1430,7 @@ pub async fn start_video_import(app: AppHandle, source_path: PathBuf) -> Result<
             },
         })),
         upload: None,
+        chapters: Vec::new(),
     };
 
     initial_meta
@@ -1522,6 +1527,7 @@ pub async fn start_video_import(app: AppHandle, source_path: PathBuf) -> Result<
                         },
                     )),
                     upload: None,
+                    chapters: Vec::new(),
                 };
 
                 if let Err(e) = meta.save_for_project() {
@@ -1705,6 +1711,7 @@ async fn append_mp4_to_editor_project(
             start: 0.0,
             end: duration,
             name: None,
+            transition: None,
         });
     add_clip_configs(
         &mut config,
@@ -1835,6 +1842,7 @@ async fn append_cap_project_to_editor_project(
                 start: source_segment.start,
                 end: source_segment.end,
                 name: None,
+                transition: None,
             });
         }
     }
@@ -1985,6 +1993,7 @@ pub async fn start_image_import(app: AppHandle, source_path: PathBuf) -> Result<
         sharing: None,
         inner: RecordingMetaInner::Studio(Box::new(StudioRecordingMeta::SingleSegment { segment })),
         upload: None,
+        chapters: Vec::new(),
     };
 
     meta.save_for_project()
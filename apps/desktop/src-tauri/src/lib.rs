@@ -3099,6 +3099,24 @@ async fn set_project_config(
     Ok(())
 }
 
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(editor_instance))]
+async fn undo_project_config(
+    editor_instance: WindowEditorInstance,
+) -> Result<Option<ProjectConfiguration>, String> {
+    editor_instance.undo_project_config().await
+}
+
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(editor_instance))]
+async fn redo_project_config(
+    editor_instance: WindowEditorInstance,
+) -> Result<Option<ProjectConfiguration>, String> {
+    editor_instance.redo_project_config().await
+}
+
 #[tauri::command]
 #[specta::specta]
 #[instrument(skip(editor_instance))]
@@ -3825,24 +3843,13 @@ async fn check_upgraded_and_update(app: AppHandle) -> Result<bool, String> {
         "Fetching plan for user {}",
         auth.user_id.as_deref().unwrap_or("unknown")
     );
-    let response = app
-        .authed_api_request("/api/desktop/plan", |client, url| client.get(url))
-        .await
-        .map_err(|e| {
-            println!("Failed to fetch plan: {e}");
-            e.to_string()
-        })?;
-
-    println!("Plan fetch response status: {}", response.status());
-    let plan_data = response.json::<serde_json::Value>().await.map_err(|e| {
-        println!("Failed to parse plan response: {e}");
-        format!("Failed to parse plan response: {e}")
+    let client = app.api_client().await.map_err(|e| e.to_string())?;
+    let plan = client.user_plan().await.map_err(|e| {
+        println!("Failed to fetch plan: {e}");
+        e.to_string()
     })?;
 
-    let is_pro = plan_data
-        .get("upgraded")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+    let is_pro = plan.upgraded;
     println!("Pro status: {is_pro}");
     let updated_auth = AuthStore {
         secret: auth.secret,
@@ -4489,6 +4496,7 @@ pub async fn run(recording_logging_handle: LoggingHandle, logs_dir: PathBuf) {
             recording::pause_recording,
             recording::resume_recording,
             recording::toggle_pause_recording,
+            recording::add_chapter_marker,
             recording::restart_recording,
             recording::delete_recording,
             recording::take_screenshot,
@@ -4540,6 +4548,8 @@ pub async fn run(recording_logging_handle: LoggingHandle, logs_dir: PathBuf) {
             stop_playback,
             set_playhead_position,
             set_project_config,
+            undo_project_config,
+            redo_project_config,
             update_project_config_in_memory,
             generate_zoom_segments_from_clicks,
             generate_keyboard_segments,
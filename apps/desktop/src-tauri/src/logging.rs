@@ -222,22 +222,14 @@ pub async fn upload_log_file(app: &AppHandle) -> Result<(), String> {
     let diagnostics = collect_diagnostics_for_upload(&recordings_dir, &app_data_dir, is_recording);
     let diagnostics_json = serde_json::to_string(&diagnostics).unwrap_or_else(|_| "{}".to_string());
 
-    let form = reqwest::multipart::Form::new()
-        .text("log", log_content)
-        .text("os", std::env::consts::OS)
-        .text("version", env!("CARGO_PKG_VERSION"))
-        .text("diagnostics", diagnostics_json);
-
-    let response = app
-        .api_request("/api/desktop/logs", |client, url| {
-            client.post(url).multipart(form)
+    app.public_api_client()
+        .await
+        .upload_logs(cap_api::DesktopLogs {
+            log: log_content,
+            os: std::env::consts::OS.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            diagnostics: diagnostics_json,
         })
         .await
-        .map_err(|e| format!("Failed to upload logs: {e}"))?;
-
-    if !response.status().is_success() {
-        return Err(format!("Upload failed with status: {}", response.status()));
-    }
-
-    Ok(())
+        .map_err(|e| format!("Failed to upload logs: {e}"))
 }
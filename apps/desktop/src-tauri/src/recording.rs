@@ -4,10 +4,10 @@ use cap_media_info::ffmpeg_sample_format_for;
 use cap_project::CursorMoveEvent;
 use cap_project::cursor::SHORT_CURSOR_SHAPE_DEBOUNCE_MS;
 use cap_project::{
-    CameraShape, CursorClickEvent, GlideDirection, InstantRecordingMeta, MultipleSegments,
-    Platform, ProjectConfiguration, RecordingMeta, RecordingMetaInner, SharingMeta,
-    StudioRecordingMeta, StudioRecordingStatus, TimelineConfiguration, TimelineSegment, ZoomMode,
-    ZoomSegment, cursor::CursorEvents,
+    CameraShape, ChapterMarker, CursorClickEvent, GlideDirection, InstantRecordingMeta,
+    MultipleSegments, Platform, ProjectConfiguration, RecordingMeta, RecordingMetaInner,
+    SharingMeta, StudioRecordingMeta, StudioRecordingStatus, TimelineConfiguration,
+    TimelineSegment, ZoomMode, ZoomSegment, cursor::CursorEvents,
 };
 #[cfg(target_os = "macos")]
 use cap_recording::SendableShareableContent;
@@ -733,6 +733,13 @@ impl InProgressRecording {
         }
     }
 
+    pub async fn add_chapter_marker(&self, title: Option<String>) -> anyhow::Result<ChapterMarker> {
+        match self {
+            Self::Instant { handle, .. } => handle.add_chapter_marker(title).await,
+            Self::Studio { handle, .. } => handle.add_chapter_marker(title).await,
+        }
+    }
+
     pub fn recording_dir(&self) -> &PathBuf {
         match self {
             Self::Instant { common, .. } => &common.recording_dir,
@@ -1131,6 +1138,7 @@ pub enum RecordingEvent {
     InputRestored { input: RecordingInputKind },
     Degraded { reason: String },
     Recovered,
+    ChapterMarked { marker: ChapterMarker },
 }
 
 #[derive(Serialize, Type)]
@@ -1601,9 +1609,9 @@ pub async fn start_recording(
                 cap_recording::FREE_INSTANT_MODE_MAX_RESOLUTION
             };
             let upload_mode = if matches!(inputs.capture_target, ScreenCaptureTarget::CameraOnly) {
-                "desktopMP4"
+                cap_api::RecordingMode::DesktopMp4
             } else {
-                "desktopSegments"
+                cap_api::RecordingMode::DesktopSegments
             };
 
             let s3_config = match crate::upload::create_or_get_video_with_mode(
@@ -1676,6 +1684,7 @@ pub async fn start_recording(
         },
         sharing: None,
         upload: None,
+        chapters: Vec::new(),
     };
 
     pending_try!(meta.save_for_project(), |e| format!(
@@ -2279,7 +2288,19 @@ pub async fn start_recording(
                         cap_recording::PipelineHealthEvent::CaptureTargetLost { target } => {
                             Some(format!("Capture target lost: {target}"))
                         }
-                        cap_recording::PipelineHealthEvent::SourceRestarted => None,
+                        cap_recording::PipelineHealthEvent::StreamReconnecting {
+                            target,
+                            attempt,
+                            reason,
+                            ..
+                        } => Some(format!(
+                            "Live stream to {target} reconnecting (attempt {attempt}): {reason}"
+                        )),
+                        cap_recording::PipelineHealthEvent::StreamFailed { target, reason } => {
+                            Some(format!("Live stream to {target} stopped: {reason}"))
+                        }
+                        cap_recording::PipelineHealthEvent::SourceRestarted
+                        | cap_recording::PipelineHealthEvent::StreamReconnected { .. } => None,
                     };
 
                     if let Some(reason) = reason {
@@ -2353,6 +2374,30 @@ pub async fn toggle_pause_recording(
     Ok(())
 }
 
+#[tauri::command]
+#[specta::specta]
+#[instrument(skip(app, state))]
+pub async fn add_chapter_marker(
+    app: AppHandle,
+    state: MutableState<'_, App>,
+    title: Option<String>,
+) -> Result<(), String> {
+    let state = state.read().await;
+
+    let Some(recording) = state.current_recording() else {
+        return Err("No recording in progress".to_string());
+    };
+
+    let marker = recording
+        .add_chapter_marker(title)
+        .await
+        .map_err(|e| e.to_string())?;
+    info!(time = marker.time, "Added chapter marker");
+    RecordingEvent::ChapterMarked { marker }.emit(&app).ok();
+
+    Ok(())
+}
+
 async fn handle_spawn_failure(
     app: &AppHandle,
     state_mtx: &MutableState<'_, App>,
@@ -2538,27 +2583,20 @@ async fn remove_recording_dir(recording_dir: &Path) -> Result<(), String> {
 }
 
 async fn delete_remote_instant_video(app: &AppHandle, video_id: &str) -> Result<(), String> {
-    let response = app
-        .authed_api_request(
-            format!("/api/desktop/video/delete?videoId={video_id}"),
-            |client, url| client.delete(url),
-        )
+    let client = app
+        .api_client()
         .await
         .map_err(|err| format!("Failed to delete instant recording: {err}"))?;
 
-    let status = response.status();
-    if status.is_success() || status == reqwest::StatusCode::NOT_FOUND {
-        return Ok(());
+    match client.delete_video(video_id).await {
+        Ok(()) => Ok(()),
+        Err(cap_api::Error::Status { status, .. }) if status == reqwest::StatusCode::NOT_FOUND => {
+            Ok(())
+        }
+        Err(err) => Err(format!(
+            "Failed to delete instant recording {video_id}: {err}"
+        )),
     }
-
-    let body = response
-        .text()
-        .await
-        .unwrap_or_else(|err| format!("Failed to read response body: {err}"));
-
-    Err(format!(
-        "Failed to delete instant recording {video_id}: {status}: {body}"
-    ))
 }
 
 async fn discard_recording(app: &AppHandle, recording: InProgressRecording) -> Result<(), String> {
@@ -2804,6 +2842,7 @@ pub async fn take_screenshot(
             cap_project::StudioRecordingMeta::SingleSegment { segment },
         )),
         upload: None,
+        chapters: Vec::new(),
     };
 
     meta.save_for_project()
@@ -3151,6 +3190,9 @@ async fn handle_recording_finish(
     let screenshots_dir = recording_dir.join("screenshots");
     std::fs::create_dir_all(&screenshots_dir).ok();
 
+    // Studio recordings save their chapter markers with the final meta when they stop; instant
+    // ones are only written here.
+    let mut chapters = Vec::new();
     let (meta_inner, sharing) = match completed_recording {
         CompletedRecording::Studio {
             recording,
@@ -3248,6 +3290,7 @@ async fn handle_recording_finish(
                     project_path: recording.project_path,
                     meta: updated_studio_meta.clone(),
                     cursor_data: recording.cursor_data,
+                    chapters: recording.chapters,
                 },
                 &recordings,
                 PresetsStore::get_default_preset(app)?.map(|p| p.config),
@@ -3383,6 +3426,8 @@ async fn handle_recording_finish(
                 }
             });
 
+            chapters = recording.chapters;
+
             (
                 RecordingMetaInner::Instant(recording.meta),
                 Some(SharingMeta {
@@ -3403,6 +3448,7 @@ async fn handle_recording_finish(
     {
         meta.inner = meta_inner.clone();
         meta.sharing = sharing;
+        meta.chapters = chapters;
         meta.save_for_project()
             .map_err(|e| format!("Failed to save recording meta: {e}"))?;
     }
@@ -3495,6 +3541,7 @@ async fn finalize_studio_recording(
             project_path: recording.project_path,
             meta: updated_studio_meta,
             cursor_data: recording.cursor_data,
+            chapters: recording.chapters,
         },
         &recordings,
         default_preset,
@@ -3603,6 +3650,7 @@ pub fn generate_zoom_segments_from_clicks(
         sharing: None,
         inner: RecordingMetaInner::Studio(Box::new(recording.meta.clone())),
         upload: None,
+        chapters: Vec::new(),
     };
 
     generate_zoom_segments_for_project(&recording_meta, recordings)
@@ -3703,6 +3751,7 @@ fn project_config_from_recording(
             end: segment.duration(),
             timescale: 1.0,
             name: None,
+            transition: None,
         })
         .collect::<Vec<_>>();
 
@@ -3721,6 +3770,7 @@ fn project_config_from_recording(
         caption_segments: Vec::new(),
         keyboard_segments: Vec::new(),
         audio_segments: Vec::new(),
+        annotation_segments: Vec::new(),
     });
 
     config
@@ -139,7 +139,10 @@ impl RecordingHealthAccumulator {
             | PipelineHealthEvent::EncoderRebuilt { .. }
             | PipelineHealthEvent::SourceAudioReset { .. }
             | PipelineHealthEvent::RecoveryFragmentCorrupt { .. }
-            | PipelineHealthEvent::CaptureTargetLost { .. } => None,
+            | PipelineHealthEvent::CaptureTargetLost { .. }
+            | PipelineHealthEvent::StreamReconnecting { .. }
+            | PipelineHealthEvent::StreamReconnected { .. }
+            | PipelineHealthEvent::StreamFailed { .. } => None,
         }
     }
 
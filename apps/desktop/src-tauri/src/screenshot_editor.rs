@@ -250,6 +250,7 @@ impl ScreenshotEditorInstances {
                 sharing: None,
                 inner: RecordingMetaInner::Studio(Box::new(studio_meta.clone())),
                 upload: None,
+                chapters: Vec::new(),
             }
         };
 
@@ -830,6 +831,7 @@ pub async fn prewarm_screenshot_renderer() {
         sharing: None,
         inner: RecordingMetaInner::Studio(Box::new(studio_meta.clone())),
         upload: None,
+        chapters: Vec::new(),
     };
 
     let options = cap_rendering::RenderOptions {
@@ -1538,6 +1540,7 @@ pub async fn render_screenshot_png(instance: &ScreenshotEditorInstance) -> Resul
             sharing: None,
             inner: RecordingMetaInner::Studio(Box::new(studio_meta)),
             upload: None,
+            chapters: Vec::new(),
         }
     };
 
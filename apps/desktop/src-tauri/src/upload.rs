@@ -9,6 +9,7 @@ use crate::{
 };
 use async_stream::{stream, try_stream};
 use bytes::Bytes;
+use cap_api::is_google_drive_resumable_url;
 use cap_project::{RecordingMeta, S3UploadMeta, UploadMeta};
 use cap_utils::spawn_actor;
 use ffmpeg::ffi::AV_TIME_BASE;
@@ -60,16 +61,6 @@ const NETWORK_RECOVERY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
 const CONNECTIVITY_PROBE_INITIAL_DELAY: Duration = Duration::from_secs(2);
 const CONNECTIVITY_PROBE_MAX_DELAY: Duration = Duration::from_secs(30);
 
-fn is_google_drive_resumable_url(url: &str) -> bool {
-    let Ok(url) = reqwest::Url::parse(url) else {
-        return false;
-    };
-    url.host_str().is_some_and(|host| {
-        (host == "googleapis.com" || host.ends_with(".googleapis.com"))
-            && url.path().starts_with("/upload/drive/")
-    })
-}
-
 fn is_google_drive_upload(provider: Option<&str>, upload_id: &str) -> bool {
     provider == Some("googleDrive") || is_google_drive_resumable_url(upload_id)
 }
@@ -256,26 +247,6 @@ async fn file_reader_stream(path: impl AsRef<Path>) -> Result<(ReaderStream<File
     Ok((ReaderStream::new(file), metadata.len()))
 }
 
-fn screenshot_upload_subpath(content_type: &str) -> &'static str {
-    if content_type.eq_ignore_ascii_case("image/png") {
-        "screenshot/screen-capture.png"
-    } else {
-        "screenshot/screen-capture.jpg"
-    }
-}
-
-fn screenshot_content_type_from_path(path: &Path) -> &'static str {
-    match path
-        .extension()
-        .and_then(|extension| extension.to_str())
-        .map(str::to_ascii_lowercase)
-        .as_deref()
-    {
-        Some("png") => "image/png",
-        _ => "image/jpeg",
-    }
-}
-
 pub async fn upload_screenshot_bytes(
     app: &AppHandle,
     image_bytes: Vec<u8>,
@@ -284,7 +255,7 @@ pub async fn upload_screenshot_bytes(
     organization_id: Option<String>,
 ) -> Result<UploadedItem, AuthedApiError> {
     let s3_config = create_or_get_video(app, true, video_id, None, None, organization_id).await?;
-    let subpath = screenshot_upload_subpath(content_type);
+    let subpath = cap_api::screenshot_upload_subpath(content_type);
     let total_size = image_bytes.len() as u64;
 
     singlepart_uploader(
@@ -312,9 +283,9 @@ pub async fn upload_screenshot_file(
     video_id: Option<String>,
     organization_id: Option<String>,
 ) -> Result<UploadedItem, AuthedApiError> {
-    let content_type = screenshot_content_type_from_path(&file_path);
+    let content_type = cap_api::screenshot_content_type_from_path(&file_path);
     let s3_config = create_or_get_video(app, true, video_id, None, None, organization_id).await?;
-    let subpath = screenshot_upload_subpath(content_type);
+    let subpath = cap_api::screenshot_upload_subpath(content_type);
     let (stream, total_size) = file_reader_stream(file_path).await?;
 
     singlepart_uploader(
@@ -352,7 +323,7 @@ pub async fn create_or_get_video(
         name,
         meta,
         organization_id,
-        "desktopMP4",
+        cap_api::RecordingMode::DesktopMp4,
     )
     .await
 }
@@ -365,74 +336,22 @@ pub async fn create_or_get_video_with_mode(
     name: Option<String>,
     meta: Option<S3VideoMeta>,
     organization_id: Option<String>,
-    recording_mode: &str,
+    recording_mode: cap_api::RecordingMode,
 ) -> Result<S3UploadMeta, AuthedApiError> {
-    let mut s3_config_url = if let Some(id) = video_id {
-        let mut url =
-            format!("/api/desktop/video/create?recordingMode={recording_mode}&videoId={id}");
-        if is_screenshot {
-            url.push_str("&isScreenshot=true");
-        }
-        url
-    } else if is_screenshot {
-        format!("/api/desktop/video/create?recordingMode={recording_mode}&isScreenshot=true")
-    } else {
-        format!("/api/desktop/video/create?recordingMode={recording_mode}")
-    };
-
-    if let Some(name) = name {
-        s3_config_url.push_str(&format!("&name={name}"));
-    }
-
-    if let Some(meta) = meta {
-        s3_config_url.push_str(&format!("&durationInSecs={}", meta.duration_in_secs));
-        s3_config_url.push_str(&format!("&width={}", meta.width));
-        s3_config_url.push_str(&format!("&height={}", meta.height));
-        if let Some(fps) = meta.fps {
-            s3_config_url.push_str(&format!("&fps={fps}"));
-        }
-    }
-
-    if let Some(org_id) = organization_id {
-        s3_config_url.push_str(&format!("&orgId={org_id}"));
-    }
-
-    let response = app
-        .authed_api_request(s3_config_url, |client, url| client.get(url))
+    let video = app
+        .api_client()
+        .await?
+        .create_video(&cap_api::CreateVideo {
+            recording_mode,
+            video_id,
+            name,
+            is_screenshot,
+            meta,
+            organization_id,
+        })
         .await?;
 
-    if response.status() != StatusCode::OK {
-        #[derive(Deserialize, Clone, Debug)]
-        pub struct CreateErrorResponse {
-            error: String,
-        }
-
-        let status = response.status();
-        let body = response.text().await;
-
-        if let Some(error) = body
-            .as_ref()
-            .ok()
-            .and_then(|body| serde_json::from_str::<CreateErrorResponse>(body).ok())
-            && status == StatusCode::FORBIDDEN
-            && error.error == "upgrade_required"
-        {
-            return Err(AuthedApiError::UpgradeRequired);
-        }
-
-        return Err(format!("create_or_get_video/error/{status}: {body:?}").into());
-    }
-
-    let response_text = response
-        .text()
-        .await
-        .map_err(|e| format!("Failed to read response body: {e}"))?;
-
-    let config = serde_json::from_str::<S3UploadMeta>(&response_text).map_err(|e| {
-        format!("Failed to deserialize response: {e}. Response body: {response_text}")
-    })?;
-
-    Ok(config)
+    Ok(S3UploadMeta { id: video.id })
 }
 
 #[instrument]
@@ -2526,19 +2445,19 @@ mod tests {
     #[test]
     fn screenshot_upload_subpath_matches_content_type() {
         assert_eq!(
-            screenshot_upload_subpath("image/png"),
+            cap_api::screenshot_upload_subpath("image/png"),
             "screenshot/screen-capture.png"
         );
         assert_eq!(
-            screenshot_upload_subpath("image/jpeg"),
+            cap_api::screenshot_upload_subpath("image/jpeg"),
             "screenshot/screen-capture.jpg"
         );
         assert_eq!(
-            screenshot_content_type_from_path(Path::new("screen-capture.png")),
+            cap_api::screenshot_content_type_from_path(Path::new("screen-capture.png")),
             "image/png"
         );
         assert_eq!(
-            screenshot_content_type_from_path(Path::new("screen-capture.jpg")),
+            cap_api::screenshot_content_type_from_path(Path::new("screen-capture.jpg")),
             "image/jpeg"
         );
     }
@@ -1,4 +1,4 @@
-use reqwest::StatusCode;
+use reqwest::header::{HeaderName, HeaderValue};
 use tauri::{Emitter, Manager, Runtime};
 use thiserror::Error;
 use tracing::{debug, error, warn};
@@ -38,6 +38,20 @@ impl From<reqwest::Error> for AuthedApiError {
     }
 }
 
+impl From<cap_api::Error> for AuthedApiError {
+    fn from(err: cap_api::Error) -> Self {
+        match err {
+            cap_api::Error::Unauthenticated => {
+                error!("Authentication expired. Please log in again.");
+                AuthedApiError::InvalidAuthentication
+            }
+            cap_api::Error::UpgradeRequired => AuthedApiError::UpgradeRequired,
+            cap_api::Error::Timeout => AuthedApiError::Timeout,
+            err => AuthedApiError::Other(err.to_string()),
+        }
+    }
+}
+
 impl From<&'static str> for AuthedApiError {
     fn from(value: &'static str) -> Self {
         AuthedApiError::Other(value.into())
@@ -50,16 +64,31 @@ impl From<String> for AuthedApiError {
     }
 }
 
-fn apply_env_headers(req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
-    let mut req = req
-        .header("X-Cap-Desktop-Version", env!("CARGO_PKG_VERSION"))
-        .header("X-Cap-Desktop-Features", "googleDriveUpload");
+/// Every desktop request goes through a [`cap_api::Client`] built here, so the version and
+/// feature headers the server keys behaviour on are set in one place.
+fn desktop_api_client(http: &reqwest::Client, server_url: String) -> cap_api::Client {
+    let mut client = cap_api::Client::new(server_url)
+        .with_http_client(http.clone())
+        .with_header(
+            HeaderName::from_static("x-cap-desktop-version"),
+            HeaderValue::from_static(env!("CARGO_PKG_VERSION")),
+        )
+        .with_header(
+            HeaderName::from_static("x-cap-desktop-features"),
+            HeaderValue::from_static("googleDriveUpload"),
+        );
 
     if let Ok(s) = std::env::var("VITE_VERCEL_AUTOMATION_BYPASS_SECRET") {
-        req = req.header("x-vercel-protection-bypass", s);
+        match HeaderValue::from_str(&s) {
+            Ok(value) => {
+                client = client
+                    .with_header(HeaderName::from_static("x-vercel-protection-bypass"), value);
+            }
+            Err(e) => warn!("Ignoring invalid VITE_VERCEL_AUTOMATION_BYPASS_SECRET: {e}"),
+        }
     }
 
-    req
+    client
 }
 
 fn default_server_url() -> String {
@@ -80,39 +109,14 @@ where
     Ok(app_state.read().await.server_url.clone())
 }
 
-async fn do_authed_request(
-    client: &reqwest::Client,
-    auth: &AuthStore,
-    build: impl FnOnce(&reqwest::Client, String) -> reqwest::RequestBuilder,
-    url: String,
-) -> Result<reqwest::Response, reqwest::Error> {
-    let req = build(client, url).header(
-        "Authorization",
-        format!(
-            "Bearer {}",
-            match &auth.secret {
-                AuthSecret::ApiKey { api_key } => api_key,
-                AuthSecret::Session { token, .. } => token,
-            }
-        ),
-    );
-
-    apply_env_headers(req).send().await
-}
-
 #[allow(async_fn_in_trait)]
 pub trait ManagerExt<R: Runtime>: Manager<R> {
-    async fn authed_api_request(
-        &self,
-        path: impl Into<String>,
-        build: impl FnOnce(&reqwest::Client, String) -> reqwest::RequestBuilder,
-    ) -> Result<reqwest::Response, AuthedApiError>;
-
-    async fn api_request(
-        &self,
-        path: impl Into<String>,
-        build: impl FnOnce(&reqwest::Client, String) -> reqwest::RequestBuilder,
-    ) -> Result<reqwest::Response, reqwest::Error>;
+    /// Typed API client for the current server, authenticated as the signed-in user.
+    async fn api_client(&self) -> Result<cap_api::Client, AuthedApiError>;
+
+    /// API client for the current server without credentials, for endpoints that don't need a
+    /// signed-in user.
+    async fn public_api_client(&self) -> cap_api::Client;
 
     async fn make_app_url(&self, pathname: impl AsRef<str>) -> String;
 
@@ -120,41 +124,30 @@ pub trait ManagerExt<R: Runtime>: Manager<R> {
 }
 
 impl<T: Manager<R> + Emitter<R>, R: Runtime> ManagerExt<R> for T {
-    async fn authed_api_request(
-        &self,
-        path: impl Into<String>,
-        build: impl FnOnce(&reqwest::Client, String) -> reqwest::RequestBuilder,
-    ) -> Result<reqwest::Response, AuthedApiError> {
+    async fn api_client(&self) -> Result<cap_api::Client, AuthedApiError> {
         let Some(auth) = AuthStore::get(self.app_handle()).map_err(AuthedApiError::AuthStore)?
         else {
             debug!("Skipping authenticated API request because user is not logged in");
             return Err(AuthedApiError::InvalidAuthentication);
         };
 
-        let path = path.into();
-        let server_url = current_server_url(self).await?;
-        let url = format!("{server_url}{path}");
-        let response =
-            do_authed_request(&self.state::<http_client::HttpClient>(), &auth, build, url).await?;
-
-        if response.status() == StatusCode::UNAUTHORIZED {
-            error!("Authentication expired. Please log in again.");
-            return Err(AuthedApiError::InvalidAuthentication);
-        }
+        let token = match &auth.secret {
+            AuthSecret::ApiKey { api_key } => api_key,
+            AuthSecret::Session { token, .. } => token,
+        };
 
-        Ok(response)
+        Ok(desktop_api_client(
+            &self.state::<http_client::HttpClient>(),
+            current_server_url(self).await?,
+        )
+        .with_token(token))
     }
 
-    async fn api_request(
-        &self,
-        path: impl Into<String>,
-        build: impl FnOnce(&reqwest::Client, String) -> reqwest::RequestBuilder,
-    ) -> Result<reqwest::Response, reqwest::Error> {
-        let url = self.make_app_url(path.into()).await;
-
-        apply_env_headers(build(&self.state::<http_client::HttpClient>(), url))
-            .send()
-            .await
+    async fn public_api_client(&self) -> cap_api::Client {
+        desktop_api_client(
+            &self.state::<http_client::HttpClient>(),
+            self.make_app_url("").await,
+        )
     }
 
     async fn make_app_url(&self, pathname: impl AsRef<str>) -> String {
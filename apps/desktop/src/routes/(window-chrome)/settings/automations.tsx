@@ -1379,6 +1379,9 @@ function ExportParams(props: {
 							{ value: "mp4", label: "MP4" },
 							{ value: "gif", label: "GIF" },
 							{ value: "mov", label: "MOV" },
+							{ value: "hevc", label: "MP4 (HEVC)" },
+							{ value: "av1", label: "MP4 (AV1)" },
+							{ value: "webm", label: "WebM (VP9)" },
 						]}
 						onChange={(v) =>
 							updateProfile((p) => {
@@ -436,6 +436,10 @@ function InProgressRecordingInner() {
 		},
 	}));
 
+	const addChapterMarker = createMutation(() => ({
+		mutationFn: () => commands.addChapterMarker(null),
+	}));
+
 	const restartRecording = createMutation(() => ({
 		mutationFn: async () => {
 			const shouldRestart = await dialog.confirm(
@@ -889,6 +893,14 @@ function InProgressRecordingInner() {
 											</ActionButton>
 										)}
 
+										<ActionButton
+											disabled={addChapterMarker.isPending || isCountdown()}
+											onClick={() => addChapterMarker.mutate()}
+											title="Add chapter marker"
+											aria-label="Add chapter marker"
+										>
+											<IconLucideBookmarkPlus class="size-5" />
+										</ActionButton>
 										<ActionButton
 											disabled={restartRecording.isPending || isCountdown()}
 											onClick={() => restartRecording.mutate()}
@@ -1,3 +1,230 @@
-//! Types and implements for the Cap web API endpoints.
+//! Types and a client for the Cap web API endpoints.
+//!
+//! Both the desktop app and `cap upload` talk to the server through [`Client`]. The base URL is
+//! pluggable so self-hosted servers and local mock servers work the same way as cap.so.
 
-// TODO: Migrate `apps/desktop/src-tauri/upload.rs` here once we figure out how auth will work with that.
+mod upload;
+mod video;
+
+pub use upload::*;
+pub use video::*;
+
+use reqwest::{
+    Method, RequestBuilder, Response, StatusCode,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::{Deserialize, de::DeserializeOwned};
+
+pub const DEFAULT_SERVER_URL: &str = "https://cap.so";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Not authenticated or credentials have expired")]
+    Unauthenticated,
+    #[error("The account needs to be upgraded to use this feature")]
+    UpgradeRequired,
+    #[error("The request has timed out")]
+    Timeout,
+    #[error("Failed to reach {path}: {source}")]
+    Request {
+        path: String,
+        source: reqwest::Error,
+    },
+    #[error("{path} failed ({status}): {body}")]
+    Status {
+        path: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("Unexpected {path} response: {source}")]
+    Response {
+        path: String,
+        source: reqwest::Error,
+    },
+    #[error("Storage upload failed ({status}): {body}")]
+    Storage { status: StatusCode, body: String },
+    #[error("Storage did not return an ETag for part {0}")]
+    MissingEtag(u32),
+    #[error("Upload failed: {0}")]
+    Upload(reqwest::Error),
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+/// Authenticated client for a Cap server.
+///
+/// Cheap to clone; clones share the underlying connection pool.
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    headers: HeaderMap,
+}
+
+impl Client {
+    /// `base_url` is the server origin, eg. `https://cap.so` or `http://127.0.0.1:3000`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// API key or desktop session token, sent as a bearer token.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Reuse an existing `reqwest::Client` so its pool, timeouts and retry policy apply.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Header sent with every API request (not with uploads to presigned storage URLs).
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// Public share page for a video.
+    pub fn share_link(&self, video_id: &str) -> String {
+        self.url(&format!("/s/{video_id}"))
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut req = self
+            .http
+            .request(method, self.url(path))
+            .headers(self.headers.clone());
+        if let Some(token) = &self.token {
+            req = req.bearer_auth(token);
+        }
+        req
+    }
+
+    async fn send(&self, path: &str, req: RequestBuilder) -> Result<Response, Error> {
+        let response = req.send().await.map_err(|source| {
+            if source.is_timeout() {
+                Error::Timeout
+            } else {
+                Error::Request {
+                    path: path.to_string(),
+                    source,
+                }
+            }
+        })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(Error::Unauthenticated);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+
+        #[derive(Deserialize)]
+        struct ErrorBody {
+            error: String,
+        }
+
+        if status == StatusCode::FORBIDDEN
+            && serde_json::from_str::<ErrorBody>(&body)
+                .is_ok_and(|body| body.error == "upgrade_required")
+        {
+            return Err(Error::UpgradeRequired);
+        }
+
+        Err(Error::Status {
+            path: path.to_string(),
+            status,
+            body,
+        })
+    }
+
+    async fn json<T: DeserializeOwned>(&self, path: &str, req: RequestBuilder) -> Result<T, Error> {
+        self.send(path, req)
+            .await?
+            .json()
+            .await
+            .map_err(|source| Error::Response {
+                path: path.to_string(),
+                source,
+            })
+    }
+
+    /// Signed-in user's profile. Doubles as a cheap credentials check.
+    pub async fn user_profile(&self) -> Result<UserProfile, Error> {
+        let path = "/api/desktop/user/profile";
+        self.json(path, self.request(Method::GET, path)).await
+    }
+
+    pub async fn user_plan(&self) -> Result<UserPlan, Error> {
+        let path = "/api/desktop/plan";
+        self.json(path, self.request(Method::GET, path)).await
+    }
+
+    /// Organizations the signed-in user belongs to, deserialized into the caller's type so the
+    /// desktop app can keep its own `Organization` with the fields it renders.
+    pub async fn organizations<T: DeserializeOwned>(&self) -> Result<Vec<T>, Error> {
+        let path = "/api/desktop/organizations";
+        self.json(path, self.request(Method::GET, path)).await
+    }
+
+    /// Sends a desktop log file to the server for support. Works without a token.
+    pub async fn upload_logs(&self, logs: DesktopLogs) -> Result<(), Error> {
+        let path = "/api/desktop/logs";
+        let form = reqwest::multipart::Form::new()
+            .text("log", logs.log)
+            .text("os", logs.os)
+            .text("version", logs.version)
+            .text("diagnostics", logs.diagnostics);
+        self.send(path, self.request(Method::POST, path).multipart(form))
+            .await
+            .map(drop)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserProfile {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DesktopLogs {
+    pub log: String,
+    pub os: String,
+    pub version: String,
+    /// JSON-encoded diagnostics collected alongside the log.
+    pub diagnostics: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPlan {
+    #[serde(default)]
+    pub upgraded: bool,
+    #[serde(default)]
+    pub stripe_subscription_status: Option<String>,
+}
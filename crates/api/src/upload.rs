@@ -0,0 +1,367 @@
+use std::{collections::HashMap, path::Path};
+
+use reqwest::{Body, Method, StatusCode, header};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio_util::io::ReaderStream;
+
+use crate::{Client, CreateVideo, Error, S3VideoMeta, SharedVideo};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresignedS3PutRequestMethod {
+    Post,
+    Put,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresignedS3PutRequest {
+    pub video_id: String,
+    /// Object key relative to the video, eg. `result.mp4`.
+    pub subpath: String,
+    pub method: PresignedS3PutRequestMethod,
+    #[serde(flatten)]
+    pub meta: Option<S3VideoMeta>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedUploadTarget {
+    pub url: String,
+    /// Extra headers the storage provider requires on the upload request.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultipartUploadInitiateResponse {
+    pub upload_id: String,
+    pub provider: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedPart {
+    pub part_number: u32,
+    pub etag: String,
+    pub size: usize,
+    #[serde(skip)]
+    pub total_size: u64,
+}
+
+/// Whether a presigned part URL is a Google Drive resumable session. Drive takes parts strictly in
+/// order, each with a `Content-Range`, and answers every part but the last with `308`.
+pub fn is_google_drive_resumable_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    url.host_str().is_some_and(|host| {
+        (host == "googleapis.com" || host.ends_with(".googleapis.com"))
+            && url.path().starts_with("/upload/drive/")
+    })
+}
+
+/// Object key a screenshot of the given MIME type is stored under.
+pub fn screenshot_upload_subpath(content_type: &str) -> &'static str {
+    if content_type.eq_ignore_ascii_case("image/png") {
+        "screenshot/screen-capture.png"
+    } else {
+        "screenshot/screen-capture.jpg"
+    }
+}
+
+pub fn screenshot_content_type_from_path(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        _ => "image/jpeg",
+    }
+}
+
+impl Client {
+    /// Presigned URL for uploading a single object in one request.
+    pub async fn presign_put(
+        &self,
+        request: &PresignedS3PutRequest,
+    ) -> Result<SignedUploadTarget, Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            presigned_put_data: SignedUploadTarget,
+        }
+
+        let path = "/api/upload/signed";
+        self.json::<Response>(path, self.request(Method::POST, path).json(request))
+            .await
+            .map(|data| data.presigned_put_data)
+    }
+
+    /// Presigned PUT URLs for several objects of the same video, keyed by subpath.
+    pub async fn presign_batch(
+        &self,
+        video_id: &str,
+        subpaths: &[String],
+    ) -> Result<HashMap<String, String>, Error> {
+        #[derive(Deserialize)]
+        struct Response {
+            urls: HashMap<String, String>,
+        }
+
+        let path = "/api/upload/signed/batch";
+        self.json::<Response>(
+            path,
+            self.request(Method::POST, path).json(&json!({
+                "videoId": video_id,
+                "subpaths": subpaths,
+            })),
+        )
+        .await
+        .map(|data| data.urls)
+    }
+
+    pub async fn multipart_initiate(
+        &self,
+        video_id: &str,
+        content_type: &str,
+    ) -> Result<MultipartUploadInitiateResponse, Error> {
+        let path = "/api/upload/multipart/initiate";
+        self.json(
+            path,
+            self.request(Method::POST, path).json(&json!({
+                "videoId": video_id,
+                "contentType": content_type,
+            })),
+        )
+        .await
+    }
+
+    pub async fn multipart_presign_part(
+        &self,
+        video_id: &str,
+        upload_id: &str,
+        part_number: u32,
+        md5_sum: Option<&str>,
+    ) -> Result<String, Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            presigned_url: String,
+        }
+
+        let mut body = json!({
+            "videoId": video_id,
+            "uploadId": upload_id,
+            "partNumber": part_number,
+        });
+        if let Some(md5_sum) = md5_sum {
+            body["md5Sum"] = json!(md5_sum);
+        }
+
+        let path = "/api/upload/multipart/presign-part";
+        self.json::<Response>(path, self.request(Method::POST, path).json(&body))
+            .await
+            .map(|data| data.presigned_url)
+    }
+
+    /// Finishes a multipart upload, returning the object location when the provider reports one.
+    pub async fn multipart_complete(
+        &self,
+        video_id: &str,
+        upload_id: &str,
+        parts: &[UploadedPart],
+        meta: Option<S3VideoMeta>,
+    ) -> Result<Option<String>, Error> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Request<'a> {
+            video_id: &'a str,
+            upload_id: &'a str,
+            parts: &'a [UploadedPart],
+            #[serde(flatten)]
+            meta: Option<S3VideoMeta>,
+        }
+
+        #[derive(Deserialize)]
+        struct Response {
+            location: Option<String>,
+        }
+
+        let path = "/api/upload/multipart/complete";
+        self.json::<Response>(
+            path,
+            self.request(Method::POST, path).json(&Request {
+                video_id,
+                upload_id,
+                parts,
+                meta,
+            }),
+        )
+        .await
+        .map(|data| data.location)
+    }
+
+    pub async fn multipart_abort(&self, video_id: &str, upload_id: &str) -> Result<(), Error> {
+        let path = "/api/upload/multipart/abort";
+        self.send(
+            path,
+            self.request(Method::POST, path).json(&json!({
+                "videoId": video_id,
+                "uploadId": upload_id,
+            })),
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Uploads a body to a presigned URL, returning the object's ETag if the provider sent one.
+    ///
+    /// Storage providers reject chunked transfer encoding for presigned PUTs, so the body length
+    /// must be known up front.
+    pub async fn put_object(
+        &self,
+        target: &SignedUploadTarget,
+        body: impl Into<Body>,
+        content_length: u64,
+    ) -> Result<Option<String>, Error> {
+        let mut request = self
+            .http
+            .put(&target.url)
+            .header(header::CONTENT_LENGTH, content_length)
+            .body(body);
+
+        for (key, value) in &target.headers {
+            if key.eq_ignore_ascii_case("content-length") {
+                continue;
+            }
+            request = request.header(key, value);
+        }
+
+        let response = request.send().await.map_err(Error::Upload)?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Storage { status, body });
+        }
+
+        Ok(response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.trim_matches('"').to_string()))
+    }
+
+    /// Uploads one part of a multipart upload to its presigned URL.
+    pub async fn put_part(
+        &self,
+        url: &str,
+        part_number: u32,
+        chunk: Vec<u8>,
+        offset: u64,
+        total_size: u64,
+    ) -> Result<UploadedPart, Error> {
+        let size = chunk.len();
+        let is_drive = is_google_drive_resumable_url(url);
+
+        let mut request = self
+            .http
+            .put(url)
+            .header(header::CONTENT_LENGTH, size as u64)
+            .body(chunk);
+        if is_drive && size > 0 {
+            let end = offset + size as u64 - 1;
+            request = request.header(
+                header::CONTENT_RANGE,
+                format!("bytes {offset}-{end}/{total_size}"),
+            );
+        }
+
+        let response = request.send().await.map_err(Error::Upload)?;
+        let status = response.status();
+        let accepted = status.is_success()
+            || (is_drive
+                && status == StatusCode::PERMANENT_REDIRECT
+                && offset + (size as u64) < total_size);
+        if !accepted {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Storage { status, body });
+        }
+
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.trim_matches('"').to_string());
+        let etag = match etag {
+            Some(etag) => etag,
+            None if is_drive => format!("drive-{part_number}"),
+            None => return Err(Error::MissingEtag(part_number)),
+        };
+
+        Ok(UploadedPart {
+            part_number,
+            etag,
+            size,
+            total_size,
+        })
+    }
+
+    /// Streams a file to a presigned URL without buffering it in memory.
+    pub async fn upload_file(
+        &self,
+        target: &SignedUploadTarget,
+        file_path: &Path,
+    ) -> Result<Option<String>, Error> {
+        let io_error = |source| Error::Io {
+            path: file_path.display().to_string(),
+            source,
+        };
+
+        let file = tokio::fs::File::open(file_path).await.map_err(io_error)?;
+        let total_size = file.metadata().await.map_err(io_error)?.len();
+
+        self.put_object(
+            target,
+            Body::wrap_stream(ReaderStream::new(file)),
+            total_size,
+        )
+        .await
+    }
+
+    /// Creates (or reuses) a screenshot video and uploads the image to it.
+    pub async fn upload_screenshot(
+        &self,
+        image: Vec<u8>,
+        content_type: &str,
+        video_id: Option<String>,
+        organization_id: Option<String>,
+    ) -> Result<SharedVideo, Error> {
+        let video = self
+            .create_video(&CreateVideo {
+                video_id,
+                is_screenshot: true,
+                organization_id,
+                ..Default::default()
+            })
+            .await?;
+
+        let target = self
+            .presign_put(&PresignedS3PutRequest {
+                video_id: video.id.clone(),
+                subpath: screenshot_upload_subpath(content_type).to_string(),
+                method: PresignedS3PutRequestMethod::Put,
+                meta: None,
+            })
+            .await?;
+
+        let len = image.len() as u64;
+        self.put_object(&target, image, len).await?;
+
+        Ok(self.shared_video(video))
+    }
+}
@@ -0,0 +1,157 @@
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{Client, Error};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecordingMode {
+    /// A single finished MP4, uploaded once recording ends.
+    #[default]
+    DesktopMp4,
+    /// Fragmented segments uploaded while recording (instant mode).
+    DesktopSegments,
+    Hls,
+}
+
+impl RecordingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DesktopMp4 => "desktopMP4",
+            Self::DesktopSegments => "desktopSegments",
+            Self::Hls => "hls",
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct S3VideoMeta {
+    #[serde(rename = "durationInSecs")]
+    pub duration_in_secs: f64,
+    pub width: u32,
+    pub height: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fps: Option<f32>,
+}
+
+/// Parameters for `/api/desktop/video/create`.
+#[derive(Debug, Clone, Default)]
+pub struct CreateVideo {
+    pub recording_mode: RecordingMode,
+    /// Reuse a video the caller already owns instead of creating a new one.
+    pub video_id: Option<String>,
+    pub name: Option<String>,
+    pub is_screenshot: bool,
+    pub meta: Option<S3VideoMeta>,
+    pub organization_id: Option<String>,
+}
+
+impl CreateVideo {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![("recordingMode", self.recording_mode.as_str().to_string())];
+        if let Some(id) = &self.video_id {
+            query.push(("videoId", id.clone()));
+        }
+        if self.is_screenshot {
+            query.push(("isScreenshot", "true".to_string()));
+        }
+        if let Some(name) = &self.name {
+            query.push(("name", name.clone()));
+        }
+        if let Some(meta) = &self.meta {
+            query.push(("durationInSecs", meta.duration_in_secs.to_string()));
+            query.push(("width", meta.width.to_string()));
+            query.push(("height", meta.height.to_string()));
+            if let Some(fps) = meta.fps {
+                query.push(("fps", fps.to_string()));
+            }
+        }
+        if let Some(org_id) = &self.organization_id {
+            query.push(("orgId", org_id.clone()));
+        }
+        query
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct Video {
+    pub id: String,
+}
+
+/// What a finished upload hands back to the user.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SharedVideo {
+    pub id: String,
+    pub link: String,
+}
+
+impl Client {
+    /// Creates a video, or returns the existing one when `video_id` is set and owned by the
+    /// caller. Long recordings on a free plan fail with [`Error::UpgradeRequired`].
+    pub async fn create_video(&self, params: &CreateVideo) -> Result<Video, Error> {
+        let path = "/api/desktop/video/create";
+        self.json(path, self.request(Method::GET, path).query(&params.query()))
+            .await
+    }
+
+    /// Looks up a video the caller owns.
+    pub async fn get_video(&self, video_id: &str) -> Result<Video, Error> {
+        self.create_video(&CreateVideo {
+            video_id: Some(video_id.to_string()),
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn delete_video(&self, video_id: &str) -> Result<(), Error> {
+        let path = "/api/desktop/video/delete";
+        self.send(
+            path,
+            self.request(Method::DELETE, path)
+                .query(&[("videoId", video_id)]),
+        )
+        .await
+        .map(drop)
+    }
+
+    pub fn shared_video(&self, video: Video) -> SharedVideo {
+        SharedVideo {
+            link: self.share_link(&video.id),
+            id: video.id,
+        }
+    }
+
+    /// Reports upload progress so the share page can show it while the upload runs.
+    pub async fn video_progress(
+        &self,
+        video_id: &str,
+        uploaded: u64,
+        total: u64,
+    ) -> Result<(), Error> {
+        let path = "/api/desktop/video/progress";
+        self.send(
+            path,
+            self.request(Method::POST, path).json(&json!({
+                "videoId": video_id,
+                "uploaded": uploaded,
+                "total": total,
+                "updatedAt": chrono::Utc::now().to_rfc3339()
+            })),
+        )
+        .await
+        .map(drop)
+    }
+
+    /// Tells the server an instant recording has stopped producing segments.
+    pub async fn recording_complete(&self, video_id: &str) -> Result<(), Error> {
+        let path = "/api/upload/recording-complete";
+        self.send(
+            path,
+            self.request(Method::POST, path)
+                .json(&json!({ "videoId": video_id })),
+        )
+        .await
+        .map(drop)
+    }
+}
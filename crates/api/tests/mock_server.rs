@@ -0,0 +1,434 @@
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use cap_api::{
+    Client, CreateVideo, DesktopLogs, Error, PresignedS3PutRequest, PresignedS3PutRequestMethod,
+    RecordingMode, S3VideoMeta, UploadedPart,
+};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::{Value, json};
+
+#[derive(Debug, Clone)]
+struct Recorded {
+    method: Method,
+    path: String,
+    query: String,
+    authorization: Option<String>,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Recorded {
+    fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+#[derive(Clone)]
+struct Mock {
+    base_url: String,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl Mock {
+    fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn request(&self, path: &str) -> Recorded {
+        self.requests()
+            .into_iter()
+            .find(|request| request.path == path)
+            .unwrap_or_else(|| panic!("no request to {path}"))
+    }
+
+    fn client(&self) -> Client {
+        Client::new(format!("{}/", self.base_url)).with_token("secret-key")
+    }
+}
+
+async fn handle(
+    State(mock): State<Mock>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri.path().to_string();
+    let query = uri.query().unwrap_or_default().to_string();
+    mock.requests.lock().unwrap().push(Recorded {
+        method,
+        path: path.clone(),
+        query: query.clone(),
+        authorization: headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+        headers,
+        body,
+    });
+
+    match path.as_str() {
+        "/api/desktop/user/profile" => Json(json!({
+            "name": "Ada",
+            "email": "ada@example.com",
+            "imageUrl": null
+        }))
+        .into_response(),
+        "/api/desktop/organizations" => Json(json!([
+            { "id": "org-1", "name": "Acme", "ownerId": "user" }
+        ]))
+        .into_response(),
+        "/api/desktop/logs" => StatusCode::OK.into_response(),
+        "/api/desktop/plan" => Json(json!({ "stripeSubscriptionStatus": null })).into_response(),
+        "/api/desktop/video/create" if query.contains("durationInSecs=600") => (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "upgrade_required" })),
+        )
+            .into_response(),
+        "/api/desktop/video/create" => {
+            let id = if query.contains("videoId=existing") {
+                "existing"
+            } else {
+                "new-video"
+            };
+            Json(json!({ "id": id, "user_id": "user", "aws_region": "n/a" })).into_response()
+        }
+        "/api/upload/signed" => Json(json!({
+            "presignedPutData": {
+                "url": format!("{}/storage/object", mock.base_url),
+                "headers": { "x-amz-meta-test": "1" }
+            }
+        }))
+        .into_response(),
+        "/api/upload/multipart/initiate" => {
+            Json(json!({ "uploadId": "upload-1", "provider": null })).into_response()
+        }
+        "/api/upload/multipart/presign-part" => Json(json!({
+            "presignedUrl": format!("{}/storage/part", mock.base_url)
+        }))
+        .into_response(),
+        "/api/upload/multipart/complete" => {
+            Json(json!({ "location": "https://bucket/new-video/result.mp4" })).into_response()
+        }
+        "/storage/object" | "/storage/part" => {
+            ([("ETag", "\"etag-123\"")], StatusCode::OK).into_response()
+        }
+        "/storage/no-etag" => StatusCode::OK.into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "boom").into_response(),
+    }
+}
+
+async fn unauthorized() -> Response {
+    (StatusCode::UNAUTHORIZED, Json(json!({ "error": true }))).into_response()
+}
+
+async fn serve(router: impl FnOnce(Mock) -> Router) -> Mock {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mock = Mock {
+        base_url: format!("http://{}", listener.local_addr().unwrap()),
+        requests: Default::default(),
+    };
+    let app = router(mock.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    mock
+}
+
+async fn mock_server() -> Mock {
+    serve(|mock| Router::new().fallback(handle).with_state(mock)).await
+}
+
+#[tokio::test]
+async fn sends_auth_and_custom_headers_to_the_configured_server() {
+    let mock = mock_server().await;
+    let client = mock.client().with_header(
+        HeaderName::from_static("x-cap-desktop-version"),
+        HeaderValue::from_static("1.2.3"),
+    );
+
+    let profile = client.user_profile().await.unwrap();
+    assert_eq!(profile.email.as_deref(), Some("ada@example.com"));
+
+    let request = mock.request("/api/desktop/user/profile");
+    assert_eq!(request.method, Method::GET);
+    assert_eq!(request.authorization.as_deref(), Some("Bearer secret-key"));
+    assert_eq!(request.headers["x-cap-desktop-version"], "1.2.3");
+    assert_eq!(client.base_url(), mock.base_url);
+    assert_eq!(client.share_link("abc"), format!("{}/s/abc", mock.base_url));
+}
+
+#[tokio::test]
+async fn fetches_organizations_and_uploads_logs() {
+    let mock = mock_server().await;
+
+    let organizations = mock.client().organizations::<Value>().await.unwrap();
+    assert_eq!(organizations[0]["name"], "Acme");
+
+    Client::new(&mock.base_url)
+        .upload_logs(DesktopLogs {
+            log: "line one".to_string(),
+            os: "macos".to_string(),
+            version: "1.2.3".to_string(),
+            diagnostics: "{}".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let request = mock.request("/api/desktop/logs");
+    assert_eq!(request.method, Method::POST);
+    assert_eq!(request.authorization, None);
+    let body = String::from_utf8_lossy(&request.body);
+    assert!(body.contains("name=\"log\"") && body.contains("line one"));
+}
+
+#[tokio::test]
+async fn create_video_encodes_metadata_as_query() {
+    let mock = mock_server().await;
+
+    let video = mock
+        .client()
+        .create_video(&CreateVideo {
+            recording_mode: RecordingMode::DesktopSegments,
+            name: Some("Demo & notes".to_string()),
+            meta: Some(S3VideoMeta {
+                duration_in_secs: 12.5,
+                width: 1920,
+                height: 1080,
+                fps: Some(30.0),
+            }),
+            organization_id: Some("org".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(video.id, "new-video");
+
+    let query = mock.request("/api/desktop/video/create").query;
+    for expected in [
+        "recordingMode=desktopSegments",
+        "name=Demo+%26+notes",
+        "durationInSecs=12.5",
+        "width=1920",
+        "height=1080",
+        "fps=30",
+        "orgId=org",
+    ] {
+        assert!(query.contains(expected), "{expected} missing from {query}");
+    }
+    assert!(!query.contains("isScreenshot"));
+
+    let existing = mock.client().get_video("existing").await.unwrap();
+    assert_eq!(existing.id, "existing");
+}
+
+#[tokio::test]
+async fn maps_auth_upgrade_and_server_errors() {
+    let mock = mock_server().await;
+    let long = CreateVideo {
+        meta: Some(S3VideoMeta {
+            duration_in_secs: 600.0,
+            width: 1280,
+            height: 720,
+            fps: None,
+        }),
+        ..Default::default()
+    };
+    assert!(matches!(
+        mock.client().create_video(&long).await,
+        Err(Error::UpgradeRequired)
+    ));
+
+    match mock.client().recording_complete("new-video").await {
+        Err(Error::Status { path, status, body }) => {
+            assert_eq!(path, "/api/upload/recording-complete");
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(body, "boom");
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+
+    let rejecting = serve(|_| Router::new().fallback(unauthorized)).await;
+    assert!(matches!(
+        rejecting.client().user_plan().await,
+        Err(Error::Unauthenticated)
+    ));
+}
+
+#[tokio::test]
+async fn plan_without_upgraded_field_is_not_upgraded() {
+    let mock = mock_server().await;
+
+    let plan = mock.client().user_plan().await.unwrap();
+    assert!(!plan.upgraded);
+    assert_eq!(plan.stripe_subscription_status, None);
+}
+
+#[tokio::test]
+async fn single_part_upload_streams_file_to_presigned_url() {
+    let mock = mock_server().await;
+    let client = mock.client();
+
+    let dir = std::env::temp_dir().join(format!("cap-api-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("result.mp4");
+    std::fs::write(&file, b"not really an mp4").unwrap();
+
+    let target = client
+        .presign_put(&PresignedS3PutRequest {
+            video_id: "new-video".to_string(),
+            subpath: "result.mp4".to_string(),
+            method: PresignedS3PutRequestMethod::Put,
+            meta: Some(S3VideoMeta {
+                duration_in_secs: 3.0,
+                width: 640,
+                height: 480,
+                fps: None,
+            }),
+        })
+        .await
+        .unwrap();
+    let etag = client.upload_file(&target, &file).await.unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(etag.as_deref(), Some("etag-123"));
+    assert_eq!(
+        mock.request("/api/upload/signed").json(),
+        json!({
+            "videoId": "new-video",
+            "subpath": "result.mp4",
+            "method": "put",
+            "durationInSecs": 3.0,
+            "width": 640,
+            "height": 480
+        })
+    );
+
+    let upload = mock.request("/storage/object");
+    assert_eq!(upload.method, Method::PUT);
+    assert_eq!(upload.body.as_ref(), b"not really an mp4");
+    assert_eq!(upload.headers["content-length"], "17");
+    assert_eq!(upload.headers["x-amz-meta-test"], "1");
+    // Storage URLs are presigned; the API token must not leak to them.
+    assert_eq!(upload.authorization, None);
+}
+
+#[tokio::test]
+async fn multipart_upload_round_trip() {
+    let mock = mock_server().await;
+    let client = mock.client();
+
+    let upload = client
+        .multipart_initiate("new-video", "video/mp4")
+        .await
+        .unwrap();
+    let url = client
+        .multipart_presign_part("new-video", &upload.upload_id, 1, Some("md5"))
+        .await
+        .unwrap();
+    assert_eq!(url, format!("{}/storage/part", mock.base_url));
+
+    let location = client
+        .multipart_complete(
+            "new-video",
+            &upload.upload_id,
+            &[UploadedPart {
+                part_number: 1,
+                etag: "etag-123".to_string(),
+                size: 5,
+                total_size: 5,
+            }],
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        location.as_deref(),
+        Some("https://bucket/new-video/result.mp4")
+    );
+
+    assert_eq!(
+        mock.request("/api/upload/multipart/presign-part").json(),
+        json!({ "videoId": "new-video", "uploadId": "upload-1", "partNumber": 1, "md5Sum": "md5" })
+    );
+    assert_eq!(
+        mock.request("/api/upload/multipart/complete").json(),
+        json!({
+            "videoId": "new-video",
+            "uploadId": "upload-1",
+            "parts": [{ "partNumber": 1, "etag": "etag-123", "size": 5 }]
+        })
+    );
+}
+
+#[tokio::test]
+async fn screenshot_upload_creates_screenshot_video() {
+    let mock = mock_server().await;
+
+    let shared = mock
+        .client()
+        .upload_screenshot(vec![1, 2, 3], "image/png", None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(shared.id, "new-video");
+    assert_eq!(shared.link, format!("{}/s/new-video", mock.base_url));
+    assert!(
+        mock.request("/api/desktop/video/create")
+            .query
+            .contains("isScreenshot=true")
+    );
+    assert_eq!(
+        mock.request("/api/upload/signed").json()["subpath"],
+        "screenshot/screen-capture.png"
+    );
+    assert_eq!(mock.request("/storage/object").body.as_ref(), &[1, 2, 3]);
+}
+
+#[tokio::test]
+async fn part_upload_returns_etag_and_rejects_parts_without_one() {
+    let mock = mock_server().await;
+    let client = mock.client();
+
+    let part = client
+        .put_part(
+            &format!("{}/storage/part", mock.base_url),
+            2,
+            vec![7; 10],
+            10,
+            20,
+        )
+        .await
+        .unwrap();
+    assert_eq!(part.part_number, 2);
+    assert_eq!(part.etag, "etag-123");
+    assert_eq!(part.size, 10);
+    assert_eq!(part.total_size, 20);
+
+    let upload = mock.request("/storage/part");
+    assert_eq!(upload.body.as_ref(), &[7; 10]);
+    // Only Drive resumable sessions take a Content-Range.
+    assert!(upload.headers.get("content-range").is_none());
+
+    assert!(matches!(
+        client
+            .put_part(
+                &format!("{}/storage/no-etag", mock.base_url),
+                3,
+                vec![1],
+                0,
+                1
+            )
+            .await,
+        Err(Error::MissingEtag(3))
+    ));
+    assert!(cap_api::is_google_drive_resumable_url(
+        "https://www.googleapis.com/upload/drive/v3/files?uploadType=resumable&upload_id=x"
+    ));
+    assert!(!cap_api::is_google_drive_resumable_url(&mock.base_url));
+}
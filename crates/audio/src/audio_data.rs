@@ -106,8 +106,9 @@ impl AudioData {
         self.samples.len() / self.channels as usize
     }
 
-    #[cfg(test)]
-    pub(crate) fn from_raw_f32(samples: Vec<f32>, channels: u16) -> Self {
+    /// Wraps already-decoded 48kHz interleaved samples, e.g. synthesized test
+    /// signals or the output of an offline processing pass.
+    pub fn from_raw_f32(samples: Vec<f32>, channels: u16) -> Self {
         Self { samples, channels }
     }
 }
@@ -0,0 +1,282 @@
+use crate::AudioData;
+
+/// ITU-R BS.1770 K-weighting coefficients for 48kHz input: a high-shelf
+/// "head" filter followed by the RLB high-pass.
+const SHELF_B: [f64; 3] = [
+    1.535_124_859_586_97,
+    -2.691_696_189_406_38,
+    1.198_392_810_852_85,
+];
+const SHELF_A: [f64; 2] = [-1.690_659_293_182_41, 0.732_480_774_215_85];
+const RLB_B: [f64; 3] = [1.0, -2.0, 1.0];
+const RLB_A: [f64; 2] = [-1.990_047_454_833_98, 0.990_072_250_366_21];
+
+/// Gating blocks are 400ms long with a 100ms hop (75% overlap).
+const SUB_BLOCK_SAMPLES: usize = AudioData::SAMPLE_RATE as usize / 10;
+const SUB_BLOCKS_PER_BLOCK: usize = 4;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// True-peak detection oversamples 4x (BS.1770 Annex 2) through a
+/// windowed-sinc interpolator with this many taps per phase.
+const OVERSAMPLING: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+#[derive(Clone, Copy, Default)]
+struct KWeighting {
+    shelf: [f64; 4],
+    rlb: [f64; 4],
+}
+
+fn biquad(b: &[f64; 3], a: &[f64; 2], state: &mut [f64; 4], x: f64) -> f64 {
+    // state = [x1, x2, y1, y2]
+    let y = b[0] * x + b[1] * state[0] + b[2] * state[1] - a[0] * state[2] - a[1] * state[3];
+    state[1] = state[0];
+    state[0] = x;
+    state[3] = state[2];
+    state[2] = y;
+    y
+}
+
+impl KWeighting {
+    fn process(&mut self, x: f64) -> f64 {
+        let shelved = biquad(&SHELF_B, &SHELF_A, &mut self.shelf, x);
+        biquad(&RLB_B, &RLB_A, &mut self.rlb, shelved)
+    }
+}
+
+fn power_to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Polyphase interpolation filter, `phases[p][k]` weighting the sample `k`
+/// steps back for output phase `p`. Each phase is normalized to unity DC gain.
+fn true_peak_phases() -> [[f64; TAPS_PER_PHASE]; OVERSAMPLING] {
+    let len = OVERSAMPLING * TAPS_PER_PHASE;
+    let centre = len as f64 / 2.0;
+    let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+
+    for (p, phase) in phases.iter_mut().enumerate() {
+        for (k, tap) in phase.iter_mut().enumerate() {
+            let m = (OVERSAMPLING * k + p) as f64;
+            let t = (m - centre) / OVERSAMPLING as f64;
+            let sinc = if t == 0.0 {
+                1.0
+            } else {
+                (std::f64::consts::PI * t).sin() / (std::f64::consts::PI * t)
+            };
+            let window = 0.5 - 0.5 * (std::f64::consts::TAU * m / len as f64).cos();
+            *tap = sinc * window;
+        }
+
+        let sum: f64 = phase.iter().sum();
+        phase.iter_mut().for_each(|tap| *tap /= sum);
+    }
+
+    phases
+}
+
+struct TruePeak {
+    phases: [[f64; TAPS_PER_PHASE]; OVERSAMPLING],
+    // Most recent input first, one history per channel.
+    history: Vec<[f64; TAPS_PER_PHASE]>,
+    peak: f64,
+}
+
+impl TruePeak {
+    fn new(channels: usize) -> Self {
+        Self {
+            phases: true_peak_phases(),
+            history: vec![[0.0; TAPS_PER_PHASE]; channels],
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, channel: usize, sample: f64) {
+        let history = &mut self.history[channel];
+        history.copy_within(..TAPS_PER_PHASE - 1, 1);
+        history[0] = sample;
+
+        for phase in &self.phases {
+            let value: f64 = phase.iter().zip(history.iter()).map(|(h, x)| h * x).sum();
+            self.peak = self.peak.max(value.abs());
+        }
+        self.peak = self.peak.max(sample.abs());
+    }
+}
+
+/// Streaming EBU R128 / BS.1770 integrated loudness and true-peak meter for
+/// 48kHz interleaved f32 audio.
+///
+/// Channels are weighted equally, which matches the standard for mono and
+/// stereo (the only layouts `AudioData` produces).
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<KWeighting>,
+    sub_block_energy: f64,
+    sub_block_len: usize,
+    recent_sub_blocks: [f64; SUB_BLOCKS_PER_BLOCK],
+    sub_blocks_seen: usize,
+    block_powers: Vec<f64>,
+    true_peak: TruePeak,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: u16) -> Self {
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            filters: vec![KWeighting::default(); channels],
+            sub_block_energy: 0.0,
+            sub_block_len: 0,
+            recent_sub_blocks: [0.0; SUB_BLOCKS_PER_BLOCK],
+            sub_blocks_seen: 0,
+            block_powers: Vec::new(),
+            true_peak: TruePeak::new(channels),
+        }
+    }
+
+    pub fn measure(data: &AudioData) -> Option<f64> {
+        let mut meter = Self::new(data.channels());
+        meter.push_interleaved(data.samples());
+        meter.integrated_lufs()
+    }
+
+    pub fn push_interleaved(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (channel, (sample, filter)) in frame.iter().zip(self.filters.iter_mut()).enumerate()
+            {
+                let weighted = filter.process(*sample as f64);
+                self.sub_block_energy += weighted * weighted;
+                self.true_peak.process(channel, *sample as f64);
+            }
+
+            self.sub_block_len += 1;
+            if self.sub_block_len == SUB_BLOCK_SAMPLES {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    fn finish_sub_block(&mut self) {
+        self.recent_sub_blocks[self.sub_blocks_seen % SUB_BLOCKS_PER_BLOCK] = self.sub_block_energy;
+        self.sub_blocks_seen += 1;
+        self.sub_block_energy = 0.0;
+        self.sub_block_len = 0;
+
+        if self.sub_blocks_seen >= SUB_BLOCKS_PER_BLOCK {
+            let energy: f64 = self.recent_sub_blocks.iter().sum();
+            self.block_powers
+                .push(energy / (SUB_BLOCK_SAMPLES * SUB_BLOCKS_PER_BLOCK) as f64);
+        }
+    }
+
+    /// Gated integrated loudness in LUFS, or `None` when nothing measured
+    /// passes the absolute gate (silence, or less than 400ms of audio).
+    pub fn integrated_lufs(&self) -> Option<f64> {
+        let absolute_gated = self
+            .block_powers
+            .iter()
+            .copied()
+            .filter(|power| *power > 0.0 && power_to_lufs(*power) > ABSOLUTE_GATE_LUFS)
+            .collect::<Vec<_>>();
+
+        if absolute_gated.is_empty() {
+            return None;
+        }
+
+        let relative_gate =
+            power_to_lufs(absolute_gated.iter().sum::<f64>() / absolute_gated.len() as f64)
+                + RELATIVE_GATE_LU;
+
+        let (sum, count) = absolute_gated
+            .iter()
+            .filter(|power| power_to_lufs(**power) > relative_gate)
+            .fold((0.0, 0usize), |(sum, count), power| {
+                (sum + power, count + 1)
+            });
+
+        (count > 0).then(|| power_to_lufs(sum / count as f64))
+    }
+
+    /// Highest inter-sample peak seen so far in dBTP, or `None` for silence.
+    pub fn true_peak_dbtp(&self) -> Option<f64> {
+        (self.true_peak.peak > 0.0).then(|| 20.0 * self.true_peak.peak.log10())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f32, amplitude: f32, seconds: f32, channels: u16) -> AudioData {
+        let frames = (AudioData::SAMPLE_RATE as f32 * seconds) as usize;
+        let mut samples = Vec::with_capacity(frames * channels as usize);
+        for i in 0..frames {
+            let t = i as f32 / AudioData::SAMPLE_RATE as f32;
+            let value = amplitude * (std::f32::consts::TAU * frequency * t).sin();
+            for _ in 0..channels {
+                samples.push(value);
+            }
+        }
+        AudioData::from_raw_f32(samples, channels)
+    }
+
+    // BS.1770 calibration: a 0dBFS 1kHz sine in a single channel reads
+    // -3.01 LUFS, so the same sine on both stereo channels reads 0 LUFS.
+    #[test]
+    fn full_scale_sine_matches_calibration() {
+        let loudness = LoudnessMeter::measure(&sine(1000.0, 1.0, 3.0, 1)).unwrap();
+        assert!((loudness + 3.01).abs() < 0.1, "{loudness}");
+
+        let loudness = LoudnessMeter::measure(&sine(1000.0, 1.0, 3.0, 2)).unwrap();
+        assert!(loudness.abs() < 0.1, "{loudness}");
+    }
+
+    #[test]
+    fn halving_amplitude_drops_six_lu() {
+        let loud = LoudnessMeter::measure(&sine(1000.0, 0.5, 3.0, 2)).unwrap();
+        let quiet = LoudnessMeter::measure(&sine(1000.0, 0.25, 3.0, 2)).unwrap();
+        assert!((loud - quiet - 6.02).abs() < 0.1, "{loud} vs {quiet}");
+    }
+
+    #[test]
+    fn silence_and_short_input_are_unmeasurable() {
+        assert_eq!(
+            LoudnessMeter::measure(&AudioData::from_raw_f32(vec![0.0; 96_000], 2)),
+            None
+        );
+        assert_eq!(LoudnessMeter::measure(&sine(1000.0, 0.5, 0.3, 2)), None);
+    }
+
+    // A quarter-rate sine sampled 45 degrees off its crests never has a sample
+    // at the true peak: the samples sit 3dB low, the reconstruction doesn't.
+    #[test]
+    fn true_peak_catches_inter_sample_peaks() {
+        let samples = (0..AudioData::SAMPLE_RATE as usize)
+            .map(|i| {
+                let phase = std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4;
+                0.5 * phase.sin()
+            })
+            .collect::<Vec<_>>();
+        let sample_peak = samples.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+
+        let mut meter = LoudnessMeter::new(1);
+        meter.push_interleaved(&samples);
+        let true_peak = meter.true_peak_dbtp().unwrap();
+
+        assert!((20.0 * sample_peak.log10() + 9.03).abs() < 0.1);
+        assert!((true_peak + 6.02).abs() < 0.3, "{true_peak}");
+        assert_eq!(LoudnessMeter::new(2).true_peak_dbtp(), None);
+    }
+
+    #[test]
+    fn relative_gate_ignores_quiet_passages() {
+        let mut samples = sine(1000.0, 0.5, 3.0, 1).samples().to_vec();
+        samples.extend(sine(1000.0, 0.005, 3.0, 1).samples());
+        let gated = LoudnessMeter::measure(&AudioData::from_raw_f32(samples, 1)).unwrap();
+        let loud_only = LoudnessMeter::measure(&sine(1000.0, 0.5, 3.0, 1)).unwrap();
+        assert!((gated - loud_only).abs() < 0.5, "{gated} vs {loud_only}");
+    }
+}
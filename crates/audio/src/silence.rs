@@ -0,0 +1,89 @@
+use crate::AudioData;
+
+/// Level is measured over 20ms windows.
+const WINDOW_FRAMES: usize = AudioData::SAMPLE_RATE as usize / 50;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SilenceSettings {
+    /// Windows whose RMS level, across all channels, is below this many dBFS are silent.
+    pub threshold_db: f64,
+    /// Shortest run of silent windows, in seconds, worth reporting.
+    pub min_duration: f64,
+}
+
+impl Default for SilenceSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            min_duration: 0.75,
+        }
+    }
+}
+
+/// Finds the stretches of `data` that stay below the silence threshold for at least
+/// `min_duration`, as `(start, end)` seconds from the start of the audio.
+pub fn detect_silences(data: &AudioData, settings: &SilenceSettings) -> Vec<(f64, f64)> {
+    let channels = data.channels().max(1) as usize;
+    let threshold = 10f64.powf(settings.threshold_db / 10.0);
+    let seconds = |frame: usize| frame as f64 / AudioData::SAMPLE_RATE as f64;
+
+    let mut silences = Vec::new();
+    let mut silent_since = None;
+    let mut frame = 0;
+
+    for window in data.samples().chunks(WINDOW_FRAMES * channels) {
+        let power =
+            window.iter().map(|s| (*s as f64).powi(2)).sum::<f64>() / window.len().max(1) as f64;
+
+        if power < threshold {
+            silent_since.get_or_insert(frame);
+        } else if let Some(start) = silent_since.take() {
+            silences.push((seconds(start), seconds(frame)));
+        }
+
+        frame += window.len() / channels;
+    }
+
+    if let Some(start) = silent_since {
+        silences.push((seconds(start), seconds(frame)));
+    }
+
+    silences.retain(|(start, end)| end - start >= settings.min_duration);
+    silences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signal(parts: &[(f32, f32)]) -> AudioData {
+        let mut samples = Vec::new();
+        for &(amplitude, seconds) in parts {
+            let frames = (AudioData::SAMPLE_RATE as f32 * seconds) as usize;
+            samples.extend((0..frames).flat_map(|i| {
+                let t = i as f32 / AudioData::SAMPLE_RATE as f32;
+                let value = amplitude * (std::f32::consts::TAU * 440.0 * t).sin();
+                [value, value]
+            }));
+        }
+        AudioData::from_raw_f32(samples, 2)
+    }
+
+    #[test]
+    fn finds_pauses_between_speech() {
+        let audio = signal(&[(0.5, 1.0), (0.001, 1.5), (0.5, 1.0), (0.0, 0.3), (0.5, 0.5)]);
+
+        let silences = detect_silences(&audio, &SilenceSettings::default());
+
+        assert_eq!(silences, vec![(1.0, 2.5)]);
+    }
+
+    #[test]
+    fn trailing_silence_runs_to_the_end() {
+        let audio = signal(&[(0.5, 0.5), (0.0, 1.0)]);
+
+        let silences = detect_silences(&audio, &SilenceSettings::default());
+
+        assert_eq!(silences, vec![(0.5, 1.5)]);
+    }
+}
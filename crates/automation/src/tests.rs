@@ -465,6 +465,22 @@ fn serialize_roundtrip() {
     assert_eq!(parsed.version, 1);
 }
 
+#[test]
+fn export_format_codec_variants_use_camel_case_tags() {
+    assert_eq!(
+        serde_json::to_value(ExportFormat::Hevc).unwrap(),
+        serde_json::json!("hevc")
+    );
+    assert_eq!(
+        serde_json::from_value::<ExportFormat>(serde_json::json!("av1")).unwrap(),
+        ExportFormat::Av1
+    );
+    assert_eq!(
+        serde_json::from_value::<ExportFormat>(serde_json::json!("webm")).unwrap(),
+        ExportFormat::Webm
+    );
+}
+
 #[test]
 fn serialize_all_condition_and_action_shapes_roundtrip() {
     use std::collections::HashMap;
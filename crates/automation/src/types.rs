@@ -190,6 +190,9 @@ pub enum ExportFormat {
     Mp4,
     Gif,
     Mov,
+    Hevc,
+    Av1,
+    Webm,
 }
 
 #[derive(Serialize, Deserialize, Type, Debug, Clone, Copy, PartialEq, Eq)]
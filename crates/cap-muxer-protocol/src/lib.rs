@@ -2,23 +2,49 @@ use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{self, Read, Write};
 
 pub const MAGIC: u32 = 0x434D5850;
-pub const PROTOCOL_VERSION: u16 = 1;
+/// Newest protocol version this crate speaks. Every frame header carries the version that
+/// introduced its kind, so v1 frames stay byte-identical and v1 peers keep working.
+pub const PROTOCOL_VERSION: u16 = 2;
+/// Oldest protocol version this crate still reads and writes.
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
 
+pub const FRAME_KIND_HELLO: u8 = 0x01;
+pub const FRAME_KIND_HELLO_ACK: u8 = 0x02;
 pub const FRAME_KIND_INIT_VIDEO: u8 = 0x10;
 pub const FRAME_KIND_INIT_AUDIO: u8 = 0x11;
+pub const FRAME_KIND_INIT_STREAM: u8 = 0x12;
 pub const FRAME_KIND_START: u8 = 0x20;
 pub const FRAME_KIND_PACKET: u8 = 0x30;
 pub const FRAME_KIND_FINISH: u8 = 0x40;
 pub const FRAME_KIND_ABORT: u8 = 0x41;
+pub const FRAME_KIND_METADATA: u8 = 0x50;
+
+/// `InitStream` frames may declare any mix of up to [`MAX_STREAMS`] streams.
+pub const FEATURE_INIT_STREAM: u64 = 1 << 0;
+/// The title and language of an `InitStream` are written to the output.
+pub const FEATURE_STREAM_METADATA: u64 = 1 << 1;
+/// `InitStream` frames may declare data streams.
+pub const FEATURE_DATA_STREAMS: u64 = 1 << 2;
+/// `Metadata` frames carrying container tags and chapters are accepted.
+pub const FEATURE_METADATA: u64 = 1 << 3;
 
 pub const STREAM_INDEX_VIDEO: u8 = 0;
+/// Stream index of the first audio track. Each `InitAudio` frame sent before `Start` declares
+/// the next track, whose packets use the following index (`STREAM_INDEX_AUDIO + track`).
 pub const STREAM_INDEX_AUDIO: u8 = 1;
+pub const MAX_AUDIO_TRACKS: u8 = 8;
+/// Most streams a v2 session may declare with `InitStream`.
+pub const MAX_STREAMS: usize = 32;
 
 pub const PACKET_FLAG_KEYFRAME: u8 = 0x01;
 pub const PACKET_FLAG_DISCARD: u8 = 0x02;
 
 pub const MAX_PAYLOAD_BYTES: u32 = 16 * 1024 * 1024;
 
+const MEDIA_KIND_VIDEO: u8 = 0;
+const MEDIA_KIND_AUDIO: u8 = 1;
+const MEDIA_KIND_DATA: u8 = 2;
+
 #[derive(thiserror::Error, Debug)]
 pub enum ProtocolError {
     #[error("io: {0}")]
@@ -29,6 +55,17 @@ pub enum ProtocolError {
     UnsupportedVersion(u16),
     #[error("unknown frame kind {0:#x}")]
     UnknownKind(u8),
+    #[error("frame kind {kind:#x} needs protocol v{required}, header says v{version}")]
+    KindNeedsVersion {
+        kind: u8,
+        required: u16,
+        version: u16,
+    },
+    #[error("no common protocol version: we speak v{ours:?}, peer speaks v{theirs:?}")]
+    NoCommonVersion {
+        ours: (u16, u16),
+        theirs: (u16, u16),
+    },
     #[error("payload size {0} exceeds maximum {1}")]
     PayloadTooLarge(u32, u32),
     #[error("crc mismatch: computed {computed:#x}, received {received:#x}")]
@@ -39,17 +76,39 @@ pub enum ProtocolError {
     Invalid(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Frame {
+    Hello(Hello),
+    HelloAck(HelloAck),
     InitVideo(InitVideo),
     InitAudio(InitAudio),
+    InitStream(InitStream),
     Start(StartParams),
     Packet(Packet),
+    Metadata(Metadata),
     Finish,
     Abort(String),
 }
 
-#[derive(Debug, Clone)]
+/// First frame of a v2 session, sent by the parent: the versions and features it can use.
+///
+/// A muxer that answers with [`HelloAck`] speaks the negotiated version from then on. One that
+/// predates the handshake rejects the frame and exits, and the parent falls back to v1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hello {
+    pub min_version: u16,
+    pub max_version: u16,
+    pub features: u64,
+}
+
+/// The muxer's answer to [`Hello`]: the version and features both sides will use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HelloAck {
+    pub version: u16,
+    pub features: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct InitVideo {
     pub codec: String,
     pub width: u32,
@@ -62,7 +121,7 @@ pub struct InitVideo {
     pub segment_duration_ms: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct InitAudio {
     pub codec: String,
     pub sample_rate: u32,
@@ -71,16 +130,50 @@ pub struct InitAudio {
     pub time_base_num: i32,
     pub time_base_den: i32,
     pub extradata: Vec<u8>,
+    /// Track title written to the output (e.g. "Microphone"). Encoded after the v1 fields only
+    /// when set, so unlabelled tracks stay byte-identical to what older muxers read.
+    pub label: Option<String>,
+}
+
+/// A timed data stream, e.g. `bin_data` or a text codec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitData {
+    pub codec: String,
+    pub time_base_num: i32,
+    pub time_base_den: i32,
+    pub extradata: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamMedia {
+    Video(InitVideo),
+    Audio(InitAudio),
+    Data(InitData),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamMetadata {
+    pub title: Option<String>,
+    /// ISO 639-2 language code, e.g. "eng".
+    pub language: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// Declares one stream of a v2 session; its packets use `stream_index`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitStream {
+    pub stream_index: u8,
+    pub media: StreamMedia,
+    pub metadata: StreamMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct StartParams {
     pub output_directory: String,
     pub init_segment_name: String,
     pub media_segment_pattern: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Packet {
     pub stream_index: u8,
     pub pts: i64,
@@ -90,17 +183,112 @@ pub struct Packet {
     pub data: Vec<u8>,
 }
 
+/// Container tags and chapters. May be sent before or after `Start`; later frames add to the
+/// earlier ones.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub tags: Vec<(String, String)>,
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub title: String,
+}
+
+/// Packet stream index for the `track`th audio track, or `None` past [`MAX_AUDIO_TRACKS`].
+pub fn audio_stream_index(track: u8) -> Option<u8> {
+    (track < MAX_AUDIO_TRACKS).then(|| STREAM_INDEX_AUDIO + track)
+}
+
+/// The audio track a packet stream index refers to, or `None` for video and unknown indices.
+pub fn audio_track(stream_index: u8) -> Option<u8> {
+    stream_index
+        .checked_sub(STREAM_INDEX_AUDIO)
+        .filter(|track| *track < MAX_AUDIO_TRACKS)
+}
+
+impl Hello {
+    /// Everything this crate can speak, offering `features`.
+    pub fn new(features: u64) -> Self {
+        Self {
+            min_version: MIN_PROTOCOL_VERSION,
+            max_version: PROTOCOL_VERSION,
+            features,
+        }
+    }
+
+    /// Picks the highest version both sides speak and the features both offer. Features only
+    /// exist from v2, so a v1 session never has any.
+    pub fn negotiate(&self, peer: &Hello) -> Result<HelloAck, ProtocolError> {
+        let version = self.max_version.min(peer.max_version);
+        if version < self.min_version.max(peer.min_version) {
+            return Err(ProtocolError::NoCommonVersion {
+                ours: (self.min_version, self.max_version),
+                theirs: (peer.min_version, peer.max_version),
+            });
+        }
+
+        Ok(HelloAck {
+            version,
+            features: if version >= 2 {
+                self.features & peer.features
+            } else {
+                0
+            },
+        })
+    }
+}
+
+impl HelloAck {
+    /// The session with a muxer that predates the handshake.
+    pub const V1: Self = Self {
+        version: 1,
+        features: 0,
+    };
+
+    pub fn supports(&self, feature: u64) -> bool {
+        self.features & feature == feature
+    }
+}
+
 impl Frame {
     pub fn kind(&self) -> u8 {
         match self {
+            Frame::Hello(_) => FRAME_KIND_HELLO,
+            Frame::HelloAck(_) => FRAME_KIND_HELLO_ACK,
             Frame::InitVideo(_) => FRAME_KIND_INIT_VIDEO,
             Frame::InitAudio(_) => FRAME_KIND_INIT_AUDIO,
+            Frame::InitStream(_) => FRAME_KIND_INIT_STREAM,
             Frame::Start(_) => FRAME_KIND_START,
             Frame::Packet(_) => FRAME_KIND_PACKET,
+            Frame::Metadata(_) => FRAME_KIND_METADATA,
             Frame::Finish => FRAME_KIND_FINISH,
             Frame::Abort(_) => FRAME_KIND_ABORT,
         }
     }
+
+    /// The protocol version that introduced this frame, which is what its header carries.
+    pub fn version(&self) -> u16 {
+        kind_version(self.kind()).unwrap_or(PROTOCOL_VERSION)
+    }
+}
+
+fn kind_version(kind: u8) -> Option<u16> {
+    match kind {
+        FRAME_KIND_INIT_VIDEO
+        | FRAME_KIND_INIT_AUDIO
+        | FRAME_KIND_START
+        | FRAME_KIND_PACKET
+        | FRAME_KIND_FINISH
+        | FRAME_KIND_ABORT => Some(1),
+        FRAME_KIND_HELLO | FRAME_KIND_HELLO_ACK | FRAME_KIND_INIT_STREAM | FRAME_KIND_METADATA => {
+            Some(2)
+        }
+        _ => None,
+    }
 }
 
 fn write_string<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
@@ -110,14 +298,28 @@ fn write_string<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
     Ok(())
 }
 
-fn read_string<R: Read>(r: &mut R) -> Result<String, ProtocolError> {
-    let len = r.read_u32::<LittleEndian>()?;
-    if len > MAX_PAYLOAD_BYTES {
-        return Err(ProtocolError::PayloadTooLarge(len, MAX_PAYLOAD_BYTES));
+fn read_string(r: &mut &[u8]) -> Result<String, ProtocolError> {
+    Ok(String::from_utf8(read_bytes(r)?)?)
+}
+
+fn write_optional_string<W: Write>(w: &mut W, s: Option<&str>) -> io::Result<()> {
+    match s {
+        Some(s) => {
+            w.write_u8(1)?;
+            write_string(w, s)
+        }
+        None => w.write_u8(0),
+    }
+}
+
+fn read_optional_string(r: &mut &[u8]) -> Result<Option<String>, ProtocolError> {
+    match r.read_u8()? {
+        0 => Ok(None),
+        1 => Ok(Some(read_string(r)?)),
+        other => Err(ProtocolError::Invalid(format!(
+            "optional string flag {other}"
+        ))),
     }
-    let mut buf = vec![0u8; len as usize];
-    r.read_exact(&mut buf)?;
-    Ok(String::from_utf8(buf)?)
 }
 
 fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
@@ -126,39 +328,138 @@ fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
     Ok(())
 }
 
-fn read_bytes<R: Read>(r: &mut R) -> Result<Vec<u8>, ProtocolError> {
+fn read_bytes(r: &mut &[u8]) -> Result<Vec<u8>, ProtocolError> {
     let len = r.read_u32::<LittleEndian>()?;
     if len > MAX_PAYLOAD_BYTES {
         return Err(ProtocolError::PayloadTooLarge(len, MAX_PAYLOAD_BYTES));
     }
-    let mut buf = vec![0u8; len as usize];
-    r.read_exact(&mut buf)?;
-    Ok(buf)
+    // Checked against what is left of the body so a corrupt length can't force a large allocation.
+    if len as usize > r.len() {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let (bytes, rest) = r.split_at(len as usize);
+    *r = rest;
+    Ok(bytes.to_vec())
+}
+
+fn encode_init_video(body: &mut Vec<u8>, init: &InitVideo) {
+    write_string(body, &init.codec).unwrap();
+    body.write_u32::<LittleEndian>(init.width).unwrap();
+    body.write_u32::<LittleEndian>(init.height).unwrap();
+    body.write_i32::<LittleEndian>(init.frame_rate_num).unwrap();
+    body.write_i32::<LittleEndian>(init.frame_rate_den).unwrap();
+    body.write_i32::<LittleEndian>(init.time_base_num).unwrap();
+    body.write_i32::<LittleEndian>(init.time_base_den).unwrap();
+    write_bytes(body, &init.extradata).unwrap();
+    body.write_u32::<LittleEndian>(init.segment_duration_ms)
+        .unwrap();
+}
+
+fn decode_init_video(r: &mut &[u8]) -> Result<InitVideo, ProtocolError> {
+    Ok(InitVideo {
+        codec: read_string(r)?,
+        width: r.read_u32::<LittleEndian>()?,
+        height: r.read_u32::<LittleEndian>()?,
+        frame_rate_num: r.read_i32::<LittleEndian>()?,
+        frame_rate_den: r.read_i32::<LittleEndian>()?,
+        time_base_num: r.read_i32::<LittleEndian>()?,
+        time_base_den: r.read_i32::<LittleEndian>()?,
+        extradata: read_bytes(r)?,
+        segment_duration_ms: r.read_u32::<LittleEndian>()?,
+    })
+}
+
+fn encode_init_audio(body: &mut Vec<u8>, init: &InitAudio) {
+    write_string(body, &init.codec).unwrap();
+    body.write_u32::<LittleEndian>(init.sample_rate).unwrap();
+    body.write_u16::<LittleEndian>(init.channels).unwrap();
+    write_string(body, &init.sample_format).unwrap();
+    body.write_i32::<LittleEndian>(init.time_base_num).unwrap();
+    body.write_i32::<LittleEndian>(init.time_base_den).unwrap();
+    write_bytes(body, &init.extradata).unwrap();
+    if let Some(label) = &init.label {
+        write_string(body, label).unwrap();
+    }
+}
+
+fn decode_init_audio(r: &mut &[u8]) -> Result<InitAudio, ProtocolError> {
+    let codec = read_string(r)?;
+    let sample_rate = r.read_u32::<LittleEndian>()?;
+    let channels = r.read_u16::<LittleEndian>()?;
+    let sample_format = read_string(r)?;
+    let time_base_num = r.read_i32::<LittleEndian>()?;
+    let time_base_den = r.read_i32::<LittleEndian>()?;
+    let extradata = read_bytes(r)?;
+    let label = if r.is_empty() {
+        None
+    } else {
+        Some(read_string(r)?)
+    };
+    Ok(InitAudio {
+        codec,
+        sample_rate,
+        channels,
+        sample_format,
+        time_base_num,
+        time_base_den,
+        extradata,
+        label,
+    })
+}
+
+fn encode_init_data(body: &mut Vec<u8>, init: &InitData) {
+    write_string(body, &init.codec).unwrap();
+    body.write_i32::<LittleEndian>(init.time_base_num).unwrap();
+    body.write_i32::<LittleEndian>(init.time_base_den).unwrap();
+    write_bytes(body, &init.extradata).unwrap();
+}
+
+fn decode_init_data(r: &mut &[u8]) -> Result<InitData, ProtocolError> {
+    Ok(InitData {
+        codec: read_string(r)?,
+        time_base_num: r.read_i32::<LittleEndian>()?,
+        time_base_den: r.read_i32::<LittleEndian>()?,
+        extradata: read_bytes(r)?,
+    })
 }
 
 fn encode_body(frame: &Frame) -> Vec<u8> {
     let mut body = Vec::new();
     match frame {
-        Frame::InitVideo(init) => {
-            write_string(&mut body, &init.codec).unwrap();
-            body.write_u32::<LittleEndian>(init.width).unwrap();
-            body.write_u32::<LittleEndian>(init.height).unwrap();
-            body.write_i32::<LittleEndian>(init.frame_rate_num).unwrap();
-            body.write_i32::<LittleEndian>(init.frame_rate_den).unwrap();
-            body.write_i32::<LittleEndian>(init.time_base_num).unwrap();
-            body.write_i32::<LittleEndian>(init.time_base_den).unwrap();
-            write_bytes(&mut body, &init.extradata).unwrap();
-            body.write_u32::<LittleEndian>(init.segment_duration_ms)
-                .unwrap();
+        Frame::Hello(hello) => {
+            body.write_u16::<LittleEndian>(hello.min_version).unwrap();
+            body.write_u16::<LittleEndian>(hello.max_version).unwrap();
+            body.write_u64::<LittleEndian>(hello.features).unwrap();
+        }
+        Frame::HelloAck(ack) => {
+            body.write_u16::<LittleEndian>(ack.version).unwrap();
+            body.write_u64::<LittleEndian>(ack.features).unwrap();
         }
-        Frame::InitAudio(init) => {
-            write_string(&mut body, &init.codec).unwrap();
-            body.write_u32::<LittleEndian>(init.sample_rate).unwrap();
-            body.write_u16::<LittleEndian>(init.channels).unwrap();
-            write_string(&mut body, &init.sample_format).unwrap();
-            body.write_i32::<LittleEndian>(init.time_base_num).unwrap();
-            body.write_i32::<LittleEndian>(init.time_base_den).unwrap();
-            write_bytes(&mut body, &init.extradata).unwrap();
+        Frame::InitVideo(init) => encode_init_video(&mut body, init),
+        Frame::InitAudio(init) => encode_init_audio(&mut body, init),
+        Frame::InitStream(init) => {
+            body.write_u8(init.stream_index).unwrap();
+            // The media body is length-prefixed so its own optional tail can't run into the
+            // metadata that follows it.
+            let mut media = Vec::new();
+            let media_kind = match &init.media {
+                StreamMedia::Video(video) => {
+                    encode_init_video(&mut media, video);
+                    MEDIA_KIND_VIDEO
+                }
+                StreamMedia::Audio(audio) => {
+                    encode_init_audio(&mut media, audio);
+                    MEDIA_KIND_AUDIO
+                }
+                StreamMedia::Data(data) => {
+                    encode_init_data(&mut media, data);
+                    MEDIA_KIND_DATA
+                }
+            };
+            body.write_u8(media_kind).unwrap();
+            write_bytes(&mut body, &media).unwrap();
+            write_optional_string(&mut body, init.metadata.title.as_deref()).unwrap();
+            write_optional_string(&mut body, init.metadata.language.as_deref()).unwrap();
         }
         Frame::Start(params) => {
             write_string(&mut body, &params.output_directory).unwrap();
@@ -174,6 +475,21 @@ fn encode_body(frame: &Frame) -> Vec<u8> {
             body.write_u64::<LittleEndian>(p.duration).unwrap();
             write_bytes(&mut body, &p.data).unwrap();
         }
+        Frame::Metadata(metadata) => {
+            body.write_u32::<LittleEndian>(metadata.tags.len() as u32)
+                .unwrap();
+            for (key, value) in &metadata.tags {
+                write_string(&mut body, key).unwrap();
+                write_string(&mut body, value).unwrap();
+            }
+            body.write_u32::<LittleEndian>(metadata.chapters.len() as u32)
+                .unwrap();
+            for chapter in &metadata.chapters {
+                body.write_u64::<LittleEndian>(chapter.start_ms).unwrap();
+                body.write_u64::<LittleEndian>(chapter.end_ms).unwrap();
+                write_string(&mut body, &chapter.title).unwrap();
+            }
+        }
         Frame::Finish => {}
         Frame::Abort(reason) => {
             write_string(&mut body, reason).unwrap();
@@ -182,6 +498,96 @@ fn encode_body(frame: &Frame) -> Vec<u8> {
     body
 }
 
+fn decode_body(kind: u8, mut body_reader: &[u8]) -> Result<Frame, ProtocolError> {
+    let r = &mut body_reader;
+    match kind {
+        FRAME_KIND_HELLO => Ok(Frame::Hello(Hello {
+            min_version: r.read_u16::<LittleEndian>()?,
+            max_version: r.read_u16::<LittleEndian>()?,
+            features: r.read_u64::<LittleEndian>()?,
+        })),
+        FRAME_KIND_HELLO_ACK => Ok(Frame::HelloAck(HelloAck {
+            version: r.read_u16::<LittleEndian>()?,
+            features: r.read_u64::<LittleEndian>()?,
+        })),
+        FRAME_KIND_INIT_VIDEO => Ok(Frame::InitVideo(decode_init_video(r)?)),
+        FRAME_KIND_INIT_AUDIO => Ok(Frame::InitAudio(decode_init_audio(r)?)),
+        FRAME_KIND_INIT_STREAM => {
+            let stream_index = r.read_u8()?;
+            let media_kind = r.read_u8()?;
+            let media_body = read_bytes(r)?;
+            let media = &mut &media_body[..];
+            let media = match media_kind {
+                MEDIA_KIND_VIDEO => StreamMedia::Video(decode_init_video(media)?),
+                MEDIA_KIND_AUDIO => StreamMedia::Audio(decode_init_audio(media)?),
+                MEDIA_KIND_DATA => StreamMedia::Data(decode_init_data(media)?),
+                other => {
+                    return Err(ProtocolError::Invalid(format!(
+                        "unknown media kind {other}"
+                    )));
+                }
+            };
+            let metadata = StreamMetadata {
+                title: read_optional_string(r)?,
+                language: read_optional_string(r)?,
+            };
+            Ok(Frame::InitStream(InitStream {
+                stream_index,
+                media,
+                metadata,
+            }))
+        }
+        FRAME_KIND_START => {
+            let output_directory = read_string(r)?;
+            let init_segment_name = read_string(r)?;
+            let media_segment_pattern = read_string(r)?;
+            Ok(Frame::Start(StartParams {
+                output_directory,
+                init_segment_name,
+                media_segment_pattern,
+            }))
+        }
+        FRAME_KIND_PACKET => {
+            let stream_index = r.read_u8()?;
+            let flags = r.read_u8()?;
+            let _reserved = r.read_u16::<LittleEndian>()?;
+            let pts = r.read_i64::<LittleEndian>()?;
+            let dts = r.read_i64::<LittleEndian>()?;
+            let duration = r.read_u64::<LittleEndian>()?;
+            let data = read_bytes(r)?;
+            Ok(Frame::Packet(Packet {
+                stream_index,
+                pts,
+                dts,
+                duration,
+                flags,
+                data,
+            }))
+        }
+        FRAME_KIND_METADATA => {
+            let mut metadata = Metadata::default();
+            // Counts are not trusted for pre-allocation; each entry must actually be in the body.
+            for _ in 0..r.read_u32::<LittleEndian>()? {
+                metadata.tags.push((read_string(r)?, read_string(r)?));
+            }
+            for _ in 0..r.read_u32::<LittleEndian>()? {
+                metadata.chapters.push(Chapter {
+                    start_ms: r.read_u64::<LittleEndian>()?,
+                    end_ms: r.read_u64::<LittleEndian>()?,
+                    title: read_string(r)?,
+                });
+            }
+            Ok(Frame::Metadata(metadata))
+        }
+        FRAME_KIND_FINISH => Ok(Frame::Finish),
+        FRAME_KIND_ABORT => {
+            let reason = read_string(r)?;
+            Ok(Frame::Abort(reason))
+        }
+        other => Err(ProtocolError::UnknownKind(other)),
+    }
+}
+
 pub fn write_frame<W: Write>(w: &mut W, frame: &Frame) -> Result<(), ProtocolError> {
     let body = encode_body(frame);
     if body.len() > MAX_PAYLOAD_BYTES as usize {
@@ -197,7 +603,7 @@ pub fn write_frame<W: Write>(w: &mut W, frame: &Frame) -> Result<(), ProtocolErr
     let crc = hasher.finalize();
 
     w.write_u32::<LittleEndian>(MAGIC)?;
-    w.write_u16::<LittleEndian>(PROTOCOL_VERSION)?;
+    w.write_u16::<LittleEndian>(frame.version())?;
     w.write_u8(frame.kind())?;
     w.write_u8(0)?;
     w.write_u32::<LittleEndian>(body.len() as u32)?;
@@ -212,7 +618,7 @@ pub fn read_frame<R: Read>(r: &mut R) -> Result<Frame, ProtocolError> {
         return Err(ProtocolError::BadMagic(magic, MAGIC));
     }
     let version = r.read_u16::<LittleEndian>()?;
-    if version != PROTOCOL_VERSION {
+    if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version) {
         return Err(ProtocolError::UnsupportedVersion(version));
     }
     let kind = r.read_u8()?;
@@ -238,82 +644,16 @@ pub fn read_frame<R: Read>(r: &mut R) -> Result<Frame, ProtocolError> {
         });
     }
 
-    let mut body_reader = &body[..];
-    match kind {
-        FRAME_KIND_INIT_VIDEO => {
-            let codec = read_string(&mut body_reader)?;
-            let width = body_reader.read_u32::<LittleEndian>()?;
-            let height = body_reader.read_u32::<LittleEndian>()?;
-            let frame_rate_num = body_reader.read_i32::<LittleEndian>()?;
-            let frame_rate_den = body_reader.read_i32::<LittleEndian>()?;
-            let time_base_num = body_reader.read_i32::<LittleEndian>()?;
-            let time_base_den = body_reader.read_i32::<LittleEndian>()?;
-            let extradata = read_bytes(&mut body_reader)?;
-            let segment_duration_ms = body_reader.read_u32::<LittleEndian>()?;
-            Ok(Frame::InitVideo(InitVideo {
-                codec,
-                width,
-                height,
-                frame_rate_num,
-                frame_rate_den,
-                time_base_num,
-                time_base_den,
-                extradata,
-                segment_duration_ms,
-            }))
-        }
-        FRAME_KIND_INIT_AUDIO => {
-            let codec = read_string(&mut body_reader)?;
-            let sample_rate = body_reader.read_u32::<LittleEndian>()?;
-            let channels = body_reader.read_u16::<LittleEndian>()?;
-            let sample_format = read_string(&mut body_reader)?;
-            let time_base_num = body_reader.read_i32::<LittleEndian>()?;
-            let time_base_den = body_reader.read_i32::<LittleEndian>()?;
-            let extradata = read_bytes(&mut body_reader)?;
-            Ok(Frame::InitAudio(InitAudio {
-                codec,
-                sample_rate,
-                channels,
-                sample_format,
-                time_base_num,
-                time_base_den,
-                extradata,
-            }))
-        }
-        FRAME_KIND_START => {
-            let output_directory = read_string(&mut body_reader)?;
-            let init_segment_name = read_string(&mut body_reader)?;
-            let media_segment_pattern = read_string(&mut body_reader)?;
-            Ok(Frame::Start(StartParams {
-                output_directory,
-                init_segment_name,
-                media_segment_pattern,
-            }))
-        }
-        FRAME_KIND_PACKET => {
-            let stream_index = body_reader.read_u8()?;
-            let flags = body_reader.read_u8()?;
-            let _reserved = body_reader.read_u16::<LittleEndian>()?;
-            let pts = body_reader.read_i64::<LittleEndian>()?;
-            let dts = body_reader.read_i64::<LittleEndian>()?;
-            let duration = body_reader.read_u64::<LittleEndian>()?;
-            let data = read_bytes(&mut body_reader)?;
-            Ok(Frame::Packet(Packet {
-                stream_index,
-                pts,
-                dts,
-                duration,
-                flags,
-                data,
-            }))
-        }
-        FRAME_KIND_FINISH => Ok(Frame::Finish),
-        FRAME_KIND_ABORT => {
-            let reason = read_string(&mut body_reader)?;
-            Ok(Frame::Abort(reason))
-        }
-        other => Err(ProtocolError::UnknownKind(other)),
+    let required = kind_version(kind).ok_or(ProtocolError::UnknownKind(kind))?;
+    if version < required {
+        return Err(ProtocolError::KindNeedsVersion {
+            kind,
+            required,
+            version,
+        });
     }
+
+    decode_body(kind, &body)
 }
 
 #[cfg(test)]
@@ -373,6 +713,69 @@ mod tests {
         }
     }
 
+    fn init_audio(label: Option<&str>) -> Frame {
+        Frame::InitAudio(InitAudio {
+            codec: "aac".to_string(),
+            sample_rate: 48_000,
+            channels: 2,
+            sample_format: "fltp".to_string(),
+            time_base_num: 1,
+            time_base_den: 48_000,
+            extradata: vec![0x11, 0x90],
+            label: label.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn round_trips_labelled_audio_tracks() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &init_audio(Some("Microphone"))).unwrap();
+        write_frame(&mut buf, &init_audio(None)).unwrap();
+        let mut cursor = Cursor::new(&buf);
+        match (
+            read_frame(&mut cursor).unwrap(),
+            read_frame(&mut cursor).unwrap(),
+        ) {
+            (Frame::InitAudio(mic), Frame::InitAudio(system)) => {
+                assert_eq!(mic.label.as_deref(), Some("Microphone"));
+                assert_eq!(mic.extradata, vec![0x11, 0x90]);
+                assert_eq!(system.label, None);
+            }
+            other => panic!("expected two InitAudio frames, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unlabelled_audio_init_keeps_v1_body() {
+        let Frame::InitAudio(init) = init_audio(None) else {
+            unreachable!()
+        };
+        let mut v1_body = Vec::new();
+        write_string(&mut v1_body, &init.codec).unwrap();
+        v1_body.write_u32::<LittleEndian>(init.sample_rate).unwrap();
+        v1_body.write_u16::<LittleEndian>(init.channels).unwrap();
+        write_string(&mut v1_body, &init.sample_format).unwrap();
+        v1_body
+            .write_i32::<LittleEndian>(init.time_base_num)
+            .unwrap();
+        v1_body
+            .write_i32::<LittleEndian>(init.time_base_den)
+            .unwrap();
+        write_bytes(&mut v1_body, &init.extradata).unwrap();
+
+        assert_eq!(encode_body(&Frame::InitAudio(init)), v1_body);
+    }
+
+    #[test]
+    fn maps_audio_tracks_to_stream_indices() {
+        assert_eq!(audio_stream_index(0), Some(STREAM_INDEX_AUDIO));
+        assert_eq!(audio_stream_index(2), Some(STREAM_INDEX_AUDIO + 2));
+        assert_eq!(audio_stream_index(MAX_AUDIO_TRACKS), None);
+        assert_eq!(audio_track(STREAM_INDEX_VIDEO), None);
+        assert_eq!(audio_track(STREAM_INDEX_AUDIO + 1), Some(1));
+        assert_eq!(audio_track(STREAM_INDEX_AUDIO + MAX_AUDIO_TRACKS), None);
+    }
+
     #[test]
     fn round_trips_start_and_finish() {
         let start = Frame::Start(StartParams {
@@ -442,4 +845,177 @@ mod tests {
             other => panic!("expected Abort, got {other:?}"),
         }
     }
+
+    fn init_video() -> InitVideo {
+        InitVideo {
+            codec: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            frame_rate_num: 30,
+            frame_rate_den: 1,
+            time_base_num: 1,
+            time_base_den: 90000,
+            extradata: vec![0x01, 0x64],
+            segment_duration_ms: 3000,
+        }
+    }
+
+    fn round_trip(frame: &Frame) -> Frame {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, frame).unwrap();
+        read_frame(&mut Cursor::new(&buf)).unwrap()
+    }
+
+    #[test]
+    fn v1_frames_keep_v1_header() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &Frame::InitVideo(init_video())).unwrap();
+        assert_eq!(u16::from_le_bytes([buf[4], buf[5]]), 1);
+
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &Frame::Hello(Hello::new(FEATURE_INIT_STREAM))).unwrap();
+        assert_eq!(u16::from_le_bytes([buf[4], buf[5]]), 2);
+    }
+
+    #[test]
+    fn rejects_v2_kind_in_v1_header() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &Frame::Metadata(Metadata::default())).unwrap();
+        buf[4..6].copy_from_slice(&1u16.to_le_bytes());
+        match read_frame(&mut Cursor::new(&buf)) {
+            Err(ProtocolError::KindNeedsVersion {
+                kind: FRAME_KIND_METADATA,
+                required: 2,
+                version: 1,
+            }) => {}
+            other => panic!("expected KindNeedsVersion, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_future_version() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, &Frame::Finish).unwrap();
+        buf[4..6].copy_from_slice(&(PROTOCOL_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            read_frame(&mut Cursor::new(&buf)),
+            Err(ProtocolError::UnsupportedVersion(v)) if v == PROTOCOL_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn negotiates_highest_common_version_and_shared_features() {
+        let ours = Hello::new(FEATURE_INIT_STREAM | FEATURE_METADATA);
+        let peer = Hello {
+            min_version: 1,
+            max_version: 7,
+            features: FEATURE_INIT_STREAM | FEATURE_DATA_STREAMS | (1 << 40),
+        };
+        let ack = ours.negotiate(&peer).unwrap();
+        assert_eq!(ack.version, PROTOCOL_VERSION);
+        assert_eq!(ack.features, FEATURE_INIT_STREAM);
+        assert!(ack.supports(FEATURE_INIT_STREAM));
+        assert!(!ack.supports(FEATURE_METADATA));
+
+        let v1_only = Hello {
+            min_version: 1,
+            max_version: 1,
+            features: FEATURE_INIT_STREAM,
+        };
+        assert_eq!(ours.negotiate(&v1_only).unwrap(), HelloAck::V1);
+
+        let too_new = Hello {
+            min_version: PROTOCOL_VERSION + 1,
+            max_version: PROTOCOL_VERSION + 3,
+            features: 0,
+        };
+        assert!(matches!(
+            ours.negotiate(&too_new),
+            Err(ProtocolError::NoCommonVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn round_trips_handshake() {
+        let hello = Frame::Hello(Hello::new(FEATURE_INIT_STREAM | FEATURE_METADATA));
+        assert_eq!(round_trip(&hello), hello);
+        let ack = Frame::HelloAck(HelloAck {
+            version: 2,
+            features: FEATURE_STREAM_METADATA,
+        });
+        assert_eq!(round_trip(&ack), ack);
+    }
+
+    #[test]
+    fn round_trips_init_streams_with_metadata() {
+        let Frame::InitAudio(audio) = init_audio(Some("Microphone")) else {
+            unreachable!()
+        };
+        let streams = [
+            InitStream {
+                stream_index: 0,
+                media: StreamMedia::Video(init_video()),
+                metadata: StreamMetadata::default(),
+            },
+            InitStream {
+                stream_index: 3,
+                media: StreamMedia::Audio(audio),
+                metadata: StreamMetadata {
+                    title: Some("Commentary".to_string()),
+                    language: Some("eng".to_string()),
+                },
+            },
+            InitStream {
+                stream_index: 9,
+                media: StreamMedia::Data(InitData {
+                    codec: "bin_data".to_string(),
+                    time_base_num: 1,
+                    time_base_den: 1000,
+                    extradata: Vec::new(),
+                }),
+                metadata: StreamMetadata {
+                    title: Some("Cursor".to_string()),
+                    language: None,
+                },
+            },
+        ];
+        for stream in streams {
+            let frame = Frame::InitStream(stream);
+            assert_eq!(round_trip(&frame), frame);
+        }
+    }
+
+    #[test]
+    fn round_trips_metadata_and_chapters() {
+        let frame = Frame::Metadata(Metadata {
+            tags: vec![
+                ("title".to_string(), "Standup".to_string()),
+                ("comment".to_string(), String::new()),
+            ],
+            chapters: vec![
+                Chapter {
+                    start_ms: 0,
+                    end_ms: 12_500,
+                    title: "Intro".to_string(),
+                },
+                Chapter {
+                    start_ms: 12_500,
+                    end_ms: 60_000,
+                    title: "Démo".to_string(),
+                },
+            ],
+        });
+        assert_eq!(round_trip(&frame), frame);
+    }
+
+    #[test]
+    fn rejects_lengths_past_end_of_body() {
+        let mut body = Vec::new();
+        body.write_u32::<LittleEndian>(1).unwrap();
+        body.write_u32::<LittleEndian>(MAX_PAYLOAD_BYTES).unwrap();
+        assert!(matches!(
+            decode_body(FRAME_KIND_METADATA, &body),
+            Err(ProtocolError::Io(_))
+        ));
+    }
 }
@@ -0,0 +1,281 @@
+//! Feeds `read_frame` corrupted, truncated and random input. The muxer reads frames straight
+//! from a pipe, so decoding must return an error for anything malformed and never panic or
+//! allocate based on an unchecked length.
+//!
+//! The inputs come from a fixed-seed generator so failures reproduce; set
+//! `CAP_MUXER_FUZZ_ITERATIONS` to run longer.
+
+use cap_muxer_protocol::*;
+use std::io::Cursor;
+
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn bytes_up_to(&mut self, max: usize) -> Vec<u8> {
+        let len = self.below(max);
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+
+    fn string(&mut self) -> String {
+        const CHARS: &[&str] = &["a", "Z", "0", " ", "é", "🎙", "\n"];
+        (0..self.below(12))
+            .map(|_| CHARS[self.below(CHARS.len())])
+            .collect()
+    }
+
+    fn optional_string(&mut self) -> Option<String> {
+        (self.below(2) == 0).then(|| self.string())
+    }
+}
+
+const KINDS: &[u8] = &[
+    FRAME_KIND_HELLO,
+    FRAME_KIND_HELLO_ACK,
+    FRAME_KIND_INIT_VIDEO,
+    FRAME_KIND_INIT_AUDIO,
+    FRAME_KIND_INIT_STREAM,
+    FRAME_KIND_START,
+    FRAME_KIND_PACKET,
+    FRAME_KIND_FINISH,
+    FRAME_KIND_ABORT,
+    FRAME_KIND_METADATA,
+];
+
+fn iterations() -> usize {
+    std::env::var("CAP_MUXER_FUZZ_ITERATIONS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5_000)
+}
+
+fn random_video(rng: &mut XorShift) -> InitVideo {
+    InitVideo {
+        codec: rng.string(),
+        width: rng.next() as u32,
+        height: rng.next() as u32,
+        frame_rate_num: rng.next() as i32,
+        frame_rate_den: rng.next() as i32,
+        time_base_num: rng.next() as i32,
+        time_base_den: rng.next() as i32,
+        extradata: rng.bytes_up_to(32),
+        segment_duration_ms: rng.next() as u32,
+    }
+}
+
+fn random_audio(rng: &mut XorShift) -> InitAudio {
+    InitAudio {
+        codec: rng.string(),
+        sample_rate: rng.next() as u32,
+        channels: rng.next() as u16,
+        sample_format: rng.string(),
+        time_base_num: rng.next() as i32,
+        time_base_den: rng.next() as i32,
+        extradata: rng.bytes_up_to(32),
+        label: rng.optional_string(),
+    }
+}
+
+fn random_frame(rng: &mut XorShift) -> Frame {
+    match rng.below(KINDS.len()) {
+        0 => Frame::Hello(Hello {
+            min_version: rng.next() as u16,
+            max_version: rng.next() as u16,
+            features: rng.next(),
+        }),
+        1 => Frame::HelloAck(HelloAck {
+            version: rng.next() as u16,
+            features: rng.next(),
+        }),
+        2 => Frame::InitVideo(random_video(rng)),
+        3 => Frame::InitAudio(random_audio(rng)),
+        4 => Frame::InitStream(InitStream {
+            stream_index: rng.next() as u8,
+            media: match rng.below(3) {
+                0 => StreamMedia::Video(random_video(rng)),
+                1 => StreamMedia::Audio(random_audio(rng)),
+                _ => StreamMedia::Data(InitData {
+                    codec: rng.string(),
+                    time_base_num: rng.next() as i32,
+                    time_base_den: rng.next() as i32,
+                    extradata: rng.bytes_up_to(32),
+                }),
+            },
+            metadata: StreamMetadata {
+                title: rng.optional_string(),
+                language: rng.optional_string(),
+            },
+        }),
+        5 => Frame::Start(StartParams {
+            output_directory: rng.string(),
+            init_segment_name: rng.string(),
+            media_segment_pattern: rng.string(),
+        }),
+        6 => Frame::Packet(Packet {
+            stream_index: rng.next() as u8,
+            pts: rng.next() as i64,
+            dts: rng.next() as i64,
+            duration: rng.next(),
+            flags: rng.next() as u8,
+            data: rng.bytes_up_to(256),
+        }),
+        7 => Frame::Finish,
+        8 => Frame::Abort(rng.string()),
+        _ => Frame::Metadata(Metadata {
+            tags: (0..rng.below(4))
+                .map(|_| (rng.string(), rng.string()))
+                .collect(),
+            chapters: (0..rng.below(4))
+                .map(|_| Chapter {
+                    start_ms: rng.next(),
+                    end_ms: rng.next(),
+                    title: rng.string(),
+                })
+                .collect(),
+        }),
+    }
+}
+
+fn encode(frame: &Frame) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_frame(&mut buf, frame).unwrap();
+    buf
+}
+
+/// A frame with a well-formed, CRC-valid header around `body`, so the body decoders see it.
+fn framed(version: u16, kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&[kind]);
+    hasher.update(&(body.len() as u32).to_le_bytes());
+    hasher.update(body);
+
+    let mut buf = Vec::with_capacity(16 + body.len());
+    buf.extend_from_slice(&MAGIC.to_le_bytes());
+    buf.extend_from_slice(&version.to_le_bytes());
+    buf.push(kind);
+    buf.push(0);
+    buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&hasher.finalize().to_le_bytes());
+    buf.extend_from_slice(body);
+    buf
+}
+
+/// Reads every frame in `bytes` until the first error, as the muxer's reader thread does.
+fn read_all(bytes: &[u8]) -> Vec<Frame> {
+    let mut cursor = Cursor::new(bytes);
+    let mut frames = Vec::new();
+    while let Ok(frame) = read_frame(&mut cursor) {
+        frames.push(frame);
+    }
+    frames
+}
+
+#[test]
+fn random_frames_round_trip() {
+    let mut rng = XorShift(0x5eed_0001);
+    for _ in 0..iterations() {
+        let frame = random_frame(&mut rng);
+        let decoded = read_frame(&mut Cursor::new(encode(&frame))).unwrap();
+        assert_eq!(decoded, frame);
+    }
+}
+
+#[test]
+fn concatenated_frames_decode_in_order() {
+    let mut rng = XorShift(0x5eed_0002);
+    for _ in 0..iterations() / 10 {
+        let frames: Vec<Frame> = (0..rng.below(8)).map(|_| random_frame(&mut rng)).collect();
+        let bytes: Vec<u8> = frames.iter().flat_map(encode).collect();
+        assert_eq!(read_all(&bytes), frames);
+    }
+}
+
+#[test]
+fn truncated_frames_error() {
+    let mut rng = XorShift(0x5eed_0003);
+    for _ in 0..iterations() {
+        let bytes = encode(&random_frame(&mut rng));
+        let cut = rng.below(bytes.len());
+        assert!(read_frame(&mut Cursor::new(&bytes[..cut])).is_err());
+    }
+}
+
+#[test]
+fn mutated_frames_never_panic() {
+    let mut rng = XorShift(0x5eed_0004);
+    for _ in 0..iterations() {
+        let mut bytes = encode(&random_frame(&mut rng));
+        for _ in 0..1 + rng.below(4) {
+            let at = rng.below(bytes.len());
+            match rng.below(3) {
+                0 => bytes[at] ^= 1 << rng.below(8),
+                1 => bytes[at] = rng.next() as u8,
+                _ => {
+                    bytes.insert(at, rng.next() as u8);
+                }
+            }
+        }
+        let _ = read_all(&bytes);
+    }
+}
+
+#[test]
+fn random_bodies_with_valid_header_never_panic() {
+    let mut rng = XorShift(0x5eed_0005);
+    for _ in 0..iterations() {
+        let kind = if rng.below(8) == 0 {
+            rng.next() as u8
+        } else {
+            KINDS[rng.below(KINDS.len())]
+        };
+        let version = MIN_PROTOCOL_VERSION + rng.below(2) as u16;
+        let body = rng.bytes_up_to(96);
+        let _ = read_all(&framed(version, kind, &body));
+    }
+}
+
+#[test]
+fn mutated_bodies_with_valid_header_never_panic() {
+    let mut rng = XorShift(0x5eed_0006);
+    for _ in 0..iterations() {
+        let frame = random_frame(&mut rng);
+        let bytes = encode(&frame);
+        let mut body = bytes[16..].to_vec();
+        if body.is_empty() {
+            continue;
+        }
+        let at = rng.below(body.len());
+        body[at] = rng.next() as u8;
+        if rng.below(2) == 0 {
+            body.truncate(rng.below(body.len()));
+        }
+        let _ = read_all(&framed(frame.version(), frame.kind(), &body));
+    }
+}
+
+#[test]
+fn huge_declared_lengths_error_without_allocating() {
+    // Every length and count field reads as u32::MAX; only the fixed-size frames decode.
+    let body = [0xff; 64];
+    for kind in KINDS {
+        let decoded = read_frame(&mut Cursor::new(framed(PROTOCOL_VERSION, *kind, &body)));
+        match *kind {
+            FRAME_KIND_HELLO | FRAME_KIND_HELLO_ACK | FRAME_KIND_FINISH => {
+                assert!(decoded.is_ok())
+            }
+            _ => assert!(decoded.is_err(), "kind {kind:#x} decoded {decoded:?}"),
+        }
+    }
+}
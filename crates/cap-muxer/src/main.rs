@@ -1,7 +1,9 @@
 use anyhow::{Context, Result, anyhow};
 use cap_muxer_protocol::{
-    Frame, InitAudio, InitVideo, PACKET_FLAG_KEYFRAME, Packet, ProtocolError, STREAM_INDEX_AUDIO,
-    STREAM_INDEX_VIDEO, StartParams, read_frame,
+    FEATURE_DATA_STREAMS, FEATURE_INIT_STREAM, FEATURE_METADATA, FEATURE_STREAM_METADATA, Frame,
+    Hello, HelloAck, InitAudio, InitStream, InitVideo, MAX_AUDIO_TRACKS, MAX_STREAMS, Metadata,
+    PACKET_FLAG_KEYFRAME, Packet, ProtocolError, STREAM_INDEX_VIDEO, StartParams, StreamMedia,
+    StreamMetadata, audio_stream_index, read_frame, write_frame,
 };
 use ffmpeg::{codec, format};
 use std::collections::VecDeque;
@@ -19,6 +21,10 @@ const EXIT_ABORT: u8 = 40;
 const EXIT_BAD_STATE: u8 = 50;
 pub const EXIT_DISK_FULL: u8 = 60;
 
+/// Features offered in the handshake. Data streams are left out because the DASH output can't
+/// carry them.
+const MUXER_FEATURES: u64 = FEATURE_INIT_STREAM | FEATURE_STREAM_METADATA | FEATURE_METADATA;
+
 fn main() -> ExitCode {
     init_tracing();
 
@@ -189,8 +195,25 @@ enum PopResult {
 fn frame_size_hint(frame: &Frame) -> usize {
     const HEADER_OVERHEAD: usize = 64;
     match frame {
+        Frame::Hello(_) | Frame::HelloAck(_) => HEADER_OVERHEAD,
         Frame::InitVideo(v) => HEADER_OVERHEAD + v.extradata.len() + v.codec.len(),
-        Frame::InitAudio(a) => HEADER_OVERHEAD + a.extradata.len() + a.codec.len(),
+        Frame::InitAudio(a) => {
+            HEADER_OVERHEAD
+                + a.extradata.len()
+                + a.codec.len()
+                + a.label.as_ref().map_or(0, String::len)
+        }
+        Frame::InitStream(s) => {
+            let media = match &s.media {
+                StreamMedia::Video(v) => v.extradata.len() + v.codec.len(),
+                StreamMedia::Audio(a) => a.extradata.len() + a.codec.len(),
+                StreamMedia::Data(d) => d.extradata.len() + d.codec.len(),
+            };
+            HEADER_OVERHEAD
+                + media
+                + s.metadata.title.as_ref().map_or(0, String::len)
+                + s.metadata.language.as_ref().map_or(0, String::len)
+        }
         Frame::Start(p) => {
             HEADER_OVERHEAD
                 + p.output_directory.len()
@@ -198,6 +221,11 @@ fn frame_size_hint(frame: &Frame) -> usize {
                 + p.media_segment_pattern.len()
         }
         Frame::Packet(p) => HEADER_OVERHEAD + p.data.len(),
+        Frame::Metadata(m) => {
+            HEADER_OVERHEAD
+                + m.tags.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
+                + m.chapters.iter().map(|c| 16 + c.title.len()).sum::<usize>()
+        }
         Frame::Finish => HEADER_OVERHEAD,
         Frame::Abort(reason) => HEADER_OVERHEAD + reason.len(),
     }
@@ -246,6 +274,12 @@ fn run() -> Result<(), MuxerError> {
         match queue.pop() {
             PopResult::Frame(frame) => match handle_frame(&mut state, frame) {
                 Ok(Control::Continue) => {}
+                Ok(Control::Reply(frame)) => {
+                    if let Err(e) = reply(&frame) {
+                        result = Err(e);
+                        break;
+                    }
+                }
                 Ok(Control::Finish) => {
                     break;
                 }
@@ -276,42 +310,85 @@ fn run() -> Result<(), MuxerError> {
     Ok(())
 }
 
+fn reply(frame: &Frame) -> Result<(), MuxerError> {
+    let mut stdout = io::stdout().lock();
+    write_frame(&mut stdout, frame)?;
+    stdout.flush().map_err(ProtocolError::from)?;
+    Ok(())
+}
+
 enum Control {
     Continue,
+    Reply(Frame),
     Finish,
 }
 
 #[derive(Default)]
 struct State {
-    init_video: Option<InitVideo>,
-    init_audio: Option<InitAudio>,
+    /// Negotiated by a leading `Hello`. Parents that open with `InitVideo` speak v1.
+    session: Option<HelloAck>,
+    frames_seen: u64,
+    /// Every declared stream in declaration order, legacy `InitVideo`/`InitAudio` included.
+    streams: Vec<InitStream>,
+    metadata: Metadata,
     started: bool,
     output: Option<OpenOutput>,
 }
 
 struct OpenOutput {
     ctx: format::context::Output,
-    video_stream_index: Option<usize>,
-    audio_stream_index: Option<usize>,
-    video_time_base: Option<ffmpeg::Rational>,
-    audio_time_base: Option<ffmpeg::Rational>,
+    tracks: Vec<TrackOutput>,
+    /// Position in `tracks` of the first video stream. Audio is held until its first keyframe.
+    primary_video: Option<usize>,
     packets_written_video: u64,
     packets_written_audio: u64,
-    video_keyframe_seen: bool,
     video_packets_dropped_pre_keyframe: u64,
     audio_packets_dropped_pre_video: u64,
-    pending_video_packet: Option<Packet>,
-    pending_audio_packet: Option<Packet>,
-    last_video_duration_input_tb: Option<i64>,
-    last_audio_duration_input_tb: Option<i64>,
+    chapters_written: i64,
     start_ts: Option<std::time::Instant>,
     _base_path: PathBuf,
 }
 
+struct TrackOutput {
+    protocol_index: u8,
+    stream_index: usize,
+    time_base: ffmpeg::Rational,
+    is_video: bool,
+    keyframe_seen: bool,
+    nominal_duration_input_tb: Option<i64>,
+    pending_packet: Option<Packet>,
+    last_duration_input_tb: Option<i64>,
+}
+
 impl State {
+    fn supports(&self, feature: u64) -> bool {
+        self.session
+            .is_some_and(|session| session.supports(feature))
+    }
+
+    fn declare_stream(&mut self, stream: InitStream) -> Result<(), MuxerError> {
+        if self.streams.len() >= MAX_STREAMS {
+            return Err(MuxerError::BadState(format!(
+                "more than {MAX_STREAMS} streams"
+            )));
+        }
+        if self
+            .streams
+            .iter()
+            .any(|s| s.stream_index == stream.stream_index)
+        {
+            return Err(MuxerError::BadState(format!(
+                "stream index {} declared twice",
+                stream.stream_index
+            )));
+        }
+        self.streams.push(stream);
+        Ok(())
+    }
+
     fn finish(&mut self) -> Result<(), MuxerError> {
         if let Some(out) = self.output.as_mut() {
-            flush_pending_packets(out, self.init_video.as_ref(), self.init_audio.as_ref())?;
+            flush_pending_packets(out)?;
         }
         if let Some(mut out) = self.output.take() {
             out.ctx.write_trailer().map_err(|e| {
@@ -340,19 +417,78 @@ impl State {
 }
 
 fn handle_frame(state: &mut State, frame: Frame) -> Result<Control, MuxerError> {
+    state.frames_seen += 1;
     match frame {
+        Frame::Hello(hello) => {
+            if state.frames_seen > 1 {
+                return Err(MuxerError::BadState(
+                    "hello is only valid as the first frame".to_string(),
+                ));
+            }
+            let ack = Hello::new(MUXER_FEATURES).negotiate(&hello)?;
+            tracing::info!(
+                version = ack.version,
+                features = format_args!("{:#x}", ack.features),
+                "cap-muxer negotiated protocol"
+            );
+            state.session = Some(ack);
+            Ok(Control::Reply(Frame::HelloAck(ack)))
+        }
+        Frame::HelloAck(_) => Err(MuxerError::BadState(
+            "hello_ack is sent by the muxer, not to it".to_string(),
+        )),
         Frame::InitVideo(init) => {
             if state.started {
                 return Err(MuxerError::BadState("init_video after start".to_string()));
             }
-            state.init_video = Some(init);
+            state.declare_stream(InitStream {
+                stream_index: STREAM_INDEX_VIDEO,
+                media: StreamMedia::Video(init),
+                metadata: StreamMetadata::default(),
+            })?;
             Ok(Control::Continue)
         }
-        Frame::InitAudio(init) => {
+        Frame::InitAudio(mut init) => {
             if state.started {
                 return Err(MuxerError::BadState("init_audio after start".to_string()));
             }
-            state.init_audio = Some(init);
+            let track = state
+                .streams
+                .iter()
+                .filter(|s| matches!(s.media, StreamMedia::Audio(_)))
+                .count();
+            let stream_index = u8::try_from(track)
+                .ok()
+                .and_then(audio_stream_index)
+                .ok_or_else(|| {
+                    MuxerError::BadState(format!("more than {MAX_AUDIO_TRACKS} audio tracks"))
+                })?;
+            let title = init.label.take();
+            state.declare_stream(InitStream {
+                stream_index,
+                media: StreamMedia::Audio(init),
+                metadata: StreamMetadata {
+                    title,
+                    language: None,
+                },
+            })?;
+            Ok(Control::Continue)
+        }
+        Frame::InitStream(init) => {
+            if state.started {
+                return Err(MuxerError::BadState("init_stream after start".to_string()));
+            }
+            if !state.supports(FEATURE_INIT_STREAM) {
+                return Err(MuxerError::BadState(
+                    "init_stream without negotiating it".to_string(),
+                ));
+            }
+            if matches!(init.media, StreamMedia::Data(_)) && !state.supports(FEATURE_DATA_STREAMS) {
+                return Err(MuxerError::BadState(
+                    "data streams are not supported".to_string(),
+                ));
+            }
+            state.declare_stream(init)?;
             Ok(Control::Continue)
         }
         Frame::Start(params) => {
@@ -371,11 +507,56 @@ fn handle_frame(state: &mut State, frame: Frame) -> Result<Control, MuxerError>
             write_packet(state, packet)?;
             Ok(Control::Continue)
         }
+        Frame::Metadata(metadata) => {
+            if !state.supports(FEATURE_METADATA) {
+                return Err(MuxerError::BadState(
+                    "metadata without negotiating it".to_string(),
+                ));
+            }
+            match state.output.as_mut() {
+                Some(out) => apply_metadata(&mut out.ctx, &mut out.chapters_written, &metadata)?,
+                None => {
+                    state.metadata.tags.extend(metadata.tags);
+                    state.metadata.chapters.extend(metadata.chapters);
+                }
+            }
+            Ok(Control::Continue)
+        }
         Frame::Finish => Ok(Control::Finish),
         Frame::Abort(reason) => Err(MuxerError::Abort(reason)),
     }
 }
 
+fn apply_metadata(
+    ctx: &mut format::context::Output,
+    chapters_written: &mut i64,
+    metadata: &Metadata,
+) -> Result<(), MuxerError> {
+    if !metadata.tags.is_empty() {
+        let mut dict = ctx.metadata().to_owned();
+        for (key, value) in &metadata.tags {
+            dict.set(key, value);
+        }
+        ctx.set_metadata(dict);
+    }
+
+    for chapter in &metadata.chapters {
+        let start = i64::try_from(chapter.start_ms).unwrap_or(i64::MAX);
+        let end = i64::try_from(chapter.end_ms).unwrap_or(i64::MAX).max(start);
+        ctx.add_chapter(
+            *chapters_written,
+            ffmpeg::Rational::new(1, 1000),
+            start,
+            end,
+            &chapter.title,
+        )
+        .map_err(|e| MuxerError::FFmpeg(anyhow!("add_chapter: {e}")))?;
+        *chapters_written += 1;
+    }
+
+    Ok(())
+}
+
 fn open_output(state: &mut State, params: &StartParams) -> Result<OpenOutput, MuxerError> {
     let base_path = PathBuf::from(&params.output_directory);
     std::fs::create_dir_all(&base_path)
@@ -402,9 +583,12 @@ fn open_output(state: &mut State, params: &StartParams) -> Result<OpenOutput, Mu
         set_opt("init_seg_name", &params.init_segment_name);
         set_opt("media_seg_name", &params.media_segment_pattern);
         let segment_duration_secs = state
-            .init_video
-            .as_ref()
-            .map(|v| v.segment_duration_ms as f64 / 1000.0)
+            .streams
+            .iter()
+            .find_map(|s| match &s.media {
+                StreamMedia::Video(v) => Some(v.segment_duration_ms as f64 / 1000.0),
+                _ => None,
+            })
             .unwrap_or(2.0);
         set_opt("seg_duration", &segment_duration_secs.to_string());
         set_opt("use_timeline", "1");
@@ -417,95 +601,25 @@ fn open_output(state: &mut State, params: &StartParams) -> Result<OpenOutput, Mu
         );
     }
 
-    let mut video_stream_index = None;
-    let mut audio_stream_index = None;
-    let mut video_time_base = None;
-    let mut audio_time_base = None;
-
-    if let Some(init) = &state.init_video {
-        let codec = codec::encoder::find_by_name(&init.codec)
-            .ok_or_else(|| MuxerError::Init(anyhow!("video codec not found: {}", init.codec)))?;
-
-        let mut stream = output
-            .add_stream(codec)
-            .map_err(|e| MuxerError::FFmpeg(anyhow!("add_stream video: {e}")))?;
-
-        unsafe {
-            let st = stream.as_mut_ptr();
-            let cp = (*st).codecpar;
-            (*cp).codec_type = ffmpeg::ffi::AVMediaType::AVMEDIA_TYPE_VIDEO;
-            (*cp).codec_id = codec.id().into();
-            (*cp).width = init.width as i32;
-            (*cp).height = init.height as i32;
-            (*cp).format = ffmpeg::ffi::AVPixelFormat::AV_PIX_FMT_YUV420P as i32;
-            (*cp).bit_rate = 0;
-
-            if !init.extradata.is_empty() {
-                let size = init.extradata.len() as i32;
-                let ptr = ffmpeg::ffi::av_mallocz(
-                    size as usize + ffmpeg::ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize,
-                ) as *mut u8;
-                if ptr.is_null() {
-                    return Err(MuxerError::Init(anyhow!(
-                        "failed to allocate extradata buffer"
-                    )));
-                }
-                std::ptr::copy_nonoverlapping(init.extradata.as_ptr(), ptr, init.extradata.len());
-                (*cp).extradata = ptr;
-                (*cp).extradata_size = size;
-            }
-        }
-
-        let tb = ffmpeg::Rational::new(init.time_base_num, init.time_base_den);
-        stream.set_time_base(tb);
-        video_stream_index = Some(stream.index());
-        video_time_base = Some(tb);
-    }
-
-    if let Some(init) = &state.init_audio {
-        let codec = codec::encoder::find_by_name(&init.codec)
-            .ok_or_else(|| MuxerError::Init(anyhow!("audio codec not found: {}", init.codec)))?;
-
-        let mut stream = output
-            .add_stream(codec)
-            .map_err(|e| MuxerError::FFmpeg(anyhow!("add_stream audio: {e}")))?;
-
-        unsafe {
-            let st = stream.as_mut_ptr();
-            let cp = (*st).codecpar;
-            (*cp).codec_type = ffmpeg::ffi::AVMediaType::AVMEDIA_TYPE_AUDIO;
-            (*cp).codec_id = codec.id().into();
-            (*cp).sample_rate = init.sample_rate as i32;
-            (*cp).ch_layout.nb_channels = init.channels as i32;
-            (*cp).ch_layout.order = ffmpeg::ffi::AVChannelOrder::AV_CHANNEL_ORDER_NATIVE;
-            (*cp).ch_layout.u.mask = match init.channels {
-                1 => ffmpeg::ffi::AV_CH_LAYOUT_MONO,
-                2 => ffmpeg::ffi::AV_CH_LAYOUT_STEREO,
-                _ => ffmpeg::ffi::AV_CH_LAYOUT_STEREO,
-            };
-
-            if !init.extradata.is_empty() {
-                let size = init.extradata.len() as i32;
-                let ptr = ffmpeg::ffi::av_mallocz(
-                    size as usize + ffmpeg::ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize,
-                ) as *mut u8;
-                if ptr.is_null() {
-                    return Err(MuxerError::Init(anyhow!(
-                        "failed to allocate audio extradata buffer"
-                    )));
-                }
-                std::ptr::copy_nonoverlapping(init.extradata.as_ptr(), ptr, init.extradata.len());
-                (*cp).extradata = ptr;
-                (*cp).extradata_size = size;
+    let mut tracks = Vec::with_capacity(state.streams.len());
+
+    for init in &state.streams {
+        let track = match &init.media {
+            StreamMedia::Video(video) => add_video_stream(&mut output, init, video)?,
+            StreamMedia::Audio(audio) => add_audio_stream(&mut output, init, audio)?,
+            StreamMedia::Data(_) => {
+                return Err(MuxerError::Init(anyhow!(
+                    "data stream {} can't be written to DASH output",
+                    init.stream_index
+                )));
             }
-        }
-
-        let tb = ffmpeg::Rational::new(init.time_base_num, init.time_base_den);
-        stream.set_time_base(tb);
-        audio_stream_index = Some(stream.index());
-        audio_time_base = Some(tb);
+        };
+        tracks.push(track);
     }
 
+    let mut chapters_written = 0;
+    apply_metadata(&mut output, &mut chapters_written, &state.metadata)?;
+
     output.write_header().map_err(|e| {
         let err = anyhow!("write_header: {e}");
         if classify_io_error(&e) {
@@ -517,32 +631,154 @@ fn open_output(state: &mut State, params: &StartParams) -> Result<OpenOutput, Mu
 
     Ok(OpenOutput {
         ctx: output,
-        video_stream_index,
-        audio_stream_index,
-        video_time_base,
-        audio_time_base,
+        primary_video: tracks.iter().position(|t| t.is_video),
+        tracks,
         packets_written_video: 0,
         packets_written_audio: 0,
-        video_keyframe_seen: video_stream_index.is_none(),
         video_packets_dropped_pre_keyframe: 0,
         audio_packets_dropped_pre_video: 0,
-        pending_video_packet: None,
-        pending_audio_packet: None,
-        last_video_duration_input_tb: None,
-        last_audio_duration_input_tb: None,
+        chapters_written,
         start_ts: Some(std::time::Instant::now()),
         _base_path: base_path,
     })
 }
 
+fn add_video_stream(
+    output: &mut format::context::Output,
+    init: &InitStream,
+    video: &InitVideo,
+) -> Result<TrackOutput, MuxerError> {
+    let codec = codec::encoder::find_by_name(&video.codec)
+        .ok_or_else(|| MuxerError::Init(anyhow!("video codec not found: {}", video.codec)))?;
+
+    let mut stream = output
+        .add_stream(codec)
+        .map_err(|e| MuxerError::FFmpeg(anyhow!("add_stream video: {e}")))?;
+
+    unsafe {
+        let st = stream.as_mut_ptr();
+        let cp = (*st).codecpar;
+        (*cp).codec_type = ffmpeg::ffi::AVMediaType::AVMEDIA_TYPE_VIDEO;
+        (*cp).codec_id = codec.id().into();
+        (*cp).width = video.width as i32;
+        (*cp).height = video.height as i32;
+        (*cp).format = ffmpeg::ffi::AVPixelFormat::AV_PIX_FMT_YUV420P as i32;
+        (*cp).bit_rate = 0;
+        set_extradata(cp, &video.extradata)?;
+    }
+
+    let tb = ffmpeg::Rational::new(video.time_base_num, video.time_base_den);
+    stream.set_time_base(tb);
+    set_stream_metadata(&mut stream, &init.metadata);
+
+    Ok(TrackOutput {
+        protocol_index: init.stream_index,
+        stream_index: stream.index(),
+        time_base: tb,
+        is_video: true,
+        keyframe_seen: false,
+        nominal_duration_input_tb: nominal_video_duration_input_tb(video),
+        pending_packet: None,
+        last_duration_input_tb: None,
+    })
+}
+
+fn add_audio_stream(
+    output: &mut format::context::Output,
+    init: &InitStream,
+    audio: &InitAudio,
+) -> Result<TrackOutput, MuxerError> {
+    let codec = codec::encoder::find_by_name(&audio.codec)
+        .ok_or_else(|| MuxerError::Init(anyhow!("audio codec not found: {}", audio.codec)))?;
+
+    let mut stream = output
+        .add_stream(codec)
+        .map_err(|e| MuxerError::FFmpeg(anyhow!("add_stream audio: {e}")))?;
+
+    unsafe {
+        let st = stream.as_mut_ptr();
+        let cp = (*st).codecpar;
+        (*cp).codec_type = ffmpeg::ffi::AVMediaType::AVMEDIA_TYPE_AUDIO;
+        (*cp).codec_id = codec.id().into();
+        (*cp).sample_rate = audio.sample_rate as i32;
+        (*cp).ch_layout.nb_channels = audio.channels as i32;
+        (*cp).ch_layout.order = ffmpeg::ffi::AVChannelOrder::AV_CHANNEL_ORDER_NATIVE;
+        (*cp).ch_layout.u.mask = match audio.channels {
+            1 => ffmpeg::ffi::AV_CH_LAYOUT_MONO,
+            2 => ffmpeg::ffi::AV_CH_LAYOUT_STEREO,
+            _ => ffmpeg::ffi::AV_CH_LAYOUT_STEREO,
+        };
+        set_extradata(cp, &audio.extradata)?;
+    }
+
+    let tb = ffmpeg::Rational::new(audio.time_base_num, audio.time_base_den);
+    stream.set_time_base(tb);
+    set_stream_metadata(&mut stream, &init.metadata);
+
+    Ok(TrackOutput {
+        protocol_index: init.stream_index,
+        stream_index: stream.index(),
+        time_base: tb,
+        is_video: false,
+        keyframe_seen: true,
+        nominal_duration_input_tb: nominal_audio_duration_input_tb(audio),
+        pending_packet: None,
+        last_duration_input_tb: None,
+    })
+}
+
+unsafe fn set_extradata(
+    cp: *mut ffmpeg::ffi::AVCodecParameters,
+    extradata: &[u8],
+) -> Result<(), MuxerError> {
+    if extradata.is_empty() {
+        return Ok(());
+    }
+    unsafe {
+        let ptr = ffmpeg::ffi::av_mallocz(
+            extradata.len() + ffmpeg::ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize,
+        ) as *mut u8;
+        if ptr.is_null() {
+            return Err(MuxerError::Init(anyhow!(
+                "failed to allocate extradata buffer"
+            )));
+        }
+        std::ptr::copy_nonoverlapping(extradata.as_ptr(), ptr, extradata.len());
+        (*cp).extradata = ptr;
+        (*cp).extradata_size = extradata.len() as i32;
+    }
+    Ok(())
+}
+
+fn set_stream_metadata(stream: &mut format::stream::StreamMut, metadata: &StreamMetadata) {
+    if metadata.title.is_none() && metadata.language.is_none() {
+        return;
+    }
+    let mut dict = ffmpeg::Dictionary::new();
+    if let Some(title) = &metadata.title {
+        dict.set("title", title);
+        dict.set("handler_name", title);
+    }
+    if let Some(language) = &metadata.language {
+        dict.set("language", language);
+    }
+    stream.set_metadata(dict);
+}
+
 fn write_packet(state: &mut State, packet: Packet) -> Result<(), MuxerError> {
     let Some(out) = state.output.as_mut() else {
         return Err(MuxerError::BadState("no output".to_string()));
     };
 
-    if packet.stream_index == STREAM_INDEX_VIDEO {
+    let position = track_position(&out.tracks, packet.stream_index)?;
+    let primary_video_ready = out
+        .primary_video
+        .is_none_or(|primary| out.tracks[primary].keyframe_seen);
+    let track = &mut out.tracks[position];
+
+    if track.is_video {
         let is_keyframe = packet.flags & PACKET_FLAG_KEYFRAME != 0;
-        if !out.video_keyframe_seen {
+        if !track.keyframe_seen {
             if !is_keyframe {
                 out.video_packets_dropped_pre_keyframe += 1;
                 if out.video_packets_dropped_pre_keyframe == 1
@@ -555,7 +791,7 @@ fn write_packet(state: &mut State, packet: Packet) -> Result<(), MuxerError> {
                 }
                 return Ok(());
             }
-            out.video_keyframe_seen = true;
+            track.keyframe_seen = true;
             if out.video_packets_dropped_pre_keyframe > 0 {
                 tracing::info!(
                     dropped = out.video_packets_dropped_pre_keyframe,
@@ -563,10 +799,7 @@ fn write_packet(state: &mut State, packet: Packet) -> Result<(), MuxerError> {
                 );
             }
         }
-    } else if packet.stream_index == STREAM_INDEX_AUDIO
-        && out.video_stream_index.is_some()
-        && !out.video_keyframe_seen
-    {
+    } else if !primary_video_ready {
         out.audio_packets_dropped_pre_video += 1;
         if out.audio_packets_dropped_pre_video == 1
             || out.audio_packets_dropped_pre_video.is_multiple_of(100)
@@ -579,31 +812,26 @@ fn write_packet(state: &mut State, packet: Packet) -> Result<(), MuxerError> {
         return Ok(());
     }
 
-    let ready_packet = match packet.stream_index {
-        STREAM_INDEX_VIDEO => queue_packet_for_stream(
-            &mut out.pending_video_packet,
-            &mut out.last_video_duration_input_tb,
-            packet,
-        ),
-        STREAM_INDEX_AUDIO => queue_packet_for_stream(
-            &mut out.pending_audio_packet,
-            &mut out.last_audio_duration_input_tb,
-            packet,
-        ),
-        other => {
-            return Err(MuxerError::BadState(format!(
-                "unknown stream index {other}"
-            )));
-        }
-    };
+    let ready_packet = queue_packet_for_stream(
+        &mut track.pending_packet,
+        &mut track.last_duration_input_tb,
+        packet,
+    );
 
     if let Some((ready_packet, duration_input_tb)) = ready_packet {
-        write_ready_packet(out, ready_packet, duration_input_tb)?;
+        write_ready_packet(out, position, ready_packet, duration_input_tb)?;
     }
 
     Ok(())
 }
 
+fn track_position(tracks: &[TrackOutput], stream_index: u8) -> Result<usize, MuxerError> {
+    tracks
+        .iter()
+        .position(|track| track.protocol_index == stream_index)
+        .ok_or_else(|| MuxerError::BadState(format!("unknown stream index {stream_index}")))
+}
+
 fn queue_packet_for_stream(
     pending: &mut Option<Packet>,
     last_duration_input_tb: &mut Option<i64>,
@@ -652,43 +880,27 @@ fn resolve_packet_duration(
         .unwrap_or(1)
 }
 
-fn flush_pending_packets(
-    out: &mut OpenOutput,
-    video_init: Option<&InitVideo>,
-    audio_init: Option<&InitAudio>,
-) -> Result<(), MuxerError> {
-    let pending_video = out.pending_video_packet.take().map(|packet| {
-        let fallback_duration = video_init.and_then(nominal_video_duration_input_tb);
-        let duration = resolve_packet_duration(
-            packet.duration,
-            out.last_video_duration_input_tb,
-            fallback_duration,
-        );
-        if duration > 0 {
-            out.last_video_duration_input_tb = Some(duration);
-        }
-        (packet, duration)
-    });
-
-    let pending_audio = out.pending_audio_packet.take().map(|packet| {
-        let fallback_duration = audio_init.and_then(nominal_audio_duration_input_tb);
-        let duration = resolve_packet_duration(
-            packet.duration,
-            out.last_audio_duration_input_tb,
-            fallback_duration,
-        );
-        if duration > 0 {
-            out.last_audio_duration_input_tb = Some(duration);
-        }
-        (packet, duration)
-    });
-
-    if let Some((packet, duration)) = pending_video {
-        write_ready_packet(out, packet, duration)?;
-    }
+fn flush_pending_packets(out: &mut OpenOutput) -> Result<(), MuxerError> {
+    let pending = out
+        .tracks
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(position, track)| {
+            let packet = track.pending_packet.take()?;
+            let duration = resolve_packet_duration(
+                packet.duration,
+                track.last_duration_input_tb,
+                track.nominal_duration_input_tb,
+            );
+            if duration > 0 {
+                track.last_duration_input_tb = Some(duration);
+            }
+            Some((position, packet, duration))
+        })
+        .collect::<Vec<_>>();
 
-    if let Some((packet, duration)) = pending_audio {
-        write_ready_packet(out, packet, duration)?;
+    for (position, packet, duration) in pending {
+        write_ready_packet(out, position, packet, duration)?;
     }
 
     Ok(())
@@ -739,28 +951,12 @@ fn nominal_audio_duration_input_tb(init: &InitAudio) -> Option<i64> {
 
 fn write_ready_packet(
     out: &mut OpenOutput,
+    position: usize,
     packet: Packet,
     duration_input_tb: i64,
 ) -> Result<(), MuxerError> {
-    let (stream_index, time_base) = match packet.stream_index {
-        STREAM_INDEX_VIDEO => (
-            out.video_stream_index
-                .ok_or_else(|| MuxerError::BadState("video not initialized".to_string()))?,
-            out.video_time_base
-                .ok_or_else(|| MuxerError::BadState("no video time base".to_string()))?,
-        ),
-        STREAM_INDEX_AUDIO => (
-            out.audio_stream_index
-                .ok_or_else(|| MuxerError::BadState("audio not initialized".to_string()))?,
-            out.audio_time_base
-                .ok_or_else(|| MuxerError::BadState("no audio time base".to_string()))?,
-        ),
-        other => {
-            return Err(MuxerError::BadState(format!(
-                "unknown stream index {other}"
-            )));
-        }
-    };
+    let track = &out.tracks[position];
+    let (stream_index, time_base, is_video) = (track.stream_index, track.time_base, track.is_video);
 
     let mut ff_packet = ffmpeg::Packet::copy(&packet.data);
     ff_packet.set_stream(stream_index);
@@ -783,10 +979,10 @@ fn write_ready_packet(
         }
     })?;
 
-    match packet.stream_index {
-        STREAM_INDEX_VIDEO => out.packets_written_video += 1,
-        STREAM_INDEX_AUDIO => out.packets_written_audio += 1,
-        _ => {}
+    if is_video {
+        out.packets_written_video += 1;
+    } else {
+        out.packets_written_audio += 1;
     }
 
     Ok(())
@@ -848,6 +1044,128 @@ mod tests {
         assert_eq!(nominal_video_duration_input_tb(&init), Some(16_667));
     }
 
+    #[test]
+    fn routes_packets_to_their_track() {
+        let tracks = [STREAM_INDEX_VIDEO, 1, 2, 7]
+            .into_iter()
+            .enumerate()
+            .map(|(i, protocol_index)| TrackOutput {
+                protocol_index,
+                stream_index: i,
+                time_base: ffmpeg::Rational::new(1, 48_000),
+                is_video: i == 0,
+                keyframe_seen: i != 0,
+                nominal_duration_input_tb: None,
+                pending_packet: None,
+                last_duration_input_tb: None,
+            })
+            .collect::<Vec<_>>();
+
+        let second = audio_stream_index(1).unwrap();
+        assert_eq!(track_position(&tracks, second).unwrap(), 2);
+        assert_eq!(track_position(&tracks, 7).unwrap(), 3);
+        assert_eq!(track_position(&tracks, STREAM_INDEX_VIDEO).unwrap(), 0);
+        assert!(track_position(&tracks, 3).is_err());
+    }
+
+    fn init_video() -> InitVideo {
+        InitVideo {
+            codec: "h264".to_string(),
+            width: 1920,
+            height: 1080,
+            frame_rate_num: 30,
+            frame_rate_den: 1,
+            time_base_num: 1,
+            time_base_den: 90_000,
+            extradata: Vec::new(),
+            segment_duration_ms: 2_000,
+        }
+    }
+
+    fn init_audio(label: Option<&str>) -> InitAudio {
+        InitAudio {
+            codec: "aac".to_string(),
+            sample_rate: 48_000,
+            channels: 2,
+            sample_format: "fltp".to_string(),
+            time_base_num: 1,
+            time_base_den: 48_000,
+            extradata: Vec::new(),
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn v1_init_frames_declare_streams() {
+        let mut state = State::default();
+        handle_frame(&mut state, Frame::InitVideo(init_video())).unwrap();
+        handle_frame(&mut state, Frame::InitAudio(init_audio(Some("Microphone")))).unwrap();
+        handle_frame(&mut state, Frame::InitAudio(init_audio(None))).unwrap();
+
+        assert!(state.session.is_none());
+        let declared = state
+            .streams
+            .iter()
+            .map(|s| (s.stream_index, s.metadata.title.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            declared,
+            [
+                (STREAM_INDEX_VIDEO, None),
+                (audio_stream_index(0).unwrap(), Some("Microphone")),
+                (audio_stream_index(1).unwrap(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn v2_frames_need_a_handshake() {
+        let stream = InitStream {
+            stream_index: 4,
+            media: StreamMedia::Audio(init_audio(None)),
+            metadata: StreamMetadata {
+                title: Some("Commentary".to_string()),
+                language: Some("eng".to_string()),
+            },
+        };
+
+        let mut legacy = State::default();
+        handle_frame(&mut legacy, Frame::InitVideo(init_video())).unwrap();
+        assert!(handle_frame(&mut legacy, Frame::InitStream(stream.clone())).is_err());
+        assert!(handle_frame(&mut legacy, Frame::Metadata(Metadata::default())).is_err());
+
+        let mut state = State::default();
+        let Control::Reply(Frame::HelloAck(ack)) =
+            handle_frame(&mut state, Frame::Hello(Hello::new(u64::MAX))).unwrap()
+        else {
+            panic!("expected a hello_ack reply");
+        };
+        assert_eq!(ack.version, cap_muxer_protocol::PROTOCOL_VERSION);
+        assert_eq!(ack.features, MUXER_FEATURES);
+
+        handle_frame(&mut state, Frame::InitStream(stream.clone())).unwrap();
+        assert!(handle_frame(&mut state, Frame::InitStream(stream)).is_err());
+        handle_frame(&mut state, Frame::Metadata(Metadata::default())).unwrap();
+        assert!(handle_frame(&mut state, Frame::Hello(Hello::new(0))).is_err());
+    }
+
+    #[test]
+    fn rejects_data_streams() {
+        let mut state = State::default();
+        handle_frame(&mut state, Frame::Hello(Hello::new(u64::MAX))).unwrap();
+        let data = InitStream {
+            stream_index: 2,
+            media: StreamMedia::Data(cap_muxer_protocol::InitData {
+                codec: "bin_data".to_string(),
+                time_base_num: 1,
+                time_base_den: 1_000,
+                extradata: Vec::new(),
+            }),
+            metadata: StreamMetadata::default(),
+        };
+        assert!(handle_frame(&mut state, Frame::InitStream(data)).is_err());
+    }
+
     #[test]
     fn computes_nominal_audio_duration_from_aac_frame_size() {
         let init = InitAudio {
@@ -858,6 +1176,7 @@ mod tests {
             time_base_num: 1,
             time_base_den: 48_000,
             extradata: Vec::new(),
+            label: None,
         };
 
         assert_eq!(nominal_audio_duration_input_tb(&init), Some(1_024));
@@ -0,0 +1,364 @@
+use strum::{EnumString, IntoStaticStr};
+
+use crate::{CursorShape, ResolvedCursor};
+
+/// Linux cursors, named after the CSS cursor keywords that freedesktop cursor themes and the
+/// Wayland cursor-shape-v1 protocol both follow.
+/// https://wayland.app/protocols/cursor-shape-v1#wp_cursor_shape_device_v1:enum:shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumString, IntoStaticStr)]
+pub enum CursorShapeLinux {
+    Default,
+    ContextMenu,
+    Help,
+    Pointer,
+    Progress,
+    Wait,
+    Cell,
+    Crosshair,
+    Text,
+    VerticalText,
+    Alias,
+    Copy,
+    Move,
+    NoDrop,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    EResize,
+    NResize,
+    NeResize,
+    NwResize,
+    SResize,
+    SeResize,
+    SwResize,
+    WResize,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ColResize,
+    RowResize,
+    AllScroll,
+    ZoomIn,
+    ZoomOut,
+}
+
+/// cursor-shape-v1 shape values, starting at 1.
+const WAYLAND_SHAPES: [CursorShapeLinux; 34] = [
+    CursorShapeLinux::Default,
+    CursorShapeLinux::ContextMenu,
+    CursorShapeLinux::Help,
+    CursorShapeLinux::Pointer,
+    CursorShapeLinux::Progress,
+    CursorShapeLinux::Wait,
+    CursorShapeLinux::Cell,
+    CursorShapeLinux::Crosshair,
+    CursorShapeLinux::Text,
+    CursorShapeLinux::VerticalText,
+    CursorShapeLinux::Alias,
+    CursorShapeLinux::Copy,
+    CursorShapeLinux::Move,
+    CursorShapeLinux::NoDrop,
+    CursorShapeLinux::NotAllowed,
+    CursorShapeLinux::Grab,
+    CursorShapeLinux::Grabbing,
+    CursorShapeLinux::EResize,
+    CursorShapeLinux::NResize,
+    CursorShapeLinux::NeResize,
+    CursorShapeLinux::NwResize,
+    CursorShapeLinux::SResize,
+    CursorShapeLinux::SeResize,
+    CursorShapeLinux::SwResize,
+    CursorShapeLinux::WResize,
+    CursorShapeLinux::EwResize,
+    CursorShapeLinux::NsResize,
+    CursorShapeLinux::NeswResize,
+    CursorShapeLinux::NwseResize,
+    CursorShapeLinux::ColResize,
+    CursorShapeLinux::RowResize,
+    CursorShapeLinux::AllScroll,
+    CursorShapeLinux::ZoomIn,
+    CursorShapeLinux::ZoomOut,
+];
+
+impl CursorShapeLinux {
+    /// Linux has no cursor art of its own here, so each shape borrows the closest macOS asset,
+    /// or the Windows one where macOS has no equivalent.
+    pub fn resolve(&self) -> Option<ResolvedCursor> {
+        Some(match self {
+            Self::Default => ResolvedCursor {
+                raw: include_str!("../assets/mac/arrow.svg"),
+                hotspot: (0.302, 0.226),
+            },
+            Self::ContextMenu => ResolvedCursor {
+                raw: include_str!("../assets/mac/contextual_menu.svg"),
+                hotspot: (0.278, 0.295),
+            },
+            Self::Help => ResolvedCursor {
+                raw: include_str!("../assets/windows/idchelp.svg"),
+                hotspot: (0.056, 0.127),
+            },
+            Self::Pointer => ResolvedCursor {
+                raw: include_str!("../assets/mac/pointing_hand.svg"),
+                hotspot: (0.342, 0.172),
+            },
+            Self::Progress => ResolvedCursor {
+                raw: include_str!("../assets/windows/appstarting.svg"),
+                hotspot: (0.055, 0.368),
+            },
+            Self::Wait => ResolvedCursor {
+                raw: include_str!("../assets/windows/wait.svg"),
+                hotspot: (0.5, 0.52),
+            },
+            Self::Cell | Self::Crosshair => ResolvedCursor {
+                raw: include_str!("../assets/mac/crosshair.svg"),
+                hotspot: (0.52, 0.51),
+            },
+            Self::Text => ResolvedCursor {
+                raw: include_str!("../assets/mac/ibeam.svg"),
+                hotspot: (0.484, 0.520),
+            },
+            Self::VerticalText => ResolvedCursor {
+                raw: include_str!("../assets/mac/ibeam_vertical.svg"),
+                hotspot: (0.51, 0.49),
+            },
+            Self::Alias => ResolvedCursor {
+                raw: include_str!("../assets/mac/drag_link.svg"),
+                hotspot: (0.621, 0.309),
+            },
+            Self::Copy => ResolvedCursor {
+                raw: include_str!("../assets/mac/drag_copy.svg"),
+                hotspot: (0.255, 0.1),
+            },
+            Self::Move | Self::AllScroll => ResolvedCursor {
+                raw: include_str!("../assets/windows/sizeall.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::NoDrop | Self::NotAllowed => ResolvedCursor {
+                raw: include_str!("../assets/mac/operation_not_allowed.svg"),
+                hotspot: (0.24, 0.1),
+            },
+            Self::Grab => ResolvedCursor {
+                raw: include_str!("../assets/mac/open_hand.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::Grabbing => ResolvedCursor {
+                raw: include_str!("../assets/mac/closed_hand.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::EResize => ResolvedCursor {
+                raw: include_str!("../assets/mac/resize_right.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::NResize => ResolvedCursor {
+                raw: include_str!("../assets/mac/resize_up.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::SResize => ResolvedCursor {
+                raw: include_str!("../assets/mac/resize_down.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::WResize => ResolvedCursor {
+                raw: include_str!("../assets/mac/resize_left.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::EwResize | Self::ColResize => ResolvedCursor {
+                raw: include_str!("../assets/mac/resize_left_right.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::NsResize | Self::RowResize => ResolvedCursor {
+                raw: include_str!("../assets/mac/resize_up_down.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::NeResize | Self::SwResize | Self::NeswResize => ResolvedCursor {
+                raw: include_str!("../assets/windows/size-nesw.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::NwResize | Self::SeResize | Self::NwseResize => ResolvedCursor {
+                raw: include_str!("../assets/windows/idcsizenwse.svg"),
+                hotspot: (0.5, 0.5),
+            },
+            Self::ZoomIn => ResolvedCursor {
+                raw: include_str!("../assets/mac/tahoe/zoom-in.svg"),
+                hotspot: (0.549, 0.550),
+            },
+            Self::ZoomOut => ResolvedCursor {
+                raw: include_str!("../assets/mac/tahoe/zoom-out.svg"),
+                hotspot: (0.551, 0.552),
+            },
+        })
+    }
+
+    /// Derive the cursor type from the name XFixes reports for the current cursor.
+    /// Toolkits load cursors from the theme by CSS name, legacy X cursor-font name, Qt name or the
+    /// Xcursor hash of an old bitmap cursor, and the name they asked for is the one reported, so
+    /// all of those spellings are accepted. Wayland cursor-shape-v1 names (`ns_resize`) work too.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase().replace('_', "-");
+
+        Some(match name.as_str() {
+            "default" | "left-ptr" | "arrow" | "top-left-arrow" | "left-arrow" | "right-ptr" => {
+                Self::Default
+            }
+            "context-menu" => Self::ContextMenu,
+            "help"
+            | "question-arrow"
+            | "whats-this"
+            | "left-ptr-help"
+            | "dnd-ask"
+            | "5c6cd98b3f3ebcb1f9c7f1c204630408"
+            | "d9ce0ab605698f320427677b458ad60b" => Self::Help,
+            "pointer"
+            | "pointing-hand"
+            | "hand"
+            | "hand1"
+            | "hand2"
+            | "e29285e634086352946a0e7090d73106"
+            | "9d800788f1b08800ae810202380a0822" => Self::Pointer,
+            "progress"
+            | "left-ptr-watch"
+            | "half-busy"
+            | "00000000000000020006000e7e9ffc3f"
+            | "08e8e1c95fe2fc01f976f1e063a24ccd"
+            | "3ecb610c1bf2410f44200f48c40d3599" => Self::Progress,
+            "wait" | "watch" | "busy" | "0426c94ea35c87780ff01dc239897213" => Self::Wait,
+            "cell" | "plus" => Self::Cell,
+            "crosshair" | "cross" | "tcross" | "cross-reverse" | "diamond-cross" => Self::Crosshair,
+            "text" | "xterm" | "ibeam" => Self::Text,
+            "vertical-text" => Self::VerticalText,
+            "alias"
+            | "link"
+            | "dnd-link"
+            | "3085a0e285430894940527032f8b26df"
+            | "640fb0e74195791501fd1ed57b41487f"
+            | "a2a266d0498c3104214a47bd64ab0fc8" => Self::Alias,
+            "copy"
+            | "dnd-copy"
+            | "1081e37283d90000800003c07f3ef6bf"
+            | "6407b0e94181790501fd1e167b474872"
+            | "b66166c04f8c3109214a4fbd64a50fc8" => Self::Copy,
+            "move"
+            | "dnd-move"
+            | "fleur"
+            | "size-all"
+            | "all-resize"
+            | "4498f0e0c1937ffe01fd06f973665830"
+            | "9081237383d90e509aa00f00170e968f" => Self::Move,
+            "no-drop" | "dnd-no-drop" | "dnd-none" => Self::NoDrop,
+            "not-allowed"
+            | "forbidden"
+            | "crossed-circle"
+            | "circle"
+            | "pirate"
+            | "x-cursor"
+            | "03b6e0fcb3499374a867c041f52298f0" => Self::NotAllowed,
+            "grab" | "openhand" | "5aca4d189052212118709018842178c0" => Self::Grab,
+            "grabbing"
+            | "closedhand"
+            | "208530c400c041818281048008011002"
+            | "fcf21c00b30f7e3f83fe0dfd12e71cff" => Self::Grabbing,
+            "e-resize" | "right-side" => Self::EResize,
+            "n-resize" | "top-side" => Self::NResize,
+            "ne-resize" | "top-right-corner" => Self::NeResize,
+            "nw-resize" | "top-left-corner" | "ul-angle" => Self::NwResize,
+            "s-resize" | "bottom-side" => Self::SResize,
+            "se-resize" | "bottom-right-corner" | "lr-angle" => Self::SeResize,
+            "sw-resize" | "bottom-left-corner" | "ll-angle" => Self::SwResize,
+            "w-resize" | "left-side" => Self::WResize,
+            "ew-resize" | "size-hor" | "h-double-arrow" | "028006030e0e7ebffc7f7070c0600140" => {
+                Self::EwResize
+            }
+            "ns-resize" | "size-ver" | "v-double-arrow" | "00008160000006810000408080010102" => {
+                Self::NsResize
+            }
+            "nesw-resize"
+            | "size-bdiag"
+            | "fd-double-arrow"
+            | "50585d75b494802d0151028115016902"
+            | "fcf1c3c7cd4491d801f1e1c78f100000" => Self::NeswResize,
+            "nwse-resize"
+            | "size-fdiag"
+            | "bd-double-arrow"
+            | "38c5dff7c7b8962045400281044508d2"
+            | "c7088f0f3e6c8088236ef8e1e3e70000" => Self::NwseResize,
+            "col-resize"
+            | "split-h"
+            | "sb-h-double-arrow"
+            | "043a9f68147c53184671403ffa811cc5"
+            | "14fef782d02440884392942c11205230" => Self::ColResize,
+            "row-resize"
+            | "split-v"
+            | "sb-v-double-arrow"
+            | "2870a09082c103050810ffdffffe0204"
+            | "c07385c7190e701020ff7ffffd08103c" => Self::RowResize,
+            "all-scroll" => Self::AllScroll,
+            "zoom-in" => Self::ZoomIn,
+            "zoom-out" => Self::ZoomOut,
+            _ => return None,
+        })
+    }
+
+    /// Derive the cursor type from a `wp_cursor_shape_device_v1` shape value.
+    pub fn from_wayland_shape(shape: u32) -> Option<Self> {
+        let index = usize::try_from(shape.checked_sub(1)?).ok()?;
+        WAYLAND_SHAPES.get(index).copied()
+    }
+}
+
+impl From<CursorShapeLinux> for CursorShape {
+    fn from(value: CursorShapeLinux) -> Self {
+        CursorShape::Linux(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_shape_resolves_and_round_trips_its_names() {
+        for (index, shape) in WAYLAND_SHAPES.iter().enumerate() {
+            assert!(shape.resolve().is_some(), "{shape:?} has no asset");
+            assert_eq!(
+                CursorShapeLinux::from_wayland_shape(index as u32 + 1),
+                Some(*shape)
+            );
+
+            let variant: &'static str = shape.into();
+            let mut css = String::new();
+            for (i, c) in variant.chars().enumerate() {
+                if c.is_ascii_uppercase() && i > 0 {
+                    css.push('-');
+                }
+                css.push(c.to_ascii_lowercase());
+            }
+            assert_eq!(CursorShapeLinux::from_name(&css), Some(*shape), "{css}");
+            assert_eq!(
+                CursorShapeLinux::from_name(&css.replace('-', "_")),
+                Some(*shape)
+            );
+        }
+        assert_eq!(CursorShapeLinux::from_wayland_shape(0), None);
+    }
+
+    #[test]
+    fn legacy_x11_names_and_hashes_map_to_css_shapes() {
+        assert_eq!(
+            CursorShapeLinux::from_name("left_ptr"),
+            Some(CursorShapeLinux::Default)
+        );
+        assert_eq!(
+            CursorShapeLinux::from_name("xterm"),
+            Some(CursorShapeLinux::Text)
+        );
+        assert_eq!(
+            CursorShapeLinux::from_name("e29285e634086352946a0e7090d73106"),
+            Some(CursorShapeLinux::Pointer)
+        );
+        assert_eq!(
+            CursorShapeLinux::from_name("sb_v_double_arrow"),
+            Some(CursorShapeLinux::RowResize)
+        );
+        assert_eq!(CursorShapeLinux::from_name("custom-app-cursor"), None);
+    }
+}
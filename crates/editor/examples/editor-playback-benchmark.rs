@@ -326,6 +326,7 @@ async fn load_recording(
                     end: duration,
                     timescale: 1.0,
                     name: None,
+                    transition: None,
                 }]
             }
             StudioRecordingMeta::MultipleSegments { inner } => inner
@@ -344,6 +345,7 @@ async fn load_recording(
                         end: duration,
                         timescale: 1.0,
                         name: None,
+                        transition: None,
                     })
                 })
                 .collect(),
@@ -359,6 +361,7 @@ async fn load_recording(
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
             });
         }
     }
@@ -520,6 +523,7 @@ async fn main() {
         render_constants,
         start_frame_number,
         project: project_rx,
+        recordings,
         segment_medias,
         music: cap_editor::MusicTracks::new(),
         telemetry: Some(telemetry),
@@ -276,6 +276,7 @@ async fn load_recording(
                     end: duration,
                     timescale: 1.0,
                     name: None,
+                    transition: None,
                 }]
             }
             StudioRecordingMeta::MultipleSegments { inner } => inner
@@ -297,6 +298,7 @@ async fn load_recording(
                         end: duration,
                         timescale: 1.0,
                         name: None,
+                        transition: None,
                     })
                 })
                 .collect(),
@@ -312,6 +314,7 @@ async fn load_recording(
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
             });
         }
     }
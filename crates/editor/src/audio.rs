@@ -3,7 +3,9 @@ use cap_audio::{
 };
 use cap_media::MediaError;
 use cap_media_info::AudioInfo;
-use cap_project::{AudioConfiguration, ClipOffsets, ProjectConfiguration, TimelineConfiguration};
+use cap_project::{
+    AudioConfiguration, ClipOffsets, ProjectConfiguration, TimelineConfiguration, TransitionKind,
+};
 use ffmpeg::{
     ChannelLayout, Dictionary, format as avformat, frame::Audio as FFAudio, software::resampling,
 };
@@ -15,6 +17,8 @@ use ringbuf::{
 use std::{collections::HashMap, sync::Arc};
 use tracing::info;
 
+use crate::enhance::EnhancedVoice;
+
 /// Decoded music/imported-audio tracks, keyed by the path string stored in the
 /// project config's `timeline.audio_segments`. The renderer mixes these on top
 /// of the recording audio in output/timeline time.
@@ -27,6 +31,7 @@ pub struct AudioRenderer {
     // this * channel count = cursor
     elapsed_samples: usize,
     music: MusicTracks,
+    wait_for_enhancement: bool,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -50,6 +55,7 @@ pub struct AudioSegmentTrack {
     get_stereo_mode: fn(&AudioConfiguration) -> StereoMode,
     get_offset: fn(&ClipOffsets) -> f32,
     timing_offset_secs: f32,
+    voice: Option<EnhancedVoice>,
 }
 
 impl AudioSegmentTrack {
@@ -65,6 +71,7 @@ impl AudioSegmentTrack {
             get_stereo_mode,
             get_offset,
             timing_offset_secs: 0.0,
+            voice: None,
         }
     }
 
@@ -73,10 +80,35 @@ impl AudioSegmentTrack {
         self
     }
 
+    /// Marks this track as the recorded voice, so `AudioConfiguration::improve`
+    /// swaps in `voice`, its enhanced version.
+    pub fn with_voice_enhancement(mut self, voice: EnhancedVoice) -> Self {
+        self.voice = Some(voice);
+        self
+    }
+
     pub fn data(&self) -> &Arc<AudioData> {
         &self.data
     }
 
+    /// The samples to mix for `config`: the enhanced voice when `improve` is
+    /// on, the raw track otherwise. Unless `wait` is set, the raw track stands
+    /// in while the enhancement pass is still running in the background.
+    pub fn render_data(&self, config: &AudioConfiguration, wait: bool) -> Arc<AudioData> {
+        let Some(voice) = self.voice.as_ref().filter(|_| config.improve) else {
+            return Arc::clone(&self.data);
+        };
+
+        if wait {
+            return voice.wait();
+        }
+
+        voice.get().unwrap_or_else(|| {
+            voice.prepare();
+            Arc::clone(&self.data)
+        })
+    }
+
     pub fn gain(&self, config: &AudioConfiguration) -> f32 {
         (self.get_gain)(config)
     }
@@ -115,6 +147,7 @@ impl AudioRenderer {
             },
             elapsed_samples: 0,
             music: MusicTracks::new(),
+            wait_for_enhancement: false,
         }
     }
 
@@ -123,6 +156,14 @@ impl AudioRenderer {
         self
     }
 
+    /// Blocks on voice enhancement instead of mixing the raw track until it's
+    /// ready. For offline renders (export, loudness analysis) that must hear
+    /// exactly what `improve` produces.
+    pub fn with_blocking_enhancement(mut self) -> Self {
+        self.wait_for_enhancement = true;
+        self
+    }
+
     pub fn set_playhead(&mut self, playhead: f64, project: &ProjectConfiguration) {
         self.elapsed_samples = self.playhead_to_samples(playhead);
 
@@ -176,6 +217,8 @@ impl AudioRenderer {
             let frame_start = self.elapsed_samples;
             let (written, mut buf) = self.render_timeline_frame_raw(samples, project, timeline)?;
 
+            self.apply_transitions(project, timeline, frame_start, written, &mut buf);
+
             if !self.music.is_empty() && !timeline.audio_segments.is_empty() {
                 mix_music(&self.music, timeline, frame_start, written, &mut buf);
             }
@@ -296,7 +339,26 @@ impl AudioRenderer {
         out_offset: usize,
         out: &mut [f32],
     ) -> usize {
-        let Some(segment) = self.data.get(self.cursor.clip_index as usize) else {
+        self.render_clip_chunk(
+            project,
+            self.cursor.clip_index,
+            self.cursor.samples,
+            samples,
+            out_offset,
+            out,
+        )
+    }
+
+    fn render_clip_chunk(
+        &self,
+        project: &ProjectConfiguration,
+        clip_index: u32,
+        start_samples: usize,
+        samples: usize,
+        out_offset: usize,
+        out: &mut [f32],
+    ) -> usize {
+        let Some(segment) = self.data.get(clip_index as usize) else {
             return 0;
         };
         let tracks = &segment.tracks;
@@ -308,7 +370,7 @@ impl AudioRenderer {
         let offsets = project
             .clips
             .iter()
-            .find(|c| c.index == self.cursor.clip_index)
+            .find(|c| c.index == clip_index)
             .map(|c| c.offsets)
             .unwrap_or_default();
 
@@ -323,16 +385,22 @@ impl AudioRenderer {
             .max()
             .unwrap_or(0);
 
-        if self.cursor.samples >= max_samples {
+        if start_samples >= max_samples {
             return 0;
         }
 
-        let samples = samples.min(max_samples - self.cursor.samples);
+        let samples = samples.min(max_samples - start_samples);
+
+        let render_datas = tracks
+            .iter()
+            .map(|t| t.render_data(&project.audio, self.wait_for_enhancement))
+            .collect::<Vec<_>>();
 
         let track_datas = tracks
             .iter()
-            .map(|t| AudioRendererTrack {
-                data: t.data().as_ref(),
+            .zip(&render_datas)
+            .map(|(t, data)| AudioRendererTrack {
+                data: data.as_ref(),
                 gain: if project.audio.mute {
                     f32::NEG_INFINITY
                 } else {
@@ -344,7 +412,93 @@ impl AudioRenderer {
             })
             .collect::<Vec<_>>();
 
-        cap_audio::render_audio(&track_datas, self.cursor.samples, samples, out_offset, out)
+        cap_audio::render_audio(&track_datas, start_samples, samples, out_offset, out)
+    }
+
+    /// Re-renders the output samples `[frame_start, frame_start + samples)` that fall inside a
+    /// clip transition as a crossfade of both clips. Dip-to-color transitions fade the outgoing
+    /// clip out and the incoming one in back to back; every other kind uses an equal-power
+    /// crossfade. Speed-changed segments are silent, so a transition next to one fades the
+    /// other clip out (or in) against that silence.
+    fn apply_transitions(
+        &self,
+        project: &ProjectConfiguration,
+        timeline: &TimelineConfiguration,
+        frame_start: usize,
+        samples: usize,
+        out: &mut [f32],
+    ) {
+        let frame_end = frame_start + samples;
+
+        for transition in timeline.transitions() {
+            let outgoing_audible = transition.outgoing.timescale == 1.0;
+            let incoming_audible = transition.incoming.timescale == 1.0;
+            if !outgoing_audible && !incoming_audible {
+                continue;
+            }
+
+            let start = self.playhead_to_samples(transition.start);
+            let end = self.playhead_to_samples(transition.end);
+            let lo = start.max(frame_start);
+            let hi = end.min(frame_end);
+            if lo >= hi {
+                continue;
+            }
+
+            let len = hi - lo;
+            let lo_time = lo as f64 / Self::SAMPLE_RATE as f64;
+
+            let mut outgoing = vec![0.0; len * 2];
+            if outgoing_audible {
+                self.render_clip_chunk(
+                    project,
+                    transition.outgoing.recording_clip,
+                    // Past the end of its recording the clip is silent, so it needn't be held.
+                    self.playhead_to_samples(transition.outgoing_time(lo_time, f64::INFINITY)),
+                    len,
+                    0,
+                    &mut outgoing,
+                );
+            }
+
+            // The incoming clip can't start before its recording does; leave that part silent.
+            let boundary = self.playhead_to_samples(transition.boundary) as isize;
+            let incoming_start = self.playhead_to_samples(transition.incoming.start) as isize
+                + (lo as isize - boundary);
+            let skip = (-incoming_start).clamp(0, len as isize) as usize;
+            let mut incoming = vec![0.0; len * 2];
+            if incoming_audible {
+                self.render_clip_chunk(
+                    project,
+                    transition.incoming.recording_clip,
+                    incoming_start.max(0) as usize,
+                    len - skip,
+                    skip * 2,
+                    &mut incoming,
+                );
+            }
+
+            for (i, out_sample) in (lo..hi).enumerate() {
+                let progress = ((out_sample - start) as f32 / (end - start) as f32).clamp(0.0, 1.0);
+                let (outgoing_gain, incoming_gain) = match transition.kind {
+                    TransitionKind::DipToColor { .. } => (
+                        (1.0 - progress * 2.0).max(0.0),
+                        (progress * 2.0 - 1.0).max(0.0),
+                    ),
+                    _ => (
+                        (progress * std::f32::consts::FRAC_PI_2).cos(),
+                        (progress * std::f32::consts::FRAC_PI_2).sin(),
+                    ),
+                };
+
+                let out_index = (out_sample - frame_start) * 2;
+                for channel in 0..2 {
+                    out[out_index + channel] = (outgoing[i * 2 + channel] * outgoing_gain
+                        + incoming[i * 2 + channel] * incoming_gain)
+                        .clamp(-1.0, 1.0);
+                }
+            }
+        }
     }
 }
 
@@ -880,6 +1034,7 @@ mod tests {
                         start: 0.0,
                         end: 1.0,
                         name: None,
+                        transition: None,
                     },
                     TimelineSegment {
                         recording_clip: 0,
@@ -887,6 +1042,7 @@ mod tests {
                         start: 1.0,
                         end: 2.0,
                         name: None,
+                        transition: None,
                     },
                     TimelineSegment {
                         recording_clip: 0,
@@ -894,6 +1050,7 @@ mod tests {
                         start: 2.0,
                         end: 3.0,
                         name: None,
+                        transition: None,
                     },
                     TimelineSegment {
                         recording_clip: 1,
@@ -901,6 +1058,7 @@ mod tests {
                         start: 0.0,
                         end: 1.0,
                         name: None,
+                        transition: None,
                     },
                     TimelineSegment {
                         recording_clip: 1,
@@ -908,6 +1066,7 @@ mod tests {
                         start: 1.0,
                         end: 2.0,
                         name: None,
+                        transition: None,
                     },
                     TimelineSegment {
                         recording_clip: 1,
@@ -915,6 +1074,7 @@ mod tests {
                         start: 2.0,
                         end: 3.0,
                         name: None,
+                        transition: None,
                     },
                 ],
                 zoom_segments: Vec::new(),
@@ -924,6 +1084,7 @@ mod tests {
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
             }),
             clips: vec![
                 ClipConfiguration {
@@ -1022,6 +1183,7 @@ mod tests {
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
             }),
             clips: vec![ClipConfiguration {
                 index: 0,
@@ -1040,6 +1202,7 @@ mod tests {
             start,
             end,
             name: None,
+            transition: None,
         }
     }
 
@@ -1112,6 +1275,7 @@ mod tests {
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
             }),
             clips: vec![ClipConfiguration {
                 index: 0,
@@ -1150,6 +1314,7 @@ mod tests {
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
             }),
             clips: vec![ClipConfiguration {
                 index: 0,
@@ -1183,6 +1348,88 @@ mod tests {
         assert_eq!(left_at_second(&playback_stream, 0), 0.0);
     }
 
+    #[test]
+    fn improve_enhances_voice_identically_in_playback_and_export() {
+        // A quiet 300Hz voice over a hiss floor, mono like most mic tracks.
+        let mut seed = 11u32;
+        let samples = (0..3 * AudioData::SAMPLE_RATE as usize)
+            .map(|i| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let hiss = (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5;
+                let t = i as f32 / AudioData::SAMPLE_RATE as f32;
+                (std::f32::consts::TAU * 300.0 * t).sin() * 0.03 + hiss * 0.01
+            })
+            .collect();
+        let data = Arc::new(AudioData::from_raw_f32(samples, 1));
+        let voice = EnhancedVoice::pending(Arc::clone(&data));
+        let segments = vec![AudioSegment {
+            tracks: vec![
+                AudioSegmentTrack::new(data, gain, stereo, no_offset)
+                    .with_voice_enhancement(voice.clone()),
+            ],
+        }];
+        let mut project = ProjectConfiguration {
+            timeline: Some(TimelineConfiguration {
+                segments: vec![segment(0, 0.0, 3.0, 1.0)],
+                zoom_segments: Vec::new(),
+                scene_segments: Vec::new(),
+                mask_segments: Vec::new(),
+                text_segments: Vec::new(),
+                caption_segments: Vec::new(),
+                keyboard_segments: Vec::new(),
+                audio_segments: Vec::new(),
+                annotation_segments: Vec::new(),
+            }),
+            clips: vec![ClipConfiguration {
+                index: 0,
+                offsets: Default::default(),
+            }],
+            ..Default::default()
+        };
+
+        let raw_stream =
+            render_export_audio(&mut AudioRenderer::new(segments.clone()), &project, 30, 90);
+
+        project.audio.improve = true;
+
+        // Until the pass lands, playback keeps mixing the raw track.
+        let mut early_playback = AudioRenderer::new(segments.clone());
+        early_playback.set_playhead(0.0, &project);
+        let (_, early_chunk) = early_playback.render_frame_raw(1024, &project).unwrap();
+        assert!(voice.get().is_none());
+        assert_eq!(early_chunk, raw_stream[..2048]);
+
+        let export_stream = render_export_audio(
+            &mut AudioRenderer::new(segments.clone()).with_blocking_enhancement(),
+            &project,
+            30,
+            90,
+        );
+        assert!(voice.get().is_some());
+
+        // Playback renders fixed 1024-sample chunks from wherever the user
+        // seeks; the enhanced samples must not depend on either.
+        let mut playback_renderer = AudioRenderer::new(segments);
+        playback_renderer.set_playhead(1.5, &project);
+        let mut playback_stream = Vec::new();
+        while let Some((_, chunk)) = playback_renderer.render_frame_raw(1024, &project) {
+            playback_stream.extend(chunk);
+        }
+
+        let seek_offset = 3 * AudioData::SAMPLE_RATE as usize;
+        assert_eq!(playback_stream, export_stream[seek_offset..]);
+        assert_ne!(playback_stream, raw_stream[seek_offset..]);
+        assert!(
+            mean_abs(&export_stream) > mean_abs(&raw_stream) * 2.0,
+            "improve should bring the quiet voice up towards the loudness target"
+        );
+        assert!(
+            export_stream
+                .iter()
+                .all(|sample| sample.abs() <= 10.0_f32.powf(-1.0 / 20.0))
+        );
+    }
+
     // Time->sample conversion rounds to nearest (not truncates), so a fractional
     // sample position lands on the nearest sample rather than biasing downward.
     #[test]
@@ -1254,6 +1501,85 @@ mod tests {
         assert!((left_at_second(&stream, 1) - expected(values[3])).abs() < 0.01);
     }
 
+    fn left_at(stream: &[f32], time: f64) -> f32 {
+        stream[(time * AudioData::SAMPLE_RATE as f64) as usize * 2]
+    }
+
+    fn transition_fixture(kind: TransitionKind) -> (TempDir, AudioRenderer, ProjectConfiguration) {
+        transition_fixture_with_incoming_timescale(kind, 1.0)
+    }
+
+    fn transition_fixture_with_incoming_timescale(
+        kind: TransitionKind,
+        incoming_timescale: f64,
+    ) -> (TempDir, AudioRenderer, ProjectConfiguration) {
+        let mut outgoing = segment(0, 0.0, 2.0, 1.0);
+        outgoing.transition = Some(cap_project::ClipTransition {
+            kind,
+            duration: 1.0,
+        });
+
+        single_clip_fixture(
+            &[3000, 6000, 9000, 12000, 15000],
+            vec![outgoing, segment(0, 3.0, 5.0, incoming_timescale)],
+        )
+    }
+
+    // A 1s crossfade centred on the 2s cut blends source 1.5..2.5 of the
+    // outgoing segment with source 2.5..3.5 of the incoming one, at equal power.
+    #[test]
+    fn crossfade_transition_blends_both_clips_at_equal_power() {
+        let (_dir, mut renderer, project) = transition_fixture(TransitionKind::Crossfade);
+        let stream = render_export_audio(&mut renderer, &project, 30, 4 * 30);
+
+        let quarter = std::f32::consts::FRAC_PI_8;
+        let cases = [
+            (1.25, expected(6000)),
+            (
+                1.75,
+                expected(6000) * quarter.cos() + expected(9000) * quarter.sin(),
+            ),
+            (
+                2.25,
+                expected(9000) * (3.0 * quarter).cos() + expected(12000) * (3.0 * quarter).sin(),
+            ),
+            (3.5, expected(15000)),
+        ];
+        for (time, want) in cases {
+            let got = left_at(&stream, time);
+            assert!(
+                (got - want).abs() < 0.01,
+                "{time}s: read {got}, expected {want}"
+            );
+        }
+    }
+
+    #[test]
+    fn dip_to_color_transition_is_silent_at_the_cut() {
+        let (_dir, mut renderer, project) =
+            transition_fixture(TransitionKind::DipToColor { color: [0, 0, 0] });
+        let stream = render_export_audio(&mut renderer, &project, 30, 4 * 30);
+
+        assert!(left_at(&stream, 2.0).abs() < 0.001);
+        assert!((left_at(&stream, 1.75) - expected(6000) * 0.5).abs() < 0.01);
+        assert!((left_at(&stream, 2.25) - expected(12000) * 0.5).abs() < 0.01);
+    }
+
+    // The sped-up incoming segment is silent, so the outgoing clip fades out
+    // into it rather than cutting off at 2s.
+    #[test]
+    fn crossfade_into_a_speed_changed_segment_fades_the_audible_side() {
+        let (_dir, mut renderer, project) =
+            transition_fixture_with_incoming_timescale(TransitionKind::Crossfade, 2.0);
+        let stream = render_export_audio(&mut renderer, &project, 30, 3 * 30);
+
+        let quarter = std::f32::consts::FRAC_PI_8;
+        assert!((left_at(&stream, 1.25) - expected(6000)).abs() < 0.01);
+        assert!((left_at(&stream, 1.75) - expected(6000) * quarter.cos()).abs() < 0.01);
+        assert!((left_at(&stream, 2.25) - expected(9000) * (3.0 * quarter).cos()).abs() < 0.01);
+        assert!(left_at(&stream, 2.75).abs() < 0.001);
+    }
+
     fn music_track_segment(
         path: &str,
         start: f64,
@@ -1287,6 +1613,7 @@ mod tests {
                 caption_segments: Vec::new(),
                 keyboard_segments: Vec::new(),
                 audio_segments,
+                annotation_segments: Vec::new(),
             }),
             clips: vec![ClipConfiguration {
                 index: 0,
@@ -1,10 +1,11 @@
 use std::sync::Arc;
 use std::time::Instant;
 
-use cap_project::{CursorEvents, ProjectConfiguration};
+use cap_project::{ClipOffsets, CursorEvents, ProjectConfiguration, TransitionKind};
 use cap_rendering::{
-    DecodedSegmentFrames, FrameRenderer, Nv12RenderedFrame, ProjectUniforms, RenderVideoConstants,
-    RenderedFrame, RendererLayers,
+    DecodedSegmentFrames, FrameRenderStageTimings, FrameRenderer, Nv12RenderedFrame,
+    ProjectRecordingsMeta, ProjectUniforms, RenderVideoConstants, RenderedFrame, RendererLayers,
+    TransitionSide,
 };
 use tokio::sync::{mpsc, oneshot};
 
@@ -21,6 +22,7 @@ pub enum RendererMessage {
         uniforms: ProjectUniforms,
         finished: oneshot::Sender<()>,
         cursor: Arc<CursorEvents>,
+        transition: Option<TransitionFrame>,
         queued_at: Instant,
     },
     Stop {
@@ -28,6 +30,83 @@ pub enum RendererMessage {
     },
 }
 
+/// Where the other clip of a frame inside a transition comes from. The frame's own clip is the
+/// one under the playhead, which is the outgoing clip before the cut and the incoming one after.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TransitionSource {
+    pub segment_index: u32,
+    pub segment_time: f64,
+    pub offsets: ClipOffsets,
+}
+
+impl TransitionSource {
+    pub fn new(
+        project: &ProjectConfiguration,
+        recordings: &ProjectRecordingsMeta,
+        frame_time: f64,
+    ) -> Option<Self> {
+        let transition = project.get_transition(frame_time)?;
+        let (segment, segment_time) = if frame_time < transition.boundary {
+            (transition.incoming, transition.incoming_time(frame_time))
+        } else {
+            let outgoing_end = recordings
+                .segments
+                .get(transition.outgoing.recording_clip as usize)?
+                .display
+                .last_frame_time();
+            (
+                transition.outgoing,
+                transition.outgoing_time(frame_time, outgoing_end),
+            )
+        };
+
+        Some(Self {
+            segment_index: segment.recording_clip,
+            segment_time,
+            offsets: project
+                .clips
+                .iter()
+                .find(|v| v.index == segment.recording_clip)
+                .map(|v| v.offsets)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// The other clip of a frame inside a transition, blended with the frame's own clip so
+/// playback matches the export.
+pub struct TransitionFrame {
+    pub kind: TransitionKind,
+    pub progress: f32,
+    /// Whether this is the incoming clip, i.e. the frame's own clip is the outgoing one.
+    pub incoming: bool,
+    pub segment_frames: DecodedSegmentFrames,
+    pub uniforms: ProjectUniforms,
+    pub cursor: Arc<CursorEvents>,
+}
+
+impl TransitionFrame {
+    /// `None` once the frame is no longer inside a transition, e.g. after an edit.
+    pub fn new(
+        project: &ProjectConfiguration,
+        frame_time: f64,
+        segment_frames: DecodedSegmentFrames,
+        uniforms: ProjectUniforms,
+        cursor: Arc<CursorEvents>,
+    ) -> Option<Self> {
+        let transition = project.get_transition(frame_time)?;
+
+        Some(Self {
+            kind: transition.kind,
+            progress: transition.progress(frame_time) as f32,
+            incoming: frame_time < transition.boundary,
+            segment_frames,
+            uniforms,
+            cursor,
+        })
+    }
+}
+
 pub enum EditorFrameOutput {
     Rgba(RenderedFrame),
     Nv12(Nv12RenderedFrame),
@@ -146,6 +225,7 @@ impl Renderer {
             uniforms: ProjectUniforms,
             finished: oneshot::Sender<()>,
             cursor: Arc<CursorEvents>,
+            transition: Option<TransitionFrame>,
             queued_at: Instant,
         }
 
@@ -165,12 +245,14 @@ impl Renderer {
                         uniforms,
                         finished,
                         cursor,
+                        transition,
                         queued_at,
                     }) => Some(PendingFrame {
                         segment_frames,
                         uniforms,
                         finished,
                         cursor,
+                        transition,
                         queued_at,
                     }),
                     Some(RendererMessage::Stop { finished }) => {
@@ -197,6 +279,7 @@ impl Renderer {
                         uniforms,
                         finished,
                         cursor,
+                        transition,
                         queued_at,
                     } => {
                         let dropped_frame_number = current.uniforms.frame_number;
@@ -213,6 +296,7 @@ impl Renderer {
                             uniforms,
                             finished,
                             cursor,
+                            transition,
                             queued_at,
                         };
                         drained_count += 1;
@@ -242,16 +326,43 @@ impl Renderer {
 
             let render_start = Instant::now();
             let input_frame_number = current.uniforms.frame_number;
-            match frame_renderer
-                .render_immediate_with_timings(
-                    current.segment_frames,
-                    current.uniforms,
-                    &current.cursor,
-                    true,
-                    &mut layers,
-                )
-                .await
-            {
+            let rendered = match current.transition {
+                Some(transition) => {
+                    let own = TransitionSide {
+                        segment_frames: current.segment_frames,
+                        uniforms: current.uniforms,
+                        cursor: &current.cursor,
+                        render_display: true,
+                    };
+                    let other = TransitionSide {
+                        segment_frames: transition.segment_frames,
+                        uniforms: transition.uniforms,
+                        cursor: &transition.cursor,
+                        render_display: true,
+                    };
+                    let sides = if transition.incoming {
+                        [own, other]
+                    } else {
+                        [other, own]
+                    };
+                    frame_renderer
+                        .render_transition(transition.kind, transition.progress, sides, &mut layers)
+                        .await
+                        .map(|frame| (frame, FrameRenderStageTimings::default()))
+                }
+                None => {
+                    frame_renderer
+                        .render_immediate_with_timings(
+                            current.segment_frames,
+                            current.uniforms,
+                            &current.cursor,
+                            true,
+                            &mut layers,
+                        )
+                        .await
+                }
+            };
+            match rendered {
                 Ok((frame, render_stage_timings)) => {
                     let render_duration = render_start.elapsed();
                     let frame_number = frame.frame_number;
@@ -313,6 +424,7 @@ impl RendererHandle {
         segment_frames: DecodedSegmentFrames,
         uniforms: ProjectUniforms,
         cursor: Arc<CursorEvents>,
+        transition: Option<TransitionFrame>,
     ) {
         let (finished_tx, _finished_rx) = oneshot::channel();
         let frame_number = uniforms.frame_number;
@@ -323,6 +435,7 @@ impl RendererHandle {
                 uniforms,
                 finished: finished_tx,
                 cursor,
+                transition,
                 queued_at: Instant::now(),
             })
             .is_err()
@@ -337,6 +450,7 @@ impl RendererHandle {
         segment_frames: DecodedSegmentFrames,
         uniforms: ProjectUniforms,
         cursor: Arc<CursorEvents>,
+        transition: Option<TransitionFrame>,
     ) {
         let (finished_tx, _finished_rx) = oneshot::channel();
         let frame_number = uniforms.frame_number;
@@ -345,6 +459,7 @@ impl RendererHandle {
             uniforms,
             finished: finished_tx,
             cursor,
+            transition,
             queued_at: Instant::now(),
         };
         if self.tx.blocking_send(msg).is_err()
@@ -359,6 +474,7 @@ impl RendererHandle {
         segment_frames: DecodedSegmentFrames,
         uniforms: ProjectUniforms,
         cursor: Arc<CursorEvents>,
+        transition: Option<TransitionFrame>,
     ) -> bool {
         let (finished_tx, finished_rx) = oneshot::channel();
         let frame_number = uniforms.frame_number;
@@ -367,6 +483,7 @@ impl RendererHandle {
             uniforms,
             finished: finished_tx,
             cursor,
+            transition,
             queued_at: Instant::now(),
         };
         if self.tx.blocking_send(msg).is_err() {
@@ -1,4 +1,5 @@
-use crate::editor;
+use crate::editor::{self, TransitionFrame, TransitionSource};
+use crate::enhance::EnhancedVoice;
 use crate::playback::{self, PlaybackHandle, PlaybackStartError};
 use cap_audio::AudioData;
 use cap_project::StudioRecordingMeta;
@@ -148,6 +149,7 @@ impl EditorInstance {
                             end: duration,
                             timescale: 1.0,
                             name: None,
+                            transition: None,
                         }],
                         _ => {
                             warn!(
@@ -180,6 +182,7 @@ impl EditorInstance {
                             end: duration,
                             timescale: 1.0,
                             name: None,
+                            transition: None,
                         })
                     })
                     .collect(),
@@ -195,6 +198,7 @@ impl EditorInstance {
                     caption_segments: Vec::new(),
                     keyboard_segments: Vec::new(),
                     audio_segments: Vec::new(),
+                    annotation_segments: Vec::new(),
                 });
 
                 if let Err(e) = project.write(&recording_meta.project_path) {
@@ -267,6 +271,11 @@ impl EditorInstance {
         let layers_rx = editor::start_renderer_layers_creation(&render_constants, &project);
 
         let segments = create_segments(&recording_meta, meta.as_ref(), false).await?;
+        if project.audio.improve {
+            for voice in segments.iter().filter_map(|s| s.enhanced_audio.as_ref()) {
+                voice.prepare();
+            }
+        }
         let layers_rx = editor::finish_renderer_layers_creation(layers_rx).await;
 
         let renderer = Arc::new(editor::Renderer::spawn(
@@ -342,6 +351,37 @@ impl EditorInstance {
         (self.on_state_change)(&state);
     }
 
+    /// Steps the project config back through its on-disk history, which survives editor restarts.
+    /// Returns the restored config, or `None` when there is nothing to undo.
+    pub async fn undo_project_config(&self) -> Result<Option<ProjectConfiguration>, String> {
+        self.step_project_config(ProjectConfiguration::undo).await
+    }
+
+    /// Re-applies the edit most recently undone with [`Self::undo_project_config`].
+    pub async fn redo_project_config(&self) -> Result<Option<ProjectConfiguration>, String> {
+        self.step_project_config(ProjectConfiguration::redo).await
+    }
+
+    async fn step_project_config(
+        &self,
+        step: fn(PathBuf) -> std::io::Result<Option<ProjectConfiguration>>,
+    ) -> Result<Option<ProjectConfiguration>, String> {
+        let project_path = self.project_path.clone();
+        let config = tokio::task::spawn_blocking(move || step(project_path))
+            .await
+            .map_err(|e| format!("Project history task failed: {e}"))?
+            .map_err(|e| format!("Failed to step project history: {e}"))?;
+
+        if let Some(config) = &config {
+            self.modify_and_emit_state(|_| {
+                self.project_config.0.send(config.clone()).ok();
+            })
+            .await;
+        }
+
+        Ok(config)
+    }
+
     /// Decodes (and caches) the music tracks referenced by the current project
     /// config off the async runtime so playback start isn't blocked by ffmpeg.
     async fn load_music_tracks(&self) -> crate::MusicTracks {
@@ -374,6 +414,7 @@ impl EditorInstance {
                 render_constants: self.render_constants.clone(),
                 start_frame_number,
                 project: self.project_config.0.subscribe(),
+                recordings: self.recordings.clone(),
                 telemetry: None,
             })
             .start(fps, resolution_base)
@@ -569,8 +610,21 @@ impl EditorInstance {
                                     total_duration,
                                     &zoom_focus_interpolator,
                                 );
-                                self.renderer
-                                    .render_frame(segment_frames, uniforms, segment_medias.cursor.clone());
+                                let transition = self
+                                    .preview_transition_frame(
+                                        &project,
+                                        frame_number,
+                                        fps,
+                                        resolution_base,
+                                        total_duration,
+                                    )
+                                    .await;
+                                self.renderer.render_frame(
+                                    segment_frames,
+                                    uniforms,
+                                    segment_medias.cursor.clone(),
+                                    transition,
+                                );
                             } else {
                                 warn!("Preview renderer: no frames returned for frame {}", frame_number);
                             }
@@ -583,6 +637,65 @@ impl EditorInstance {
         })
     }
 
+    /// Decodes the other clip of a preview frame that falls inside a transition.
+    async fn preview_transition_frame(
+        &self,
+        project: &ProjectConfiguration,
+        frame_number: u32,
+        fps: u32,
+        resolution_base: XY<u32>,
+        total_duration: f64,
+    ) -> Option<TransitionFrame> {
+        let frame_time = frame_number as f64 / fps as f64;
+        let source = TransitionSource::new(project, &self.recordings, frame_time)?;
+        let segment_medias = self.segment_medias.get(source.segment_index as usize)?;
+        let segment_frames = segment_medias
+            .decoders
+            .get_frames(
+                source.segment_time as f32,
+                !project.camera.hide,
+                true,
+                source.offsets,
+            )
+            .await?;
+
+        let zoom_focus_interpolator = ZoomFocusInterpolator::new_arc(
+            segment_medias.cursor.clone(),
+            (!project.cursor.raw).then_some(SpringMassDamperSimulationConfig {
+                tension: project.cursor.tension,
+                mass: project.cursor.mass,
+                friction: project.cursor.friction,
+            }),
+            project.cursor.click_spring_config(),
+            project.screen_movement_spring,
+            total_duration,
+            project
+                .timeline
+                .as_ref()
+                .map(|t| t.zoom_segments.as_slice())
+                .unwrap_or(&[]),
+        );
+        let uniforms = ProjectUniforms::new(
+            &self.render_constants,
+            project,
+            frame_number,
+            fps,
+            resolution_base,
+            &segment_medias.cursor,
+            &segment_frames,
+            total_duration,
+            &zoom_focus_interpolator,
+        );
+
+        TransitionFrame::new(
+            project,
+            frame_time,
+            segment_frames,
+            uniforms,
+            segment_medias.cursor.clone(),
+        )
+    }
+
     fn get_studio_meta(&self) -> &StudioRecordingMeta {
         match &self.meta.inner {
             RecordingMetaInner::Studio(meta) => meta.as_ref(),
@@ -638,6 +751,8 @@ pub struct EditorState {
 
 pub struct SegmentMedia {
     pub audio: Option<Arc<AudioData>>,
+    /// `audio` run through the voice enhancement chain, computed on first use.
+    pub enhanced_audio: Option<EnhancedVoice>,
     pub system_audio: Option<Arc<AudioData>>,
     pub audio_timing_repair: SegmentAudioTimingRepair,
     pub cursor: Arc<CursorEvents>,
@@ -848,6 +963,7 @@ pub async fn create_segments(
             let decoders = decoders?;
 
             Ok(vec![SegmentMedia {
+                enhanced_audio: audio.clone().map(EnhancedVoice::new),
                 audio,
                 system_audio: None,
                 audio_timing_repair: SegmentAudioTimingRepair {
@@ -904,6 +1020,7 @@ pub async fn create_segments(
                 let decoders = decoders?;
 
                 segments.push(SegmentMedia {
+                    enhanced_audio: audio.clone().map(EnhancedVoice::new),
                     audio,
                     system_audio,
                     audio_timing_repair: SegmentAudioTimingRepair {
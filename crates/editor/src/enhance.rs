@@ -0,0 +1,749 @@
+//! Offline voice enhancement behind `AudioConfiguration::improve`.
+//!
+//! The chain runs once over a whole decoded mic track rather than per render
+//! chunk, so playback (from any playhead), prerendered preview and export all
+//! read the exact same enhanced samples. Stages, in order:
+//!
+//! 1. 4th-order Butterworth high-pass to strip rumble and handling noise.
+//! 2. STFT spectral subtraction against a minimum-tracked noise floor.
+//! 3. Split-band de-esser that only ducks the band above the crossover.
+//! 4. Static gain to bring the track to [`TARGET_LUFS`] (BS.1770 gated).
+//! 5. Lookahead peak limiter so the gain can never push peaks past the ceiling.
+
+use cap_audio::{AudioData, LoudnessMeter};
+use std::{
+    collections::VecDeque,
+    f64::consts::{FRAC_1_SQRT_2, TAU},
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+use tracing::{info, warn};
+
+const SAMPLE_RATE: f64 = AudioData::SAMPLE_RATE as f64;
+
+const TARGET_LUFS: f64 = -16.0;
+
+const HIGH_PASS_HZ: f64 = 80.0;
+
+const FFT_SIZE: usize = 1024;
+const HOP_SIZE: usize = FFT_SIZE / 2;
+const NOISE_OVERSUBTRACTION: f32 = 2.0;
+// -14dB: deeper than this and the residual noise starts to "swirl".
+const NOISE_FLOOR_GAIN: f32 = 0.2;
+const POWER_SMOOTHING: f32 = 0.7;
+// The noise floor is the minimum smoothed power over the last
+// `NOISE_WINDOWS * NOISE_WINDOW_HOPS` hops (~1.5s), tracked in sub-windows so
+// the estimate follows a changing floor without keeping the full history.
+// Any pause in speech inside that span pins the estimate to the real floor.
+const NOISE_WINDOWS: usize = 8;
+const NOISE_WINDOW_HOPS: usize = 18;
+// The minimum of a fluctuating power sits well below its mean.
+const NOISE_MIN_BIAS: f32 = 2.5;
+const GAIN_SMOOTHING: f32 = 0.5;
+
+const DEESS_CROSSOVER_HZ: f64 = 5_000.0;
+// High band energy relative to the full band; sibilants sit around -1..-3dB,
+// voiced speech around -20dB.
+const DEESS_THRESHOLD_DB: f32 = -10.0;
+const DEESS_RATIO: f32 = 4.0;
+const DEESS_MAX_REDUCTION_DB: f32 = 12.0;
+const DEESS_ATTACK_SECS: f64 = 0.001;
+const DEESS_RELEASE_SECS: f64 = 0.06;
+
+const MAX_NORMALIZE_GAIN_DB: f64 = 20.0;
+
+const LIMITER_CEILING_DB: f32 = -1.0;
+const LIMITER_LOOKAHEAD: usize = AudioData::SAMPLE_RATE as usize / 200;
+const LIMITER_RELEASE_SECS: f64 = 0.08;
+
+fn db_to_linear(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+fn smoothing_coefficient(secs: f64) -> f32 {
+    (-1.0 / (secs * SAMPLE_RATE)).exp() as f32
+}
+
+/// The enhanced version of one decoded mic track, owned by its
+/// [`SegmentMedia`](crate::SegmentMedia) and shared by every renderer built
+/// from it (each playback start, prerender, export), so the chain runs at most
+/// once per track.
+#[derive(Clone)]
+pub struct EnhancedVoice(Arc<EnhancedVoiceState>);
+
+struct EnhancedVoiceState {
+    source: Arc<AudioData>,
+    started: AtomicBool,
+    enhanced: OnceLock<Arc<AudioData>>,
+}
+
+impl EnhancedVoice {
+    pub fn new(source: Arc<AudioData>) -> Self {
+        Self(Arc::new(EnhancedVoiceState {
+            source,
+            started: AtomicBool::new(false),
+            enhanced: OnceLock::new(),
+        }))
+    }
+
+    /// A voice whose background pass never starts, so tests can observe the
+    /// raw fallback until they run the pass themselves with [`Self::wait`].
+    #[cfg(test)]
+    pub(crate) fn pending(source: Arc<AudioData>) -> Self {
+        let voice = Self::new(source);
+        voice.0.started.store(true, Ordering::Release);
+        voice
+    }
+
+    /// Starts the pass on a background thread unless it already ran or is running.
+    pub fn prepare(&self) {
+        if self.0.enhanced.get().is_some() || self.0.started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let state = Arc::clone(&self.0);
+        let spawned = std::thread::Builder::new()
+            .name("voice-enhancement".into())
+            .spawn(move || {
+                state.wait();
+            });
+
+        if let Err(e) = spawned {
+            warn!("Failed to spawn voice enhancement thread: {e}");
+            self.0.started.store(false, Ordering::Release);
+        }
+    }
+
+    /// The enhanced track if the pass has finished, without waiting for it.
+    pub fn get(&self) -> Option<Arc<AudioData>> {
+        self.0.enhanced.get().cloned()
+    }
+
+    /// The enhanced track, running the pass on this thread (or waiting for the
+    /// background one) if it isn't ready yet. For offline consumers like export.
+    pub fn wait(&self) -> Arc<AudioData> {
+        self.0.wait()
+    }
+}
+
+impl EnhancedVoiceState {
+    fn wait(&self) -> Arc<AudioData> {
+        Arc::clone(self.enhanced.get_or_init(|| {
+            let started = Instant::now();
+            let enhanced = enhance_voice(&self.source);
+            info!(
+                samples = self.source.sample_count(),
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "Enhanced mic track"
+            );
+            Arc::new(enhanced)
+        }))
+    }
+}
+
+fn enhance_voice(data: &AudioData) -> AudioData {
+    let channels = data.channels().max(1) as usize;
+
+    let mut planes = (0..channels)
+        .map(|channel| {
+            data.samples()
+                .iter()
+                .skip(channel)
+                .step_by(channels)
+                .copied()
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    for plane in &mut planes {
+        high_pass(plane);
+        suppress_noise(plane);
+    }
+    de_ess(&mut planes);
+
+    let frames = planes.first().map_or(0, Vec::len);
+    let mut samples = Vec::with_capacity(frames * channels);
+    for frame in 0..frames {
+        samples.extend(planes.iter().map(|plane| plane[frame]));
+    }
+
+    normalize_loudness(&mut samples, channels);
+    limit(&mut samples, channels);
+
+    AudioData::from_raw_f32(samples, data.channels())
+}
+
+#[derive(Clone, Copy)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl Biquad {
+    fn normalized(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+            x1: 0.0,
+            x2: 0.0,
+            y1: 0.0,
+            y2: 0.0,
+        }
+    }
+
+    fn high_pass(cutoff_hz: f64, q: f64) -> Self {
+        let w0 = TAU * cutoff_hz / SAMPLE_RATE;
+        let cos = w0.cos();
+        let alpha = w0.sin() / (2.0 * q);
+        Self::normalized(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let x = x as f64;
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2
+            - self.a1 * self.y1
+            - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y as f32
+    }
+}
+
+fn high_pass(plane: &mut [f32]) {
+    // Butterworth Q values for the two sections of a 4th-order filter.
+    let mut sections = [
+        Biquad::high_pass(HIGH_PASS_HZ, 0.541_196_100_146_197),
+        Biquad::high_pass(HIGH_PASS_HZ, 1.306_562_964_876_376_5),
+    ];
+
+    for sample in plane {
+        *sample = sections
+            .iter_mut()
+            .fold(*sample, |value, section| section.process(value));
+    }
+}
+
+/// In-place iterative radix-2 FFT over split real/imaginary buffers.
+struct Fft {
+    twiddles: Vec<(f32, f32)>,
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    fn new(size: usize) -> Self {
+        let bits = size.trailing_zeros();
+        Self {
+            twiddles: (0..size / 2)
+                .map(|k| {
+                    let angle = -TAU * k as f64 / size as f64;
+                    (angle.cos() as f32, angle.sin() as f32)
+                })
+                .collect(),
+            reversed: (0..size)
+                .map(|i| i.reverse_bits() >> (usize::BITS - bits))
+                .collect(),
+        }
+    }
+
+    fn transform(&self, re: &mut [f32], im: &mut [f32], inverse: bool) {
+        let size = re.len();
+
+        for (i, &j) in self.reversed.iter().enumerate() {
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= size {
+            let stride = size / len;
+            for start in (0..size).step_by(len) {
+                for k in 0..len / 2 {
+                    let (cos, sin) = self.twiddles[k * stride];
+                    let sin = if inverse { -sin } else { sin };
+                    let (a, b) = (start + k, start + k + len / 2);
+                    let tr = re[b] * cos - im[b] * sin;
+                    let ti = re[b] * sin + im[b] * cos;
+                    re[b] = re[a] - tr;
+                    im[b] = im[a] - ti;
+                    re[a] += tr;
+                    im[a] += ti;
+                }
+            }
+            len *= 2;
+        }
+
+        if inverse {
+            let scale = 1.0 / size as f32;
+            re.iter_mut().for_each(|v| *v *= scale);
+            im.iter_mut().for_each(|v| *v *= scale);
+        }
+    }
+}
+
+/// Spectral subtraction with a minimum-statistics noise estimate.
+///
+/// Uses a sqrt-Hann window for analysis and synthesis at 50% overlap, which
+/// reconstructs the input exactly when every bin gain is 1.
+fn suppress_noise(plane: &mut [f32]) {
+    if plane.is_empty() {
+        return;
+    }
+
+    let fft = Fft::new(FFT_SIZE);
+    let window = (0..FFT_SIZE)
+        .map(|i| (std::f64::consts::PI * i as f64 / FFT_SIZE as f64).sin() as f32)
+        .collect::<Vec<_>>();
+    let bins = FFT_SIZE / 2 + 1;
+
+    // Lead with one hop of silence so the first real samples are covered by
+    // two overlapping windows like every other sample.
+    let mut padded = vec![0.0; HOP_SIZE + plane.len() + FFT_SIZE];
+    padded[HOP_SIZE..HOP_SIZE + plane.len()].copy_from_slice(plane);
+    let mut output = vec![0.0; padded.len()];
+
+    let mut re = vec![0.0; FFT_SIZE];
+    let mut im = vec![0.0; FFT_SIZE];
+    let mut smoothed = vec![0.0_f32; bins];
+    let mut window_min = vec![f32::INFINITY; bins];
+    let mut past_minima = vec![vec![f32::INFINITY; bins]; NOISE_WINDOWS];
+    let mut gains = vec![1.0_f32; bins];
+    let mut hop = 0usize;
+
+    let mut start = 0;
+    while start + FFT_SIZE <= padded.len() {
+        for ((r, i), (sample, w)) in re
+            .iter_mut()
+            .zip(im.iter_mut())
+            .zip(padded[start..start + FFT_SIZE].iter().zip(&window))
+        {
+            *r = sample * w;
+            *i = 0.0;
+        }
+
+        fft.transform(&mut re, &mut im, false);
+
+        for (bin, ((smoothed, window_min), gain)) in smoothed
+            .iter_mut()
+            .zip(window_min.iter_mut())
+            .zip(gains.iter_mut())
+            .enumerate()
+        {
+            let power = re[bin] * re[bin] + im[bin] * im[bin];
+
+            *smoothed = if hop == 0 {
+                power
+            } else {
+                POWER_SMOOTHING * *smoothed + (1.0 - POWER_SMOOTHING) * power
+            };
+            *window_min = window_min.min(*smoothed);
+
+            let noise = past_minima
+                .iter()
+                .fold(*window_min, |noise, minima| noise.min(minima[bin]))
+                * NOISE_MIN_BIAS;
+
+            let target = if power > f32::EPSILON {
+                (1.0 - NOISE_OVERSUBTRACTION * noise / power)
+                    .max(0.0)
+                    .sqrt()
+                    .max(NOISE_FLOOR_GAIN)
+            } else {
+                NOISE_FLOOR_GAIN
+            };
+            // Gains drop immediately but recover over a couple of hops, which
+            // keeps isolated noise peaks from "chirping" through.
+            *gain = if target < *gain {
+                target
+            } else {
+                GAIN_SMOOTHING * *gain + (1.0 - GAIN_SMOOTHING) * target
+            };
+
+            re[bin] *= *gain;
+            im[bin] *= *gain;
+            if bin > 0 && bin < FFT_SIZE / 2 {
+                re[FFT_SIZE - bin] *= *gain;
+                im[FFT_SIZE - bin] *= *gain;
+            }
+        }
+
+        hop += 1;
+        if hop.is_multiple_of(NOISE_WINDOW_HOPS) {
+            let slot = (hop / NOISE_WINDOW_HOPS) % NOISE_WINDOWS;
+            past_minima[slot].copy_from_slice(&window_min);
+            window_min.fill(f32::INFINITY);
+        }
+
+        fft.transform(&mut re, &mut im, true);
+
+        for (out, (r, w)) in output[start..start + FFT_SIZE]
+            .iter_mut()
+            .zip(re.iter().zip(&window))
+        {
+            *out += r * w;
+        }
+
+        start += HOP_SIZE;
+    }
+
+    let len = plane.len();
+    plane.copy_from_slice(&output[HOP_SIZE..HOP_SIZE + len]);
+}
+
+/// Split-band de-esser: a high-pass at the crossover isolates the sibilance
+/// band, and `x - hp` is its exact complement, so only the high band is ever
+/// attenuated and unity gain reconstructs the input.
+fn de_ess(planes: &mut [Vec<f32>]) {
+    let frames = planes.first().map_or(0, Vec::len);
+    let mut splitters = vec![Biquad::high_pass(DEESS_CROSSOVER_HZ, FRAC_1_SQRT_2); planes.len()];
+    let mut highs = vec![0.0_f32; planes.len()];
+    let attack = smoothing_coefficient(DEESS_ATTACK_SECS);
+    let release = smoothing_coefficient(DEESS_RELEASE_SECS);
+    let follow = |envelope: f32, energy: f32| {
+        let coefficient = if energy > envelope { attack } else { release };
+        coefficient * envelope + (1.0 - coefficient) * energy
+    };
+
+    let mut high_envelope = 0.0_f32;
+    let mut full_envelope = 0.0_f32;
+
+    for frame in 0..frames {
+        let mut high_energy = 0.0;
+        let mut full_energy = 0.0;
+        for ((plane, splitter), high) in planes.iter().zip(&mut splitters).zip(&mut highs) {
+            let sample = plane[frame];
+            *high = splitter.process(sample);
+            high_energy += *high * *high;
+            full_energy += sample * sample;
+        }
+
+        high_envelope = follow(high_envelope, high_energy);
+        full_envelope = follow(full_envelope, full_energy);
+
+        if full_envelope <= 1e-9 {
+            continue;
+        }
+
+        let ratio_db = 10.0 * (high_envelope.max(1e-12) / full_envelope).log10();
+        let reduction_db = ((ratio_db - DEESS_THRESHOLD_DB).max(0.0) * (1.0 - 1.0 / DEESS_RATIO))
+            .min(DEESS_MAX_REDUCTION_DB);
+        if reduction_db <= 0.0 {
+            continue;
+        }
+
+        let duck = 1.0 - db_to_linear(-reduction_db);
+        for (plane, high) in planes.iter_mut().zip(&highs) {
+            plane[frame] -= high * duck;
+        }
+    }
+}
+
+fn normalize_loudness(samples: &mut [f32], channels: usize) {
+    let mut meter = LoudnessMeter::new(channels as u16);
+    meter.push_interleaved(samples);
+
+    let Some(loudness) = meter.integrated_lufs() else {
+        return;
+    };
+
+    let gain_db = (TARGET_LUFS - loudness).clamp(-MAX_NORMALIZE_GAIN_DB, MAX_NORMALIZE_GAIN_DB);
+    let gain = db_to_linear(gain_db as f32);
+    samples.iter_mut().for_each(|sample| *sample *= gain);
+}
+
+/// Lookahead peak limiter.
+///
+/// Each frame's required gain is the minimum over the next
+/// `LIMITER_LOOKAHEAD` frames, relaxed by a one-pole release, then averaged
+/// over the lookahead window. Every term of that average already covers the
+/// frame it is applied to, so the result never exceeds the ceiling while
+/// still ramping into peaks instead of stepping.
+fn limit(samples: &mut [f32], channels: usize) {
+    let ceiling = db_to_linear(LIMITER_CEILING_DB);
+    let required = samples
+        .chunks_exact(channels)
+        .map(|frame| {
+            let peak = frame.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+            if peak > ceiling { ceiling / peak } else { 1.0 }
+        })
+        .collect::<Vec<_>>();
+
+    if required.iter().all(|gain| *gain >= 1.0) {
+        return;
+    }
+
+    let mut lookahead_min = vec![1.0_f32; required.len()];
+    let mut window = VecDeque::<usize>::new();
+    for (index, slot) in lookahead_min.iter_mut().enumerate().rev() {
+        while window
+            .back()
+            .is_some_and(|&j| required[j] >= required[index])
+        {
+            window.pop_back();
+        }
+        window.push_back(index);
+        while window
+            .front()
+            .is_some_and(|&j| j > index + LIMITER_LOOKAHEAD)
+        {
+            window.pop_front();
+        }
+        *slot = required[window[0]];
+    }
+
+    let release = smoothing_coefficient(LIMITER_RELEASE_SECS);
+    let mut released = 1.0_f32;
+    for gain in &mut lookahead_min {
+        released = (*gain).min(released + (1.0 - released) * (1.0 - release));
+        *gain = released;
+    }
+
+    let mut sum = 0.0_f64;
+    for (index, frame) in samples.chunks_exact_mut(channels).enumerate() {
+        sum += lookahead_min[index] as f64;
+        if index > LIMITER_LOOKAHEAD {
+            sum -= lookahead_min[index - LIMITER_LOOKAHEAD - 1] as f64;
+        }
+        let count = index.min(LIMITER_LOOKAHEAD) + 1;
+        let gain = (sum / count as f64) as f32;
+
+        for sample in frame {
+            *sample = (*sample * gain).clamp(-ceiling, ceiling);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: usize = AudioData::SAMPLE_RATE as usize;
+
+    // Deterministic white noise in [-1, 1] so the tests don't need `rand`.
+    struct Noise(u64);
+
+    impl Noise {
+        fn next(&mut self) -> f32 {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((self.0 >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+        }
+    }
+
+    fn tone(frequency: f64, amplitude: f32, index: usize) -> f32 {
+        amplitude * (TAU * frequency * index as f64 / SAMPLE_RATE).sin() as f32
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn ratio_db(after: f32, before: f32) -> f32 {
+        20.0 * (after / before).log10()
+    }
+
+    /// Two seconds of noise floor, two seconds of 300Hz "syllables" (a 2.5Hz
+    /// half-wave envelope, so there are short gaps like real speech) over the
+    /// same floor, then two more seconds of floor.
+    fn noisy_speech(voice_amplitude: f32, noise_amplitude: f32) -> AudioData {
+        let mut noise = Noise(7);
+        let samples = (0..6 * SECOND)
+            .map(|i| {
+                let voice = if (2 * SECOND..4 * SECOND).contains(&i) {
+                    let syllable = tone(2.5, 1.0, i).max(0.0);
+                    tone(300.0, voice_amplitude, i) * syllable
+                } else {
+                    0.0
+                };
+                voice + noise.next() * noise_amplitude
+            })
+            .collect();
+        AudioData::from_raw_f32(samples, 1)
+    }
+
+    #[test]
+    fn high_pass_removes_rumble_and_keeps_voice() {
+        let mut rumble = (0..SECOND).map(|i| tone(30.0, 0.5, i)).collect::<Vec<_>>();
+        let mut voice = (0..SECOND)
+            .map(|i| tone(1000.0, 0.5, i))
+            .collect::<Vec<_>>();
+        high_pass(&mut rumble);
+        high_pass(&mut voice);
+
+        let settled = SECOND / 2..SECOND;
+        assert!(ratio_db(rms(&rumble[settled.clone()]), 0.5 / 2f32.sqrt()) < -30.0);
+        assert!(ratio_db(rms(&voice[settled]), 0.5 / 2f32.sqrt()).abs() < 0.5);
+    }
+
+    #[test]
+    fn noise_suppression_attenuates_floor_between_speech() {
+        let input = noisy_speech(0.3, 0.02);
+        let mut plane = input.samples().to_vec();
+        suppress_noise(&mut plane);
+
+        // The trailing floor, once the minimum window holds only real noise.
+        let gap = 9 * SECOND / 2..6 * SECOND;
+        let speech = 5 * SECOND / 2..7 * SECOND / 2;
+
+        let gap_change = ratio_db(rms(&plane[gap.clone()]), rms(&input.samples()[gap]));
+        let speech_change = ratio_db(rms(&plane[speech.clone()]), rms(&input.samples()[speech]));
+
+        assert!(gap_change < -8.0, "noise only dropped {gap_change}dB");
+        assert!(speech_change.abs() < 1.0, "voice changed {speech_change}dB");
+    }
+
+    #[test]
+    fn noise_suppression_is_transparent_on_clean_signal() {
+        let input = noisy_speech(0.5, 0.0).samples().to_vec();
+        let mut plane = input.clone();
+        suppress_noise(&mut plane);
+
+        let error = plane
+            .iter()
+            .zip(&input)
+            .map(|(a, b)| a - b)
+            .collect::<Vec<_>>();
+        let error_db = ratio_db(rms(&error), rms(&input));
+        assert!(error_db < -30.0, "{error_db}dB");
+    }
+
+    #[test]
+    fn de_esser_ducks_sibilance_but_not_voice() {
+        let mut noise = Noise(3);
+        let mut sibilant_split = Biquad::high_pass(7_000.0, FRAC_1_SQRT_2);
+        let sibilant = (0..SECOND / 2)
+            .map(|_| sibilant_split.process(noise.next() * 0.5))
+            .collect::<Vec<_>>();
+        let voiced = (0..SECOND / 2)
+            .map(|i| tone(250.0, 0.5, i))
+            .collect::<Vec<_>>();
+
+        let mut planes = vec![sibilant.clone()];
+        de_ess(&mut planes);
+        let sibilant_change = ratio_db(rms(&planes[0]), rms(&sibilant));
+
+        let mut planes = vec![voiced.clone()];
+        de_ess(&mut planes);
+        let voiced_change = ratio_db(rms(&planes[0]), rms(&voiced));
+
+        assert!(
+            sibilant_change < -4.0,
+            "sibilance only dropped {sibilant_change}dB"
+        );
+        assert!(voiced_change.abs() < 0.1, "voice changed {voiced_change}dB");
+    }
+
+    #[test]
+    fn limiter_holds_ceiling_without_touching_quiet_audio() {
+        let mut samples = (0..SECOND)
+            .map(|i| {
+                let burst = if (SECOND / 2..SECOND / 2 + 480).contains(&i) {
+                    1.8
+                } else {
+                    0.2
+                };
+                tone(500.0, burst, i)
+            })
+            .collect::<Vec<_>>();
+        let before = samples.clone();
+        limit(&mut samples, 1);
+
+        let ceiling = db_to_linear(LIMITER_CEILING_DB);
+        assert!(samples.iter().all(|s| s.abs() <= ceiling));
+        // Well before the burst (beyond the lookahead) nothing changes.
+        assert_eq!(&samples[..SECOND / 4], &before[..SECOND / 4]);
+    }
+
+    #[test]
+    fn full_chain_normalizes_quiet_noisy_mic() {
+        let input = noisy_speech(0.05, 0.005);
+        let output = enhance_voice(&input);
+
+        assert_eq!(output.channels(), input.channels());
+        assert_eq!(output.sample_count(), input.sample_count());
+
+        let speech = 5 * SECOND / 2..7 * SECOND / 2;
+        let speech_only = AudioData::from_raw_f32(output.samples()[speech.clone()].to_vec(), 1);
+        let loudness = LoudnessMeter::measure(&speech_only).unwrap();
+        assert!((loudness - TARGET_LUFS).abs() < 1.5, "{loudness} LUFS");
+
+        let ceiling = db_to_linear(LIMITER_CEILING_DB);
+        assert!(output.samples().iter().all(|s| s.abs() <= ceiling));
+
+        // The floor comes up with the normalization gain, but far less than
+        // the voice does.
+        let gap = 9 * SECOND / 2..6 * SECOND;
+        let voice_gain = ratio_db(
+            rms(&output.samples()[speech.clone()]),
+            rms(&input.samples()[speech]),
+        );
+        let floor_gain = ratio_db(
+            rms(&output.samples()[gap.clone()]),
+            rms(&input.samples()[gap]),
+        );
+        assert!(
+            voice_gain - floor_gain > 8.0,
+            "{voice_gain} vs {floor_gain}"
+        );
+    }
+
+    #[test]
+    fn stereo_input_keeps_layout_and_channel_balance() {
+        let samples = (0..2 * SECOND)
+            .flat_map(|i| [tone(300.0, 0.1, i), tone(300.0, 0.05, i)])
+            .collect();
+        let output = enhance_voice(&AudioData::from_raw_f32(samples, 2));
+
+        assert_eq!(output.channels(), 2);
+        let left = output
+            .samples()
+            .iter()
+            .step_by(2)
+            .copied()
+            .collect::<Vec<_>>();
+        let right = output
+            .samples()
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .copied()
+            .collect::<Vec<_>>();
+        let balance = ratio_db(rms(&left[SECOND..]), rms(&right[SECOND..]));
+        assert!((balance - 6.02).abs() < 0.5, "{balance}");
+    }
+
+    #[test]
+    fn background_pass_matches_a_blocking_one() {
+        let voice = EnhancedVoice::new(Arc::new(noisy_speech(0.1, 0.01)));
+        assert!(voice.get().is_none());
+
+        voice.prepare();
+        let waited = voice.wait();
+        assert!(Arc::ptr_eq(&waited, &voice.get().unwrap()));
+        assert!(Arc::ptr_eq(&waited, &voice.clone().wait()));
+
+        let fresh = EnhancedVoice::new(Arc::new(noisy_speech(0.1, 0.01))).wait();
+        assert_eq!(waited.samples(), fresh.samples());
+    }
+}
@@ -1,16 +1,18 @@
 mod audio;
 mod editor;
 mod editor_instance;
+mod enhance;
 mod playback;
 mod segments;
 mod telemetry;
 
-pub use audio::{AudioRenderer, MusicTracks};
+pub use audio::{AudioRenderer, AudioSegment, MusicTracks};
 pub use editor::{
-    EditorFrameOutput, Renderer, RendererHandle, finish_renderer_layers_creation,
+    EditorFrameOutput, Renderer, RendererHandle, TransitionFrame, finish_renderer_layers_creation,
     start_renderer_layers_creation,
 };
 pub use editor_instance::{EditorInstance, EditorState, SegmentMedia, create_segments};
+pub use enhance::EnhancedVoice;
 pub use playback::{Playback, PlaybackEvent, PlaybackHandle, PlaybackStartError};
 pub use segments::{get_audio_segments, load_music_tracks, load_music_tracks_uncached};
 pub use telemetry::{
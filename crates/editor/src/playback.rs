@@ -5,8 +5,9 @@ use cap_media::MediaError;
 use cap_media_info::AudioInfo;
 use cap_project::{ProjectConfiguration, XY};
 use cap_rendering::{
-    DecodedSegmentFrames, PrecomputedCursorTimeline, ProjectUniforms, RenderVideoConstants,
-    ZoomFocusInterpolator, spring_mass_damper::SpringMassDamperSimulationConfig,
+    DecodedSegmentFrames, PrecomputedCursorTimeline, ProjectRecordingsMeta, ProjectUniforms,
+    RecordingSegmentDecoders, RenderVideoConstants, ZoomFocusInterpolator,
+    spring_mass_damper::SpringMassDamperSimulationConfig,
 };
 #[cfg(not(target_os = "windows"))]
 use cpal::{BufferSize, SupportedBufferSize};
@@ -33,7 +34,7 @@ use tracing::{error, info, warn};
 use crate::audio::AudioPlaybackBuffer;
 use crate::{
     audio::AudioSegment,
-    editor,
+    editor::{self, TransitionSource},
     editor_instance::SegmentMedia,
     segments::get_audio_segments,
     telemetry::{
@@ -111,6 +112,7 @@ pub struct Playback {
     pub render_constants: Arc<RenderVideoConstants>,
     pub start_frame_number: u32,
     pub project: watch::Receiver<ProjectConfiguration>,
+    pub recordings: Arc<ProjectRecordingsMeta>,
     pub segment_medias: Arc<Vec<SegmentMedia>>,
     pub music: crate::audio::MusicTracks,
     pub telemetry: Option<PlaybackTelemetry>,
@@ -133,10 +135,20 @@ struct PrefetchedFrame {
     frame_number: u32,
     segment_frames: DecodedSegmentFrames,
     segment_index: u32,
+    transition: Option<TransitionFrames>,
 }
 
+/// The other clip of a frame inside a transition, decoded alongside the frame's own clip.
+#[derive(Clone)]
+struct TransitionFrames {
+    segment_index: u32,
+    segment_frames: DecodedSegmentFrames,
+}
+
+type CachedFrame = (Arc<DecodedSegmentFrames>, u32, Option<TransitionFrames>);
+
 struct FrameCache {
-    cache: LruCache<u32, (Arc<DecodedSegmentFrames>, u32)>,
+    cache: LruCache<u32, CachedFrame>,
 }
 
 impl FrameCache {
@@ -146,10 +158,10 @@ impl FrameCache {
         }
     }
 
-    fn get(&mut self, frame_number: u32) -> Option<(Arc<DecodedSegmentFrames>, u32)> {
+    fn get(&mut self, frame_number: u32) -> Option<CachedFrame> {
         self.cache
             .get(&frame_number)
-            .map(|(frames, idx)| (Arc::clone(frames), *idx))
+            .map(|(frames, idx, transition)| (Arc::clone(frames), *idx, transition.clone()))
     }
 
     fn insert(
@@ -157,9 +169,10 @@ impl FrameCache {
         frame_number: u32,
         segment_frames: Arc<DecodedSegmentFrames>,
         segment_index: u32,
+        transition: Option<TransitionFrames>,
     ) {
         self.cache
-            .put(frame_number, (segment_frames, segment_index));
+            .put(frame_number, (segment_frames, segment_index, transition));
     }
 
     fn evict_far_from(&mut self, current_frame: u32, max_distance: u32) {
@@ -181,6 +194,79 @@ impl FrameCache {
     }
 }
 
+/// Where to decode the other clip of the frame at `frame_time`, if it falls inside a transition.
+fn transition_request(
+    project: &ProjectConfiguration,
+    recordings: &ProjectRecordingsMeta,
+    segment_medias: &[SegmentMedia],
+    frame_time: f64,
+) -> Option<(TransitionSource, RecordingSegmentDecoders)> {
+    let source = TransitionSource::new(project, recordings, frame_time)?;
+    let segment_media = segment_medias.get(source.segment_index as usize)?;
+    Some((source, segment_media.decoders.clone()))
+}
+
+async fn decode_transition_frames(
+    request: Option<(TransitionSource, RecordingSegmentDecoders)>,
+    needs_camera: bool,
+) -> Option<TransitionFrames> {
+    let (source, decoders) = request?;
+    let segment_frames = decoders
+        .get_frames(
+            source.segment_time as f32,
+            needs_camera,
+            true,
+            source.offsets,
+        )
+        .await?;
+
+    Some(TransitionFrames {
+        segment_index: source.segment_index,
+        segment_frames,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn transition_frame(
+    frames: Option<TransitionFrames>,
+    constants: &RenderVideoConstants,
+    project: &ProjectConfiguration,
+    segment_medias: &[SegmentMedia],
+    zoom_interpolators: &mut [ZoomFocusInterpolator],
+    cursor_timelines: &[Arc<PrecomputedCursorTimeline>],
+    frame_number: u32,
+    fps: u32,
+    resolution_base: XY<u32>,
+    duration: f64,
+) -> Option<editor::TransitionFrame> {
+    let frames = frames?;
+    let index = frames.segment_index as usize;
+    let segment_media = segment_medias.get(index)?;
+    let zoom_interpolator = zoom_interpolators.get_mut(index)?;
+    zoom_interpolator.ensure_precomputed_until((frame_number as f32 + 1.0) / fps as f32);
+
+    let uniforms = ProjectUniforms::new_with_precomputed_cursor(
+        constants,
+        project,
+        frame_number,
+        fps,
+        resolution_base,
+        &segment_media.cursor,
+        &frames.segment_frames,
+        duration,
+        zoom_interpolator,
+        cursor_timelines.get(index)?,
+    );
+
+    editor::TransitionFrame::new(
+        project,
+        frame_number as f64 / fps as f64,
+        frames.segment_frames,
+        uniforms,
+        segment_media.cursor.clone(),
+    )
+}
+
 impl Playback {
     pub async fn start(
         mut self,
@@ -224,6 +310,7 @@ impl Playback {
         let prefetch_stop_rx = stop_rx.clone();
         let mut prefetch_project = self.project.clone();
         let prefetch_segment_medias = self.segment_medias.clone();
+        let prefetch_recordings = self.recordings.clone();
         let (prefetch_duration, has_timeline) = self
             .project
             .borrow()
@@ -243,8 +330,14 @@ impl Playback {
             }
             type PrefetchFuture = std::pin::Pin<
                 Box<
-                    dyn std::future::Future<Output = (u32, u32, Option<DecodedSegmentFrames>)>
-                        + Send,
+                    dyn std::future::Future<
+                            Output = (
+                                u32,
+                                u32,
+                                Option<DecodedSegmentFrames>,
+                                Option<TransitionFrames>,
+                            ),
+                        > + Send,
                 >,
             >;
             let mut next_prefetch_frame = *frame_request_rx.borrow();
@@ -343,6 +436,12 @@ impl Playback {
                         let hide_camera = cached_project.camera.hide;
                         let segment_index = segment.recording_clip;
                         let is_initial = frames_decoded < 10;
+                        let transition = transition_request(
+                            &cached_project,
+                            &prefetch_recordings,
+                            &prefetch_segment_medias,
+                            prefetch_time,
+                        );
 
                         if let Ok(mut in_flight_guard) = prefetch_in_flight.write() {
                             in_flight_guard.insert(frame_num);
@@ -368,7 +467,9 @@ impl Playback {
                                     )
                                     .await
                             };
-                            (frame_num, segment_index, result)
+                            let transition =
+                                decode_transition_frames(transition, !hide_camera).await;
+                            (frame_num, segment_index, result, transition)
                         }));
                     }
 
@@ -413,6 +514,12 @@ impl Playback {
                             let decoders = segment_media.decoders.clone();
                             let hide_camera = cached_project.camera.hide;
                             let segment_index = segment.recording_clip;
+                            let transition = transition_request(
+                                &cached_project,
+                                &prefetch_recordings,
+                                &prefetch_segment_medias,
+                                prefetch_time,
+                            );
 
                             if let Ok(mut in_flight_guard) = prefetch_in_flight.write() {
                                 in_flight_guard.insert(behind_frame);
@@ -428,7 +535,9 @@ impl Playback {
                                         clip_offsets,
                                     )
                                     .await;
-                                (behind_frame, segment_index, result)
+                                let transition =
+                                    decode_transition_frames(transition, !hide_camera).await;
+                                (behind_frame, segment_index, result, transition)
                             }));
                         }
                     }
@@ -437,7 +546,7 @@ impl Playback {
                 tokio::select! {
                     biased;
 
-                    Some((frame_num, segment_index, result)) = in_flight.next() => {
+                    Some((frame_num, segment_index, result, transition)) = in_flight.next() => {
                         if let Ok(mut in_flight_guard) = prefetch_in_flight.write() {
                             in_flight_guard.remove(&frame_num);
                         }
@@ -448,6 +557,7 @@ impl Playback {
                                 frame_number: frame_num,
                                 segment_frames,
                                 segment_index,
+                                transition,
                             });
                         } else if frames_decoded <= 5 {
                             warn!(
@@ -677,6 +787,18 @@ impl Playback {
                         zoom_ref,
                         precomputed_cursor,
                     );
+                    let transition = transition_frame(
+                        prefetched.transition,
+                        &self.render_constants,
+                        &cached_project,
+                        &self.segment_medias,
+                        &mut zoom_interpolators,
+                        &cursor_timelines,
+                        frame_number,
+                        fps,
+                        resolution_base,
+                        duration,
+                    );
                     let uniforms_duration = uniforms_start.elapsed();
                     let submit_start = Instant::now();
                     let submitted_frame_number = frame_number;
@@ -684,6 +806,7 @@ impl Playback {
                         Arc::unwrap_or_clone(segment_frames),
                         uniforms,
                         segment_media.cursor.clone(),
+                        transition,
                     );
                     let submit_duration = submit_start.elapsed();
 
@@ -830,6 +953,7 @@ impl Playback {
                     Some((
                         Arc::new(prefetched.segment_frames),
                         prefetched.segment_index,
+                        prefetched.transition,
                     ))
                 } else {
                     let prefetched_idx = prefetch_buffer
@@ -843,6 +967,7 @@ impl Playback {
                         Some((
                             Arc::new(prefetched.segment_frames),
                             prefetched.segment_index,
+                            prefetched.transition,
                         ))
                     } else if prefetch_buffer.is_empty() {
                         let _ = frame_request_tx.send(frame_number);
@@ -865,6 +990,7 @@ impl Playback {
                                     Some((
                                         Arc::new(prefetched.segment_frames),
                                         prefetched.segment_index,
+                                        prefetched.transition,
                                     ))
                                 } else if prefetched.frame_number > frame_number {
                                     frame_source = PlaybackFrameSource::PrefetchWaitFuture;
@@ -882,6 +1008,7 @@ impl Playback {
                                     Some((
                                         Arc::new(prefetched.segment_frames),
                                         prefetched.segment_index,
+                                        prefetched.transition,
                                     ))
                                 } else {
                                     prefetch_buffer.push_back(prefetched);
@@ -965,6 +1092,7 @@ impl Playback {
                             Some((
                                 Arc::new(prefetched.segment_frames),
                                 prefetched.segment_index,
+                                prefetched.transition,
                             ))
                         } else {
                             let min_buffered = prefetch_buffer.iter().map(|p| p.frame_number).min();
@@ -1018,7 +1146,8 @@ impl Playback {
                 };
                 let frame_acquire_duration = frame_acquire_start.elapsed();
 
-                if let Some((segment_frames, segment_index)) = segment_frames_opt {
+                if let Some((segment_frames, segment_index, transition_frames)) = segment_frames_opt
+                {
                     let Some(segment_media) = self.segment_medias.get(segment_index as usize)
                     else {
                         frame_number = frame_number.saturating_add(1);
@@ -1030,6 +1159,7 @@ impl Playback {
                             frame_number,
                             Arc::clone(&segment_frames),
                             segment_index,
+                            transition_frames.clone(),
                         );
                     }
 
@@ -1070,6 +1200,18 @@ impl Playback {
                         zoom_ref,
                         precomputed_cursor,
                     );
+                    let transition = transition_frame(
+                        transition_frames,
+                        &self.render_constants,
+                        &cached_project,
+                        &self.segment_medias,
+                        &mut zoom_interpolators,
+                        &cursor_timelines,
+                        frame_number,
+                        fps,
+                        resolution_base,
+                        duration,
+                    );
                     let uniforms_duration = uniforms_start.elapsed();
                     let submit_start = Instant::now();
                     let submitted_frame_number = frame_number;
@@ -1077,6 +1219,7 @@ impl Playback {
                         Arc::unwrap_or_clone(segment_frames),
                         uniforms,
                         segment_media.cursor.clone(),
+                        transition,
                     );
                     let submit_duration = submit_start.elapsed();
 
@@ -7,6 +7,7 @@ use tracing::warn;
 use crate::{
     SegmentMedia,
     audio::{AudioSegment, AudioSegmentTrack, MusicTracks},
+    enhance::EnhancedVoice,
 };
 
 fn resolve_music_path(project_path: &Path, path: &str) -> std::path::PathBuf {
@@ -80,6 +81,10 @@ pub fn get_audio_segments(segments: &[SegmentMedia]) -> Vec<AudioSegment> {
         .map(|s| AudioSegment {
             tracks: [
                 s.audio.clone().map(|a| {
+                    let voice = s
+                        .enhanced_audio
+                        .clone()
+                        .unwrap_or_else(|| EnhancedVoice::new(Arc::clone(&a)));
                     AudioSegmentTrack::new(
                         a,
                         |c| c.mic_volume_db,
@@ -91,6 +96,7 @@ pub fn get_audio_segments(segments: &[SegmentMedia]) -> Vec<AudioSegment> {
                         |o| o.mic,
                     )
                     .with_timing_offset_secs(s.audio_timing_repair.mic_offset_secs)
+                    .with_voice_enhancement(voice)
                 }),
                 s.system_audio.clone().map(|a| -> AudioSegmentTrack {
                     AudioSegmentTrack::new(
@@ -5,25 +5,48 @@ use tracing::*;
 
 use crate::{
     audio::AudioEncoder,
-    h264,
-    video::h264::{H264Encoder, H264EncoderError},
+    video::{
+        VideoEncoder,
+        h264::{H264Encoder, H264EncoderError},
+    },
 };
 
-pub struct MP4File {
+/// An MP4 file with one video stream and an optional audio stream. Defaults to H264; HEVC and
+/// AV1 exports pass their own [`VideoEncoder`] through the `video` factory in [`MP4File::init`].
+/// The same muxer writes WebM when initialized with [`Container::WebM`].
+pub struct MP4File<V: VideoEncoder = H264Encoder> {
     #[allow(unused)]
     tag: &'static str,
     output: format::context::Output,
-    video: H264Encoder,
+    video: V,
     audio: Option<Box<dyn AudioEncoder + Send>>,
     is_finished: bool,
 }
 
+/// Container an [`MP4File`] is written as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Container {
+    /// MP4, with the `moov` atom moved to the front when `faststart` is set.
+    Mp4 { faststart: bool },
+    /// WebM (Matroska), for VP9 or AV1 video with Opus audio.
+    WebM,
+}
+
+impl Container {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 { .. } => "mp4",
+            Self::WebM => "webm",
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
-pub enum InitError {
+pub enum InitError<E: std::error::Error = H264EncoderError> {
     #[error("{0:?}")]
     Ffmpeg(ffmpeg::Error),
     #[error("Video/{0}")]
-    VideoInit(H264EncoderError),
+    VideoInit(E),
     #[error("Audio/{0}")]
     AudioInit(Box<dyn std::error::Error>),
 }
@@ -41,35 +64,39 @@ pub struct FinishResult {
     pub audio_finish: Result<(), ffmpeg::Error>,
 }
 
-impl MP4File {
+impl<V: VideoEncoder> MP4File<V> {
     pub fn init(
         tag: &'static str,
         mut output: PathBuf,
-        faststart: bool,
-        video: impl FnOnce(&mut format::context::Output) -> Result<H264Encoder, H264EncoderError>,
+        container: Container,
+        video: impl FnOnce(&mut format::context::Output) -> Result<V, V::BuildError>,
         audio: impl FnOnce(
             &mut format::context::Output,
         )
             -> Option<Result<Box<dyn AudioEncoder + Send>, Box<dyn std::error::Error>>>,
-    ) -> Result<Self, InitError> {
-        output.set_extension("mp4");
+    ) -> Result<Self, InitError<V::BuildError>> {
+        output.set_extension(container.extension());
 
         if let Some(parent) = output.parent() {
             let _ = std::fs::create_dir_all(parent);
         }
 
-        let mut output = format::output(&output).map_err(InitError::Ffmpeg)?;
+        let mut output = match container {
+            Container::Mp4 { .. } => format::output(&output),
+            Container::WebM => format::output_as(&output, "webm"),
+        }
+        .map_err(InitError::Ffmpeg)?;
 
-        trace!("Preparing encoders for mp4 file");
+        trace!("Preparing encoders for {} file", container.extension());
 
         let video = video(&mut output).map_err(InitError::VideoInit)?;
         let audio = audio(&mut output)
             .transpose()
             .map_err(InitError::AudioInit)?;
 
-        info!("Prepared encoders for mp4 file");
+        info!("Prepared encoders for {} file", container.extension());
 
-        if faststart {
+        if let Container::Mp4 { faststart: true } = container {
             let mut opts = ffmpeg::Dictionary::new();
             opts.set("movflags", "+faststart");
             output
@@ -97,7 +124,7 @@ impl MP4File {
         &mut self,
         frame: frame::Video,
         timestamp: Duration,
-    ) -> Result<(), h264::QueueFrameError> {
+    ) -> Result<(), V::QueueError> {
         if self.is_finished {
             return Ok(());
         }
@@ -110,7 +137,7 @@ impl MP4File {
         frame: &mut frame::Video,
         converted_frame: &mut Option<frame::Video>,
         timestamp: Duration,
-    ) -> Result<(), h264::QueueFrameError> {
+    ) -> Result<(), V::QueueError> {
         if self.is_finished {
             return Ok(());
         }
@@ -166,16 +193,16 @@ impl MP4File {
         })
     }
 
-    pub fn video(&self) -> &H264Encoder {
+    pub fn video(&self) -> &V {
         &self.video
     }
 
-    pub fn video_mut(&mut self) -> &mut H264Encoder {
+    pub fn video_mut(&mut self) -> &mut V {
         &mut self.video
     }
 }
 
-impl Drop for MP4File {
+impl<V: VideoEncoder> Drop for MP4File<V> {
     fn drop(&mut self) {
         let _ = self.finish();
     }
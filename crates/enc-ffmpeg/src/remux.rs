@@ -8,9 +8,14 @@ use std::{
 };
 
 use cap_media_info::{AudioInfo, AudioInfoError};
-use ffmpeg::{ChannelLayout, codec as avcodec, format as avformat, packet::Mut as PacketMut};
+use ffmpeg::{
+    ChannelLayout, Rescale, codec as avcodec, format as avformat, packet::Mut as PacketMut,
+};
 
-use crate::audio::opus::{OpusEncoder, OpusEncoderError};
+use crate::audio::{
+    aac::{AACEncoder, AACEncoderError},
+    opus::{OpusEncoder, OpusEncoderError},
+};
 
 static ORIGINAL_LOG_LEVEL: AtomicI32 = AtomicI32::new(-1);
 const SEEK_PROBE_PACKET_LIMIT: usize = 240;
@@ -47,6 +52,8 @@ pub enum RemuxError {
     NoAudioStream,
     #[error("Opus encoder error: {0}")]
     OpusEncoder(#[from] OpusEncoderError),
+    #[error("AAC encoder error: {0}")]
+    AacEncoder(#[from] AACEncoderError),
     #[error("Audio info error: {0}")]
     AudioInfo(#[from] AudioInfoError),
     #[error("Concat demuxer not found")]
@@ -668,24 +675,39 @@ pub fn merge_video_audio(
     video_path: &Path,
     audio_path: &Path,
     output_path: &Path,
+) -> Result<(), RemuxError> {
+    merge_video_audio_tracks(video_path, &[(audio_path, None)], output_path)
+}
+
+/// Muxes the video of `video_path` with every audio stream of each `audio_tracks` file, in order.
+///
+/// Each file's audio is labelled with its `title`/`handler_name` when a label is given, and only
+/// the first audio track is marked as the default one players pick.
+pub fn merge_video_audio_tracks(
+    video_path: &Path,
+    audio_tracks: &[(&Path, Option<&str>)],
+    output_path: &Path,
 ) -> Result<(), RemuxError> {
     suppress_ffmpeg_logs();
-    let result = merge_video_audio_inner(video_path, audio_path, output_path);
+    let result = merge_video_audio_tracks_inner(video_path, audio_tracks, output_path);
     restore_ffmpeg_logs();
     result
 }
 
-fn merge_video_audio_inner(
+fn merge_video_audio_tracks_inner(
     video_path: &Path,
-    audio_path: &Path,
+    audio_tracks: &[(&Path, Option<&str>)],
     output_path: &Path,
 ) -> Result<(), RemuxError> {
     let mut video_ctx = avformat::input(video_path)?;
-    let mut audio_ctx = avformat::input(audio_path)?;
+    let mut audio_ctxs = audio_tracks
+        .iter()
+        .map(|(path, _)| avformat::input(path))
+        .collect::<Result<Vec<_>, _>>()?;
     let mut octx = avformat::output(output_path)?;
 
     let mut video_stream_map: Vec<Option<usize>> = Vec::new();
-    let mut audio_stream_map: Vec<Option<usize>> = Vec::new();
+    let mut audio_stream_maps: Vec<Vec<Option<usize>>> = Vec::new();
     let mut out_idx = 0usize;
 
     for stream in video_ctx.streams() {
@@ -702,18 +724,36 @@ fn merge_video_audio_inner(
         }
     }
 
-    for stream in audio_ctx.streams() {
-        if stream.parameters().medium() == ffmpeg::media::Type::Audio {
-            audio_stream_map.push(Some(out_idx));
-            out_idx += 1;
-            let mut out_stream = octx.add_stream(None)?;
-            out_stream.set_parameters(stream.parameters());
-            unsafe {
-                (*out_stream.as_mut_ptr()).time_base = (*stream.as_ptr()).time_base;
+    let mut first_audio = true;
+    for (audio_ctx, (_, label)) in audio_ctxs.iter().zip(audio_tracks) {
+        let mut audio_stream_map = Vec::new();
+        for stream in audio_ctx.streams() {
+            if stream.parameters().medium() == ffmpeg::media::Type::Audio {
+                audio_stream_map.push(Some(out_idx));
+                out_idx += 1;
+                let mut out_stream = octx.add_stream(None)?;
+                out_stream.set_parameters(stream.parameters());
+                unsafe {
+                    (*out_stream.as_mut_ptr()).time_base = (*stream.as_ptr()).time_base;
+                    (*out_stream.as_mut_ptr()).disposition = if first_audio {
+                        ffmpeg::format::stream::Disposition::DEFAULT.bits()
+                    } else {
+                        0
+                    };
+                }
+                first_audio = false;
+
+                if let Some(label) = label {
+                    let mut metadata = ffmpeg::Dictionary::new();
+                    metadata.set("title", label);
+                    metadata.set("handler_name", label);
+                    out_stream.set_metadata(metadata);
+                }
+            } else {
+                audio_stream_map.push(None);
             }
-        } else {
-            audio_stream_map.push(None);
         }
+        audio_stream_maps.push(audio_stream_map);
     }
 
     octx.write_header()?;
@@ -722,63 +762,553 @@ fn merge_video_audio_inner(
 
     for (stream, packet) in video_ctx.packets() {
         if let Some(Some(oidx)) = video_stream_map.get(stream.index()) {
-            let oidx = *oidx;
-            let mut packet = packet;
-            packet.rescale_ts(stream.time_base(), octx.stream(oidx).unwrap().time_base());
+            write_merged_packet(&mut octx, packet, stream.time_base(), *oidx, &mut last_dts)?;
+        }
+    }
+
+    for (audio_ctx, audio_stream_map) in audio_ctxs.iter_mut().zip(&audio_stream_maps) {
+        for (stream, packet) in audio_ctx.packets() {
+            if let Some(Some(oidx)) = audio_stream_map.get(stream.index()) {
+                write_merged_packet(&mut octx, packet, stream.time_base(), *oidx, &mut last_dts)?;
+            }
+        }
+    }
+
+    octx.write_trailer()?;
+    Ok(())
+}
+
+fn write_merged_packet(
+    octx: &mut avformat::context::Output,
+    mut packet: ffmpeg::Packet,
+    input_time_base: ffmpeg::Rational,
+    oidx: usize,
+    last_dts: &mut [i64],
+) -> Result<(), RemuxError> {
+    packet.rescale_ts(input_time_base, octx.stream(oidx).unwrap().time_base());
+
+    let dts = packet.dts().unwrap_or(0);
+    if last_dts[oidx] != i64::MIN && dts <= last_dts[oidx] {
+        let fixed = last_dts[oidx] + 1;
+        unsafe {
+            (*packet.as_mut_ptr()).dts = fixed;
+            if let Some(pts) = packet.pts()
+                && pts <= fixed
+            {
+                (*packet.as_mut_ptr()).pts = fixed;
+            }
+        }
+    }
+    last_dts[oidx] = packet.dts().unwrap_or(0);
 
-            let dts = packet.dts().unwrap_or(0);
-            if last_dts[oidx] != i64::MIN && dts <= last_dts[oidx] {
-                let fixed = last_dts[oidx] + 1;
+    packet.set_stream(oidx);
+    packet.set_position(-1);
+    packet.write_interleaved(octx)?;
+    Ok(())
+}
+
+/// Counts the audio streams in a media file, or `None` if it can't be opened.
+pub fn audio_stream_count(path: &Path) -> Option<usize> {
+    suppress_ffmpeg_logs();
+    let count = avformat::input(path).ok().map(|ictx| {
+        ictx.streams()
+            .filter(|stream| stream.parameters().medium() == ffmpeg::media::Type::Audio)
+            .count()
+    });
+    restore_ffmpeg_logs();
+    count
+}
+
+const DOWNMIX_SAMPLE_RATE: u32 = 48_000;
+
+/// Copies `input_path` into `output_path` with all of its audio tracks mixed into a single stereo
+/// AAC track, for players and services that only ever play the first one.
+///
+/// Video is stream-copied; tracks are mixed the same way the recorder mixes live sources.
+pub fn downmix_audio_tracks(input_path: &Path, output_path: &Path) -> Result<(), RemuxError> {
+    suppress_ffmpeg_logs();
+    let result = downmix_audio_tracks_inner(input_path, output_path);
+    restore_ffmpeg_logs();
+    result
+}
+
+struct DownmixInput {
+    stream_index: usize,
+    decoder: ffmpeg::decoder::Audio,
+    abuffer: ffmpeg::filter::Context,
+}
+
+impl DownmixInput {
+    fn drain(&mut self, frame: &mut ffmpeg::frame::Audio) -> Result<(), RemuxError> {
+        while self.decoder.receive_frame(frame).is_ok() {
+            self.abuffer.source().add(frame)?;
+        }
+        Ok(())
+    }
+}
+
+fn downmix_audio_tracks_inner(input_path: &Path, output_path: &Path) -> Result<(), RemuxError> {
+    let mut ictx = avformat::input(input_path)?;
+    let mut octx = avformat::output(output_path)?;
+
+    let mut target = AudioInfo::new(
+        avformat::Sample::F32(avformat::sample::Type::Planar),
+        DOWNMIX_SAMPLE_RATE,
+        2,
+    )?;
+    target.time_base = ffmpeg::Rational(1, DOWNMIX_SAMPLE_RATE as i32);
+    let target_sample_fmt = target.sample_format.name();
+    let target_channel_layout_bits = target.channel_layout().bits();
+
+    let mut filter_graph = ffmpeg::filter::Graph::new();
+    let mut video_stream_map: Vec<Option<usize>> = Vec::new();
+    let mut inputs: Vec<DownmixInput> = Vec::new();
+    let mut resamplers: Vec<ffmpeg::filter::Context> = Vec::new();
+    let mut out_idx = 0usize;
+
+    for stream in ictx.streams() {
+        match stream.parameters().medium() {
+            ffmpeg::media::Type::Video => {
+                video_stream_map.push(Some(out_idx));
+                out_idx += 1;
+                let mut out_stream = octx.add_stream(None)?;
+                out_stream.set_parameters(stream.parameters());
                 unsafe {
-                    (*packet.as_mut_ptr()).dts = fixed;
-                    if let Some(pts) = packet.pts()
-                        && pts <= fixed
-                    {
-                        (*packet.as_mut_ptr()).pts = fixed;
-                    }
+                    (*out_stream.as_mut_ptr()).time_base = (*stream.as_ptr()).time_base;
                 }
             }
-            last_dts[oidx] = packet.dts().unwrap_or(0);
+            ffmpeg::media::Type::Audio => {
+                video_stream_map.push(None);
 
-            packet.set_stream(oidx);
-            packet.set_position(-1);
-            packet.write_interleaved(&mut octx)?;
+                let decoder_ctx = avcodec::Context::from_parameters(stream.parameters())?;
+                let mut decoder = decoder_ctx.decoder().audio()?;
+                if decoder.channel_layout().is_empty() {
+                    decoder.set_channel_layout(ChannelLayout::default(decoder.channels() as i32));
+                }
+                decoder.set_packet_time_base(stream.time_base());
+                let info = AudioInfo::from_decoder(&decoder)?;
+
+                let i = inputs.len();
+                let mut abuffer = filter_graph.add(
+                    &ffmpeg::filter::find("abuffer").ok_or(ffmpeg::Error::FilterNotFound)?,
+                    &format!("src{i}"),
+                    &format!(
+                        "time_base={}:sample_rate={}:sample_fmt={}:channel_layout=0x{:x}",
+                        stream.time_base(),
+                        info.rate(),
+                        info.sample_format.name(),
+                        info.channel_layout().bits()
+                    ),
+                )?;
+                let mut resample = filter_graph.add(
+                    &ffmpeg::filter::find("aresample").ok_or(ffmpeg::Error::FilterNotFound)?,
+                    &format!("resample{i}"),
+                    &format!(
+                        "out_sample_rate={DOWNMIX_SAMPLE_RATE}:out_sample_fmt={target_sample_fmt}:out_chlayout=0x{target_channel_layout_bits:x}"
+                    ),
+                )?;
+                abuffer.link(0, &mut resample, 0);
+
+                inputs.push(DownmixInput {
+                    stream_index: stream.index(),
+                    decoder,
+                    abuffer,
+                });
+                resamplers.push(resample);
+            }
+            _ => video_stream_map.push(None),
         }
     }
 
-    for (stream, packet) in audio_ctx.packets() {
-        if let Some(Some(oidx)) = audio_stream_map.get(stream.index()) {
-            let oidx = *oidx;
-            let mut packet = packet;
-            packet.rescale_ts(stream.time_base(), octx.stream(oidx).unwrap().time_base());
+    if inputs.is_empty() {
+        return Err(RemuxError::NoAudioStream);
+    }
 
-            let dts = packet.dts().unwrap_or(0);
-            if last_dts[oidx] != i64::MIN && dts <= last_dts[oidx] {
-                let fixed = last_dts[oidx] + 1;
-                unsafe {
-                    (*packet.as_mut_ptr()).dts = fixed;
-                    if let Some(pts) = packet.pts()
-                        && pts <= fixed
-                    {
-                        (*packet.as_mut_ptr()).pts = fixed;
+    let mut amix = filter_graph.add(
+        &ffmpeg::filter::find("amix").ok_or(ffmpeg::Error::FilterNotFound)?,
+        "amix",
+        &format!("inputs={}:duration=longest", inputs.len()),
+    )?;
+    let mut aformat = filter_graph.add(
+        &ffmpeg::filter::find("aformat").ok_or(ffmpeg::Error::FilterNotFound)?,
+        "aformat",
+        &format!(
+            "sample_fmts={target_sample_fmt}:sample_rates={DOWNMIX_SAMPLE_RATE}:channel_layouts=0x{target_channel_layout_bits:x}"
+        ),
+    )?;
+    let mut abuffersink = filter_graph.add(
+        &ffmpeg::filter::find("abuffersink").ok_or(ffmpeg::Error::FilterNotFound)?,
+        "sink",
+        "",
+    )?;
+    for (i, resample) in resamplers.iter_mut().enumerate() {
+        resample.link(0, &mut amix, i as u32);
+    }
+    amix.link(0, &mut aformat, 0);
+    aformat.link(0, &mut abuffersink, 0);
+    filter_graph.validate()?;
+
+    let mut encoder = AACEncoder::init(target, &mut octx)?;
+
+    let mut options = ffmpeg::Dictionary::new();
+    options.set("movflags", "+faststart");
+    octx.write_header_with(options)?;
+
+    let mut decoded = ffmpeg::frame::Audio::empty();
+    let mut mixed = ffmpeg::frame::Audio::empty();
+    let mut last_dts: Vec<i64> = vec![i64::MIN; out_idx];
+
+    for (stream, packet) in ictx.packets() {
+        if let Some(Some(oidx)) = video_stream_map.get(stream.index()) {
+            write_merged_packet(&mut octx, packet, stream.time_base(), *oidx, &mut last_dts)?;
+        } else if let Some(input) = inputs
+            .iter_mut()
+            .find(|input| input.stream_index == stream.index())
+        {
+            input.decoder.send_packet(&packet)?;
+            input.drain(&mut decoded)?;
+
+            while abuffersink.sink().frame(&mut mixed).is_ok() {
+                encoder.send_frame(mixed.clone(), Duration::MAX, &mut octx)?;
+            }
+        }
+    }
+
+    for input in &mut inputs {
+        input.decoder.send_eof()?;
+        input.drain(&mut decoded)?;
+        input.abuffer.source().flush()?;
+    }
+    while abuffersink.sink().frame(&mut mixed).is_ok() {
+        encoder.send_frame(mixed.clone(), Duration::MAX, &mut octx)?;
+    }
+
+    encoder.flush(&mut octx)?;
+    octx.write_trailer()?;
+
+    Ok(())
+}
+
+/// A single timed subtitle cue, in output (edited) time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+}
+
+/// The subtitle stream format each container can carry as a soft track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleCodec {
+    /// 3GPP timed text (`tx3g`), the only text format MP4/MOV players understand.
+    MovText,
+    /// WebVTT, the only text format the WebM profile of Matroska allows.
+    WebVtt,
+    /// SubRip, the most widely supported text format in Matroska.
+    SubRip,
+}
+
+impl SubtitleCodec {
+    /// Picks the subtitle format for a container from the output file's extension.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "mp4" | "m4v" | "mov" => Some(Self::MovText),
+            "webm" => Some(Self::WebVtt),
+            "mkv" => Some(Self::SubRip),
+            _ => None,
+        }
+    }
+
+    fn codec_id(self) -> avcodec::Id {
+        match self {
+            Self::MovText => avcodec::Id::MOV_TEXT,
+            Self::WebVtt => avcodec::Id::WEBVTT,
+            Self::SubRip => avcodec::Id::SUBRIP,
+        }
+    }
+
+    fn packet_payload(self, text: &str) -> Vec<u8> {
+        match self {
+            // tx3g samples are a big-endian u16 byte length followed by the UTF-8 text.
+            Self::MovText => {
+                let text = &text.as_bytes()[..text.len().min(u16::MAX as usize)];
+                let mut payload = Vec::with_capacity(text.len() + 2);
+                payload.extend_from_slice(&(text.len() as u16).to_be_bytes());
+                payload.extend_from_slice(text);
+                payload
+            }
+            Self::WebVtt | Self::SubRip => text.as_bytes().to_vec(),
+        }
+    }
+}
+
+// Default tx3g sample description (the same one FFmpeg's mov_text encoder emits): bottom-centred
+// white text on a transparent box using the "Serif" font. Without it players reject the track.
+const MOV_TEXT_SAMPLE_ENTRY: [u8; 48] = [
+    0x00, 0x00, 0x00, 0x00, // display flags
+    0x01, 0xFF, // horizontal / vertical justification
+    0x00, 0x00, 0x00, 0x00, // background rgba
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // box record
+    0x00, 0x00, 0x00, 0x00, // style record: start/end char
+    0x00, 0x01, // font id
+    0x00, // face style flags
+    0x12, // font size
+    0xFF, 0xFF, 0xFF, 0xFF, // text rgba
+    0x00, 0x00, 0x00, 0x12, b'f', b't', b'a', b'b', // font table box
+    0x00, 0x01, // entry count
+    0x00, 0x01, 0x05, b'S', b'e', b'r', b'i', b'f', // font record
+];
+
+const SUBTITLE_TIME_BASE: ffmpeg::Rational = ffmpeg::Rational(1, 1000);
+
+/// Copies `input_path` into `output_path` and adds `cues` as a soft subtitle track.
+///
+/// Video and audio are stream-copied with their metadata and disposition, so this is cheap
+/// compared to the export itself and keeps audio track labels. Cues are interleaved with the
+/// copied packets in timestamp order. MP4 output keeps `+faststart` so the result is still
+/// progressively playable.
+pub fn mux_subtitle_track(
+    input_path: &Path,
+    output_path: &Path,
+    cues: &[SubtitleCue],
+    codec: SubtitleCodec,
+    language: Option<&str>,
+) -> Result<(), RemuxError> {
+    suppress_ffmpeg_logs();
+    let result = mux_subtitle_track_inner(input_path, output_path, cues, codec, language);
+    restore_ffmpeg_logs();
+    result
+}
+
+fn mux_subtitle_track_inner(
+    input_path: &Path,
+    output_path: &Path,
+    cues: &[SubtitleCue],
+    codec: SubtitleCodec,
+    language: Option<&str>,
+) -> Result<(), RemuxError> {
+    let mut ictx = avformat::input(input_path)?;
+    let mut octx = avformat::output(output_path)?;
+
+    octx.set_metadata(ictx.metadata().to_owned());
+
+    let mut stream_mapping: Vec<Option<usize>> = Vec::new();
+    let mut output_stream_index = 0usize;
+
+    for input_stream in ictx.streams() {
+        let medium = input_stream.parameters().medium();
+
+        if medium == ffmpeg::media::Type::Video || medium == ffmpeg::media::Type::Audio {
+            stream_mapping.push(Some(output_stream_index));
+            output_stream_index += 1;
+
+            let mut output_stream = octx.add_stream(None)?;
+            output_stream.set_parameters(input_stream.parameters());
+            output_stream.set_metadata(input_stream.metadata().to_owned());
+            unsafe {
+                (*output_stream.as_mut_ptr()).time_base = (*input_stream.as_ptr()).time_base;
+                (*output_stream.as_mut_ptr()).disposition = (*input_stream.as_ptr()).disposition;
+            }
+        } else {
+            stream_mapping.push(None);
+        }
+    }
+
+    let subtitle_index = output_stream_index;
+    {
+        let mut subtitle_stream = octx.add_stream(None)?;
+        subtitle_stream.set_time_base(SUBTITLE_TIME_BASE);
+        unsafe {
+            let codecpar = (*subtitle_stream.as_mut_ptr()).codecpar;
+            (*codecpar).codec_type = ffmpeg::ffi::AVMediaType::AVMEDIA_TYPE_SUBTITLE;
+            (*codecpar).codec_id = codec.codec_id().into();
+
+            if codec == SubtitleCodec::MovText {
+                let size = MOV_TEXT_SAMPLE_ENTRY.len();
+                let extradata = ffmpeg::ffi::av_mallocz(
+                    size + ffmpeg::ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize,
+                ) as *mut u8;
+                if extradata.is_null() {
+                    return Err(ffmpeg::Error::Other {
+                        errno: ffmpeg::error::ENOMEM,
                     }
+                    .into());
                 }
+                ptr::copy_nonoverlapping(MOV_TEXT_SAMPLE_ENTRY.as_ptr(), extradata, size);
+                (*codecpar).extradata = extradata;
+                (*codecpar).extradata_size = size as i32;
             }
-            last_dts[oidx] = packet.dts().unwrap_or(0);
+        }
 
-            packet.set_stream(oidx);
-            packet.set_position(-1);
-            packet.write_interleaved(&mut octx)?;
+        if let Some(language) = language {
+            let mut metadata = ffmpeg::Dictionary::new();
+            metadata.set("language", language);
+            subtitle_stream.set_metadata(metadata);
         }
     }
 
+    let mut options = ffmpeg::Dictionary::new();
+    if codec == SubtitleCodec::MovText {
+        options.set("movflags", "+faststart");
+    }
+    octx.write_header_with(options)?;
+
+    let mut cues = cues
+        .iter()
+        .filter(|cue| cue.end > cue.start && !cue.text.trim().is_empty())
+        .collect::<Vec<_>>();
+    cues.sort_by_key(|cue| cue.start);
+    let mut pending_cues = cues.into_iter().peekable();
+
+    for (input_stream, mut packet) in ictx.packets() {
+        let Some(Some(output_index)) = stream_mapping.get(input_stream.index()).copied() else {
+            continue;
+        };
+
+        // Cues go out just before the first packet at or past their start, so the muxer sees
+        // every stream in timestamp order.
+        if let Some(packet_ms) = packet
+            .dts()
+            .or(packet.pts())
+            .map(|ts| ts.rescale(input_stream.time_base(), SUBTITLE_TIME_BASE))
+        {
+            while let Some(cue) =
+                pending_cues.next_if(|cue| cue.start.as_millis() as i64 <= packet_ms)
+            {
+                write_subtitle_cue(&mut octx, cue, codec, subtitle_index)?;
+            }
+        }
+
+        let output_time_base = octx.stream(output_index).unwrap().time_base();
+        packet.rescale_ts(input_stream.time_base(), output_time_base);
+        packet.set_stream(output_index);
+        packet.set_position(-1);
+        packet.write_interleaved(&mut octx)?;
+    }
+
+    for cue in pending_cues {
+        write_subtitle_cue(&mut octx, cue, codec, subtitle_index)?;
+    }
+
     octx.write_trailer()?;
+
+    Ok(())
+}
+
+fn write_subtitle_cue(
+    octx: &mut avformat::context::Output,
+    cue: &SubtitleCue,
+    codec: SubtitleCodec,
+    subtitle_index: usize,
+) -> Result<(), RemuxError> {
+    let subtitle_time_base = octx.stream(subtitle_index).unwrap().time_base();
+    let start = (cue.start.as_millis() as i64).rescale(SUBTITLE_TIME_BASE, subtitle_time_base);
+    let end = (cue.end.as_millis() as i64).rescale(SUBTITLE_TIME_BASE, subtitle_time_base);
+
+    let mut packet = ffmpeg::Packet::copy(&codec.packet_payload(&cue.text));
+    packet.set_pts(Some(start));
+    packet.set_dts(Some(start));
+    packet.set_duration(end - start);
+    packet.set_stream(subtitle_index);
+    packet.set_position(-1);
+    packet.write_interleaved(octx)?;
+    Ok(())
+}
+
+/// A chapter of an export, in output (edited) time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChapterCue {
+    pub start: Duration,
+    pub end: Duration,
+    pub title: String,
+}
+
+/// Whether the container `path` is written in can carry chapters: MP4/MOV, and Matroska including
+/// WebM.
+pub fn container_supports_chapters(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .is_some_and(|ext| matches!(ext.as_str(), "mp4" | "m4v" | "mov" | "mkv" | "webm"))
+}
+
+const CHAPTER_TIME_BASE: ffmpeg::Rational = ffmpeg::Rational(1, 1000);
+
+/// Copies `input_path` into `output_path` with `chapters` in place of any it had.
+///
+/// Video, audio and subtitle streams are stream-copied with their metadata, so this can run after
+/// [`mux_subtitle_track`] without dropping the captions. MP4 output keeps `+faststart`.
+pub fn mux_chapters(
+    input_path: &Path,
+    output_path: &Path,
+    chapters: &[ChapterCue],
+) -> Result<(), RemuxError> {
+    let mut ictx = avformat::input(input_path)?;
+    let mut octx = avformat::output(output_path)?;
+
+    octx.set_metadata(ictx.metadata().to_owned());
+
+    let mut stream_mapping: Vec<Option<usize>> = Vec::new();
+    let mut output_stream_index = 0usize;
+
+    for input_stream in ictx.streams() {
+        let medium = input_stream.parameters().medium();
+
+        // An existing chapter track is demuxed as a data stream; skipping it keeps the old
+        // chapters from being carried over next to the new ones.
+        if matches!(
+            medium,
+            ffmpeg::media::Type::Video | ffmpeg::media::Type::Audio | ffmpeg::media::Type::Subtitle
+        ) {
+            stream_mapping.push(Some(output_stream_index));
+            output_stream_index += 1;
+
+            let mut output_stream = octx.add_stream(None)?;
+            output_stream.set_parameters(input_stream.parameters());
+            output_stream.set_metadata(input_stream.metadata().to_owned());
+            unsafe {
+                (*output_stream.as_mut_ptr()).time_base = (*input_stream.as_ptr()).time_base;
+                (*output_stream.as_mut_ptr()).disposition = (*input_stream.as_ptr()).disposition;
+            }
+        } else {
+            stream_mapping.push(None);
+        }
+    }
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        let start = chapter.start.as_millis() as i64;
+        let end = (chapter.end.as_millis() as i64).max(start);
+        octx.add_chapter(index as i64, CHAPTER_TIME_BASE, start, end, &chapter.title)?;
+    }
+
+    let mut options = ffmpeg::Dictionary::new();
+    if SubtitleCodec::for_path(output_path) == Some(SubtitleCodec::MovText) {
+        options.set("movflags", "+faststart");
+    }
+    octx.write_header_with(options)?;
+
+    for (input_stream, mut packet) in ictx.packets() {
+        let Some(Some(output_index)) = stream_mapping.get(input_stream.index()).copied() else {
+            continue;
+        };
+
+        let output_time_base = octx.stream(output_index).unwrap().time_base();
+        packet.rescale_ts(input_stream.time_base(), output_time_base);
+        packet.set_stream(output_index);
+        packet.set_position(-1);
+        packet.write_interleaved(&mut octx)?;
+    }
+
+    octx.write_trailer()?;
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::build_seek_probe_positions;
+    use super::{SubtitleCodec, build_seek_probe_positions, container_supports_chapters};
+    use std::path::Path;
 
     #[test]
     fn seek_probe_positions_cover_start_middle_and_end() {
@@ -795,6 +1325,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mov_text_payload_is_length_prefixed() {
+        let payload = SubtitleCodec::MovText.packet_payload("héllo");
+
+        assert_eq!(&payload[..2], &[0x00, 0x06]);
+        assert_eq!(&payload[2..], "héllo".as_bytes());
+        assert_eq!(SubtitleCodec::WebVtt.packet_payload("hi"), b"hi");
+    }
+
+    #[test]
+    fn subtitle_codec_follows_container_extension() {
+        assert_eq!(
+            SubtitleCodec::for_path(Path::new("out.MP4")),
+            Some(SubtitleCodec::MovText)
+        );
+        assert_eq!(
+            SubtitleCodec::for_path(Path::new("out.webm")),
+            Some(SubtitleCodec::WebVtt)
+        );
+        assert_eq!(
+            SubtitleCodec::for_path(Path::new("out.mkv")),
+            Some(SubtitleCodec::SubRip)
+        );
+        assert_eq!(SubtitleCodec::for_path(Path::new("out.gif")), None);
+    }
+
+    #[test]
+    fn chapters_only_go_in_containers_that_carry_them() {
+        assert!(container_supports_chapters(Path::new("out.MP4")));
+        assert!(container_supports_chapters(Path::new("out.mkv")));
+        assert!(container_supports_chapters(Path::new("out.webm")));
+        assert!(!container_supports_chapters(Path::new("out.gif")));
+    }
+
     #[test]
     fn seek_probe_positions_are_sorted_and_unique() {
         let positions = build_seek_probe_positions(1_000_000, 12);
@@ -0,0 +1,94 @@
+use std::thread;
+
+use ffmpeg::Dictionary;
+
+use crate::video::codec_encoder::{
+    CodecEncoder, CodecEncoderBuilder, CodecEncoderError, EncoderCodec,
+};
+
+pub use crate::video::codec_encoder::QueueFrameError;
+
+/// AV1. Prefers a hardware encoder where the GPU has one and otherwise falls back to SVT-AV1 or
+/// libaom.
+pub struct Av1;
+
+pub type Av1Encoder = CodecEncoder<Av1>;
+pub type Av1EncoderBuilder = CodecEncoderBuilder<Av1>;
+pub type Av1EncoderError = CodecEncoderError;
+
+const SOFTWARE_ENCODERS: &[&str] = &["libsvtav1", "libaom-av1"];
+
+impl EncoderCodec for Av1 {
+    const NAME: &'static str = "AV1";
+    const QUALITY_BPP: f32 = 0.15;
+    const MAX_CRF: u8 = 63;
+
+    fn candidates(software: bool) -> &'static [&'static str] {
+        if software {
+            return SOFTWARE_ENCODERS;
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use cap_frame_converter::{GpuVendor, detect_primary_gpu};
+
+            static ENCODER_PRIORITY_NVIDIA: &[&str] =
+                &["av1_nvenc", "av1_qsv", "av1_amf", "libsvtav1", "libaom-av1"];
+            static ENCODER_PRIORITY_AMD: &[&str] =
+                &["av1_amf", "av1_nvenc", "av1_qsv", "libsvtav1", "libaom-av1"];
+            static ENCODER_PRIORITY_INTEL: &[&str] =
+                &["av1_qsv", "av1_nvenc", "av1_amf", "libsvtav1", "libaom-av1"];
+
+            match detect_primary_gpu().map(|info| info.vendor) {
+                Some(GpuVendor::Amd) => ENCODER_PRIORITY_AMD,
+                Some(GpuVendor::Intel) => ENCODER_PRIORITY_INTEL,
+                _ => ENCODER_PRIORITY_NVIDIA,
+            }
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            SOFTWARE_ENCODERS
+        }
+    }
+
+    fn set_options(encoder: &str, options: &mut Dictionary<'static>, crf: Option<u8>) {
+        match encoder {
+            "av1_nvenc" => {
+                options.set("preset", "p5");
+                options.set("tune", "hq");
+                options.set("rc", "vbr");
+            }
+            "av1_qsv" => {
+                options.set("preset", "medium");
+            }
+            "av1_amf" => {
+                options.set("quality", "quality");
+                options.set("rc", "vbr_peak");
+            }
+            "libsvtav1" => {
+                // Presets 8-10 are the realtime-ish range; anything lower is far too slow for
+                // long screen recordings on a CPU-only box.
+                options.set("preset", "8");
+                if let Some(crf) = crf {
+                    options.set("crf", &crf.to_string());
+                }
+            }
+            "libaom-av1" => {
+                let thread_count = thread::available_parallelism()
+                    .map(|v| v.get())
+                    .unwrap_or(4);
+
+                options.set("usage", "good");
+                options.set("cpu-used", "6");
+                options.set("row-mt", "1");
+                options.set("tiles", "2x2");
+                options.set("threads", &thread_count.to_string());
+                if let Some(crf) = crf {
+                    options.set("crf", &crf.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+}
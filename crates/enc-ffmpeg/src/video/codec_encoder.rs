@@ -0,0 +1,424 @@
+use std::{marker::PhantomData, thread, time::Duration};
+
+use cap_media_info::{Pixel, VideoInfo, ensure_even};
+use ffmpeg::{
+    Dictionary,
+    codec::{codec::Codec, context, encoder},
+    color,
+    format::{self},
+    frame,
+    threading::Config,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{base::EncoderBase, video::VideoEncoder};
+
+/// What sets one codec apart for [`CodecEncoder`]: which FFmpeg encoders to try, in what order,
+/// and the options each of them is opened with. Everything else (scaling, colour tagging, rate
+/// control, stream setup) is shared.
+pub trait EncoderCodec: Send + 'static {
+    /// Name used in logs, e.g. `"AV1"`.
+    const NAME: &'static str;
+    /// Default bits per pixel when the caller doesn't set one.
+    const QUALITY_BPP: f32;
+    /// Top of the codec's CRF scale.
+    const MAX_CRF: u8;
+
+    /// FFmpeg encoder names to try, best first. `software` is set when a CRF was requested or
+    /// software encoding is forced, since only the software encoders honour `crf`.
+    fn candidates(software: bool) -> &'static [&'static str];
+
+    /// Fills in the options `encoder` is opened with, besides the keyframe interval every
+    /// candidate gets.
+    fn set_options(encoder: &str, options: &mut Dictionary<'static>, crf: Option<u8>);
+}
+
+pub struct CodecEncoderBuilder<C: EncoderCodec> {
+    bpp: f32,
+    input_config: VideoInfo,
+    output_size: Option<(u32, u32)>,
+    crf: Option<u8>,
+    codec: PhantomData<C>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CodecEncoderError {
+    #[error("{0:?}")]
+    FFmpeg(#[from] ffmpeg::Error),
+    #[error("Codec not found")]
+    CodecNotFound,
+    #[error("Pixel format {0:?} not supported")]
+    PixFmtNotSupported(Pixel),
+    #[error("Invalid output dimensions {width}x{height}; expected non-zero even width and height")]
+    InvalidOutputDimensions { width: u32, height: u32 },
+}
+
+impl<C: EncoderCodec> CodecEncoderBuilder<C> {
+    pub const QUALITY_BPP: f32 = C::QUALITY_BPP;
+
+    pub fn new(input_config: VideoInfo) -> Self {
+        Self {
+            input_config,
+            bpp: Self::QUALITY_BPP,
+            output_size: None,
+            crf: None,
+            codec: PhantomData,
+        }
+    }
+
+    pub fn with_bpp(mut self, bpp: f32) -> Self {
+        self.bpp = bpp;
+        self
+    }
+
+    pub fn with_output_size(mut self, width: u32, height: u32) -> Result<Self, CodecEncoderError> {
+        if width == 0 || height == 0 {
+            return Err(CodecEncoderError::InvalidOutputDimensions { width, height });
+        }
+
+        self.output_size = Some((width, height));
+        Ok(self)
+    }
+
+    /// Constant-quality mode on the codec's CRF scale (lower is better). Forces a software
+    /// encoder, since the hardware encoders don't expose a comparable quality knob.
+    pub fn with_crf(mut self, crf: u8) -> Self {
+        self.crf = Some(crf.min(C::MAX_CRF));
+        self
+    }
+
+    pub fn build(
+        self,
+        output: &mut format::context::Output,
+    ) -> Result<CodecEncoder<C>, CodecEncoderError> {
+        let input_config = self.input_config;
+        let (raw_width, raw_height) = self
+            .output_size
+            .unwrap_or((input_config.width, input_config.height));
+
+        let output_width = ensure_even(raw_width);
+        let output_height = ensure_even(raw_height);
+
+        if raw_width != output_width || raw_height != output_height {
+            warn!(
+                raw_width,
+                raw_height,
+                output_width,
+                output_height,
+                "Auto-adjusted odd dimensions to even for {} encoding",
+                C::NAME
+            );
+        }
+
+        let candidates = get_codec_and_options::<C>(&input_config, self.crf);
+        if candidates.is_empty() {
+            return Err(CodecEncoderError::CodecNotFound);
+        }
+
+        let mut last_error = None;
+
+        for (codec, encoder_options) in candidates {
+            let codec_name = codec.name().to_string();
+
+            match Self::build_with_codec(
+                codec,
+                encoder_options,
+                &input_config,
+                output,
+                output_width,
+                output_height,
+                self.bpp,
+                self.crf,
+            ) {
+                Ok(encoder) => {
+                    info!(
+                        encoder = %codec_name,
+                        width = output_width,
+                        height = output_height,
+                        "Selected {} encoder",
+                        C::NAME
+                    );
+                    return Ok(encoder);
+                }
+                Err(err) => {
+                    debug!("{} encoder {} init failed: {:?}", C::NAME, codec_name, err);
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(CodecEncoderError::CodecNotFound))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_with_codec(
+        codec: Codec,
+        encoder_options: Dictionary<'static>,
+        input_config: &VideoInfo,
+        output: &mut format::context::Output,
+        output_width: u32,
+        output_height: u32,
+        bpp: f32,
+        crf: Option<u8>,
+    ) -> Result<CodecEncoder<C>, CodecEncoderError> {
+        let supported_formats = codec
+            .video()
+            .ok()
+            .and_then(|codec_video| codec_video.formats())
+            .map(|formats| formats.collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let output_format = if supported_formats.contains(&input_config.pixel_format) {
+            input_config.pixel_format
+        } else if supported_formats.contains(&ffmpeg::format::Pixel::NV12) {
+            ffmpeg::format::Pixel::NV12
+        } else {
+            ffmpeg::format::Pixel::YUV420P
+        };
+
+        let needs_conversion = output_format != input_config.pixel_format
+            || output_width != input_config.width
+            || output_height != input_config.height;
+
+        let converter = if needs_conversion {
+            debug!(
+                "Converting {:?} {}x{} to {:?} {}x{} for {} encoding",
+                input_config.pixel_format,
+                input_config.width,
+                input_config.height,
+                output_format,
+                output_width,
+                output_height,
+                C::NAME
+            );
+
+            match ffmpeg::software::scaling::Context::get(
+                input_config.pixel_format,
+                input_config.width,
+                input_config.height,
+                output_format,
+                output_width,
+                output_height,
+                ffmpeg::software::scaling::flag::Flags::BICUBIC,
+            ) {
+                Ok(context) => Some(context),
+                Err(e) => {
+                    error!(
+                        "Failed to create converter from {:?} to {:?}: {:?}",
+                        input_config.pixel_format, output_format, e
+                    );
+                    return Err(CodecEncoderError::PixFmtNotSupported(
+                        input_config.pixel_format,
+                    ));
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut encoder_ctx = context::Context::new_with_codec(codec);
+
+        let thread_count = thread::available_parallelism()
+            .map(|v| v.get())
+            .unwrap_or(1);
+        encoder_ctx.set_threading(Config::count(thread_count));
+        let mut encoder = encoder_ctx.encoder().video()?;
+
+        encoder.set_width(output_width);
+        encoder.set_height(output_height);
+        encoder.set_format(output_format);
+        encoder.set_time_base(input_config.time_base);
+        encoder.set_frame_rate(Some(input_config.frame_rate));
+        encoder.set_colorspace(color::Space::BT709);
+        encoder.set_color_range(color::Range::MPEG);
+        unsafe {
+            (*encoder.as_mut_ptr()).color_primaries =
+                ffmpeg::ffi::AVColorPrimaries::AVCOL_PRI_BT709;
+            (*encoder.as_mut_ptr()).color_trc =
+                ffmpeg::ffi::AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+            if output
+                .format()
+                .flags()
+                .contains(format::Flags::GLOBAL_HEADER)
+            {
+                (*encoder.as_mut_ptr()).flags |= ffmpeg::ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32;
+            }
+        }
+
+        if crf.is_some() {
+            encoder.set_bit_rate(0);
+        } else {
+            let bitrate = get_bitrate(
+                output_width,
+                output_height,
+                input_config.frame_rate.0 as f32 / input_config.frame_rate.1.max(1) as f32,
+                bpp,
+            );
+            encoder.set_bit_rate(bitrate);
+            encoder.set_max_bit_rate(bitrate * 3 / 2);
+        }
+
+        let encoder = encoder.open_with(encoder_options)?;
+
+        let mut output_stream = output.add_stream(codec)?;
+        let stream_index = output_stream.index();
+        output_stream.set_time_base((1, CodecEncoder::<C>::TIME_BASE));
+        output_stream.set_rate(input_config.frame_rate);
+        output_stream.set_parameters(&encoder);
+
+        let converted_frame_pool = converter
+            .as_ref()
+            .map(|_| frame::Video::new(output_format, output_width, output_height));
+
+        Ok(CodecEncoder {
+            base: EncoderBase::new(stream_index),
+            encoder,
+            converter,
+            converted_frame_pool,
+            output_format,
+            output_width,
+            output_height,
+            codec: PhantomData,
+        })
+    }
+}
+
+/// A video encoder for any [`EncoderCodec`], falling back through its candidate encoders until
+/// one opens.
+pub struct CodecEncoder<C: EncoderCodec> {
+    base: EncoderBase,
+    encoder: encoder::Video,
+    converter: Option<ffmpeg::software::scaling::Context>,
+    converted_frame_pool: Option<frame::Video>,
+    output_format: format::Pixel,
+    output_width: u32,
+    output_height: u32,
+    codec: PhantomData<C>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QueueFrameError {
+    #[error("Converter: {0}")]
+    Converter(ffmpeg::Error),
+    #[error("Encode: {0}")]
+    Encode(ffmpeg::Error),
+}
+
+impl<C: EncoderCodec> CodecEncoder<C> {
+    const TIME_BASE: i32 = 90000;
+
+    pub fn builder(input_config: VideoInfo) -> CodecEncoderBuilder<C> {
+        CodecEncoderBuilder::new(input_config)
+    }
+
+    pub fn queue_frame(
+        &mut self,
+        mut frame: frame::Video,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        let mut converted_frame = self.converted_frame_pool.take();
+        let result = self.queue_frame_reusable(&mut frame, &mut converted_frame, timestamp, output);
+        self.converted_frame_pool = converted_frame;
+        result
+    }
+
+    pub fn queue_frame_reusable(
+        &mut self,
+        frame: &mut frame::Video,
+        converted_frame: &mut Option<frame::Video>,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        self.base.update_pts(frame, timestamp, &mut self.encoder);
+
+        let frame_to_send = if let Some(converter) = &mut self.converter {
+            let pts = frame.pts();
+            let converted = converted_frame.get_or_insert_with(|| {
+                frame::Video::new(self.output_format, self.output_width, self.output_height)
+            });
+            converter
+                .run(frame, converted)
+                .map_err(QueueFrameError::Converter)?;
+            converted.set_pts(pts);
+            converted as &frame::Video
+        } else {
+            frame as &frame::Video
+        };
+
+        self.base
+            .send_frame(frame_to_send, output, &mut self.encoder)
+            .map_err(QueueFrameError::Encode)?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        self.base.process_eof(output, &mut self.encoder)
+    }
+}
+
+impl<C: EncoderCodec> VideoEncoder for CodecEncoder<C> {
+    type BuildError = CodecEncoderError;
+    type QueueError = QueueFrameError;
+
+    fn queue_frame(
+        &mut self,
+        frame: frame::Video,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        CodecEncoder::queue_frame(self, frame, timestamp, output)
+    }
+
+    fn queue_frame_reusable(
+        &mut self,
+        frame: &mut frame::Video,
+        converted_frame: &mut Option<frame::Video>,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        CodecEncoder::queue_frame_reusable(self, frame, converted_frame, timestamp, output)
+    }
+
+    fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        CodecEncoder::flush(self, output)
+    }
+}
+
+unsafe impl<C: EncoderCodec> Send for CodecEncoder<C> {}
+
+fn get_codec_and_options<C: EncoderCodec>(
+    config: &VideoInfo,
+    crf: Option<u8>,
+) -> Vec<(Codec, Dictionary<'static>)> {
+    let keyframe_interval_secs = crate::h264::DEFAULT_KEYFRAME_INTERVAL_SECS;
+    let denominator = config.frame_rate.denominator();
+    let frames_per_sec = config.frame_rate.numerator() as f64
+        / if denominator == 0 { 1 } else { denominator } as f64;
+    let keyframe_interval = (keyframe_interval_secs as f64 * frames_per_sec)
+        .round()
+        .max(1.0) as i32;
+    let keyframe_interval_str = keyframe_interval.to_string();
+
+    let software = crf.is_some() || crate::h264::force_software_encoder();
+
+    C::candidates(software)
+        .iter()
+        .filter_map(|encoder_name| {
+            let codec = encoder::find_by_name(encoder_name)?;
+            let mut options = Dictionary::new();
+            C::set_options(encoder_name, &mut options, crf);
+            options.set("g", &keyframe_interval_str);
+            Some((codec, options))
+        })
+        .collect()
+}
+
+fn get_bitrate(width: u32, height: u32, frame_rate: f32, bpp: f32) -> usize {
+    let frame_rate_multiplier = ((frame_rate as f64 - 30.0).max(0.0) * 0.6) + 30.0;
+    let area = (width as f64) * (height as f64);
+    let pixels_per_second = area * frame_rate_multiplier;
+
+    (pixels_per_second * bpp as f64) as usize
+}
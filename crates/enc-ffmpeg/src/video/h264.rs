@@ -12,7 +12,7 @@ use ffmpeg::{
 use tracing::{debug, error, info, trace, warn};
 
 use crate::base::EncoderBase;
-use crate::video::h264_packet::H264PacketEncoder;
+use crate::video::{VideoEncoder, h264_packet::H264PacketEncoder};
 
 fn is_420(format: ffmpeg::format::Pixel) -> bool {
     format
@@ -31,6 +31,7 @@ pub struct H264EncoderBuilder {
     encoder_priority_override: Option<&'static [&'static str]>,
     is_export: bool,
     crf: Option<u8>,
+    global_header: bool,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -68,6 +69,7 @@ impl H264EncoderBuilder {
             encoder_priority_override: None,
             is_export: false,
             crf: None,
+            global_header: false,
         }
     }
 
@@ -117,6 +119,12 @@ impl H264EncoderBuilder {
         self
     }
 
+    /// Puts SPS/PPS in the codec extradata rather than in-band, which FLV (RTMP) requires.
+    pub fn with_global_header(mut self) -> Self {
+        self.global_header = true;
+        self
+    }
+
     pub fn build(
         self,
         output: &mut format::context::Output,
@@ -165,6 +173,7 @@ impl H264EncoderBuilder {
                 self.bpp,
                 self.external_conversion,
                 self.crf,
+                self.global_header,
             ) {
                 Ok(encoder) => {
                     let is_hardware = matches!(
@@ -226,6 +235,7 @@ impl H264EncoderBuilder {
         bpp: f32,
         external_conversion: bool,
         crf: Option<u8>,
+        global_header: bool,
     ) -> Result<H264Encoder, H264EncoderError> {
         let OpenedVideoEncoder {
             encoder,
@@ -237,7 +247,7 @@ impl H264EncoderBuilder {
             input_format,
             input_width,
             input_height,
-        } = open_video_encoder(
+        } = open_video_encoder_with_flags(
             codec,
             encoder_options,
             input_config,
@@ -246,6 +256,7 @@ impl H264EncoderBuilder {
             bpp,
             external_conversion,
             crf,
+            global_header,
         )?;
 
         let mut output_stream = output.add_stream(codec)?;
@@ -380,30 +391,6 @@ pub(crate) struct OpenedVideoEncoder {
     pub input_height: u32,
 }
 
-#[allow(clippy::too_many_arguments)]
-pub(crate) fn open_video_encoder(
-    codec: Codec,
-    encoder_options: Dictionary<'static>,
-    input_config: &VideoInfo,
-    output_width: u32,
-    output_height: u32,
-    bpp: f32,
-    external_conversion: bool,
-    crf: Option<u8>,
-) -> Result<OpenedVideoEncoder, H264EncoderError> {
-    open_video_encoder_inner(
-        codec,
-        encoder_options,
-        input_config,
-        output_width,
-        output_height,
-        bpp,
-        external_conversion,
-        crf,
-        false,
-    )
-}
-
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn open_video_encoder_with_flags(
     codec: Codec,
@@ -752,6 +739,34 @@ impl H264Encoder {
     }
 }
 
+impl VideoEncoder for H264Encoder {
+    type BuildError = H264EncoderError;
+    type QueueError = QueueFrameError;
+
+    fn queue_frame(
+        &mut self,
+        frame: frame::Video,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        H264Encoder::queue_frame(self, frame, timestamp, output)
+    }
+
+    fn queue_frame_reusable(
+        &mut self,
+        frame: &mut frame::Video,
+        converted_frame: &mut Option<frame::Video>,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        H264Encoder::queue_frame_reusable(self, frame, converted_frame, timestamp, output)
+    }
+
+    fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        H264Encoder::flush(self, output)
+    }
+}
+
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 const VIDEOTOOLBOX_4K_MAX_FPS: f64 = 55.0;
 #[cfg(any(target_os = "macos", target_os = "windows"))]
@@ -912,7 +927,7 @@ fn get_encoder_priority_with_override(
     override_priority.unwrap_or_else(|| get_default_encoder_priority(config))
 }
 
-fn force_software_encoder() -> bool {
+pub(crate) fn force_software_encoder() -> bool {
     std::env::var("CAP_EXPORT_FORCE_SOFTWARE_ENCODER").is_ok_and(|value| {
         matches!(
             value.to_ascii_lowercase().as_str(),
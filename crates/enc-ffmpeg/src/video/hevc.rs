@@ -11,7 +11,7 @@ use ffmpeg::{
 };
 use tracing::{debug, error, trace, warn};
 
-use crate::base::EncoderBase;
+use crate::{base::EncoderBase, video::VideoEncoder};
 
 fn is_420(format: ffmpeg::format::Pixel) -> bool {
     format
@@ -26,6 +26,9 @@ pub struct HevcEncoderBuilder {
     preset: HevcPreset,
     output_size: Option<(u32, u32)>,
     external_conversion: bool,
+    is_export: bool,
+    crf: Option<u8>,
+    hvc1_tag: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -57,6 +60,9 @@ impl HevcEncoderBuilder {
             preset: HevcPreset::Ultrafast,
             output_size: None,
             external_conversion: false,
+            is_export: false,
+            crf: None,
+            hvc1_tag: false,
         }
     }
 
@@ -84,6 +90,23 @@ impl HevcEncoderBuilder {
         self
     }
 
+    pub fn with_export_settings(mut self) -> Self {
+        self.is_export = true;
+        self
+    }
+
+    pub fn with_crf(mut self, crf: u8) -> Self {
+        self.crf = Some(crf);
+        self
+    }
+
+    /// Tag the stream `hvc1` instead of FFmpeg's default `hev1`. QuickTime and Safari only play
+    /// `hvc1`, which keeps the parameter sets out of band, so MP4 exports should set this.
+    pub fn with_hvc1_tag(mut self) -> Self {
+        self.hvc1_tag = true;
+        self
+    }
+
     pub fn build(
         self,
         output: &mut format::context::Output,
@@ -106,7 +129,8 @@ impl HevcEncoderBuilder {
             );
         }
 
-        let candidates = get_codec_and_options(&input_config, self.preset);
+        let candidates =
+            get_codec_and_options(&input_config, self.preset, self.is_export, self.crf);
         if candidates.is_empty() {
             return Err(HevcEncoderError::CodecNotFound);
         }
@@ -125,6 +149,8 @@ impl HevcEncoderBuilder {
                 output_height,
                 self.bpp,
                 self.external_conversion,
+                self.crf,
+                self.hvc1_tag,
             ) {
                 Ok(encoder) => {
                     debug!("Using HEVC encoder {}", codec_name);
@@ -150,20 +176,30 @@ impl HevcEncoderBuilder {
         output_height: u32,
         bpp: f32,
         external_conversion: bool,
+        crf: Option<u8>,
+        hvc1_tag: bool,
     ) -> Result<HevcEncoder, HevcEncoderError> {
-        let encoder_supports_input_format = codec
+        let supported_formats = codec
             .video()
             .ok()
             .and_then(|codec_video| codec_video.formats())
-            .is_some_and(|mut formats| formats.any(|f| f == input_config.pixel_format));
+            .map(|formats| formats.collect::<Vec<_>>())
+            .unwrap_or_default();
 
         let mut needs_pixel_conversion = false;
 
-        let output_format = if encoder_supports_input_format {
+        let output_format = if supported_formats.contains(&input_config.pixel_format) {
             input_config.pixel_format
         } else {
             needs_pixel_conversion = true;
-            let format = ffmpeg::format::Pixel::NV12;
+            // Hardware encoders take NV12, while libx265 only accepts planar YUV.
+            let format = if supported_formats.is_empty()
+                || supported_formats.contains(&ffmpeg::format::Pixel::NV12)
+            {
+                ffmpeg::format::Pixel::NV12
+            } else {
+                ffmpeg::format::Pixel::YUV420P
+            };
             if !external_conversion {
                 debug!(
                     "Converting from {:?} to {:?} for HEVC encoding",
@@ -253,17 +289,28 @@ impl HevcEncoderBuilder {
                 ffmpeg::ffi::AVColorPrimaries::AVCOL_PRI_BT709;
             (*encoder.as_mut_ptr()).color_trc =
                 ffmpeg::ffi::AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+            if output
+                .format()
+                .flags()
+                .contains(format::Flags::GLOBAL_HEADER)
+            {
+                (*encoder.as_mut_ptr()).flags |= ffmpeg::ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32;
+            }
         }
 
-        let bitrate = get_bitrate(
-            output_width,
-            output_height,
-            input_config.frame_rate.0 as f32 / input_config.frame_rate.1.max(1) as f32,
-            bpp,
-        );
+        if crf.is_some() {
+            encoder.set_bit_rate(0);
+        } else {
+            let bitrate = get_bitrate(
+                output_width,
+                output_height,
+                input_config.frame_rate.0 as f32 / input_config.frame_rate.1.max(1) as f32,
+                bpp,
+            );
 
-        encoder.set_bit_rate(bitrate);
-        encoder.set_max_bit_rate(bitrate);
+            encoder.set_bit_rate(bitrate);
+            encoder.set_max_bit_rate(bitrate);
+        }
 
         let encoder = encoder.open_with(encoder_options)?;
 
@@ -273,10 +320,22 @@ impl HevcEncoderBuilder {
         output_stream.set_rate(input_config.frame_rate);
         output_stream.set_parameters(&encoder);
 
+        if hvc1_tag {
+            unsafe {
+                let codecpar = (*output_stream.as_mut_ptr()).codecpar;
+                (*codecpar).codec_tag = mktag(b"hvc1");
+            }
+        }
+
+        let converted_frame_pool = converter
+            .as_ref()
+            .map(|_| frame::Video::new(output_format, output_width, output_height));
+
         Ok(HevcEncoder {
             base: EncoderBase::new(stream_index),
             encoder,
             converter,
+            converted_frame_pool,
             output_format,
             output_width,
             output_height,
@@ -291,6 +350,7 @@ pub struct HevcEncoder {
     base: EncoderBase,
     encoder: encoder::Video,
     converter: Option<ffmpeg::software::scaling::Context>,
+    converted_frame_pool: Option<frame::Video>,
     output_format: format::Pixel,
     output_width: u32,
     output_height: u32,
@@ -348,19 +408,50 @@ impl HevcEncoder {
         self.base
             .update_pts(&mut frame, timestamp, &mut self.encoder);
 
-        if let Some(converter) = &mut self.converter {
+        let frame_to_send = if let Some(converter) = &mut self.converter {
             let pts = frame.pts();
-            let mut converted =
-                frame::Video::new(self.output_format, self.output_width, self.output_height);
+            let converted = self.converted_frame_pool.as_mut().unwrap();
             converter
-                .run(&frame, &mut converted)
+                .run(&frame, converted)
                 .map_err(QueueFrameError::Converter)?;
             converted.set_pts(pts);
-            frame = converted;
-        }
+            converted as &frame::Video
+        } else {
+            &frame
+        };
 
         self.base
-            .send_frame(&frame, output, &mut self.encoder)
+            .send_frame(frame_to_send, output, &mut self.encoder)
+            .map_err(QueueFrameError::Encode)?;
+
+        Ok(())
+    }
+
+    pub fn queue_frame_reusable(
+        &mut self,
+        frame: &mut frame::Video,
+        converted_frame: &mut Option<frame::Video>,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        self.base.update_pts(frame, timestamp, &mut self.encoder);
+
+        let frame_to_send = if let Some(converter) = &mut self.converter {
+            let pts = frame.pts();
+            let converted = converted_frame.get_or_insert_with(|| {
+                frame::Video::new(self.output_format, self.output_width, self.output_height)
+            });
+            converter
+                .run(frame, converted)
+                .map_err(QueueFrameError::Converter)?;
+            converted.set_pts(pts);
+            converted as &frame::Video
+        } else {
+            frame as &frame::Video
+        };
+
+        self.base
+            .send_frame(frame_to_send, output, &mut self.encoder)
             .map_err(QueueFrameError::Encode)?;
 
         Ok(())
@@ -397,6 +488,40 @@ impl HevcEncoder {
     }
 }
 
+impl VideoEncoder for HevcEncoder {
+    type BuildError = HevcEncoderError;
+    type QueueError = QueueFrameError;
+
+    fn queue_frame(
+        &mut self,
+        frame: frame::Video,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        HevcEncoder::queue_frame(self, frame, timestamp, output)
+    }
+
+    fn queue_frame_reusable(
+        &mut self,
+        frame: &mut frame::Video,
+        converted_frame: &mut Option<frame::Video>,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), QueueFrameError> {
+        HevcEncoder::queue_frame_reusable(self, frame, converted_frame, timestamp, output)
+    }
+
+    fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error> {
+        HevcEncoder::flush(self, output)
+    }
+}
+
+unsafe impl Send for HevcEncoder {}
+
+pub(crate) fn mktag(tag: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*tag)
+}
+
 fn get_encoder_priority() -> &'static [&'static str] {
     #[cfg(target_os = "macos")]
     {
@@ -440,6 +565,8 @@ fn get_encoder_priority() -> &'static [&'static str] {
 fn get_codec_and_options(
     config: &VideoInfo,
     preset: HevcPreset,
+    is_export: bool,
+    crf: Option<u8>,
 ) -> Vec<(Codec, Dictionary<'static>)> {
     let keyframe_interval_secs = 2;
     let denominator = config.frame_rate.denominator();
@@ -450,7 +577,13 @@ fn get_codec_and_options(
         .max(1.0) as i32;
     let keyframe_interval_str = keyframe_interval.to_string();
 
-    let encoder_priority = get_encoder_priority();
+    let encoder_priority = if crf.is_some() || crate::h264::force_software_encoder() {
+        &["libx265"] as &[&str]
+    } else {
+        get_encoder_priority()
+    };
+
+    let crf_str = crf.map(|v| v.to_string());
 
     let mut encoders = Vec::new();
 
@@ -463,13 +596,23 @@ fn get_codec_and_options(
 
         match *encoder_name {
             "hevc_videotoolbox" => {
-                options.set("realtime", "true");
-                options.set("prio_speed", "true");
-                options.set("max_ref_frames", "1");
+                if is_export {
+                    options.set("realtime", "false");
+                    options.set("profile", "main");
+                } else {
+                    options.set("realtime", "true");
+                    options.set("prio_speed", "true");
+                    options.set("max_ref_frames", "1");
+                }
             }
             "hevc_nvenc" => {
-                options.set("preset", "p4");
-                options.set("tune", "ll");
+                if is_export {
+                    options.set("preset", "p5");
+                    options.set("tune", "hq");
+                } else {
+                    options.set("preset", "p4");
+                    options.set("tune", "ll");
+                }
                 options.set("rc", "vbr");
                 options.set("spatial-aq", "1");
                 options.set("temporal-aq", "1");
@@ -477,13 +620,18 @@ fn get_codec_and_options(
                 options.set("g", &keyframe_interval_str);
             }
             "hevc_qsv" => {
-                options.set("preset", "faster");
+                options.set("preset", if is_export { "medium" } else { "faster" });
                 options.set("look_ahead", "1");
                 options.set("g", &keyframe_interval_str);
             }
             "hevc_amf" => {
-                options.set("quality", "balanced");
-                options.set("rc", "vbr_latency");
+                if is_export {
+                    options.set("quality", "quality");
+                    options.set("rc", "vbr_peak");
+                } else {
+                    options.set("quality", "balanced");
+                    options.set("rc", "vbr_latency");
+                }
                 options.set("g", &keyframe_interval_str);
             }
             "hevc_mf" => {
@@ -493,16 +641,30 @@ fn get_codec_and_options(
                 options.set("g", &keyframe_interval_str);
             }
             "libx265" => {
-                options.set(
-                    "preset",
-                    match preset {
-                        HevcPreset::Slow => "slow",
-                        HevcPreset::Medium => "medium",
-                        HevcPreset::Ultrafast => "ultrafast",
-                    },
-                );
-                if let HevcPreset::Ultrafast = preset {
-                    options.set("tune", "zerolatency");
+                if let Some(ref crf_val) = crf_str {
+                    options.set("preset", "slow");
+                    options.set("crf", crf_val);
+                } else if is_export {
+                    options.set(
+                        "preset",
+                        match preset {
+                            HevcPreset::Slow => "slow",
+                            HevcPreset::Medium => "medium",
+                            HevcPreset::Ultrafast => "veryfast",
+                        },
+                    );
+                } else {
+                    options.set(
+                        "preset",
+                        match preset {
+                            HevcPreset::Slow => "slow",
+                            HevcPreset::Medium => "medium",
+                            HevcPreset::Ultrafast => "ultrafast",
+                        },
+                    );
+                    if let HevcPreset::Ultrafast = preset {
+                        options.set("tune", "zerolatency");
+                    }
                 }
                 options.set("g", &keyframe_interval_str);
             }
@@ -1,4 +1,10 @@
+mod video_encoder;
+pub use video_encoder::*;
+
+pub mod av1;
+pub mod codec_encoder;
 pub mod h264;
 pub mod h264_packet;
 pub mod hevc;
 pub mod prores;
+pub mod vp9;
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use ffmpeg::{format, frame};
+
+/// A video encoder that owns a stream in a muxer's output context. Implemented by every codec
+/// the file muxers (e.g. [`crate::mp4::MP4File`]) can carry, so the muxer doesn't have to care
+/// which codec it's writing.
+pub trait VideoEncoder: Send {
+    type BuildError: std::error::Error;
+    type QueueError: std::error::Error;
+
+    fn queue_frame(
+        &mut self,
+        frame: frame::Video,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), Self::QueueError>;
+
+    fn queue_frame_reusable(
+        &mut self,
+        frame: &mut frame::Video,
+        converted_frame: &mut Option<frame::Video>,
+        timestamp: Duration,
+        output: &mut format::context::Output,
+    ) -> Result<(), Self::QueueError>;
+
+    fn flush(&mut self, output: &mut format::context::Output) -> Result<(), ffmpeg::Error>;
+}
@@ -0,0 +1,61 @@
+use ffmpeg::Dictionary;
+
+use crate::video::codec_encoder::{
+    CodecEncoder, CodecEncoderBuilder, CodecEncoderError, EncoderCodec,
+};
+
+pub use crate::video::codec_encoder::QueueFrameError;
+
+/// VP9. Uses Quick Sync on Windows where it's available and libvpx everywhere else.
+pub struct Vp9;
+
+pub type Vp9Encoder = CodecEncoder<Vp9>;
+pub type Vp9EncoderBuilder = CodecEncoderBuilder<Vp9>;
+pub type Vp9EncoderError = CodecEncoderError;
+
+const SOFTWARE_ENCODERS: &[&str] = &["libvpx-vp9"];
+
+impl EncoderCodec for Vp9 {
+    const NAME: &'static str = "VP9";
+    const QUALITY_BPP: f32 = 0.2;
+    const MAX_CRF: u8 = 63;
+
+    fn candidates(software: bool) -> &'static [&'static str] {
+        if software {
+            return SOFTWARE_ENCODERS;
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            &["vp9_qsv", "libvpx-vp9"]
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            SOFTWARE_ENCODERS
+        }
+    }
+
+    fn set_options(encoder: &str, options: &mut Dictionary<'static>, crf: Option<u8>) {
+        match encoder {
+            "vp9_qsv" => {
+                options.set("preset", "medium");
+            }
+            "libvpx-vp9" => {
+                // `good` with cpu-used 4 is libvpx's recommended speed/quality trade-off for
+                // offline encodes; row-mt lets it use more than one thread per tile column.
+                options.set("deadline", "good");
+                options.set("cpu-used", "4");
+                options.set("row-mt", "1");
+                options.set("tile-columns", "2");
+                options.set("frame-parallel", "0");
+                options.set("auto-alt-ref", "1");
+                options.set("lag-in-frames", "25");
+                if let Some(crf) = crf {
+                    options.set("crf", &crf.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use cap_enc_ffmpeg::remux::{audio_stream_count, downmix_audio_tracks};
+use std::path::Path;
+use tracing::info;
+
+use crate::ExportError;
+
+/// Number of audio tracks in a finished recording or export, or 0 if it can't be read.
+///
+/// Instant recordings made with separate audio tracks carry the microphone and system audio as
+/// two labelled tracks, which most players and the share page only play the first of.
+pub fn audio_track_count(path: &Path) -> usize {
+    audio_stream_count(path).unwrap_or(0)
+}
+
+/// Writes `input` to `output`, mixing its audio tracks into a single one when it has several and
+/// copying it unchanged otherwise. Returns whether the audio was down-mixed.
+pub fn copy_with_downmix(input: &Path, output: &Path) -> Result<bool, ExportError> {
+    let tracks = audio_track_count(input);
+    if tracks < 2 {
+        std::fs::copy(input, output)?;
+        return Ok(false);
+    }
+
+    downmix_audio_tracks(input, output).map_err(|e| ExportError::FFmpeg(e.to_string()))?;
+    info!(
+        "Mixed {tracks} audio tracks of '{}' into '{}'",
+        input.display(),
+        output.display()
+    );
+
+    Ok(true)
+}
@@ -0,0 +1,145 @@
+use cap_enc_ffmpeg::remux::{
+    ChapterCue, container_supports_chapters, get_media_duration, mux_chapters,
+};
+use cap_project::{
+    Chapter, ChapterMarker, ProjectConfiguration, RecordingMeta, project_chapters,
+    write_chapters_json,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::ExportError;
+
+/// Where the chapters JSON for the web player is written next to an export.
+pub fn chapters_json_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("chapters.json")
+}
+
+/// The recording's chapter markers projected onto the project's timeline. Empty when nothing was
+/// marked while recording.
+pub fn export_chapters(
+    recording_meta: &RecordingMeta,
+    config: &ProjectConfiguration,
+    recording_durations: &[f64],
+) -> Vec<Chapter> {
+    project_chapters(
+        &recording_meta.chapters,
+        config.timeline.as_ref(),
+        recording_durations,
+    )
+}
+
+/// Chapters to write into an export, snapshotted before the render consumes the
+/// [`crate::ExporterBase`].
+pub(crate) struct PendingChapters {
+    chapters: Vec<Chapter>,
+    json_sidecar: bool,
+}
+
+impl PendingChapters {
+    pub(crate) fn new(chapters: Vec<Chapter>, json_sidecar: bool) -> Self {
+        Self {
+            chapters,
+            json_sidecar,
+        }
+    }
+
+    /// Rewrites the finished export with its chapters and, if requested, writes the web player's
+    /// chapters JSON next to it.
+    pub(crate) async fn apply(self, output_path: PathBuf) -> Result<PathBuf, String> {
+        if self.chapters.is_empty() {
+            return Ok(output_path);
+        }
+
+        tokio::task::spawn_blocking(move || {
+            self.apply_blocking(&output_path)?;
+            Ok::<_, ExportError>(output_path)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+    }
+
+    fn apply_blocking(&self, output_path: &Path) -> Result<(), ExportError> {
+        if self.json_sidecar {
+            std::fs::write(
+                chapters_json_path(output_path),
+                write_chapters_json(&self.chapters),
+            )?;
+        }
+
+        if container_supports_chapters(output_path) {
+            embed_chapters(output_path, &self.chapters)?;
+        } else {
+            warn!(
+                path = %output_path.display(),
+                "Container can't carry chapters, skipping embedded chapters"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes an instant recording's markers into its exported copy. Instant recordings are never
+/// edited, so marker time is output time. Returns the chapters JSON path if one was written.
+pub fn apply_instant_chapters(
+    output_path: &Path,
+    markers: &[ChapterMarker],
+    json_sidecar: bool,
+) -> Result<Option<PathBuf>, ExportError> {
+    if markers.is_empty() {
+        return Ok(None);
+    }
+
+    let Some(duration) = get_media_duration(output_path) else {
+        warn!(
+            path = %output_path.display(),
+            "Couldn't read the recording's duration, skipping chapters"
+        );
+        return Ok(None);
+    };
+
+    let chapters = project_chapters(markers, None, &[duration.as_secs_f64()]);
+    if chapters.is_empty() {
+        return Ok(None);
+    }
+
+    PendingChapters::new(chapters, json_sidecar).apply_blocking(output_path)?;
+
+    Ok(json_sidecar.then(|| chapters_json_path(output_path)))
+}
+
+/// Rewrites the video at `output_path` in place with `chapters`. Every stream is copied, so this
+/// also works on instant recordings, which are never re-encoded.
+pub fn embed_chapters(output_path: &Path, chapters: &[Chapter]) -> Result<(), ExportError> {
+    let cues = chapters
+        .iter()
+        .map(|chapter| ChapterCue {
+            start: Duration::from_secs_f64(chapter.start.max(0.0)),
+            end: Duration::from_secs_f64(chapter.end.max(chapter.start).max(0.0)),
+            title: chapter.title.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let ext = output_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let temp_path = output_path.with_extension(format!("chapters.{ext}"));
+
+    if let Err(e) = mux_chapters(output_path, &temp_path, &cues) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(ExportError::FFmpeg(format!(
+            "Failed to embed chapters: {e}"
+        )));
+    }
+
+    std::fs::rename(&temp_path, output_path)?;
+    info!(chapters = cues.len(), "Embedded chapters");
+
+    Ok(())
+}
@@ -46,7 +46,7 @@ impl GifExportSettings {
         let fps = self.fps;
 
         let output_size = ProjectUniforms::get_output_size(
-            &base.render_constants.options,
+            base.render_backend.options(),
             &base.project_config,
             self.resolution_base,
         );
@@ -114,26 +114,27 @@ impl GifExportSettings {
                 .and_then(|v| v.map_err(|v| v.to_string()))
         });
 
-        let render_video_task = cap_rendering::render_video_to_channel(
-            &base.render_constants,
-            &base.project_config,
-            tx_image_data,
-            &base.recording_meta,
-            meta,
-            base.segments
-                .iter()
-                .map(|s| RenderSegment {
-                    cursor: s.cursor.clone(),
-                    keyboard: s.keyboard.clone(),
-                    decoders: s.decoders.clone(),
-                    render_display: true,
-                })
-                .collect(),
-            fps,
-            self.resolution_base,
-            &base.recordings,
-        )
-        .then(|f| async { f.map_err(|v| v.to_string()) });
+        let render_video_task = base
+            .render_backend
+            .render_to_channel(
+                &base.project_config,
+                tx_image_data,
+                &base.recording_meta,
+                meta,
+                base.segments
+                    .iter()
+                    .map(|s| RenderSegment {
+                        cursor: s.cursor.clone(),
+                        keyboard: s.keyboard.clone(),
+                        decoders: s.decoders.clone(),
+                        render_display: true,
+                    })
+                    .collect(),
+                fps,
+                self.resolution_base,
+                &base.recordings,
+            )
+            .then(|f| async { f.map_err(|v| v.to_string()) });
 
         let (output_path, _) =
             tokio::try_join!(encoder_thread, render_video_task).map_err(|e| e.to_string())?;
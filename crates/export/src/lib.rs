@@ -1,16 +1,25 @@
+pub mod audio_tracks;
+pub mod chapters;
 pub mod gif;
+pub mod loudness;
 pub mod mov;
 pub mod mp4;
+pub mod mp4_codec;
 pub mod preview;
 pub mod settings;
+pub mod subtitles;
+pub mod webm;
 
-use cap_editor::SegmentMedia;
+use cap_editor::{SegmentMedia, get_audio_segments, load_music_tracks_uncached};
 use cap_project::{
     BackgroundSource, ProjectConfiguration, RecordingMeta, StudioRecordingMeta,
     TimelineConfiguration, TimelineSegment,
 };
-use cap_rendering::{ProjectRecordingsMeta, RenderVideoConstants};
+use cap_rendering::{ProjectRecordingsMeta, RenderBackend};
+use chapters::PendingChapters;
+use loudness::{LoudnessNormalization, LoudnessReport};
 use std::{path::PathBuf, sync::Arc};
+use subtitles::{PendingSubtitles, SubtitleExportOptions};
 
 #[derive(thiserror::Error, Debug)]
 pub enum ExportError {
@@ -59,6 +68,9 @@ pub struct ExporterBuilder {
     config: Option<ProjectConfiguration>,
     output_path: Option<PathBuf>,
     force_ffmpeg_decoder: bool,
+    subtitles: SubtitleExportOptions,
+    chapters_json: bool,
+    loudness: Option<LoudnessNormalization>,
 }
 
 impl ExporterBuilder {
@@ -77,6 +89,23 @@ impl ExporterBuilder {
         self
     }
 
+    pub fn with_subtitles(mut self, subtitles: SubtitleExportOptions) -> Self {
+        self.subtitles = subtitles;
+        self
+    }
+
+    /// Also write the web player's chapters JSON next to the export when the recording has
+    /// chapter markers. Chapters are embedded in MP4, MOV and Matroska outputs either way.
+    pub fn with_chapters_json(mut self, chapters_json: bool) -> Self {
+        self.chapters_json = chapters_json;
+        self
+    }
+
+    pub fn with_loudness_normalization(mut self, loudness: Option<LoudnessNormalization>) -> Self {
+        self.loudness = loudness;
+        self
+    }
+
     pub async fn build(self) -> Result<ExporterBase, ExporterBuildError> {
         type Error = ExporterBuildError;
 
@@ -117,6 +146,7 @@ impl ExporterBuilder {
                         end: duration,
                         timescale: 1.0,
                         name: None,
+                        transition: None,
                     })
                 })
                 .collect();
@@ -130,12 +160,13 @@ impl ExporterBuilder {
                     caption_segments: Vec::new(),
                     keyboard_segments: Vec::new(),
                     audio_segments: Vec::new(),
+                    annotation_segments: Vec::new(),
                 });
             }
         }
 
-        let render_constants = Arc::new(
-            RenderVideoConstants::new(
+        let render_backend = Arc::new(
+            RenderBackend::new(
                 &recordings.segments,
                 recording_meta.clone(),
                 studio_meta.clone(),
@@ -162,11 +193,15 @@ impl ExporterBuilder {
             output_path,
             studio_meta: studio_meta.clone(),
             recordings,
-            render_constants,
+            render_backend,
             segments,
             recording_meta,
             project_config,
             project_path: self.project_path,
+            subtitles: self.subtitles,
+            chapters_json: self.chapters_json,
+            loudness: self.loudness,
+            loudness_report: None,
         })
     }
 }
@@ -183,12 +218,14 @@ pub fn make_cursor_only_project(mut project_config: ProjectConfiguration) -> Pro
     project_config.camera.hide = true;
     project_config.captions = None;
     project_config.keyboard = None;
+    project_config.watermark = None;
 
     if let Some(timeline) = project_config.timeline.as_mut() {
         timeline.mask_segments.clear();
         timeline.text_segments.clear();
         timeline.caption_segments.clear();
         timeline.keyboard_segments.clear();
+        timeline.annotation_segments.clear();
     }
 
     project_config
@@ -200,9 +237,13 @@ pub struct ExporterBase {
     project_config: ProjectConfiguration,
     studio_meta: StudioRecordingMeta,
     recordings: Arc<ProjectRecordingsMeta>,
-    render_constants: Arc<RenderVideoConstants>,
+    render_backend: Arc<RenderBackend>,
     segments: Vec<SegmentMedia>,
     output_path: PathBuf,
+    subtitles: SubtitleExportOptions,
+    chapters_json: bool,
+    loudness: Option<LoudnessNormalization>,
+    loudness_report: Option<LoudnessReport>,
 }
 
 impl ExporterBase {
@@ -223,6 +264,92 @@ impl ExporterBase {
             config: None,
             output_path: None,
             force_ffmpeg_decoder: false,
+            subtitles: SubtitleExportOptions::default(),
+            chapters_json: false,
+            loudness: None,
+        }
+    }
+
+    /// Extensions of the sidecar caption files the export will write next to its output, given
+    /// the project's captions and the requested [`SubtitleExportOptions`]. Empty when there are
+    /// no captions.
+    pub fn subtitle_sidecar_extensions(&self) -> Vec<&'static str> {
+        if subtitles::caption_segments(&self.project_config).is_empty() {
+            return vec![];
+        }
+
+        [
+            (self.subtitles.srt_sidecar, "srt"),
+            (self.subtitles.vtt_sidecar, "vtt"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, ext)| enabled.then_some(ext))
+        .collect()
+    }
+
+    pub(crate) fn pending_subtitles(&self) -> PendingSubtitles {
+        PendingSubtitles::new(&self.project_config, self.subtitles)
+    }
+
+    /// The export's chapters, projected from the markers set while recording.
+    pub fn chapters(&self) -> Vec<cap_project::Chapter> {
+        let durations = self
+            .recordings
+            .segments
+            .iter()
+            .map(|segment| segment.duration())
+            .collect::<Vec<_>>();
+        chapters::export_chapters(&self.recording_meta, &self.project_config, &durations)
+    }
+
+    /// Whether the export will write a chapters JSON next to its output.
+    pub fn writes_chapters_json(&self) -> bool {
+        self.chapters_json && !self.chapters().is_empty()
+    }
+
+    pub(crate) fn pending_chapters(&self) -> PendingChapters {
+        PendingChapters::new(self.chapters(), self.chapters_json)
+    }
+
+    /// First pass of loudness normalization: renders and measures the mixed timeline audio, and
+    /// returns the gain the export will apply. `None` when normalization wasn't requested. The
+    /// result is cached, so calling this ahead of an export (to report it) doesn't render twice.
+    pub async fn analyze_loudness(&mut self) -> Result<Option<LoudnessReport>, String> {
+        let Some(target) = self.loudness else {
+            return Ok(None);
+        };
+
+        if let Some(report) = self.loudness_report {
+            return Ok(Some(report));
         }
+
+        let segments = get_audio_segments(&self.segments);
+        let music = load_music_tracks_uncached(&self.project_config, &self.project_path);
+        let project = self.project_config.clone();
+        let duration_secs = cap_rendering::get_duration(
+            &self.recordings,
+            &self.recording_meta,
+            &self.studio_meta,
+            &self.project_config,
+        );
+
+        let report = tokio::task::spawn_blocking(move || {
+            loudness::analyze(segments, music, &project, duration_secs, target)
+        })
+        .await
+        .map_err(|e| format!("Loudness analysis failed: {e}"))?;
+
+        tracing::info!(?report, "Analyzed export loudness");
+
+        self.loudness_report = Some(report);
+        Ok(Some(report))
+    }
+
+    /// Linear gain the encode pass applies to every audio frame.
+    pub(crate) async fn audio_gain(&mut self) -> Result<f32, String> {
+        Ok(self
+            .analyze_loudness()
+            .await?
+            .map_or(1.0, |report| report.linear_gain()))
     }
 }
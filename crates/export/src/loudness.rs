@@ -0,0 +1,199 @@
+use cap_audio::LoudnessMeter;
+use cap_editor::{AudioRenderer, AudioSegment, MusicTracks};
+use cap_project::ProjectConfiguration;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Loudest boost normalization will apply, so a near-silent timeline isn't
+/// blown up into amplified noise.
+const MAX_GAIN_DB: f64 = 24.0;
+
+const ANALYSIS_CHUNK_SAMPLES: usize = 4096;
+
+/// EBU R128 style loudness normalization applied to the mixed export audio.
+#[derive(Deserialize, Serialize, Type, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LoudnessNormalization {
+    /// Integrated loudness to hit, in LUFS.
+    pub target_lufs: f64,
+    /// Ceiling for the true peak after gain, in dBTP. Wins over `target_lufs`
+    /// when both can't be met with a single gain.
+    pub true_peak_dbtp: f64,
+}
+
+impl Default for LoudnessNormalization {
+    fn default() -> Self {
+        Self {
+            target_lufs: -16.0,
+            true_peak_dbtp: -1.0,
+        }
+    }
+}
+
+/// Result of the loudness analysis pass and the gain the encode pass applies.
+#[derive(Serialize, Type, Clone, Copy, Debug, PartialEq)]
+pub struct LoudnessReport {
+    /// Integrated loudness of the mix before normalization. `None` when the
+    /// timeline is silent.
+    pub measured_lufs: Option<f64>,
+    pub measured_true_peak_dbtp: Option<f64>,
+    pub target_lufs: f64,
+    pub target_true_peak_dbtp: f64,
+    pub gain_db: f64,
+}
+
+impl LoudnessReport {
+    fn new(
+        measured_lufs: Option<f64>,
+        measured_true_peak_dbtp: Option<f64>,
+        target: LoudnessNormalization,
+    ) -> Self {
+        let gain_db = measured_lufs.map_or(0.0, |lufs| {
+            let gain = target.target_lufs - lufs;
+            let gain = match measured_true_peak_dbtp {
+                Some(peak) => gain.min(target.true_peak_dbtp - peak),
+                None => gain,
+            };
+            gain.min(MAX_GAIN_DB)
+        });
+
+        Self {
+            measured_lufs,
+            measured_true_peak_dbtp,
+            target_lufs: target.target_lufs,
+            target_true_peak_dbtp: target.true_peak_dbtp,
+            gain_db,
+        }
+    }
+
+    /// Normalized loudness the export will have once `gain_db` is applied.
+    pub fn output_lufs(&self) -> Option<f64> {
+        self.measured_lufs.map(|lufs| lufs + self.gain_db)
+    }
+
+    pub fn linear_gain(&self) -> f32 {
+        10.0_f64.powf(self.gain_db / 20.0) as f32
+    }
+}
+
+/// First pass: renders the mixed timeline audio exactly as the encoder will
+/// (recording tracks plus timeline music) and measures it.
+pub(crate) fn analyze(
+    segments: Vec<AudioSegment>,
+    music: MusicTracks,
+    project: &ProjectConfiguration,
+    duration_secs: f64,
+    target: LoudnessNormalization,
+) -> LoudnessReport {
+    let mut renderer = AudioRenderer::new(segments)
+        .with_music(music)
+        .with_blocking_enhancement();
+    renderer.set_playhead(0.0, project);
+
+    let mut meter = LoudnessMeter::new(AudioRenderer::CHANNELS);
+    let total_samples = (duration_secs * AudioRenderer::SAMPLE_RATE as f64).ceil() as usize;
+    let silence = vec![0.0; ANALYSIS_CHUNK_SAMPLES * AudioRenderer::CHANNELS as usize];
+    let mut rendered = 0;
+
+    while rendered < total_samples {
+        let samples = ANALYSIS_CHUNK_SAMPLES.min(total_samples - rendered);
+        match renderer.render_frame_raw(samples, project) {
+            Some((written, data)) => {
+                meter.push_interleaved(&data);
+                rendered += written;
+            }
+            None => {
+                meter.push_interleaved(&silence[..samples * AudioRenderer::CHANNELS as usize]);
+                rendered += samples;
+            }
+        }
+    }
+
+    LoudnessReport::new(meter.integrated_lufs(), meter.true_peak_dbtp(), target)
+}
+
+/// Scales a rendered packed-f32 audio frame in place.
+pub(crate) fn apply_gain(frame: &mut ffmpeg::frame::Audio, gain: f32) {
+    if gain == 1.0 {
+        return;
+    }
+
+    let len = frame.samples() * frame.channels() as usize * size_of::<f32>();
+    for bytes in frame.data_mut(0)[..len].chunks_exact_mut(size_of::<f32>()) {
+        let sample = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) * gain;
+        bytes.copy_from_slice(&sample.to_ne_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_hits_target_loudness() {
+        let report = LoudnessReport::new(Some(-30.0), Some(-12.0), Default::default());
+        assert_eq!(report.gain_db, 14.0);
+        assert_eq!(report.output_lufs(), Some(-16.0));
+    }
+
+    #[test]
+    fn true_peak_ceiling_limits_gain() {
+        let report = LoudnessReport::new(Some(-30.0), Some(-6.0), Default::default());
+        assert_eq!(report.gain_db, 5.0);
+        assert_eq!(report.output_lufs(), Some(-25.0));
+    }
+
+    #[test]
+    fn loud_mixes_are_turned_down() {
+        let target = LoudnessNormalization {
+            target_lufs: -23.0,
+            true_peak_dbtp: -1.0,
+        };
+        let report = LoudnessReport::new(Some(-10.0), Some(-0.5), target);
+        assert_eq!(report.gain_db, -13.0);
+    }
+
+    #[test]
+    fn silent_and_near_silent_mixes_are_left_alone_or_capped() {
+        let silent = LoudnessReport::new(None, None, Default::default());
+        assert_eq!(silent.gain_db, 0.0);
+        assert_eq!(silent.linear_gain(), 1.0);
+
+        let whisper = LoudnessReport::new(Some(-65.0), Some(-50.0), Default::default());
+        assert_eq!(whisper.gain_db, MAX_GAIN_DB);
+    }
+
+    #[test]
+    fn apply_gain_scales_packed_samples() {
+        ffmpeg::init().unwrap();
+
+        let mut frame = ffmpeg::frame::Audio::new(
+            AudioRenderer::SAMPLE_FORMAT,
+            4,
+            ffmpeg::ChannelLayout::STEREO,
+        );
+        let samples = [0.1_f32, -0.2, 0.3, -0.4, 0.5, -0.6, 0.7, -0.8];
+        for (bytes, sample) in frame.data_mut(0).chunks_exact_mut(4).zip(samples) {
+            bytes.copy_from_slice(&sample.to_ne_bytes());
+        }
+
+        apply_gain(&mut frame, 0.5);
+
+        for (bytes, sample) in frame.data(0).chunks_exact(4).zip(samples) {
+            let scaled = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            assert!((scaled - sample * 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn defaults_round_trip_through_partial_json() {
+        let parsed: LoudnessNormalization = serde_json::from_str(r#"{"targetLufs":-23}"#).unwrap();
+        assert_eq!(
+            parsed,
+            LoudnessNormalization {
+                target_lufs: -23.0,
+                true_peak_dbtp: -1.0,
+            }
+        );
+    }
+}
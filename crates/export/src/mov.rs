@@ -29,7 +29,7 @@ impl MovExportSettings {
         let fps = self.fps;
 
         let output_size = ProjectUniforms::get_output_size(
-            &base.render_constants.options,
+            base.render_backend.options(),
             &base.project_config,
             self.resolution_base,
         );
@@ -46,6 +46,8 @@ impl MovExportSettings {
         let video_info =
             VideoInfo::from_raw(RawVideoFormat::Rgba, output_size.0, output_size.1, fps);
 
+        let chapters = base.pending_chapters();
+
         let encoder_thread = tokio::task::spawn_blocking(move || {
             let mut mov_encoder = MOVFile::init(mov_output_path.clone(), |output| {
                 ProResEncoder::builder(video_info).build(output)
@@ -86,31 +88,32 @@ impl MovExportSettings {
                 .and_then(|v| v.map_err(|v| v.to_string()))
         });
 
-        let render_video_task = cap_rendering::render_video_to_channel(
-            &base.render_constants,
-            &base.project_config,
-            tx_image_data,
-            &base.recording_meta,
-            meta,
-            base.segments
-                .iter()
-                .map(|s| RenderSegment {
-                    cursor: s.cursor.clone(),
-                    keyboard: s.keyboard.clone(),
-                    decoders: s.decoders.clone(),
-                    render_display: false,
-                })
-                .collect(),
-            fps,
-            self.resolution_base,
-            &base.recordings,
-        )
-        .then(|f| async { f.map_err(|v| v.to_string()) });
+        let render_video_task = base
+            .render_backend
+            .render_to_channel(
+                &base.project_config,
+                tx_image_data,
+                &base.recording_meta,
+                meta,
+                base.segments
+                    .iter()
+                    .map(|s| RenderSegment {
+                        cursor: s.cursor.clone(),
+                        keyboard: s.keyboard.clone(),
+                        decoders: s.decoders.clone(),
+                        render_display: false,
+                    })
+                    .collect(),
+                fps,
+                self.resolution_base,
+                &base.recordings,
+            )
+            .then(|f| async { f.map_err(|v| v.to_string()) });
 
         let (output_path, _) =
             tokio::try_join!(encoder_thread, render_video_task).map_err(|e| e.to_string())?;
 
-        Ok(output_path)
+        chapters.apply(output_path).await
     }
 }
 
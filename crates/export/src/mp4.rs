@@ -1,6 +1,8 @@
-use crate::ExporterBase;
+use crate::{ExporterBase, loudness::apply_gain};
 use cap_editor::{AudioRenderer, get_audio_segments, load_music_tracks_uncached};
-use cap_enc_ffmpeg::{AudioEncoder, aac::AACEncoder, h264::H264Encoder, mp4::*};
+use cap_enc_ffmpeg::{
+    AudioEncoder, VideoEncoder, aac::AACEncoder, h264::H264Encoder, mp4::*, opus::OpusEncoder,
+};
 use cap_media_info::{RawVideoFormat, VideoInfo};
 use cap_project::XY;
 use cap_rendering::{
@@ -49,7 +51,7 @@ impl ExportCompression {
 }
 
 #[derive(Clone, Default)]
-struct ExportNv12Mode {
+pub(crate) struct ExportNv12Mode {
     stop_after_frames_sent: Option<u32>,
     record_first_queued_ms_since_pipeline: Option<Arc<AtomicU64>>,
     nv12_render_startup_breakdown_ms:
@@ -93,7 +95,7 @@ impl Mp4ExportSettings {
         let fps = self.fps;
 
         let output_size = ProjectUniforms::get_output_size(
-            &base.render_constants.options,
+            base.render_backend.options(),
             &base.project_config,
             self.resolution_base,
         );
@@ -119,7 +121,7 @@ impl Mp4ExportSettings {
     ) -> Result<FirstFrameQueuedBenchmark, String> {
         let fps = self.fps;
         let output_size = ProjectUniforms::get_output_size(
-            &base.render_constants.options,
+            base.render_backend.options(),
             &base.project_config,
             self.resolution_base,
         );
@@ -168,183 +170,227 @@ impl Mp4ExportSettings {
         on_progress: impl FnMut(u32) -> bool + Send + 'static,
         mode: ExportNv12Mode,
     ) -> Result<PathBuf, String> {
-        let pipeline_start = std::time::Instant::now();
-        let output_path = base.output_path.clone();
-        let meta = &base.studio_meta;
-
-        let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<ExportFrame>(4);
-
-        let mut video_info =
-            VideoInfo::from_raw(RawVideoFormat::Nv12, output_size.0, output_size.1, fps);
-        video_info.time_base = ffmpeg::Rational::new(1, fps as i32);
-
-        let audio_segments = get_audio_segments(&base.segments);
-        let music = load_music_tracks_uncached(&base.project_config, &base.project_path);
-
-        let has_recording_audio = audio_segments
-            .first()
-            .filter(|_| !base.project_config.audio.mute)
-            .is_some();
-        let has_audio = has_recording_audio || !music.is_empty();
-
-        let record_first_queued_ms = mode.record_first_queued_ms_since_pipeline;
-        let nv12_render_startup_breakdown_ms = mode.nv12_render_startup_breakdown_ms;
-
-        let project_for_audio = base.project_config.clone();
-        let pipeline_start_for_encoder = pipeline_start;
-        let encoder_thread = tokio::task::spawn_blocking(move || {
-            trace!("Creating MP4File encoder (NV12 path)");
-
-            let mut encoder = MP4File::init(
-                "output",
-                base.output_path.clone(),
-                self.optimize_filesize,
-                |o| {
-                    let builder = H264Encoder::builder(video_info)
-                        .with_bpp(self.effective_bpp())
-                        .with_export_priority()
-                        .with_export_settings()
-                        .with_external_conversion();
-                    let builder = if self.optimize_filesize {
-                        builder.with_crf(self.compression.crf_value())
-                    } else {
-                        builder
-                    };
-                    builder.build(o)
-                },
-                |o| {
-                    has_audio.then(|| {
-                        AACEncoder::init(AudioRenderer::info(), o)
-                            .map(|v| v.boxed())
-                            .map_err(Into::into)
-                    })
-                },
-            )
-            .map_err(|v| v.to_string())?;
+        export_nv12_video(
+            base,
+            output_size,
+            fps,
+            self.resolution_base,
+            Container::Mp4 {
+                faststart: self.optimize_filesize,
+            },
+            move |video_info, o| {
+                let builder = H264Encoder::builder(video_info)
+                    .with_bpp(self.effective_bpp())
+                    .with_export_priority()
+                    .with_export_settings()
+                    .with_external_conversion();
+                let builder = if self.optimize_filesize {
+                    builder.with_crf(self.compression.crf_value())
+                } else {
+                    builder
+                };
+                builder.build(o)
+            },
+            on_progress,
+            mode,
+        )
+        .await
+    }
+}
 
-            info!("Created MP4File encoder (NV12, external conversion, export settings)");
+/// Renders the project as NV12 and muxes it into `container` alongside the mixed timeline audio,
+/// AAC in MP4 and Opus in WebM. `video` builds the stream's encoder, which is how the H264,
+/// HEVC, AV1 and WebM exports share the render, audio, subtitle, chapter and progress plumbing.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn export_nv12_video<V: VideoEncoder + 'static>(
+    mut base: ExporterBase,
+    output_size: (u32, u32),
+    fps: u32,
+    resolution_base: XY<u32>,
+    container: Container,
+    video: impl FnOnce(VideoInfo, &mut ffmpeg::format::context::Output) -> Result<V, V::BuildError>
+    + Send
+    + 'static,
+    on_progress: impl FnMut(u32) -> bool + Send + 'static,
+    mode: ExportNv12Mode,
+) -> Result<PathBuf, String> {
+    let pipeline_start = std::time::Instant::now();
+    let mut output_path = base.output_path.clone();
+    output_path.set_extension(container.extension());
+    let meta = &base.studio_meta;
+
+    let (frame_tx, frame_rx) = std::sync::mpsc::sync_channel::<ExportFrame>(4);
+
+    let mut video_info =
+        VideoInfo::from_raw(RawVideoFormat::Nv12, output_size.0, output_size.1, fps);
+    video_info.time_base = ffmpeg::Rational::new(1, fps as i32);
+
+    let audio_segments = get_audio_segments(&base.segments);
+    let music = load_music_tracks_uncached(&base.project_config, &base.project_path);
+
+    let has_recording_audio = audio_segments
+        .first()
+        .filter(|_| !base.project_config.audio.mute)
+        .is_some();
+    let has_audio = has_recording_audio || !music.is_empty();
+    let audio_gain = if has_audio {
+        base.audio_gain().await?
+    } else {
+        1.0
+    };
 
-            let mut audio_renderer = if has_audio {
-                Some(AudioRenderer::new(audio_segments).with_music(music))
-            } else {
-                None
-            };
+    let record_first_queued_ms = mode.record_first_queued_ms_since_pipeline;
+    let nv12_render_startup_breakdown_ms = mode.nv12_render_startup_breakdown_ms;
+
+    let project_for_audio = base.project_config.clone();
+    let subtitles = base.pending_subtitles();
+    let chapters = base.pending_chapters();
+    let pipeline_start_for_encoder = pipeline_start;
+    let encoder_thread = tokio::task::spawn_blocking(move || {
+        trace!("Creating MP4File encoder (NV12 path)");
+
+        let mut encoder = MP4File::init(
+            "output",
+            output_path.clone(),
+            container,
+            |o| video(video_info, o),
+            |o| {
+                has_audio.then(|| match container {
+                    Container::Mp4 { .. } => AACEncoder::init(AudioRenderer::info(), o)
+                        .map(|v| v.boxed())
+                        .map_err(Into::into),
+                    Container::WebM => OpusEncoder::init(AudioRenderer::info(), o)
+                        .map(|v| v.boxed())
+                        .map_err(Into::into),
+                })
+            },
+        )
+        .map_err(|v| v.to_string())?;
 
-            let mut reusable_frame = ffmpeg::frame::Video::new(
-                ffmpeg::format::Pixel::NV12,
-                output_size.0,
-                output_size.1,
-            );
-            let mut converted_frame: Option<ffmpeg::frame::Video> = None;
-            let mut encoded_frames = 0u32;
-            let encode_start = std::time::Instant::now();
-            let sample_rate = u64::from(AudioRenderer::SAMPLE_RATE);
-            let fps_u64 = u64::from(fps);
-            let mut audio_sample_cursor = 0u64;
-
-            while let Ok(input) = frame_rx.recv() {
-                if encoded_frames == 0
-                    && let Some(audio) = &mut audio_renderer
-                {
-                    audio.set_playhead(0.0, &project_for_audio);
-                }
+        info!("Created MP4File encoder (NV12, export settings)");
 
-                let audio_frame = audio_renderer.as_mut().and_then(|audio| {
-                    let n = u64::from(input.frame_number);
-                    let (pts, samples) =
-                        audio_frame_budget(n, sample_rate, fps_u64, audio_sample_cursor)?;
-                    audio_sample_cursor = pts as u64 + samples as u64;
-                    let mut frame = audio
-                        .render_frame(samples, &project_for_audio)
-                        .unwrap_or_else(|| silent_audio_frame(samples));
-                    frame.set_pts(Some(pts));
-                    Some(frame)
-                });
+        let mut audio_renderer = if has_audio {
+            Some(
+                AudioRenderer::new(audio_segments)
+                    .with_music(music)
+                    .with_blocking_enhancement(),
+            )
+        } else {
+            None
+        };
 
-                fill_nv12_frame_direct(
-                    &mut reusable_frame,
-                    &input.nv12_data,
-                    input.width,
-                    input.height,
-                    input.y_stride,
-                    input.frame_number as i64,
-                );
-                encoder
-                    .queue_video_frame_reusable(
-                        &mut reusable_frame,
-                        &mut converted_frame,
-                        Duration::MAX,
-                    )
-                    .map_err(|err| err.to_string())?;
-                if let Some(audio) = audio_frame {
-                    encoder.queue_audio_frame(audio);
-                }
-                encoded_frames += 1;
-                if encoded_frames == 1
-                    && let Some(atom) = record_first_queued_ms.as_ref()
-                {
-                    let ms = pipeline_start_for_encoder.elapsed().as_millis() as u64;
-                    let _ =
-                        atom.compare_exchange(u64::MAX, ms, Ordering::Relaxed, Ordering::Relaxed);
-                }
-            }
+        let mut reusable_frame =
+            ffmpeg::frame::Video::new(ffmpeg::format::Pixel::NV12, output_size.0, output_size.1);
+        let mut converted_frame: Option<ffmpeg::frame::Video> = None;
+        let mut encoded_frames = 0u32;
+        let encode_start = std::time::Instant::now();
+        let sample_rate = u64::from(AudioRenderer::SAMPLE_RATE);
+        let fps_u64 = u64::from(fps);
+        let mut audio_sample_cursor = 0u64;
 
-            let encode_elapsed = encode_start.elapsed();
-            if encoded_frames > 0 {
-                let encode_fps = encoded_frames as f64 / encode_elapsed.as_secs_f64().max(0.001);
-                info!(
-                    encoded_frames = encoded_frames,
-                    elapsed_secs = format!("{:.2}", encode_elapsed.as_secs_f64()),
-                    encode_fps = format!("{:.1}", encode_fps),
-                    "Encoder thread finished"
-                );
+        while let Ok(input) = frame_rx.recv() {
+            if encoded_frames == 0
+                && let Some(audio) = &mut audio_renderer
+            {
+                audio.set_playhead(0.0, &project_for_audio);
             }
 
-            let res = encoder
-                .finish()
-                .map_err(|e| format!("Failed to finish encoding: {e}"))?;
-
-            if let Err(e) = res.video_finish {
-                return Err(format!("Video encoding failed: {e}"));
+            let audio_frame = audio_renderer.as_mut().and_then(|audio| {
+                let n = u64::from(input.frame_number);
+                let (pts, samples) =
+                    audio_frame_budget(n, sample_rate, fps_u64, audio_sample_cursor)?;
+                audio_sample_cursor = pts as u64 + samples as u64;
+                let mut frame = audio
+                    .render_frame(samples, &project_for_audio)
+                    .unwrap_or_else(|| silent_audio_frame(samples));
+                apply_gain(&mut frame, audio_gain);
+                frame.set_pts(Some(pts));
+                Some(frame)
+            });
+
+            fill_nv12_frame_direct(
+                &mut reusable_frame,
+                &input.nv12_data,
+                input.width,
+                input.height,
+                input.y_stride,
+                input.frame_number as i64,
+            );
+            encoder
+                .queue_video_frame_reusable(
+                    &mut reusable_frame,
+                    &mut converted_frame,
+                    Duration::MAX,
+                )
+                .map_err(|err| err.to_string())?;
+            if let Some(audio) = audio_frame {
+                encoder.queue_audio_frame(audio);
             }
-            if let Err(e) = res.audio_finish {
-                return Err(format!("Audio encoding failed: {e}"));
+            encoded_frames += 1;
+            if encoded_frames == 1
+                && let Some(atom) = record_first_queued_ms.as_ref()
+            {
+                let ms = pipeline_start_for_encoder.elapsed().as_millis() as u64;
+                let _ = atom.compare_exchange(u64::MAX, ms, Ordering::Relaxed, Ordering::Relaxed);
             }
+        }
 
-            Ok::<_, String>(base.output_path)
-        })
-        .then(|r| async { r.map_err(|e| e.to_string()).and_then(|v| v) });
+        let encode_elapsed = encode_start.elapsed();
+        if encoded_frames > 0 {
+            let encode_fps = encoded_frames as f64 / encode_elapsed.as_secs_f64().max(0.001);
+            info!(
+                encoded_frames = encoded_frames,
+                elapsed_secs = format!("{:.2}", encode_elapsed.as_secs_f64()),
+                encode_fps = format!("{:.1}", encode_fps),
+                "Encoder thread finished"
+            );
+        }
 
-        let stop_after_frames_sent = mode.stop_after_frames_sent;
-        let render_video_task = export_render_to_channel(
-            &base.render_constants,
-            &base.project_config,
-            frame_tx,
-            &base.recording_meta,
-            meta,
-            base.segments
-                .iter()
-                .map(|s| RenderSegment {
-                    cursor: s.cursor.clone(),
-                    keyboard: s.keyboard.clone(),
-                    decoders: s.decoders.clone(),
-                    render_display: true,
-                })
-                .collect(),
-            fps,
-            self.resolution_base,
-            &base.recordings,
-            stop_after_frames_sent,
-            nv12_render_startup_breakdown_ms,
-            on_progress,
-            base.project_path.clone(),
-        )
-        .then(|v| async { v.map_err(|e| e.to_string()) });
+        let res = encoder
+            .finish()
+            .map_err(|e| format!("Failed to finish encoding: {e}"))?;
 
-        tokio::try_join!(encoder_thread, render_video_task)?;
+        if let Err(e) = res.video_finish {
+            return Err(format!("Video encoding failed: {e}"));
+        }
+        if let Err(e) = res.audio_finish {
+            return Err(format!("Audio encoding failed: {e}"));
+        }
 
-        Ok(output_path)
-    }
+        Ok::<_, String>(output_path)
+    })
+    .then(|r| async { r.map_err(|e| e.to_string()).and_then(|v| v) });
+
+    let stop_after_frames_sent = mode.stop_after_frames_sent;
+    let render_video_task = export_render_to_channel(
+        &base.render_backend,
+        &base.project_config,
+        frame_tx,
+        &base.recording_meta,
+        meta,
+        base.segments
+            .iter()
+            .map(|s| RenderSegment {
+                cursor: s.cursor.clone(),
+                keyboard: s.keyboard.clone(),
+                decoders: s.decoders.clone(),
+                render_display: true,
+            })
+            .collect(),
+        fps,
+        resolution_base,
+        &base.recordings,
+        stop_after_frames_sent,
+        nv12_render_startup_breakdown_ms,
+        on_progress,
+        base.project_path.clone(),
+    )
+    .then(|v| async { v.map_err(|e| e.to_string()) });
+
+    let (output_path, _) = tokio::try_join!(encoder_thread, render_video_task)?;
+
+    let output_path = subtitles.apply(output_path).await?;
+    chapters.apply(output_path).await
 }
 
 struct ExportFrame {
@@ -609,7 +655,7 @@ fn save_screenshot_from_nv12(
 }
 
 use cap_project::{ProjectConfiguration, RecordingMeta, StudioRecordingMeta};
-use cap_rendering::{ProjectRecordingsMeta, RenderVideoConstants};
+use cap_rendering::{ProjectRecordingsMeta, RenderBackend};
 
 const FRAME_RECEIVE_INITIAL_TIMEOUT_SECS: u64 = 120;
 const FRAME_RECEIVE_STEADY_TIMEOUT_SECS: u64 = 90;
@@ -617,7 +663,7 @@ const MAX_CONSECUTIVE_FRAME_TIMEOUTS: u32 = 3;
 
 #[allow(clippy::too_many_arguments)]
 async fn export_render_to_channel(
-    constants: &RenderVideoConstants,
+    backend: &RenderBackend,
     project: &ProjectConfiguration,
     sender: std::sync::mpsc::SyncSender<ExportFrame>,
     recording_meta: &RecordingMeta,
@@ -636,8 +682,7 @@ async fn export_render_to_channel(
     let screenshot_project_path = project_path;
 
     let render_result = {
-        let render_future = cap_rendering::render_video_to_channel_nv12(
-            constants,
+        let render_future = backend.render_to_channel_nv12(
             project,
             tx_image_data,
             recording_meta,
@@ -0,0 +1,180 @@
+use cap_enc_ffmpeg::{
+    VideoEncoder,
+    av1::Av1Encoder,
+    hevc::{HevcEncoder, HevcPreset},
+    mp4::Container,
+};
+use cap_media_info::VideoInfo;
+use cap_project::XY;
+use cap_rendering::ProjectUniforms;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::{
+    ExporterBase,
+    mp4::{ExportCompression, ExportNv12Mode, export_nv12_video},
+};
+
+/// Video codecs an MP4 export can use besides H264. Picked by the
+/// [`ExportSettings`](crate::settings::ExportSettings) variant, so the settings themselves are
+/// shared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mp4VideoCodec {
+    /// Tagged `hvc1` so it plays in QuickTime and Safari.
+    Hevc,
+    /// Prefers a hardware AV1 encoder where the GPU has one and otherwise falls back to SVT-AV1
+    /// or libaom, so it also works on machines without a GPU.
+    Av1,
+}
+
+impl Mp4VideoCodec {
+    /// Share of H264's bits-per-pixel budget the codec needs for the same quality.
+    fn bpp_scale(self) -> f32 {
+        match self {
+            Self::Hevc => 0.6,
+            Self::Av1 => 0.5,
+        }
+    }
+
+    pub fn crf_value(self, compression: ExportCompression) -> u8 {
+        match (self, compression) {
+            (Self::Hevc, ExportCompression::Maximum) => 26,
+            (Self::Hevc, ExportCompression::Social) => 30,
+            (Self::Hevc, ExportCompression::Web) => 33,
+            (Self::Hevc, ExportCompression::Potato) => 37,
+            (Self::Av1, ExportCompression::Maximum) => 30,
+            (Self::Av1, ExportCompression::Social) => 36,
+            (Self::Av1, ExportCompression::Web) => 42,
+            (Self::Av1, ExportCompression::Potato) => 50,
+        }
+    }
+}
+
+/// HEVC or AV1 in an MP4 container, using the same rate control as
+/// [`crate::mp4::Mp4ExportSettings`] with the bits-per-pixel budget scaled for the codec. The codec
+/// comes from the [`ExportSettings`](crate::settings::ExportSettings) variant, so exports go
+/// through [`ExportSettings::export`](crate::settings::ExportSettings::export).
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug)]
+pub struct Mp4CodecExportSettings {
+    pub fps: u32,
+    pub resolution_base: XY<u32>,
+    pub compression: ExportCompression,
+    pub custom_bpp: Option<f32>,
+    #[serde(default)]
+    pub force_ffmpeg_decoder: bool,
+    #[serde(default)]
+    pub optimize_filesize: bool,
+}
+
+impl Mp4CodecExportSettings {
+    pub(crate) fn effective_bpp(&self, codec: Mp4VideoCodec) -> f32 {
+        self.custom_bpp
+            .unwrap_or_else(|| self.compression.bits_per_pixel() * codec.bpp_scale())
+    }
+
+    fn crf(&self, codec: Mp4VideoCodec) -> Option<u8> {
+        self.optimize_filesize
+            .then(|| codec.crf_value(self.compression))
+    }
+
+    pub(crate) async fn export(
+        self,
+        codec: Mp4VideoCodec,
+        base: ExporterBase,
+        on_progress: impl FnMut(u32) -> bool + Send + 'static,
+    ) -> Result<PathBuf, String> {
+        info!("Exporting {codec:?} mp4 with settings: {:?}", &self);
+
+        let bpp = self.effective_bpp(codec);
+        let crf = self.crf(codec);
+
+        match codec {
+            Mp4VideoCodec::Hevc => {
+                self.export_with(base, on_progress, move |video_info, o| {
+                    let builder = HevcEncoder::builder(video_info)
+                        .with_preset(HevcPreset::Medium)
+                        .with_bpp(bpp)
+                        .with_export_settings()
+                        .with_hvc1_tag();
+                    let builder = match crf {
+                        Some(crf) => builder.with_crf(crf),
+                        None => builder,
+                    };
+                    builder.build(o)
+                })
+                .await
+            }
+            Mp4VideoCodec::Av1 => {
+                self.export_with(base, on_progress, move |video_info, o| {
+                    let builder = Av1Encoder::builder(video_info).with_bpp(bpp);
+                    let builder = match crf {
+                        Some(crf) => builder.with_crf(crf),
+                        None => builder,
+                    };
+                    builder.build(o)
+                })
+                .await
+            }
+        }
+    }
+
+    async fn export_with<V: VideoEncoder + 'static>(
+        self,
+        base: ExporterBase,
+        on_progress: impl FnMut(u32) -> bool + Send + 'static,
+        video: impl FnOnce(VideoInfo, &mut ffmpeg::format::context::Output) -> Result<V, V::BuildError>
+        + Send
+        + 'static,
+    ) -> Result<PathBuf, String> {
+        info!("Expected to render {} frames", base.total_frames(self.fps));
+
+        let output_size = ProjectUniforms::get_output_size(
+            base.render_backend.options(),
+            &base.project_config,
+            self.resolution_base,
+        );
+
+        export_nv12_video(
+            base,
+            output_size,
+            self.fps,
+            self.resolution_base,
+            Container::Mp4 {
+                faststart: self.optimize_filesize,
+            },
+            video,
+            on_progress,
+            ExportNv12Mode::default(),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec_scales_the_h264_budget_unless_bpp_is_custom() {
+        let mut settings = Mp4CodecExportSettings {
+            fps: 30,
+            resolution_base: XY::new(1920, 1080),
+            compression: ExportCompression::Social,
+            custom_bpp: None,
+            force_ffmpeg_decoder: false,
+            optimize_filesize: true,
+        };
+        let h264 = ExportCompression::Social.bits_per_pixel();
+
+        assert_eq!(settings.effective_bpp(Mp4VideoCodec::Hevc), h264 * 0.6);
+        assert_eq!(settings.effective_bpp(Mp4VideoCodec::Av1), h264 * 0.5);
+        assert_eq!(settings.crf(Mp4VideoCodec::Hevc), Some(30));
+
+        settings.custom_bpp = Some(0.1);
+        settings.optimize_filesize = false;
+        assert_eq!(settings.effective_bpp(Mp4VideoCodec::Av1), 0.1);
+        assert_eq!(settings.crf(Mp4VideoCodec::Av1), None);
+    }
+}
@@ -3,8 +3,8 @@ use std::path::PathBuf;
 use base64::{Engine, engine::general_purpose::STANDARD};
 use cap_project::{RecordingMeta, XY};
 use cap_rendering::{
-    FrameRenderer, ProjectUniforms, RendererLayers, ZoomFocusInterpolator,
-    spring_mass_damper::SpringMassDamperSimulationConfig,
+    CpuCompositor, FrameRenderer, ProjectUniforms, RenderBackend, RendererLayers,
+    ZoomFocusInterpolator, spring_mass_damper::SpringMassDamperSimulationConfig,
 };
 use image::codecs::jpeg::JpegEncoder;
 use serde::{Deserialize, Serialize};
@@ -118,8 +118,8 @@ async fn render_preview_with_base(
             .unwrap_or(&[]),
     );
 
-    let uniforms = ProjectUniforms::new(
-        &exporter_base.render_constants,
+    let uniforms = ProjectUniforms::with_options(
+        exporter_base.render_backend.options(),
         &exporter_base.project_config,
         frame_number,
         settings.fps,
@@ -130,22 +130,32 @@ async fn render_preview_with_base(
         &zoom_focus_interpolator,
     );
 
-    let mut frame_renderer = FrameRenderer::new(&exporter_base.render_constants);
-    let mut layers = RendererLayers::new_with_options(
-        &exporter_base.render_constants.device,
-        &exporter_base.render_constants.queue,
-        exporter_base.render_constants.is_software_adapter,
-    );
-
-    let frame = frame_renderer
-        .render_immediate(
+    let frame = match exporter_base.render_backend.as_ref() {
+        RenderBackend::Gpu(constants) => {
+            let mut frame_renderer = FrameRenderer::new(constants);
+            let mut layers = RendererLayers::new_with_options(
+                &constants.device,
+                &constants.queue,
+                constants.is_software_adapter,
+            );
+
+            frame_renderer
+                .render_immediate(
+                    segment_frames,
+                    uniforms,
+                    &segment_media.cursor,
+                    !settings.cursor_only,
+                    &mut layers,
+                )
+                .await?
+        }
+        RenderBackend::Cpu(constants) => CpuCompositor::new(constants).render(
             segment_frames,
             uniforms,
             &segment_media.cursor,
             !settings.cursor_only,
-            &mut layers,
-        )
-        .await?;
+        ),
+    };
 
     let frame_render_time_ms = render_start.elapsed().as_secs_f64() * 1000.0;
     let width = frame.width;
@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
 use specta::Type;
+use std::path::PathBuf;
 
+use crate::ExporterBase;
 use crate::gif::GifExportSettings;
 use crate::mov::MovExportSettings;
 use crate::mp4::Mp4ExportSettings;
+use crate::mp4_codec::{Mp4CodecExportSettings, Mp4VideoCodec};
+use crate::webm::WebmExportSettings;
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, Type)]
 #[serde(tag = "format")]
@@ -14,6 +18,12 @@ pub enum ExportSettings {
     Gif(GifExportSettings),
     #[serde(alias = "mov")]
     Mov(MovExportSettings),
+    #[serde(alias = "hevc")]
+    Hevc(Mp4CodecExportSettings),
+    #[serde(alias = "av1")]
+    Av1(Mp4CodecExportSettings),
+    #[serde(alias = "webm")]
+    Webm(WebmExportSettings),
 }
 
 impl ExportSettings {
@@ -22,13 +32,18 @@ impl ExportSettings {
             Self::Mp4(s) => s.fps,
             Self::Gif(s) => s.fps,
             Self::Mov(s) => s.fps,
+            Self::Hevc(s) => s.fps,
+            Self::Av1(s) => s.fps,
+            Self::Webm(s) => s.fps,
         }
     }
 
     pub fn force_ffmpeg_decoder(&self) -> bool {
         match self {
             Self::Mp4(s) => s.force_ffmpeg_decoder,
-            Self::Gif(_) | Self::Mov(_) => false,
+            Self::Hevc(s) => s.force_ffmpeg_decoder,
+            Self::Av1(s) => s.force_ffmpeg_decoder,
+            Self::Gif(_) | Self::Mov(_) | Self::Webm(_) => false,
         }
     }
 
@@ -38,4 +53,32 @@ impl ExportSettings {
             _ => false,
         }
     }
+
+    /// The bits per pixel the video encoder budgets for, for the formats that use one.
+    pub fn effective_bpp(&self) -> Option<f32> {
+        match self {
+            Self::Mp4(s) => Some(s.effective_bpp()),
+            Self::Hevc(s) => Some(s.effective_bpp(Mp4VideoCodec::Hevc)),
+            Self::Av1(s) => Some(s.effective_bpp(Mp4VideoCodec::Av1)),
+            Self::Webm(s) => Some(s.effective_bpp()),
+            Self::Gif(_) | Self::Mov(_) => None,
+        }
+    }
+
+    /// Runs the export the variant describes. HEVC and AV1 share [`Mp4CodecExportSettings`], so
+    /// this is where their codec is picked.
+    pub async fn export(
+        self,
+        base: ExporterBase,
+        on_progress: impl FnMut(u32) -> bool + Send + 'static,
+    ) -> Result<PathBuf, String> {
+        match self {
+            Self::Mp4(s) => s.export(base, on_progress).await,
+            Self::Gif(s) => s.export(base, on_progress).await,
+            Self::Mov(s) => s.export(base, on_progress).await,
+            Self::Hevc(s) => s.export(Mp4VideoCodec::Hevc, base, on_progress).await,
+            Self::Av1(s) => s.export(Mp4VideoCodec::Av1, base, on_progress).await,
+            Self::Webm(s) => s.export(base, on_progress).await,
+        }
+    }
 }
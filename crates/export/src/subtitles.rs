@@ -0,0 +1,151 @@
+use cap_enc_ffmpeg::remux::{SubtitleCodec, SubtitleCue, mux_subtitle_track};
+use cap_project::{
+    CaptionTrackSegment, ProjectConfiguration, webvtt_cue_text, write_srt, write_webvtt,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::ExportError;
+
+/// Which caption outputs to produce alongside an export.
+///
+/// Applied to the MP4 (H264/HEVC/AV1) and WebM exports. Embedding is also enabled whenever the project's
+/// [`CaptionSettings::export_with_subtitles`](cap_project::CaptionSettings) is set, so
+/// `embed` only needs to be passed to force a soft track on.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SubtitleExportOptions {
+    pub embed: bool,
+    pub srt_sidecar: bool,
+    pub vtt_sidecar: bool,
+}
+
+/// Captions to attach to an export, snapshotted before the render consumes the
+/// [`crate::ExporterBase`].
+pub(crate) struct PendingSubtitles {
+    segments: Vec<CaptionTrackSegment>,
+    embed: bool,
+    srt_sidecar: bool,
+    vtt_sidecar: bool,
+}
+
+impl PendingSubtitles {
+    pub(crate) fn new(config: &ProjectConfiguration, options: SubtitleExportOptions) -> Self {
+        let embed = options.embed
+            || config
+                .captions
+                .as_ref()
+                .is_some_and(|c| c.settings.export_with_subtitles);
+
+        Self {
+            segments: caption_segments(config),
+            embed,
+            srt_sidecar: options.srt_sidecar,
+            vtt_sidecar: options.vtt_sidecar,
+        }
+    }
+
+    /// Writes sidecars next to the finished export and, if requested, rewrites it with a soft
+    /// subtitle track.
+    pub(crate) async fn apply(self, output_path: PathBuf) -> Result<PathBuf, String> {
+        if self.segments.is_empty() {
+            return Ok(output_path);
+        }
+
+        tokio::task::spawn_blocking(move || {
+            self.apply_blocking(&output_path)?;
+            Ok::<_, ExportError>(output_path)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+    }
+
+    fn apply_blocking(&self, output_path: &Path) -> Result<(), ExportError> {
+        if self.srt_sidecar {
+            std::fs::write(output_path.with_extension("srt"), write_srt(&self.segments))?;
+        }
+
+        if self.vtt_sidecar {
+            std::fs::write(
+                output_path.with_extension("vtt"),
+                write_webvtt(&self.segments),
+            )?;
+        }
+
+        if self.embed {
+            match SubtitleCodec::for_path(output_path) {
+                Some(codec) => embed_subtitles(output_path, &self.segments, codec)?,
+                None => warn!(
+                    path = %output_path.display(),
+                    "Container can't carry a subtitle track, skipping embedded captions"
+                ),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn embed_subtitles(
+    output_path: &Path,
+    segments: &[CaptionTrackSegment],
+    codec: SubtitleCodec,
+) -> Result<(), ExportError> {
+    let cues = segments
+        .iter()
+        .map(|segment| SubtitleCue {
+            start: Duration::from_secs_f64(segment.start),
+            end: Duration::from_secs_f64(segment.end),
+            text: match codec {
+                SubtitleCodec::WebVtt => webvtt_cue_text(segment),
+                SubtitleCodec::MovText | SubtitleCodec::SubRip => segment.text.trim().to_string(),
+            },
+        })
+        .collect::<Vec<_>>();
+
+    let ext = output_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let temp_path = output_path.with_extension(format!("subtitles.{ext}"));
+
+    if let Err(e) = mux_subtitle_track(output_path, &temp_path, &cues, codec, None) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(ExportError::FFmpeg(format!(
+            "Failed to embed subtitle track: {e}"
+        )));
+    }
+
+    std::fs::rename(&temp_path, output_path)?;
+    info!(cues = cues.len(), ?codec, "Embedded subtitle track");
+
+    Ok(())
+}
+
+/// The timeline's caption segments in playback order, without empty or zero-length cues.
+/// They're already in output time, so no edit-list projection is needed.
+pub fn caption_segments(config: &ProjectConfiguration) -> Vec<CaptionTrackSegment> {
+    let Some(timeline) = &config.timeline else {
+        return vec![];
+    };
+    let duration = timeline.duration();
+
+    let mut segments = timeline
+        .caption_segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty() && s.end > s.start && s.start < duration)
+        .cloned()
+        .map(|mut s| {
+            s.end = s.end.min(duration);
+            s
+        })
+        .collect::<Vec<_>>();
+    segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+    segments
+}
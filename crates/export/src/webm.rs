@@ -0,0 +1,149 @@
+use cap_enc_ffmpeg::{av1::Av1Encoder, mp4::Container, vp9::Vp9Encoder};
+use cap_project::XY;
+use cap_rendering::ProjectUniforms;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::{
+    ExporterBase,
+    mp4::{ExportCompression, ExportNv12Mode, export_nv12_video},
+    mp4_codec::Mp4VideoCodec,
+};
+
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WebmVideoCodec {
+    #[default]
+    Vp9,
+    Av1,
+}
+
+/// Royalty-free WebM: VP9 or AV1 video with Opus audio, using the same rate control as
+/// [`crate::mp4::Mp4ExportSettings`].
+#[derive(Serialize, Deserialize, Type, Clone, Copy, Debug)]
+pub struct WebmExportSettings {
+    pub fps: u32,
+    pub resolution_base: XY<u32>,
+    #[serde(default)]
+    pub codec: WebmVideoCodec,
+    pub compression: ExportCompression,
+    pub custom_bpp: Option<f32>,
+    #[serde(default)]
+    pub optimize_filesize: bool,
+}
+
+impl WebmExportSettings {
+    pub fn effective_bpp(&self) -> f32 {
+        let scale = match self.codec {
+            WebmVideoCodec::Vp9 => 0.7,
+            WebmVideoCodec::Av1 => 0.5,
+        };
+        self.custom_bpp
+            .unwrap_or_else(|| self.compression.bits_per_pixel() * scale)
+    }
+
+    pub fn crf_value(&self) -> u8 {
+        match self.codec {
+            WebmVideoCodec::Vp9 => match self.compression {
+                ExportCompression::Maximum => 28,
+                ExportCompression::Social => 33,
+                ExportCompression::Web => 38,
+                ExportCompression::Potato => 45,
+            },
+            WebmVideoCodec::Av1 => Mp4VideoCodec::Av1.crf_value(self.compression),
+        }
+    }
+
+    pub async fn export(
+        self,
+        base: ExporterBase,
+        on_progress: impl FnMut(u32) -> bool + Send + 'static,
+    ) -> Result<PathBuf, String> {
+        info!("Exporting webm with settings: {:?}", &self);
+        info!("Expected to render {} frames", base.total_frames(self.fps));
+
+        let bpp = self.effective_bpp();
+        let crf = self.optimize_filesize.then(|| self.crf_value());
+        let output_size = ProjectUniforms::get_output_size(
+            base.render_backend.options(),
+            &base.project_config,
+            self.resolution_base,
+        );
+
+        match self.codec {
+            WebmVideoCodec::Vp9 => {
+                export_nv12_video(
+                    base,
+                    output_size,
+                    self.fps,
+                    self.resolution_base,
+                    Container::WebM,
+                    move |video_info, o| {
+                        let builder = Vp9Encoder::builder(video_info).with_bpp(bpp);
+                        match crf {
+                            Some(crf) => builder.with_crf(crf),
+                            None => builder,
+                        }
+                        .build(o)
+                    },
+                    on_progress,
+                    ExportNv12Mode::default(),
+                )
+                .await
+            }
+            WebmVideoCodec::Av1 => {
+                export_nv12_video(
+                    base,
+                    output_size,
+                    self.fps,
+                    self.resolution_base,
+                    Container::WebM,
+                    move |video_info, o| {
+                        let builder = Av1Encoder::builder(video_info).with_bpp(bpp);
+                        match crf {
+                            Some(crf) => builder.with_crf(crf),
+                            None => builder,
+                        }
+                        .build(o)
+                    },
+                    on_progress,
+                    ExportNv12Mode::default(),
+                )
+                .await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec_defaults_to_vp9_when_omitted() {
+        let settings: WebmExportSettings = serde_json::from_str(
+            r#"{"fps":30,"resolution_base":{"x":1280,"y":720},"compression":"Web","custom_bpp":null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.codec, WebmVideoCodec::Vp9);
+        assert!(!settings.optimize_filesize);
+    }
+
+    #[test]
+    fn custom_bpp_overrides_codec_scaling() {
+        let mut settings = WebmExportSettings {
+            fps: 30,
+            resolution_base: XY::new(1280, 720),
+            codec: WebmVideoCodec::Av1,
+            compression: ExportCompression::Maximum,
+            custom_bpp: None,
+            optimize_filesize: false,
+        };
+        assert!(settings.effective_bpp() < ExportCompression::Maximum.bits_per_pixel());
+
+        settings.custom_bpp = Some(0.25);
+        assert_eq!(settings.effective_bpp(), 0.25);
+    }
+}
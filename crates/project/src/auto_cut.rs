@@ -0,0 +1,480 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{CaptionWord, TimelineConfiguration, TimelineSegment, caption_text_from_words};
+
+const DEFAULT_FILLER_WORDS: &[&str] = &[
+    "um", "umm", "uh", "uhh", "uhm", "erm", "er", "ah", "hmm", "mm",
+];
+
+/// Pieces of a split timeline segment shorter than this are dropped rather than kept as slivers.
+const MIN_PIECE_DURATION: f64 = 0.05;
+/// Overlays squeezed shorter than this by a cut are removed.
+const MIN_ITEM_DURATION: f64 = 0.05;
+
+/// Tunes [`silence_cuts`] and [`filler_cuts`].
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoCutSettings {
+    /// Seconds of each silence left in place on both sides, so speech isn't clipped.
+    pub padding: f64,
+    /// Silences with less than this left to remove after padding are kept.
+    pub min_cut: f64,
+    pub cut_silences: bool,
+    pub cut_fillers: bool,
+    /// Compared case-insensitively, ignoring punctuation.
+    pub filler_words: Vec<String>,
+}
+
+impl Default for AutoCutSettings {
+    fn default() -> Self {
+        Self {
+            padding: 0.15,
+            min_cut: 0.3,
+            cut_silences: true,
+            cut_fillers: true,
+            filler_words: DEFAULT_FILLER_WORDS
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CutReason {
+    Silence,
+    Filler,
+}
+
+/// A stretch of one recording segment to remove, in seconds from that segment's start.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceCut {
+    pub recording_segment: u32,
+    pub start: f64,
+    pub end: f64,
+    pub reason: CutReason,
+    /// The word that was cut, for filler cuts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word: Option<String>,
+}
+
+/// A stretch of the current output timeline that an [`AutoCutPlan`] removes.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputCut {
+    pub start: f64,
+    pub end: f64,
+    pub reasons: Vec<CutReason>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<String>,
+}
+
+/// The edit list an auto-cut would make: the timeline segments that replace the current ones,
+/// and the output-time ranges that disappear.
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoCutPlan {
+    pub cuts: Vec<OutputCut>,
+    pub segments: Vec<TimelineSegment>,
+    pub duration_before: f64,
+    pub duration_after: f64,
+}
+
+/// Turns silences detected on each recording segment's mic track, as `(start, end)` seconds from
+/// that segment's start, into cuts that leave `padding` of quiet around the speech.
+pub fn silence_cuts(silences: &[Vec<(f64, f64)>], settings: &AutoCutSettings) -> Vec<SourceCut> {
+    if !settings.cut_silences {
+        return Vec::new();
+    }
+
+    let padding = settings.padding.max(0.0);
+    silences
+        .iter()
+        .enumerate()
+        .flat_map(|(index, silences)| {
+            silences.iter().filter_map(move |&(start, end)| {
+                // Silence at the very start of a recording has no speech before it to protect.
+                let start = if start <= 0.0 { 0.0 } else { start + padding };
+                let end = end - padding;
+                (end - start >= settings.min_cut).then_some(SourceCut {
+                    recording_segment: index as u32,
+                    start,
+                    end,
+                    reason: CutReason::Silence,
+                    word: None,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Finds filler words in the timeline's caption track and maps them back to the recording.
+pub fn filler_cuts(timeline: &TimelineConfiguration, settings: &AutoCutSettings) -> Vec<SourceCut> {
+    if !settings.cut_fillers {
+        return Vec::new();
+    }
+
+    let fillers = settings
+        .filler_words
+        .iter()
+        .map(|word| normalize_word(word))
+        .collect::<Vec<_>>();
+
+    timeline
+        .caption_segments
+        .iter()
+        .flat_map(|caption| &caption.words)
+        .filter(|word| fillers.contains(&normalize_word(&word.text)))
+        .filter_map(|word| {
+            let (start, segment) = timeline.get_segment_time(word.start as f64)?;
+            let length = (word.end - word.start).max(0.0) as f64 * segment.timescale;
+            Some(SourceCut {
+                recording_segment: segment.recording_clip,
+                start,
+                end: (start + length).min(segment.end),
+                reason: CutReason::Filler,
+                word: Some(word.text.trim().to_string()),
+            })
+        })
+        .collect()
+}
+
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Works out how `timeline`'s segments change when `cuts` are removed from the recording. A cut
+/// applies everywhere its recording range is used, including clips that repeat it.
+pub fn plan_auto_cut(timeline: &TimelineConfiguration, cuts: &[SourceCut]) -> AutoCutPlan {
+    let mut output_cuts: Vec<OutputCut> = Vec::new();
+    let mut segments = Vec::new();
+    let mut offset = 0.0;
+
+    for segment in &timeline.segments {
+        let mut ranges = cuts
+            .iter()
+            .filter(|cut| {
+                cut.recording_segment == segment.recording_clip
+                    && cut.start < segment.end
+                    && cut.end > segment.start
+            })
+            .collect::<Vec<_>>();
+        ranges.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        let to_output = |time: f64| offset + (time - segment.start) / segment.timescale;
+        let min_piece = MIN_PIECE_DURATION * segment.timescale;
+        let mut pieces = Vec::new();
+        let mut kept_from = segment.start;
+
+        for cut in ranges {
+            let start = cut.start.max(segment.start);
+            let end = cut.end.min(segment.end);
+
+            // Overlapping cuts, and cuts separated by a sliver too short to keep, are merged.
+            if let Some(last) = output_cuts.last_mut()
+                && start - kept_from < min_piece
+                && last.end >= to_output(kept_from)
+            {
+                last.end = last.end.max(to_output(end));
+                add_reason(last, cut);
+                kept_from = kept_from.max(end);
+                continue;
+            }
+
+            let start = if start - kept_from < min_piece {
+                kept_from
+            } else {
+                pieces.push((kept_from, start));
+                start
+            };
+            let mut output_cut = OutputCut {
+                start: to_output(start),
+                end: to_output(end),
+                reasons: Vec::new(),
+                words: Vec::new(),
+            };
+            add_reason(&mut output_cut, cut);
+            output_cuts.push(output_cut);
+            kept_from = end;
+        }
+
+        if segment.end - kept_from >= min_piece {
+            pieces.push((kept_from, segment.end));
+        } else if let Some(last) = output_cuts.last_mut()
+            && last.end >= to_output(kept_from)
+        {
+            last.end = to_output(segment.end);
+        }
+
+        let last_piece = pieces.len().saturating_sub(1);
+        segments.extend(pieces.into_iter().enumerate().map(|(i, (start, end))| {
+            TimelineSegment {
+                start,
+                end,
+                // The clip's hand-over to the next clip stays on its last piece; the new cuts
+                // inside it are hard cuts.
+                transition: if i == last_piece {
+                    segment.transition.clone()
+                } else {
+                    None
+                },
+                ..segment.clone()
+            }
+        }));
+
+        offset += segment.duration();
+    }
+
+    let duration_before = timeline.duration();
+    let removed = output_cuts
+        .iter()
+        .map(|cut| cut.end - cut.start)
+        .sum::<f64>();
+
+    AutoCutPlan {
+        cuts: output_cuts,
+        segments,
+        duration_before,
+        duration_after: (duration_before - removed).max(0.0),
+    }
+}
+
+fn add_reason(output_cut: &mut OutputCut, cut: &SourceCut) {
+    if !output_cut.reasons.contains(&cut.reason) {
+        output_cut.reasons.push(cut.reason);
+    }
+    if let Some(word) = &cut.word {
+        output_cut.words.push(word.clone());
+    }
+}
+
+impl AutoCutPlan {
+    /// Where a point on the current output timeline lands once the cuts are made. Points inside
+    /// a cut move to where it was.
+    pub fn remap_time(&self, time: f64) -> f64 {
+        let removed = self
+            .cuts
+            .iter()
+            .take_while(|cut| cut.start < time)
+            .map(|cut| time.min(cut.end) - cut.start)
+            .sum::<f64>();
+
+        time - removed
+    }
+
+    /// Replaces `timeline`'s segments and moves every overlay with the footage it sits on. Zoom,
+    /// scene, mask, text, caption, keyboard and annotation segments shrink by the cuts they
+    /// span and are dropped if nothing is left; keyframes and caption words move with them.
+    /// Music keeps its length and moves to where its start now plays.
+    pub fn apply(&self, timeline: &mut TimelineConfiguration) {
+        timeline.segments = self.segments.clone();
+
+        let remap = |start: &mut f64, end: &mut f64| {
+            let (old_start, old_end) = (*start, *end);
+            *start = self.remap_time(old_start);
+            *end = self.remap_time(old_end);
+            *end - *start >= MIN_ITEM_DURATION.min(old_end - old_start)
+        };
+        // Keyframe times are relative to their segment's start.
+        macro_rules! remap_keyframes {
+            ($old_start:expr, $new_start:expr, $($keys:expr),+) => {
+                $(for key in $keys.iter_mut() {
+                    key.time = self.remap_time($old_start + key.time) - $new_start;
+                })+
+            };
+        }
+
+        timeline
+            .zoom_segments
+            .retain_mut(|s| remap(&mut s.start, &mut s.end));
+        timeline
+            .scene_segments
+            .retain_mut(|s| remap(&mut s.start, &mut s.end));
+        timeline.mask_segments.retain_mut(|s| {
+            let old_start = s.start;
+            let keep = remap(&mut s.start, &mut s.end);
+            let k = &mut s.keyframes;
+            remap_keyframes!(old_start, s.start, k.position, k.size, k.intensity);
+            keep
+        });
+        timeline.text_segments.retain_mut(|s| {
+            let old_start = s.start;
+            let keep = remap(&mut s.start, &mut s.end);
+            let k = &mut s.keyframes;
+            remap_keyframes!(
+                old_start, s.start, k.position, k.size, k.rotation, k.scale, k.opacity
+            );
+            keep
+        });
+        timeline.annotation_segments.retain_mut(|s| {
+            let old_start = s.start;
+            let keep = remap(&mut s.start, &mut s.end);
+            let k = &mut s.keyframes;
+            remap_keyframes!(old_start, s.start, k.position, k.size, k.opacity);
+            keep
+        });
+        timeline.keyboard_segments.retain_mut(|s| {
+            let old_start = s.start;
+            let keep = remap(&mut s.start, &mut s.end);
+            for key in &mut s.keys {
+                key.time_offset = self.remap_time(old_start + key.time_offset) - s.start;
+            }
+            keep
+        });
+        timeline.caption_segments.retain_mut(|s| {
+            let word_count = s.words.len();
+            s.words.retain_mut(|word| self.remap_word(word));
+            if s.words.len() != word_count {
+                s.text = caption_text_from_words(&s.words);
+            }
+            remap(&mut s.start, &mut s.end) && (word_count == 0 || !s.words.is_empty())
+        });
+        for segment in &mut timeline.audio_segments {
+            let length = segment.end - segment.start;
+            segment.start = self.remap_time(segment.start);
+            segment.end = segment.start + length;
+        }
+    }
+
+    fn remap_word(&self, word: &mut CaptionWord) -> bool {
+        let start = self.remap_time(word.start as f64);
+        let end = self.remap_time(word.end as f64);
+        word.start = start as f32;
+        word.end = end as f32;
+        end - start > 1e-3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CaptionTrackSegment, ZoomMode, ZoomSegment};
+
+    fn timeline(segments: Vec<TimelineSegment>) -> TimelineConfiguration {
+        serde_json::from_value(serde_json::json!({
+            "segments": segments,
+            "zoomSegments": [],
+        }))
+        .unwrap()
+    }
+
+    fn clip(recording_clip: u32, start: f64, end: f64, timescale: f64) -> TimelineSegment {
+        TimelineSegment {
+            recording_clip,
+            timescale,
+            start,
+            end,
+            ..Default::default()
+        }
+    }
+
+    fn word(text: &str, start: f32, end: f32) -> CaptionWord {
+        CaptionWord {
+            text: text.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn silences_are_padded_and_short_ones_kept() {
+        let settings = AutoCutSettings::default();
+        let cuts = silence_cuts(&[vec![(0.0, 1.0), (3.0, 3.5), (5.0, 7.0)]], &settings);
+
+        assert_eq!(
+            cuts.iter().map(|c| (c.start, c.end)).collect::<Vec<_>>(),
+            vec![(0.0, 0.85), (5.15, 6.85)]
+        );
+    }
+
+    #[test]
+    fn cuts_split_segments_and_respect_timescale() {
+        let timeline = timeline(vec![clip(0, 0.0, 10.0, 1.0), clip(1, 0.0, 8.0, 2.0)]);
+        let cut = |recording_segment, start, end| SourceCut {
+            recording_segment,
+            start,
+            end,
+            reason: CutReason::Silence,
+            word: None,
+        };
+
+        let plan = plan_auto_cut(&timeline, &[cut(0, 2.0, 4.0), cut(1, 2.0, 6.0)]);
+
+        assert_eq!(
+            plan.segments
+                .iter()
+                .map(|s| (s.recording_clip, s.start, s.end))
+                .collect::<Vec<_>>(),
+            vec![(0, 0.0, 2.0), (0, 4.0, 10.0), (1, 0.0, 2.0), (1, 6.0, 8.0)]
+        );
+        // The second clip plays at double speed, so 4s of recording is 2s of output.
+        assert_eq!(
+            plan.cuts
+                .iter()
+                .map(|c| (c.start, c.end))
+                .collect::<Vec<_>>(),
+            vec![(2.0, 4.0), (11.0, 13.0)]
+        );
+        assert_eq!(plan.duration_before, 14.0);
+        assert_eq!(plan.duration_after, 10.0);
+        assert_eq!(plan.remap_time(12.0), 9.0);
+        assert_eq!(plan.remap_time(13.5), 9.5);
+    }
+
+    #[test]
+    fn fillers_are_cut_and_overlays_follow_the_footage() {
+        let mut timeline = timeline(vec![clip(0, 0.0, 10.0, 1.0)]);
+        timeline.caption_segments.push(CaptionTrackSegment {
+            id: "c1".to_string(),
+            start: 1.0,
+            end: 3.0,
+            text: "so um this".to_string(),
+            words: vec![
+                word("so", 1.0, 1.5),
+                word("um,", 1.5, 2.0),
+                word("this", 2.0, 3.0),
+            ],
+            fade_duration_override: None,
+            linger_duration_override: None,
+            position_override: None,
+            color_override: None,
+            background_color_override: None,
+            font_size_override: None,
+        });
+        timeline.zoom_segments.push(ZoomSegment {
+            start: 4.0,
+            end: 6.0,
+            amount: 2.0,
+            mode: ZoomMode::Auto,
+            glide_direction: Default::default(),
+            glide_speed: 0.5,
+            instant_animation: false,
+            edge_snap_ratio: 0.25,
+        });
+
+        let cuts = filler_cuts(&timeline, &AutoCutSettings::default());
+        assert_eq!(cuts.len(), 1);
+        assert_eq!(cuts[0].word.as_deref(), Some("um,"));
+
+        let plan = plan_auto_cut(&timeline, &cuts);
+        plan.apply(&mut timeline);
+
+        assert_eq!(timeline.segments.len(), 2);
+        let caption = &timeline.caption_segments[0];
+        assert_eq!(caption.text, "so this");
+        assert_eq!((caption.start, caption.end), (1.0, 2.5));
+        assert_eq!(
+            (
+                timeline.zoom_segments[0].start,
+                timeline.zoom_segments[0].end
+            ),
+            (3.5, 5.5)
+        );
+    }
+}
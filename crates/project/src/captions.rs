@@ -0,0 +1,380 @@
+use crate::{
+    CaptionSegment, CaptionTrackSegment, CaptionWord, CaptionsData, TimelineConfiguration,
+};
+
+/// Transcription can stretch a trailing word across a following silence, which keeps its caption
+/// on screen and duplicates it across timeline cuts once projected. Kept in sync with
+/// `MAX_CAPTION_WORD_DURATION` in the editor's `captions.ts` and the caption render layer.
+pub const MAX_CAPTION_WORD_DURATION: f32 = 2.5;
+
+/// Joins a track segment's source caption id and the index of the piece it became when a cut
+/// split the caption. Matches `CAPTION_EDL_SEPARATOR` in the editor.
+const CAPTION_EDL_SEPARATOR: &str = "::edl";
+
+/// The source caption a derived track segment came from. A caption that spans timeline cuts
+/// becomes several track segments that share its id.
+pub fn source_caption_id(track_id: &str) -> &str {
+    track_id
+        .split_once(CAPTION_EDL_SEPARATOR)
+        .map_or(track_id, |(id, _)| id)
+}
+
+/// Whether a token such as `,` or `'s` joins the word before it rather than standing alone.
+pub fn caption_token_attaches_to_previous(text: &str) -> bool {
+    text.trim().chars().next().is_some_and(|first| {
+        matches!(
+            first,
+            ',' | '.'
+                | '!'
+                | '?'
+                | ';'
+                | ':'
+                | '%'
+                | ')'
+                | ']'
+                | '}'
+                | '\''
+                | '’'
+                | '、'
+                | '。'
+                | '！'
+                | '？'
+                | '；'
+                | '：'
+                | '，'
+        )
+    })
+}
+
+/// Caption text for `words`, with punctuation attached to the word before it.
+pub fn caption_text_from_words<'a>(words: impl IntoIterator<Item = &'a CaptionWord>) -> String {
+    let mut text = String::new();
+
+    for word in words {
+        let word_text = word.text.trim();
+        if word_text.is_empty() {
+            continue;
+        }
+
+        if !text.is_empty() && !caption_token_attaches_to_previous(word_text) {
+            text.push(' ');
+        }
+        text.push_str(word_text);
+    }
+
+    text
+}
+
+fn clamp_caption_words(segment: &CaptionSegment) -> CaptionSegment {
+    let mut segment = segment.clone();
+    for word in &mut segment.words {
+        word.end = word.end.min(word.start + MAX_CAPTION_WORD_DURATION);
+    }
+    if let Some(last) = segment.words.last() {
+        segment.end = segment.end.min(last.end);
+    }
+    segment
+}
+
+pub(crate) struct SourceMapping {
+    pub(crate) source_start: f64,
+    pub(crate) source_end: f64,
+    pub(crate) output_start: f64,
+    pub(crate) timescale: f64,
+}
+
+impl SourceMapping {
+    fn output_end(&self) -> f64 {
+        self.output_start + (self.source_end - self.source_start) / self.timescale
+    }
+
+    fn map_range(&self, start: f64, end: f64) -> Option<(f64, f64)> {
+        let start = start.max(self.source_start);
+        let end = end.min(self.source_end);
+        (start < end).then(|| {
+            (
+                self.output_start + (start - self.source_start) / self.timescale,
+                self.output_start + (end - self.source_start) / self.timescale,
+            )
+        })
+    }
+}
+
+/// Where each timeline segment's clip sits in source time and in output time.
+pub(crate) fn source_mappings(
+    timeline: &TimelineConfiguration,
+    recording_durations: &[f64],
+) -> Vec<SourceMapping> {
+    let mut recording_offsets = Vec::with_capacity(recording_durations.len());
+    let mut offset = 0.0;
+    for duration in recording_durations {
+        recording_offsets.push(offset);
+        offset += duration;
+    }
+
+    let mut output_start = 0.0;
+    timeline
+        .segments
+        .iter()
+        .map(|segment| {
+            let offset = recording_offsets
+                .get(segment.recording_clip as usize)
+                .copied()
+                .unwrap_or(0.0);
+            let mapping = SourceMapping {
+                source_start: offset + segment.start,
+                source_end: offset + segment.end,
+                output_start,
+                timescale: segment.timescale,
+            };
+            output_start += segment.duration();
+            mapping
+        })
+        .collect()
+}
+
+/// The source time shown at output time `time`. Where one clip ends as the next begins, a start
+/// belongs to the later clip and an end to the earlier one.
+fn source_time(mappings: &[SourceMapping], time: f64, is_end: bool) -> f64 {
+    let mapping = mappings
+        .iter()
+        .find(|m| {
+            if is_end {
+                time > m.output_start && time <= m.output_end()
+            } else {
+                time >= m.output_start && time < m.output_end()
+            }
+        })
+        .or_else(|| {
+            if time <= 0.0 {
+                mappings.first()
+            } else {
+                mappings.last()
+            }
+        });
+
+    match mapping {
+        Some(m) => (m.source_start + (time - m.output_start) * m.timescale)
+            .clamp(m.source_start, m.source_end),
+        None => time,
+    }
+}
+
+impl CaptionsData {
+    /// Projects the source-time captions through `timeline`'s edit list, producing the output-time
+    /// track the renderer and exporter read. `recording_durations` are each recording segment's
+    /// display duration, which place segment-local clip ranges in source time. Style overrides on
+    /// the timeline's current track carry over by source caption id.
+    ///
+    /// This mirrors `deriveCaptionTrackSegments` in the editor, so captions written outside the
+    /// editor line up with what it would derive on load.
+    pub fn derive_track(
+        &self,
+        timeline: &TimelineConfiguration,
+        recording_durations: &[f64],
+    ) -> Vec<CaptionTrackSegment> {
+        let mappings = source_mappings(timeline, recording_durations);
+
+        let mut track = Vec::new();
+        for caption in self.segments.iter().map(clamp_caption_words) {
+            let pieces = if mappings.is_empty() || recording_durations.is_empty() {
+                vec![(
+                    caption.start as f64,
+                    caption.end as f64,
+                    caption.words.clone(),
+                )]
+            } else {
+                mappings
+                    .iter()
+                    .filter_map(|mapping| map_caption(&caption, mapping))
+                    .collect()
+            };
+
+            let count = pieces.len();
+            for (index, (start, end, words)) in pieces.into_iter().enumerate() {
+                let id = if count == 1 {
+                    caption.id.clone()
+                } else {
+                    format!("{}{CAPTION_EDL_SEPARATOR}{index}", caption.id)
+                };
+                let text = if words.is_empty() {
+                    caption.text.clone()
+                } else {
+                    caption_text_from_words(&words)
+                };
+                let previous = timeline
+                    .caption_segments
+                    .iter()
+                    .find(|segment| source_caption_id(&segment.id) == caption.id);
+
+                track.push(CaptionTrackSegment {
+                    id,
+                    start,
+                    end,
+                    text,
+                    words,
+                    fade_duration_override: previous.and_then(|p| p.fade_duration_override),
+                    linger_duration_override: previous.and_then(|p| p.linger_duration_override),
+                    position_override: previous.and_then(|p| p.position_override.clone()),
+                    color_override: previous.and_then(|p| p.color_override.clone()),
+                    background_color_override: previous
+                        .and_then(|p| p.background_color_override.clone()),
+                    font_size_override: previous.and_then(|p| p.font_size_override),
+                });
+            }
+        }
+
+        track.sort_by(|a, b| a.start.total_cmp(&b.start));
+        track
+    }
+}
+
+impl CaptionsData {
+    /// Moves captions timed against the edited output, such as a transcript of an exported video,
+    /// into source time: the inverse of [`Self::derive_track`]. A caption that spans a cut keeps
+    /// the removed source between its ends.
+    pub fn retime_from_output(
+        &mut self,
+        timeline: &TimelineConfiguration,
+        recording_durations: &[f64],
+    ) {
+        let mappings = source_mappings(timeline, recording_durations);
+        if mappings.is_empty() || recording_durations.is_empty() {
+            return;
+        }
+
+        let to_source =
+            |time: f32, is_end: bool| source_time(&mappings, time as f64, is_end) as f32;
+        for segment in &mut self.segments {
+            segment.start = to_source(segment.start, false);
+            segment.end = to_source(segment.end, true);
+            for word in &mut segment.words {
+                word.start = to_source(word.start, false);
+                word.end = to_source(word.end, true);
+            }
+        }
+        self.source_timed = true;
+    }
+}
+
+fn map_caption(
+    caption: &CaptionSegment,
+    mapping: &SourceMapping,
+) -> Option<(f64, f64, Vec<CaptionWord>)> {
+    if caption.words.is_empty() {
+        let (start, end) = mapping.map_range(caption.start as f64, caption.end as f64)?;
+        return Some((start, end, Vec::new()));
+    }
+
+    let words = caption
+        .words
+        .iter()
+        .filter_map(|word| {
+            let (start, end) = mapping.map_range(word.start as f64, word.end as f64)?;
+            Some(CaptionWord {
+                text: word.text.clone(),
+                start: start as f32,
+                end: end as f32,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let start = words.first()?.start as f64;
+    let end = words.last()?.end as f64;
+    Some((start, end, words))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimelineSegment;
+
+    fn word(text: &str, start: f32, end: f32) -> CaptionWord {
+        CaptionWord {
+            text: text.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn captions_follow_cuts_and_split_across_them() {
+        let captions = CaptionsData {
+            segments: vec![CaptionSegment {
+                id: "a".to_string(),
+                start: 1.0,
+                end: 4.0,
+                text: "one two three".to_string(),
+                words: vec![
+                    word("one", 1.0, 1.5),
+                    word("two", 2.0, 2.5),
+                    word("three", 3.5, 4.0),
+                ],
+            }],
+            source_timed: true,
+            ..Default::default()
+        };
+        let timeline: TimelineConfiguration = serde_json::from_value(serde_json::json!({
+            "segments": [
+                TimelineSegment { timescale: 1.0, start: 0.0, end: 2.75, ..Default::default() },
+                TimelineSegment { timescale: 1.0, start: 3.25, end: 6.0, ..Default::default() },
+            ],
+            "zoomSegments": [],
+        }))
+        .unwrap();
+
+        let track = captions.derive_track(&timeline, &[6.0]);
+
+        assert_eq!(
+            track
+                .iter()
+                .map(|s| (s.id.as_str(), s.text.as_str(), s.start, s.end))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a::edl0", "one two", 1.0, 2.5),
+                ("a::edl1", "three", 3.0, 3.5)
+            ]
+        );
+        assert_eq!(source_caption_id(&track[1].id), "a");
+    }
+
+    #[test]
+    fn output_timed_captions_move_back_to_source_time() {
+        let timeline: TimelineConfiguration = serde_json::from_value(serde_json::json!({
+            "segments": [
+                TimelineSegment { timescale: 1.0, start: 0.0, end: 2.75, ..Default::default() },
+                TimelineSegment { timescale: 2.0, start: 3.25, end: 6.0, ..Default::default() },
+            ],
+            "zoomSegments": [],
+        }))
+        .unwrap();
+        let mut captions = CaptionsData {
+            segments: vec![CaptionSegment {
+                id: "a".to_string(),
+                start: 2.0,
+                end: 3.5,
+                text: "two three".to_string(),
+                words: vec![word("two", 2.0, 2.75), word("three", 2.75, 3.5)],
+            }],
+            ..Default::default()
+        };
+
+        captions.retime_from_output(&timeline, &[6.0]);
+
+        let segment = &captions.segments[0];
+        assert!(captions.source_timed);
+        assert_eq!((segment.start, segment.end), (2.0, 4.75));
+        assert_eq!((segment.words[0].start, segment.words[0].end), (2.0, 2.75));
+        assert_eq!((segment.words[1].start, segment.words[1].end), (3.25, 4.75));
+    }
+
+    #[test]
+    fn punctuation_attaches_to_the_previous_word() {
+        let words = [
+            word("test", 0.0, 0.5),
+            word(",", 0.5, 0.6),
+            word("again.", 1.0, 1.5),
+        ];
+        assert_eq!(caption_text_from_words(&words), "test, again.");
+    }
+}
@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    TimelineConfiguration,
+    captions::{SourceMapping, source_mappings},
+};
+
+/// Chapters shorter than this are merged into their neighbour, so a marker pressed twice or one
+/// that lands just before a cut doesn't leave a sliver of a chapter players can't seek into.
+pub const MIN_CHAPTER_DURATION: f64 = 1.0;
+
+/// A "the next section starts here" point marked while recording.
+///
+/// `time` is in source time, the recording segments' display durations laid end to end, which is
+/// how captions are stored too. Markers are projected through the timeline with
+/// [`project_chapters`] at export.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChapterMarker {
+    pub time: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// A chapter of the edited output, in output time.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+/// Projects source-time `markers` through `timeline`'s edit list into the output's chapters.
+///
+/// A marker inside a cut moves to the start of the next kept clip, and one after the last kept
+/// clip is dropped. Without a timeline the output is the recording as captured, so source time is
+/// output time. The first chapter always starts at zero, so a recording whose first marker comes
+/// later gets an untitled opening chapter. Untitled chapters are numbered in order.
+pub fn project_chapters(
+    markers: &[ChapterMarker],
+    timeline: Option<&TimelineConfiguration>,
+    recording_durations: &[f64],
+) -> Vec<Chapter> {
+    if markers.is_empty() {
+        return vec![];
+    }
+
+    let (starts, duration) = match timeline {
+        Some(timeline) if !timeline.segments.is_empty() && !recording_durations.is_empty() => {
+            let mappings = source_mappings(timeline, recording_durations);
+            let starts = markers
+                .iter()
+                .filter_map(|marker| {
+                    output_time(&mappings, marker.time).map(|time| (time, marker.title.clone()))
+                })
+                .collect::<Vec<_>>();
+            (starts, timeline.duration())
+        }
+        _ => {
+            let starts = markers
+                .iter()
+                .map(|marker| (marker.time, marker.title.clone()))
+                .collect::<Vec<_>>();
+            (starts, recording_durations.iter().sum())
+        }
+    };
+
+    let mut starts = starts
+        .into_iter()
+        .filter(|(time, _)| time.is_finite() && *time + MIN_CHAPTER_DURATION <= duration)
+        .map(|(time, title)| (time.max(0.0), title))
+        .collect::<Vec<_>>();
+    starts.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut merged: Vec<(f64, Option<String>)> = Vec::with_capacity(starts.len() + 1);
+    for (time, title) in starts {
+        if let Some(last) = merged.last_mut()
+            && time - last.0 < MIN_CHAPTER_DURATION
+        {
+            if last.1.is_none() {
+                last.1 = title;
+            }
+        } else if merged.is_empty() && time < MIN_CHAPTER_DURATION {
+            merged.push((0.0, title));
+        } else {
+            if merged.is_empty() {
+                merged.push((0.0, None));
+            }
+            merged.push((time, title));
+        }
+    }
+
+    let ends = merged
+        .iter()
+        .skip(1)
+        .map(|(start, _)| *start)
+        .chain(std::iter::once(duration))
+        .collect::<Vec<_>>();
+
+    merged
+        .into_iter()
+        .zip(ends)
+        .enumerate()
+        .map(|(index, ((start, title), end))| Chapter {
+            start,
+            end,
+            title: title
+                .map(|title| title.trim().to_string())
+                .filter(|title| !title.is_empty())
+                .unwrap_or_else(|| format!("Chapter {}", index + 1)),
+        })
+        .collect()
+}
+
+/// Where source time `time` plays in the output: inside the first clip that shows it, otherwise
+/// at the start of the clip that picks up after it.
+fn output_time(mappings: &[SourceMapping], time: f64) -> Option<f64> {
+    if let Some(mapping) = mappings
+        .iter()
+        .find(|m| time >= m.source_start && time < m.source_end)
+    {
+        return Some(mapping.output_start + (time - mapping.source_start) / mapping.timescale);
+    }
+
+    mappings
+        .iter()
+        .filter(|m| m.source_start > time)
+        .min_by(|a, b| a.source_start.total_cmp(&b.source_start))
+        .map(|m| m.output_start)
+}
+
+#[derive(Serialize)]
+struct WebChapter<'a> {
+    title: &'a str,
+    start: f64,
+}
+
+#[derive(Serialize)]
+struct WebChapters<'a> {
+    chapters: Vec<WebChapter<'a>>,
+}
+
+/// Chapters as the web player reads them from a video's metadata: `{ "chapters": [{ "title",
+/// "start" }] }` with `start` in seconds.
+pub fn write_chapters_json(chapters: &[Chapter]) -> String {
+    let chapters = chapters
+        .iter()
+        .map(|chapter| WebChapter {
+            title: &chapter.title,
+            start: (chapter.start * 1000.0).round() / 1000.0,
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&WebChapters { chapters }).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimelineSegment;
+
+    fn marker(time: f64, title: Option<&str>) -> ChapterMarker {
+        ChapterMarker {
+            time,
+            title: title.map(str::to_string),
+        }
+    }
+
+    fn summary(chapters: &[Chapter]) -> Vec<(f64, f64, &str)> {
+        chapters
+            .iter()
+            .map(|c| (c.start, c.end, c.title.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn markers_follow_cuts_and_speed_changes() {
+        let timeline: TimelineConfiguration = serde_json::from_value(serde_json::json!({
+            "segments": [
+                TimelineSegment { timescale: 1.0, start: 0.0, end: 4.0, ..Default::default() },
+                TimelineSegment { timescale: 2.0, start: 6.0, end: 10.0, ..Default::default() },
+                TimelineSegment { recording_clip: 1, timescale: 1.0, start: 0.0, end: 5.0, ..Default::default() },
+            ],
+            "zoomSegments": [],
+        }))
+        .unwrap();
+        let markers = [
+            marker(2.0, Some("Setup")),
+            marker(5.0, Some("Cut away")),
+            marker(8.0, None),
+            marker(12.0, Some("Second take")),
+        ];
+
+        let chapters = project_chapters(&markers, Some(&timeline), &[10.0, 5.0]);
+
+        assert_eq!(
+            summary(&chapters),
+            vec![
+                (0.0, 2.0, "Chapter 1"),
+                (2.0, 4.0, "Setup"),
+                (4.0, 5.0, "Cut away"),
+                (5.0, 8.0, "Chapter 4"),
+                (8.0, 11.0, "Second take"),
+            ]
+        );
+    }
+
+    #[test]
+    fn close_markers_merge_and_late_markers_drop() {
+        let markers = [
+            marker(0.4, None),
+            marker(3.0, Some("Demo")),
+            marker(3.5, Some("Duplicate")),
+            marker(9.5, Some("Too late")),
+        ];
+
+        let chapters = project_chapters(&markers, None, &[10.0]);
+
+        assert_eq!(
+            summary(&chapters),
+            vec![(0.0, 3.0, "Chapter 1"), (3.0, 10.0, "Demo")]
+        );
+    }
+
+    #[test]
+    fn no_markers_means_no_chapters() {
+        assert!(project_chapters(&[], None, &[10.0]).is_empty());
+    }
+
+    #[test]
+    fn web_player_json_has_title_and_start() {
+        let json = write_chapters_json(&[Chapter {
+            start: 1.23456,
+            end: 4.0,
+            title: "Intro".to_string(),
+        }]);
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::json!({ "chapters": [{ "title": "Intro", "start": 1.235 }] })
+        );
+    }
+}
@@ -1,13 +1,21 @@
 use std::{
     fmt,
     ops::{Add, Div, Mul, Sub, SubAssign},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use specta::Type;
 
+use crate::{
+    history::write_config_contents,
+    migration::{
+        PROJECT_CONFIG_SCHEMA_VERSION, ProjectFile, Versioned, back_up, migrate_value,
+        schema_version, write_atomic,
+    },
+};
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum AspectRatio {
@@ -414,6 +422,8 @@ pub enum StereoMode {
 #[serde(rename_all = "camelCase", default)]
 pub struct AudioConfiguration {
     pub mute: bool,
+    /// Runs the mic track through the voice enhancement chain (high-pass,
+    /// noise suppression, de-essing, loudness normalization and limiting).
     pub improve: bool,
     pub mic_volume_db: f32,
     pub mic_stereo_mode: StereoMode,
@@ -614,6 +624,9 @@ pub struct TimelineSegment {
     pub end: f64,
     #[serde(default)]
     pub name: Option<String>,
+    /// How this segment hands over to the next one. Ignored on the last segment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transition: Option<ClipTransition>,
 }
 
 impl TimelineSegment {
@@ -631,6 +644,65 @@ impl TimelineSegment {
     }
 }
 
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipTransition {
+    pub kind: TransitionKind,
+    /// In seconds of output time, centred on the cut.
+    pub duration: f64,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum TransitionKind {
+    Crossfade,
+    DipToColor { color: Color },
+    Slide { direction: SlideDirection },
+    ZoomThrough,
+}
+
+/// The direction the incoming clip travels as it pushes the outgoing one off screen.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SlideDirection {
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A transition between two adjacent timeline segments, resolved to output time.
+#[derive(Debug, Clone, Copy)]
+pub struct TimelineTransition<'a> {
+    pub kind: TransitionKind,
+    pub outgoing: &'a TimelineSegment,
+    pub incoming: &'a TimelineSegment,
+    /// Output time of the cut.
+    pub boundary: f64,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl TimelineTransition<'_> {
+    /// 0 at the start of the window, 1 at its end.
+    pub fn progress(&self, frame_time: f64) -> f64 {
+        ((frame_time - self.start) / (self.end - self.start)).clamp(0.0, 1.0)
+    }
+
+    /// Source time of the outgoing clip, which keeps playing past its end point. It holds on
+    /// `source_end`, the last time its recording has a frame for, when the cut is too close to
+    /// the end of the recording to play on through the window.
+    pub fn outgoing_time(&self, frame_time: f64, source_end: f64) -> f64 {
+        (self.outgoing.end + (frame_time - self.boundary) * self.outgoing.timescale).min(source_end)
+    }
+
+    /// Source time of the incoming clip, which starts playing before its start point.
+    pub fn incoming_time(&self, frame_time: f64) -> f64 {
+        (self.incoming.start + (frame_time - self.boundary) * self.incoming.timescale).max(0.0)
+    }
+}
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Copy)]
 #[serde(rename_all = "kebab-case")]
 pub enum GlideDirection {
@@ -660,11 +732,11 @@ pub struct ZoomSegment {
 }
 
 impl ZoomSegment {
-    fn default_glide_speed() -> f64 {
+    pub(crate) fn default_glide_speed() -> f64 {
         0.5
     }
 
-    fn default_edge_snap_ratio() -> f64 {
+    pub(crate) fn default_edge_snap_ratio() -> f64 {
         0.25
     }
 }
@@ -749,6 +821,166 @@ impl MaskSegment {
     }
 }
 
+/// Curve a keyframe follows on its way to the next keyframe.
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyframeEasing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Keeps this keyframe's value until the next keyframe is reached.
+    Hold,
+}
+
+impl KeyframeEasing {
+    /// Maps linear progress between two keyframes (`0..=1`) onto this curve.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::Hold => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextScalarKeyframe {
+    pub time: f64,
+    pub value: f64,
+    #[serde(default)]
+    pub easing: KeyframeEasing,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextVectorKeyframe {
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub easing: KeyframeEasing,
+}
+
+/// Animation tracks for a text overlay, with times relative to the segment start. An empty
+/// track leaves the matching static value (or the identity for rotation, scale and opacity)
+/// in place.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TextKeyframes {
+    #[serde(default)]
+    pub position: Vec<TextVectorKeyframe>,
+    #[serde(default)]
+    pub size: Vec<TextVectorKeyframe>,
+    /// Clockwise, in degrees.
+    #[serde(default)]
+    pub rotation: Vec<TextScalarKeyframe>,
+    #[serde(default)]
+    pub scale: Vec<TextScalarKeyframe>,
+    #[serde(default)]
+    pub opacity: Vec<TextScalarKeyframe>,
+}
+
+/// Resolves `relative`, a path stored in the project config, against the project bundle at
+/// `project_path`. Absolute paths and paths that climb out of the bundle are rejected so a
+/// shared project can't point the renderer at arbitrary files.
+pub fn bundled_file_path(project_path: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative.trim());
+    let inside_bundle = relative.components().all(|component| {
+        matches!(
+            component,
+            std::path::Component::Normal(_) | std::path::Component::CurDir
+        )
+    });
+
+    (inside_bundle && relative.file_name().is_some()).then(|| project_path.join(relative))
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TextAlign {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+/// Pixel sizes in the text styles below are measured at 1080p and scale with the output height.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TextOutline {
+    pub color: String,
+    pub width: f64,
+}
+
+impl Default for TextOutline {
+    fn default() -> Self {
+        Self {
+            color: "#000000".to_string(),
+            width: 2.0,
+        }
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TextShadow {
+    pub color: String,
+    pub opacity: f64,
+    pub offset: XY<f64>,
+    /// Softening radius in pixels; 0 draws a hard shadow.
+    pub blur: f64,
+}
+
+impl Default for TextShadow {
+    fn default() -> Self {
+        Self {
+            color: "#000000".to_string(),
+            opacity: 0.6,
+            offset: XY::new(3.0, 3.0),
+            blur: 4.0,
+        }
+    }
+}
+
+/// A rounded box drawn behind the laid-out text, `padding` pixels larger on each side.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TextBackground {
+    pub color: String,
+    pub opacity: f64,
+    pub padding: XY<f64>,
+    pub corner_radius: f64,
+}
+
+impl Default for TextBackground {
+    fn default() -> Self {
+        Self {
+            color: "#000000".to_string(),
+            opacity: 0.6,
+            padding: XY::new(24.0, 12.0),
+            corner_radius: 12.0,
+        }
+    }
+}
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TextSegment {
@@ -776,6 +1008,26 @@ pub struct TextSegment {
     pub color: String,
     #[serde(default = "TextSegment::default_fade_duration")]
     pub fade_duration: f64,
+    #[serde(default)]
+    pub keyframes: TextKeyframes,
+    /// A `.ttf`/`.otf` file inside the project bundle, relative to its root. When it loads,
+    /// it replaces `font_family`, so exports don't depend on the fonts installed locally.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_path: Option<String>,
+    #[serde(default)]
+    pub align: TextAlign,
+    /// Extra space between characters, in ems.
+    #[serde(default)]
+    pub letter_spacing: f32,
+    /// Line height as a multiple of the font size.
+    #[serde(default = "TextSegment::default_line_height")]
+    pub line_height: f32,
+    #[serde(default)]
+    pub outline: Option<TextOutline>,
+    #[serde(default)]
+    pub shadow: Option<TextShadow>,
+    #[serde(default)]
+    pub background: Option<TextBackground>,
 }
 
 impl TextSegment {
@@ -814,6 +1066,10 @@ impl TextSegment {
     fn default_fade_duration() -> f64 {
         0.15
     }
+
+    fn default_line_height() -> f32 {
+        1.2
+    }
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default)]
@@ -921,6 +1177,8 @@ pub struct TimelineConfiguration {
     pub keyboard_segments: Vec<crate::KeyboardTrackSegment>,
     #[serde(default)]
     pub audio_segments: Vec<AudioTrackSegment>,
+    #[serde(default)]
+    pub annotation_segments: Vec<AnnotationSegment>,
 }
 
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
@@ -966,6 +1224,39 @@ impl TimelineConfiguration {
     pub fn duration(&self) -> f64 {
         self.segments.iter().map(|s| s.duration()).sum()
     }
+
+    /// Every transition on the timeline, in order. A transition can't be longer than either
+    /// segment it joins, so neighbouring windows never overlap.
+    pub fn transitions(&self) -> impl Iterator<Item = TimelineTransition<'_>> {
+        let mut boundary = 0.0;
+
+        self.segments.windows(2).filter_map(move |pair| {
+            let [outgoing, incoming] = pair else {
+                return None;
+            };
+            boundary += outgoing.duration();
+
+            let transition = outgoing.transition.as_ref()?;
+            let duration = transition
+                .duration
+                .min(outgoing.duration())
+                .min(incoming.duration());
+            (duration > 0.0).then_some(TimelineTransition {
+                kind: transition.kind,
+                outgoing,
+                incoming,
+                boundary,
+                start: boundary - duration / 2.0,
+                end: boundary + duration / 2.0,
+            })
+        })
+    }
+
+    pub fn get_transition(&self, frame_time: f64) -> Option<TimelineTransition<'_>> {
+        self.transitions()
+            .take_while(|t| t.start <= frame_time)
+            .find(|t| frame_time < t.end)
+    }
 }
 
 pub const WALLPAPERS_PATH: &str = "assets/backgrounds/macOS";
@@ -1306,6 +1597,110 @@ impl Annotation {
     }
 }
 
+/// Animation tracks for an annotation segment, with times relative to the segment start.
+/// `position` moves the annotation's top-left corner (an arrow's tail) and `size` its extent.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationKeyframes {
+    #[serde(default)]
+    pub position: Vec<TextVectorKeyframe>,
+    #[serde(default)]
+    pub size: Vec<TextVectorKeyframe>,
+    #[serde(default)]
+    pub opacity: Vec<TextScalarKeyframe>,
+}
+
+/// A screenshot-editor annotation shown over part of the video timeline. Its `x`, `y`, `width`
+/// and `height` are fractions of the output frame rather than screenshot pixels, and its
+/// `stroke_width` is in pixels at 1080p.
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationSegment {
+    pub start: f64,
+    pub end: f64,
+    #[serde(default)]
+    pub track: u32,
+    #[serde(default = "AnnotationSegment::default_enabled")]
+    pub enabled: bool,
+    pub annotation: Annotation,
+    #[serde(default = "AnnotationSegment::default_fade_duration")]
+    pub fade_duration: f64,
+    /// Seconds an arrow takes to draw itself from tail to tip after `start`; 0 shows it whole.
+    #[serde(default)]
+    pub draw_on_duration: f64,
+    #[serde(default)]
+    pub keyframes: AnnotationKeyframes,
+}
+
+impl AnnotationSegment {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_fade_duration() -> f64 {
+        0.15
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkAnchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatermarkTimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// A logo composited over every rendered frame, in the editor preview and in exports.
+#[derive(Type, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatermarkConfiguration {
+    pub enabled: bool,
+    pub image_path: String,
+    pub anchor: WatermarkAnchor,
+    /// Gap between the logo and the anchored edges, as a fraction of the output's shorter side.
+    pub margin: f32,
+    /// Logo width as a fraction of the output width. The height follows the image's aspect ratio.
+    pub scale: f32,
+    pub opacity: f32,
+    /// Output-time range (seconds) the logo is shown for. `None` shows it for the whole video.
+    pub time_range: Option<WatermarkTimeRange>,
+}
+
+impl Default for WatermarkConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            image_path: String::new(),
+            anchor: WatermarkAnchor::default(),
+            margin: 0.03,
+            scale: 0.12,
+            opacity: 0.8,
+            time_range: None,
+        }
+    }
+}
+
+impl WatermarkConfiguration {
+    pub fn is_visible_at(&self, time: f64) -> bool {
+        self.enabled
+            && !self.image_path.is_empty()
+            && self.opacity > 0.0
+            && self.scale > 0.0
+            && self
+                .time_range
+                .is_none_or(|range| time >= range.start && time <= range.end)
+    }
+}
+
 #[derive(Type, Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase", default)]
 pub struct ProjectConfiguration {
@@ -1318,6 +1713,7 @@ pub struct ProjectConfiguration {
     pub timeline: Option<TimelineConfiguration>,
     pub captions: Option<CaptionsData>,
     pub keyboard: Option<KeyboardData>,
+    pub watermark: Option<WatermarkConfiguration>,
     pub clips: Vec<ClipConfiguration>,
     pub annotations: Vec<Annotation>,
     #[serde(skip_serializing)]
@@ -1328,17 +1724,6 @@ pub struct ProjectConfiguration {
     pub screen_movement_spring: ScreenMovementSpring,
 }
 
-fn camera_config_needs_migration(value: &Value) -> bool {
-    value
-        .get("camera")
-        .and_then(|camera| camera.as_object())
-        .is_some_and(|camera| {
-            camera.contains_key("zoom_size")
-                || camera.contains_key("advanced_shadow")
-                || camera.contains_key("rounding_type")
-        })
-}
-
 impl Default for ProjectConfiguration {
     fn default() -> Self {
         Self {
@@ -1351,6 +1736,7 @@ impl Default for ProjectConfiguration {
             timeline: Default::default(),
             captions: Default::default(),
             keyboard: Default::default(),
+            watermark: Default::default(),
             clips: Default::default(),
             annotations: Default::default(),
             hidden_text_segments: Default::default(),
@@ -1370,34 +1756,39 @@ impl ProjectConfiguration {
             annotation.validate()?;
         }
 
+        for segment in self
+            .timeline
+            .iter()
+            .flat_map(|timeline| &timeline.annotation_segments)
+        {
+            segment.annotation.validate()?;
+        }
+
         Ok(())
     }
 
+    /// Loads `project-config.json`, upgrading older schema versions and writing the result back
+    /// (after backing up the original) when anything had to change.
     pub fn load(project_path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
         let project_path = project_path.as_ref();
         let config_path = project_path.join("project-config.json");
         let config_str = std::fs::read_to_string(&config_path)?;
-        let parsed_value = serde_json::from_str::<Value>(&config_str).ok();
-        let missing_screen_motion_blur = parsed_value.as_ref().is_some_and(|value| {
-            value
-                .as_object()
-                .is_some_and(|object| !object.contains_key("screenMotionBlur"))
-        });
-        let needs_camera_migration = parsed_value
-            .as_ref()
-            .map(camera_config_needs_migration)
-            .unwrap_or(false);
-        let mut config: Self = serde_json::from_str(&config_str)
+        let mut value = serde_json::from_str::<Value>(&config_str)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        let from_version = schema_version(ProjectFile::ProjectConfig, &value)?;
+        let needs_schema_migration =
+            !migrate_value(ProjectFile::ProjectConfig, &mut value)?.is_empty();
+        let mut config: Self = serde_json::from_value(value)
             .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
         let cursor_motion_blur = config.cursor.motion_blur.clamp(0.0, 1.0);
         let screen_motion_blur = config.screen_motion_blur.clamp(0.0, 1.0);
         let needs_motion_blur_clamp = (config.cursor.motion_blur - cursor_motion_blur).abs()
             > f32::EPSILON
             || (config.screen_motion_blur - screen_motion_blur).abs() > f32::EPSILON;
-        let needs_screen_motion_blur_migration = missing_screen_motion_blur
-            || (screen_motion_blur - cursor_motion_blur).abs() > f32::EPSILON;
+        let needs_screen_motion_blur_sync =
+            (screen_motion_blur - cursor_motion_blur).abs() > f32::EPSILON;
         config.cursor.motion_blur = cursor_motion_blur;
-        if needs_screen_motion_blur_migration {
+        if needs_screen_motion_blur_sync {
             config.screen_motion_blur = config.cursor.motion_blur;
         } else {
             config.screen_motion_blur = screen_motion_blur;
@@ -1406,8 +1797,15 @@ impl ProjectConfiguration {
             .validate()
             .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
 
-        if needs_camera_migration || needs_motion_blur_clamp || needs_screen_motion_blur_migration {
-            match config.write(project_path) {
+        if needs_schema_migration || needs_motion_blur_clamp || needs_screen_motion_blur_sync {
+            let result = if needs_schema_migration {
+                back_up(&config_path, from_version)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| config.write_migrated(&config_path))
+            } else {
+                config.write_migrated(&config_path)
+            };
+            match result {
                 Ok(_) => {
                     eprintln!("Updated project-config.json migrated settings");
                 }
@@ -1420,23 +1818,27 @@ impl ProjectConfiguration {
         Ok(config)
     }
 
+    /// Atomically replaces `project-config.json` and records the new revision in the project's
+    /// [`ConfigHistory`].
     pub fn write(&self, project_path: impl AsRef<Path>) -> Result<(), std::io::Error> {
-        self.validate()
-            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
-
-        let project_path = project_path.as_ref();
-        let config_path = project_path.join("project-config.json");
-        let temp_path =
-            project_path.join(format!(".project-config-{}.json.tmp", uuid::Uuid::new_v4()));
+        let contents = self.serialize_for_write()?;
+        write_config_contents(project_path.as_ref(), &contents, None).map(drop)
+    }
 
-        std::fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+    /// Replaces `project-config.json` without recording a revision, since a load-time migration
+    /// isn't an edit the user would want to undo.
+    fn write_migrated(&self, config_path: &Path) -> Result<(), std::io::Error> {
+        write_atomic(config_path, &self.serialize_for_write()?)
+    }
 
-        if let Err(error) = std::fs::rename(&temp_path, &config_path) {
-            let _ = std::fs::remove_file(&temp_path);
-            return Err(error);
-        }
+    pub(crate) fn serialize_for_write(&self) -> Result<String, std::io::Error> {
+        self.validate()
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
 
-        Ok(())
+        Ok(serde_json::to_string_pretty(&Versioned {
+            schema_version: PROJECT_CONFIG_SCHEMA_VERSION,
+            inner: self,
+        })?)
     }
 
     pub fn get_segment_time(&self, frame_time: f64) -> Option<(f64, &TimelineSegment)> {
@@ -1444,6 +1846,12 @@ impl ProjectConfiguration {
             .as_ref()
             .and_then(|t| t.get_segment_time(frame_time))
     }
+
+    pub fn get_transition(&self, frame_time: f64) -> Option<TimelineTransition<'_>> {
+        self.timeline
+            .as_ref()
+            .and_then(|t| t.get_transition(frame_time))
+    }
 }
 
 pub const SLOW_SMOOTHING_SAMPLES: usize = 24;
@@ -1527,4 +1935,232 @@ mod tests {
         assert_eq!(config.cursor.motion_blur, 1.0);
         assert_eq!(config.screen_motion_blur, 1.0);
     }
+
+    #[test]
+    fn load_upgrades_unversioned_config_and_keeps_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        write_config_with_motion_blur_values(dir.path(), 0.3, None);
+        let config_path = dir.path().join("project-config.json");
+        let original = std::fs::read_to_string(&config_path).unwrap();
+
+        ProjectConfiguration::load(dir.path()).unwrap();
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(written["schemaVersion"], PROJECT_CONFIG_SCHEMA_VERSION);
+        assert_eq!(
+            std::fs::read_to_string(crate::backup_path(&config_path, 0)).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn load_rejects_configs_from_newer_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("project-config.json"),
+            format!(
+                r#"{{ "schemaVersion": {} }}"#,
+                PROJECT_CONFIG_SCHEMA_VERSION + 1
+            ),
+        )
+        .unwrap();
+
+        let error = ProjectConfiguration::load(dir.path()).unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    fn segment(start: f64, end: f64, transition: Option<ClipTransition>) -> TimelineSegment {
+        TimelineSegment {
+            recording_clip: 0,
+            timescale: 1.0,
+            start,
+            end,
+            name: None,
+            transition,
+        }
+    }
+
+    fn crossfade(duration: f64) -> Option<ClipTransition> {
+        Some(ClipTransition {
+            kind: TransitionKind::Crossfade,
+            duration,
+        })
+    }
+
+    fn timeline(segments: Vec<TimelineSegment>) -> TimelineConfiguration {
+        TimelineConfiguration {
+            segments,
+            zoom_segments: vec![],
+            scene_segments: vec![],
+            mask_segments: vec![],
+            text_segments: vec![],
+            caption_segments: vec![],
+            keyboard_segments: vec![],
+            audio_segments: vec![],
+            annotation_segments: vec![],
+        }
+    }
+
+    #[test]
+    fn transition_window_is_centred_on_the_cut() {
+        let timeline = timeline(vec![
+            segment(10.0, 14.0, crossfade(1.0)),
+            segment(20.0, 25.0, None),
+        ]);
+
+        assert!(timeline.get_transition(3.4).is_none());
+        assert!(timeline.get_transition(4.5).is_none());
+
+        let transition = timeline.get_transition(3.75).unwrap();
+        assert_eq!(transition.boundary, 4.0);
+        assert_eq!((transition.start, transition.end), (3.5, 4.5));
+        assert_eq!(transition.progress(3.75), 0.25);
+        assert_eq!(transition.outgoing_time(3.75, 60.0), 13.75);
+        assert_eq!(transition.incoming_time(3.75), 19.75);
+        assert_eq!(transition.outgoing_time(4.25, 60.0), 14.25);
+        assert_eq!(transition.incoming_time(4.25), 20.25);
+    }
+
+    #[test]
+    fn outgoing_clip_holds_its_last_frame_when_cut_at_the_end_of_the_recording() {
+        let last_frame = 14.0 - 1.0 / 30.0;
+        let timeline = timeline(vec![
+            segment(10.0, 14.0, crossfade(1.0)),
+            segment(20.0, 25.0, None),
+        ]);
+        let transition = timeline.get_transition(3.75).unwrap();
+
+        assert_eq!(transition.outgoing_time(3.75, last_frame), 13.75);
+        assert_eq!(transition.outgoing_time(4.0, last_frame), last_frame);
+        assert_eq!(transition.outgoing_time(4.49, last_frame), last_frame);
+        assert_eq!(transition.outgoing_time(4.25, 14.1), 14.1);
+        assert_eq!(transition.outgoing_time(4.25, 60.0), 14.25);
+    }
+
+    #[test]
+    fn transition_is_clamped_to_the_shorter_segment() {
+        let timeline = timeline(vec![
+            segment(0.0, 4.0, crossfade(3.0)),
+            segment(0.0, 1.0, crossfade(3.0)),
+            segment(0.0, 4.0, None),
+        ]);
+
+        let transitions = timeline.transitions().collect::<Vec<_>>();
+
+        assert_eq!(transitions.len(), 2);
+        assert_eq!((transitions[0].start, transitions[0].end), (3.5, 4.5));
+        assert_eq!((transitions[1].start, transitions[1].end), (4.5, 5.5));
+        assert_eq!(transitions[1].incoming_time(4.5), 0.0);
+    }
+
+    #[test]
+    fn transition_on_the_last_segment_is_ignored() {
+        let timeline = timeline(vec![segment(0.0, 2.0, crossfade(1.0))]);
+
+        assert_eq!(timeline.transitions().count(), 0);
+    }
+
+    #[test]
+    fn transition_round_trips_through_json() {
+        let mut with_transition = segment(0.0, 1.0, None);
+        with_transition.transition = Some(ClipTransition {
+            kind: TransitionKind::Slide {
+                direction: SlideDirection::Up,
+            },
+            duration: 0.5,
+        });
+
+        let json = serde_json::to_value(&with_transition).unwrap();
+        assert_eq!(
+            json["transition"],
+            serde_json::json!({ "kind": { "type": "slide", "direction": "up" }, "duration": 0.5 })
+        );
+        let parsed: TimelineSegment = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.transition, with_transition.transition);
+
+        let without = serde_json::to_value(segment(0.0, 1.0, None)).unwrap();
+        assert!(without.get("transition").is_none());
+    }
+
+    #[test]
+    fn keyframe_easings_start_and_end_on_the_keyframes() {
+        for easing in [
+            KeyframeEasing::Linear,
+            KeyframeEasing::EaseIn,
+            KeyframeEasing::EaseOut,
+            KeyframeEasing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+        }
+
+        assert!(KeyframeEasing::EaseIn.apply(0.5) < 0.5);
+        assert!(KeyframeEasing::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(KeyframeEasing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(KeyframeEasing::Hold.apply(0.99), 0.0);
+    }
+
+    #[test]
+    fn text_keyframes_are_optional_and_default_to_linear() {
+        let plain: TextSegment =
+            serde_json::from_value(serde_json::json!({ "start": 0.0, "end": 2.0 })).unwrap();
+        assert!(plain.keyframes.rotation.is_empty());
+
+        let animated: TextSegment = serde_json::from_value(serde_json::json!({
+            "start": 0.0,
+            "end": 2.0,
+            "keyframes": {
+                "rotation": [
+                    { "time": 0.0, "value": 0.0 },
+                    { "time": 1.0, "value": 90.0, "easing": "easeInOut" }
+                ]
+            }
+        }))
+        .unwrap();
+        let easings = animated
+            .keyframes
+            .rotation
+            .iter()
+            .map(|k| k.easing)
+            .collect::<Vec<_>>();
+        assert_eq!(easings, [KeyframeEasing::Linear, KeyframeEasing::EaseInOut]);
+        assert!(animated.keyframes.position.is_empty());
+    }
+
+    #[test]
+    fn text_styles_fill_in_defaults() {
+        let styled: TextSegment = serde_json::from_value(serde_json::json!({
+            "start": 0.0,
+            "end": 2.0,
+            "align": "right",
+            "outline": { "width": 4.0 },
+            "background": { "color": "#ff0000" }
+        }))
+        .unwrap();
+
+        assert_eq!(styled.align, TextAlign::Right);
+        assert_eq!(styled.line_height, 1.2);
+        assert_eq!(styled.outline.unwrap().color, "#000000");
+        assert_eq!(styled.background.unwrap().padding, XY::new(24.0, 12.0));
+        assert!(styled.shadow.is_none());
+        assert!(styled.font_path.is_none());
+    }
+
+    #[test]
+    fn bundled_files_must_stay_inside_the_project() {
+        let project = Path::new("/projects/demo.cap");
+
+        assert_eq!(
+            bundled_file_path(project, "fonts/Brand.ttf"),
+            Some(project.join("fonts/Brand.ttf"))
+        );
+        assert_eq!(bundled_file_path(project, "../other.cap/Brand.ttf"), None);
+        assert_eq!(
+            bundled_file_path(project, "/usr/share/fonts/Brand.ttf"),
+            None
+        );
+        assert_eq!(bundled_file_path(project, ""), None);
+    }
 }
@@ -0,0 +1,622 @@
+//! On-disk revision history for `project-config.json`.
+//!
+//! Every [`ProjectConfiguration::write`] records the written document as a revision under
+//! `config-history/` in the project. Revisions form a tree: each one remembers the revision that
+//! was current when it was written, so undo walks to the parent and redo to the newest child.
+//! Nothing is discarded by undo, restore or a new edit after an undo; only the oldest revisions
+//! are pruned once the history grows past [`CONFIG_HISTORY_LIMIT`].
+//!
+//! Anything that changes the index holds an exclusive lock on `config-history/index.lock` from
+//! reading it to saving it, so two processes editing one project can't drop each other's
+//! revisions. Writes made by [`ProjectConfiguration::load`]'s migrations aren't recorded.
+
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use fs4::fs_std::FileExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    ProjectConfiguration,
+    migration::{ProjectFile, migrate_value, write_atomic},
+};
+
+pub const CONFIG_HISTORY_DIR: &str = "config-history";
+pub const CONFIG_HISTORY_LIMIT: usize = 100;
+
+const INDEX_FILE: &str = "index.json";
+const LOCK_FILE: &str = "index.lock";
+/// Changed paths listed per revision before the rest are summarised as a count.
+const MAX_LISTED_CHANGES: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigRevision {
+    pub id: u64,
+    /// Revision that was current when this one was written.
+    pub parent: Option<u64>,
+    pub created_at_ms: u64,
+    /// Dotted paths of the settings that differ from `parent`, eg. `cursor.size`.
+    pub changes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryIndex {
+    head: Option<u64>,
+    next_id: u64,
+    revisions: Vec<ConfigRevision>,
+}
+
+#[derive(Debug)]
+pub struct ConfigHistory {
+    dir: PathBuf,
+    index: HistoryIndex,
+}
+
+impl ConfigHistory {
+    /// Opens the project's history; a project without one has an empty history.
+    pub fn open(project_path: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = project_path.as_ref().join(CONFIG_HISTORY_DIR);
+        let index = match std::fs::read_to_string(dir.join(INDEX_FILE)) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => HistoryIndex::default(),
+            Err(error) => return Err(error),
+        };
+
+        Ok(Self { dir, index })
+    }
+
+    /// Oldest first.
+    pub fn revisions(&self) -> &[ConfigRevision] {
+        &self.index.revisions
+    }
+
+    /// The revision `project-config.json` currently matches.
+    pub fn head(&self) -> Option<u64> {
+        self.index.head
+    }
+
+    pub fn revision(&self, id: u64) -> Option<&ConfigRevision> {
+        self.index
+            .revisions
+            .iter()
+            .find(|revision| revision.id == id)
+    }
+
+    pub fn undo_target(&self) -> Option<u64> {
+        let parent = self.revision(self.index.head?)?.parent?;
+        self.revision(parent).map(|revision| revision.id)
+    }
+
+    /// The most recently written child of the head.
+    pub fn redo_target(&self) -> Option<u64> {
+        let head = self.index.head?;
+        self.index
+            .revisions
+            .iter()
+            .rev()
+            .find(|revision| revision.parent == Some(head))
+            .map(|revision| revision.id)
+    }
+
+    fn revision_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{id:06}.json"))
+    }
+
+    pub fn read_revision(&self, id: u64) -> io::Result<String> {
+        if self.revision(id).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("revision {id} is not in the project's config history"),
+            ));
+        }
+        std::fs::read_to_string(self.revision_path(id))
+    }
+
+    pub fn load_revision(&self, id: u64) -> io::Result<ProjectConfiguration> {
+        parse_config(&self.read_revision(id)?)
+    }
+
+    /// Records `contents` as the new head. Writing the same document as the head is a no-op.
+    pub(crate) fn record(&mut self, contents: &str, note: Option<String>) -> io::Result<u64> {
+        let previous = match self.index.head {
+            Some(head) => self.read_revision(head).ok(),
+            None => None,
+        };
+        if let (Some(head), Some(previous)) = (self.index.head, &previous)
+            && previous == contents
+        {
+            return Ok(head);
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+
+        let id = self.index.next_id.max(1);
+        write_atomic(&self.revision_path(id), contents)?;
+
+        self.index.revisions.push(ConfigRevision {
+            id,
+            parent: self.index.head,
+            created_at_ms: now_ms(),
+            changes: previous
+                .map(|previous| summarize_changes(&previous, contents))
+                .unwrap_or_default(),
+            note,
+        });
+        self.index.head = Some(id);
+        self.index.next_id = id + 1;
+        self.prune();
+        self.save()?;
+
+        Ok(id)
+    }
+
+    /// Points the head at an existing revision without recording a new one (undo/redo).
+    fn move_head(&mut self, id: u64) -> io::Result<()> {
+        self.index.head = Some(id);
+        self.save()
+    }
+
+    fn prune(&mut self) {
+        while self.index.revisions.len() > CONFIG_HISTORY_LIMIT {
+            let Some(position) = self
+                .index
+                .revisions
+                .iter()
+                .position(|revision| Some(revision.id) != self.index.head)
+            else {
+                break;
+            };
+            let removed = self.index.revisions.remove(position);
+            let _ = std::fs::remove_file(self.revision_path(removed.id));
+        }
+
+        let remaining: BTreeSet<u64> = self.index.revisions.iter().map(|r| r.id).collect();
+        for revision in &mut self.index.revisions {
+            if revision
+                .parent
+                .is_some_and(|parent| !remaining.contains(&parent))
+            {
+                revision.parent = None;
+            }
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        write_atomic(
+            &self.dir.join(INDEX_FILE),
+            &serde_json::to_string_pretty(&self.index)?,
+        )
+    }
+}
+
+/// Exclusive lock on a project's history, released on drop. Take it before
+/// [`ConfigHistory::open`] when the index is going to be saved.
+struct HistoryLock(File);
+
+impl HistoryLock {
+    fn acquire(project_path: &Path) -> io::Result<Self> {
+        let dir = project_path.join(CONFIG_HISTORY_DIR);
+        std::fs::create_dir_all(&dir)?;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE))?;
+        file.lock_exclusive()?;
+
+        Ok(Self(file))
+    }
+}
+
+impl Drop for HistoryLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.0);
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn parse_config(contents: &str) -> io::Result<ProjectConfiguration> {
+    let mut value: Value = serde_json::from_str(contents)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    migrate_value(ProjectFile::ProjectConfig, &mut value)?;
+    serde_json::from_value(value).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Dotted paths that differ between two config documents, two levels deep.
+fn summarize_changes(before: &str, after: &str) -> Vec<String> {
+    let (Ok(before), Ok(after)) = (
+        serde_json::from_str::<Value>(before),
+        serde_json::from_str::<Value>(after),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut changes = Vec::new();
+    collect_changes(&before, &after, "", 2, &mut changes);
+
+    if changes.len() > MAX_LISTED_CHANGES {
+        let hidden = changes.len() - MAX_LISTED_CHANGES;
+        changes.truncate(MAX_LISTED_CHANGES);
+        changes.push(format!("…and {hidden} more"));
+    }
+
+    changes
+}
+
+fn collect_changes(before: &Value, after: &Value, path: &str, depth: u32, out: &mut Vec<String>) {
+    if before == after {
+        return;
+    }
+
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) if depth > 0 => {
+            let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                collect_changes(
+                    before.get(key).unwrap_or(&Value::Null),
+                    after.get(key).unwrap_or(&Value::Null),
+                    &child,
+                    depth - 1,
+                    out,
+                );
+            }
+        }
+        _ => out.push(path.to_string()),
+    }
+}
+
+/// Writes `project-config.json` and records it as the new head. Failing to record history is
+/// logged rather than failing the write; the revision id is `None` in that case.
+pub(crate) fn write_config_contents(
+    project_path: &Path,
+    contents: &str,
+    note: Option<String>,
+) -> io::Result<Option<u64>> {
+    let lock = HistoryLock::acquire(project_path)
+        .map_err(|error| warn!("Failed to lock project config history: {error}"))
+        .ok();
+    let history = lock.as_ref().and_then(|_| {
+        ConfigHistory::open(project_path)
+            .map_err(|error| warn!("Failed to open project config history: {error}"))
+            .ok()
+    });
+
+    write_and_record(project_path, contents, note, history)
+}
+
+/// The body of [`write_config_contents`] for callers already holding the [`HistoryLock`] that
+/// `history` was opened under.
+fn write_and_record(
+    project_path: &Path,
+    contents: &str,
+    note: Option<String>,
+    mut history: Option<ConfigHistory>,
+) -> io::Result<Option<u64>> {
+    let config_path = project_path.join("project-config.json");
+
+    // Seed the history with whatever the project had before it was tracked.
+    if let Some(tracked) = &mut history
+        && tracked.head().is_none()
+        && let Ok(existing) = std::fs::read_to_string(&config_path)
+        && existing != contents
+        && let Err(error) = tracked.record(&existing, Some("before history".to_string()))
+    {
+        warn!("Failed to record the untracked project config: {error}");
+        history = None;
+    }
+
+    write_atomic(&config_path, contents)?;
+
+    Ok(history.and_then(|mut history| {
+        history
+            .record(contents, note)
+            .map_err(|error| warn!("Failed to record project config revision: {error}"))
+            .ok()
+    }))
+}
+
+impl ProjectConfiguration {
+    /// Restores an earlier revision as a new revision, so the restore itself can be undone.
+    pub fn restore_revision(project_path: impl AsRef<Path>, id: u64) -> io::Result<(Self, u64)> {
+        let project_path = project_path.as_ref();
+        let _lock = HistoryLock::acquire(project_path)?;
+        let history = ConfigHistory::open(project_path)?;
+        let config = history.load_revision(id)?;
+        let contents = config.serialize_for_write()?;
+        let revision = write_and_record(
+            project_path,
+            &contents,
+            Some(format!("restored {id}")),
+            Some(history),
+        )?
+        .ok_or_else(|| io::Error::other("failed to record the restored revision"))?;
+        Ok((config, revision))
+    }
+
+    /// Steps `project-config.json` back to the revision the current one was edited from.
+    /// Returns `None` when there is nothing to undo.
+    pub fn undo(project_path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        Self::step_history(project_path.as_ref(), ConfigHistory::undo_target)
+    }
+
+    /// Re-applies the most recent edit made on top of the current revision.
+    pub fn redo(project_path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        Self::step_history(project_path.as_ref(), ConfigHistory::redo_target)
+    }
+
+    fn step_history(
+        project_path: &Path,
+        target: fn(&ConfigHistory) -> Option<u64>,
+    ) -> io::Result<Option<Self>> {
+        let _lock = HistoryLock::acquire(project_path)?;
+        let mut history = ConfigHistory::open(project_path)?;
+        let Some(id) = target(&history) else {
+            return Ok(None);
+        };
+
+        let contents = history.read_revision(id)?;
+        let config = parse_config(&contents)?;
+        write_atomic(&project_path.join("project-config.json"), &contents)?;
+        history.move_head(id)?;
+
+        Ok(Some(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_cursor_size(size: u32) -> ProjectConfiguration {
+        let mut config = ProjectConfiguration::default();
+        config.cursor.size = size;
+        config
+    }
+
+    fn cursor_size(project_path: &Path) -> u32 {
+        ProjectConfiguration::load(project_path)
+            .unwrap()
+            .cursor
+            .size
+    }
+
+    #[test]
+    fn writes_are_recorded_with_a_change_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        config_with_cursor_size(100).write(dir.path()).unwrap();
+        config_with_cursor_size(100).write(dir.path()).unwrap();
+        config_with_cursor_size(150).write(dir.path()).unwrap();
+
+        let history = ConfigHistory::open(dir.path()).unwrap();
+        let revisions = history.revisions();
+
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].parent, None);
+        assert!(revisions[0].changes.is_empty());
+        assert_eq!(revisions[1].parent, Some(revisions[0].id));
+        assert_eq!(revisions[1].changes, vec!["cursor.size".to_string()]);
+        assert_eq!(history.head(), Some(revisions[1].id));
+    }
+
+    #[test]
+    fn untracked_config_is_kept_as_the_first_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("project-config.json"),
+            serde_json::to_string(&config_with_cursor_size(70)).unwrap(),
+        )
+        .unwrap();
+
+        config_with_cursor_size(90).write(dir.path()).unwrap();
+
+        assert_eq!(
+            ProjectConfiguration::undo(dir.path())
+                .unwrap()
+                .unwrap()
+                .cursor
+                .size,
+            70
+        );
+    }
+
+    #[test]
+    fn load_time_migration_is_not_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("project-config.json"),
+            serde_json::to_string(&config_with_cursor_size(70)).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(cursor_size(dir.path()), 70);
+        assert!(
+            ConfigHistory::open(dir.path())
+                .unwrap()
+                .revisions()
+                .is_empty()
+        );
+
+        config_with_cursor_size(90).write(dir.path()).unwrap();
+        let history = ConfigHistory::open(dir.path()).unwrap();
+        assert_eq!(history.revisions().len(), 2);
+        assert_eq!(
+            history.revisions()[0].note.as_deref(),
+            Some("before history")
+        );
+    }
+
+    #[test]
+    fn concurrent_writers_keep_every_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        std::thread::scope(|scope| {
+            for writer in 0..4 {
+                let path = dir.path();
+                scope.spawn(move || {
+                    for edit in 0..5 {
+                        config_with_cursor_size(writer * 100 + edit)
+                            .write(path)
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let history = ConfigHistory::open(dir.path()).unwrap();
+        assert_eq!(history.revisions().len(), 20);
+        assert_eq!(
+            history.head(),
+            history.revisions().last().map(|revision| revision.id)
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_history_across_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        for size in [100, 120, 140] {
+            config_with_cursor_size(size).write(dir.path()).unwrap();
+        }
+
+        assert_eq!(
+            ProjectConfiguration::undo(dir.path())
+                .unwrap()
+                .unwrap()
+                .cursor
+                .size,
+            120
+        );
+        assert_eq!(
+            ProjectConfiguration::undo(dir.path())
+                .unwrap()
+                .unwrap()
+                .cursor
+                .size,
+            100
+        );
+        assert!(ProjectConfiguration::undo(dir.path()).unwrap().is_none());
+        assert_eq!(cursor_size(dir.path()), 100);
+
+        assert_eq!(
+            ProjectConfiguration::redo(dir.path())
+                .unwrap()
+                .unwrap()
+                .cursor
+                .size,
+            120
+        );
+        assert_eq!(cursor_size(dir.path()), 120);
+
+        // Editing after an undo branches; the undone revision stays restorable.
+        config_with_cursor_size(200).write(dir.path()).unwrap();
+        assert!(ProjectConfiguration::redo(dir.path()).unwrap().is_none());
+        assert_eq!(
+            ProjectConfiguration::undo(dir.path())
+                .unwrap()
+                .unwrap()
+                .cursor
+                .size,
+            120
+        );
+        assert_eq!(
+            ProjectConfiguration::redo(dir.path())
+                .unwrap()
+                .unwrap()
+                .cursor
+                .size,
+            200
+        );
+
+        let history = ConfigHistory::open(dir.path()).unwrap();
+        assert_eq!(history.revisions().len(), 4);
+    }
+
+    #[test]
+    fn restore_records_a_new_revision() {
+        let dir = tempfile::tempdir().unwrap();
+        config_with_cursor_size(100).write(dir.path()).unwrap();
+        config_with_cursor_size(150).write(dir.path()).unwrap();
+        let first = ConfigHistory::open(dir.path()).unwrap().revisions()[0].id;
+
+        let (config, revision) = ProjectConfiguration::restore_revision(dir.path(), first).unwrap();
+
+        assert_eq!(config.cursor.size, 100);
+        assert_eq!(cursor_size(dir.path()), 100);
+        let history = ConfigHistory::open(dir.path()).unwrap();
+        assert_eq!(history.head(), Some(revision));
+        assert_eq!(
+            history.revision(revision).unwrap().note.as_deref(),
+            Some(format!("restored {first}").as_str())
+        );
+        assert_eq!(
+            ProjectConfiguration::undo(dir.path())
+                .unwrap()
+                .unwrap()
+                .cursor
+                .size,
+            150
+        );
+        assert!(ProjectConfiguration::restore_revision(dir.path(), 999).is_err());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        for size in 0..(CONFIG_HISTORY_LIMIT as u32 + 5) {
+            config_with_cursor_size(size).write(dir.path()).unwrap();
+        }
+
+        let history = ConfigHistory::open(dir.path()).unwrap();
+        assert_eq!(history.revisions().len(), CONFIG_HISTORY_LIMIT);
+        assert_eq!(history.revisions()[0].parent, None);
+        let files = std::fs::read_dir(dir.path().join(CONFIG_HISTORY_DIR))
+            .unwrap()
+            .count();
+        // The revisions plus the index and its lock.
+        assert_eq!(files, CONFIG_HISTORY_LIMIT + 2);
+    }
+
+    #[test]
+    fn change_summary_lists_nested_paths_and_caps_them() {
+        let before = serde_json::json!({ "a": { "b": 1, "c": { "d": 1 } }, "e": 1 }).to_string();
+        let after = serde_json::json!({ "a": { "b": 2, "c": { "d": 2 } }, "f": 1 }).to_string();
+
+        assert_eq!(
+            summarize_changes(&before, &after),
+            vec!["a.b", "a.c", "e", "f"]
+        );
+
+        let wide_before = serde_json::json!({}).to_string();
+        let wide_after = Value::Object(
+            (0..20)
+                .map(|i| (format!("k{i:02}"), Value::from(i)))
+                .collect(),
+        )
+        .to_string();
+        let changes = summarize_changes(&wide_before, &wide_after);
+        assert_eq!(changes.len(), MAX_LISTED_CHANGES + 1);
+        assert_eq!(changes.last().unwrap(), "…and 8 more");
+    }
+}
@@ -84,7 +84,7 @@ const SPECIAL_KEY_SYMBOLS: &[(&str, &str)] = &[
     ("PageDown", "⇟"),
 ];
 
-fn is_modifier_key(key: &str) -> bool {
+pub(crate) fn is_modifier_key(key: &str) -> bool {
     MODIFIER_KEYS.contains(&key)
 }
 
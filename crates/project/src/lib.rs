@@ -1,12 +1,26 @@
+mod auto_cut;
+mod captions;
+mod chapters;
 mod configuration;
 pub mod cursor;
+mod history;
 pub mod keyboard;
 mod meta;
+mod migration;
+mod subtitles;
+mod zoom_suggestions;
 
+pub use auto_cut::*;
+pub use captions::*;
+pub use chapters::*;
 pub use configuration::*;
 pub use cursor::*;
+pub use history::*;
 pub use keyboard::*;
 pub use meta::*;
+pub use migration::*;
+pub use subtitles::*;
+pub use zoom_suggestions::*;
 
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -10,8 +10,12 @@ use std::{
 use tracing::{debug, info, warn};
 
 use crate::{
-    CaptionsData, CursorEvents, CursorImage, KeyboardEvents, ProjectConfiguration, XY,
+    CaptionsData, ChapterMarker, CursorEvents, CursorImage, KeyboardEvents, ProjectConfiguration,
+    XY,
     cursor::SHORT_CURSOR_SHAPE_DEBOUNCE_MS,
+    migration::{
+        ProjectFile, RECORDING_META_SCHEMA_VERSION, SCHEMA_VERSION_KEY, Versioned, migrate_value,
+    },
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
@@ -100,6 +104,8 @@ pub struct RecordingMeta {
     pub inner: RecordingMetaInner,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub upload: Option<UploadMeta>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<ChapterMarker>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Type, Debug)]
@@ -164,7 +170,14 @@ impl RecordingMeta {
 
     pub fn load_for_project(project_path: &Path) -> Result<Self, Box<dyn Error>> {
         let meta_path = project_path.join("recording-meta.json");
-        let mut meta: Self = serde_json::from_str(&std::fs::read_to_string(&meta_path)?)?;
+        let mut value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&meta_path)?)?;
+        migrate_value(ProjectFile::RecordingMeta, &mut value)?;
+        // Keep the version out of the flattened, untagged `inner`.
+        if let Some(object) = value.as_object_mut() {
+            object.remove(SCHEMA_VERSION_KEY);
+        }
+        let mut meta: Self = serde_json::from_value(value)?;
         meta.project_path = project_path.to_path_buf();
         meta.normalize_paths();
 
@@ -173,7 +186,11 @@ impl RecordingMeta {
 
     pub fn save_for_project(&self) -> Result<(), Either<serde_json::Error, std::io::Error>> {
         let meta_path = &self.project_path.join("recording-meta.json");
-        let meta = serde_json::to_string_pretty(&self).map_err(Either::Left)?;
+        let meta = serde_json::to_string_pretty(&Versioned {
+            schema_version: RECORDING_META_SCHEMA_VERSION,
+            inner: self,
+        })
+        .map_err(Either::Left)?;
         std::fs::write(meta_path, meta).map_err(Either::Right)?;
         Ok(())
     }
@@ -456,6 +473,9 @@ impl MultipleSegments {
                     ))
                     | Some(cap_cursor_info::CursorShape::Windows(
                         cap_cursor_info::CursorShapeWindows::Arrow,
+                    ))
+                    | Some(cap_cursor_info::CursorShape::Linux(
+                        cap_cursor_info::CursorShapeLinux::Default,
                     )) => Some(id.clone()),
                     _ => None,
                 })
@@ -0,0 +1,446 @@
+//! Schema versions for the JSON files in a project directory.
+//!
+//! Every file carries a `schemaVersion` (files written before versioning have none and count as
+//! version 0). Upgrades are an ordered list of steps per file, each taking a document from one
+//! version to the next, applied to the raw JSON before it is deserialized.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+pub const PROJECT_CONFIG_SCHEMA_VERSION: u32 = 1;
+pub const RECORDING_META_SCHEMA_VERSION: u32 = 1;
+
+pub(crate) const SCHEMA_VERSION_KEY: &str = "schemaVersion";
+
+/// One upgrade step, taking a document from `from` to `from + 1`.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    apply: fn(&mut Map<String, Value>),
+}
+
+static PROJECT_CONFIG_MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "rename snake_case camera keys and derive screenMotionBlur from the cursor",
+    apply: project_config_v0,
+}];
+
+static RECORDING_META_MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "add schemaVersion",
+    apply: |_| {},
+}];
+
+fn project_config_v0(config: &mut Map<String, Value>) {
+    if let Some(camera) = config.get_mut("camera").and_then(Value::as_object_mut) {
+        for (old, new) in [
+            ("zoom_size", "zoomSize"),
+            ("advanced_shadow", "advancedShadow"),
+            ("rounding_type", "roundingType"),
+        ] {
+            if let Some(value) = camera.remove(old) {
+                camera.entry(new).or_insert(value);
+            }
+        }
+    }
+
+    // Screen motion blur used to follow the cursor setting.
+    if !config.contains_key("screenMotionBlur") {
+        let cursor_motion_blur = config
+            .get("cursor")
+            .and_then(|cursor| cursor.get("motionBlur"))
+            .cloned();
+        if let Some(motion_blur) = cursor_motion_blur {
+            config.insert("screenMotionBlur".to_string(), motion_blur);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectFile {
+    ProjectConfig,
+    RecordingMeta,
+}
+
+impl ProjectFile {
+    pub const ALL: [Self; 2] = [Self::RecordingMeta, Self::ProjectConfig];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::ProjectConfig => "project-config.json",
+            Self::RecordingMeta => "recording-meta.json",
+        }
+    }
+
+    pub fn current_version(self) -> u32 {
+        match self {
+            Self::ProjectConfig => PROJECT_CONFIG_SCHEMA_VERSION,
+            Self::RecordingMeta => RECORDING_META_SCHEMA_VERSION,
+        }
+    }
+
+    pub fn migrations(self) -> &'static [Migration] {
+        match self {
+            Self::ProjectConfig => PROJECT_CONFIG_MIGRATIONS,
+            Self::RecordingMeta => RECORDING_META_MIGRATIONS,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    NotAnObject(ProjectFile),
+    InvalidVersion(ProjectFile),
+    UnsupportedVersion {
+        file: ProjectFile,
+        found: u32,
+        supported: u32,
+    },
+    MissingStep {
+        file: ProjectFile,
+        from: u32,
+    },
+    Json(PathBuf, serde_json::Error),
+    Io(PathBuf, std::io::Error),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnObject(file) => write!(f, "{} is not a JSON object", file.file_name()),
+            Self::InvalidVersion(file) => {
+                write!(f, "{} has an invalid schemaVersion", file.file_name())
+            }
+            Self::UnsupportedVersion {
+                file,
+                found,
+                supported,
+            } => write!(
+                f,
+                "{} has schemaVersion {found} but this build only supports up to {supported}; update Cap",
+                file.file_name()
+            ),
+            Self::MissingStep { file, from } => write!(
+                f,
+                "no migration registered for {} schemaVersion {from}",
+                file.file_name()
+            ),
+            Self::Json(path, error) => write!(f, "failed to parse {}: {error}", path.display()),
+            Self::Io(path, error) => write!(f, "{}: {error}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<MigrationError> for std::io::Error {
+    fn from(error: MigrationError) -> Self {
+        match error {
+            MigrationError::Io(_, error) => error,
+            error => std::io::Error::new(std::io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+/// The document's `schemaVersion`, or 0 for files written before versioning.
+pub fn schema_version(file: ProjectFile, document: &Value) -> Result<u32, MigrationError> {
+    match document
+        .as_object()
+        .ok_or(MigrationError::NotAnObject(file))?
+        .get(SCHEMA_VERSION_KEY)
+    {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(MigrationError::InvalidVersion(file)),
+    }
+}
+
+/// Upgrades `document` in place to the current schema, returning the steps that were applied.
+pub fn migrate_value(
+    file: ProjectFile,
+    document: &mut Value,
+) -> Result<Vec<&'static Migration>, MigrationError> {
+    let supported = file.current_version();
+    let mut version = schema_version(file, document)?;
+    if version > supported {
+        return Err(MigrationError::UnsupportedVersion {
+            file,
+            found: version,
+            supported,
+        });
+    }
+
+    let object = document
+        .as_object_mut()
+        .ok_or(MigrationError::NotAnObject(file))?;
+    let mut applied = Vec::new();
+
+    while version < supported {
+        let step = file
+            .migrations()
+            .iter()
+            .find(|step| step.from == version)
+            .ok_or(MigrationError::MissingStep {
+                file,
+                from: version,
+            })?;
+        (step.apply)(object);
+        applied.push(step);
+        version += 1;
+    }
+
+    object.insert(SCHEMA_VERSION_KEY.to_string(), Value::from(supported));
+
+    Ok(applied)
+}
+
+/// What upgrading one file did, or would do in a dry run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMigration {
+    pub file: ProjectFile,
+    pub path: PathBuf,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<&'static str>,
+    /// Copy of the file as it was before the upgrade. Not set for dry runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_path: Option<PathBuf>,
+}
+
+impl FileMigration {
+    pub fn is_needed(&self) -> bool {
+        self.from_version < self.to_version
+    }
+}
+
+/// Where the pre-upgrade copy of a file at `from_version` is kept, eg. `project-config.json.v0.bak`.
+pub fn backup_path(path: &Path, from_version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{from_version}.bak"));
+    path.with_file_name(name)
+}
+
+/// Copies `path` to its backup location. An existing backup is left alone so the oldest
+/// original survives repeated upgrades.
+pub(crate) fn back_up(path: &Path, from_version: u32) -> Result<PathBuf, MigrationError> {
+    let backup = backup_path(path, from_version);
+    if !backup.exists() {
+        std::fs::copy(path, &backup).map_err(|error| MigrationError::Io(backup.clone(), error))?;
+    }
+    Ok(backup)
+}
+
+/// Writes `contents` next to `path` and renames it into place so readers never see a partial file.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{stem}-{}.json.tmp", uuid::Uuid::new_v4()));
+
+    std::fs::write(&temp_path, contents)?;
+
+    if let Err(error) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Upgrades one file of a project. Returns `None` when the project has no such file.
+///
+/// With `dry_run` nothing is written; the report says what would change.
+pub fn migrate_file(
+    project_path: &Path,
+    file: ProjectFile,
+    dry_run: bool,
+) -> Result<Option<FileMigration>, MigrationError> {
+    let path = project_path.join(file.file_name());
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(MigrationError::Io(path, error)),
+    };
+    let mut document: Value = serde_json::from_str(&contents)
+        .map_err(|error| MigrationError::Json(path.clone(), error))?;
+
+    let from_version = schema_version(file, &document)?;
+    let steps = migrate_value(file, &mut document)?;
+    let mut report = FileMigration {
+        file,
+        from_version,
+        to_version: file.current_version(),
+        steps: steps.iter().map(|step| step.description).collect(),
+        backup_path: None,
+        path,
+    };
+
+    if dry_run || !report.is_needed() {
+        return Ok(Some(report));
+    }
+
+    report.backup_path = Some(back_up(&report.path, from_version)?);
+    let contents = serde_json::to_string_pretty(&document)
+        .map_err(|error| MigrationError::Json(report.path.clone(), error))?;
+    write_atomic(&report.path, &contents)
+        .map_err(|error| MigrationError::Io(report.path.clone(), error))?;
+
+    Ok(Some(report))
+}
+
+/// Upgrades every versioned file in a project directory.
+pub fn migrate_project(
+    project_path: &Path,
+    dry_run: bool,
+) -> Result<Vec<FileMigration>, MigrationError> {
+    let mut reports = Vec::new();
+    for file in ProjectFile::ALL {
+        if let Some(report) = migrate_file(project_path, file, dry_run)? {
+            reports.push(report);
+        }
+    }
+    Ok(reports)
+}
+
+/// Serializes `inner` with a leading `schemaVersion`.
+#[derive(Serialize)]
+pub(crate) struct Versioned<'a, T> {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub inner: &'a T,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn legacy_config() -> Value {
+        json!({
+            "camera": { "zoom_size": 40.0, "rounding_type": "squircle", "hide": false },
+            "cursor": { "motionBlur": 0.2 }
+        })
+    }
+
+    #[test]
+    fn upgrades_legacy_project_config() {
+        let mut config = legacy_config();
+
+        let steps = migrate_value(ProjectFile::ProjectConfig, &mut config).unwrap();
+
+        assert_eq!(steps.len(), 1);
+        assert_eq!(
+            config,
+            json!({
+                "schemaVersion": PROJECT_CONFIG_SCHEMA_VERSION,
+                "camera": { "zoomSize": 40.0, "roundingType": "squircle", "hide": false },
+                "cursor": { "motionBlur": 0.2 },
+                "screenMotionBlur": 0.2
+            })
+        );
+    }
+
+    #[test]
+    fn camel_case_camera_keys_win_over_legacy_ones() {
+        let mut config = json!({ "camera": { "zoom_size": 40.0, "zoomSize": 60.0 } });
+
+        migrate_value(ProjectFile::ProjectConfig, &mut config).unwrap();
+
+        assert_eq!(config["camera"], json!({ "zoomSize": 60.0 }));
+    }
+
+    #[test]
+    fn current_documents_are_left_alone() {
+        let mut config = json!({ "schemaVersion": PROJECT_CONFIG_SCHEMA_VERSION, "camera": {} });
+        let before = config.clone();
+
+        let steps = migrate_value(ProjectFile::ProjectConfig, &mut config).unwrap();
+
+        assert!(steps.is_empty());
+        assert_eq!(config, before);
+    }
+
+    #[test]
+    fn rejects_newer_and_malformed_versions() {
+        let mut newer = json!({ "schemaVersion": RECORDING_META_SCHEMA_VERSION + 1 });
+        assert!(matches!(
+            migrate_value(ProjectFile::RecordingMeta, &mut newer),
+            Err(MigrationError::UnsupportedVersion { found, .. })
+                if found == RECORDING_META_SCHEMA_VERSION + 1
+        ));
+
+        let mut malformed = json!({ "schemaVersion": "one" });
+        assert!(matches!(
+            migrate_value(ProjectFile::RecordingMeta, &mut malformed),
+            Err(MigrationError::InvalidVersion(ProjectFile::RecordingMeta))
+        ));
+    }
+
+    #[test]
+    fn registries_cover_every_version() {
+        for file in ProjectFile::ALL {
+            for version in 0..file.current_version() {
+                assert_eq!(
+                    file.migrations()
+                        .iter()
+                        .filter(|step| step.from == version)
+                        .count(),
+                    1,
+                    "{file:?} needs exactly one step from v{version}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dry_run_reports_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("project-config.json");
+        let original = legacy_config().to_string();
+        std::fs::write(&config_path, &original).unwrap();
+
+        let reports = migrate_project(dir.path(), true).unwrap();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].file, ProjectFile::ProjectConfig);
+        assert!(reports[0].is_needed());
+        assert_eq!(reports[0].backup_path, None);
+        assert_eq!(std::fs::read_to_string(&config_path).unwrap(), original);
+        assert!(!backup_path(&config_path, 0).exists());
+    }
+
+    #[test]
+    fn migrating_backs_up_the_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("project-config.json");
+        let meta_path = dir.path().join("recording-meta.json");
+        let original = legacy_config().to_string();
+        std::fs::write(&config_path, &original).unwrap();
+        std::fs::write(&meta_path, r#"{ "pretty_name": "Demo" }"#).unwrap();
+
+        let reports = migrate_project(dir.path(), false).unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(FileMigration::is_needed));
+        let backup = backup_path(&config_path, 0);
+        assert_eq!(reports[1].backup_path.as_deref(), Some(backup.as_path()));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+
+        let migrated: Value =
+            serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(migrated["schemaVersion"], PROJECT_CONFIG_SCHEMA_VERSION);
+        assert_eq!(migrated["camera"]["zoomSize"], 40.0);
+
+        let rerun = migrate_project(dir.path(), true).unwrap();
+        assert!(rerun.iter().all(|report| !report.is_needed()));
+    }
+}
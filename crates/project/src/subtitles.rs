@@ -0,0 +1,896 @@
+use std::{fmt, fmt::Write, path::Path};
+
+use crate::{
+    CaptionSegment, CaptionSettings, CaptionTrackSegment, CaptionWord, CaptionsData,
+    caption_token_attaches_to_previous,
+};
+
+/// A caption as a subtitle file sees it: a timed line of text with optional per-word timings.
+/// Source-time captions and the timeline's output-time track both write out the same way.
+pub trait CaptionCue {
+    fn start(&self) -> f64;
+    fn end(&self) -> f64;
+    fn text(&self) -> &str;
+    fn words(&self) -> &[CaptionWord];
+}
+
+impl CaptionCue for CaptionSegment {
+    fn start(&self) -> f64 {
+        f64::from(self.start)
+    }
+
+    fn end(&self) -> f64 {
+        f64::from(self.end)
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn words(&self) -> &[CaptionWord] {
+        &self.words
+    }
+}
+
+impl CaptionCue for CaptionTrackSegment {
+    fn start(&self) -> f64 {
+        self.start
+    }
+
+    fn end(&self) -> f64 {
+        self.end
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn words(&self) -> &[CaptionWord] {
+        &self.words
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+    /// Advanced SubStation Alpha. Plain SSA files parse too, as their events use the same layout.
+    Ass,
+}
+
+impl SubtitleFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::WebVtt),
+            "ass" | "ssa" => Some(Self::Ass),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::WebVtt => "vtt",
+            Self::Ass => "ass",
+        }
+    }
+
+    pub fn parse(self, input: &str) -> Result<Vec<CaptionSegment>, SubtitleParseError> {
+        match self {
+            Self::Srt => parse_srt(input),
+            Self::WebVtt => parse_webvtt(input),
+            Self::Ass => parse_ass(input),
+        }
+    }
+
+    /// `settings` style the ASS output; SRT and WebVTT carry no styling.
+    pub fn write(self, cues: &[impl CaptionCue], settings: &CaptionSettings) -> String {
+        match self {
+            Self::Srt => write_srt(cues),
+            Self::WebVtt => write_webvtt(cues),
+            Self::Ass => write_ass(cues, settings),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SubtitleParseError {
+    MissingWebVttHeader,
+    MissingAssEvents,
+    /// `line` is 1-based.
+    InvalidTiming {
+        line: usize,
+        value: String,
+    },
+    MissingTiming {
+        line: usize,
+    },
+}
+
+impl fmt::Display for SubtitleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingWebVttHeader => write!(f, "WebVTT file doesn't start with WEBVTT"),
+            Self::MissingAssEvents => {
+                write!(f, "ASS file has no [Events] section with a Format line")
+            }
+            Self::InvalidTiming { line, value } => {
+                write!(f, "line {line}: invalid cue timing {value:?}")
+            }
+            Self::MissingTiming { line } => write!(f, "line {line}: cue has no timing line"),
+        }
+    }
+}
+
+impl std::error::Error for SubtitleParseError {}
+
+impl CaptionsData {
+    /// Source-time captions read from a subtitle file. The cues are taken to already be in source
+    /// time; see [`CaptionsData::retime_from_output`] for files timed against an edited export.
+    pub fn from_subtitles(
+        input: &str,
+        format: SubtitleFormat,
+        settings: CaptionSettings,
+    ) -> Result<Self, SubtitleParseError> {
+        Ok(Self {
+            segments: format.parse(input)?,
+            settings,
+            source_timed: true,
+        })
+    }
+
+    pub fn to_subtitles(&self, format: SubtitleFormat) -> String {
+        format.write(&self.segments, &self.settings)
+    }
+}
+
+pub fn write_srt(cues: &[impl CaptionCue]) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.start(), ','),
+            format_timestamp(cue.end(), ','),
+            cue.text().trim()
+        );
+    }
+    srt
+}
+
+/// WebVTT with per-word timings carried as inline cue timestamps
+/// (`Hello <00:00:01.200>world`), which players use for karaoke-style highlighting.
+pub fn write_webvtt(cues: &[impl CaptionCue]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for (i, cue) in cues.iter().enumerate() {
+        let _ = write!(
+            vtt,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.start(), '.'),
+            format_timestamp(cue.end(), '.'),
+            webvtt_cue_text(cue)
+        );
+    }
+    vtt
+}
+
+/// A cue's WebVTT payload, with inline timestamps before every word after the first.
+pub fn webvtt_cue_text(cue: &impl CaptionCue) -> String {
+    let words = cue
+        .words()
+        .iter()
+        .filter(|w| !w.text.trim().is_empty())
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return escape_vtt(cue.text().trim());
+    }
+
+    let mut text = String::new();
+    for (i, word) in words.iter().enumerate() {
+        let start = f64::from(word.start);
+        if i > 0 {
+            text.push(' ');
+            // Timestamps must fall strictly inside the cue and never go backwards.
+            if start > cue.start() && start < cue.end() {
+                let _ = write!(text, "<{}>", format_timestamp(start, '.'));
+            }
+        }
+        text.push_str(&escape_vtt(word.text.trim()));
+    }
+    text
+}
+
+/// ASS at 1080p with a single style built from `settings`. Word timings become `\k` karaoke tags,
+/// so players that support them highlight each word as it is spoken.
+pub fn write_ass(cues: &[impl CaptionCue], settings: &CaptionSettings) -> String {
+    let font = match settings.font.as_str() {
+        "System Sans-Serif" => "Arial",
+        "System Serif" => "Times New Roman",
+        "System Monospace" => "Courier New",
+        font => font,
+    };
+    let alignment = match settings.position.as_str() {
+        "top-left" => 7,
+        "top-center" | "top" => 8,
+        "top-right" => 9,
+        "bottom-left" => 1,
+        "bottom-right" => 3,
+        _ => 2,
+    };
+    let background_alpha = ((100 - settings.background_opacity.min(100)) * 255 / 100) as u8;
+    let (border_style, outline) = if settings.background_opacity > 0 {
+        (3, 0)
+    } else if settings.outline {
+        (1, 2)
+    } else {
+        (1, 0)
+    };
+
+    let mut ass = String::from(
+        "[Script Info]\nScriptType: v4.00+\nPlayResX: 1920\nPlayResY: 1080\nScaledBorderAndShadow: yes\n\n",
+    );
+    ass.push_str(
+        "[V4+ Styles]\nFormat: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n",
+    );
+    let _ = writeln!(
+        ass,
+        "Style: Default,{font},{},{},{},{},{},{},{},0,0,100,100,0,0,{border_style},{outline},0,{alignment},60,60,60,1",
+        settings.size,
+        ass_colour(&settings.highlight_color, 0),
+        ass_colour(&settings.color, 0),
+        ass_colour(&settings.outline_color, 0),
+        ass_colour(&settings.background_color, background_alpha),
+        if settings.font_weight >= 700 { -1 } else { 0 },
+        if settings.italic { -1 } else { 0 },
+    );
+
+    ass.push_str("\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n");
+    for cue in cues {
+        let _ = writeln!(
+            ass,
+            "Dialogue: 0,{},{},Default,,0,0,0,,{}",
+            format_ass_timestamp(cue.start()),
+            format_ass_timestamp(cue.end()),
+            ass_cue_text(cue)
+        );
+    }
+    ass
+}
+
+fn ass_cue_text(cue: &impl CaptionCue) -> String {
+    let words = cue
+        .words()
+        .iter()
+        .filter(|w| !w.text.trim().is_empty())
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return escape_ass(cue.text().trim());
+    }
+
+    let centis = |seconds: f64| (seconds.max(0.0) * 100.0).round() as u64;
+    let mut text = String::new();
+    let lead_in = centis(f64::from(words[0].start) - cue.start());
+    if lead_in > 0 {
+        let _ = write!(text, "{{\\k{lead_in}}}");
+    }
+    for (i, word) in words.iter().enumerate() {
+        let next = words
+            .get(i + 1)
+            .map_or(cue.end(), |next| f64::from(next.start));
+        if i > 0 {
+            text.push(' ');
+        }
+        let _ = write!(
+            text,
+            "{{\\k{}}}{}",
+            centis(next - f64::from(word.start)),
+            escape_ass(word.text.trim())
+        );
+    }
+    text
+}
+
+/// `&HAABBGGRR` from `#RRGGBB`; ASS alpha counts up from opaque.
+fn ass_colour(hex: &str, alpha: u8) -> String {
+    let hex = hex.trim_start_matches('#');
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .unwrap_or(0)
+    };
+    format!(
+        "&H{alpha:02X}{:02X}{:02X}{:02X}",
+        channel(4),
+        channel(2),
+        channel(0)
+    )
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace("-->", "--&gt;")
+}
+
+/// ASS has no escape for override braces, so they become parentheses.
+fn escape_ass(text: &str) -> String {
+    text.replace('{', "(")
+        .replace('}', ")")
+        .replace('\n', "\\N")
+}
+
+pub(crate) fn format_timestamp(seconds: f64, millis_separator: char) -> String {
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis / 60_000) % 60;
+    let secs = (total_millis / 1000) % 60;
+    let millis = total_millis % 1000;
+    format!("{hours:02}:{minutes:02}:{secs:02}{millis_separator}{millis:03}")
+}
+
+fn format_ass_timestamp(seconds: f64) -> String {
+    let total_centis = (seconds.max(0.0) * 100.0).round() as u64;
+    let hours = total_centis / 360_000;
+    let minutes = (total_centis / 6000) % 60;
+    let secs = (total_centis / 100) % 60;
+    let centis = total_centis % 100;
+    format!("{hours}:{minutes:02}:{secs:02}.{centis:02}")
+}
+
+/// `[hh:]mm:ss[.,]fff` as SRT, WebVTT and ASS write it, in seconds.
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let mut parts = value.trim().rsplitn(3, ':');
+    let seconds = parts.next()?.replace(',', ".");
+    let minutes = parts.next()?;
+    let hours = parts.next().unwrap_or("0");
+
+    if !seconds.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let seconds = seconds.parse::<f64>().ok()?;
+    let minutes = minutes.parse::<u64>().ok()?;
+    let hours = hours.parse::<u64>().ok()?;
+    Some(hours as f64 * 3600.0 + minutes as f64 * 60.0 + seconds)
+}
+
+/// Parses `start --> end`, ignoring any WebVTT cue settings after the end time.
+fn parse_timing_line(line: &str, line_number: usize) -> Result<(f64, f64), SubtitleParseError> {
+    let invalid = || SubtitleParseError::InvalidTiming {
+        line: line_number,
+        value: line.to_string(),
+    };
+    let (start, rest) = line.split_once("-->").ok_or_else(invalid)?;
+    let end = rest.split_whitespace().next().ok_or_else(invalid)?;
+    let start = parse_timestamp(start).ok_or_else(invalid)?;
+    let end = parse_timestamp(end).ok_or_else(invalid)?;
+    Ok((start, end.max(start)))
+}
+
+/// Blank-line separated blocks, each as its lines paired with 1-based line numbers.
+fn blocks(input: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut blocks = Vec::new();
+    let mut current = Vec::new();
+    for (index, line) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push((index + 1, line));
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+pub fn parse_srt(input: &str) -> Result<Vec<CaptionSegment>, SubtitleParseError> {
+    let mut segments = Vec::new();
+    for block in blocks(input) {
+        let timing = block
+            .iter()
+            .position(|(_, line)| line.contains("-->"))
+            .ok_or(SubtitleParseError::MissingTiming { line: block[0].0 })?;
+        let (line_number, timing_line) = block[timing];
+        let (start, end) = parse_timing_line(timing_line, line_number)?;
+
+        let text = block[timing + 1..]
+            .iter()
+            .map(|(_, line)| strip_markup(line))
+            .collect::<Vec<_>>()
+            .join(" ");
+        push_segment(&mut segments, start, end, vec![(start, text)]);
+    }
+    Ok(segments)
+}
+
+/// Inline `<hh:mm:ss.mmm>` timestamps become word timings; other tags and cue settings are
+/// dropped. NOTE, STYLE and REGION blocks are skipped.
+pub fn parse_webvtt(input: &str) -> Result<Vec<CaptionSegment>, SubtitleParseError> {
+    let blocks = blocks(input);
+    let is_webvtt = blocks.first().is_some_and(|block| {
+        block[0]
+            .1
+            .strip_prefix("WEBVTT")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+    });
+    if !is_webvtt {
+        return Err(SubtitleParseError::MissingWebVttHeader);
+    }
+
+    let mut segments = Vec::new();
+    for block in &blocks[1..] {
+        let Some(timing) = block.iter().position(|(_, line)| line.contains("-->")) else {
+            let first = block[0].1;
+            if ["NOTE", "STYLE", "REGION"]
+                .iter()
+                .any(|keyword| first.starts_with(keyword))
+            {
+                continue;
+            }
+            return Err(SubtitleParseError::MissingTiming { line: block[0].0 });
+        };
+        let (line_number, timing_line) = block[timing];
+        let (start, end) = parse_timing_line(timing_line, line_number)?;
+
+        let payload = block[timing + 1..]
+            .iter()
+            .map(|(_, line)| *line)
+            .collect::<Vec<_>>()
+            .join(" ");
+        push_segment(&mut segments, start, end, webvtt_chunks(&payload, start));
+    }
+    Ok(segments)
+}
+
+fn webvtt_chunks(payload: &str, cue_start: f64) -> Vec<(f64, String)> {
+    let mut chunks = vec![(cue_start, String::new())];
+    let mut rest = payload;
+    while let Some(open) = rest.find('<') {
+        push_text(&mut chunks, &decode_vtt_entities(&rest[..open]));
+        let Some(close) = rest[open..].find('>') else {
+            rest = &rest[open..];
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        if let Some(time) = parse_timestamp(tag) {
+            chunks.push((time, String::new()));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    push_text(&mut chunks, &decode_vtt_entities(rest));
+    chunks
+}
+
+fn push_text(chunks: &mut [(f64, String)], text: &str) {
+    if let Some((_, last)) = chunks.last_mut() {
+        last.push_str(text);
+    }
+}
+
+fn decode_vtt_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&lrm;", "")
+        .replace("&rlm;", "")
+        .replace("&amp;", "&")
+}
+
+/// Reads `Dialogue` events using the `[Events]` Format line, so fields may come in any order.
+/// `\k`-family karaoke tags become word timings; other override tags are dropped.
+pub fn parse_ass(input: &str) -> Result<Vec<CaptionSegment>, SubtitleParseError> {
+    let mut in_events = false;
+    let mut format: Option<Vec<String>> = None;
+    let mut cues = Vec::new();
+
+    for (index, line) in input.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = Some(
+                fields
+                    .split(',')
+                    .map(|field| field.trim().to_ascii_lowercase())
+                    .collect(),
+            );
+        } else if let Some(fields) = line.strip_prefix("Dialogue:") {
+            let format = format
+                .as_ref()
+                .ok_or(SubtitleParseError::MissingAssEvents)?;
+            let values = fields
+                .trim_start()
+                .splitn(format.len(), ',')
+                .collect::<Vec<_>>();
+            let field = |name: &str| {
+                format
+                    .iter()
+                    .position(|f| f == name)
+                    .and_then(|i| values.get(i))
+                    .copied()
+            };
+
+            let line_number = index + 1;
+            let timestamp = |name: &str| {
+                field(name).and_then(parse_timestamp).ok_or_else(|| {
+                    SubtitleParseError::InvalidTiming {
+                        line: line_number,
+                        value: line.to_string(),
+                    }
+                })
+            };
+            let start = timestamp("start")?;
+            let end = timestamp("end")?.max(start);
+            let text = field("text").unwrap_or_default();
+            cues.push((start, end, ass_chunks(text, start)));
+        }
+    }
+
+    if format.is_none() {
+        return Err(SubtitleParseError::MissingAssEvents);
+    }
+
+    // Events don't have to be in time order.
+    cues.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut segments = Vec::new();
+    for (start, end, chunks) in cues {
+        push_segment(&mut segments, start, end, chunks);
+    }
+    Ok(segments)
+}
+
+fn ass_chunks(text: &str, cue_start: f64) -> Vec<(f64, String)> {
+    let mut chunks = vec![(cue_start, String::new())];
+    let mut time = cue_start;
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        push_text(&mut chunks, &decode_ass_text(&rest[..open]));
+        let Some(close) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let block = &rest[open + 1..open + close];
+        if let Some(centis) = karaoke_duration(block) {
+            chunks.push((time, String::new()));
+            time += centis as f64 / 100.0;
+        }
+        rest = &rest[open + close + 1..];
+    }
+    push_text(&mut chunks, &decode_ass_text(rest));
+    chunks
+}
+
+/// The duration, in centiseconds, of a `\k`, `\K`, `\kf` or `\ko` tag in an override block.
+fn karaoke_duration(block: &str) -> Option<u64> {
+    block.split('\\').find_map(|tag| {
+        let value = tag
+            .strip_prefix("kf")
+            .or_else(|| tag.strip_prefix("ko"))
+            .or_else(|| tag.strip_prefix('k'))
+            .or_else(|| tag.strip_prefix('K'))?;
+        value.trim().parse().ok()
+    })
+}
+
+fn decode_ass_text(text: &str) -> String {
+    text.replace("\\N", " ")
+        .replace("\\n", " ")
+        .replace("\\h", " ")
+}
+
+/// Drops the `<i>`-style tags and `{\an8}`-style overrides SRT files commonly carry.
+fn strip_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    loop {
+        let next = rest.find(['<', '{']);
+        let Some(open) = next else {
+            text.push_str(rest);
+            break;
+        };
+        let closing = if rest[open..].starts_with('<') {
+            '>'
+        } else {
+            '}'
+        };
+        let is_tag = rest[open + 1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '\\');
+        match rest[open..].find(closing) {
+            Some(close) if is_tag => {
+                text.push_str(&rest[..open]);
+                rest = &rest[open + close + 1..];
+            }
+            _ => {
+                text.push_str(&rest[..=open]);
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    text
+}
+
+/// Builds a caption from text chunks that each start at a known time. Words in a chunk share the
+/// time until the next chunk in proportion to their length, so a cue without word timings still
+/// gets usable ones for highlighting and filler cuts. A chunk that doesn't start with whitespace
+/// continues the previous word, as karaoke syllables do.
+fn push_segment(
+    segments: &mut Vec<CaptionSegment>,
+    start: f64,
+    end: f64,
+    chunks: Vec<(f64, String)>,
+) {
+    let mut words: Vec<CaptionWord> = Vec::new();
+    let mut continues_word = false;
+
+    for (index, (chunk_start, text)) in chunks.iter().enumerate() {
+        let chunk_start = chunk_start.clamp(start, end);
+        let chunk_end = chunks
+            .get(index + 1)
+            .map_or(end, |(next, _)| next.clamp(chunk_start, end));
+
+        let starts_with_space = text.starts_with(char::is_whitespace);
+        let tokens = text.split_whitespace().collect::<Vec<_>>();
+        let total_len = tokens.iter().map(|t| t.chars().count()).sum::<usize>();
+        let mut time = chunk_start;
+
+        for (token_index, token) in tokens.iter().enumerate() {
+            let share = if total_len == 0 {
+                0.0
+            } else {
+                (chunk_end - chunk_start) * token.chars().count() as f64 / total_len as f64
+            };
+            let token_end = time + share;
+
+            let joins_previous = (token_index == 0 && continues_word && !starts_with_space)
+                || caption_token_attaches_to_previous(token);
+            match words.last_mut() {
+                Some(previous) if joins_previous => {
+                    previous.text.push_str(token);
+                    previous.end = token_end as f32;
+                }
+                _ => words.push(CaptionWord {
+                    text: token.to_string(),
+                    start: time as f32,
+                    end: token_end as f32,
+                }),
+            }
+            time = token_end;
+        }
+
+        if !tokens.is_empty() {
+            continues_word = !text.ends_with(char::is_whitespace);
+        } else if !text.is_empty() {
+            continues_word = false;
+        }
+    }
+
+    if words.is_empty() {
+        return;
+    }
+
+    segments.push(CaptionSegment {
+        id: format!("segment-{}", segments.len()),
+        start: start as f32,
+        end: end as f32,
+        text: crate::caption_text_from_words(&words),
+        words,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(
+        start: f64,
+        end: f64,
+        text: &str,
+        words: &[(&str, f32, f32)],
+    ) -> CaptionTrackSegment {
+        CaptionTrackSegment {
+            id: format!("{start}"),
+            start,
+            end,
+            text: text.to_string(),
+            words: words
+                .iter()
+                .map(|(text, start, end)| CaptionWord {
+                    text: text.to_string(),
+                    start: *start,
+                    end: *end,
+                })
+                .collect(),
+            fade_duration_override: None,
+            linger_duration_override: None,
+            position_override: None,
+            color_override: None,
+            background_color_override: None,
+            font_size_override: None,
+        }
+    }
+
+    fn caption(start: f32, end: f32, words: &[(&str, f32)]) -> CaptionSegment {
+        let words = words
+            .iter()
+            .enumerate()
+            .map(|(i, (text, word_start))| CaptionWord {
+                text: text.to_string(),
+                start: *word_start,
+                end: words.get(i + 1).map_or(end, |(_, next)| *next),
+            })
+            .collect::<Vec<_>>();
+        CaptionSegment {
+            id: String::new(),
+            start,
+            end,
+            text: crate::caption_text_from_words(&words),
+            words,
+        }
+    }
+
+    fn word_starts(segments: &[CaptionSegment]) -> Vec<Vec<(&str, f32)>> {
+        segments
+            .iter()
+            .map(|s| s.words.iter().map(|w| (w.text.as_str(), w.start)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn srt_uses_comma_millis_and_sequential_indices() {
+        let srt = write_srt(&[
+            segment(0.0, 1.5, " Hello ", &[]),
+            segment(3661.25, 3662.0, "World", &[]),
+        ]);
+
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,500\nHello\n\n2\n01:01:01,250 --> 01:01:02,000\nWorld\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_keeps_word_timings_as_cue_timestamps() {
+        let vtt = write_webvtt(&[segment(
+            1.0,
+            2.5,
+            "Hi there you",
+            &[("Hi", 1.0, 1.3), ("there", 1.4, 1.8), ("you", 1.9, 2.4)],
+        )]);
+
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n1\n00:00:01.000 --> 00:00:02.500\nHi <00:00:01.400>there <00:00:01.900>you\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_escapes_markup_characters() {
+        let vtt = write_webvtt(&[segment(0.0, 1.0, "a <b> & c", &[])]);
+        assert!(vtt.contains("a &lt;b&gt; &amp; c"));
+    }
+
+    #[test]
+    fn timestamps_round_instead_of_truncating() {
+        assert_eq!(format_timestamp(0.9999, ','), "00:00:01,000");
+        assert_eq!(format_timestamp(59.9994, '.'), "00:00:59.999");
+    }
+
+    #[test]
+    fn srt_round_trips_cues() {
+        let captions = vec![
+            caption(0.5, 2.0, &[("Hello", 0.5), ("there", 1.25)]),
+            caption(3661.25, 3662.0, &[("Again", 3661.25)]),
+        ];
+
+        let parsed = parse_srt(&write_srt(&captions)).unwrap();
+
+        assert_eq!(
+            parsed
+                .iter()
+                .map(|s| (s.id.as_str(), s.text.as_str(), s.start, s.end))
+                .collect::<Vec<_>>(),
+            vec![
+                ("segment-0", "Hello there", 0.5, 2.0),
+                ("segment-1", "Again", 3661.25, 3662.0)
+            ]
+        );
+        // Without word timings, the cue's time is shared out by word length.
+        assert_eq!(parsed[0].words[1].start, 1.25);
+        assert_eq!(parsed[0].words[1].end, 2.0);
+    }
+
+    #[test]
+    fn webvtt_round_trips_word_timings() {
+        let captions = vec![
+            caption(1.0, 2.5, &[("Hi", 1.0), ("there", 1.375), ("you", 1.875)]),
+            caption(4.0, 5.0, &[("a", 4.0), ("<b>", 4.5)]),
+        ];
+
+        let parsed = parse_webvtt(&write_webvtt(&captions)).unwrap();
+
+        assert_eq!(word_starts(&parsed), word_starts(&captions));
+        assert_eq!(parsed[0].words[2].end, 2.5);
+        assert_eq!(parsed[1].text, "a <b>");
+    }
+
+    #[test]
+    fn ass_round_trips_karaoke_timings() {
+        let captions = vec![
+            caption(1.25, 3.0, &[("One", 1.5), ("two,", 2.0), ("three", 2.5)]),
+            caption(10.0, 11.0, &[("{braces}", 10.0)]),
+        ];
+
+        let ass = write_ass(&captions, &CaptionSettings::default());
+        let parsed = parse_ass(&ass).unwrap();
+
+        assert!(ass.contains("Dialogue: 0,0:00:01.25,0:00:03.00,Default,,0,0,0,,{\\k25}{\\k50}One {\\k50}two, {\\k50}three"));
+        assert_eq!(parsed[0].start, 1.25);
+        assert_eq!(word_starts(&parsed)[0], word_starts(&captions)[0]);
+        assert_eq!(parsed[1].text, "(braces)");
+    }
+
+    #[test]
+    fn webvtt_skips_metadata_blocks_and_cue_settings() {
+        let vtt = "\u{feff}WEBVTT - transcript\r\n\r\nNOTE reviewed by editor\r\n\r\nSTYLE\r\n::cue { color: red }\r\n\r\nintro\r\n00:01.000 --> 00:02.000 align:start line:0\r\n<v Sam>Hello <c.loud>there</c>&amp; bye\r\n";
+
+        let parsed = parse_webvtt(vtt).unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].text, "Hello there& bye");
+        assert_eq!((parsed[0].start, parsed[0].end), (1.0, 2.0));
+    }
+
+    #[test]
+    fn srt_strips_formatting_and_joins_lines() {
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\n{\\an8}<i>First line</i>\nsecond line\n\n";
+
+        let parsed = parse_srt(srt).unwrap();
+
+        assert_eq!(parsed[0].text, "First line second line");
+    }
+
+    #[test]
+    fn ass_reads_fields_by_format_and_joins_syllables() {
+        let ass = "[Script Info]\nTitle: x\n\n[Events]\nFormat: Layer, Start, End, Style, Text\nDialogue: 0,0:00:02.00,0:00:03.00,Default,{\\k20}Hel{\\k30}lo\\N{\\kf50} world, again!\nDialogue: 0,0:00:00.50,0:00:01.00,Default,{\\i1}Earlier{\\i0}\n";
+
+        let parsed = parse_ass(ass).unwrap();
+
+        assert_eq!(parsed[0].text, "Earlier");
+        assert_eq!(parsed[1].text, "Hello world, again!");
+        assert_eq!(
+            word_starts(&parsed)[1],
+            vec![("Hello", 2.0), ("world,", 2.5), ("again!", 2.75)]
+        );
+    }
+
+    #[test]
+    fn invalid_timings_report_their_line() {
+        assert_eq!(
+            parse_srt("1\n00:00:01,000 --> soon\nHi\n").unwrap_err(),
+            SubtitleParseError::InvalidTiming {
+                line: 2,
+                value: "00:00:01,000 --> soon".to_string()
+            }
+        );
+        assert_eq!(
+            parse_webvtt("1\n00:00:01.000 --> 00:00:02.000\nHi\n").unwrap_err(),
+            SubtitleParseError::MissingWebVttHeader
+        );
+    }
+}
@@ -0,0 +1,542 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    CursorEvents, GlideDirection, KeyboardEvents, RecordingMeta, StudioRecordingMeta,
+    TimelineConfiguration, XY, ZoomMode, ZoomSegment, keyboard::is_modifier_key,
+};
+
+/// Seconds of lead-in before the activity that triggered a zoom.
+const PRE_PADDING: f64 = 0.4;
+/// Seconds the zoom holds after the activity ends.
+const POST_PADDING: f64 = 1.2;
+/// Suggestions closer together than this are merged into one zoom.
+const MERGE_GAP: f64 = 1.0;
+const MIN_ZOOM_DURATION: f64 = 1.5;
+/// A long dwell is usually the presenter talking, not pointing; only its start is zoomed.
+const MAX_DWELL_DURATION: f64 = 8.0;
+/// Cursor nudges needed inside a dwell region, so an idle cursor doesn't count as attention.
+const MIN_DWELL_MOVES: usize = 3;
+const TYPING_GAP: f64 = 1.2;
+
+/// Tunes [`suggest_zoom_segments`]. `sensitivity` runs from 0 (only the most deliberate activity
+/// is zoomed) to 1 (single clicks, short dwells and a few keystrokes are enough).
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ZoomSuggestionSettings {
+    pub sensitivity: f64,
+    pub amount: f64,
+    pub clicks: bool,
+    pub dwell: bool,
+    pub typing: bool,
+}
+
+impl Default for ZoomSuggestionSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.5,
+            amount: 2.0,
+            clicks: true,
+            dwell: true,
+            typing: true,
+        }
+    }
+}
+
+impl ZoomSuggestionSettings {
+    fn sensitivity(&self) -> f64 {
+        self.sensitivity.clamp(0.0, 1.0)
+    }
+
+    /// Longest pause, in seconds, between clicks of one cluster.
+    fn click_gap(&self) -> f64 {
+        0.8 + 1.2 * self.sensitivity()
+    }
+
+    fn min_clicks(&self) -> usize {
+        match self.sensitivity() {
+            s if s >= 0.6 => 1,
+            s if s >= 0.3 => 2,
+            _ => 3,
+        }
+    }
+
+    fn dwell_duration(&self) -> f64 {
+        3.0 - 2.0 * self.sensitivity()
+    }
+
+    /// Dwell region radius, as a fraction of the display.
+    fn dwell_radius(&self) -> f64 {
+        0.03 + 0.04 * self.sensitivity()
+    }
+
+    fn min_keys(&self) -> usize {
+        (14.0 - 10.0 * self.sensitivity()).round() as usize
+    }
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum ZoomSuggestionReason {
+    Clicks,
+    Dwell,
+    Typing,
+}
+
+#[derive(Type, Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ZoomSuggestion {
+    pub segment: ZoomSegment,
+    pub reasons: Vec<ZoomSuggestionReason>,
+    /// How strongly the activity cleared the sensitivity thresholds; 1 is just enough.
+    pub score: f64,
+    /// Where the activity happened, as a fraction of the display.
+    pub focus: XY<f64>,
+}
+
+impl ZoomSuggestion {
+    pub fn overlaps(&self, segments: &[ZoomSegment]) -> bool {
+        segments
+            .iter()
+            .any(|s| s.start < self.segment.end && self.segment.start < s.end)
+    }
+}
+
+/// The cursor and keyboard input captured for one recording segment, timed in milliseconds
+/// from that segment's start.
+#[derive(Debug, Clone, Default)]
+pub struct SegmentActivity {
+    pub cursor: CursorEvents,
+    pub keyboard: KeyboardEvents,
+}
+
+impl SegmentActivity {
+    /// One entry per recording segment, in order. Instant recordings have no input events.
+    pub fn load(meta: &RecordingMeta) -> Vec<Self> {
+        match meta.studio_meta() {
+            Some(StudioRecordingMeta::SingleSegment { segment }) => vec![Self {
+                cursor: segment
+                    .cursor
+                    .as_ref()
+                    .and_then(|path| CursorEvents::load_from_file(&meta.path(path)).ok())
+                    .unwrap_or_default(),
+                keyboard: KeyboardEvents::default(),
+            }],
+            Some(StudioRecordingMeta::MultipleSegments { inner, .. }) => inner
+                .segments
+                .iter()
+                .map(|segment| Self {
+                    cursor: segment.cursor_events(meta),
+                    keyboard: segment.keyboard_events(meta),
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+struct Activity {
+    start: f64,
+    end: f64,
+    score: f64,
+    reasons: Vec<ZoomSuggestionReason>,
+    focus: XY<f64>,
+}
+
+/// Proposes zoom segments from click clusters, places the cursor settles on, and bursts of
+/// typing. `activity` is indexed by recording segment; events cut out of `timeline` are ignored
+/// and the rest are mapped onto its output time. Existing zoom segments are not consulted, see
+/// [`apply_zoom_suggestions`].
+pub fn suggest_zoom_segments(
+    activity: &[SegmentActivity],
+    timeline: &TimelineConfiguration,
+    settings: &ZoomSuggestionSettings,
+) -> Vec<ZoomSuggestion> {
+    let duration = timeline.duration();
+    if duration <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut moves = Vec::new();
+    let mut clicks = Vec::new();
+    let mut keys = Vec::new();
+    for (clip, segment) in activity.iter().enumerate() {
+        let clip = clip as u32;
+        for event in &segment.cursor.moves {
+            moves.extend(
+                output_times(timeline, clip, event.time_ms / 1000.0)
+                    .map(|time| (time, XY::new(event.x, event.y))),
+            );
+        }
+        for event in segment.cursor.clicks.iter().filter(|e| e.down) {
+            clicks.extend(output_times(timeline, clip, event.time_ms / 1000.0));
+        }
+        for event in &segment.keyboard.presses {
+            if event.down && !is_modifier_key(&event.key) {
+                keys.extend(output_times(timeline, clip, event.time_ms / 1000.0));
+            }
+        }
+    }
+    moves.sort_by(|a, b| a.0.total_cmp(&b.0));
+    clicks.sort_by(f64::total_cmp);
+    keys.sort_by(f64::total_cmp);
+
+    let mut found = Vec::new();
+    if settings.clicks {
+        found.extend(
+            bursts(&clicks, settings.click_gap(), settings.min_clicks()).map(
+                |(start, end, count)| Activity {
+                    start,
+                    end,
+                    score: count as f64 / settings.min_clicks() as f64,
+                    reasons: vec![ZoomSuggestionReason::Clicks],
+                    focus: mean_position(&moves, &clicks, start, end),
+                },
+            ),
+        );
+    }
+    if settings.dwell {
+        found.extend(dwells(&moves, settings));
+    }
+    if settings.typing {
+        found.extend(
+            bursts(&keys, TYPING_GAP, settings.min_keys()).map(|(start, end, count)| Activity {
+                start,
+                end,
+                score: count as f64 / settings.min_keys() as f64,
+                reasons: vec![ZoomSuggestionReason::Typing],
+                focus: position_at(&moves, start),
+            }),
+        );
+    }
+
+    merge(found, duration)
+        .into_iter()
+        .map(|activity| ZoomSuggestion {
+            segment: ZoomSegment {
+                start: round_ms(activity.start),
+                end: round_ms(activity.end),
+                amount: settings.amount,
+                mode: ZoomMode::Auto,
+                glide_direction: GlideDirection::None,
+                glide_speed: ZoomSegment::default_glide_speed(),
+                instant_animation: false,
+                edge_snap_ratio: ZoomSegment::default_edge_snap_ratio(),
+            },
+            reasons: activity.reasons,
+            score: activity.score,
+            focus: activity.focus,
+        })
+        .collect()
+}
+
+/// Adds the suggestions that don't overlap a zoom segment already on `timeline`, keeping the
+/// zoom track sorted. Returns how many were added.
+pub fn apply_zoom_suggestions(
+    timeline: &mut TimelineConfiguration,
+    suggestions: &[ZoomSuggestion],
+) -> usize {
+    let mut added = 0;
+    for suggestion in suggestions {
+        if !suggestion.overlaps(&timeline.zoom_segments) {
+            timeline.zoom_segments.push(suggestion.segment.clone());
+            added += 1;
+        }
+    }
+    timeline
+        .zoom_segments
+        .sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    added
+}
+
+/// Every point on the output timeline that shows `time` seconds into recording segment `clip`.
+fn output_times(
+    timeline: &TimelineConfiguration,
+    clip: u32,
+    time: f64,
+) -> impl Iterator<Item = f64> {
+    timeline
+        .segments
+        .iter()
+        .scan(0.0, |offset, segment| {
+            let start = *offset;
+            *offset += segment.duration();
+            Some((start, segment))
+        })
+        .filter(move |(_, segment)| {
+            segment.recording_clip == clip && time >= segment.start && time < segment.end
+        })
+        .map(move |(offset, segment)| offset + (time - segment.start) / segment.timescale)
+}
+
+/// Runs of sorted `times` no more than `gap` apart with at least `min_count` entries, as
+/// `(first, last, count)`.
+fn bursts(times: &[f64], gap: f64, min_count: usize) -> impl Iterator<Item = (f64, f64, usize)> {
+    let mut runs: Vec<(f64, f64, usize)> = Vec::new();
+    for &time in times {
+        match runs.last_mut() {
+            Some(run) if time - run.1 <= gap => {
+                run.1 = time;
+                run.2 += 1;
+            }
+            _ => runs.push((time, time, 1)),
+        }
+    }
+
+    runs.into_iter()
+        .filter(move |(_, _, count)| *count >= min_count.max(1))
+}
+
+/// Places the cursor arrives at and then hovers around, nudging it, for long enough to suggest
+/// the viewer should look there. The cursor resting where the recording started doesn't count.
+fn dwells(moves: &[(f64, XY<f64>)], settings: &ZoomSuggestionSettings) -> Vec<Activity> {
+    let radius = settings.dwell_radius();
+    let min_duration = settings.dwell_duration();
+    let mut found = Vec::new();
+    let mut anchor = 0;
+
+    for i in 1..=moves.len() {
+        let left = moves
+            .get(i)
+            .is_none_or(|(_, position)| distance(*position, moves[anchor].1) > radius);
+        if !left {
+            continue;
+        }
+
+        let run = &moves[anchor..i];
+        let start = run[0].0;
+        // The cursor leaves at the next move; the last dwell ends with its last nudge.
+        let end = moves.get(i).map_or(run[run.len() - 1].0, |(time, _)| *time);
+        if anchor > 0 && run.len() >= MIN_DWELL_MOVES && end - start >= min_duration {
+            let count = run.len() as f64;
+            found.push(Activity {
+                start,
+                end: end.min(start + MAX_DWELL_DURATION),
+                score: (end - start).min(MAX_DWELL_DURATION) / min_duration,
+                reasons: vec![ZoomSuggestionReason::Dwell],
+                focus: XY::new(
+                    run.iter().map(|(_, p)| p.x).sum::<f64>() / count,
+                    run.iter().map(|(_, p)| p.y).sum::<f64>() / count,
+                ),
+            });
+        }
+        anchor = i;
+    }
+
+    found
+}
+
+/// Pads each activity, merges the ones that touch and clamps them to the timeline.
+fn merge(mut found: Vec<Activity>, duration: f64) -> Vec<Activity> {
+    found.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut merged: Vec<(Activity, f64)> = Vec::new();
+    for mut activity in found {
+        activity.start = (activity.start - PRE_PADDING).max(0.0);
+        activity.end = (activity.end + POST_PADDING).max(activity.start + MIN_ZOOM_DURATION);
+        let score = activity.score;
+
+        match merged.last_mut() {
+            Some((last, best)) if activity.start <= last.end + MERGE_GAP => {
+                last.end = last.end.max(activity.end);
+                last.score += score;
+                for reason in activity.reasons {
+                    if !last.reasons.contains(&reason) {
+                        last.reasons.push(reason);
+                    }
+                }
+                last.reasons.sort();
+                // The merged zoom centres on its strongest activity.
+                if score > *best {
+                    *best = score;
+                    last.focus = activity.focus;
+                }
+            }
+            _ => merged.push((activity, score)),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(mut activity, _)| {
+            activity.end = activity.end.min(duration);
+            activity
+        })
+        .filter(|activity| activity.end - activity.start >= MIN_ZOOM_DURATION / 2.0)
+        .collect()
+}
+
+/// The cursor position at `time`, from moves sorted by time.
+fn position_at(moves: &[(f64, XY<f64>)], time: f64) -> XY<f64> {
+    let index = moves.partition_point(|(t, _)| *t <= time);
+    match index.checked_sub(1).or((!moves.is_empty()).then_some(0)) {
+        Some(index) => moves[index].1,
+        None => XY::new(0.5, 0.5),
+    }
+}
+
+fn mean_position(moves: &[(f64, XY<f64>)], times: &[f64], start: f64, end: f64) -> XY<f64> {
+    let positions = times
+        .iter()
+        .filter(|time| (start..=end).contains(*time))
+        .map(|time| position_at(moves, *time))
+        .collect::<Vec<_>>();
+    let count = positions.len().max(1) as f64;
+
+    XY::new(
+        positions.iter().map(|p| p.x).sum::<f64>() / count,
+        positions.iter().map(|p| p.y).sum::<f64>() / count,
+    )
+}
+
+fn distance(a: XY<f64>, b: XY<f64>) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+fn round_ms(seconds: f64) -> f64 {
+    (seconds * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CursorClickEvent, CursorMoveEvent, KeyPressEvent, TimelineSegment};
+
+    fn timeline(segments: Vec<TimelineSegment>) -> TimelineConfiguration {
+        serde_json::from_value(serde_json::json!({
+            "segments": segments,
+            "zoomSegments": [],
+        }))
+        .unwrap()
+    }
+
+    fn clip(recording_clip: u32, start: f64, end: f64) -> TimelineSegment {
+        TimelineSegment {
+            recording_clip,
+            timescale: 1.0,
+            start,
+            end,
+            ..Default::default()
+        }
+    }
+
+    fn cursor_move(time_ms: f64, x: f64, y: f64) -> CursorMoveEvent {
+        CursorMoveEvent {
+            active_modifiers: vec![],
+            cursor_id: "0".to_string(),
+            time_ms,
+            x,
+            y,
+        }
+    }
+
+    fn click(time_ms: f64) -> CursorClickEvent {
+        CursorClickEvent {
+            active_modifiers: vec![],
+            cursor_num: 0,
+            cursor_id: "0".to_string(),
+            time_ms,
+            down: true,
+        }
+    }
+
+    fn key(time_ms: f64) -> KeyPressEvent {
+        KeyPressEvent {
+            key: "a".to_string(),
+            key_code: "KeyA".to_string(),
+            time_ms,
+            down: true,
+        }
+    }
+
+    #[test]
+    fn clicks_are_mapped_through_cuts_and_clustered() {
+        let mut activity = SegmentActivity::default();
+        activity.cursor.moves = vec![cursor_move(0.0, 0.2, 0.3)];
+        // Two quick clicks at 12s, and one at 5s which the timeline cuts out.
+        activity.cursor.clicks = vec![click(5_000.0), click(12_000.0), click(12_600.0)];
+        let timeline = timeline(vec![clip(0, 0.0, 4.0), clip(0, 10.0, 20.0)]);
+        let settings = ZoomSuggestionSettings {
+            sensitivity: 0.4,
+            ..Default::default()
+        };
+
+        let suggestions = suggest_zoom_segments(&[activity], &timeline, &settings);
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].reasons, vec![ZoomSuggestionReason::Clicks]);
+        assert_eq!(suggestions[0].segment.start, 5.6);
+        assert_eq!(suggestions[0].segment.end, 7.8);
+        assert_eq!(suggestions[0].focus, XY::new(0.2, 0.3));
+    }
+
+    #[test]
+    fn sensitivity_controls_what_counts() {
+        let mut activity = SegmentActivity::default();
+        activity.cursor.clicks = vec![click(3_000.0)];
+        activity.keyboard.presses = (0..6).map(|i| key(10_000.0 + i as f64 * 200.0)).collect();
+        let timeline = timeline(vec![clip(0, 0.0, 30.0)]);
+
+        let strict = ZoomSuggestionSettings {
+            sensitivity: 0.0,
+            ..Default::default()
+        };
+        let eager = ZoomSuggestionSettings {
+            sensitivity: 1.0,
+            ..Default::default()
+        };
+
+        assert!(
+            suggest_zoom_segments(std::slice::from_ref(&activity), &timeline, &strict).is_empty()
+        );
+        let reasons = suggest_zoom_segments(&[activity], &timeline, &eager)
+            .into_iter()
+            .flat_map(|s| s.reasons)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![ZoomSuggestionReason::Clicks, ZoomSuggestionReason::Typing]
+        );
+    }
+
+    #[test]
+    fn dwelling_after_moving_suggests_a_zoom_on_that_spot() {
+        let mut activity = SegmentActivity::default();
+        // Idle at the start, travel across the screen, then hover around (0.8, 0.7) for 3s.
+        activity.cursor.moves = vec![
+            cursor_move(0.0, 0.1, 0.1),
+            cursor_move(4_000.0, 0.5, 0.4),
+            cursor_move(4_500.0, 0.8, 0.7),
+            cursor_move(5_500.0, 0.81, 0.7),
+            cursor_move(6_500.0, 0.8, 0.71),
+            cursor_move(7_500.0, 0.2, 0.2),
+        ];
+        let timeline = timeline(vec![clip(0, 0.0, 20.0)]);
+
+        let suggestions =
+            suggest_zoom_segments(&[activity], &timeline, &ZoomSuggestionSettings::default());
+
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].reasons, vec![ZoomSuggestionReason::Dwell]);
+        assert_eq!(suggestions[0].segment.start, 4.1);
+        assert!((suggestions[0].focus.x - 0.803).abs() < 1e-3);
+    }
+
+    #[test]
+    fn applying_skips_suggestions_that_overlap_existing_zooms() {
+        let mut activity = SegmentActivity::default();
+        activity.cursor.clicks = vec![click(2_000.0), click(15_000.0)];
+        let mut timeline = timeline(vec![clip(0, 0.0, 30.0)]);
+        let settings = ZoomSuggestionSettings {
+            sensitivity: 1.0,
+            ..Default::default()
+        };
+        let suggestions = suggest_zoom_segments(&[activity], &timeline, &settings);
+        timeline.zoom_segments.push(suggestions[1].segment.clone());
+
+        assert_eq!(apply_zoom_suggestions(&mut timeline, &suggestions), 1);
+        assert_eq!(timeline.zoom_segments.len(), 2);
+        assert_eq!(timeline.zoom_segments[0].start, 1.6);
+    }
+}
@@ -1455,6 +1455,7 @@ async fn execute_recording(
         sharing: None,
         inner: RecordingMetaInner::Studio(Box::new(completed.meta)),
         upload: None,
+        chapters: Vec::new(),
     };
     meta.save_for_project()
         .map_err(|e| anyhow::anyhow!("Failed to save recording metadata: {:?}", e))?;
@@ -76,6 +76,7 @@ pub trait MakeCapturePipeline: ScreenCaptureFormat + std::fmt::Debug + 'static {
         output_size: (u32, u32),
         start_time: Timestamps,
         segment_tx: Option<std::sync::mpsc::Sender<SegmentCompletedEvent>>,
+        stream: Option<LiveStream>,
     ) -> anyhow::Result<OutputPipeline>
     where
         Self: Sized;
@@ -205,15 +206,19 @@ impl MakeCapturePipeline for screen_capture::CMSampleBufferCapture {
         output_size: (u32, u32),
         start_time: Timestamps,
         segment_tx: Option<std::sync::mpsc::Sender<SegmentCompletedEvent>>,
+        stream: Option<LiveStream>,
     ) -> anyhow::Result<OutputPipeline> {
         OutputPipeline::builder(segments_dir)
             .with_video::<screen_capture::VideoSource>(screen_capture)
             .with_timestamps(start_time)
-            .build::<MacOSFragmentedM4SMuxer>(MacOSFragmentedM4SMuxerConfig {
-                bpp: H264EncoderBuilder::INSTANT_MODE_BPP,
-                output_size: Some(output_size),
-                segment_tx,
-                ..Default::default()
+            .build::<StreamingMuxer<MacOSFragmentedM4SMuxer>>(StreamingMuxerConfig {
+                inner: MacOSFragmentedM4SMuxerConfig {
+                    bpp: H264EncoderBuilder::INSTANT_MODE_BPP,
+                    output_size: Some(output_size),
+                    segment_tx,
+                    ..Default::default()
+                },
+                stream,
             })
             .await
     }
@@ -339,18 +344,22 @@ impl MakeCapturePipeline for screen_capture::Direct3DCapture {
         output_size: (u32, u32),
         start_time: Timestamps,
         segment_tx: Option<std::sync::mpsc::Sender<SegmentCompletedEvent>>,
+        stream: Option<LiveStream>,
     ) -> anyhow::Result<OutputPipeline> {
         OutputPipeline::builder(segments_dir)
             .with_video::<screen_capture::VideoSource>(screen_capture)
             .with_timestamps(start_time)
-            .build::<WindowsFragmentedM4SMuxer>(WindowsFragmentedM4SMuxerConfig {
-                segment_duration: std::time::Duration::from_secs(2),
-                preset: H264Preset::Ultrafast,
-                bpp: H264EncoderBuilder::INSTANT_MODE_BPP,
-                output_size: Some(output_size),
-                shared_pause_state: None,
-                disk_space_callback: None,
-                segment_tx,
+            .build::<StreamingMuxer<WindowsFragmentedM4SMuxer>>(StreamingMuxerConfig {
+                inner: WindowsFragmentedM4SMuxerConfig {
+                    segment_duration: std::time::Duration::from_secs(2),
+                    preset: H264Preset::Ultrafast,
+                    bpp: H264EncoderBuilder::INSTANT_MODE_BPP,
+                    output_size: Some(output_size),
+                    shared_pause_state: None,
+                    disk_space_callback: None,
+                    segment_tx,
+                },
+                stream,
             })
             .await
     }
@@ -396,15 +405,19 @@ impl MakeCapturePipeline for screen_capture::X11Capture {
         output_size: (u32, u32),
         start_time: Timestamps,
         _segment_tx: Option<std::sync::mpsc::Sender<SegmentCompletedEvent>>,
+        stream: Option<LiveStream>,
     ) -> anyhow::Result<OutputPipeline> {
         OutputPipeline::builder(segments_dir)
             .with_video::<screen_capture::VideoSource>(screen_capture)
             .with_timestamps(start_time)
-            .build::<crate::ffmpeg::SegmentedVideoMuxer>(crate::ffmpeg::SegmentedVideoMuxerConfig {
-                segment_duration: std::time::Duration::from_secs(2),
-                preset: H264Preset::Ultrafast,
-                output_size: Some(output_size),
-                shared_pause_state: None,
+            .build::<StreamingMuxer<crate::ffmpeg::SegmentedVideoMuxer>>(StreamingMuxerConfig {
+                inner: crate::ffmpeg::SegmentedVideoMuxerConfig {
+                    segment_duration: std::time::Duration::from_secs(2),
+                    preset: H264Preset::Ultrafast,
+                    output_size: Some(output_size),
+                    shared_pause_state: None,
+                },
+                stream,
             })
             .await
     }
@@ -474,11 +474,12 @@ fn get_cursor_data() -> Option<CursorData> {
 
 #[cfg(target_os = "linux")]
 fn get_x11_cursor_data() -> Option<CursorData> {
+    use cap_cursor_info::CursorShapeLinux;
     use x11rb::protocol::xfixes::ConnectionExt as _;
 
     let (conn, _) = x11rb::connect(None).ok()?;
     conn.xfixes_query_version(5, 0).ok()?.reply().ok()?;
-    let cursor = conn.xfixes_get_cursor_image().ok()?.reply().ok()?;
+    let cursor = conn.xfixes_get_cursor_image_and_name().ok()?.reply().ok()?;
 
     let width = u32::from(cursor.width);
     let height = u32::from(cursor.height);
@@ -505,18 +506,21 @@ fn get_x11_cursor_data() -> Option<CursorData> {
         .write_to(&mut bytes, image::ImageFormat::Png)
         .ok()?;
 
+    let shape = CursorShapeLinux::from_name(&String::from_utf8_lossy(&cursor.name));
+
     Some(CursorData {
         image: bytes.into_inner(),
         hotspot: XY::new(
             f64::from(cursor.xhot) / f64::from(width),
             f64::from(cursor.yhot) / f64::from(height),
         ),
-        shape: None,
+        shape: shape.map(Into::into),
     })
 }
 
 #[cfg(target_os = "linux")]
 fn fallback_cursor_data() -> Option<CursorData> {
+    use cap_cursor_info::CursorShapeLinux;
     use std::sync::OnceLock;
 
     static CURSOR_PNG: OnceLock<Vec<u8>> = OnceLock::new();
@@ -529,7 +533,7 @@ fn fallback_cursor_data() -> Option<CursorData> {
     Some(CursorData {
         image,
         hotspot: XY::new(0.0, 0.0),
-        shape: None,
+        shape: Some(CursorShapeLinux::Default.into()),
     })
 }
 
@@ -12,7 +12,7 @@ use crate::{
 };
 use anyhow::Context as _;
 use cap_media_info::VideoInfo;
-use cap_project::InstantRecordingMeta;
+use cap_project::{ChapterMarker, InstantRecordingMeta};
 use cap_timestamp::Timestamps;
 use cap_utils::ensure_dir;
 use kameo::{Actor as _, prelude::*};
@@ -23,9 +23,14 @@ use std::{
 };
 use tracing::*;
 
+/// Sub-directories of `content/audio` that hold each source when the microphone and system audio
+/// are recorded as separate tracks, with each track's label, in output track order.
+pub const SEPARATE_AUDIO_TRACKS: [(&str, &str); 2] =
+    [("mic", "Microphone"), ("system", "System audio")];
+
 struct Pipeline {
     video: OutputPipeline,
-    audio: Option<OutputPipeline>,
+    audio: Vec<OutputPipeline>,
     video_info: VideoInfo,
     segments_dir: PathBuf,
     segment_rx:
@@ -89,6 +94,12 @@ impl ActorHandle {
         Ok(self.actor_ref.ask(IsPaused).await?)
     }
 
+    /// Marks the start of a new chapter at the current point of the recording, returning the
+    /// marker as it will be saved in the project's meta.
+    pub async fn add_chapter_marker(&self, title: Option<String>) -> anyhow::Result<ChapterMarker> {
+        Ok(self.actor_ref.ask(AddChapterMarker { title }).await?)
+    }
+
     pub fn take_segment_rx(
         &self,
     ) -> Option<std::sync::mpsc::Receiver<cap_enc_ffmpeg::segmented_stream::SegmentCompletedEvent>>
@@ -117,6 +128,7 @@ pub struct Actor {
     state: ActorState,
     total_pause_duration: std::time::Duration,
     pause_started_at: Option<f64>,
+    chapters: Vec<ChapterMarker>,
 }
 
 impl Actor {
@@ -133,15 +145,16 @@ impl Actor {
         });
 
         if let Some(pipeline) = pipeline {
-            if let Some(audio) = pipeline.audio {
-                let (audio_res, video_res) = tokio::join!(audio.stop(), pipeline.video.stop());
-                if let Err(e) = audio_res {
+            let (audio_res, video_res) = tokio::join!(
+                futures::future::join_all(pipeline.audio.into_iter().map(|audio| audio.stop())),
+                pipeline.video.stop()
+            );
+            for res in audio_res {
+                if let Err(e) = res {
                     warn!("Audio pipeline stop failed: {e:#}");
                 }
-                video_res?;
-            } else {
-                pipeline.video.stop().await?;
             }
+            video_res?;
         }
 
         Ok(())
@@ -212,6 +225,7 @@ impl Message<Stop> for Actor {
             },
             display_source: self.capture_target.clone(),
             health,
+            chapters: std::mem::take(&mut self.chapters),
         })
     }
 }
@@ -230,7 +244,7 @@ impl Message<Pause> for Actor {
             } = state
             {
                 pipeline.video.pause();
-                if let Some(ref audio) = pipeline.audio {
+                for audio in &pipeline.audio {
                     audio.pause();
                 }
                 return ActorState::Paused {
@@ -263,7 +277,7 @@ impl Message<Resume> for Actor {
             } = state
             {
                 pipeline.video.resume();
-                if let Some(ref audio) = pipeline.audio {
+                for audio in &pipeline.audio {
                     audio.resume();
                 }
                 return ActorState::Recording {
@@ -299,12 +313,49 @@ impl Message<IsPaused> for Actor {
     }
 }
 
+pub struct AddChapterMarker {
+    pub title: Option<String>,
+}
+
+impl Message<AddChapterMarker> for Actor {
+    type Reply = anyhow::Result<ChapterMarker>;
+
+    async fn handle(
+        &mut self,
+        msg: AddChapterMarker,
+        _: &mut Context<Self, Self::Reply>,
+    ) -> Self::Reply {
+        let segment_start_time = match &self.state {
+            ActorState::Recording {
+                segment_start_time, ..
+            }
+            | ActorState::Paused {
+                segment_start_time, ..
+            } => *segment_start_time,
+            ActorState::Stopped => anyhow::bail!("Recording no longer active"),
+        };
+
+        // The output skips paused stretches, including one still in progress.
+        let now = current_time_f64();
+        let paused = self.total_pause_duration.as_secs_f64()
+            + self.pause_started_at.map_or(0.0, |start| now - start);
+        let marker = ChapterMarker {
+            time: (now - segment_start_time - paused).max(0.0),
+            title: msg.title,
+        };
+        self.chapters.push(marker.clone());
+
+        Ok(marker)
+    }
+}
+
 #[derive(Debug)]
 pub struct CompletedRecording {
     pub project_path: PathBuf,
     pub display_source: ScreenCaptureTarget,
     pub meta: InstantRecordingMeta,
     pub health: crate::RecordingHealth,
+    pub chapters: Vec<ChapterMarker>,
 }
 
 async fn create_pipeline(
@@ -314,6 +365,8 @@ async fn create_pipeline(
     mic_feed: Option<Arc<MicrophoneFeedLock>>,
     system_audio_source: Option<crate::sources::screen_capture::SystemAudioSourceConfig>,
     max_output_size: Option<u32>,
+    separate_audio_tracks: bool,
+    live_stream: Option<output_pipeline::LiveStreamConfig>,
     start_time: Timestamps,
 ) -> anyhow::Result<Pipeline> {
     let output_resolution = max_output_size
@@ -343,46 +396,85 @@ async fn create_pipeline(
 
     let segment_tx_for_video = segment_channel.as_ref().map(|(tx, _)| tx.clone());
 
+    let has_audio = mic_feed.is_some() || system_audio_source.is_some();
+
+    let stream = live_stream
+        .map(|mut config| {
+            config.output_size.get_or_insert(output_resolution);
+            output_pipeline::LiveStream::spawn(config, has_audio)
+        })
+        .transpose()
+        .context("live stream setup")?;
+    if let Some(stream) = &stream {
+        info!(stream = stream.target(), "Live streaming instant recording");
+    }
+
     let video = ScreenCaptureMethod::make_instant_segmented_video_pipeline(
         screen_capture,
         segments_dir.clone(),
         output_resolution,
         start_time,
         segment_tx_for_video,
+        stream.clone(),
     )
     .await?;
 
-    let has_audio = mic_feed.is_some() || system_audio_source.is_some();
-    let audio = if has_audio {
-        let audio_dir = content_dir.join("audio");
-        let mut builder =
-            output_pipeline::OutputPipeline::builder(audio_dir.clone()).with_timestamps(start_time);
-
-        if let Some(sys_audio) = system_audio_source {
-            builder = builder
-                .with_audio_source::<crate::sources::screen_capture::SystemAudioSource>(sys_audio);
+    let audio_dir = content_dir.join("audio");
+    let audio = match (mic_feed, system_audio_source) {
+        (Some(mic), Some(sys_audio)) if separate_audio_tracks => {
+            // Neither track is mixed into the progressive upload; it only carries the video.
+            let [(mic_dir, _), (system_dir, _)] = SEPARATE_AUDIO_TRACKS;
+            vec![
+                create_audio_track_pipeline::<crate::sources::Microphone>(
+                    audio_dir.join(mic_dir),
+                    mic,
+                    start_time,
+                )
+                .await
+                .context("microphone audio pipeline setup")?,
+                create_audio_track_pipeline::<crate::sources::screen_capture::SystemAudioSource>(
+                    audio_dir.join(system_dir),
+                    sys_audio,
+                    start_time,
+                )
+                .await
+                .context("system audio pipeline setup")?,
+            ]
         }
+        (mic_feed, system_audio_source) if has_audio => {
+            let mut builder = output_pipeline::OutputPipeline::builder(audio_dir.clone())
+                .with_timestamps(start_time);
+
+            if let Some(sys_audio) = system_audio_source {
+                builder = builder
+                    .with_audio_source::<crate::sources::screen_capture::SystemAudioSource>(
+                        sys_audio,
+                    );
+            }
 
-        if let Some(mic) = mic_feed {
-            builder = builder.with_audio_source::<crate::sources::Microphone>(mic);
-        }
+            if let Some(mic) = mic_feed {
+                builder = builder.with_audio_source::<crate::sources::Microphone>(mic);
+            }
 
-        let segment_tx_for_audio = segment_channel.as_ref().map(|(tx, _)| tx.clone());
+            let segment_tx_for_audio = segment_channel.as_ref().map(|(tx, _)| tx.clone());
 
-        let audio_pipeline = builder
-            .build::<output_pipeline::DashSegmentedAudioMuxer>(
-                output_pipeline::DashSegmentedAudioMuxerConfig {
-                    shared_pause_state: None,
-                    segment_tx: segment_tx_for_audio,
-                    ..Default::default()
-                },
-            )
-            .await
-            .context("audio pipeline setup")?;
+            let audio_pipeline = builder
+                .build::<output_pipeline::StreamingMuxer<output_pipeline::DashSegmentedAudioMuxer>>(
+                    output_pipeline::StreamingMuxerConfig {
+                        inner: output_pipeline::DashSegmentedAudioMuxerConfig {
+                            shared_pause_state: None,
+                            segment_tx: segment_tx_for_audio,
+                            ..Default::default()
+                        },
+                        stream,
+                    },
+                )
+                .await
+                .context("audio pipeline setup")?;
 
-        Some(audio_pipeline)
-    } else {
-        None
+            vec![audio_pipeline]
+        }
+        _ => Vec::new(),
     };
 
     let segment_rx = segment_channel.map(|(_, rx)| rx);
@@ -401,6 +493,20 @@ async fn create_pipeline(
     })
 }
 
+async fn create_audio_track_pipeline<TAudio: output_pipeline::AudioSource>(
+    dir: PathBuf,
+    config: TAudio::Config,
+    start_time: Timestamps,
+) -> anyhow::Result<OutputPipeline> {
+    OutputPipeline::builder(dir)
+        .with_timestamps(start_time)
+        .with_audio_source::<TAudio>(config)
+        .build::<output_pipeline::DashSegmentedAudioMuxer>(
+            output_pipeline::DashSegmentedAudioMuxerConfig::default(),
+        )
+        .await
+}
+
 impl Actor {
     pub fn builder(output: PathBuf, capture_target: ScreenCaptureTarget) -> ActorBuilder {
         ActorBuilder::new(output, capture_target)
@@ -415,6 +521,8 @@ pub struct ActorBuilder {
     camera_feed: Option<Arc<crate::feeds::camera::CameraFeedLock>>,
     max_output_size: Option<u32>,
     max_fps: u32,
+    separate_audio_tracks: bool,
+    live_stream: Option<output_pipeline::LiveStreamConfig>,
     #[cfg(target_os = "macos")]
     excluded_windows: Vec<scap_targets::WindowId>,
 }
@@ -429,6 +537,8 @@ impl ActorBuilder {
             camera_feed: None,
             max_output_size: None,
             max_fps: crate::defaults::DEFAULT_INSTANT_MODE_FPS,
+            separate_audio_tracks: false,
+            live_stream: None,
             #[cfg(target_os = "macos")]
             excluded_windows: Vec::new(),
         }
@@ -462,6 +572,21 @@ impl ActorBuilder {
         self
     }
 
+    /// Writes the microphone and system audio as separate, labelled tracks in the output MP4
+    /// instead of mixing them into one. Only applies when both are captured, and can't be combined
+    /// with a live stream. The progressive upload then carries the video without audio.
+    pub fn with_separate_audio_tracks(mut self, separate_audio_tracks: bool) -> Self {
+        self.separate_audio_tracks = separate_audio_tracks;
+        self
+    }
+
+    /// Also pushes the screen recording live to an RTMP/SRT ingest or an HLS playlist while it
+    /// is written to disk. Only screen, window and area targets can be streamed.
+    pub fn with_live_stream(mut self, live_stream: output_pipeline::LiveStreamConfig) -> Self {
+        self.live_stream = Some(live_stream);
+        self
+    }
+
     #[cfg(target_os = "macos")]
     pub fn with_excluded_windows(mut self, excluded_windows: Vec<scap_targets::WindowId>) -> Self {
         self.excluded_windows = excluded_windows;
@@ -486,6 +611,8 @@ impl ActorBuilder {
             },
             self.max_output_size,
             self.max_fps,
+            self.separate_audio_tracks,
+            self.live_stream,
         )
         .await
     }
@@ -497,7 +624,13 @@ pub async fn spawn_instant_recording_actor(
     inputs: RecordingBaseInputs,
     max_output_size: Option<u32>,
     max_fps: u32,
+    separate_audio_tracks: bool,
+    live_stream: Option<output_pipeline::LiveStreamConfig>,
 ) -> anyhow::Result<ActorHandle> {
+    if separate_audio_tracks && live_stream.is_some() {
+        anyhow::bail!("Separate audio tracks can't be combined with live streaming");
+    }
+
     ensure_dir(&recording_dir)?;
 
     let timestamps = Timestamps::now();
@@ -511,6 +644,10 @@ pub async fn spawn_instant_recording_actor(
 
     let (mut pipeline, video_info) = match inputs.capture_target {
         ScreenCaptureTarget::CameraOnly => {
+            if live_stream.is_some() {
+                anyhow::bail!("Live streaming is not supported for camera-only recordings");
+            }
+
             #[cfg(target_os = "linux")]
             {
                 let camera_feed = inputs.camera_feed.clone().ok_or_else(|| {
@@ -541,7 +678,7 @@ pub async fn spawn_instant_recording_actor(
                 (
                     Pipeline {
                         video: cam_pipeline,
-                        audio: None,
+                        audio: Vec::new(),
                         video_info,
                         segments_dir: content_dir.clone(),
                         segment_rx: None,
@@ -598,7 +735,7 @@ pub async fn spawn_instant_recording_actor(
                 (
                     Pipeline {
                         video: cam_pipeline,
-                        audio: None,
+                        audio: Vec::new(),
                         video_info,
                         segments_dir: content_dir.clone(),
                         segment_rx: None,
@@ -651,6 +788,8 @@ pub async fn spawn_instant_recording_actor(
                 inputs.mic_feed.clone(),
                 system_audio_source,
                 max_output_size,
+                separate_audio_tracks,
+                live_stream,
                 timestamps,
             )
             .await?;
@@ -680,6 +819,7 @@ pub async fn spawn_instant_recording_actor(
         },
         total_pause_duration: std::time::Duration::ZERO,
         pause_started_at: None,
+        chapters: Vec::new(),
     });
 
     let actor_handle = ActorHandle {
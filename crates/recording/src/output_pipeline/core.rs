@@ -358,6 +358,20 @@ pub enum PipelineHealthEvent {
     CaptureTargetLost {
         target: String,
     },
+    StreamReconnecting {
+        target: String,
+        attempt: u32,
+        retry_in_ms: u64,
+        reason: String,
+    },
+    StreamReconnected {
+        target: String,
+        attempts: u32,
+    },
+    StreamFailed {
+        target: String,
+        reason: String,
+    },
 }
 
 pub type HealthSender = tokio::sync::mpsc::Sender<PipelineHealthEvent>;
@@ -348,7 +348,7 @@ impl OutOfProcessFragmentedM4SMuxer {
                     media_segment_pattern: FragmentManifestTracker::media_segment_pattern()
                         .to_string(),
                     video_init: Some(video_init),
-                    audio_init: None,
+                    audio_tracks: Vec::new(),
                 };
 
                 let subprocess = match RespawningMuxerSubprocess::new(
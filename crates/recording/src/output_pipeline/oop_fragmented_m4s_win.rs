@@ -339,7 +339,7 @@ impl WindowsOOPFragmentedM4SMuxer {
                     media_segment_pattern: FragmentManifestTracker::media_segment_pattern()
                         .to_string(),
                     video_init: Some(video_init),
-                    audio_init: None,
+                    audio_tracks: Vec::new(),
                 };
 
                 let mut subprocess = match RespawningMuxerSubprocess::new(
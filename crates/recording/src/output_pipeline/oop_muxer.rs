@@ -1,8 +1,10 @@
 use crate::output_pipeline::core::{HealthSender, PipelineHealthEvent, emit_health};
 use anyhow::{Context, Result, anyhow};
 use cap_muxer_protocol::{
-    Frame, InitAudio, InitVideo, PACKET_FLAG_KEYFRAME, Packet, STREAM_INDEX_AUDIO,
-    STREAM_INDEX_VIDEO, StartParams, write_frame,
+    FEATURE_INIT_STREAM, FEATURE_METADATA, FEATURE_STREAM_METADATA, Frame, Hello, HelloAck,
+    InitAudio, InitStream, InitVideo, MAX_AUDIO_TRACKS, MIN_PROTOCOL_VERSION, Metadata,
+    PACKET_FLAG_KEYFRAME, PROTOCOL_VERSION, Packet, STREAM_INDEX_VIDEO, StartParams, StreamMedia,
+    StreamMetadata, audio_stream_index, read_frame, write_frame,
 };
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
@@ -10,7 +12,7 @@ use std::{
     collections::VecDeque,
     io::{BufWriter, Write},
     path::{Path, PathBuf},
-    process::{Child, ChildStderr, ChildStdin, Command, ExitStatus, Stdio},
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Stdio},
     sync::{
         Arc, OnceLock,
         atomic::{AtomicBool, AtomicU64, Ordering},
@@ -24,6 +26,8 @@ pub const ENV_BIN_PATH: &str = "CAP_MUXER_BIN";
 const STDERR_RING_LIMIT: usize = 128;
 const RESPAWN_COOLDOWN: Duration = Duration::from_millis(500);
 const RESPAWN_STABILITY_WINDOW: Duration = Duration::from_secs(5);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+const PARENT_FEATURES: u64 = FEATURE_INIT_STREAM | FEATURE_STREAM_METADATA | FEATURE_METADATA;
 pub const EXIT_DISK_FULL: u8 = 60;
 
 #[cfg(windows)]
@@ -114,6 +118,9 @@ pub struct AudioStreamInit {
     pub sample_format: String,
     pub time_base: (i32, i32),
     pub extradata: Vec<u8>,
+    pub label: Option<String>,
+    /// ISO 639-2 language code. Only muxers that speak protocol v2 record it.
+    pub language: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -122,7 +129,8 @@ pub struct MuxerSubprocessConfig {
     pub init_segment_name: String,
     pub media_segment_pattern: String,
     pub video_init: Option<VideoStreamInit>,
-    pub audio_init: Option<AudioStreamInit>,
+    /// Audio tracks in output order; packets for `audio_tracks[n]` are written with track `n`.
+    pub audio_tracks: Vec<AudioStreamInit>,
 }
 
 impl MuxerSubprocessConfig {
@@ -132,7 +140,7 @@ impl MuxerSubprocessConfig {
     }
 
     pub fn with_audio(mut self, audio: AudioStreamInit) -> Self {
-        self.audio_init = Some(audio);
+        self.audio_tracks.push(audio);
         self
     }
 }
@@ -148,6 +156,7 @@ pub struct MuxerSubprocess {
     packets_written: Arc<AtomicU64>,
     started_at: Instant,
     crash_reported: Arc<AtomicBool>,
+    protocol: HelloAck,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -172,10 +181,36 @@ impl MuxerSubprocess {
         config: MuxerSubprocessConfig,
         health_tx: Option<HealthSender>,
     ) -> Result<Self, MuxerSubprocessError> {
+        match Self::spawn_with_protocol(bin_path.clone(), config.clone(), health_tx.clone(), true)?
+        {
+            Ok(subprocess) => Ok(subprocess),
+            Err(reason) => {
+                warn!(
+                    reason,
+                    "cap-muxer did not answer the protocol handshake; falling back to protocol v1"
+                );
+                Self::spawn_with_protocol(bin_path, config, health_tx, false)?
+                    .map_err(MuxerSubprocessError::ExitDuringInit)
+            }
+        }
+    }
+
+    /// Spawns the muxer and sends its init frames. With `negotiate`, a `Hello` goes first and
+    /// the inner error is the reason the muxer didn't acknowledge it.
+    fn spawn_with_protocol(
+        bin_path: PathBuf,
+        config: MuxerSubprocessConfig,
+        health_tx: Option<HealthSender>,
+        negotiate: bool,
+    ) -> Result<Result<Self, String>, MuxerSubprocessError> {
         let mut command = Command::new(&bin_path);
         command
             .stdin(Stdio::piped())
-            .stdout(Stdio::null())
+            .stdout(if negotiate {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
             .stderr(Stdio::piped())
             .env(
                 "CAP_MUXER_LOG",
@@ -191,6 +226,7 @@ impl MuxerSubprocess {
             .stdin
             .take()
             .ok_or_else(|| MuxerSubprocessError::Spawn(anyhow!("subprocess stdin missing")))?;
+        let stdout = child.stdout.take();
         let stderr = child
             .stderr
             .take()
@@ -217,17 +253,76 @@ impl MuxerSubprocess {
             packets_written: Arc::new(AtomicU64::new(0)),
             started_at: Instant::now(),
             crash_reported: Arc::new(AtomicBool::new(false)),
+            protocol: HelloAck::V1,
         };
 
+        if let Some(stdout) = stdout {
+            match subprocess.handshake(stdout) {
+                Ok(protocol) => subprocess.protocol = protocol,
+                Err(reason) => {
+                    subprocess.kill_quietly();
+                    return Ok(Err(reason));
+                }
+            }
+        }
+
         subprocess.send_init_frames()?;
 
         info!(
             bin_path = %subprocess.bin_path.display(),
             output_dir = %config.output_directory.display(),
+            protocol_version = subprocess.protocol.version,
             "cap-muxer subprocess spawned"
         );
 
-        Ok(subprocess)
+        Ok(Ok(subprocess))
+    }
+
+    /// Sends `Hello` and waits for the muxer's `HelloAck`. A muxer that predates the handshake
+    /// rejects the frame and exits, which closes its stdout.
+    fn handshake(&mut self, stdout: ChildStdout) -> Result<HelloAck, String> {
+        let stdin = self.stdin.as_mut().ok_or("stdin closed")?;
+        write_frame(stdin, &Frame::Hello(Hello::new(PARENT_FEATURES)))
+            .map_err(|e| format!("hello: {e}"))?;
+        stdin
+            .flush()
+            .map_err(|e| format!("flush after hello: {e}"))?;
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::Builder::new()
+            .name("cap-muxer-handshake".into())
+            .spawn(move || {
+                let mut stdout = stdout;
+                let _ = tx.send(read_frame(&mut stdout));
+            })
+            .map_err(|e| format!("spawn handshake reader: {e}"))?;
+
+        match rx.recv_timeout(HANDSHAKE_TIMEOUT) {
+            Ok(Ok(Frame::HelloAck(ack)))
+                if (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&ack.version) =>
+            {
+                Ok(HelloAck {
+                    version: ack.version,
+                    features: ack.features & PARENT_FEATURES,
+                })
+            }
+            Ok(Ok(other)) => Err(format!("unexpected reply {other:?}")),
+            Ok(Err(e)) => Err(format!("reading hello_ack: {e}")),
+            Err(_) => Err(format!("no hello_ack within {HANDSHAKE_TIMEOUT:?}")),
+        }
+    }
+
+    fn kill_quietly(&mut self) {
+        self.stdin = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    /// The protocol version and features agreed with the muxer.
+    pub fn protocol(&self) -> HelloAck {
+        self.protocol
     }
 
     fn send_init_frames(&mut self) -> Result<(), MuxerSubprocessError> {
@@ -236,8 +331,17 @@ impl MuxerSubprocess {
             .as_mut()
             .ok_or_else(|| MuxerSubprocessError::Write(anyhow!("stdin closed")))?;
 
+        if self.config.audio_tracks.len() > MAX_AUDIO_TRACKS as usize {
+            return Err(MuxerSubprocessError::Write(anyhow!(
+                "{} audio tracks exceeds the muxer limit of {MAX_AUDIO_TRACKS}",
+                self.config.audio_tracks.len()
+            )));
+        }
+
+        let init_stream = self.protocol.supports(FEATURE_INIT_STREAM);
+
         if let Some(video) = &self.config.video_init {
-            let frame = Frame::InitVideo(InitVideo {
+            let init = InitVideo {
                 codec: video.codec.clone(),
                 width: video.width,
                 height: video.height,
@@ -247,13 +351,22 @@ impl MuxerSubprocess {
                 time_base_den: video.time_base.1,
                 extradata: video.extradata.clone(),
                 segment_duration_ms: video.segment_duration_ms,
-            });
+            };
+            let frame = if init_stream {
+                Frame::InitStream(InitStream {
+                    stream_index: STREAM_INDEX_VIDEO,
+                    media: StreamMedia::Video(init),
+                    metadata: StreamMetadata::default(),
+                })
+            } else {
+                Frame::InitVideo(init)
+            };
             write_frame(stdin, &frame)
                 .map_err(|e| MuxerSubprocessError::Write(anyhow!("init_video: {e}")))?;
         }
 
-        if let Some(audio) = &self.config.audio_init {
-            let frame = Frame::InitAudio(InitAudio {
+        for (track, audio) in (0u8..).zip(&self.config.audio_tracks) {
+            let init = InitAudio {
                 codec: audio.codec.clone(),
                 sample_rate: audio.sample_rate,
                 channels: audio.channels,
@@ -261,7 +374,22 @@ impl MuxerSubprocess {
                 time_base_num: audio.time_base.0,
                 time_base_den: audio.time_base.1,
                 extradata: audio.extradata.clone(),
-            });
+                label: None,
+            };
+            let frame = match audio_stream_index(track) {
+                Some(stream_index) if init_stream => Frame::InitStream(InitStream {
+                    stream_index,
+                    media: StreamMedia::Audio(init),
+                    metadata: StreamMetadata {
+                        title: audio.label.clone(),
+                        language: audio.language.clone(),
+                    },
+                }),
+                _ => Frame::InitAudio(InitAudio {
+                    label: audio.label.clone(),
+                    ..init
+                }),
+            };
             write_frame(stdin, &frame)
                 .map_err(|e| MuxerSubprocessError::Write(anyhow!("init_audio: {e}")))?;
         }
@@ -313,13 +441,17 @@ impl MuxerSubprocess {
 
     pub fn write_audio_packet(
         &mut self,
+        track: u8,
         pts_time_base_units: i64,
         dts_time_base_units: i64,
         duration_time_base_units: u64,
         data: &[u8],
     ) -> Result<(), MuxerSubprocessError> {
+        let stream_index = audio_stream_index(track)
+            .filter(|_| (track as usize) < self.config.audio_tracks.len())
+            .ok_or_else(|| MuxerSubprocessError::Write(anyhow!("unknown audio track {track}")))?;
         self.write_packet(
-            STREAM_INDEX_AUDIO,
+            stream_index,
             pts_time_base_units,
             dts_time_base_units,
             duration_time_base_units,
@@ -356,14 +488,34 @@ impl MuxerSubprocess {
                 self.packets_written.fetch_add(1, Ordering::Relaxed);
                 Ok(())
             }
-            Err(e) => {
-                let reason = self.on_write_failure(&format!("packet write: {e}"));
-                if self.exit_code_if_exited() == Some(EXIT_DISK_FULL as i32) {
-                    Err(MuxerSubprocessError::DiskFull(reason))
-                } else {
-                    Err(MuxerSubprocessError::Crashed(reason))
-                }
-            }
+            Err(e) => Err(self.write_failure(&format!("packet write: {e}"))),
+        }
+    }
+
+    /// Sends container tags and chapters. Returns `false` without sending anything when the
+    /// muxer only speaks protocol v1.
+    pub fn write_metadata(&mut self, metadata: &Metadata) -> Result<bool, MuxerSubprocessError> {
+        if !self.protocol.supports(FEATURE_METADATA) {
+            return Ok(false);
+        }
+
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| MuxerSubprocessError::Write(anyhow!("stdin closed")))?;
+
+        match write_frame(stdin, &Frame::Metadata(metadata.clone())) {
+            Ok(()) => Ok(true),
+            Err(e) => Err(self.write_failure(&format!("metadata write: {e}"))),
+        }
+    }
+
+    fn write_failure(&mut self, context: &str) -> MuxerSubprocessError {
+        let reason = self.on_write_failure(context);
+        if self.exit_code_if_exited() == Some(EXIT_DISK_FULL as i32) {
+            MuxerSubprocessError::DiskFull(reason)
+        } else {
+            MuxerSubprocessError::Crashed(reason)
         }
     }
 
@@ -614,6 +766,8 @@ pub struct RespawningMuxerSubprocess {
     max_consecutive_fast_failures: u32,
     last_crash_at: Option<Instant>,
     current_spawned_at: Instant,
+    /// Everything passed to `write_metadata`, replayed into respawned muxers.
+    metadata: Metadata,
 }
 
 impl RespawningMuxerSubprocess {
@@ -638,6 +792,7 @@ impl RespawningMuxerSubprocess {
             max_consecutive_fast_failures,
             last_crash_at: None,
             current_spawned_at: Instant::now(),
+            metadata: Metadata::default(),
         })
     }
 
@@ -656,12 +811,30 @@ impl RespawningMuxerSubprocess {
 
     pub fn write_audio_packet(
         &mut self,
+        track: u8,
         pts: i64,
         dts: i64,
         duration: u64,
         data: &[u8],
     ) -> Result<(), MuxerSubprocessError> {
-        self.run_with_respawn(|child| child.write_audio_packet(pts, dts, duration, data))
+        self.run_with_respawn(|child| child.write_audio_packet(track, pts, dts, duration, data))
+    }
+
+    pub fn write_metadata(&mut self, metadata: &Metadata) -> Result<bool, MuxerSubprocessError> {
+        let mut accepted = false;
+        self.run_with_respawn(|child| {
+            accepted = child.write_metadata(metadata)?;
+            Ok(())
+        })?;
+        self.metadata.tags.extend(metadata.tags.iter().cloned());
+        self.metadata
+            .chapters
+            .extend(metadata.chapters.iter().cloned());
+        Ok(accepted)
+    }
+
+    pub fn protocol(&self) -> Option<HelloAck> {
+        self.current.as_ref().map(MuxerSubprocess::protocol)
     }
 
     fn run_with_respawn<F>(&mut self, mut op: F) -> Result<(), MuxerSubprocessError>
@@ -737,11 +910,14 @@ impl RespawningMuxerSubprocess {
             "respawning cap-muxer subprocess into isolated directory"
         );
 
-        let new = MuxerSubprocess::spawn(
+        let mut new = MuxerSubprocess::spawn(
             self.bin_path.clone(),
             respawned_config,
             self.health_tx.clone(),
         )?;
+        if !self.metadata.tags.is_empty() || !self.metadata.chapters.is_empty() {
+            new.write_metadata(&self.metadata)?;
+        }
         self.current = Some(new);
         self.current_spawned_at = Instant::now();
         Ok(())
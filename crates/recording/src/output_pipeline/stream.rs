@@ -0,0 +1,858 @@
+use crate::{
+    SharedPauseState, TaskPool,
+    output_pipeline::{
+        AudioFrame, AudioMuxer, FFmpegVideoFrame, HealthSender, Muxer, PipelineHealthEvent,
+        SharedHealthSender, VideoFrame, VideoMuxer,
+    },
+};
+use anyhow::{Context, anyhow};
+use cap_enc_ffmpeg::{aac::AACEncoder, h264::*};
+use cap_media_info::{AudioInfo, VideoInfo};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{Receiver, SyncSender, TrySendError, sync_channel},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+use tracing::*;
+
+/// Frames and stream attachments waiting for the network thread. Roughly a second of video and
+/// audio; anything beyond that is dropped rather than stalling the recording.
+const STREAM_QUEUE_CAPACITY: usize = 120;
+/// Bounds every connect and write so a dead ingest fails over to a reconnect instead of hanging.
+const STREAM_IO_TIMEOUT_US: &str = "5000000";
+const STREAM_FINISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where a live stream is pushed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamTarget {
+    /// `rtmp://` or `rtmps://` ingest, muxed as FLV.
+    Rtmp(String),
+    /// `srt://` endpoint in caller mode, muxed as MPEG-TS.
+    Srt(String),
+    /// Rolling HLS playlist, written to a local `.m3u8` path or PUT to an HTTP(S) origin.
+    Hls(String),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum StreamTargetError {
+    #[error("Stream URL '{0}' has no host")]
+    MissingHost(String),
+    #[error(
+        "Unsupported stream URL '{0}'; use rtmp://, rtmps://, srt:// or a path/URL ending in .m3u8"
+    )]
+    Unsupported(String),
+}
+
+impl StreamTarget {
+    pub fn parse(url: &str) -> Result<Self, StreamTargetError> {
+        let url = url.trim();
+        let lower = url.to_ascii_lowercase();
+
+        if lower.starts_with("rtmp://") || lower.starts_with("rtmps://") {
+            Self::require_host(url)?;
+            return Ok(Self::Rtmp(url.to_string()));
+        }
+
+        if lower.starts_with("srt://") {
+            Self::require_host(url)?;
+            return Ok(Self::Srt(url.to_string()));
+        }
+
+        let path = lower.split(['?', '#']).next().unwrap_or_default();
+        if path.ends_with(".m3u8") {
+            if lower.starts_with("http://") || lower.starts_with("https://") {
+                Self::require_host(url)?;
+            }
+            return Ok(Self::Hls(url.to_string()));
+        }
+
+        Err(StreamTargetError::Unsupported(url.to_string()))
+    }
+
+    fn require_host(url: &str) -> Result<(), StreamTargetError> {
+        if split_url(url).is_some_and(|(_, authority, _)| !authority.is_empty()) {
+            Ok(())
+        } else {
+            Err(StreamTargetError::MissingHost(url.to_string()))
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        match self {
+            Self::Rtmp(url) | Self::Srt(url) | Self::Hls(url) => url,
+        }
+    }
+
+    fn format_name(&self) -> &'static str {
+        match self {
+            Self::Rtmp(_) => "flv",
+            Self::Srt(_) => "mpegts",
+            Self::Hls(_) => "hls",
+        }
+    }
+
+    fn local_playlist(&self) -> Option<PathBuf> {
+        let Self::Hls(url) = self else {
+            return None;
+        };
+        if url.contains("://") && !url.starts_with("file://") {
+            return None;
+        }
+        Some(PathBuf::from(url.trim_start_matches("file://")))
+    }
+
+    fn muxer_options(&self) -> ffmpeg::Dictionary<'static> {
+        let mut options = ffmpeg::Dictionary::new();
+        match self {
+            Self::Rtmp(_) => {
+                // Live ingest can't seek back to patch duration/filesize into the header.
+                options.set("flvflags", "no_duration_filesize");
+            }
+            Self::Srt(_) => {}
+            Self::Hls(_) => {
+                options.set("hls_time", "2");
+                options.set("hls_list_size", "6");
+                options.set("hls_flags", "delete_segments+independent_segments");
+                if let Some(playlist) = self.local_playlist() {
+                    let stem = playlist
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "stream".to_string());
+                    let segments = playlist.with_file_name(format!("{stem}_%05d.ts"));
+                    options.set("hls_segment_filename", &segments.to_string_lossy());
+                } else {
+                    options.set("method", "PUT");
+                }
+            }
+        }
+        options
+    }
+}
+
+/// Shows the target without credentials: RTMP stream keys (everything after the app name),
+/// query strings (SRT passphrases and stream ids, signed HLS URLs) and URL user info are
+/// replaced, so it is safe to log and to put in health events.
+impl fmt::Display for StreamTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let url = self.url();
+        let Some((scheme, authority, path)) = split_url(url) else {
+            return f.write_str(url.split(['?', '#']).next().unwrap_or_default());
+        };
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+
+        match self {
+            Self::Rtmp(_) => {
+                let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+                write!(f, "{scheme}://{host}")?;
+                if let Some(app) = segments.next() {
+                    write!(f, "/{app}")?;
+                }
+                if segments.next().is_some() {
+                    f.write_str("/***")?;
+                }
+                Ok(())
+            }
+            Self::Srt(_) | Self::Hls(_) => write!(f, "{scheme}://{host}{path}"),
+        }
+    }
+}
+
+fn split_url(url: &str) -> Option<(&str, &str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    Some((scheme, authority, path))
+}
+
+/// How a live stream retries after it fails to connect or loses its connection.
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Consecutive failed connection attempts before the stream gives up; `None` keeps trying
+    /// until the recording stops.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Exponential backoff: the delay doubles with each failed attempt (starting at 1) up to
+    /// `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+
+    fn exhausted(&self, failures: u32) -> bool {
+        self.max_attempts.is_some_and(|max| failures >= max)
+    }
+}
+
+pub struct LiveStreamConfig {
+    pub target: StreamTarget,
+    pub reconnect: ReconnectPolicy,
+    pub output_size: Option<(u32, u32)>,
+    pub bpp: f32,
+}
+
+impl LiveStreamConfig {
+    pub fn new(target: StreamTarget) -> Self {
+        Self {
+            target,
+            reconnect: ReconnectPolicy::default(),
+            output_size: None,
+            bpp: H264EncoderBuilder::INSTANT_MODE_BPP,
+        }
+    }
+}
+
+enum StreamMessage {
+    AttachVideo(VideoInfo),
+    AttachAudio(AudioInfo),
+    Video(ffmpeg::frame::Video, Duration),
+    Audio(ffmpeg::frame::Audio, Duration),
+}
+
+/// A network output that H.264/AAC-encodes frames on its own thread and pushes them to a
+/// [`StreamTarget`], reconnecting with backoff when the connection drops.
+///
+/// Cloned into every pipeline that feeds it (instant mode records video and audio in separate
+/// pipelines); the stream ends once the last [`StreamingMuxer`] holding it finishes.
+#[derive(Clone)]
+pub struct LiveStream {
+    inner: Arc<LiveStreamInner>,
+}
+
+struct LiveStreamInner {
+    tx: SyncSender<StreamMessage>,
+    queued: Arc<AtomicUsize>,
+    dropped: AtomicU64,
+    health: SharedHealthSender,
+    target: String,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl LiveStream {
+    /// Starts the network thread. It connects once a video stream has been attached and, with
+    /// `with_audio`, an audio stream too.
+    pub fn spawn(config: LiveStreamConfig, with_audio: bool) -> anyhow::Result<Self> {
+        let (tx, rx) = sync_channel(STREAM_QUEUE_CAPACITY);
+        let queued = Arc::new(AtomicUsize::new(0));
+        let health = SharedHealthSender::new();
+        let target = config.target.to_string();
+
+        let worker = StreamWorker {
+            target: target.clone(),
+            config,
+            with_audio,
+            video_info: None,
+            audio_info: None,
+            health: health.clone(),
+            session: None,
+            failures: 0,
+            retry_at: None,
+            given_up: false,
+        };
+        let worker_queued = queued.clone();
+        let worker = std::thread::Builder::new()
+            .name("live-stream".to_string())
+            .spawn(move || worker.run(rx, &worker_queued))
+            .context("Failed to spawn live stream thread")?;
+
+        Ok(Self {
+            inner: Arc::new(LiveStreamInner {
+                tx,
+                queued,
+                dropped: AtomicU64::new(0),
+                health,
+                target,
+                worker: Mutex::new(Some(worker)),
+            }),
+        })
+    }
+
+    /// The target with credentials redacted.
+    pub fn target(&self) -> &str {
+        &self.inner.target
+    }
+
+    fn attach_video(&self, info: VideoInfo) {
+        let _ = self.inner.tx.send(StreamMessage::AttachVideo(info));
+    }
+
+    fn attach_audio(&self, info: AudioInfo) {
+        let _ = self.inner.tx.send(StreamMessage::AttachAudio(info));
+    }
+
+    /// Both pipelines offer their health sender; the first one (the video pipeline's, whose
+    /// receiver the recording actor hands out) wins.
+    fn set_health_sender(&self, tx: HealthSender) {
+        if self.inner.health.get().is_none() {
+            self.inner.health.set(tx);
+        }
+    }
+
+    fn has_capacity(&self) -> bool {
+        self.inner.queued.load(Ordering::Acquire) < STREAM_QUEUE_CAPACITY
+    }
+
+    fn push(&self, message: StreamMessage) {
+        match self.inner.tx.try_send(message) {
+            Ok(()) => {
+                self.inner.queued.fetch_add(1, Ordering::AcqRel);
+            }
+            Err(TrySendError::Full(_)) => {
+                self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                trace!("Live stream thread already exited");
+            }
+        }
+    }
+
+    fn send_video(&self, frame: ffmpeg::frame::Video, timestamp: Duration) {
+        self.push(StreamMessage::Video(frame, timestamp));
+    }
+
+    fn send_audio(&self, frame: ffmpeg::frame::Audio, timestamp: Duration) {
+        self.push(StreamMessage::Audio(frame, timestamp));
+    }
+
+    /// Releases this handle. The last one closes the queue and waits for the network thread to
+    /// flush the encoders and write the trailer (which ends an HLS playlist).
+    fn finish(self) {
+        let Ok(inner) = Arc::try_unwrap(self.inner) else {
+            return;
+        };
+        let LiveStreamInner {
+            tx,
+            dropped,
+            target,
+            worker,
+            ..
+        } = inner;
+        drop(tx);
+
+        let dropped = dropped.into_inner();
+        if dropped > 0 {
+            warn!(stream = %target, dropped, "Live stream dropped frames because the network fell behind");
+        }
+
+        let Some(handle) = worker.into_inner().ok().flatten() else {
+            return;
+        };
+        let start = Instant::now();
+        while !handle.is_finished() {
+            if start.elapsed() > STREAM_FINISH_TIMEOUT {
+                warn!(stream = %target, "Live stream did not finish within {STREAM_FINISH_TIMEOUT:?}, abandoning");
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        if handle.join().is_err() {
+            warn!(stream = %target, "Live stream thread panicked");
+        }
+    }
+}
+
+struct StreamWorker {
+    config: LiveStreamConfig,
+    target: String,
+    with_audio: bool,
+    video_info: Option<VideoInfo>,
+    audio_info: Option<AudioInfo>,
+    health: SharedHealthSender,
+    session: Option<StreamSession>,
+    failures: u32,
+    retry_at: Option<Instant>,
+    given_up: bool,
+}
+
+impl StreamWorker {
+    fn run(mut self, rx: Receiver<StreamMessage>, queued: &AtomicUsize) {
+        while let Ok(message) = rx.recv() {
+            match message {
+                StreamMessage::AttachVideo(info) => self.video_info = Some(info),
+                StreamMessage::AttachAudio(info) => self.audio_info = Some(info),
+                StreamMessage::Video(frame, timestamp) => {
+                    queued.fetch_sub(1, Ordering::AcqRel);
+                    if self.ensure_connected()
+                        && let Some(session) = &mut self.session
+                        && let Err(error) = session.write_video(frame, timestamp)
+                    {
+                        self.disconnect(error);
+                    }
+                }
+                StreamMessage::Audio(frame, timestamp) => {
+                    queued.fetch_sub(1, Ordering::AcqRel);
+                    if self.ensure_connected()
+                        && let Some(session) = &mut self.session
+                        && let Err(error) = session.write_audio(frame, timestamp)
+                    {
+                        self.disconnect(error);
+                    }
+                }
+            }
+        }
+
+        if let Some(session) = self.session.take() {
+            match session.finish() {
+                Ok(()) => info!(stream = %self.target, "Live stream finished"),
+                Err(error) => {
+                    warn!(stream = %self.target, "Live stream finished uncleanly: {error:#}")
+                }
+            }
+        }
+    }
+
+    fn ensure_connected(&mut self) -> bool {
+        if self.session.is_some() {
+            return true;
+        }
+        if self.given_up {
+            return false;
+        }
+        let Some(video_info) = self.video_info else {
+            return false;
+        };
+        if self.with_audio && self.audio_info.is_none() {
+            return false;
+        }
+        if self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return false;
+        }
+
+        match StreamSession::connect(&self.config, video_info, self.audio_info) {
+            Ok(session) => {
+                if self.failures > 0 {
+                    info!(stream = %self.target, attempts = self.failures, "Live stream reconnected");
+                    self.health.emit(PipelineHealthEvent::StreamReconnected {
+                        target: self.target.clone(),
+                        attempts: self.failures,
+                    });
+                } else {
+                    info!(stream = %self.target, "Live stream connected");
+                }
+                self.failures = 0;
+                self.retry_at = None;
+                self.session = Some(session);
+                true
+            }
+            Err(error) => {
+                self.schedule_retry(format!("{error:#}"));
+                false
+            }
+        }
+    }
+
+    /// Drops the broken connection without a trailer; the next frame after the backoff opens a
+    /// fresh one with new encoders, so the stream restarts on a keyframe.
+    fn disconnect(&mut self, error: anyhow::Error) {
+        self.session = None;
+        self.schedule_retry(format!("{error:#}"));
+    }
+
+    fn schedule_retry(&mut self, reason: String) {
+        self.failures += 1;
+
+        if self.config.reconnect.exhausted(self.failures) {
+            error!(stream = %self.target, attempts = self.failures, "Live stream gave up: {reason}");
+            self.given_up = true;
+            self.health.emit(PipelineHealthEvent::StreamFailed {
+                target: self.target.clone(),
+                reason,
+            });
+            return;
+        }
+
+        let delay = self.config.reconnect.delay(self.failures);
+        warn!(
+            stream = %self.target,
+            attempt = self.failures,
+            retry_in_ms = delay.as_millis() as u64,
+            "Live stream connection failed: {reason}"
+        );
+        self.health.emit(PipelineHealthEvent::StreamReconnecting {
+            target: self.target.clone(),
+            attempt: self.failures,
+            retry_in_ms: delay.as_millis() as u64,
+            reason,
+        });
+        self.retry_at = Some(Instant::now() + delay);
+    }
+}
+
+struct StreamSession {
+    output: ffmpeg::format::context::Output,
+    video: H264Encoder,
+    audio: Option<AACEncoder>,
+    video_started: bool,
+}
+
+impl StreamSession {
+    fn connect(
+        config: &LiveStreamConfig,
+        video_info: VideoInfo,
+        audio_info: Option<AudioInfo>,
+    ) -> anyhow::Result<Self> {
+        if let Some(dir) = config
+            .target
+            .local_playlist()
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create HLS directory {}", dir.display()))?;
+        }
+
+        let mut io_options = ffmpeg::Dictionary::new();
+        io_options.set("rw_timeout", STREAM_IO_TIMEOUT_US);
+        let mut output = ffmpeg::format::output_as_with(
+            &config.target.url(),
+            config.target.format_name(),
+            io_options,
+        )
+        .map_err(|e| anyhow!("Failed to open {}: {e}", config.target))?;
+
+        let mut video = H264Encoder::builder(video_info)
+            .with_preset(H264Preset::Ultrafast)
+            .with_bpp(config.bpp);
+        // FLV carries SPS/PPS once in the header; MPEG-TS (SRT, HLS) wants them in-band.
+        if matches!(config.target, StreamTarget::Rtmp(_)) {
+            video = video.with_global_header();
+        }
+        if let Some((width, height)) = config.output_size {
+            video = video.with_output_size(width, height)?;
+        }
+        let video = video.build(&mut output).context("video encoder")?;
+
+        let audio = audio_info
+            .map(|info| AACEncoder::init(info, &mut output))
+            .transpose()
+            .context("audio encoder")?;
+
+        output
+            .write_header_with(config.target.muxer_options())
+            .map_err(|e| anyhow!("Failed to start stream to {}: {e}", config.target))?;
+
+        Ok(Self {
+            output,
+            video,
+            audio,
+            video_started: false,
+        })
+    }
+
+    fn write_video(
+        &mut self,
+        frame: ffmpeg::frame::Video,
+        timestamp: Duration,
+    ) -> anyhow::Result<()> {
+        self.video.queue_frame(frame, timestamp, &mut self.output)?;
+        self.video_started = true;
+        Ok(())
+    }
+
+    /// Audio waits for the first video frame so both encoders start their clocks together.
+    fn write_audio(
+        &mut self,
+        frame: ffmpeg::frame::Audio,
+        timestamp: Duration,
+    ) -> anyhow::Result<()> {
+        if !self.video_started {
+            return Ok(());
+        }
+        if let Some(audio) = &mut self.audio {
+            audio.send_frame(frame, timestamp, &mut self.output)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> anyhow::Result<()> {
+        let video_result = self.video.flush(&mut self.output);
+        let audio_result = self
+            .audio
+            .as_mut()
+            .map(|audio| audio.flush(&mut self.output))
+            .unwrap_or(Ok(()));
+        self.output.write_trailer().context("write_trailer")?;
+
+        video_result.context("video flush")?;
+        audio_result.context("audio flush")?;
+        Ok(())
+    }
+}
+
+/// Video frames that can be copied into an FFmpeg frame for the live stream's encoder.
+pub trait StreamVideoFrame: VideoFrame {
+    fn to_ffmpeg_frame(&self) -> anyhow::Result<ffmpeg::frame::Video>;
+}
+
+impl StreamVideoFrame for FFmpegVideoFrame {
+    fn to_ffmpeg_frame(&self) -> anyhow::Result<ffmpeg::frame::Video> {
+        Ok(self.inner.clone())
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl StreamVideoFrame for crate::sources::screen_capture::VideoFrame {
+    fn to_ffmpeg_frame(&self) -> anyhow::Result<ffmpeg::frame::Video> {
+        use cidre::cv::PixelFormat;
+
+        let image_buf = self
+            .sample_buf
+            .image_buf()
+            .ok_or_else(|| anyhow!("Sample buffer has no image"))?;
+        let pixel_format = match image_buf.pixel_format() {
+            PixelFormat::_420V => ffmpeg::format::Pixel::NV12,
+            PixelFormat::_32_BGRA => ffmpeg::format::Pixel::BGRA,
+            PixelFormat::_2VUY => ffmpeg::format::Pixel::UYVY422,
+            format => return Err(anyhow!("Unsupported pixel format {format:?}")),
+        };
+
+        let mut frame = ffmpeg::frame::Video::new(
+            pixel_format,
+            image_buf.width() as u32,
+            image_buf.height() as u32,
+        );
+        super::macos_frame_convert::fill_frame_from_sample_buf(&self.sample_buf, &mut frame)
+            .map_err(|e| anyhow!("Failed to copy sample buffer: {e:?}"))?;
+        Ok(frame)
+    }
+}
+
+#[cfg(windows)]
+impl StreamVideoFrame for crate::sources::screen_capture::VideoFrame {
+    fn to_ffmpeg_frame(&self) -> anyhow::Result<ffmpeg::frame::Video> {
+        self.frame
+            .as_ffmpeg()
+            .map_err(|e| anyhow!("Failed to read back frame: {e}"))
+    }
+}
+
+/// Wraps a file muxer and, when a [`LiveStream`] is attached, also pushes every frame to it.
+/// The wrapped muxer always gets the frame, so a failing stream never affects the recording.
+pub struct StreamingMuxer<M> {
+    inner: M,
+    stream: Option<LiveStream>,
+    pause: SharedPauseState,
+    frame_rate: u32,
+    video_attached: bool,
+}
+
+pub struct StreamingMuxerConfig<C> {
+    pub inner: C,
+    pub stream: Option<LiveStream>,
+}
+
+impl<M: Muxer> StreamingMuxer<M> {
+    fn stream_timestamp(&self, timestamp: Duration) -> Option<Duration> {
+        self.stream.as_ref()?;
+        self.pause.adjust(timestamp).ok().flatten()
+    }
+}
+
+impl<M: Muxer> Muxer for StreamingMuxer<M>
+where
+    M::Config: Send,
+{
+    type Config = StreamingMuxerConfig<M::Config>;
+
+    async fn setup(
+        config: Self::Config,
+        output_path: PathBuf,
+        video_config: Option<VideoInfo>,
+        audio_config: Option<AudioInfo>,
+        pause_flag: Arc<AtomicBool>,
+        tasks: &mut TaskPool,
+    ) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        let inner = M::setup(
+            config.inner,
+            output_path,
+            video_config,
+            audio_config,
+            pause_flag.clone(),
+            tasks,
+        )
+        .await?;
+
+        if let Some(stream) = &config.stream
+            && let Some(audio_config) = audio_config
+        {
+            stream.attach_audio(audio_config);
+        }
+
+        Ok(Self {
+            inner,
+            stream: config.stream,
+            pause: SharedPauseState::new(pause_flag),
+            frame_rate: video_config.map_or(30, |info| info.fps()),
+            video_attached: false,
+        })
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+
+    fn finish(&mut self, timestamp: Duration) -> anyhow::Result<anyhow::Result<()>> {
+        let result = self.inner.finish(timestamp);
+        if let Some(stream) = self.stream.take() {
+            stream.finish();
+        }
+        result
+    }
+
+    fn set_health_sender(&mut self, tx: HealthSender) {
+        if let Some(stream) = &self.stream {
+            stream.set_health_sender(tx.clone());
+        }
+        self.inner.set_health_sender(tx);
+    }
+}
+
+impl<M: VideoMuxer> VideoMuxer for StreamingMuxer<M>
+where
+    M::Config: Send,
+    M::VideoFrame: StreamVideoFrame,
+{
+    type VideoFrame = M::VideoFrame;
+
+    fn send_video_frame(
+        &mut self,
+        frame: Self::VideoFrame,
+        timestamp: Duration,
+    ) -> anyhow::Result<()> {
+        if let Some(stream_timestamp) = self.stream_timestamp(timestamp)
+            && let Some(stream) = &self.stream
+            && stream.has_capacity()
+        {
+            match frame.to_ffmpeg_frame() {
+                Ok(video) => {
+                    // The encoder is sized from the frames themselves: platform capture can hand
+                    // the muxer frames that differ from the source's advertised format.
+                    if !self.video_attached {
+                        stream.attach_video(VideoInfo::from_raw_ffmpeg(
+                            video.format(),
+                            video.width(),
+                            video.height(),
+                            self.frame_rate,
+                        ));
+                        self.video_attached = true;
+                    }
+                    stream.send_video(video, stream_timestamp);
+                }
+                Err(error) => trace!("Skipping live stream frame: {error:#}"),
+            }
+        }
+
+        self.inner.send_video_frame(frame, timestamp)
+    }
+}
+
+impl<M: AudioMuxer> AudioMuxer for StreamingMuxer<M>
+where
+    M::Config: Send,
+{
+    fn send_audio_frame(&mut self, frame: AudioFrame, timestamp: Duration) -> anyhow::Result<()> {
+        if let Some(stream_timestamp) = self.stream_timestamp(timestamp)
+            && let Some(stream) = &self.stream
+            && stream.has_capacity()
+        {
+            stream.send_audio(frame.inner.clone(), stream_timestamp);
+        }
+
+        self.inner.send_audio_frame(frame, timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_target_is_picked_from_the_url() {
+        assert_eq!(
+            StreamTarget::parse("rtmp://localhost/live/key"),
+            Ok(StreamTarget::Rtmp("rtmp://localhost/live/key".to_string()))
+        );
+        assert_eq!(
+            StreamTarget::parse("srt://127.0.0.1:9000?streamid=demo"),
+            Ok(StreamTarget::Srt(
+                "srt://127.0.0.1:9000?streamid=demo".to_string()
+            ))
+        );
+        assert_eq!(
+            StreamTarget::parse("/tmp/live/index.m3u8"),
+            Ok(StreamTarget::Hls("/tmp/live/index.m3u8".to_string()))
+        );
+        assert_eq!(
+            StreamTarget::parse("rtmp:///live"),
+            Err(StreamTargetError::MissingHost("rtmp:///live".to_string()))
+        );
+        assert!(matches!(
+            StreamTarget::parse("udp://127.0.0.1:1234"),
+            Err(StreamTargetError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn stream_target_display_hides_credentials() {
+        let target = |url| StreamTarget::parse(url).unwrap().to_string();
+
+        assert_eq!(
+            target("rtmp://user:pw@a.rtmp.youtube.com/live2/abcd-efgh"),
+            "rtmp://a.rtmp.youtube.com/live2/***"
+        );
+        assert_eq!(target("rtmp://localhost/live"), "rtmp://localhost/live");
+        assert_eq!(
+            target("srt://ingest:9000?passphrase=secret"),
+            "srt://ingest:9000"
+        );
+        assert_eq!(
+            target("https://cdn.example.com/live/index.m3u8?token=abc"),
+            "https://cdn.example.com/live/index.m3u8"
+        );
+        assert_eq!(target("out/index.m3u8"), "out/index.m3u8");
+    }
+
+    #[test]
+    fn reconnect_backoff_doubles_up_to_the_cap() {
+        let policy = ReconnectPolicy {
+            max_attempts: Some(4),
+            ..Default::default()
+        };
+
+        let delays: Vec<_> = (1..=7).map(|attempt| policy.delay(attempt)).collect();
+        assert_eq!(
+            delays,
+            [1, 2, 4, 8, 16, 30, 30].map(Duration::from_secs).to_vec()
+        );
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(30));
+        assert!(!policy.exhausted(3));
+        assert!(policy.exhausted(4));
+        assert!(!ReconnectPolicy::default().exhausted(u32::MAX));
+    }
+}
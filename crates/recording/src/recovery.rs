@@ -6,7 +6,7 @@ use std::{
 use cap_enc_ffmpeg::fragmented_mp4::tail_is_complete;
 use cap_enc_ffmpeg::remux::{
     concatenate_audio_to_ogg, concatenate_m4s_segments_with_init, concatenate_video_fragments,
-    get_media_duration, get_video_fps, merge_video_audio, probe_media_valid,
+    get_media_duration, get_video_fps, merge_video_audio_tracks, probe_media_valid,
     probe_video_can_decode, probe_video_seek_points, remux_file,
 };
 use cap_project::{
@@ -17,7 +17,10 @@ use cap_project::{
 use relative_path::RelativePathBuf;
 use tracing::{debug, warn};
 
-use crate::output_pipeline::{HealthSender, PipelineHealthEvent, emit_health};
+use crate::{
+    instant_recording::SEPARATE_AUDIO_TRACKS,
+    output_pipeline::{HealthSender, PipelineHealthEvent, emit_health},
+};
 
 macro_rules! finalization_info {
     ($($arg:tt)*) => {
@@ -1038,6 +1041,11 @@ impl RecoveryManager {
         Self::finalize_to_progressive_mp4_with_health(fragmented_dir, output, None)
     }
 
+    /// Finalizes an instant recording's fragmented video and audio into one progressive MP4.
+    ///
+    /// Audio recorded as separate tracks (see
+    /// [`SEPARATE_AUDIO_TRACKS`](crate::instant_recording::SEPARATE_AUDIO_TRACKS)) becomes one
+    /// labelled audio track per source; otherwise the mixed audio becomes the only track.
     pub fn finalize_instant_output(
         display_dir: &Path,
         audio_dir: &Path,
@@ -1047,9 +1055,26 @@ impl RecoveryManager {
             return Self::finalize_to_progressive_mp4(display_dir, output);
         }
 
-        Self::rescue_pending_tmp_fragments(audio_dir, None);
-        let audio_info = Self::find_complete_fragments_with_init(audio_dir);
-        if audio_info.fragments.is_empty() {
+        let separate_tracks = SEPARATE_AUDIO_TRACKS
+            .iter()
+            .map(|(dir, label)| (audio_dir.join(dir), Some(*label)))
+            .filter(|(dir, _)| dir.is_dir())
+            .collect::<Vec<_>>();
+        let track_dirs = if separate_tracks.is_empty() {
+            vec![(audio_dir.to_path_buf(), None)]
+        } else {
+            separate_tracks
+        };
+
+        let audio_tracks = track_dirs
+            .into_iter()
+            .filter_map(|(dir, label)| {
+                Self::rescue_pending_tmp_fragments(&dir, None);
+                let info = Self::find_complete_fragments_with_init(&dir);
+                (!info.fragments.is_empty()).then_some((info, label))
+            })
+            .collect::<Vec<_>>();
+        if audio_tracks.is_empty() {
             return Self::finalize_to_progressive_mp4(display_dir, output);
         }
 
@@ -1060,25 +1085,40 @@ impl RecoveryManager {
             .and_then(|s| s.to_str())
             .unwrap_or("instant");
         let video_output = parent.join(format!("{stem}.video.mp4"));
-        let audio_output = parent.join(format!("{stem}.audio.mp4"));
+        let audio_outputs = (0..audio_tracks.len())
+            .map(|i| match i {
+                0 => parent.join(format!("{stem}.audio.mp4")),
+                i => parent.join(format!("{stem}.audio-{i}.mp4")),
+            })
+            .collect::<Vec<_>>();
         let merged_output = parent.join(format!("{stem}.merged.mp4"));
 
         let result = (|| {
             Self::finalize_to_progressive_mp4(display_dir, &video_output)?;
-            Self::finalize_audio_fragments_to_progressive_mp4(
-                &audio_info.fragments,
-                audio_info.init_segment.as_deref(),
-                &audio_output,
-                "audio",
-            )?;
-            merge_video_audio(&video_output, &audio_output, &merged_output)
+            for ((info, label), audio_output) in audio_tracks.iter().zip(&audio_outputs) {
+                Self::finalize_audio_fragments_to_progressive_mp4(
+                    &info.fragments,
+                    info.init_segment.as_deref(),
+                    audio_output,
+                    label.unwrap_or("audio"),
+                )?;
+            }
+            let merge_inputs = audio_outputs
+                .iter()
+                .zip(&audio_tracks)
+                .map(|(path, (_, label))| (path.as_path(), *label))
+                .collect::<Vec<_>>();
+            merge_video_audio_tracks(&video_output, &merge_inputs, &merged_output)
                 .map_err(RecoveryError::MediaMerge)?;
             Self::validate_required_video(&merged_output, "display")?;
             replace_file(&merged_output, output)?;
             Ok(output.to_path_buf())
         })();
 
-        for path in [&video_output, &audio_output, &merged_output] {
+        for path in std::iter::once(&video_output)
+            .chain(&audio_outputs)
+            .chain(std::iter::once(&merged_output))
+        {
             if path.exists() && path != output {
                 let _ = std::fs::remove_file(path);
             }
@@ -1463,6 +1503,7 @@ impl RecoveryManager {
                     end: duration,
                     timescale: 1.0,
                     name: None,
+                    transition: None,
                 })
             })
             .collect();
@@ -1483,6 +1524,7 @@ impl RecoveryManager {
             caption_segments: Vec::new(),
             keyboard_segments: Vec::new(),
             audio_segments: Vec::new(),
+            annotation_segments: Vec::new(),
         });
 
         config
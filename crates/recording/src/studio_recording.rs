@@ -29,8 +29,8 @@ use crate::output_pipeline::{
 use anyhow::{Context as _, anyhow, bail};
 use cap_media_info::VideoInfo;
 use cap_project::{
-    CursorEvents, KeyboardEvents, MultipleSegment, MultipleSegments, Platform, RecordingMeta,
-    RecordingMetaInner, StudioRecordingMeta, StudioRecordingStatus,
+    ChapterMarker, CursorEvents, KeyboardEvents, MultipleSegment, MultipleSegments, Platform,
+    RecordingMeta, RecordingMetaInner, StudioRecordingMeta, StudioRecordingStatus,
 };
 use cap_timestamp::{Timestamp, Timestamps};
 use futures::{FutureExt, StreamExt, future::OptionFuture, stream::FuturesUnordered};
@@ -96,6 +96,7 @@ pub struct Actor {
     state: Option<ActorState>,
     segment_factory: SegmentPipelineFactory,
     segments: Vec<RecordingSegment>,
+    chapters: Vec<ChapterMarker>,
     completion_tx: watch::Sender<Option<Result<(), PipelineDoneError>>>,
 }
 
@@ -191,6 +192,7 @@ impl Message<Stop> for Actor {
             self.recording_dir.clone(),
             std::mem::take(&mut self.segments),
             cursors,
+            std::mem::take(&mut self.chapters),
             self.segment_factory.fragmented,
         )
         .await?;
@@ -339,6 +341,38 @@ impl Message<IsPaused> for Actor {
     }
 }
 
+struct AddChapterMarker {
+    title: Option<String>,
+}
+
+impl Message<AddChapterMarker> for Actor {
+    type Reply = anyhow::Result<ChapterMarker>;
+
+    async fn handle(
+        &mut self,
+        msg: AddChapterMarker,
+        _: &mut Context<Self, Self::Reply>,
+    ) -> Self::Reply {
+        // Source time is the finished segments laid end to end, so pauses don't count.
+        let recorded: f64 = self.segments.iter().map(|s| s.end - s.start).sum();
+        let time = match self.state.as_ref() {
+            Some(ActorState::Recording {
+                segment_start_time, ..
+            }) => recorded + (current_time_f64() - segment_start_time).max(0.0),
+            Some(ActorState::Paused { .. }) => recorded,
+            None => bail!("Recording no longer active"),
+        };
+
+        let marker = ChapterMarker {
+            time,
+            title: msg.title,
+        };
+        self.chapters.push(marker.clone());
+
+        Ok(marker)
+    }
+}
+
 pub struct RecordingSegment {
     pub start: f64,
     pub end: f64,
@@ -687,6 +721,12 @@ impl ActorHandle {
     pub async fn is_paused(&self) -> anyhow::Result<bool> {
         Ok(self.actor_ref.ask(IsPaused).await?)
     }
+
+    /// Marks the start of a new chapter at the current point of the recording, returning the
+    /// marker as it will be saved in the project's meta.
+    pub async fn add_chapter_marker(&self, title: Option<String>) -> anyhow::Result<ChapterMarker> {
+        Ok(self.actor_ref.ask(AddChapterMarker { title }).await?)
+    }
 }
 
 impl Actor {
@@ -885,6 +925,7 @@ async fn spawn_studio_recording_actor(
         }),
         segment_factory: segment_pipeline_factory,
         segments: Vec::new(),
+        chapters: Vec::new(),
         completion_tx: completion_tx.clone(),
     });
 
@@ -899,12 +940,14 @@ pub struct CompletedRecording {
     pub project_path: PathBuf,
     pub meta: StudioRecordingMeta,
     pub cursor_data: cap_project::CursorImages,
+    pub chapters: Vec<ChapterMarker>,
 }
 
 async fn stop_recording(
     recording_dir: PathBuf,
     segments: Vec<RecordingSegment>,
     cursors: Cursors,
+    chapters: Vec<ChapterMarker>,
     fragmented: bool,
 ) -> Result<CompletedRecording, RecordingError> {
     use cap_project::*;
@@ -1099,6 +1142,7 @@ async fn stop_recording(
                 end: segment.duration,
                 timescale: 1.0,
                 name: None,
+                transition: None,
             })
         })
         .collect();
@@ -1162,7 +1206,7 @@ async fn stop_recording(
         },
     };
 
-    persist_final_recording_meta(&recording_dir, &meta);
+    persist_final_recording_meta(&recording_dir, &meta, &chapters);
 
     let mut project_config = cap_project::ProjectConfiguration::default();
     if !timeline_segments.is_empty() {
@@ -1175,6 +1219,7 @@ async fn stop_recording(
             caption_segments: Vec::new(),
             keyboard_segments: Vec::new(),
             audio_segments: Vec::new(),
+            annotation_segments: Vec::new(),
         });
     }
     if let Some(clips) = clip_configs {
@@ -1198,6 +1243,7 @@ async fn stop_recording(
         project_path: recording_dir,
         meta,
         cursor_data: Default::default(),
+        chapters,
         // display_source: actor.options.capture_target,
         // segments: actor.segments,
     })
@@ -1744,7 +1790,11 @@ fn current_time_f64() -> f64 {
         .as_secs_f64()
 }
 
-fn persist_final_recording_meta(recording_dir: &Path, studio_meta: &StudioRecordingMeta) {
+fn persist_final_recording_meta(
+    recording_dir: &Path,
+    studio_meta: &StudioRecordingMeta,
+    chapters: &[ChapterMarker],
+) {
     use chrono::Local;
 
     let pretty_name = Local::now().format("Cap %Y-%m-%d at %H.%M.%S").to_string();
@@ -1755,6 +1805,7 @@ fn persist_final_recording_meta(recording_dir: &Path, studio_meta: &StudioRecord
         sharing: None,
         inner: RecordingMetaInner::Studio(Box::new(studio_meta.clone())),
         upload: None,
+        chapters: chapters.to_vec(),
     };
 
     if let Err(err) = recording_meta.save_for_project() {
@@ -1784,6 +1835,7 @@ fn write_in_progress_meta(recording_dir: &Path) -> anyhow::Result<()> {
             },
         })),
         upload: None,
+        chapters: Vec::new(),
     };
 
     meta.save_for_project()
@@ -2121,6 +2173,7 @@ mod tests {
             recording_dir.clone(),
             vec![segment],
             Default::default(),
+            Vec::new(),
             false,
         )
         .await
@@ -1,8 +1,9 @@
 use cap_enc_ffmpeg::{
     dash_audio::{DashAudioSegmentEncoder, DashAudioSegmentEncoderConfig},
     remux::{
-        concatenate_m4s_segments_with_init, get_media_duration, merge_video_audio,
-        probe_media_valid, probe_video_can_decode,
+        audio_stream_count, concatenate_m4s_segments_with_init, downmix_audio_tracks,
+        get_media_duration, merge_video_audio, merge_video_audio_tracks, probe_media_valid,
+        probe_video_can_decode,
     },
     segmented_stream::{
         SegmentCompletedEvent, SegmentMediaType, SegmentedVideoEncoder, SegmentedVideoEncoderConfig,
@@ -599,6 +600,77 @@ fn full_pipeline_video_plus_audio_assembly() {
     assert_duration_in_range(&result_path, 3.0, 0.5);
 }
 
+#[test]
+fn separate_audio_tracks_merge_labelled_and_downmix() {
+    common::init();
+
+    let temp = TempDir::new().unwrap();
+    let content_dir = temp.path().join("content");
+    std::fs::create_dir_all(&content_dir).unwrap();
+
+    let segment_duration = Duration::from_millis(500);
+    let recording_ms = 3000u64;
+
+    let video = encode_video_segments(
+        &content_dir,
+        default_video_info(),
+        segment_duration,
+        recording_ms,
+        33,
+    );
+    let video_only_path = temp.path().join("video_only.mp4");
+    concatenate_m4s_segments_with_init(&video.init_path, &video.segment_paths, &video_only_path)
+        .unwrap();
+
+    let mut track_paths = Vec::new();
+    for name in ["mic", "system"] {
+        let audio = encode_audio_segments(
+            &content_dir.join(name),
+            default_audio_info(),
+            segment_duration,
+            recording_ms,
+            None,
+        );
+        let path = temp.path().join(format!("{name}.m4a"));
+        concatenate_m4s_segments_with_init(&audio.init_path, &audio.segment_paths, &path).unwrap();
+        track_paths.push(path);
+    }
+
+    let merged_path = temp.path().join("merged.mp4");
+    merge_video_audio_tracks(
+        &video_only_path,
+        &[
+            (track_paths[0].as_path(), Some("Microphone")),
+            (track_paths[1].as_path(), Some("System audio")),
+        ],
+        &merged_path,
+    )
+    .unwrap();
+
+    assert_valid_playable_mp4(&merged_path);
+    assert_eq!(audio_stream_count(&merged_path), Some(2));
+    let input = ffmpeg::format::input(&merged_path).unwrap();
+    let titles: Vec<String> = input
+        .streams()
+        .filter(|s| s.parameters().medium() == ffmpeg::media::Type::Audio)
+        .map(|s| {
+            s.metadata()
+                .get("handler_name")
+                .unwrap_or_default()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(titles, ["Microphone", "System audio"]);
+
+    let mixed_path = temp.path().join("mixed.mp4");
+    downmix_audio_tracks(&merged_path, &mixed_path).unwrap();
+
+    assert_valid_playable_mp4(&mixed_path);
+    assert_has_video_stream(&mixed_path);
+    assert_eq!(audio_stream_count(&mixed_path), Some(1));
+    assert_duration_in_range(&mixed_path, 3.0, 0.5);
+}
+
 #[test]
 fn shared_event_channel_video_and_audio() {
     common::init();
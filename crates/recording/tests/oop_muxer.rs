@@ -43,7 +43,7 @@ fn minimal_video_config(output_dir: &std::path::Path, extradata: Vec<u8>) -> Mux
             extradata,
             segment_duration_ms: 2000,
         }),
-        audio_init: None,
+        audio_tracks: Vec::new(),
     }
 }
 
@@ -160,6 +160,37 @@ fn respawning_subprocess_reports_clean_exit_when_no_crash() {
     assert_eq!(report.exit_code, Some(0));
 }
 
+#[test]
+fn subprocess_negotiates_current_protocol_and_accepts_chapters() {
+    use cap_muxer_protocol::{Chapter, FEATURE_METADATA, Metadata, PROTOCOL_VERSION};
+
+    let bin = setup_muxer_binary();
+    let temp_dir = TempDir::new().unwrap();
+    let output_dir = temp_dir.path().join("video");
+
+    let config = minimal_video_config(&output_dir, Vec::new());
+    let mut subprocess = MuxerSubprocess::spawn(bin, config, None).expect("spawn cap-muxer");
+
+    let protocol = subprocess.protocol();
+    assert_eq!(protocol.version, PROTOCOL_VERSION);
+    assert!(protocol.supports(FEATURE_METADATA));
+
+    let accepted = subprocess
+        .write_metadata(&Metadata {
+            tags: vec![("title".to_string(), "Standup".to_string())],
+            chapters: vec![Chapter {
+                start_ms: 0,
+                end_ms: 1_000,
+                title: "Intro".to_string(),
+            }],
+        })
+        .expect("write metadata");
+    assert!(accepted);
+
+    let report = subprocess.finish().expect("finish cleanly");
+    assert_eq!(report.exit_code, Some(0));
+}
+
 #[test]
 fn subprocess_survives_finish_after_init_only() {
     let bin = setup_muxer_binary();
@@ -232,7 +263,7 @@ fn encoder_to_subprocess_end_to_end_produces_playable_init_and_segments() {
             extradata,
             segment_duration_ms: 500,
         }),
-        audio_init: None,
+        audio_tracks: Vec::new(),
     };
 
     let mut subprocess =
@@ -122,6 +122,7 @@ impl TestRecording {
             pretty_name: "Test Recording".to_string(),
             sharing: None,
             upload: None,
+            chapters: Vec::new(),
             inner: RecordingMetaInner::Studio(Box::new(StudioRecordingMeta::MultipleSegments {
                 inner: MultipleSegments {
                     segments: vec![MultipleSegment {
@@ -0,0 +1,548 @@
+use cap_project::{
+    AnnotationSegment, AnnotationType, MaskKind, TextAlign, TextScalarKeyframe, TextVectorKeyframe,
+    XY,
+};
+
+use crate::{MaskRenderMode, PreparedMask, text::PreparedText};
+
+/// Annotation sizes in the project config are measured at this output height.
+const BASE_HEIGHT: f32 = 1080.0;
+const MAX_FONT_SIZE_PX: f32 = 256.0;
+
+/// Everything the annotation segments on screen contribute to a frame. Shapes go to the
+/// annotation layer; text callouts and mask annotations reuse the text and mask layers.
+#[derive(Debug, Clone, Default)]
+pub struct PreparedAnnotations {
+    pub shapes: Vec<PreparedAnnotation>,
+    pub texts: Vec<PreparedText>,
+    pub masks: Vec<PreparedMask>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnnotationShape {
+    /// `rotation` is clockwise, in radians, around `center`.
+    Rectangle {
+        center: [f32; 2],
+        half_size: [f32; 2],
+        rotation: f32,
+    },
+    Ellipse {
+        center: [f32; 2],
+        half_size: [f32; 2],
+        rotation: f32,
+    },
+    /// `tip` is where the head currently sits, which trails the full arrow while it draws on.
+    Arrow {
+        tail: [f32; 2],
+        tip: [f32; 2],
+        head_length: f32,
+        head_width: f32,
+    },
+}
+
+/// A rectangle, ellipse or arrow in output pixels, drawn by `annotation.wgsl` and
+/// [`PreparedAnnotation::shade`]. Colors are straight RGBA.
+#[derive(Debug, Clone, Copy)]
+pub struct PreparedAnnotation {
+    pub shape: AnnotationShape,
+    pub stroke_color: [f32; 4],
+    pub fill_color: [f32; 4],
+    pub stroke_width: f32,
+    pub opacity: f32,
+}
+
+impl PreparedAnnotation {
+    /// The pixels this annotation can touch, as `[left, top, right, bottom]`.
+    pub fn bounds(&self) -> [f32; 4] {
+        let pad = self.stroke_width / 2.0 + 2.0;
+        match self.shape {
+            AnnotationShape::Rectangle {
+                center, half_size, ..
+            }
+            | AnnotationShape::Ellipse {
+                center, half_size, ..
+            } => {
+                // Large enough for any rotation.
+                let radius = half_size[0].hypot(half_size[1]) + pad;
+                [
+                    center[0] - radius,
+                    center[1] - radius,
+                    center[0] + radius,
+                    center[1] + radius,
+                ]
+            }
+            AnnotationShape::Arrow {
+                tail,
+                tip,
+                head_width,
+                ..
+            } => {
+                let pad = pad.max(head_width / 2.0 + 2.0);
+                [
+                    tail[0].min(tip[0]) - pad,
+                    tail[1].min(tip[1]) - pad,
+                    tail[0].max(tip[0]) + pad,
+                    tail[1].max(tip[1]) + pad,
+                ]
+            }
+        }
+    }
+
+    /// Straight RGBA color of the annotation at output pixel position `point`. This is the
+    /// CPU twin of `annotation.wgsl`'s fragment shader.
+    pub fn shade(&self, point: [f32; 2]) -> [f32; 4] {
+        let half_stroke = self.stroke_width / 2.0;
+        let (fill, stroke) = match self.shape {
+            AnnotationShape::Rectangle {
+                center,
+                half_size,
+                rotation,
+            } => {
+                let d = box_distance(unrotate(point, center, rotation), half_size);
+                (coverage(d), coverage(d.abs() - half_stroke))
+            }
+            AnnotationShape::Ellipse {
+                center,
+                half_size,
+                rotation,
+            } => {
+                let d = ellipse_distance(unrotate(point, center, rotation), half_size);
+                (coverage(d), coverage(d.abs() - half_stroke))
+            }
+            AnnotationShape::Arrow {
+                tail,
+                tip,
+                head_length,
+                head_width,
+            } => {
+                let (dx, dy) = (tip[0] - tail[0], tip[1] - tail[1]);
+                let length = dx.hypot(dy);
+                if length < 1e-3 {
+                    return [0.0; 4];
+                }
+
+                let direction = [dx / length, dy / length];
+                let head_length = head_length.min(length);
+                let base = [
+                    tip[0] - direction[0] * head_length,
+                    tip[1] - direction[1] * head_length,
+                ];
+                let normal = [
+                    -direction[1] * head_width / 2.0,
+                    direction[0] * head_width / 2.0,
+                ];
+                let head = triangle_distance(
+                    point,
+                    tip,
+                    [base[0] + normal[0], base[1] + normal[1]],
+                    [base[0] - normal[0], base[1] - normal[1]],
+                );
+                let shaft = segment_distance(point, tail, base) - half_stroke;
+
+                (0.0, coverage(shaft.min(head)))
+            }
+        };
+
+        let fill_alpha = self.fill_color[3] * fill;
+        let stroke_alpha = self.stroke_color[3] * stroke;
+        let alpha = stroke_alpha + fill_alpha * (1.0 - stroke_alpha);
+        if alpha <= 0.0 {
+            return [0.0; 4];
+        }
+
+        let mut color = [0.0; 4];
+        for (i, channel) in color.iter_mut().take(3).enumerate() {
+            *channel = (self.stroke_color[i] * stroke_alpha
+                + self.fill_color[i] * fill_alpha * (1.0 - stroke_alpha))
+                / alpha;
+        }
+        color[3] = alpha * self.opacity;
+        color
+    }
+}
+
+/// Anti-aliased coverage of a pixel whose centre is `distance` outside a shape's edge.
+fn coverage(distance: f32) -> f32 {
+    (0.5 - distance).clamp(0.0, 1.0)
+}
+
+fn unrotate(point: [f32; 2], center: [f32; 2], rotation: f32) -> [f32; 2] {
+    let (sin, cos) = rotation.sin_cos();
+    let (x, y) = (point[0] - center[0], point[1] - center[1]);
+    [x * cos + y * sin, -x * sin + y * cos]
+}
+
+fn box_distance(p: [f32; 2], half_size: [f32; 2]) -> f32 {
+    let q = [p[0].abs() - half_size[0], p[1].abs() - half_size[1]];
+    q[0].max(0.0).hypot(q[1].max(0.0)) + q[0].max(q[1]).min(0.0)
+}
+
+/// Approximate signed distance to an axis-aligned ellipse, exact on its axes.
+fn ellipse_distance(p: [f32; 2], radii: [f32; 2]) -> f32 {
+    let radii = [radii[0].max(1e-3), radii[1].max(1e-3)];
+    let k0 = (p[0] / radii[0]).hypot(p[1] / radii[1]);
+    let k1 = (p[0] / (radii[0] * radii[0])).hypot(p[1] / (radii[1] * radii[1]));
+    if k1 <= 1e-6 {
+        return -radii[0].min(radii[1]);
+    }
+
+    k0 * (k0 - 1.0) / k1
+}
+
+fn segment_distance(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let pa = [p[0] - a[0], p[1] - a[1]];
+    let ba = [b[0] - a[0], b[1] - a[1]];
+    let len2 = ba[0] * ba[0] + ba[1] * ba[1];
+    let h = if len2 > 0.0 {
+        ((pa[0] * ba[0] + pa[1] * ba[1]) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (pa[0] - ba[0] * h).hypot(pa[1] - ba[1] * h)
+}
+
+fn triangle_distance(p: [f32; 2], a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+    let edge = |from: [f32; 2], to: [f32; 2]| {
+        let cross = (to[0] - from[0]) * (p[1] - from[1]) - (to[1] - from[1]) * (p[0] - from[0]);
+        (segment_distance(p, from, to), cross)
+    };
+    let (ab, c0) = edge(a, b);
+    let (bc, c1) = edge(b, c);
+    let (ca, c2) = edge(c, a);
+    let distance = ab.min(bc).min(ca);
+    let inside = (c0 >= 0.0 && c1 >= 0.0 && c2 >= 0.0) || (c0 <= 0.0 && c1 <= 0.0 && c2 <= 0.0);
+
+    if inside { -distance } else { distance }
+}
+
+/// Parses the CSS colors the annotation editor writes: `#rgb`, `#rrggbb`, `#rrggbbaa` and
+/// `transparent`.
+fn parse_css_color(color: &str) -> Option<[f32; 4]> {
+    let color = color.trim();
+    if color.eq_ignore_ascii_case("transparent") {
+        return Some([0.0; 4]);
+    }
+
+    let hex = color.strip_prefix('#')?;
+    let channel = |digits: &str| {
+        u8::from_str_radix(digits, 16)
+            .ok()
+            .map(|v| v as f32 / 255.0)
+    };
+    let expanded;
+    let hex = if hex.len() == 3 {
+        expanded = hex.chars().flat_map(|c| [c, c]).collect::<String>();
+        expanded.as_str()
+    } else {
+        hex
+    };
+
+    match hex.len() {
+        6 => Some([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+            1.0,
+        ]),
+        8 => Some([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+            channel(&hex[6..8])?,
+        ]),
+        _ => None,
+    }
+}
+
+fn interpolate_vector(base: XY<f64>, keys: &[TextVectorKeyframe], time: f64) -> XY<f64> {
+    if keys.is_empty() {
+        return base;
+    }
+
+    let mut sorted = keys.to_vec();
+    sorted.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    if time <= sorted[0].time {
+        return XY::new(sorted[0].x, sorted[0].y);
+    }
+
+    for window in sorted.windows(2) {
+        let prev = &window[0];
+        let next = &window[1];
+        if time <= next.time {
+            let span = (next.time - prev.time).max(1e-6);
+            let t = prev.easing.apply((time - prev.time) / span);
+            return XY::new(
+                prev.x + (next.x - prev.x) * t,
+                prev.y + (next.y - prev.y) * t,
+            );
+        }
+    }
+
+    let last = sorted.last().unwrap();
+    XY::new(last.x, last.y)
+}
+
+fn interpolate_scalar(base: f64, keys: &[TextScalarKeyframe], time: f64) -> f64 {
+    if keys.is_empty() {
+        return base;
+    }
+
+    let mut sorted = keys.to_vec();
+    sorted.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    if time <= sorted[0].time {
+        return sorted[0].value;
+    }
+
+    for window in sorted.windows(2) {
+        let prev = &window[0];
+        let next = &window[1];
+        if time <= next.time {
+            let span = (next.time - prev.time).max(1e-6);
+            let t = prev.easing.apply((time - prev.time) / span);
+            return prev.value + (next.value - prev.value) * t;
+        }
+    }
+
+    sorted.last().map(|k| k.value).unwrap_or(base)
+}
+
+pub fn prepare_annotations(
+    output_size: XY<u32>,
+    frame_time: f64,
+    segments: &[AnnotationSegment],
+) -> PreparedAnnotations {
+    let mut prepared = PreparedAnnotations::default();
+    let output = [output_size.x as f32, output_size.y as f32];
+    let pixel_scale = output[1] / BASE_HEIGHT;
+
+    for segment in segments.iter().filter(|s| s.enabled) {
+        if frame_time < segment.start || frame_time > segment.end {
+            continue;
+        }
+
+        let annotation = &segment.annotation;
+        let keyframes = &segment.keyframes;
+        let relative_time = (frame_time - segment.start).max(0.0);
+        let position = interpolate_vector(
+            XY::new(annotation.x, annotation.y),
+            &keyframes.position,
+            relative_time,
+        );
+        let size = interpolate_vector(
+            XY::new(annotation.width, annotation.height),
+            &keyframes.size,
+            relative_time,
+        );
+
+        let fade_duration = segment.fade_duration.max(0.0);
+        let fade = if fade_duration > 0.0 {
+            let fade_in = (relative_time / fade_duration).min(1.0);
+            let fade_out = ((segment.end - frame_time).max(0.0) / fade_duration).min(1.0);
+            fade_in * fade_out
+        } else {
+            1.0
+        };
+        let opacity = (interpolate_scalar(annotation.opacity, &keyframes.opacity, relative_time)
+            .clamp(0.0, 1.0)
+            * fade) as f32;
+
+        let origin = [position.x as f32 * output[0], position.y as f32 * output[1]];
+        let extent = [size.x as f32 * output[0], size.y as f32 * output[1]];
+        let center = [origin[0] + extent[0] / 2.0, origin[1] + extent[1] / 2.0];
+        let half_size = [extent[0].abs() / 2.0, extent[1].abs() / 2.0];
+        let rotation = (annotation.rotation as f32).to_radians();
+        let stroke_width = annotation.stroke_width.max(0.0) as f32 * pixel_scale;
+        let stroke_color = parse_css_color(&annotation.stroke_color).unwrap_or([1.0; 4]);
+        let fill_color = parse_css_color(&annotation.fill_color).unwrap_or([0.0; 4]);
+
+        let shape = match annotation.annotation_type {
+            AnnotationType::Rectangle => AnnotationShape::Rectangle {
+                center,
+                half_size,
+                rotation,
+            },
+            AnnotationType::Circle => AnnotationShape::Ellipse {
+                center,
+                half_size,
+                rotation,
+            },
+            AnnotationType::Arrow => {
+                let progress = if segment.draw_on_duration > 0.0 {
+                    let t = (relative_time / segment.draw_on_duration).clamp(0.0, 1.0) as f32;
+                    1.0 - (1.0 - t).powi(3)
+                } else {
+                    1.0
+                };
+                // Head proportions match the screenshot editor's `arrow.ts`.
+                let width = stroke_width.max(pixel_scale);
+                AnnotationShape::Arrow {
+                    tail: origin,
+                    tip: [
+                        origin[0] + extent[0] * progress,
+                        origin[1] + extent[1] * progress,
+                    ],
+                    head_length: (20.0 * pixel_scale).max(width * 6.0),
+                    head_width: (14.0 * pixel_scale).max(width * 5.0),
+                }
+            }
+            AnnotationType::Text => {
+                let Some(content) = annotation.text.as_deref().filter(|t| !t.is_empty()) else {
+                    continue;
+                };
+
+                // Like the screenshot editor, the box height is the font size.
+                let left = origin[0].min(origin[0] + extent[0]);
+                let top = origin[1].min(origin[1] + extent[1]);
+                let right = if extent[0].abs() >= 1.0 {
+                    left + extent[0].abs()
+                } else {
+                    output[0]
+                };
+                let font_size = extent[1].abs().clamp(1.0, MAX_FONT_SIZE_PX);
+
+                prepared.texts.push(PreparedText {
+                    content: content.to_string(),
+                    bounds: [left, top, right, top + font_size * 1.25],
+                    color: stroke_color,
+                    font_family: "sans-serif".to_string(),
+                    font_size,
+                    font_weight: 400.0,
+                    italic: false,
+                    opacity,
+                    rotation,
+                    font_path: None,
+                    align: TextAlign::Left,
+                    letter_spacing: 0.0,
+                    line_height: 1.25,
+                    outline: None,
+                    shadow: None,
+                    background: None,
+                });
+                continue;
+            }
+            AnnotationType::Mask => {
+                // The mask layer only pixelates, so blur masks are drawn pixelated too.
+                let level = annotation.mask_level.unwrap_or(16.0).max(1.0) as f32;
+
+                prepared.masks.push(PreparedMask {
+                    center: XY::new(center[0] / output[0], center[1] / output[1]),
+                    size: XY::new(
+                        half_size[0] * 2.0 / output[0],
+                        half_size[1] * 2.0 / output[1],
+                    ),
+                    feather: 0.0001,
+                    opacity,
+                    pixel_size: level * pixel_scale,
+                    darkness: 0.0,
+                    mode: MaskRenderMode::from_kind(MaskKind::Sensitive),
+                    output_size,
+                });
+                continue;
+            }
+        };
+
+        prepared.shapes.push(PreparedAnnotation {
+            shape,
+            stroke_color,
+            fill_color,
+            stroke_width,
+            opacity,
+        });
+    }
+
+    prepared
+}
+
+#[cfg(test)]
+mod tests {
+    use cap_project::{Annotation, MaskType};
+
+    use super::*;
+
+    fn segment(annotation_type: AnnotationType, draw_on_duration: f64) -> AnnotationSegment {
+        AnnotationSegment {
+            start: 0.0,
+            end: 4.0,
+            track: 0,
+            enabled: true,
+            annotation: Annotation {
+                id: "a".to_string(),
+                annotation_type,
+                x: 0.25,
+                y: 0.5,
+                width: 0.5,
+                height: 0.0,
+                stroke_color: "#ff0000".to_string(),
+                stroke_width: 4.0,
+                fill_color: "transparent".to_string(),
+                opacity: 1.0,
+                rotation: 0.0,
+                text: None,
+                mask_type: None,
+                mask_level: None,
+            },
+            fade_duration: 0.0,
+            draw_on_duration,
+            keyframes: Default::default(),
+        }
+    }
+
+    #[test]
+    fn arrows_draw_on_from_the_tail() {
+        let arrow = segment(AnnotationType::Arrow, 2.0);
+
+        let start = prepare_annotations(XY::new(1920, 1080), 0.0, std::slice::from_ref(&arrow));
+        let done = prepare_annotations(XY::new(1920, 1080), 3.0, std::slice::from_ref(&arrow));
+
+        let AnnotationShape::Arrow { tail, tip, .. } = start.shapes[0].shape else {
+            panic!("expected an arrow");
+        };
+        assert_eq!(tail, tip);
+        let AnnotationShape::Arrow { tip, .. } = done.shapes[0].shape else {
+            panic!("expected an arrow");
+        };
+        assert_eq!(tip, [1440.0, 540.0]);
+
+        // The head is solid at the tip and nothing is drawn past it.
+        assert_eq!(done.shapes[0].shade([1435.0, 540.0])[3], 1.0);
+        assert_eq!(done.shapes[0].shade([1450.0, 540.0])[3], 0.0);
+    }
+
+    #[test]
+    fn rectangles_stroke_their_edge_and_fill_inside() {
+        let mut rectangle = segment(AnnotationType::Rectangle, 0.0);
+        rectangle.annotation.height = 0.5;
+        rectangle.annotation.fill_color = "#0000ff80".to_string();
+
+        let shape = prepare_annotations(XY::new(1000, 1000), 1.0, &[rectangle])
+            .shapes
+            .pop()
+            .unwrap();
+
+        let edge = shape.shade([250.0, 600.0]);
+        assert_eq!(edge, [1.0, 0.0, 0.0, 1.0]);
+        let inside = shape.shade([500.0, 750.0]);
+        assert!((inside[2] - 1.0).abs() < 1e-6 && (inside[3] - 128.0 / 255.0).abs() < 1e-6);
+        assert_eq!(shape.shade([100.0, 100.0])[3], 0.0);
+    }
+
+    #[test]
+    fn text_and_mask_annotations_use_the_text_and_mask_layers() {
+        let mut text = segment(AnnotationType::Text, 0.0);
+        text.annotation.text = Some("Look here".to_string());
+        text.annotation.height = 0.05;
+        let mut mask = segment(AnnotationType::Mask, 0.0);
+        mask.annotation.mask_type = Some(MaskType::Pixelate);
+        mask.annotation.mask_level = Some(12.0);
+
+        let prepared = prepare_annotations(XY::new(1920, 1080), 1.0, &[text, mask]);
+
+        assert!(prepared.shapes.is_empty());
+        assert_eq!(prepared.texts[0].content, "Look here");
+        assert_eq!(prepared.texts[0].font_size, 54.0);
+        assert_eq!(prepared.masks[0].pixel_size, 12.0);
+    }
+}
@@ -0,0 +1,2067 @@
+//! Pure-CPU compositor for exporting on machines without any wgpu adapter.
+//!
+//! Every layer is a port of its WGSL shader running over an 8-bit RGBA canvas.
+//! The canvas is quantized after each pass and blended in gamma space, exactly
+//! like the `Rgba8Unorm` render targets of the GPU path, so both backends produce
+//! pixel-comparable frames. Camera background blur and the keyboard overlay have
+//! no CPU implementation and are skipped with a warning.
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
+
+use cap_project::{
+    CursorEvents, CursorType, ProjectConfiguration, RecordingMeta, StudioRecordingMeta, XY,
+};
+use glyphon::{Buffer, Color, FontSystem, SwashCache, SwashContent, TextBounds};
+use rayon::prelude::*;
+use tokio::sync::mpsc;
+
+use crate::{
+    DECODE_MAX_RETRIES_INITIAL, DECODE_MAX_RETRIES_STEADY, DecodedFrame, DecodedSegmentFrames,
+    MAX_INITIAL_CONSECUTIVE_FAILURES, MaskRenderMode, Nv12RenderedFrame, PixelFormat,
+    PrecomputedCursorTimeline, PreparedAnnotation, PreparedMask, PreparedWatermark,
+    ProjectRecordingsMeta, ProjectUniforms, RenderOptions, RenderSegment, RenderedFrame,
+    RenderingError, SegmentRecordings, ZoomFocusInterpolator, blend_transition,
+    composite_frame::CompositeVideoFrameUniforms,
+    cpu_yuv, decode_segment_frames_with_retry, decode_transition_frames,
+    frame_pipeline::NV12BufferPool,
+    get_duration,
+    layers::{
+        Background, BundledFonts, CaptionBackgroundUniforms, CaptionFrame, CursorImage,
+        GradientOrColorUniforms, MAX_BACKGROUND_DIMENSION, clean_background_path, cursor_uniforms,
+        decode_background_rgba, image_background_insets, layout_caption_frame, new_font_system,
+        shape_text,
+    },
+    spring_mass_damper::SpringMassDamperSimulationConfig,
+    text::PreparedText,
+};
+
+/// Everything the CPU compositor needs to know about a recording; the
+/// device-less counterpart of [`crate::RenderVideoConstants`].
+pub struct CpuRenderConstants {
+    pub options: RenderOptions,
+    pub meta: StudioRecordingMeta,
+    pub recording_meta: RecordingMeta,
+}
+
+impl CpuRenderConstants {
+    pub fn new(
+        segments: &[SegmentRecordings],
+        recording_meta: RecordingMeta,
+        meta: StudioRecordingMeta,
+    ) -> Result<Self, RenderingError> {
+        Ok(Self {
+            options: RenderOptions::from_segments(segments)?,
+            meta,
+            recording_meta,
+        })
+    }
+}
+
+struct CachedBackground {
+    background: Background,
+    blur: f64,
+    size: (u32, u32),
+    pixels: Vec<u8>,
+}
+
+pub struct CpuCompositor<'a> {
+    constants: &'a CpuRenderConstants,
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+    bundled_fonts: BundledFonts,
+    images: HashMap<String, Option<Arc<Texture>>>,
+    cursors: HashMap<(String, bool), Option<Arc<CursorTexture>>>,
+    circle_cursor: Option<Arc<CursorTexture>>,
+    background: Option<CachedBackground>,
+    display_texture: Option<Texture>,
+    camera_texture: Option<Texture>,
+    warned_camera_blur: bool,
+    warned_keyboard: bool,
+}
+
+impl<'a> CpuCompositor<'a> {
+    pub fn new(constants: &'a CpuRenderConstants) -> Self {
+        Self {
+            constants,
+            font_system: new_font_system(),
+            swash_cache: SwashCache::new(),
+            bundled_fonts: BundledFonts::default(),
+            images: HashMap::new(),
+            cursors: HashMap::new(),
+            circle_cursor: None,
+            background: None,
+            display_texture: None,
+            camera_texture: None,
+            warned_camera_blur: false,
+            warned_keyboard: false,
+        }
+    }
+
+    /// Composites one frame in the same layer order as `RendererLayers::render`.
+    pub fn render(
+        &mut self,
+        segment_frames: DecodedSegmentFrames,
+        uniforms: ProjectUniforms,
+        cursor: &CursorEvents,
+        render_display: bool,
+    ) -> RenderedFrame {
+        let (width, height) = uniforms.output_size;
+        self.warn_unsupported_layers(&uniforms);
+
+        let mut canvas = self.background(&uniforms);
+
+        if render_display
+            && let Some(texture) = segment_frames
+                .screen_frame
+                .as_ref()
+                .and_then(frame_to_texture)
+        {
+            self.display_texture = Some(texture);
+        }
+
+        let has_camera =
+            segment_frames.segment_has_camera && self.constants.options.camera_size.is_some();
+        if has_camera
+            && let Some(texture) = segment_frames
+                .camera_frame
+                .as_ref()
+                .and_then(frame_to_texture)
+        {
+            self.camera_texture = Some(texture);
+        }
+
+        let should_render_screen = render_display && uniforms.scene.should_render_screen();
+        let should_render_cursor = if render_display {
+            uniforms.scene.should_render_screen()
+        } else {
+            true
+        };
+
+        if should_render_screen && let Some(texture) = &self.display_texture {
+            draw_composite_frame(&mut canvas, width, height, &uniforms.display, texture);
+        }
+
+        if should_render_cursor {
+            self.draw_cursor(&mut canvas, &uniforms, &segment_frames, cursor);
+        }
+
+        let camera_uniforms = |camera: Option<CompositeVideoFrameUniforms>| {
+            camera.filter(|_| segment_frames.segment_has_camera)
+        };
+
+        if uniforms.scene.is_transitioning_camera_only()
+            && let Some(camera_only) = camera_uniforms(uniforms.camera_only)
+            && let Some(texture) = &self.camera_texture
+        {
+            draw_composite_frame(&mut canvas, width, height, &camera_only, texture);
+        }
+
+        if uniforms.scene.should_render_camera()
+            && uniforms.scene.regular_camera_transition_opacity() > 0.01
+            && let Some(camera) = camera_uniforms(uniforms.camera)
+            && let Some(texture) = &self.camera_texture
+        {
+            draw_composite_frame(&mut canvas, width, height, &camera, texture);
+        }
+
+        for mask in &uniforms.masks {
+            canvas = apply_mask(canvas, width, height, mask);
+        }
+
+        for annotation in &uniforms.annotations {
+            draw_annotation(&mut canvas, width, height, annotation);
+        }
+
+        let screen = [0, 0, width, height];
+        for text in &uniforms.texts {
+            let family = self.bundled_fonts.family(
+                &mut self.font_system,
+                &self.constants.recording_meta.project_path,
+                text,
+            );
+            let shaped = shape_text(&mut self.font_system, text, family.as_deref());
+            let rotated = text.rotation.abs() >= 1e-4;
+
+            // Rotated text is drawn unrotated into its own layer first, like the GPU text
+            // layer does, then composited with the rotation applied.
+            let mut layer = rotated.then(|| vec![0u8; canvas.len()]);
+            let target = layer.as_deref_mut().unwrap_or(&mut canvas[..]);
+
+            if let Some(background) = &shaped.background {
+                let [x, y, w, h] = background.rect;
+                let scissor = [
+                    (x - 2.0).max(0.0) as u32,
+                    (y - 2.0).max(0.0) as u32,
+                    (w + 4.0).ceil() as u32,
+                    (h + 4.0).ceil() as u32,
+                ];
+                draw_caption_background(target, width, background, scissor);
+            }
+
+            for pass in &shaped.passes {
+                draw_text(
+                    target,
+                    width,
+                    &mut self.font_system,
+                    &mut self.swash_cache,
+                    &shaped.buffer,
+                    (pass.left, pass.top),
+                    1.0,
+                    shaped.bounds,
+                    pass.color,
+                    screen,
+                );
+            }
+
+            if let Some(layer) = layer {
+                draw_rotated_layer(&mut canvas, width, height, layer, text);
+            }
+        }
+
+        if let Some(frame) =
+            layout_caption_frame(&mut self.font_system, &uniforms, XY::new(width, height))
+        {
+            self.draw_caption(&mut canvas, width, height, &frame);
+        }
+
+        if let Some(watermark) = &uniforms.watermark {
+            self.draw_watermark(&mut canvas, width, height, watermark);
+        }
+
+        RenderedFrame {
+            data: Arc::new(canvas),
+            width,
+            height,
+            padded_bytes_per_row: width * 4,
+            frame_number: uniforms.frame_number,
+            target_time_ns: (uniforms.frame_number as u64 * 1_000_000_000)
+                / uniforms.frame_rate.max(1) as u64,
+        }
+    }
+
+    fn warn_unsupported_layers(&mut self, uniforms: &ProjectUniforms) {
+        if !self.warned_camera_blur
+            && crate::blur_mode_from_config(&uniforms.project.camera.background_blur).is_some()
+        {
+            self.warned_camera_blur = true;
+            tracing::warn!("Camera background blur is not supported by the CPU compositor");
+        }
+
+        let keyboard_enabled = uniforms
+            .project
+            .keyboard
+            .as_ref()
+            .is_some_and(|keyboard| keyboard.settings.enabled)
+            && uniforms
+                .project
+                .timeline
+                .as_ref()
+                .is_some_and(|timeline| !timeline.keyboard_segments.is_empty());
+        if !self.warned_keyboard && keyboard_enabled {
+            self.warned_keyboard = true;
+            tracing::warn!("Keyboard overlay is not supported by the CPU compositor");
+        }
+    }
+
+    fn image(&mut self, path: &str) -> Option<Arc<Texture>> {
+        self.images
+            .entry(path.to_string())
+            .or_insert_with(
+                || match decode_background_rgba(path, MAX_BACKGROUND_DIMENSION) {
+                    Ok((data, width, height)) => Some(Arc::new(Texture {
+                        width,
+                        height,
+                        data,
+                    })),
+                    Err(e) => {
+                        tracing::warn!("Failed to load image '{}': {}", path, e);
+                        None
+                    }
+                },
+            )
+            .clone()
+    }
+
+    /// Background and background blur only change with the project config, so
+    /// the composited result is reused until they do.
+    fn background(&mut self, uniforms: &ProjectUniforms) -> Vec<u8> {
+        let size = uniforms.output_size;
+        let background = Background::from_source(
+            uniforms.project.background.source.clone(),
+            self.constants.options.preserve_screen_alpha,
+        );
+        let blur = uniforms.project.background.blur;
+
+        if let Some(cached) = &self.background
+            && cached.background == background
+            && cached.blur == blur
+            && cached.size == size
+        {
+            return cached.pixels.clone();
+        }
+
+        let image = match &background {
+            Background::Image { path } => Some(self.image(path)),
+            _ => None,
+        };
+
+        let mut pixels = match image {
+            Some(Some(texture)) => image_background(&texture, size),
+            Some(None) => color_background(
+                &GradientOrColorUniforms::from(Background::Color([1.0, 1.0, 1.0, 1.0])),
+                size,
+            ),
+            None => color_background(&GradientOrColorUniforms::from(background.clone()), size),
+        };
+
+        if blur > 0.0 {
+            pixels = blur_background(pixels, size, blur as f32 / 100.0);
+        }
+
+        self.background = Some(CachedBackground {
+            background,
+            blur,
+            size,
+            pixels: pixels.clone(),
+        });
+
+        pixels
+    }
+
+    fn cursor_image(&mut self, cursor_id: &str, use_svg: bool) -> Option<Arc<CursorTexture>> {
+        let constants = self.constants;
+        self.cursors
+            .entry((cursor_id.to_string(), use_svg))
+            .or_insert_with(|| {
+                let image = CursorImage::load(
+                    &constants.recording_meta,
+                    &constants.meta,
+                    cursor_id,
+                    use_svg,
+                );
+                if image.is_none() {
+                    tracing::error!("Cursor {:?} not found!", cursor_id);
+                }
+                image.map(cursor_texture)
+            })
+            .clone()
+    }
+
+    fn draw_cursor(
+        &mut self,
+        canvas: &mut [u8],
+        uniforms: &ProjectUniforms,
+        segment_frames: &DecodedSegmentFrames,
+        cursor: &CursorEvents,
+    ) {
+        if uniforms.project.cursor.hide {
+            return;
+        }
+
+        let Some(interpolated_cursor) = &uniforms.interpolated_cursor else {
+            return;
+        };
+
+        let cursor_uv = &interpolated_cursor.position.coord;
+        if !(0.0..=1.0).contains(&cursor_uv.x) || !(0.0..=1.0).contains(&cursor_uv.y) {
+            return;
+        }
+
+        let image = if uniforms.project.cursor.cursor_type() == &CursorType::Circle {
+            self.circle_cursor
+                .get_or_insert_with(|| cursor_texture(CursorImage::circle()))
+                .clone()
+        } else {
+            let Some(image) = self.cursor_image(
+                &interpolated_cursor.cursor_id,
+                uniforms.project.cursor.use_svg,
+            ) else {
+                return;
+            };
+            image
+        };
+
+        let texture = &image.texture;
+        let cursor_uniforms = cursor_uniforms(
+            &self.constants.options,
+            uniforms,
+            interpolated_cursor,
+            cursor,
+            segment_frames.recording_time,
+            uniforms.resolution_base,
+            &uniforms.zoom,
+            (texture.width, texture.height),
+            image.hotspot,
+        );
+
+        let (width, height) = uniforms.output_size;
+        draw_cursor_quad(
+            canvas,
+            width,
+            height,
+            &cursor_uniforms.position_size,
+            &cursor_uniforms.motion_vector_strength,
+            &cursor_uniforms.rotation_params,
+            texture,
+        );
+    }
+
+    fn draw_caption(&mut self, canvas: &mut [u8], width: u32, height: u32, frame: &CaptionFrame) {
+        let text_scissor = if let Some(scissor) = frame.background_scissor {
+            draw_caption_background(canvas, width, &frame.background, scissor);
+
+            if let Some((highlight, highlight_scissor)) = &frame.highlight {
+                draw_caption_background(canvas, width, highlight, *highlight_scissor);
+            }
+
+            scissor
+        } else {
+            [0, 0, width, height]
+        };
+
+        for pass in &frame.text_passes {
+            draw_text(
+                canvas,
+                width,
+                &mut self.font_system,
+                &mut self.swash_cache,
+                &frame.buffer,
+                (pass.left, pass.top),
+                frame.scale,
+                frame.bounds,
+                pass.color,
+                text_scissor,
+            );
+        }
+    }
+
+    fn draw_watermark(
+        &mut self,
+        canvas: &mut [u8],
+        width: u32,
+        height: u32,
+        watermark: &PreparedWatermark,
+    ) {
+        let Some(texture) =
+            clean_background_path(&watermark.image_path).and_then(|path| self.image(&path))
+        else {
+            return;
+        };
+
+        let (position, size) = watermark.rect(XY::new(texture.width, texture.height));
+        if size.x < 1.0 || size.y < 1.0 {
+            return;
+        }
+
+        let Some(rect) = pixel_rect(
+            [position.x, position.y],
+            [position.x + size.x, position.y + size.y],
+            width,
+            height,
+        ) else {
+            return;
+        };
+
+        let opacity = watermark.opacity;
+        for_each_pixel(canvas, width, rect, |x, y, pixel| {
+            let u = (x as f32 + 0.5 - position.x) / size.x;
+            let v = (y as f32 + 0.5 - position.y) / size.y;
+            if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                return;
+            }
+
+            let color = texture.sample([u, v]);
+            let alpha = color[3] * opacity;
+            blend_premultiplied(
+                pixel,
+                [color[0] * alpha, color[1] * alpha, color[2] * alpha, alpha],
+            );
+        });
+    }
+}
+
+struct CursorTexture {
+    texture: Texture,
+    hotspot: XY<f64>,
+}
+
+fn cursor_texture(image: CursorImage) -> Arc<CursorTexture> {
+    Arc::new(CursorTexture {
+        texture: Texture {
+            width: image.width,
+            height: image.height,
+            data: image.rgba,
+        },
+        hotspot: image.hotspot,
+    })
+}
+
+/// Renders a project on the CPU and sends RGBA frames, mirroring
+/// [`crate::render_video_to_channel`].
+#[allow(clippy::too_many_arguments)]
+pub async fn render_video_to_channel(
+    constants: &CpuRenderConstants,
+    project: &ProjectConfiguration,
+    sender: mpsc::Sender<(RenderedFrame, u32)>,
+    recording_meta: &RecordingMeta,
+    meta: &StudioRecordingMeta,
+    render_segments: Vec<RenderSegment>,
+    fps: u32,
+    resolution_base: XY<u32>,
+    recordings: &ProjectRecordingsMeta,
+) -> Result<(), RenderingError> {
+    render_frames(
+        constants,
+        project,
+        FrameSink::Rgba(sender),
+        recording_meta,
+        meta,
+        render_segments,
+        fps,
+        resolution_base,
+        recordings,
+    )
+    .await
+}
+
+/// Renders a project on the CPU and sends NV12 frames, mirroring
+/// [`crate::render_video_to_channel_nv12`].
+#[allow(clippy::too_many_arguments)]
+pub async fn render_video_to_channel_nv12(
+    constants: &CpuRenderConstants,
+    project: &ProjectConfiguration,
+    sender: mpsc::Sender<(Nv12RenderedFrame, u32)>,
+    recording_meta: &RecordingMeta,
+    meta: &StudioRecordingMeta,
+    render_segments: Vec<RenderSegment>,
+    fps: u32,
+    resolution_base: XY<u32>,
+    recordings: &ProjectRecordingsMeta,
+    stop_after_frames_sent: Option<u32>,
+) -> Result<(), RenderingError> {
+    render_frames(
+        constants,
+        project,
+        FrameSink::Nv12 {
+            sender,
+            pool: NV12BufferPool::new(6),
+            stop_after_frames_sent,
+            frames_sent: 0,
+        },
+        recording_meta,
+        meta,
+        render_segments,
+        fps,
+        resolution_base,
+        recordings,
+    )
+    .await
+}
+
+enum FrameSink {
+    Rgba(mpsc::Sender<(RenderedFrame, u32)>),
+    Nv12 {
+        sender: mpsc::Sender<(Nv12RenderedFrame, u32)>,
+        pool: NV12BufferPool,
+        stop_after_frames_sent: Option<u32>,
+        frames_sent: u32,
+    },
+}
+
+impl FrameSink {
+    /// Returns `false` once the receiver does not want any more frames.
+    async fn send(
+        &mut self,
+        frame: RenderedFrame,
+        frame_number: u32,
+    ) -> Result<bool, RenderingError> {
+        match self {
+            Self::Rgba(sender) => {
+                sender.send((frame, frame_number)).await?;
+                Ok(true)
+            }
+            Self::Nv12 {
+                sender,
+                pool,
+                stop_after_frames_sent,
+                frames_sent,
+            } => {
+                sender
+                    .send((Nv12RenderedFrame::from_rgba(&frame, pool), frame_number))
+                    .await?;
+
+                *frames_sent += 1;
+                Ok(!stop_after_frames_sent.is_some_and(|max| *frames_sent >= max))
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn render_frames(
+    constants: &CpuRenderConstants,
+    project: &ProjectConfiguration,
+    mut sink: FrameSink,
+    recording_meta: &RecordingMeta,
+    meta: &StudioRecordingMeta,
+    render_segments: Vec<RenderSegment>,
+    fps: u32,
+    resolution_base: XY<u32>,
+    recordings: &ProjectRecordingsMeta,
+) -> Result<(), RenderingError> {
+    ffmpeg::init().unwrap();
+
+    let start_time = Instant::now();
+
+    let duration = get_duration(recordings, recording_meta, meta, project);
+
+    let total_frames = (fps as f64 * duration).ceil() as u32;
+
+    let cursor_smoothing = (!project.cursor.raw).then_some(SpringMassDamperSimulationConfig {
+        tension: project.cursor.tension,
+        mass: project.cursor.mass,
+        friction: project.cursor.friction,
+    });
+
+    let click_spring = project.cursor.click_spring_config();
+
+    let precomputed_cursor_timelines: Vec<Arc<PrecomputedCursorTimeline>> = render_segments
+        .iter()
+        .map(|segment| {
+            Arc::new(PrecomputedCursorTimeline::new(
+                &segment.cursor,
+                cursor_smoothing,
+                Some(click_spring),
+            ))
+        })
+        .collect();
+
+    let mut zoom_focus_interpolators: Vec<ZoomFocusInterpolator> = render_segments
+        .iter()
+        .zip(precomputed_cursor_timelines.iter())
+        .map(|(segment, precomputed_cursor)| {
+            ZoomFocusInterpolator::new_with_precomputed_cursor(
+                &segment.cursor,
+                cursor_smoothing,
+                click_spring,
+                project.screen_movement_spring,
+                duration,
+                project
+                    .timeline
+                    .as_ref()
+                    .map(|t| t.zoom_segments.as_slice())
+                    .unwrap_or(&[]),
+                Some(precomputed_cursor.clone()),
+            )
+        })
+        .collect();
+
+    let mut compositor = CpuCompositor::new(constants);
+
+    let needs_camera = !project.camera.hide;
+    let mut last_successful_frame: Option<RenderedFrame> = None;
+    let mut consecutive_failures = 0u32;
+    const MAX_CONSECUTIVE_FAILURES: u32 = 200;
+
+    let mut frame_number = 0;
+
+    while frame_number < total_frames {
+        let Some((segment_time, segment)) =
+            project.get_segment_time(frame_number as f64 / fps as f64)
+        else {
+            break;
+        };
+
+        let clip_config = project
+            .clips
+            .iter()
+            .find(|v| v.index == segment.recording_clip);
+
+        let current_frame_number = frame_number;
+        frame_number += 1;
+
+        if let Some(transition) = project.get_transition(current_frame_number as f64 / fps as f64)
+            && let Some(sides) = decode_transition_frames(
+                project,
+                recordings,
+                &render_segments,
+                &mut zoom_focus_interpolators,
+                &transition,
+                current_frame_number,
+                fps,
+            )
+            .await
+        {
+            let [outgoing, incoming] = sides.map(|(clip_index, segment_frames)| {
+                let render_segment = &render_segments[clip_index];
+                let precomputed_cursor = &precomputed_cursor_timelines[clip_index];
+                let uniforms = ProjectUniforms::new_inner(
+                    &constants.options,
+                    project,
+                    current_frame_number,
+                    fps,
+                    resolution_base,
+                    &render_segment.cursor,
+                    &segment_frames,
+                    duration,
+                    &zoom_focus_interpolators[clip_index],
+                    &|time| precomputed_cursor.interpolate(time),
+                );
+                compositor.render(
+                    segment_frames,
+                    uniforms,
+                    &render_segment.cursor,
+                    render_segment.render_display,
+                )
+            });
+
+            let progress = transition.progress(current_frame_number as f64 / fps as f64);
+            let frame = blend_transition(transition.kind, progress as f32, &outgoing, &incoming);
+            consecutive_failures = 0;
+            last_successful_frame = Some(frame.clone());
+            if !sink.send(frame, current_frame_number).await? {
+                break;
+            }
+            continue;
+        }
+
+        let segment_clip_index = segment.recording_clip as usize;
+        let render_segment = &render_segments[segment_clip_index];
+        let is_initial_frame = current_frame_number == 0 || last_successful_frame.is_none();
+
+        let zoom_until = (current_frame_number as f32 + 1.0) / fps as f32;
+        zoom_focus_interpolators[segment_clip_index].ensure_precomputed_until(zoom_until);
+
+        let segment_frames = decode_segment_frames_with_retry(
+            &render_segment.decoders,
+            segment_time,
+            needs_camera,
+            render_segment.render_display,
+            clip_config.map(|v| v.offsets).unwrap_or_default(),
+            current_frame_number,
+            is_initial_frame,
+            fps,
+        )
+        .await;
+
+        let frame = if let Some(segment_frames) = segment_frames {
+            consecutive_failures = 0;
+
+            let precomputed_cursor = &precomputed_cursor_timelines[segment_clip_index];
+            let uniforms = ProjectUniforms::new_inner(
+                &constants.options,
+                project,
+                current_frame_number,
+                fps,
+                resolution_base,
+                &render_segment.cursor,
+                &segment_frames,
+                duration,
+                &zoom_focus_interpolators[segment_clip_index],
+                &|time| precomputed_cursor.interpolate(time),
+            );
+
+            let frame = compositor.render(
+                segment_frames,
+                uniforms,
+                &render_segment.cursor,
+                render_segment.render_display,
+            );
+            last_successful_frame = Some(frame.clone());
+            frame
+        } else {
+            consecutive_failures += 1;
+
+            if last_successful_frame.is_none()
+                && consecutive_failures >= MAX_INITIAL_CONSECUTIVE_FAILURES
+            {
+                tracing::error!(
+                    frame_number = current_frame_number,
+                    consecutive_failures = consecutive_failures,
+                    max_retries = DECODE_MAX_RETRIES_INITIAL,
+                    "No initial frame could be decoded - aborting export"
+                );
+                return Err(RenderingError::FrameDecodeFailed {
+                    frame_number: current_frame_number,
+                    consecutive_failures,
+                });
+            }
+
+            if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                tracing::error!(
+                    frame_number = current_frame_number,
+                    consecutive_failures = consecutive_failures,
+                    "Too many consecutive frame failures - aborting export"
+                );
+                return Err(RenderingError::FrameDecodeFailed {
+                    frame_number: current_frame_number,
+                    consecutive_failures,
+                });
+            }
+
+            let max_retries = if is_initial_frame {
+                DECODE_MAX_RETRIES_INITIAL
+            } else {
+                DECODE_MAX_RETRIES_STEADY
+            };
+
+            let Some(last_frame) = &last_successful_frame else {
+                tracing::error!(
+                    frame_number = current_frame_number,
+                    segment_time = segment_time,
+                    max_retries = max_retries,
+                    "First frame decode failed after retries - cannot continue"
+                );
+                continue;
+            };
+
+            tracing::warn!(
+                frame_number = current_frame_number,
+                segment_time = segment_time,
+                consecutive_failures = consecutive_failures,
+                max_retries = max_retries,
+                "Frame decode failed after retries - using previous frame"
+            );
+            let mut fallback = last_frame.clone();
+            fallback.frame_number = current_frame_number;
+            fallback.target_time_ns = (current_frame_number as u64 * 1_000_000_000) / fps as u64;
+            fallback
+        };
+
+        if !sink.send(frame, current_frame_number).await? {
+            break;
+        }
+    }
+
+    let total_time = start_time.elapsed();
+    tracing::info!(
+        frames = frame_number,
+        elapsed_secs = format!("{:.2}", total_time.as_secs_f32()),
+        "CPU render complete"
+    );
+
+    Ok(())
+}
+
+/// An RGBA8 image sampled like a single-mip `Rgba8Unorm` texture with
+/// clamp-to-edge addressing.
+struct Texture {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Texture {
+    fn texel(&self, x: i64, y: i64) -> [f32; 4] {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        let index = (y * self.width as usize + x) * 4;
+        load(&self.data[index..index + 4])
+    }
+
+    /// Bilinear sample at a top-left origin `uv`, like a `Linear` sampler.
+    fn sample(&self, uv: [f32; 2]) -> [f32; 4] {
+        let x = uv[0] * self.width as f32 - 0.5;
+        let y = uv[1] * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top_left = self.texel(x0, y0);
+        let top_right = self.texel(x0 + 1, y0);
+        let bottom_left = self.texel(x0, y0 + 1);
+        let bottom_right = self.texel(x0 + 1, y0 + 1);
+
+        std::array::from_fn(|i| {
+            let top = top_left[i] + (top_right[i] - top_left[i]) * fx;
+            let bottom = bottom_left[i] + (bottom_right[i] - bottom_left[i]) * fx;
+            top + (bottom - top) * fy
+        })
+    }
+
+    fn sample_nearest(&self, uv: [f32; 2]) -> [f32; 4] {
+        self.texel(
+            (uv[0] * self.width as f32).floor() as i64,
+            (uv[1] * self.height as f32).floor() as i64,
+        )
+    }
+}
+
+/// Converts a decoded frame to RGBA. Frames without CPU-readable planes (e.g.
+/// hardware surfaces) return `None` so the previous frame is kept.
+fn frame_to_texture(frame: &DecodedFrame) -> Option<Texture> {
+    let (width, height) = (frame.width(), frame.height());
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut data = vec![0u8; width as usize * height as usize * 4];
+    let chroma_len = frame.uv_stride() as usize * (height / 2) as usize;
+
+    match frame.format() {
+        PixelFormat::Rgba => data.copy_from_slice(frame.data().get(..data.len())?),
+        PixelFormat::Nv12 => {
+            let y_plane = frame.y_plane()?;
+            let uv_plane = frame.uv_plane().filter(|plane| plane.len() >= chroma_len)?;
+            cpu_yuv::nv12_to_rgba_simd(
+                y_plane,
+                uv_plane,
+                width,
+                height,
+                frame.y_stride(),
+                frame.uv_stride(),
+                &mut data,
+            );
+        }
+        PixelFormat::Yuv420p => {
+            let y_plane = frame.y_plane()?;
+            let u_plane = frame.u_plane().filter(|plane| plane.len() >= chroma_len)?;
+            let v_plane = frame.v_plane().filter(|plane| plane.len() >= chroma_len)?;
+            cpu_yuv::yuv420p_to_rgba_simd(
+                y_plane,
+                u_plane,
+                v_plane,
+                width,
+                height,
+                frame.y_stride(),
+                frame.uv_stride(),
+                &mut data,
+            );
+        }
+    }
+
+    Some(Texture {
+        width,
+        height,
+        data,
+    })
+}
+
+fn load(pixel: &[u8]) -> [f32; 4] {
+    std::array::from_fn(|i| pixel[i] as f32 / 255.0)
+}
+
+fn store(pixel: &mut [u8], color: [f32; 4]) {
+    for (channel, value) in pixel.iter_mut().zip(color) {
+        *channel = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+/// `wgpu::BlendState::ALPHA_BLENDING` for a straight-alpha source.
+fn blend_over(pixel: &mut [u8], src: [f32; 4]) {
+    if src[3] <= 0.0 {
+        return;
+    }
+
+    let dst = load(pixel);
+    let inv = 1.0 - src[3];
+    store(
+        pixel,
+        [
+            src[0] * src[3] + dst[0] * inv,
+            src[1] * src[3] + dst[1] * inv,
+            src[2] * src[3] + dst[2] * inv,
+            src[3] + dst[3] * inv,
+        ],
+    );
+}
+
+/// The premultiplied-alpha blend used by the cursor and watermark pipelines.
+fn blend_premultiplied(pixel: &mut [u8], src: [f32; 4]) {
+    if src.iter().all(|channel| *channel <= 0.0) {
+        return;
+    }
+
+    let dst = load(pixel);
+    let inv = 1.0 - src[3];
+    store(
+        pixel,
+        [
+            src[0] + dst[0] * inv,
+            src[1] + dst[1] * inv,
+            src[2] + dst[2] * inv,
+            src[3] + dst[3] * inv,
+        ],
+    );
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let range = edge1 - edge0;
+    // Degenerate edges (e.g. a zero-sized shadow) would divide by zero.
+    if range == 0.0 {
+        return 0.0;
+    }
+
+    let t = ((x - edge0) / range).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// `clamp` with WGSL semantics, which (unlike `f32::clamp`) tolerates `min > max`.
+fn wgsl_clamp(x: f32, min: f32, max: f32) -> f32 {
+    x.max(min).min(max)
+}
+
+/// Pixel rect `[x0, y0, x1, y1)` covering `min..max`, clipped to the output.
+fn pixel_rect(min: [f32; 2], max: [f32; 2], width: u32, height: u32) -> Option<[u32; 4]> {
+    let x0 = min[0].floor().max(0.0) as u32;
+    let y0 = min[1].floor().max(0.0) as u32;
+    let x1 = (max[0].ceil().max(0.0) as u32).min(width);
+    let y1 = (max[1].ceil().max(0.0) as u32).min(height);
+
+    (x0 < x1 && y0 < y1).then_some([x0, y0, x1, y1])
+}
+
+fn for_each_pixel(
+    canvas: &mut [u8],
+    width: u32,
+    rect: [u32; 4],
+    f: impl Fn(u32, u32, &mut [u8]) + Send + Sync,
+) {
+    let [x0, y0, x1, y1] = rect;
+    let row_len = width as usize * 4;
+
+    canvas
+        .par_chunks_mut(row_len)
+        .enumerate()
+        .skip(y0 as usize)
+        .take((y1 - y0) as usize)
+        .for_each(|(y, row)| {
+            for x in x0..x1 {
+                let index = x as usize * 4;
+                f(x, y as u32, &mut row[index..index + 4]);
+            }
+        });
+}
+
+fn color_background(uniforms: &GradientOrColorUniforms, size: (u32, u32)) -> Vec<u8> {
+    let (width, height) = size;
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    for_each_pixel(&mut pixels, width, [0, 0, width, height], |x, y, pixel| {
+        store(
+            pixel,
+            uniforms.color_at([
+                (x as f32 + 0.5) / width as f32,
+                (y as f32 + 0.5) / height as f32,
+            ]),
+        );
+    });
+    pixels
+}
+
+fn image_background(texture: &Texture, size: (u32, u32)) -> Vec<u8> {
+    let (width, height) = size;
+    let (x_width, y_height) = image_background_insets(size, (texture.width, texture.height));
+    let scale = [(0.5 - x_width) * 2.0, (0.5 - y_height) * 2.0];
+
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    for_each_pixel(&mut pixels, width, [0, 0, width, height], |x, y, pixel| {
+        let u = (x as f32 + 0.5) / width as f32;
+        let v = (y as f32 + 0.5) / height as f32;
+        store(
+            pixel,
+            texture.sample([x_width + scale[0] * u, y_height + scale[1] * v]),
+        );
+    });
+    pixels
+}
+
+/// Port of `background-blur.wgsl`.
+fn blur_background(pixels: Vec<u8>, size: (u32, u32), strength: f32) -> Vec<u8> {
+    if strength <= 0.01 {
+        return pixels;
+    }
+
+    let (width, height) = size;
+    let source = Texture {
+        width,
+        height,
+        data: pixels,
+    };
+
+    let radius = strength * 16.0;
+    let sigma = radius * 0.5;
+    let samples = ((radius * 0.3) as i32).clamp(4, 16);
+
+    let mut kernel = Vec::new();
+    for y in -samples..=samples {
+        for x in -samples..=samples {
+            let offset = [
+                x as f32 * radius / width as f32,
+                y as f32 * radius / height as f32,
+            ];
+            let weight = (-((x * x + y * y) as f32) / (2.0 * sigma * sigma)).exp();
+            kernel.push((offset, weight));
+        }
+    }
+    let total_weight: f32 = kernel.iter().map(|(_, weight)| weight).sum();
+
+    let mut output = vec![0u8; source.data.len()];
+    for_each_pixel(&mut output, width, [0, 0, width, height], |x, y, pixel| {
+        let uv = [
+            (x as f32 + 0.5) / width as f32,
+            (y as f32 + 0.5) / height as f32,
+        ];
+        let mut color = [0.0; 4];
+        for (offset, weight) in &kernel {
+            let sample = source.sample([uv[0] + offset[0], uv[1] + offset[1]]);
+            for (channel, value) in color.iter_mut().zip(sample) {
+                *channel += value * weight;
+            }
+        }
+        store(pixel, color.map(|channel| channel / total_weight));
+    });
+    output
+}
+
+fn sdf_rounded_rect(p: [f32; 2], b: [f32; 2], r: f32, rounding_type: f32) -> f32 {
+    let q = [p[0].abs() - b[0] + r, p[1].abs() - b[1] + r];
+    let outside = [q[0].max(0.0), q[1].max(0.0)];
+    let outside_norm = if rounding_type < 0.5 {
+        outside[0].hypot(outside[1])
+    } else {
+        (outside[0].powi(4) + outside[1].powi(4)).powf(0.25)
+    };
+    outside_norm + q[0].max(q[1]).min(0.0) - r
+}
+
+fn coverage_from_distance(distance: f32, anti_alias_width: f32) -> f32 {
+    (1.0 - smoothstep(-anti_alias_width, anti_alias_width, distance)).clamp(0.0, 1.0)
+}
+
+fn rounded_rect_coverage(p: [f32; 2], b: [f32; 2], r: f32, rounding_type: f32) -> f32 {
+    let distance = sdf_rounded_rect(p, b, r, rounding_type);
+    // `fwidth` approximated with forward differences to the neighbouring pixels.
+    let fwidth = (sdf_rounded_rect([p[0] + 1.0, p[1]], b, r, rounding_type) - distance).abs()
+        + (sdf_rounded_rect([p[0], p[1] + 1.0], b, r, rounding_type) - distance).abs();
+    let anti_alias_width = fwidth.max(1.0);
+
+    if distance <= -anti_alias_width {
+        return 1.0;
+    }
+
+    if distance >= anti_alias_width {
+        return 0.0;
+    }
+
+    let offset = 0.25;
+    [
+        [-offset, -offset],
+        [offset, -offset],
+        [-offset, offset],
+        [offset, offset],
+    ]
+    .iter()
+    .map(|o| {
+        coverage_from_distance(
+            sdf_rounded_rect([p[0] + o[0], p[1] + o[1]], b, r, rounding_type),
+            anti_alias_width,
+        )
+    })
+    .sum::<f32>()
+        * 0.25
+}
+
+fn composite_source_over(foreground: [f32; 4], background: [f32; 4]) -> [f32; 4] {
+    let alpha = foreground[3] + background[3] * (1.0 - foreground[3]);
+
+    if alpha <= 0.0001 {
+        return [0.0; 4];
+    }
+
+    let color: [f32; 3] = std::array::from_fn(|i| {
+        (foreground[i] * foreground[3] + background[i] * background[3] * (1.0 - foreground[3]))
+            / alpha
+    });
+
+    [color[0], color[1], color[2], alpha]
+}
+
+/// Port of `composite-video-frame.wgsl`, used for the display and both camera layers.
+struct CompositeShader<'a> {
+    u: &'a CompositeVideoFrameUniforms,
+    texture: &'a Texture,
+    center: [f32; 2],
+    half_size: [f32; 2],
+    shadow_size: f32,
+    shadow_opacity: f32,
+    shadow_blur: f32,
+    crop_uv: [f32; 4],
+}
+
+impl<'a> CompositeShader<'a> {
+    fn new(u: &'a CompositeVideoFrameUniforms, texture: &'a Texture) -> Self {
+        let bounds = u.target_bounds;
+        let half_size = [(bounds[2] - bounds[0]) * 0.5, (bounds[3] - bounds[1]) * 0.5];
+        let min_frame_size = half_size[0].min(half_size[1]);
+        let shadow_strength = u.shadow / 100.0;
+
+        let (shadow_size, shadow_opacity, shadow_blur) = if u.shadow > 0.0 {
+            (
+                shadow_strength * (u.shadow_size / 100.0) * min_frame_size,
+                shadow_strength * (u.shadow_opacity / 100.0),
+                shadow_strength * (u.shadow_blur / 100.0) * min_frame_size,
+            )
+        } else {
+            (
+                shadow_strength * min_frame_size,
+                shadow_strength * 0.18,
+                shadow_strength * min_frame_size * 0.5,
+            )
+        };
+
+        Self {
+            u,
+            texture,
+            center: [(bounds[0] + bounds[2]) * 0.5, (bounds[1] + bounds[3]) * 0.5],
+            half_size,
+            shadow_size,
+            shadow_opacity,
+            shadow_blur,
+            crop_uv: [
+                u.crop_bounds[0] / u.frame_size[0],
+                u.crop_bounds[1] / u.frame_size[1],
+                u.crop_bounds[2] / u.frame_size[0],
+                u.crop_bounds[3] / u.frame_size[1],
+            ],
+        }
+    }
+
+    fn edge_padding(&self) -> f32 {
+        (self.u.border_width + 2.0).max(2.0)
+    }
+
+    /// Pixels outside this rect would only receive a fully transparent shadow.
+    fn pixel_rect(&self, width: u32, height: u32) -> Option<[u32; 4]> {
+        let shadow_reach = if self.shadow_opacity > 0.0 {
+            self.shadow_size + self.shadow_blur
+        } else {
+            0.0
+        };
+        let margin = self.edge_padding().max(shadow_reach) + 2.0;
+        let bounds = self.u.target_bounds;
+
+        pixel_rect(
+            [bounds[0] - margin, bounds[1] - margin],
+            [bounds[2] + margin, bounds[3] + margin],
+            width,
+            height,
+        )
+    }
+
+    fn fragment(&self, p: [f32; 2]) -> [f32; 4] {
+        let u = self.u;
+        let local = [p[0] - self.center[0], p[1] - self.center[1]];
+
+        let shadow_alpha = if self.shadow_opacity > 0.0 {
+            let dist = sdf_rounded_rect(local, self.half_size, u.rounding_px, u.rounding_type);
+            smoothstep(
+                self.shadow_size + self.shadow_blur,
+                -self.shadow_blur,
+                dist.abs(),
+            ) * self.shadow_opacity
+        } else {
+            0.0
+        };
+        let shadow_color = [0.0, 0.0, 0.0, shadow_alpha];
+
+        let target_uv = [
+            (p[0] - u.target_bounds[0]) / u.target_size[0],
+            (p[1] - u.target_bounds[1]) / u.target_size[1],
+        ];
+        let edge_padding = self.edge_padding();
+        let edge_padding_uv = [
+            edge_padding / u.target_size[0],
+            edge_padding / u.target_size[1],
+        ];
+
+        if target_uv[0] < -edge_padding_uv[0]
+            || target_uv[0] > 1.0 + edge_padding_uv[0]
+            || target_uv[1] < -edge_padding_uv[1]
+            || target_uv[1] > 1.0 + edge_padding_uv[1]
+        {
+            return shadow_color;
+        }
+
+        if u.border_enabled > 0.0 {
+            let outer = rounded_rect_coverage(
+                local,
+                [
+                    self.half_size[0] + u.border_width,
+                    self.half_size[1] + u.border_width,
+                ],
+                u.rounding_px + u.border_width,
+                u.rounding_type,
+            );
+            let inner =
+                rounded_rect_coverage(local, self.half_size, u.rounding_px, u.rounding_type);
+            let border_coverage = (outer - inner).clamp(0.0, 1.0);
+
+            if border_coverage > 0.001 {
+                let border_color = [
+                    u.border_color[0],
+                    u.border_color[1],
+                    u.border_color[2],
+                    border_coverage * u.border_color[3],
+                ];
+                return composite_source_over(border_color, shadow_color);
+            }
+        }
+
+        let shape_coverage =
+            rounded_rect_coverage(local, self.half_size, u.rounding_px, u.rounding_type);
+
+        if shape_coverage <= 0.001 {
+            return shadow_color;
+        }
+
+        let mut base_color =
+            self.sample_texture([target_uv[0].clamp(0.0, 1.0), target_uv[1].clamp(0.0, 1.0)]);
+        base_color[3] *= shape_coverage * u.opacity;
+
+        let [blur_mode, blur_strength, zoom_amount, _] = u.motion_blur_params;
+
+        if blur_mode < 0.5 || blur_strength < 0.001 {
+            return composite_source_over(base_color, shadow_color);
+        }
+
+        let base_weight = base_color[3].max(0.001);
+        let mut accum = base_color.map(|channel| channel * base_weight);
+        let mut weight_sum = base_weight;
+
+        let mut accumulate = |sample_uv: [f32; 2], weight: f32| {
+            if !(0.0..=1.0).contains(&sample_uv[0]) || !(0.0..=1.0).contains(&sample_uv[1]) {
+                return;
+            }
+
+            let sample_color =
+                self.apply_rounded_corners(self.sample_texture(sample_uv), sample_uv);
+            let sample_weight = weight * sample_color[3];
+            if sample_weight > 1e-6 {
+                for (channel, value) in accum.iter_mut().zip(sample_color) {
+                    *channel += value * sample_weight;
+                }
+                weight_sum += sample_weight;
+            }
+        };
+
+        if blur_mode < 1.5 {
+            let motion_vec = u.motion_blur_vector;
+            if motion_vec[0].hypot(motion_vec[1]) < 1e-4 {
+                return composite_source_over(base_color, shadow_color);
+            }
+
+            for i in 0..20 {
+                let t = i as f32 / 20.0;
+                accumulate(
+                    [
+                        target_uv[0] + motion_vec[0] * t,
+                        target_uv[1] + motion_vec[1] * t,
+                    ],
+                    1.0,
+                );
+            }
+        } else {
+            let center = u.motion_blur_zoom_center;
+            let dir = [center[0] - target_uv[0], center[1] - target_uv[1]];
+            let dist = dir[0].hypot(dir[1]);
+            if dist < 1e-4 || zoom_amount < 1e-4 {
+                return composite_source_over(base_color, shadow_color);
+            }
+
+            let max_zoom_offset = 0.035;
+            let zoom_scale = zoom_amount.min(1.0);
+            let step = (dist * zoom_scale).min(max_zoom_offset) / dist;
+            let scaled_dir = [dir[0] * step, dir[1] * step];
+
+            for i in 0..13 {
+                let percent = (i as f32 + 0.5) / 13.0;
+                let weight = 4.0 * (percent - percent * percent);
+                accumulate(
+                    [
+                        target_uv[0] + scaled_dir[0] * percent,
+                        target_uv[1] + scaled_dir[1] * percent,
+                    ],
+                    weight,
+                );
+            }
+        }
+
+        let blur_mix = blur_strength.clamp(0.0, 1.0);
+        let mut blended = base_color;
+        for (channel, value) in blended.iter_mut().zip(accum).take(3) {
+            *channel += (value / weight_sum - *channel) * blur_mix;
+        }
+        composite_source_over(blended, shadow_color)
+    }
+
+    fn sample_texture(&self, uv: [f32; 2]) -> [f32; 4] {
+        if !(0.0..=1.0).contains(&uv[0]) || !(0.0..=1.0).contains(&uv[1]) {
+            return [0.0; 4];
+        }
+
+        let u = self.u;
+        let crop = self.crop_uv;
+        let sample_uv = [
+            if u.mirror_x != 0.0 {
+                1.0 - uv[0]
+            } else {
+                uv[0]
+            },
+            uv[1],
+        ];
+        let crop_size = [crop[2] - crop[0], crop[3] - crop[1]];
+        let texel_size = [1.0 / u.frame_size[0], 1.0 / u.frame_size[1]];
+        let safe_min = [crop[0] + texel_size[0], crop[1] + texel_size[1]];
+        let safe_max = [crop[2] - texel_size[0], crop[3] - texel_size[1]];
+        let safe = |uv: [f32; 2]| {
+            [
+                wgsl_clamp(uv[0], safe_min[0], safe_max[0]),
+                wgsl_clamp(uv[1], safe_min[1], safe_max[1]),
+            ]
+        };
+
+        let cropped_uv = safe([
+            sample_uv[0] * crop_size[0] + crop[0],
+            sample_uv[1] * crop_size[1] + crop[1],
+        ]);
+
+        let source_size = [
+            u.frame_size[0] * crop_size[0],
+            u.frame_size[1] * crop_size[1],
+        ];
+        let scale_ratio = [
+            source_size[0] / u.target_size[0],
+            source_size[1] / u.target_size[1],
+        ];
+        let upscale_ratio =
+            (u.target_size[0] / source_size[0]).max(u.target_size[1] / source_size[1]);
+
+        let center = self.texture.sample(cropped_uv);
+        let out_alpha = if u.preserve_source_alpha > 0.5 {
+            center[3]
+        } else {
+            1.0
+        };
+
+        let sharpness = if scale_ratio[0].max(scale_ratio[1]) > 1.1 {
+            Some((scale_ratio[0] * 0.3).min(0.7))
+        } else if upscale_ratio > 1.05 {
+            Some(((upscale_ratio - 1.0) * 0.25).min(0.45))
+        } else {
+            None
+        };
+
+        let Some(sharpness) = sharpness else {
+            return [center[0], center[1], center[2], out_alpha];
+        };
+
+        let neighbours = [
+            [-texel_size[0], 0.0],
+            [texel_size[0], 0.0],
+            [0.0, -texel_size[1]],
+            [0.0, texel_size[1]],
+        ]
+        .map(|offset| {
+            self.texture
+                .sample(safe([cropped_uv[0] + offset[0], cropped_uv[1] + offset[1]]))
+        });
+
+        let sharpened: [f32; 3] = std::array::from_fn(|i| {
+            let blurred = neighbours.iter().map(|n| n[i]).sum::<f32>() * 0.25;
+            (center[i] + (center[i] - blurred) * sharpness).clamp(0.0, 1.0)
+        });
+
+        [sharpened[0], sharpened[1], sharpened[2], out_alpha]
+    }
+
+    fn apply_rounded_corners(&self, color: [f32; 4], target_uv: [f32; 2]) -> [f32; 4] {
+        let u = self.u;
+        let centered = [
+            (target_uv[0] - 0.5) * u.target_size[0],
+            (target_uv[1] - 0.5) * u.target_size[1],
+        ];
+        let half_size = [u.target_size[0] * 0.5, u.target_size[1] * 0.5];
+        let coverage = rounded_rect_coverage(centered, half_size, u.rounding_px, u.rounding_type);
+
+        [color[0], color[1], color[2], color[3] * coverage]
+    }
+}
+
+fn draw_composite_frame(
+    canvas: &mut [u8],
+    width: u32,
+    height: u32,
+    uniforms: &CompositeVideoFrameUniforms,
+    texture: &Texture,
+) {
+    if uniforms.target_size[0] <= 0.0 || uniforms.target_size[1] <= 0.0 {
+        return;
+    }
+
+    let shader = CompositeShader::new(uniforms, texture);
+    let Some(rect) = shader.pixel_rect(width, height) else {
+        return;
+    };
+
+    for_each_pixel(canvas, width, rect, |x, y, pixel| {
+        blend_over(pixel, shader.fragment([x as f32 + 0.5, y as f32 + 0.5]));
+    });
+}
+
+const MAX_CURSOR_ROTATION_RADIANS: f32 = 0.34906584;
+const MAX_CURSOR_BLUR_UV: f32 = 0.24;
+
+/// Port of `cursor.wgsl`: the rotated, motion-blurred cursor quad.
+fn draw_cursor_quad(
+    canvas: &mut [u8],
+    width: u32,
+    height: u32,
+    position_size: &[f32; 4],
+    motion_vector_strength: &[f32; 4],
+    rotation_params: &[f32; 4],
+    texture: &Texture,
+) {
+    let [screen_x, screen_y, cursor_w, cursor_h] = *position_size;
+    let [motion_x, motion_y, blur_strength, opacity] = *motion_vector_strength;
+
+    if cursor_w <= 0.0 || cursor_h <= 0.0 {
+        return;
+    }
+
+    let velocity_uv = {
+        let motion_len = motion_x.hypot(motion_y);
+        let raw = [motion_x / cursor_w, motion_y / cursor_h];
+        let raw_len = raw[0].hypot(raw[1]);
+
+        if motion_len < 0.5 || blur_strength < 0.001 || raw_len < 0.005 {
+            [0.0, 0.0]
+        } else {
+            let scale = (MAX_CURSOR_BLUR_UV / raw_len).min(1.0);
+            [raw[0] * scale, raw[1] * scale]
+        }
+    };
+
+    let uv_min = [(-velocity_uv[0]).min(0.0), (-velocity_uv[1]).min(0.0)];
+    let uv_max = [
+        (1.0 - velocity_uv[0]).max(1.0),
+        (1.0 - velocity_uv[1]).max(1.0),
+    ];
+
+    let rotation = rotation_params[2]
+        .clamp(-MAX_CURSOR_ROTATION_RADIANS, MAX_CURSOR_ROTATION_RADIANS)
+        + rotation_params[1];
+    let (sin, cos) = rotation.sin_cos();
+
+    let to_screen = |u: f32, v: f32| {
+        [
+            screen_x + cursor_w * (u * cos + v * sin),
+            screen_y + cursor_h * (v * cos - u * sin),
+        ]
+    };
+    let corners = [
+        to_screen(uv_min[0], uv_min[1]),
+        to_screen(uv_max[0], uv_min[1]),
+        to_screen(uv_min[0], uv_max[1]),
+        to_screen(uv_max[0], uv_max[1]),
+    ];
+    let min = corners
+        .iter()
+        .fold([f32::MAX; 2], |acc, c| [acc[0].min(c[0]), acc[1].min(c[1])]);
+    let max = corners
+        .iter()
+        .fold([f32::MIN; 2], |acc, c| [acc[0].max(c[0]), acc[1].max(c[1])]);
+
+    let Some(rect) = pixel_rect(min, max, width, height) else {
+        return;
+    };
+
+    let sample = |uv: [f32; 2]| {
+        if (0.0..=1.0).contains(&uv[0]) && (0.0..=1.0).contains(&uv[1]) {
+            texture.sample(uv)
+        } else {
+            [0.0; 4]
+        }
+    };
+    let blurred = velocity_uv[0].hypot(velocity_uv[1]) >= 0.005 && blur_strength >= 0.001;
+    let blur_mix = blur_strength.clamp(0.0, 1.0);
+
+    for_each_pixel(canvas, width, rect, |x, y, pixel| {
+        let dx = (x as f32 + 0.5 - screen_x) / cursor_w;
+        let dy = (y as f32 + 0.5 - screen_y) / cursor_h;
+        let uv = [dx * cos - dy * sin, dx * sin + dy * cos];
+
+        if uv[0] < uv_min[0] || uv[0] > uv_max[0] || uv[1] < uv_min[1] || uv[1] > uv_max[1] {
+            return;
+        }
+
+        let base = sample(uv);
+        let color = if blurred {
+            let mut sum = base;
+            for i in 1..=20 {
+                let t = i as f32 / 20.0;
+                let tap = sample([uv[0] + velocity_uv[0] * t, uv[1] + velocity_uv[1] * t]);
+                for (channel, value) in sum.iter_mut().zip(tap) {
+                    *channel += value;
+                }
+            }
+            std::array::from_fn(|i| base[i] + (sum[i] / 21.0 - base[i]) * blur_mix)
+        } else {
+            base
+        };
+
+        blend_premultiplied(pixel, color.map(|channel| channel * opacity));
+    });
+}
+
+/// Port of `mask.wgsl`. Masks read the whole frame and replace it, like the
+/// ping-pong pass on the GPU.
+/// Rasterizes a shape annotation with the same signed-distance shading as
+/// `annotation.wgsl`.
+fn draw_annotation(canvas: &mut [u8], width: u32, height: u32, annotation: &PreparedAnnotation) {
+    if annotation.opacity <= 0.0 {
+        return;
+    }
+
+    let [left, top, right, bottom] = annotation.bounds();
+    let Some(rect) = pixel_rect([left, top], [right, bottom], width, height) else {
+        return;
+    };
+
+    for_each_pixel(canvas, width, rect, |x, y, pixel| {
+        blend_over(pixel, annotation.shade([x as f32 + 0.5, y as f32 + 0.5]));
+    });
+}
+
+fn apply_mask(canvas: Vec<u8>, width: u32, height: u32, mask: &PreparedMask) -> Vec<u8> {
+    let source = Texture {
+        width,
+        height,
+        data: canvas,
+    };
+    let mut output = vec![0u8; source.data.len()];
+
+    let output_size = [mask.output_size.x as f32, mask.output_size.y as f32];
+    let cell_px = mask.pixel_size.max(1.0);
+    let cell = [cell_px / output_size[0], cell_px / output_size[1]];
+    let edge = mask.feather.max(1e-4);
+    let opacity = mask.opacity.clamp(0.0, 1.0);
+    let darkness = (mask.darkness * mask.opacity).clamp(0.0, 1.0);
+
+    for_each_pixel(&mut output, width, [0, 0, width, height], |x, y, pixel| {
+        let uv = [
+            (x as f32 + 0.5) / width as f32,
+            (y as f32 + 0.5) / height as f32,
+        ];
+        let base = source.sample_nearest(uv);
+
+        let delta = [
+            (uv[0] - mask.center.x).abs() - mask.size.x * 0.5,
+            (uv[1] - mask.center.y).abs() - mask.size.y * 0.5,
+        ];
+        let sdf = delta[0].max(0.0).hypot(delta[1].max(0.0)) + delta[0].max(delta[1]).min(0.0);
+        let coverage = smoothstep(0.0, edge, -sdf);
+
+        let color: [f32; 4] = match mask.mode {
+            MaskRenderMode::Sensitive => {
+                let snapped = [
+                    (uv[0] / cell[0]).floor() * cell[0] + cell[0] * 0.5,
+                    (uv[1] / cell[1]).floor() * cell[1] + cell[1] * 0.5,
+                ];
+                let pixelated = source.sample_nearest(snapped);
+                std::array::from_fn(|i| {
+                    let effect = base[i] + (pixelated[i] - base[i]) * opacity;
+                    base[i] + (effect - base[i]) * coverage * opacity
+                })
+            }
+            MaskRenderMode::Highlight => std::array::from_fn(|i| {
+                let outside = if i < 3 {
+                    base[i] * (1.0 - darkness)
+                } else {
+                    base[i]
+                };
+                outside + (base[i] - outside) * coverage
+            }),
+        };
+
+        store(pixel, color);
+    });
+
+    output
+}
+
+/// Port of `caption_bg.wgsl`, limited to `scissor` (`[x, y, width, height]`).
+fn draw_caption_background(
+    canvas: &mut [u8],
+    width: u32,
+    uniforms: &CaptionBackgroundUniforms,
+    scissor: [u32; 4],
+) {
+    let [x, y, w, h] = scissor;
+    let height = (canvas.len() / (width as usize * 4)) as u32;
+    let rect = [
+        x.min(width),
+        y.min(height),
+        (x + w).min(width),
+        (y + h).min(height),
+    ];
+    if rect[0] >= rect[2] || rect[1] >= rect[3] {
+        return;
+    }
+
+    let [rect_x, rect_y, rect_w, rect_h] = uniforms.rect;
+    let center = [rect_x + rect_w * 0.5, rect_y + rect_h * 0.5];
+    let radius = uniforms.radius;
+    let adjusted_half = [rect_w * 0.5 - radius, rect_h * 0.5 - radius];
+    let color = uniforms.color;
+
+    for_each_pixel(canvas, width, rect, |px, py, pixel| {
+        let q = [
+            (px as f32 + 0.5 - center[0]).abs() - adjusted_half[0],
+            (py as f32 + 0.5 - center[1]).abs() - adjusted_half[1],
+        ];
+        let distance = if q[0] <= 0.0 && q[1] <= 0.0 {
+            q[0].max(q[1]) - radius
+        } else {
+            let corner = [q[0].max(0.0), q[1].max(0.0)];
+            (corner[0].powi(4) + corner[1].powi(4)).powf(0.25) - radius
+        };
+        let alpha = 1.0 - smoothstep(-1.5, 1.5, distance);
+
+        blend_over(pixel, [color[0], color[1], color[2], color[3] * alpha]);
+    });
+}
+
+fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Rasterizes a shaped buffer the way glyphon's `TextRenderer` does with
+/// `ColorMode::Accurate`: glyphs at whole-pixel positions, clipped to `bounds`
+/// and `scissor` (`[x, y, width, height]`).
+#[allow(clippy::too_many_arguments)]
+fn draw_text(
+    canvas: &mut [u8],
+    width: u32,
+    font_system: &mut FontSystem,
+    swash_cache: &mut SwashCache,
+    buffer: &Buffer,
+    (left, top): (f32, f32),
+    scale: f32,
+    bounds: TextBounds,
+    default_color: Color,
+    scissor: [u32; 4],
+) {
+    let height = (canvas.len() / (width as usize * 4)) as i32;
+    let clip_left = bounds.left.max(scissor[0] as i32).max(0);
+    let clip_top = bounds.top.max(scissor[1] as i32).max(0);
+    let clip_right = bounds
+        .right
+        .min((scissor[0] + scissor[2]) as i32)
+        .min(width as i32);
+    let clip_bottom = bounds
+        .bottom
+        .min((scissor[1] + scissor[3]) as i32)
+        .min(height);
+
+    if clip_left >= clip_right || clip_top >= clip_bottom {
+        return;
+    }
+
+    let row_len = width as usize * 4;
+
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            let physical = glyph.physical((left, top), scale);
+            let Some(image) = swash_cache.get_image_uncached(font_system, physical.cache_key)
+            else {
+                continue;
+            };
+
+            let placement = image.placement;
+            let glyph_x = physical.x + placement.left;
+            let glyph_y = (run.line_y * scale).round() as i32 + physical.y - placement.top;
+
+            let color = glyph.color_opt.unwrap_or(default_color);
+            let linear_color = [
+                srgb_to_linear(color.r()),
+                srgb_to_linear(color.g()),
+                srgb_to_linear(color.b()),
+                color.a() as f32 / 255.0,
+            ];
+
+            for gy in 0..placement.height as i32 {
+                let y = glyph_y + gy;
+                if y < clip_top || y >= clip_bottom {
+                    continue;
+                }
+
+                for gx in 0..placement.width as i32 {
+                    let x = glyph_x + gx;
+                    if x < clip_left || x >= clip_right {
+                        continue;
+                    }
+
+                    let texel = (gy * placement.width as i32 + gx) as usize;
+                    let src = match image.content {
+                        SwashContent::Mask | SwashContent::SubpixelMask => {
+                            let Some(coverage) = image.data.get(texel) else {
+                                continue;
+                            };
+                            [
+                                linear_color[0],
+                                linear_color[1],
+                                linear_color[2],
+                                linear_color[3] * *coverage as f32 / 255.0,
+                            ]
+                        }
+                        SwashContent::Color => {
+                            let Some(rgba) = image.data.get(texel * 4..texel * 4 + 4) else {
+                                continue;
+                            };
+                            [
+                                srgb_to_linear(rgba[0]),
+                                srgb_to_linear(rgba[1]),
+                                srgb_to_linear(rgba[2]),
+                                rgba[3] as f32 / 255.0,
+                            ]
+                        }
+                    };
+
+                    let index = y as usize * row_len + x as usize * 4;
+                    blend_over(&mut canvas[index..index + 4], src);
+                }
+            }
+        }
+    }
+}
+
+/// Composites a premultiplied layer holding `text` drawn without rotation, turning it
+/// around the centre of the text's bounds like `rotated-text.wgsl`.
+fn draw_rotated_layer(
+    canvas: &mut [u8],
+    width: u32,
+    height: u32,
+    layer: Vec<u8>,
+    text: &PreparedText,
+) {
+    let [left, top, right, bottom] = text.bounds;
+    let pivot = [(left + right) / 2.0, (top + bottom) / 2.0];
+    let overhang = text.overhang();
+    let [left, top, right, bottom] = [
+        left - overhang,
+        top - overhang,
+        right + overhang,
+        bottom + overhang,
+    ];
+    let (sin, cos) = text.rotation.sin_cos();
+    let rotate = |[x, y]: [f32; 2]| {
+        let (dx, dy) = (x - pivot[0], y - pivot[1]);
+        [
+            pivot[0] + dx * cos - dy * sin,
+            pivot[1] + dx * sin + dy * cos,
+        ]
+    };
+
+    let corners = [[left, top], [right, top], [left, bottom], [right, bottom]].map(rotate);
+    let min = [0, 1].map(|axis| corners.iter().map(|c| c[axis]).fold(f32::MAX, f32::min));
+    let max = [0, 1].map(|axis| corners.iter().map(|c| c[axis]).fold(f32::MIN, f32::max));
+    let Some(rect) = pixel_rect(min, max, width, height) else {
+        return;
+    };
+
+    let layer = Texture {
+        width,
+        height,
+        data: layer,
+    };
+    for_each_pixel(canvas, width, rect, |x, y, pixel| {
+        let (dx, dy) = (x as f32 + 0.5 - pivot[0], y as f32 + 0.5 - pivot[1]);
+        let u = (pivot[0] + dx * cos + dy * sin) / width as f32;
+        let v = (pivot[1] - dx * sin + dy * cos) / height as f32;
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return;
+        }
+
+        blend_premultiplied(pixel, layer.sample([u, v]));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_texture(width: u32, height: u32, color: [u8; 4]) -> Texture {
+        Texture {
+            width,
+            height,
+            data: color.repeat((width * height) as usize),
+        }
+    }
+
+    fn pixel(canvas: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * width + x) * 4) as usize;
+        canvas[index..index + 4].try_into().unwrap()
+    }
+
+    fn display_uniforms(target: [f32; 4], rounding_px: f32) -> CompositeVideoFrameUniforms {
+        CompositeVideoFrameUniforms {
+            crop_bounds: [0.0, 0.0, 32.0, 32.0],
+            target_bounds: target,
+            output_size: [64.0, 64.0],
+            frame_size: [32.0, 32.0],
+            target_size: [target[2] - target[0], target[3] - target[1]],
+            rounding_px,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn display_covers_its_target_bounds() {
+        let mut canvas = [10, 20, 30, 255].repeat(64 * 64);
+        let texture = solid_texture(32, 32, [200, 100, 50, 255]);
+
+        draw_composite_frame(
+            &mut canvas,
+            64,
+            64,
+            &display_uniforms([16.0, 16.0, 48.0, 48.0], 0.0),
+            &texture,
+        );
+
+        assert_eq!(pixel(&canvas, 64, 32, 32), [200, 100, 50, 255]);
+        assert_eq!(pixel(&canvas, 64, 17, 17), [200, 100, 50, 255]);
+        assert_eq!(pixel(&canvas, 64, 4, 4), [10, 20, 30, 255]);
+        assert_eq!(pixel(&canvas, 64, 60, 32), [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn rounded_corners_leave_the_background_visible() {
+        let mut canvas = [0, 0, 0, 255].repeat(64 * 64);
+        let texture = solid_texture(32, 32, [255, 255, 255, 255]);
+
+        draw_composite_frame(
+            &mut canvas,
+            64,
+            64,
+            &display_uniforms([0.0, 0.0, 64.0, 64.0], 16.0),
+            &texture,
+        );
+
+        assert_eq!(pixel(&canvas, 64, 0, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(&canvas, 64, 32, 2), [255, 255, 255, 255]);
+        assert_eq!(pixel(&canvas, 64, 32, 32), [255, 255, 255, 255]);
+    }
+
+    fn mask(mode: MaskRenderMode) -> PreparedMask {
+        PreparedMask {
+            center: XY::new(0.25, 0.5),
+            size: XY::new(0.5, 1.0),
+            feather: 0.0,
+            opacity: 1.0,
+            pixel_size: 4.0,
+            darkness: 0.5,
+            mode,
+            output_size: XY::new(8, 8),
+        }
+    }
+
+    #[test]
+    fn highlight_mask_darkens_outside_the_rect() {
+        let canvas = [200, 200, 200, 255].repeat(64);
+
+        let output = apply_mask(canvas, 8, 8, &mask(MaskRenderMode::Highlight));
+
+        assert_eq!(pixel(&output, 8, 1, 4), [200, 200, 200, 255]);
+        assert_eq!(pixel(&output, 8, 6, 4), [100, 100, 100, 255]);
+    }
+
+    #[test]
+    fn sensitive_mask_pixelates_inside_the_rect() {
+        let canvas: Vec<u8> = (0..64u8).flat_map(|i| [i * 4, 0, 0, 255]).collect();
+
+        let output = apply_mask(canvas.clone(), 8, 8, &mask(MaskRenderMode::Sensitive));
+
+        let cell_center = pixel(&canvas, 8, 2, 2);
+        assert_eq!(pixel(&output, 8, 0, 0), cell_center);
+        assert_eq!(pixel(&output, 8, 3, 3), cell_center);
+        assert_eq!(pixel(&output, 8, 6, 6), pixel(&canvas, 8, 6, 6));
+    }
+
+    #[test]
+    fn gradient_background_runs_from_start_to_end() {
+        let background = Background::from(cap_project::BackgroundSource::Gradient {
+            from: [255, 0, 0],
+            to: [0, 0, 255],
+            angle: 90,
+            noise_intensity: None,
+            noise_scale: None,
+            animated: None,
+            animation_speed: None,
+        });
+
+        let pixels = color_background(&GradientOrColorUniforms::from(background), (16, 4));
+
+        let left = pixel(&pixels, 16, 0, 2);
+        let right = pixel(&pixels, 16, 15, 2);
+        assert!(left[0] > 230 && left[2] < 25, "{left:?}");
+        assert!(right[2] > 230 && right[0] < 25, "{right:?}");
+    }
+
+    #[test]
+    fn cursor_quad_is_drawn_at_its_position() {
+        let mut canvas = vec![0u8; 32 * 32 * 4];
+        let texture = solid_texture(4, 4, [255, 255, 255, 255]);
+
+        draw_cursor_quad(
+            &mut canvas,
+            32,
+            32,
+            &[8.0, 8.0, 8.0, 8.0],
+            &[0.0, 0.0, 0.0, 1.0],
+            &[0.0; 4],
+            &texture,
+        );
+
+        assert_eq!(pixel(&canvas, 32, 12, 12), [255, 255, 255, 255]);
+        assert_eq!(pixel(&canvas, 32, 4, 4), [0, 0, 0, 0]);
+        assert_eq!(pixel(&canvas, 32, 20, 12), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rotated_text_layer_turns_around_the_text_centre() {
+        let mut layer = vec![0u8; 9 * 9 * 4];
+        for x in 2..7 {
+            let index = (4 * 9 + x) * 4;
+            layer[index..index + 4].copy_from_slice(&[255, 255, 255, 255]);
+        }
+        let text = PreparedText {
+            content: String::new(),
+            bounds: [0.0, 0.0, 9.0, 9.0],
+            color: [1.0; 4],
+            font_family: String::new(),
+            font_size: 12.0,
+            font_weight: 400.0,
+            italic: false,
+            opacity: 1.0,
+            rotation: std::f32::consts::FRAC_PI_2,
+            font_path: None,
+            align: cap_project::TextAlign::Center,
+            letter_spacing: 0.0,
+            line_height: 1.2,
+            outline: None,
+            shadow: None,
+            background: None,
+        };
+        let mut canvas = [0, 0, 0, 255].repeat(9 * 9);
+
+        draw_rotated_layer(&mut canvas, 9, 9, layer, &text);
+
+        assert_eq!(pixel(&canvas, 9, 4, 2), [255, 255, 255, 255]);
+        assert_eq!(pixel(&canvas, 9, 2, 4), [0, 0, 0, 255]);
+    }
+}
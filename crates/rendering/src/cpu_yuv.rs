@@ -911,6 +911,61 @@ pub fn yuv420p_to_rgba_simd_with_progress(
     );
 }
 
+/// Converts an RGBA frame into a tightly packed NV12 buffer using integer BT.601
+/// limited-range coefficients, averaging each 2x2 block for the chroma plane.
+///
+/// `output` must hold at least `width * height + width * (height / 2)` bytes.
+pub fn rgba_to_nv12(src: &[u8], width: u32, height: u32, src_stride: u32, output: &mut [u8]) {
+    let width = width as usize;
+    let height = height as usize;
+    let src_stride = src_stride as usize;
+    let y_stride = width;
+    let uv_stride = width;
+    let y_plane_size = y_stride * height;
+
+    for row in 0..height {
+        let src_row = &src[row * src_stride..row * src_stride + width * 4];
+        let y_row = &mut output[row * y_stride..(row + 1) * y_stride];
+        for col in 0..width {
+            let r = src_row[col * 4] as i32;
+            let g = src_row[col * 4 + 1] as i32;
+            let b = src_row[col * 4 + 2] as i32;
+            y_row[col] = ((16 + ((65 * r + 129 * g + 25 * b + 128) >> 8)) as u8).clamp(16, 235);
+        }
+    }
+
+    for row in 0..(height / 2) {
+        let src_row0 = &src[row * 2 * src_stride..row * 2 * src_stride + width * 4];
+        let src_row1 = &src[(row * 2 + 1) * src_stride..(row * 2 + 1) * src_stride + width * 4];
+        let uv_row =
+            &mut output[y_plane_size + row * uv_stride..y_plane_size + (row + 1) * uv_stride];
+        for col in 0..(width / 2) {
+            let r = (src_row0[col * 8] as i32
+                + src_row0[col * 8 + 4] as i32
+                + src_row1[col * 8] as i32
+                + src_row1[col * 8 + 4] as i32
+                + 2)
+                / 4;
+            let g = (src_row0[col * 8 + 1] as i32
+                + src_row0[col * 8 + 5] as i32
+                + src_row1[col * 8 + 1] as i32
+                + src_row1[col * 8 + 5] as i32
+                + 2)
+                / 4;
+            let b = (src_row0[col * 8 + 2] as i32
+                + src_row0[col * 8 + 6] as i32
+                + src_row1[col * 8 + 2] as i32
+                + src_row1[col * 8 + 6] as i32
+                + 2)
+                / 4;
+            uv_row[col * 2] =
+                ((128 + ((-38 * r - 74 * g + 112 * b + 128) >> 8)) as u8).clamp(16, 240);
+            uv_row[col * 2 + 1] =
+                ((128 + ((112 * r - 94 * g - 18 * b + 128) >> 8)) as u8).clamp(16, 240);
+        }
+    }
+}
+
 #[inline(always)]
 fn clamp_u8(val: i32) -> u8 {
     val.clamp(0, 255) as u8
@@ -951,6 +1006,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rgba_to_nv12_round_trips() {
+        let width = 4u32;
+        let height = 2u32;
+        let rgba: Vec<u8> = [[200u8, 40, 90, 255]; 8].concat();
+
+        let mut nv12 = vec![0u8; (width * height + width * (height / 2)) as usize];
+        rgba_to_nv12(&rgba, width, height, width * 4, &mut nv12);
+
+        let (y_plane, uv_plane) = nv12.split_at((width * height) as usize);
+        let mut output = vec![0u8; (width * height * 4) as usize];
+        nv12_to_rgba(y_plane, uv_plane, width, height, width, width, &mut output);
+
+        for pixel in output.chunks(4) {
+            for (actual, expected) in pixel.iter().zip([200u8, 40, 90, 255]) {
+                assert!(
+                    (*actual as i32 - expected as i32).abs() <= 3,
+                    "expected {expected}, got {actual}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_nv12_simd_matches_scalar() {
         let width = 16u32;
@@ -495,6 +495,31 @@ pub struct Nv12RenderedFrame {
 }
 
 impl Nv12RenderedFrame {
+    /// Converts an RGBA frame on the CPU, for frames that never went through the GPU converter.
+    pub(crate) fn from_rgba(frame: &RenderedFrame, pool: &NV12BufferPool) -> Self {
+        let (width, height) = (frame.width, frame.height);
+        let size = width as usize * height as usize + width as usize * (height as usize / 2);
+        let mut nv12_buf = pool.acquire(size);
+        nv12_buf.resize(size, 0);
+        crate::cpu_yuv::rgba_to_nv12(
+            &frame.data,
+            width,
+            height,
+            frame.padded_bytes_per_row,
+            &mut nv12_buf,
+        );
+
+        Self {
+            data: pool.wrap(nv12_buf),
+            width,
+            height,
+            y_stride: width,
+            frame_number: frame.frame_number,
+            target_time_ns: frame.target_time_ns,
+            format: GpuOutputFormat::Nv12,
+        }
+    }
+
     pub fn clone_metadata_with_data(&self) -> Self {
         Self {
             data: self.data.clone(),
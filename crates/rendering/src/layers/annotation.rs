@@ -0,0 +1,202 @@
+use bytemuck::{Pod, Zeroable};
+use cap_project::XY;
+use wgpu::util::DeviceExt;
+
+use crate::{AnnotationShape, PreparedAnnotation};
+
+/// Draws the timeline's rectangle, ellipse and arrow annotations with `annotation.wgsl`,
+/// one bounds-sized quad per shape.
+pub struct AnnotationLayer {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+    shapes: Vec<AnnotationShapeBinding>,
+    visible: usize,
+}
+
+struct AnnotationShapeBinding {
+    uniforms_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct AnnotationUniforms {
+    output_size: [f32; 2],
+    kind: u32,
+    stroke_width: f32,
+    bounds: [f32; 4],
+    geometry: [f32; 4],
+    params: [f32; 4],
+    stroke_color: [f32; 4],
+    fill_color: [f32; 4],
+}
+
+impl AnnotationUniforms {
+    fn new(output_size: XY<u32>, annotation: &PreparedAnnotation) -> Self {
+        let (kind, geometry, params) = match annotation.shape {
+            AnnotationShape::Rectangle {
+                center,
+                half_size,
+                rotation,
+            } => (
+                0,
+                [center[0], center[1], half_size[0], half_size[1]],
+                [rotation, 0.0, 0.0, annotation.opacity],
+            ),
+            AnnotationShape::Ellipse {
+                center,
+                half_size,
+                rotation,
+            } => (
+                1,
+                [center[0], center[1], half_size[0], half_size[1]],
+                [rotation, 0.0, 0.0, annotation.opacity],
+            ),
+            AnnotationShape::Arrow {
+                tail,
+                tip,
+                head_length,
+                head_width,
+            } => (
+                2,
+                [tail[0], tail[1], tip[0], tip[1]],
+                [0.0, head_length, head_width, annotation.opacity],
+            ),
+        };
+
+        Self {
+            output_size: [output_size.x as f32, output_size.y as f32],
+            kind,
+            stroke_width: annotation.stroke_width,
+            bounds: annotation.bounds(),
+            geometry,
+            params,
+            stroke_color: annotation.stroke_color,
+            fill_color: annotation.fill_color,
+        }
+    }
+}
+
+impl AnnotationLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Annotation Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Annotation Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/annotation.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Annotation Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Annotation Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    // The shader outputs straight colour.
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline,
+            shapes: Vec::new(),
+            visible: 0,
+        }
+    }
+
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        output_size: XY<u32>,
+        annotations: &[PreparedAnnotation],
+    ) {
+        self.visible = 0;
+
+        for annotation in annotations {
+            if annotation.opacity <= 0.0 {
+                continue;
+            }
+
+            let uniforms = AnnotationUniforms::new(output_size, annotation);
+            if let Some(binding) = self.shapes.get(self.visible) {
+                queue.write_buffer(&binding.uniforms_buffer, 0, bytemuck::bytes_of(&uniforms));
+            } else {
+                let uniforms_buffer =
+                    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("Annotation Uniform Buffer"),
+                        contents: bytemuck::bytes_of(&uniforms),
+                        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Annotation Bind Group"),
+                    layout: &self.bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms_buffer.as_entire_binding(),
+                    }],
+                });
+                self.shapes.push(AnnotationShapeBinding {
+                    uniforms_buffer,
+                    bind_group,
+                });
+            }
+
+            self.visible += 1;
+        }
+    }
+
+    pub fn has_content(&self) -> bool {
+        self.visible > 0
+    }
+
+    pub fn render(&self, pass: &mut wgpu::RenderPass<'_>) {
+        if self.visible == 0 {
+            return;
+        }
+
+        pass.set_pipeline(&self.pipeline);
+        for shape in &self.shapes[..self.visible] {
+            pass.set_bind_group(0, &shape.bind_group, &[]);
+            pass.draw(0..6, 0..1);
+        }
+    }
+}
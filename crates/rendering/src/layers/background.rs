@@ -10,7 +10,7 @@ use wgpu::{include_wgsl, util::DeviceExt};
 
 use crate::{ProjectUniforms, RenderVideoConstants, RenderingError, create_shader_render_pipeline};
 
-const MAX_BACKGROUND_DIMENSION: u32 = 2560;
+pub(crate) const MAX_BACKGROUND_DIMENSION: u32 = 2560;
 
 const DEFAULT_BACKGROUND_CACHE_CAPACITY: usize = 8;
 
@@ -29,7 +29,7 @@ pub fn clean_background_path(path: &str) -> Option<String> {
         .then_some(clean_path)
 }
 
-fn decode_background_rgba(
+pub(crate) fn decode_background_rgba(
     path: &str,
     max_dimension: u32,
 ) -> Result<(Vec<u8>, u32, u32), image::ImageError> {
@@ -258,7 +258,7 @@ pub enum ColorOrGradient {
     Gradient(Gradient),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
 pub enum Background {
     Color([f32; 4]),
     Gradient(Gradient),
@@ -392,24 +392,10 @@ impl BackgroundLayer {
                             }
                         };
 
-                        let output_ar =
-                            uniforms.output_size.1 as f32 / uniforms.output_size.0 as f32;
-                        let image_ar = texture.height() as f32 / texture.width() as f32;
-
-                        let y_height = if output_ar < image_ar {
-                            ((image_ar - output_ar) / 2.0) / image_ar
-                        } else {
-                            0.0
-                        };
-
-                        let x_width = if output_ar > image_ar {
-                            let output_ar = 1.0 / output_ar;
-                            let image_ar = 1.0 / image_ar;
-
-                            ((image_ar - output_ar) / 2.0) / image_ar
-                        } else {
-                            0.0
-                        };
+                        let (x_width, y_height) = image_background_insets(
+                            uniforms.output_size,
+                            (texture.width(), texture.height()),
+                        );
 
                         let image_uniforms = ImageBackgroundUniforms {
                             output_size: [
@@ -491,6 +477,33 @@ impl BackgroundLayer {
     }
 }
 
+/// UV insets that crop an image background so it covers the output without
+/// stretching ("object-fit: cover").
+pub(crate) fn image_background_insets(
+    output_size: (u32, u32),
+    image_size: (u32, u32),
+) -> (f32, f32) {
+    let output_ar = output_size.1 as f32 / output_size.0 as f32;
+    let image_ar = image_size.1 as f32 / image_size.0 as f32;
+
+    let y_height = if output_ar < image_ar {
+        ((image_ar - output_ar) / 2.0) / image_ar
+    } else {
+        0.0
+    };
+
+    let x_width = if output_ar > image_ar {
+        let output_ar = 1.0 / output_ar;
+        let image_ar = 1.0 / image_ar;
+
+        ((image_ar - output_ar) / 2.0) / image_ar
+    } else {
+        0.0
+    };
+
+    (x_width, y_height)
+}
+
 pub struct ImageBackgroundPipeline {
     pub bind_group_layout: wgpu::BindGroupLayout,
     pub render_pipeline: wgpu::RenderPipeline,
@@ -656,6 +669,68 @@ impl GradientOrColorUniforms {
             }),
         )
     }
+
+    /// CPU port of `gradient-or-color.wgsl`, evaluated at a top-left origin `uv`.
+    pub(crate) fn color_at(&self, uv: [f32; 2]) -> [f32; 4] {
+        let angle = (self.angle + 270.0).to_radians();
+        let projection = (uv[0] - 0.5) * angle.cos() + (uv[1] - 0.5) * angle.sin() + 0.5;
+        let t = projection.clamp(0.0, 1.0);
+        let mut color: [f32; 4] =
+            std::array::from_fn(|i| self.start[i] + (self.end[i] - self.start[i]) * t);
+
+        if self.noise_intensity > 0.0 {
+            let frequency = 0.3 + ((100.0 - self.noise_scale) / 100.0) * 1.2;
+            let n = fbm([uv[0] * frequency * 600.0, uv[1] * frequency * 600.0]);
+            let intensity = (self.noise_intensity / 100.0) * 0.25;
+
+            for channel in &mut color[..3] {
+                let blended = if *channel < 0.5 {
+                    2.0 * *channel * n
+                } else {
+                    1.0 - 2.0 * (1.0 - *channel) * (1.0 - n)
+                };
+                *channel += (blended - *channel) * intensity;
+            }
+        }
+
+        color
+    }
+}
+
+fn noise_hash(p: [f32; 2]) -> f32 {
+    let mut p3 = [p[0], p[1], p[0]].map(|v| (v * 0.1031).fract());
+    let dot = p3[0] * (p3[1] + 33.33) + p3[1] * (p3[2] + 33.33) + p3[2] * (p3[0] + 33.33);
+    p3 = p3.map(|v| v + dot);
+    ((p3[0] + p3[1]) * p3[2]).fract()
+}
+
+fn value_noise(p: [f32; 2]) -> f32 {
+    let i = [p[0].floor(), p[1].floor()];
+    let f = [p[0] - i[0], p[1] - i[1]];
+    let u = f.map(|f| f * f * (3.0 - 2.0 * f));
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    lerp(
+        lerp(noise_hash(i), noise_hash([i[0] + 1.0, i[1]]), u[0]),
+        lerp(
+            noise_hash([i[0], i[1] + 1.0]),
+            noise_hash([i[0] + 1.0, i[1] + 1.0]),
+            u[0],
+        ),
+        u[1],
+    )
+}
+
+fn fbm(p: [f32; 2]) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 0.5;
+    let mut coord = p;
+    for _ in 0..4 {
+        value += amplitude * value_noise(coord);
+        coord = coord.map(|c| c * 2.0);
+        amplitude *= 0.5;
+    }
+    value
 }
 
 impl From<Background> for GradientOrColorUniforms {
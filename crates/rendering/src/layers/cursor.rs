@@ -7,8 +7,9 @@ use tracing::error;
 use wgpu::{BindGroup, FilterMode, include_wgsl, util::DeviceExt};
 
 use crate::{
-    Coord, DecodedSegmentFrames, FrameSpace, ProjectUniforms, RenderVideoConstants,
-    STANDARD_CURSOR_HEIGHT, zoom::InterpolatedZoom,
+    Coord, DecodedSegmentFrames, FrameSpace, ProjectUniforms, RenderOptions, RenderVideoConstants,
+    STANDARD_CURSOR_HEIGHT, cursor_interpolation::InterpolatedCursorPosition,
+    zoom::InterpolatedZoom,
 };
 
 const CURSOR_CLICK_DURATION: f64 = 0.13;
@@ -201,65 +202,7 @@ impl CursorLayer {
     }
 
     fn create_circle_cursor(constants: &RenderVideoConstants) -> CursorTexture {
-        let size = CIRCLE_CURSOR_SIZE;
-        let mut rgba = vec![0u8; (size * size * 4) as usize];
-        let center = size as f32 / 2.0;
-        let outer_radius = center - size as f32 * 0.08;
-        let dark_ring_width = size as f32 * 0.014;
-        let light_ring_width = size as f32 * 0.016;
-        let shadow_spread = size as f32 * 0.035;
-        let edge_softness = size as f32 * 0.018;
-
-        let inner_edge = outer_radius - dark_ring_width - light_ring_width;
-
-        for y in 0..size {
-            for x in 0..size {
-                let dx = x as f32 - center + 0.5;
-                let dy = y as f32 - center + 0.5;
-                let dist = (dx * dx + dy * dy).sqrt();
-                let idx = ((y * size + x) * 4) as usize;
-
-                if dist > outer_radius + shadow_spread {
-                    continue;
-                }
-
-                let mut color = [0.0_f32; 4];
-
-                if dist > outer_radius {
-                    let shadow_t = ((dist - outer_radius) / shadow_spread).clamp(0.0, 1.0);
-                    let shadow_alpha = 0.16 * (1.0 - shadow_t * shadow_t);
-                    composite_cursor_layer(&mut color, [0.0, 0.0, 0.0, shadow_alpha]);
-                }
-
-                if dist <= outer_radius + edge_softness {
-                    let outer_fade = 1.0
-                        - ((dist - outer_radius) / edge_softness)
-                            .clamp(0.0, 1.0)
-                            .powf(1.2);
-
-                    if dist > outer_radius - dark_ring_width {
-                        let ring_alpha = 0.38 * outer_fade;
-                        composite_cursor_layer(&mut color, [0.0, 0.0, 0.0, ring_alpha]);
-                    } else if dist > inner_edge {
-                        let ring_alpha = 0.42 * outer_fade;
-                        composite_cursor_layer(&mut color, [1.0, 1.0, 1.0, ring_alpha]);
-                    } else {
-                        let fill_alpha = 0.14 * outer_fade;
-                        composite_cursor_layer(&mut color, [1.0, 1.0, 1.0, fill_alpha]);
-                    }
-                }
-
-                if color[3] > 0.0 {
-                    let a = color[3];
-                    rgba[idx] = (color[0] * a * 255.0).round() as u8;
-                    rgba[idx + 1] = (color[1] * a * 255.0).round() as u8;
-                    rgba[idx + 2] = (color[2] * a * 255.0).round() as u8;
-                    rgba[idx + 3] = (a * 255.0).round() as u8;
-                }
-            }
-        }
-
-        CursorTexture::prepare(constants, &rgba, (size, size), XY::new(0.5, 0.5))
+        CursorTexture::prepare(constants, &CursorImage::circle())
     }
 
     fn load_cursor_texture(
@@ -267,46 +210,13 @@ impl CursorLayer {
         cursor_id: &str,
         use_svg: bool,
     ) -> Option<CursorTexture> {
-        let mut loaded_cursor = None;
-
-        let cursor_shape = match &constants.recording_meta.inner {
-            RecordingMetaInner::Studio(studio) => match studio.as_ref() {
-                StudioRecordingMeta::MultipleSegments {
-                    inner:
-                        MultipleSegments {
-                            cursors: Cursors::Correct(cursors),
-                            ..
-                        },
-                } => cursors.get(cursor_id).and_then(|v| v.shape),
-                _ => None,
-            },
-            _ => None,
-        };
-
-        if let Some(cursor_shape) = cursor_shape
-            && use_svg
-            && let Some(info) = cursor_shape.resolve()
-        {
-            loaded_cursor = CursorTexture::prepare_svg(constants, info.raw, info.hotspot.into())
-                .map_err(|err| error!("Error loading SVG cursor {cursor_id:?}: {err}"))
-                .ok();
-        }
-
-        if let StudioRecordingMeta::MultipleSegments { inner, .. } = &constants.meta
-            && loaded_cursor.is_none()
-            && let Some(c) = inner.get_cursor_image(&constants.recording_meta, cursor_id)
-            && let Ok(img) = image::open(&c.path)
-                .map_err(|err| error!("Failed to load cursor image from {:?}: {err}", c.path))
-        {
-            loaded_cursor = Some(CursorTexture::prepare(
-                constants,
-                &img.to_rgba8(),
-                img.dimensions(),
-                c.hotspot,
-            ));
-        }
-
-        loaded_cursor
+        CursorImage::load(
+            &constants.recording_meta,
+            &constants.meta,
+            cursor_id,
+            use_svg,
+        )
+        .map(|image| CursorTexture::prepare(constants, &image))
     }
 
     fn preload_cursor_textures(&mut self, constants: &RenderVideoConstants, use_svg: bool) {
@@ -361,8 +271,6 @@ impl CursorLayer {
             return;
         }
 
-        let time_s = segment_frames.recording_time;
-
         let Some(interpolated_cursor) = &uniforms.interpolated_cursor else {
             return;
         };
@@ -373,64 +281,6 @@ impl CursorLayer {
             return;
         }
 
-        let fps = uniforms.frame_rate.max(1) as f32;
-        let screen_size = constants.options.screen_size;
-        let screen_diag =
-            (((screen_size.x as f32).powi(2) + (screen_size.y as f32).powi(2)).sqrt()).max(1.0);
-        let fps_scale = fps / CURSOR_BASELINE_FPS;
-        let cursor_strength = (uniforms.motion_blur_amount * CURSOR_MULTIPLIER * fps_scale)
-            .clamp(0.0, CURSOR_MAX_STRENGTH);
-        let child_motion = uniforms
-            .prev_cursor
-            .as_ref()
-            .filter(|prev| prev.cursor_id == interpolated_cursor.cursor_id)
-            .map(|prev| {
-                let delta_uv = XY::new(
-                    (interpolated_cursor.position.coord.x - prev.position.coord.x) as f32,
-                    (interpolated_cursor.position.coord.y - prev.position.coord.y) as f32,
-                );
-                XY::new(
-                    delta_uv.x * screen_size.x as f32,
-                    delta_uv.y * screen_size.y as f32,
-                )
-            })
-            .unwrap_or_else(|| XY::new(0.0, 0.0));
-
-        let combined_motion_px = if cursor_strength <= f32::EPSILON {
-            XY::new(0.0, 0.0)
-        } else {
-            child_motion
-        };
-
-        let normalized_motion = ((combined_motion_px.x / screen_diag).powi(2)
-            + (combined_motion_px.y / screen_diag).powi(2))
-        .sqrt();
-        let motion_response = cursor_motion_response(normalized_motion);
-        let effective_cursor_strength = cursor_strength * motion_response;
-        let scaled_motion = if effective_cursor_strength > f32::EPSILON {
-            cursor_blur_vector(combined_motion_px, effective_cursor_strength)
-        } else {
-            XY::new(0.0, 0.0)
-        };
-
-        let mut cursor_opacity = 1.0f32;
-        if uniforms.project.cursor.hide_when_idle && !cursor.moves.is_empty() {
-            let hide_delay_secs = uniforms
-                .project
-                .cursor
-                .hide_when_idle_delay
-                .max((CURSOR_IDLE_MIN_DELAY_MS / 1000.0) as f32);
-            let hide_delay_ms = (hide_delay_secs as f64 * 1000.0).max(CURSOR_IDLE_MIN_DELAY_MS);
-            cursor_opacity = compute_cursor_idle_opacity(
-                cursor,
-                segment_frames.recording_time as f64 * 1000.0,
-                hide_delay_ms,
-            );
-            if cursor_opacity <= f32::EPSILON {
-                cursor_opacity = 0.0;
-            }
-        }
-
         let cursor_type = uniforms.project.cursor.cursor_type().clone();
 
         if self.prev_cursor_type.as_ref() != Some(&cursor_type) {
@@ -471,145 +321,19 @@ impl CursorLayer {
             tex
         };
 
-        let size = {
-            let click_t = get_click_t(&cursor.clicks, (time_s as f64) * 1000.0);
-            let click_scale_factor = click_t * 1.0 + (1.0 - click_t) * CLICK_SHRINK_SIZE;
-            let crop = ProjectUniforms::get_crop(&constants.options, &uniforms.project);
-            let display_size = ProjectUniforms::display_size(
-                &constants.options,
-                &uniforms.project,
-                resolution_base,
-            );
-            let size = cursor_height_px(
-                constants.options.screen_size.y as f32,
-                crop.size.y as f32,
-                display_size.y as f32,
-                uniforms.cursor_size,
-                click_scale_factor,
-            );
-
-            let texture_size_aspect = {
-                let texture_size = cursor_texture.texture.size();
-                texture_size.width as f32 / texture_size.height as f32
-            };
-
-            Coord::<FrameSpace>::new(if texture_size_aspect > 1.0 {
-                // Wide cursor: base sizing on width to prevent excessive width
-                let width = size;
-                let height = size / texture_size_aspect;
-                XY::new(width, height).into()
-            } else {
-                // Tall or square cursor: base sizing on height (current behavior)
-                XY::new(size * texture_size_aspect, size).into()
-            })
-        };
-
-        let hotspot = Coord::<FrameSpace>::new(size.coord * cursor_texture.hotspot);
-
-        // Calculate position without hotspot first
-        let position = interpolated_cursor.position.to_frame_space(
+        let texture_size = cursor_texture.texture.size();
+        let cursor_uniforms = cursor_uniforms(
             &constants.options,
-            &uniforms.project,
-            resolution_base,
-        ) - hotspot;
-
-        // Transform to zoomed space
-        let zoomed_position = position.to_zoomed_frame_space(
-            &constants.options,
-            &uniforms.project,
+            uniforms,
+            interpolated_cursor,
+            cursor,
+            segment_frames.recording_time,
             resolution_base,
             zoom,
+            (texture_size.width, texture_size.height),
+            cursor_texture.hotspot,
         );
 
-        let zoomed_size = (position + size).to_zoomed_frame_space(
-            &constants.options,
-            &uniforms.project,
-            resolution_base,
-            zoom,
-        ) - zoomed_position;
-
-        // In split-screen the screen only occupies a half-rect, so remap the
-        // cursor from its raw source UV into that pane (matching the display
-        // layer's split crop -> half-rect mapping) and scale the sprite by the
-        // same factor. The cursor shader's screen_bounds clip already follows
-        // uniforms.display.target_bounds (the morphing half), so a cursor that
-        // lands outside the visible crop is confined automatically.
-        let position_size = match &uniforms.split {
-            Some(split) if split.factor > 0.001 => {
-                let screen_size = constants.options.screen_size;
-                let crop = ProjectUniforms::get_crop(&constants.options, &uniforms.project);
-                let display_size = ProjectUniforms::display_size(
-                    &constants.options,
-                    &uniforms.project,
-                    resolution_base,
-                );
-
-                let scrop = split.screen.crop;
-                let starget = split.screen.target;
-                let crop_w = (scrop[2] - scrop[0]).max(f32::EPSILON);
-                let crop_h = (scrop[3] - scrop[1]).max(f32::EPSILON);
-                let target_w = starget[2] - starget[0];
-                let target_h = starget[3] - starget[1];
-
-                let cursor_px = [
-                    cursor_uv.x as f32 * screen_size.x as f32,
-                    cursor_uv.y as f32 * screen_size.y as f32,
-                ];
-                let tip = [
-                    starget[0] + (cursor_px[0] - scrop[0]) / crop_w * target_w,
-                    starget[1] + (cursor_px[1] - scrop[1]) / crop_h * target_h,
-                ];
-
-                // Source->pane scale (uniform; the split crop matches the pane
-                // aspect) relative to the normal source->frame scale.
-                let normal_scale = display_size.x as f32 / (crop.size.x as f32).max(f32::EPSILON);
-                let size_factor = (target_w / crop_w) / normal_scale.max(f32::EPSILON);
-
-                let split_pos = [
-                    tip[0] - hotspot.x as f32 * size_factor,
-                    tip[1] - hotspot.y as f32 * size_factor,
-                ];
-                let split_size = [size.x as f32 * size_factor, size.y as f32 * size_factor];
-
-                let t = split.factor as f32;
-                [
-                    crate::lerp_f32(zoomed_position.x as f32, split_pos[0], t),
-                    crate::lerp_f32(zoomed_position.y as f32, split_pos[1], t),
-                    crate::lerp_f32(zoomed_size.x as f32, split_size[0], t),
-                    crate::lerp_f32(zoomed_size.y as f32, split_size[1], t),
-                ]
-            }
-            _ => [
-                zoomed_position.x as f32,
-                zoomed_position.y as f32,
-                zoomed_size.x as f32,
-                zoomed_size.y as f32,
-            ],
-        };
-
-        let cursor_uniforms = CursorUniforms {
-            position_size,
-            output_size: [
-                uniforms.output_size.0 as f32,
-                uniforms.output_size.1 as f32,
-                0.0,
-                0.0,
-            ],
-            screen_bounds: uniforms.display.target_bounds,
-            motion_vector_strength: [
-                scaled_motion.x,
-                scaled_motion.y,
-                effective_cursor_strength,
-                cursor_opacity,
-            ],
-            rotation_params: [
-                0.0,
-                uniforms.project.cursor.base_rotation,
-                uniforms.cursor_x_axis_tilt_radians,
-                0.0,
-            ],
-        };
-
         constants.queue.write_buffer(
             &self.statics.uniform_buffer,
             0,
@@ -631,6 +355,201 @@ impl CursorLayer {
     }
 }
 
+/// Computes the cursor quad (position, size, motion blur and idle fade) for a
+/// frame. Shared by the GPU cursor layer and the CPU compositor so both place the
+/// cursor identically.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cursor_uniforms(
+    options: &RenderOptions,
+    uniforms: &ProjectUniforms,
+    interpolated_cursor: &InterpolatedCursorPosition,
+    cursor: &CursorEvents,
+    recording_time: f32,
+    resolution_base: XY<u32>,
+    zoom: &InterpolatedZoom,
+    texture_size: (u32, u32),
+    hotspot: XY<f64>,
+) -> CursorUniforms {
+    let cursor_uv = &interpolated_cursor.position.coord;
+    let fps = uniforms.frame_rate.max(1) as f32;
+    let screen_size = options.screen_size;
+    let screen_diag =
+        (((screen_size.x as f32).powi(2) + (screen_size.y as f32).powi(2)).sqrt()).max(1.0);
+    let fps_scale = fps / CURSOR_BASELINE_FPS;
+    let cursor_strength = (uniforms.motion_blur_amount * CURSOR_MULTIPLIER * fps_scale)
+        .clamp(0.0, CURSOR_MAX_STRENGTH);
+    let child_motion = uniforms
+        .prev_cursor
+        .as_ref()
+        .filter(|prev| prev.cursor_id == interpolated_cursor.cursor_id)
+        .map(|prev| {
+            let delta_uv = XY::new(
+                (interpolated_cursor.position.coord.x - prev.position.coord.x) as f32,
+                (interpolated_cursor.position.coord.y - prev.position.coord.y) as f32,
+            );
+            XY::new(
+                delta_uv.x * screen_size.x as f32,
+                delta_uv.y * screen_size.y as f32,
+            )
+        })
+        .unwrap_or_else(|| XY::new(0.0, 0.0));
+
+    let combined_motion_px = if cursor_strength <= f32::EPSILON {
+        XY::new(0.0, 0.0)
+    } else {
+        child_motion
+    };
+
+    let normalized_motion = ((combined_motion_px.x / screen_diag).powi(2)
+        + (combined_motion_px.y / screen_diag).powi(2))
+    .sqrt();
+    let motion_response = cursor_motion_response(normalized_motion);
+    let effective_cursor_strength = cursor_strength * motion_response;
+    let scaled_motion = if effective_cursor_strength > f32::EPSILON {
+        cursor_blur_vector(combined_motion_px, effective_cursor_strength)
+    } else {
+        XY::new(0.0, 0.0)
+    };
+
+    let mut cursor_opacity = 1.0f32;
+    if uniforms.project.cursor.hide_when_idle && !cursor.moves.is_empty() {
+        let hide_delay_secs = uniforms
+            .project
+            .cursor
+            .hide_when_idle_delay
+            .max((CURSOR_IDLE_MIN_DELAY_MS / 1000.0) as f32);
+        let hide_delay_ms = (hide_delay_secs as f64 * 1000.0).max(CURSOR_IDLE_MIN_DELAY_MS);
+        cursor_opacity =
+            compute_cursor_idle_opacity(cursor, recording_time as f64 * 1000.0, hide_delay_ms);
+        if cursor_opacity <= f32::EPSILON {
+            cursor_opacity = 0.0;
+        }
+    }
+
+    let size = {
+        let click_t = get_click_t(&cursor.clicks, (recording_time as f64) * 1000.0);
+        let click_scale_factor = click_t * 1.0 + (1.0 - click_t) * CLICK_SHRINK_SIZE;
+        let crop = ProjectUniforms::get_crop(options, &uniforms.project);
+        let display_size =
+            ProjectUniforms::display_size(options, &uniforms.project, resolution_base);
+        let size = cursor_height_px(
+            options.screen_size.y as f32,
+            crop.size.y as f32,
+            display_size.y as f32,
+            uniforms.cursor_size,
+            click_scale_factor,
+        );
+
+        let texture_size_aspect = texture_size.0 as f32 / texture_size.1 as f32;
+
+        Coord::<FrameSpace>::new(if texture_size_aspect > 1.0 {
+            // Wide cursor: base sizing on width to prevent excessive width
+            let width = size;
+            let height = size / texture_size_aspect;
+            XY::new(width, height).into()
+        } else {
+            // Tall or square cursor: base sizing on height (current behavior)
+            XY::new(size * texture_size_aspect, size).into()
+        })
+    };
+
+    let hotspot = Coord::<FrameSpace>::new(size.coord * hotspot);
+
+    // Calculate position without hotspot first
+    let position =
+        interpolated_cursor
+            .position
+            .to_frame_space(options, &uniforms.project, resolution_base)
+            - hotspot;
+
+    // Transform to zoomed space
+    let zoomed_position =
+        position.to_zoomed_frame_space(options, &uniforms.project, resolution_base, zoom);
+
+    let zoomed_size =
+        (position + size).to_zoomed_frame_space(options, &uniforms.project, resolution_base, zoom)
+            - zoomed_position;
+
+    // In split-screen the screen only occupies a half-rect, so remap the
+    // cursor from its raw source UV into that pane (matching the display
+    // layer's split crop -> half-rect mapping) and scale the sprite by the
+    // same factor. The cursor shader's screen_bounds clip already follows
+    // uniforms.display.target_bounds (the morphing half), so a cursor that
+    // lands outside the visible crop is confined automatically.
+    let position_size = match &uniforms.split {
+        Some(split) if split.factor > 0.001 => {
+            let screen_size = options.screen_size;
+            let crop = ProjectUniforms::get_crop(options, &uniforms.project);
+            let display_size =
+                ProjectUniforms::display_size(options, &uniforms.project, resolution_base);
+
+            let scrop = split.screen.crop;
+            let starget = split.screen.target;
+            let crop_w = (scrop[2] - scrop[0]).max(f32::EPSILON);
+            let crop_h = (scrop[3] - scrop[1]).max(f32::EPSILON);
+            let target_w = starget[2] - starget[0];
+            let target_h = starget[3] - starget[1];
+
+            let cursor_px = [
+                cursor_uv.x as f32 * screen_size.x as f32,
+                cursor_uv.y as f32 * screen_size.y as f32,
+            ];
+            let tip = [
+                starget[0] + (cursor_px[0] - scrop[0]) / crop_w * target_w,
+                starget[1] + (cursor_px[1] - scrop[1]) / crop_h * target_h,
+            ];
+
+            // Source->pane scale (uniform; the split crop matches the pane
+            // aspect) relative to the normal source->frame scale.
+            let normal_scale = display_size.x as f32 / (crop.size.x as f32).max(f32::EPSILON);
+            let size_factor = (target_w / crop_w) / normal_scale.max(f32::EPSILON);
+
+            let split_pos = [
+                tip[0] - hotspot.x as f32 * size_factor,
+                tip[1] - hotspot.y as f32 * size_factor,
+            ];
+            let split_size = [size.x as f32 * size_factor, size.y as f32 * size_factor];
+
+            let t = split.factor as f32;
+            [
+                crate::lerp_f32(zoomed_position.x as f32, split_pos[0], t),
+                crate::lerp_f32(zoomed_position.y as f32, split_pos[1], t),
+                crate::lerp_f32(zoomed_size.x as f32, split_size[0], t),
+                crate::lerp_f32(zoomed_size.y as f32, split_size[1], t),
+            ]
+        }
+        _ => [
+            zoomed_position.x as f32,
+            zoomed_position.y as f32,
+            zoomed_size.x as f32,
+            zoomed_size.y as f32,
+        ],
+    };
+
+    CursorUniforms {
+        position_size,
+        output_size: [
+            uniforms.output_size.0 as f32,
+            uniforms.output_size.1 as f32,
+            0.0,
+            0.0,
+        ],
+        screen_bounds: uniforms.display.target_bounds,
+        motion_vector_strength: [
+            scaled_motion.x,
+            scaled_motion.y,
+            effective_cursor_strength,
+            cursor_opacity,
+        ],
+        rotation_params: [
+            0.0,
+            uniforms.project.cursor.base_rotation,
+            uniforms.cursor_x_axis_tilt_radians,
+            0.0,
+        ],
+    }
+}
+
 fn composite_cursor_layer(dst: &mut [f32; 4], src: [f32; 4]) {
     let src_a = src[3];
     if src_a <= 0.0 {
@@ -710,11 +629,11 @@ fn clamp_cursor_vector(vec: XY<f32>) -> XY<f32> {
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
 pub struct CursorUniforms {
-    position_size: [f32; 4],
-    output_size: [f32; 4],
-    screen_bounds: [f32; 4],
-    motion_vector_strength: [f32; 4],
-    rotation_params: [f32; 4],
+    pub(crate) position_size: [f32; 4],
+    pub(crate) output_size: [f32; 4],
+    pub(crate) screen_bounds: [f32; 4],
+    pub(crate) motion_vector_strength: [f32; 4],
+    pub(crate) rotation_params: [f32; 4],
 }
 
 fn compute_cursor_idle_opacity(
@@ -846,12 +765,8 @@ struct CursorTexture {
 
 impl CursorTexture {
     /// Prepare a cursor texture on the GPU from RGBA data.
-    fn prepare(
-        constants: &RenderVideoConstants,
-        rgba: &[u8],
-        dimensions: (u32, u32),
-        hotspot: XY<f64>,
-    ) -> Self {
+    fn prepare(constants: &RenderVideoConstants, image: &CursorImage) -> Self {
+        let dimensions = (image.width, image.height);
         let texture = constants.device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Cursor Texture"),
             size: wgpu::Extent3d {
@@ -874,7 +789,7 @@ impl CursorTexture {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            rgba,
+            &image.rgba,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * dimensions.0),
@@ -887,15 +802,143 @@ impl CursorTexture {
             },
         );
 
-        Self { texture, hotspot }
+        Self {
+            texture,
+            hotspot: image.hotspot,
+        }
     }
+}
 
-    /// Prepare a cursor texture on the GPU from a raw SVG file
-    fn prepare_svg(
-        constants: &RenderVideoConstants,
-        svg_data: &str,
-        hotspot: XY<f64>,
-    ) -> Result<Self, String> {
+/// Cursor pixels shared by the GPU cursor layer and the CPU compositor.
+///
+/// SVG and circle cursors are premultiplied; PNG cursors captured from the OS are
+/// straight alpha, matching what the cursor shader has always been fed.
+pub(crate) struct CursorImage {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub hotspot: XY<f64>,
+}
+
+impl CursorImage {
+    pub(crate) fn circle() -> Self {
+        let size = CIRCLE_CURSOR_SIZE;
+        let mut rgba = vec![0u8; (size * size * 4) as usize];
+        let center = size as f32 / 2.0;
+        let outer_radius = center - size as f32 * 0.08;
+        let dark_ring_width = size as f32 * 0.014;
+        let light_ring_width = size as f32 * 0.016;
+        let shadow_spread = size as f32 * 0.035;
+        let edge_softness = size as f32 * 0.018;
+
+        let inner_edge = outer_radius - dark_ring_width - light_ring_width;
+
+        for y in 0..size {
+            for x in 0..size {
+                let dx = x as f32 - center + 0.5;
+                let dy = y as f32 - center + 0.5;
+                let dist = (dx * dx + dy * dy).sqrt();
+                let idx = ((y * size + x) * 4) as usize;
+
+                if dist > outer_radius + shadow_spread {
+                    continue;
+                }
+
+                let mut color = [0.0_f32; 4];
+
+                if dist > outer_radius {
+                    let shadow_t = ((dist - outer_radius) / shadow_spread).clamp(0.0, 1.0);
+                    let shadow_alpha = 0.16 * (1.0 - shadow_t * shadow_t);
+                    composite_cursor_layer(&mut color, [0.0, 0.0, 0.0, shadow_alpha]);
+                }
+
+                if dist <= outer_radius + edge_softness {
+                    let outer_fade = 1.0
+                        - ((dist - outer_radius) / edge_softness)
+                            .clamp(0.0, 1.0)
+                            .powf(1.2);
+
+                    if dist > outer_radius - dark_ring_width {
+                        let ring_alpha = 0.38 * outer_fade;
+                        composite_cursor_layer(&mut color, [0.0, 0.0, 0.0, ring_alpha]);
+                    } else if dist > inner_edge {
+                        let ring_alpha = 0.42 * outer_fade;
+                        composite_cursor_layer(&mut color, [1.0, 1.0, 1.0, ring_alpha]);
+                    } else {
+                        let fill_alpha = 0.14 * outer_fade;
+                        composite_cursor_layer(&mut color, [1.0, 1.0, 1.0, fill_alpha]);
+                    }
+                }
+
+                if color[3] > 0.0 {
+                    let a = color[3];
+                    rgba[idx] = (color[0] * a * 255.0).round() as u8;
+                    rgba[idx + 1] = (color[1] * a * 255.0).round() as u8;
+                    rgba[idx + 2] = (color[2] * a * 255.0).round() as u8;
+                    rgba[idx + 3] = (a * 255.0).round() as u8;
+                }
+            }
+        }
+
+        Self {
+            rgba,
+            width: size,
+            height: size,
+            hotspot: XY::new(0.5, 0.5),
+        }
+    }
+
+    pub(crate) fn load(
+        recording_meta: &RecordingMeta,
+        meta: &StudioRecordingMeta,
+        cursor_id: &str,
+        use_svg: bool,
+    ) -> Option<Self> {
+        let mut loaded_cursor = None;
+
+        let cursor_shape = match &recording_meta.inner {
+            RecordingMetaInner::Studio(studio) => match studio.as_ref() {
+                StudioRecordingMeta::MultipleSegments {
+                    inner:
+                        MultipleSegments {
+                            cursors: Cursors::Correct(cursors),
+                            ..
+                        },
+                } => cursors.get(cursor_id).and_then(|v| v.shape),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        if let Some(cursor_shape) = cursor_shape
+            && use_svg
+            && let Some(info) = cursor_shape.resolve()
+        {
+            loaded_cursor = Self::from_svg(info.raw, info.hotspot.into())
+                .map_err(|err| error!("Error loading SVG cursor {cursor_id:?}: {err}"))
+                .ok();
+        }
+
+        if let StudioRecordingMeta::MultipleSegments { inner, .. } = meta
+            && loaded_cursor.is_none()
+            && let Some(c) = inner.get_cursor_image(recording_meta, cursor_id)
+            && let Ok(img) = image::open(&c.path)
+                .map_err(|err| error!("Failed to load cursor image from {:?}: {err}", c.path))
+        {
+            let (width, height) = img.dimensions();
+            loaded_cursor = Some(Self {
+                rgba: img.to_rgba8().into_raw(),
+                width,
+                height,
+                hotspot: c.hotspot,
+            });
+        }
+
+        loaded_cursor
+    }
+
+    /// Rasterize a raw SVG cursor at a fixed height.
+    fn from_svg(svg_data: &str, hotspot: XY<f64>) -> Result<Self, String> {
         let rtree = resvg::usvg::Tree::from_str(svg_data, &resvg::usvg::Options::default())
             .map_err(|e| format!("Failed to parse SVG: {e}"))?;
 
@@ -924,12 +967,12 @@ impl CursorTexture {
             .flat_map(|p| [p.red(), p.green(), p.blue(), p.alpha()])
             .collect();
 
-        Ok(Self::prepare(
-            constants,
-            &rgba,
-            (pixmap.width(), pixmap.height()),
+        Ok(Self {
+            rgba,
+            width: pixmap.width(),
+            height: pixmap.height(),
             hotspot,
-        ))
+        })
     }
 }
 
@@ -1,3 +1,4 @@
+mod annotation;
 mod background;
 mod blur;
 mod camera;
@@ -7,6 +8,7 @@ mod display;
 mod keyboard;
 mod mask;
 mod text;
+mod watermark;
 
 use std::sync::OnceLock;
 
@@ -31,6 +33,7 @@ pub(crate) fn new_font_system() -> glyphon::FontSystem {
     glyphon::FontSystem::new_with_locale_and_db(locale.clone(), db.clone())
 }
 
+pub use annotation::*;
 pub use background::*;
 pub use blur::*;
 pub use camera::*;
@@ -40,3 +43,4 @@ pub use display::*;
 pub use keyboard::*;
 pub use mask::*;
 pub use text::*;
+pub use watermark::*;
@@ -1,20 +1,139 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use bytemuck::{Pod, Zeroable};
+use cap_project::{TextAlign, bundled_file_path};
 use glyphon::cosmic_text::Align;
 use glyphon::{
     Attrs, Buffer, Cache, Color, Family, FontSystem, Metrics, Resolution, Shaping, Style,
-    SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer, Viewport, Weight,
+    SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer, Viewport, Weight, fontdb,
 };
 use log::warn;
-use wgpu::{Device, Queue};
+use wgpu::{Device, Queue, util::DeviceExt};
 
+use super::{CaptionBackgroundUniforms, caption_background_pipeline};
 use crate::text::PreparedText;
 
+/// Rotations smaller than this (in radians) are drawn straight onto the frame.
+const MIN_ROTATION: f32 = 1e-4;
+/// Offsets the outline is stamped at around each glyph.
+const OUTLINE_TAPS: usize = 16;
+/// Offsets a blurred shadow is stamped at on each of its two rings.
+const SHADOW_BLUR_TAPS: usize = 8;
+
 pub struct TextLayer {
     font_system: FontSystem,
     swash_cache: SwashCache,
     text_atlas: TextAtlas,
     text_renderer: TextRenderer,
     viewport: Viewport,
-    buffers: Vec<Buffer>,
+    shaped: Vec<ShapedText>,
+    rotated_pipeline: RotatedTextPipeline,
+    sampler: wgpu::Sampler,
+    rotated: Vec<RotatedText>,
+    rotated_count: usize,
+    bundled_fonts: BundledFonts,
+    background_layout: wgpu::BindGroupLayout,
+    background_pipeline: wgpu::RenderPipeline,
+    backgrounds: Vec<TextBackground>,
+    background_count: usize,
+}
+
+/// glyphon can only draw axis-aligned text, so each rotated text is drawn into its own
+/// frame-sized texture and composited onto the frame through `rotated-text.wgsl`.
+struct RotatedText {
+    text_renderer: TextRenderer,
+    size: (u32, u32),
+    view: wgpu::TextureView,
+    uniforms_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    background: TextBackground,
+    has_background: bool,
+}
+
+/// One rounded box behind a text, drawn with the captions' `caption_bg.wgsl` pipeline.
+struct TextBackground {
+    uniforms_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl TextBackground {
+    fn new(
+        device: &Device,
+        layout: &wgpu::BindGroupLayout,
+        uniforms: &CaptionBackgroundUniforms,
+    ) -> Self {
+        let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Text Background Uniform Buffer"),
+            contents: bytemuck::bytes_of(uniforms),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Text Background Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            uniforms_buffer,
+            bind_group,
+        }
+    }
+}
+
+/// Fonts loaded from the project bundle, keyed by path, with the family each one registered
+/// under (`None` when the file couldn't be loaded, so it isn't retried every frame).
+#[derive(Default)]
+pub(crate) struct BundledFonts {
+    families: HashMap<PathBuf, Option<String>>,
+}
+
+impl BundledFonts {
+    /// The family to shape `text` with when it names a font file in the project at
+    /// `project_path`, loading the file into `font_system` the first time it's seen.
+    pub fn family(
+        &mut self,
+        font_system: &mut FontSystem,
+        project_path: &Path,
+        text: &PreparedText,
+    ) -> Option<String> {
+        let path = bundled_file_path(project_path, text.font_path.as_deref()?)?;
+
+        self.families
+            .entry(path)
+            .or_insert_with_key(|path| load_font(font_system, path))
+            .clone()
+    }
+}
+
+fn load_font(font_system: &mut FontSystem, path: &Path) -> Option<String> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(error) => {
+            warn!("Failed to read bundled font {}: {error}", path.display());
+            return None;
+        }
+    };
+
+    let db = font_system.db_mut();
+    let family = db
+        .load_font_source(fontdb::Source::Binary(Arc::new(data)))
+        .first()
+        .and_then(|id| db.face(*id))
+        .and_then(|face| face.families.first())
+        .map(|(family, _)| family.clone());
+
+    if family.is_none() {
+        warn!("{} is not a font file that can be loaded", path.display());
+    }
+
+    family
 }
 
 impl TextLayer {
@@ -31,13 +150,32 @@ impl TextLayer {
             None,
         );
 
+        let (background_layout, background_pipeline) = caption_background_pipeline(device);
+
         Self {
             font_system,
             swash_cache,
             text_atlas,
             text_renderer,
             viewport,
-            buffers: Vec::new(),
+            shaped: Vec::new(),
+            rotated_pipeline: RotatedTextPipeline::new(device),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }),
+            rotated: Vec::new(),
+            rotated_count: 0,
+            bundled_fonts: BundledFonts::default(),
+            background_layout,
+            background_pipeline,
+            backgrounds: Vec::new(),
+            background_count: 0,
         }
     }
 
@@ -45,91 +183,19 @@ impl TextLayer {
         &mut self,
         device: &Device,
         queue: &Queue,
+        project_path: &Path,
         output_size: (u32, u32),
         texts: &[PreparedText],
     ) {
-        self.buffers.clear();
-        self.buffers.reserve(texts.len());
-        let mut text_area_data = Vec::with_capacity(texts.len());
-
+        self.shaped.clear();
         for text in texts {
-            let alpha = text.color[3].clamp(0.0, 1.0) * text.opacity.clamp(0.0, 1.0);
-            let color = Color::rgba(
-                (text.color[0].clamp(0.0, 1.0) * 255.0) as u8,
-                (text.color[1].clamp(0.0, 1.0) * 255.0) as u8,
-                (text.color[2].clamp(0.0, 1.0) * 255.0) as u8,
-                (alpha * 255.0) as u8,
-            );
-
-            let width = (text.bounds[2] - text.bounds[0]).max(1.0);
-            let height = (text.bounds[3] - text.bounds[1]).max(1.0);
-
-            let metrics = Metrics::new(text.font_size, text.font_size * 1.2);
-            let mut buffer = Buffer::new(&mut self.font_system, metrics);
-            buffer.set_size(&mut self.font_system, Some(width), Some(height));
-            buffer.set_wrap(&mut self.font_system, glyphon::Wrap::Word);
-
-            let family = match text.font_family.trim() {
-                "" => Family::SansSerif,
-                name => match name.to_ascii_lowercase().as_str() {
-                    "sans" | "sans-serif" | "system sans" | "system sans-serif" => {
-                        Family::SansSerif
-                    }
-                    "serif" | "system serif" => Family::Serif,
-                    "mono" | "monospace" | "system mono" | "system monospace" => Family::Monospace,
-                    _ => Family::Name(name),
-                },
-            };
-            let weight = Weight(text.font_weight.round().clamp(100.0, 900.0) as u16);
-            let attrs = Attrs::new()
-                .family(family)
-                .color(color)
-                .weight(weight)
-                .style(if text.italic {
-                    Style::Italic
-                } else {
-                    Style::Normal
-                });
-
-            buffer.set_text(
-                &mut self.font_system,
-                &text.content,
-                &attrs,
-                Shaping::Advanced,
-            );
-
-            for line in buffer.lines.iter_mut() {
-                line.set_align(Some(Align::Center));
-            }
-
-            buffer.shape_until_scroll(&mut self.font_system, false);
-
-            let bounds = TextBounds {
-                left: text.bounds[0].floor() as i32,
-                top: text.bounds[1].floor() as i32,
-                right: (text.bounds[0] + width).ceil() as i32,
-                bottom: (text.bounds[1] + height).ceil() as i32,
-            };
-
-            self.buffers.push(buffer);
-            text_area_data.push((bounds, text.bounds[0], text.bounds[1], color));
+            let family = self
+                .bundled_fonts
+                .family(&mut self.font_system, project_path, text);
+            self.shaped
+                .push(shape_text(&mut self.font_system, text, family.as_deref()));
         }
 
-        let text_areas = self
-            .buffers
-            .iter()
-            .zip(text_area_data)
-            .map(|(buffer, (bounds, left, top, color))| TextArea {
-                buffer,
-                left,
-                top,
-                scale: 1.0,
-                bounds,
-                default_color: color,
-                custom_glyphs: &[],
-            })
-            .collect::<Vec<_>>();
-
         self.viewport.update(
             queue,
             Resolution {
@@ -138,6 +204,14 @@ impl TextLayer {
             },
         );
 
+        let text_areas = self
+            .shaped
+            .iter()
+            .zip(texts)
+            .filter(|(_, text)| text.rotation.abs() < MIN_ROTATION)
+            .flat_map(|(shaped, _)| shaped.text_areas())
+            .collect::<Vec<_>>();
+
         if let Err(error) = self.text_renderer.prepare(
             device,
             queue,
@@ -149,14 +223,555 @@ impl TextLayer {
         ) {
             warn!("Failed to prepare text: {error:?}");
         }
+
+        self.background_count = 0;
+        for (shaped, text) in self.shaped.iter().zip(texts) {
+            let Some(uniforms) = &shaped.background else {
+                continue;
+            };
+            if text.rotation.abs() >= MIN_ROTATION {
+                continue;
+            }
+
+            if let Some(background) = self.backgrounds.get(self.background_count) {
+                queue.write_buffer(&background.uniforms_buffer, 0, bytemuck::bytes_of(uniforms));
+            } else {
+                self.backgrounds.push(TextBackground::new(
+                    device,
+                    &self.background_layout,
+                    uniforms,
+                ));
+            }
+            self.background_count += 1;
+        }
+
+        self.rotated_count = 0;
+        for (shaped, text) in self.shaped.iter().zip(texts) {
+            if text.rotation.abs() < MIN_ROTATION {
+                continue;
+            }
+
+            if self.rotated.len() == self.rotated_count {
+                self.rotated.push(RotatedText::new(
+                    device,
+                    &mut self.text_atlas,
+                    &self.rotated_pipeline,
+                    &self.background_layout,
+                    &self.sampler,
+                    output_size,
+                ));
+            }
+
+            let rotated = &mut self.rotated[self.rotated_count];
+            if rotated.size != output_size {
+                *rotated = RotatedText::new(
+                    device,
+                    &mut self.text_atlas,
+                    &self.rotated_pipeline,
+                    &self.background_layout,
+                    &self.sampler,
+                    output_size,
+                );
+            }
+
+            if let Err(error) = rotated.text_renderer.prepare(
+                device,
+                queue,
+                &mut self.font_system,
+                &mut self.text_atlas,
+                &self.viewport,
+                shaped.text_areas(),
+                &mut self.swash_cache,
+            ) {
+                warn!("Failed to prepare rotated text: {error:?}");
+                continue;
+            }
+
+            rotated.has_background = shaped.background.is_some();
+            if let Some(uniforms) = &shaped.background {
+                queue.write_buffer(
+                    &rotated.background.uniforms_buffer,
+                    0,
+                    bytemuck::bytes_of(uniforms),
+                );
+            }
+
+            let (sin, cos) = text.rotation.sin_cos();
+            let uniforms = RotatedTextUniforms {
+                output_size: [output_size.0 as f32, output_size.1 as f32],
+                pivot: [
+                    (text.bounds[0] + text.bounds[2]) / 2.0,
+                    (text.bounds[1] + text.bounds[3]) / 2.0,
+                ],
+                rotation: [sin, cos],
+                _padding: [0.0; 2],
+            };
+            queue.write_buffer(
+                &rotated.uniforms_buffer,
+                0,
+                bytemuck::cast_slice(&[uniforms]),
+            );
+
+            self.rotated_count += 1;
+        }
     }
 
-    pub fn render<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let rotated = &self.rotated[..self.rotated_count];
+
+        for text in rotated {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Rotated Text Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &text.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            if text.has_background {
+                pass.set_pipeline(&self.background_pipeline);
+                pass.set_bind_group(0, &text.background.bind_group, &[]);
+                pass.draw(0..6, 0..1);
+            }
+
+            if let Err(error) =
+                text.text_renderer
+                    .render(&self.text_atlas, &self.viewport, &mut pass)
+            {
+                warn!("Failed to render rotated text: {error:?}");
+            }
+        }
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Text Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        // Every box goes under every unrotated text; glyphon draws all of them in one call.
+        pass.set_pipeline(&self.background_pipeline);
+        for background in &self.backgrounds[..self.background_count] {
+            pass.set_bind_group(0, &background.bind_group, &[]);
+            pass.draw(0..6, 0..1);
+        }
+
         if let Err(error) = self
             .text_renderer
-            .render(&self.text_atlas, &self.viewport, pass)
+            .render(&self.text_atlas, &self.viewport, &mut pass)
         {
             warn!("Failed to render text: {error:?}");
         }
+
+        pass.set_pipeline(&self.rotated_pipeline.render_pipeline);
+        for text in rotated {
+            pass.set_bind_group(0, &text.bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+impl RotatedText {
+    fn new(
+        device: &Device,
+        text_atlas: &mut TextAtlas,
+        pipeline: &RotatedTextPipeline,
+        background_layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        size: (u32, u32),
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Rotated Text Texture"),
+            size: wgpu::Extent3d {
+                width: size.0.max(1),
+                height: size.1.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let uniforms_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rotated Text Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[RotatedTextUniforms::zeroed()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = pipeline.bind_group(device, &view, sampler, &uniforms_buffer);
+
+        Self {
+            text_renderer: TextRenderer::new(
+                text_atlas,
+                device,
+                wgpu::MultisampleState::default(),
+                None,
+            ),
+            size,
+            view,
+            uniforms_buffer,
+            bind_group,
+            background: TextBackground::new(
+                device,
+                background_layout,
+                &CaptionBackgroundUniforms::zeroed(),
+            ),
+            has_background: false,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct RotatedTextUniforms {
+    output_size: [f32; 2],
+    pivot: [f32; 2],
+    rotation: [f32; 2],
+    _padding: [f32; 2],
+}
+
+struct RotatedTextPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl RotatedTextPipeline {
+    fn new(device: &Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Rotated Text Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Rotated Text Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/rotated-text.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Rotated Text Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Rotated Text Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[],
+                    zero_initialize_workgroup_memory: false,
+                },
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[],
+                    zero_initialize_workgroup_memory: false,
+                },
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+
+    fn bind_group(
+        &self,
+        device: &Device,
+        texture_view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        uniforms_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Rotated Text Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniforms_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
+
+/// A text overlay shaped for drawing at its prepared bounds, shared by the GPU
+/// text layer and the CPU compositor.
+pub(crate) struct ShapedText {
+    pub buffer: Buffer,
+    /// Clip rectangle for every pass, grown to fit outlines and shadows.
+    pub bounds: TextBounds,
+    /// The buffer is drawn once per pass, in order: shadow, outline, then the fill.
+    pub passes: Vec<TextPass>,
+    pub background: Option<CaptionBackgroundUniforms>,
+}
+
+pub(crate) struct TextPass {
+    pub left: f32,
+    pub top: f32,
+    pub color: Color,
+}
+
+impl ShapedText {
+    fn text_areas(&self) -> impl Iterator<Item = TextArea<'_>> {
+        self.passes.iter().map(|pass| TextArea {
+            buffer: &self.buffer,
+            left: pass.left,
+            top: pass.top,
+            scale: 1.0,
+            bounds: self.bounds,
+            default_color: pass.color,
+            custom_glyphs: &[],
+        })
+    }
+}
+
+fn text_color(color: [f32; 4], opacity: f32) -> Color {
+    let alpha = color[3].clamp(0.0, 1.0) * opacity;
+    Color::rgba(
+        (color[0].clamp(0.0, 1.0) * 255.0) as u8,
+        (color[1].clamp(0.0, 1.0) * 255.0) as u8,
+        (color[2].clamp(0.0, 1.0) * 255.0) as u8,
+        (alpha * 255.0) as u8,
+    )
+}
+
+/// `count` points evenly spaced on a circle of `radius`.
+fn ring(radius: f32, count: usize) -> impl Iterator<Item = (f32, f32)> {
+    (0..count).map(move |i| {
+        let (sin, cos) = (i as f32 * std::f32::consts::TAU / count as f32).sin_cos();
+        (cos * radius, sin * radius)
+    })
+}
+
+/// The laid-out glyphs' extent relative to the buffer origin, as `[left, top, right, bottom]`.
+fn layout_extent(buffer: &Buffer) -> Option<[f32; 4]> {
+    buffer
+        .layout_runs()
+        .filter(|run| !run.glyphs.is_empty())
+        .map(|run| {
+            let left = run.glyphs.iter().map(|g| g.x).fold(f32::MAX, f32::min);
+            let right = run
+                .glyphs
+                .iter()
+                .map(|g| g.x + g.w)
+                .fold(f32::MIN, f32::max);
+            [left, run.line_top, right, run.line_top + run.line_height]
+        })
+        .reduce(|a, b| {
+            [
+                a[0].min(b[0]),
+                a[1].min(b[1]),
+                a[2].max(b[2]),
+                a[3].max(b[3]),
+            ]
+        })
+}
+
+/// Shapes `text`, using `bundled_family` (a font loaded from the project bundle) in place of
+/// its font family when given.
+pub(crate) fn shape_text(
+    font_system: &mut FontSystem,
+    text: &PreparedText,
+    bundled_family: Option<&str>,
+) -> ShapedText {
+    let opacity = text.opacity.clamp(0.0, 1.0);
+
+    let width = (text.bounds[2] - text.bounds[0]).max(1.0);
+    let height = (text.bounds[3] - text.bounds[1]).max(1.0);
+
+    let metrics = Metrics::new(text.font_size, text.font_size * text.line_height);
+    let mut buffer = Buffer::new(font_system, metrics);
+    buffer.set_size(font_system, Some(width), Some(height));
+    buffer.set_wrap(font_system, glyphon::Wrap::Word);
+
+    let family = match (bundled_family, text.font_family.trim()) {
+        (Some(name), _) => Family::Name(name),
+        (None, "") => Family::SansSerif,
+        (None, name) => match name.to_ascii_lowercase().as_str() {
+            "sans" | "sans-serif" | "system sans" | "system sans-serif" => Family::SansSerif,
+            "serif" | "system serif" => Family::Serif,
+            "mono" | "monospace" | "system mono" | "system monospace" => Family::Monospace,
+            _ => Family::Name(name),
+        },
+    };
+    let weight = Weight(text.font_weight.round().clamp(100.0, 900.0) as u16);
+    // No color here: every pass draws the same glyphs in its own `default_color`.
+    let attrs = Attrs::new()
+        .family(family)
+        .weight(weight)
+        .style(if text.italic {
+            Style::Italic
+        } else {
+            Style::Normal
+        })
+        .letter_spacing(text.letter_spacing);
+
+    buffer.set_text(font_system, &text.content, &attrs, Shaping::Advanced);
+
+    let align = match text.align {
+        TextAlign::Left => Align::Left,
+        TextAlign::Center => Align::Center,
+        TextAlign::Right => Align::Right,
+    };
+    for line in buffer.lines.iter_mut() {
+        line.set_align(Some(align));
+    }
+
+    buffer.shape_until_scroll(font_system, false);
+
+    let overhang = text.overhang();
+    let bounds = TextBounds {
+        left: (text.bounds[0] - overhang).floor() as i32,
+        top: (text.bounds[1] - overhang).floor() as i32,
+        right: (text.bounds[0] + width + overhang).ceil() as i32,
+        bottom: (text.bounds[1] + height + overhang).ceil() as i32,
+    };
+
+    let (left, top) = (text.bounds[0], text.bounds[1]);
+    let mut passes = Vec::new();
+
+    if let Some(shadow) = text.shadow {
+        let (x, y) = (left + shadow.offset[0], top + shadow.offset[1]);
+        let mut taps = vec![(0.0, 0.0)];
+        if shadow.blur >= 0.5 {
+            taps.extend(ring(shadow.blur * 0.5, SHADOW_BLUR_TAPS));
+            taps.extend(ring(shadow.blur, SHADOW_BLUR_TAPS));
+        }
+
+        // Split the alpha so the taps add back up to it where they all overlap and thin out
+        // towards the edge, which reads as a soft shadow.
+        let alpha = shadow.color[3].clamp(0.0, 1.0) * opacity;
+        let tap_alpha = 1.0 - (1.0 - alpha).powf(1.0 / taps.len() as f32);
+        let [r, g, b, _] = shadow.color;
+        passes.extend(taps.into_iter().map(|(dx, dy)| TextPass {
+            left: x + dx,
+            top: y + dy,
+            color: text_color([r, g, b, tap_alpha], 1.0),
+        }));
+    }
+
+    if let Some(outline) = text.outline {
+        let color = text_color(outline.color, opacity);
+        let mut taps = ring(outline.width, OUTLINE_TAPS).collect::<Vec<_>>();
+        if outline.width > 3.0 {
+            taps.extend(ring(outline.width * 0.5, OUTLINE_TAPS / 2));
+        }
+
+        passes.extend(taps.into_iter().map(|(dx, dy)| TextPass {
+            left: left + dx,
+            top: top + dy,
+            color,
+        }));
+    }
+
+    passes.push(TextPass {
+        left,
+        top,
+        color: text_color(text.color, opacity),
+    });
+
+    let background = text.background.zip(layout_extent(&buffer)).map(
+        |(background, [min_x, min_y, max_x, max_y])| {
+            let [pad_x, pad_y] = background.padding;
+            let rect = [
+                left + min_x - pad_x,
+                top + min_y - pad_y,
+                max_x - min_x + pad_x * 2.0,
+                max_y - min_y + pad_y * 2.0,
+            ];
+            let [r, g, b, a] = background.color;
+
+            CaptionBackgroundUniforms {
+                rect,
+                color: [r, g, b, a.clamp(0.0, 1.0) * opacity],
+                radius: background.radius.min(rect[2] / 2.0).min(rect[3] / 2.0),
+                _padding: [0.0; 3],
+                _padding2: [0.0; 4],
+            }
+        },
+    );
+
+    ShapedText {
+        buffer,
+        bounds,
+        passes,
+        background,
     }
 }
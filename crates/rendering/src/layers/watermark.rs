@@ -0,0 +1,276 @@
+use bytemuck::{Pod, Zeroable};
+use cap_project::XY;
+use wgpu::util::DeviceExt;
+
+use crate::{PreparedWatermark, RenderVideoConstants, clean_background_path};
+
+/// Composites the project's logo over the finished frame using `watermark.wgsl`.
+pub struct WatermarkLayer {
+    sampler: wgpu::Sampler,
+    pipeline: WatermarkPipeline,
+    uniforms_buffer: wgpu::Buffer,
+    image: Option<WatermarkImage>,
+    /// The last image path that failed to load, so a missing logo is reported once instead of
+    /// being retried on every frame.
+    failed_path: Option<String>,
+    visible: bool,
+}
+
+struct WatermarkImage {
+    path: String,
+    size: XY<u32>,
+    bind_group: wgpu::BindGroup,
+}
+
+impl WatermarkLayer {
+    pub fn new(device: &wgpu::Device) -> Self {
+        Self {
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }),
+            pipeline: WatermarkPipeline::new(device),
+            uniforms_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Watermark Uniform Buffer"),
+                contents: bytemuck::cast_slice(&[WatermarkUniforms::zeroed()]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }),
+            image: None,
+            failed_path: None,
+            visible: false,
+        }
+    }
+
+    pub async fn prepare(
+        &mut self,
+        constants: &RenderVideoConstants,
+        watermark: Option<&PreparedWatermark>,
+    ) {
+        self.visible = false;
+
+        let Some(watermark) = watermark else {
+            return;
+        };
+
+        if self
+            .image
+            .as_ref()
+            .is_none_or(|image| image.path != watermark.image_path)
+        {
+            self.image = None;
+
+            if self.failed_path.as_deref() == Some(watermark.image_path.as_str()) {
+                return;
+            }
+
+            // Shares the decoded-image cache with image backgrounds, so a logo is only decoded
+            // once per process even though every export builds its own layers.
+            let texture = match clean_background_path(&watermark.image_path) {
+                Some(path) => {
+                    constants
+                        .background_textures
+                        .ensure(&constants.device, &constants.queue, &path)
+                        .await
+                }
+                None => None,
+            };
+            let Some(texture) = texture else {
+                tracing::warn!(
+                    path = %watermark.image_path,
+                    "Watermark image couldn't be loaded, skipping watermark"
+                );
+                self.failed_path = Some(watermark.image_path.clone());
+                return;
+            };
+            self.failed_path = None;
+
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.image = Some(WatermarkImage {
+                path: watermark.image_path.clone(),
+                size: XY::new(texture.width(), texture.height()),
+                bind_group: self.pipeline.bind_group(
+                    &constants.device,
+                    &view,
+                    &self.sampler,
+                    &self.uniforms_buffer,
+                ),
+            });
+        }
+
+        let Some(image) = &self.image else {
+            return;
+        };
+
+        let (position, size) = watermark.rect(image.size);
+        let uniforms = WatermarkUniforms {
+            output_size: [
+                watermark.output_size.x as f32,
+                watermark.output_size.y as f32,
+            ],
+            watermark_size: [size.x, size.y],
+            position: [position.x, position.y],
+            opacity: watermark.opacity,
+            is_upgraded: 1.0,
+        };
+        constants
+            .queue
+            .write_buffer(&self.uniforms_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+        self.visible = size.x >= 1.0 && size.y >= 1.0;
+    }
+
+    pub fn has_content(&self) -> bool {
+        self.visible
+    }
+
+    pub fn render(&self, pass: &mut wgpu::RenderPass<'_>) {
+        let Some(image) = self.image.as_ref().filter(|_| self.visible) else {
+            return;
+        };
+
+        pass.set_pipeline(&self.pipeline.render_pipeline);
+        pass.set_bind_group(0, &image.bind_group, &[]);
+        pass.draw(0..6, 0..1);
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct WatermarkUniforms {
+    output_size: [f32; 2],
+    watermark_size: [f32; 2],
+    position: [f32; 2],
+    opacity: f32,
+    is_upgraded: f32,
+}
+
+struct WatermarkPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl WatermarkPipeline {
+    fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Watermark Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Watermark Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/watermark.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Watermark Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Watermark Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[],
+                    zero_initialize_workgroup_memory: false,
+                },
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    // The shader outputs premultiplied colour.
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &[],
+                    zero_initialize_workgroup_memory: false,
+                },
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                // The quad's winding flips with the shader's y-down screen space.
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            bind_group_layout,
+            render_pipeline,
+        }
+    }
+
+    fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        texture_view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+        uniforms_buffer: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Watermark Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniforms_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
@@ -2,7 +2,7 @@ use anyhow::Result;
 use cap_project::{
     AspectRatio, CameraShape, CameraXPosition, CameraYPosition, ClipOffsets, CornerStyle, Crop,
     CursorEvents, CursorType, MaskKind, ProjectConfiguration, RecordingMeta, SceneMode,
-    StudioRecordingMeta, XY,
+    StudioRecordingMeta, TimelineTransition, TransitionKind, XY,
 };
 use composite_frame::CompositeVideoFrameUniforms;
 use core::f64;
@@ -17,7 +17,7 @@ use frame_pipeline::{
 use futures::future::OptionFuture;
 use layers::{
     Background, BackgroundLayer, BlurLayer, CameraLayer, CaptionsLayer, CursorLayer, DisplayLayer,
-    KeyboardLayer, MaskLayer, TextLayer,
+    KeyboardLayer, MaskLayer, TextLayer, WatermarkLayer,
 };
 use specta::Type;
 use spring_mass_damper::SpringMassDamperSimulationConfig;
@@ -28,8 +28,10 @@ use std::sync::{
 use std::{path::PathBuf, time::Instant};
 use tokio::sync::mpsc;
 
+mod annotation;
 pub mod composite_frame;
 mod coord;
+pub mod cpu_compositor;
 pub mod cpu_yuv;
 mod cursor_interpolation;
 #[cfg(target_os = "windows")]
@@ -44,11 +46,15 @@ mod project_recordings;
 mod scene;
 pub mod spring_mass_damper;
 mod text;
+mod transition;
+mod watermark;
 pub mod yuv_converter;
 mod zoom;
 pub mod zoom_focus_interpolation;
 
+pub use annotation::{AnnotationShape, PreparedAnnotation, PreparedAnnotations};
 pub use coord::*;
+pub use cpu_compositor::{CpuCompositor, CpuRenderConstants};
 pub use decoder::{DecodedFrame, DecoderStatus, DecoderType, PixelFormat};
 pub use frame_pipeline::{GpuOutputFormat, Nv12RenderedFrame, RenderedFrame, SharedNv12Buffer};
 pub use layers::{BackgroundTextureCache, clean_background_path};
@@ -63,10 +69,14 @@ pub fn prewarm_fonts() {
     drop(layers::new_font_system());
 }
 
+use annotation::prepare_annotations;
 pub use cursor_interpolation::PrecomputedCursorTimeline;
 use mask::interpolate_masks;
 use scene::*;
 use text::{PreparedText, prepare_texts};
+pub use transition::blend_transition;
+pub use watermark::PreparedWatermark;
+use watermark::prepare_watermark;
 use zoom::*;
 pub use zoom_focus_interpolation::ZoomFocusInterpolator;
 
@@ -112,6 +122,7 @@ impl Nv12RenderStartupBreakdownMs {
 }
 
 static FORCE_SOFTWARE_WGPU_ADAPTER: AtomicBool = AtomicBool::new(false);
+static FORCE_CPU_COMPOSITOR: AtomicBool = AtomicBool::new(false);
 
 const NON_HARDWARE_WGPU_ADAPTER_MARKERS: &[&str] = &[
     "parsec",
@@ -150,6 +161,22 @@ pub fn force_software_wgpu_adapter() -> bool {
         })
 }
 
+pub fn set_force_cpu_compositor(value: bool) {
+    FORCE_CPU_COMPOSITOR.store(value, Ordering::Release);
+}
+
+/// Skips wgpu entirely and renders with [`cpu_compositor`], for hosts where even
+/// requesting an adapter is undesirable.
+pub fn force_cpu_compositor() -> bool {
+    FORCE_CPU_COMPOSITOR.load(Ordering::Acquire)
+        || std::env::var("CAP_RENDER_FORCE_CPU_COMPOSITOR").is_ok_and(|value| {
+            matches!(
+                value.to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+}
+
 pub fn create_wgpu_instance_sync() -> wgpu::Instance {
     #[cfg(not(target_os = "windows"))]
     let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
@@ -215,6 +242,21 @@ pub struct RenderOptions {
     pub preserve_screen_alpha: bool,
 }
 
+impl RenderOptions {
+    fn from_segments(segments: &[SegmentRecordings]) -> Result<Self, RenderingError> {
+        let first_segment = segments.first().ok_or(RenderingError::NoSegments)?;
+
+        Ok(Self {
+            screen_size: XY::new(first_segment.display.width, first_segment.display.height),
+            camera_size: first_segment
+                .camera
+                .as_ref()
+                .map(|c| XY::new(c.width, c.height)),
+            preserve_screen_alpha: false,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum MaskRenderMode {
     Sensitive,
@@ -647,6 +689,38 @@ pub async fn render_video_to_channel(
             std::mem::replace(&mut frame_number, prev + 1)
         };
 
+        if let Some(transition) = project.get_transition(current_frame_number as f64 / fps as f64) {
+            if let Some(Ok(pending)) = frame_renderer.flush_pipeline().await
+                && pending.width > 0
+                && pending.height > 0
+            {
+                let pending_frame_number = pending.frame_number;
+                sender.send((pending, pending_frame_number)).await?;
+            }
+
+            if let Some(frame) = render_transition_frame(
+                &mut frame_renderer,
+                &mut layers,
+                project,
+                recordings,
+                &render_segments,
+                &mut zoom_focus_interpolators,
+                &precomputed_cursor_timelines,
+                &transition,
+                current_frame_number,
+                fps,
+                resolution_base,
+                duration,
+            )
+            .await
+            {
+                consecutive_failures = 0;
+                last_successful_frame = Some(frame.clone());
+                sender.send((frame, current_frame_number)).await?;
+                continue;
+            }
+        }
+
         let render_segment = &render_segments[segment.recording_clip as usize];
         let is_initial_frame = current_frame_number == 0 || last_successful_frame.is_none();
         let segment_clip_index = segment.recording_clip as usize;
@@ -1011,6 +1085,58 @@ pub async fn render_video_to_channel_nv12(
             std::mem::replace(&mut frame_number, prev + 1)
         };
 
+        if let Some(transition) = project.get_transition(current_frame_number as f64 / fps as f64) {
+            let pending = if constants.is_software_adapter {
+                frame_renderer.flush_pipeline().await.map(|result| {
+                    result.map(|frame| {
+                        Nv12RenderedFrame::from_rgba(&frame, &frame_renderer.nv12_buffer_pool)
+                    })
+                })
+            } else {
+                frame_renderer.flush_pipeline_nv12().await
+            };
+            if let Some(Ok(pending)) = pending
+                && pending.width > 0
+                && pending.height > 0
+            {
+                let pending_frame_number = pending.frame_number;
+                sender.send((pending, pending_frame_number)).await?;
+                channel_frames_sent += 1;
+                if stop_after_frames_sent.is_some_and(|m| channel_frames_sent >= m) {
+                    stopped_after_frame_limit = true;
+                    break;
+                }
+            }
+
+            if let Some(frame) = render_transition_frame(
+                &mut frame_renderer,
+                &mut layers,
+                project,
+                recordings,
+                &render_segments,
+                &mut zoom_focus_interpolators,
+                &precomputed_cursor_timelines,
+                &transition,
+                current_frame_number,
+                fps,
+                resolution_base,
+                duration,
+            )
+            .await
+            {
+                let frame = Nv12RenderedFrame::from_rgba(&frame, &frame_renderer.nv12_buffer_pool);
+                consecutive_failures = 0;
+                last_successful_frame = Some(frame.clone_metadata_with_data());
+                sender.send((frame, current_frame_number)).await?;
+                channel_frames_sent += 1;
+                if stop_after_frames_sent.is_some_and(|m| channel_frames_sent >= m) {
+                    stopped_after_frame_limit = true;
+                    break;
+                }
+                continue;
+            }
+        }
+
         let render_segment = &render_segments[segment.recording_clip as usize];
         let is_initial_frame = current_frame_number == 0 || last_successful_frame.is_none();
         let segment_clip_index = segment.recording_clip as usize;
@@ -1487,6 +1613,128 @@ async fn decode_segment_frames_with_retry(
     result
 }
 
+/// Decodes the clips either side of `transition` for the frame at `current_frame_number`, as
+/// `(recording clip index, frames)` for the outgoing then the incoming clip.
+async fn decode_transition_frames(
+    project: &ProjectConfiguration,
+    recordings: &ProjectRecordingsMeta,
+    render_segments: &[RenderSegment],
+    zoom_focus_interpolators: &mut [ZoomFocusInterpolator],
+    transition: &TimelineTransition<'_>,
+    current_frame_number: u32,
+    fps: u32,
+) -> Option<[(usize, DecodedSegmentFrames); 2]> {
+    let frame_time = current_frame_number as f64 / fps as f64;
+    let zoom_until = (current_frame_number as f32 + 1.0) / fps as f32;
+    let outgoing_end = recordings
+        .segments
+        .get(transition.outgoing.recording_clip as usize)?
+        .display
+        .last_frame_time();
+
+    let mut sides = Vec::with_capacity(2);
+    for (segment, segment_time) in [
+        (
+            transition.outgoing,
+            transition.outgoing_time(frame_time, outgoing_end),
+        ),
+        (transition.incoming, transition.incoming_time(frame_time)),
+    ] {
+        let clip_index = segment.recording_clip as usize;
+        let render_segment = render_segments.get(clip_index)?;
+        let offsets = project
+            .clips
+            .iter()
+            .find(|v| v.index == segment.recording_clip)
+            .map(|v| v.offsets)
+            .unwrap_or_default();
+
+        zoom_focus_interpolators[clip_index].ensure_precomputed_until(zoom_until);
+
+        let frames = decode_segment_frames_with_retry(
+            &render_segment.decoders,
+            segment_time,
+            !project.camera.hide,
+            render_segment.render_display,
+            offsets,
+            current_frame_number,
+            false,
+            fps,
+        )
+        .await?;
+        sides.push((clip_index, frames));
+    }
+
+    sides.try_into().ok()
+}
+
+/// Renders both sides of a transition and blends them. The pipeline must have been flushed
+/// first; `None` means the frame should be rendered as a hard cut instead.
+#[allow(clippy::too_many_arguments)]
+async fn render_transition_frame(
+    frame_renderer: &mut FrameRenderer<'_>,
+    layers: &mut RendererLayers,
+    project: &ProjectConfiguration,
+    recordings: &ProjectRecordingsMeta,
+    render_segments: &[RenderSegment],
+    zoom_focus_interpolators: &mut [ZoomFocusInterpolator],
+    precomputed_cursor_timelines: &[Arc<PrecomputedCursorTimeline>],
+    transition: &TimelineTransition<'_>,
+    current_frame_number: u32,
+    fps: u32,
+    resolution_base: XY<u32>,
+    duration: f64,
+) -> Option<RenderedFrame> {
+    let sides = decode_transition_frames(
+        project,
+        recordings,
+        render_segments,
+        zoom_focus_interpolators,
+        transition,
+        current_frame_number,
+        fps,
+    )
+    .await?;
+
+    let sides = sides.map(|(clip_index, segment_frames)| {
+        let render_segment = &render_segments[clip_index];
+        let uniforms = ProjectUniforms::new_with_precomputed_cursor(
+            frame_renderer.constants,
+            project,
+            current_frame_number,
+            fps,
+            resolution_base,
+            &render_segment.cursor,
+            &segment_frames,
+            duration,
+            &zoom_focus_interpolators[clip_index],
+            &precomputed_cursor_timelines[clip_index],
+        );
+        TransitionSide {
+            segment_frames,
+            uniforms,
+            cursor: &render_segment.cursor,
+            render_display: render_segment.render_display,
+        }
+    });
+
+    let progress = transition.progress(current_frame_number as f64 / fps as f64);
+    match frame_renderer
+        .render_transition(transition.kind, progress as f32, sides, layers)
+        .await
+    {
+        Ok(frame) => Some(frame),
+        Err(e) => {
+            tracing::warn!(
+                frame_number = current_frame_number,
+                error = %e,
+                "Transition frame rendering failed - falling back to a cut"
+            );
+            None
+        }
+    }
+}
+
 pub fn get_duration(
     recordings: &ProjectRecordingsMeta,
     recording_meta: &RecordingMeta,
@@ -1543,16 +1791,7 @@ impl RenderVideoConstants {
         recording_meta: RecordingMeta,
         meta: StudioRecordingMeta,
     ) -> Result<Self, RenderingError> {
-        let first_segment = segments.first().ok_or(RenderingError::NoSegments)?;
-
-        let options = RenderOptions {
-            screen_size: XY::new(first_segment.display.width, first_segment.display.height),
-            camera_size: first_segment
-                .camera
-                .as_ref()
-                .map(|c| XY::new(c.width, c.height)),
-            preserve_screen_alpha: false,
-        };
+        let options = RenderOptions::from_segments(segments)?;
 
         let background_textures = Arc::new(BackgroundTextureCache::default());
 
@@ -1603,16 +1842,7 @@ impl RenderVideoConstants {
         recording_meta: RecordingMeta,
         meta: StudioRecordingMeta,
     ) -> Result<Self, RenderingError> {
-        let first_segment = segments.first().ok_or(RenderingError::NoSegments)?;
-
-        let options = RenderOptions {
-            screen_size: XY::new(first_segment.display.width, first_segment.display.height),
-            camera_size: first_segment
-                .camera
-                .as_ref()
-                .map(|c| XY::new(c.width, c.height)),
-            preserve_screen_alpha: false,
-        };
+        let options = RenderOptions::from_segments(segments)?;
 
         let instance = create_wgpu_instance().await;
 
@@ -1706,6 +1936,140 @@ impl RenderVideoConstants {
     }
 }
 
+/// The renderer an export runs on: wgpu when an adapter can be created, the
+/// CPU compositor otherwise.
+pub enum RenderBackend {
+    Gpu(RenderVideoConstants),
+    Cpu(CpuRenderConstants),
+}
+
+impl RenderBackend {
+    pub async fn new(
+        segments: &[SegmentRecordings],
+        recording_meta: RecordingMeta,
+        meta: StudioRecordingMeta,
+    ) -> Result<Self, RenderingError> {
+        if force_cpu_compositor() {
+            tracing::warn!("Forcing CPU compositor");
+            return CpuRenderConstants::new(segments, recording_meta, meta).map(Self::Cpu);
+        }
+
+        match RenderVideoConstants::new(segments, recording_meta.clone(), meta.clone()).await {
+            Ok(constants) => Ok(Self::Gpu(constants)),
+            Err(e @ (RenderingError::NoAdapter | RenderingError::RequestDeviceFailed(_))) => {
+                tracing::warn!(error = %e, "No usable wgpu device, falling back to CPU compositor");
+                CpuRenderConstants::new(segments, recording_meta, meta).map(Self::Cpu)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn options(&self) -> &RenderOptions {
+        match self {
+            Self::Gpu(constants) => &constants.options,
+            Self::Cpu(constants) => &constants.options,
+        }
+    }
+
+    pub fn is_cpu(&self) -> bool {
+        matches!(self, Self::Cpu(_))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn render_to_channel(
+        &self,
+        project: &ProjectConfiguration,
+        sender: mpsc::Sender<(RenderedFrame, u32)>,
+        recording_meta: &RecordingMeta,
+        meta: &StudioRecordingMeta,
+        render_segments: Vec<RenderSegment>,
+        fps: u32,
+        resolution_base: XY<u32>,
+        recordings: &ProjectRecordingsMeta,
+    ) -> Result<(), RenderingError> {
+        match self {
+            Self::Gpu(constants) => {
+                render_video_to_channel(
+                    constants,
+                    project,
+                    sender,
+                    recording_meta,
+                    meta,
+                    render_segments,
+                    fps,
+                    resolution_base,
+                    recordings,
+                )
+                .await
+            }
+            Self::Cpu(constants) => {
+                cpu_compositor::render_video_to_channel(
+                    constants,
+                    project,
+                    sender,
+                    recording_meta,
+                    meta,
+                    render_segments,
+                    fps,
+                    resolution_base,
+                    recordings,
+                )
+                .await
+            }
+        }
+    }
+
+    /// The startup breakdown is only recorded by the GPU path.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn render_to_channel_nv12(
+        &self,
+        project: &ProjectConfiguration,
+        sender: mpsc::Sender<(Nv12RenderedFrame, u32)>,
+        recording_meta: &RecordingMeta,
+        meta: &StudioRecordingMeta,
+        render_segments: Vec<RenderSegment>,
+        fps: u32,
+        resolution_base: XY<u32>,
+        recordings: &ProjectRecordingsMeta,
+        stop_after_frames_sent: Option<u32>,
+        startup_breakdown_ms: Option<Arc<Mutex<Option<Nv12RenderStartupBreakdownMs>>>>,
+    ) -> Result<(), RenderingError> {
+        match self {
+            Self::Gpu(constants) => {
+                render_video_to_channel_nv12(
+                    constants,
+                    project,
+                    sender,
+                    recording_meta,
+                    meta,
+                    render_segments,
+                    fps,
+                    resolution_base,
+                    recordings,
+                    stop_after_frames_sent,
+                    startup_breakdown_ms,
+                )
+                .await
+            }
+            Self::Cpu(constants) => {
+                cpu_compositor::render_video_to_channel_nv12(
+                    constants,
+                    project,
+                    sender,
+                    recording_meta,
+                    meta,
+                    render_segments,
+                    fps,
+                    resolution_base,
+                    recordings,
+                    stop_after_frames_sent,
+                )
+                .await
+            }
+        }
+    }
+}
+
 /// One pane of the split-screen layout. `target` is the fully-split destination
 /// rect in output px `[x0,y0,x1,y1]`. The crop is NOT stored as a fixed rect —
 /// during the morph it is re-derived each frame from the *current* (lerped)
@@ -1852,6 +2216,8 @@ pub struct ProjectUniforms {
     pub motion_blur_amount: f32,
     pub masks: Vec<PreparedMask>,
     pub texts: Vec<PreparedText>,
+    pub annotations: Vec<PreparedAnnotation>,
+    pub watermark: Option<PreparedWatermark>,
 }
 
 #[derive(Debug, Clone)]
@@ -2576,6 +2942,33 @@ impl ProjectUniforms {
         segment_frames: &DecodedSegmentFrames,
         total_duration: f64,
         zoom_focus_interpolator: &ZoomFocusInterpolator,
+    ) -> Self {
+        Self::with_options(
+            &constants.options,
+            project,
+            frame_number,
+            fps,
+            resolution_base,
+            cursor_events,
+            segment_frames,
+            total_duration,
+            zoom_focus_interpolator,
+        )
+    }
+
+    /// Same as [`ProjectUniforms::new`], for callers that only hold the render
+    /// options (e.g. the CPU compositor, which has no wgpu device).
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_options(
+        options: &RenderOptions,
+        project: &ProjectConfiguration,
+        frame_number: u32,
+        fps: u32,
+        resolution_base: XY<u32>,
+        cursor_events: &CursorEvents,
+        segment_frames: &DecodedSegmentFrames,
+        total_duration: f64,
+        zoom_focus_interpolator: &ZoomFocusInterpolator,
     ) -> Self {
         let cursor_smoothing = (!project.cursor.raw).then_some(SpringMassDamperSimulationConfig {
             tension: project.cursor.tension,
@@ -2597,7 +2990,7 @@ impl ProjectUniforms {
         };
 
         Self::new_inner(
-            constants,
+            options,
             project,
             frame_number,
             fps,
@@ -2628,7 +3021,7 @@ impl ProjectUniforms {
         };
 
         Self::new_inner(
-            constants,
+            &constants.options,
             project,
             frame_number,
             fps,
@@ -2643,7 +3036,7 @@ impl ProjectUniforms {
 
     #[allow(clippy::too_many_arguments)]
     fn new_inner(
-        constants: &RenderVideoConstants,
+        options: &RenderOptions,
         project: &ProjectConfiguration,
         frame_number: u32,
         fps: u32,
@@ -2654,7 +3047,6 @@ impl ProjectUniforms {
         zoom_focus_interpolator: &ZoomFocusInterpolator,
         cursor_interp_fn: &dyn Fn(f32) -> Option<InterpolatedCursorPosition>,
     ) -> Self {
-        let options = &constants.options;
         let output_size = Self::get_output_size(options, project, resolution_base);
         let fps_f32 = fps as f32;
         let frame_time = frame_number as f32 / fps_f32;
@@ -3363,7 +3755,7 @@ impl ProjectUniforms {
                 }
             });
 
-        let masks = project
+        let mut masks = project
             .timeline
             .as_ref()
             .map(|timeline| {
@@ -3375,7 +3767,7 @@ impl ProjectUniforms {
             })
             .unwrap_or_default();
 
-        let texts = project
+        let mut texts = project
             .timeline
             .as_ref()
             .map(|timeline| {
@@ -3388,6 +3780,26 @@ impl ProjectUniforms {
             })
             .unwrap_or_default();
 
+        let annotations = project
+            .timeline
+            .as_ref()
+            .map(|timeline| {
+                prepare_annotations(
+                    XY::new(output_size.0, output_size.1),
+                    frame_time as f64,
+                    &timeline.annotation_segments,
+                )
+            })
+            .unwrap_or_default();
+        masks.extend(annotations.masks);
+        texts.extend(annotations.texts);
+
+        let watermark = prepare_watermark(
+            XY::new(output_size.0, output_size.1),
+            frame_time as f64,
+            project.watermark.as_ref(),
+        );
+
         Self {
             output_size,
             cursor_size: project.cursor.size as f32,
@@ -3409,6 +3821,8 @@ impl ProjectUniforms {
             motion_blur_amount: cursor_motion_blur,
             masks,
             texts,
+            annotations: annotations.shapes,
+            watermark,
         }
     }
 }
@@ -3634,6 +4048,15 @@ pub struct DecodedSegmentFrames {
     pub segment_has_camera: bool,
 }
 
+/// One clip of a frame that falls inside a transition, ready for
+/// [`FrameRenderer::render_transition`].
+pub struct TransitionSide<'a> {
+    pub segment_frames: DecodedSegmentFrames,
+    pub uniforms: ProjectUniforms,
+    pub cursor: &'a CursorEvents,
+    pub render_display: bool,
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct FrameRenderStageTimings {
     pub prepare_duration: std::time::Duration,
@@ -3770,6 +4193,38 @@ impl<'a> FrameRenderer<'a> {
         Err(last_error.unwrap_or(RenderingError::BufferMapWaitingFailed))
     }
 
+    /// Renders the outgoing then the incoming clip of a transition and blends them at
+    /// `progress`. Any pipelined frame must have been flushed first.
+    pub async fn render_transition(
+        &mut self,
+        kind: TransitionKind,
+        progress: f32,
+        sides: [TransitionSide<'_>; 2],
+        layers: &mut RendererLayers,
+    ) -> Result<RenderedFrame, RenderingError> {
+        let [outgoing, incoming] = sides;
+        let outgoing = self
+            .render_immediate(
+                outgoing.segment_frames,
+                outgoing.uniforms,
+                outgoing.cursor,
+                outgoing.render_display,
+                layers,
+            )
+            .await?;
+        let incoming = self
+            .render_immediate(
+                incoming.segment_frames,
+                incoming.uniforms,
+                incoming.cursor,
+                incoming.render_display,
+                layers,
+            )
+            .await?;
+
+        Ok(blend_transition(kind, progress, &outgoing, &incoming))
+    }
+
     pub async fn render_immediate(
         &mut self,
         segment_frames: DecodedSegmentFrames,
@@ -3887,78 +4342,10 @@ impl<'a> FrameRenderer<'a> {
             return Ok(None);
         };
 
-        let width = rgba_frame.width;
-        let height = rgba_frame.height;
-        let padded_bytes_per_row = rgba_frame.padded_bytes_per_row;
-        let frame_number = rgba_frame.frame_number;
-        let target_time_ns = rgba_frame.target_time_ns;
-
-        let nv12_size = (width as usize) * (height as usize) * 3 / 2;
-        let mut nv12_buf = self.nv12_buffer_pool.acquire(nv12_size);
-
-        let y_stride = width as usize;
-        let uv_stride = width as usize;
-        let y_plane_size = y_stride * height as usize;
-        let uv_plane_size = uv_stride * (height as usize / 2);
-        nv12_buf.resize(y_plane_size + uv_plane_size, 0);
-
-        let src_data = &rgba_frame.data;
-        let src_stride = padded_bytes_per_row as usize;
-
-        for row in 0..height as usize {
-            let src_row = &src_data[row * src_stride..row * src_stride + width as usize * 4];
-            let y_row = &mut nv12_buf[row * y_stride..(row + 1) * y_stride];
-            for col in 0..width as usize {
-                let r = src_row[col * 4] as i32;
-                let g = src_row[col * 4 + 1] as i32;
-                let b = src_row[col * 4 + 2] as i32;
-                y_row[col] = ((16 + ((65 * r + 129 * g + 25 * b + 128) >> 8)) as u8).clamp(16, 235);
-            }
-        }
-
-        let uv_offset = y_plane_size;
-        for row in 0..(height as usize / 2) {
-            let src_row0 =
-                &src_data[row * 2 * src_stride..row * 2 * src_stride + width as usize * 4];
-            let src_row1 = &src_data
-                [(row * 2 + 1) * src_stride..(row * 2 + 1) * src_stride + width as usize * 4];
-            let uv_row =
-                &mut nv12_buf[uv_offset + row * uv_stride..uv_offset + (row + 1) * uv_stride];
-            for col in 0..(width as usize / 2) {
-                let r = (src_row0[col * 8] as i32
-                    + src_row0[col * 8 + 4] as i32
-                    + src_row1[col * 8] as i32
-                    + src_row1[col * 8 + 4] as i32
-                    + 2)
-                    / 4;
-                let g = (src_row0[col * 8 + 1] as i32
-                    + src_row0[col * 8 + 5] as i32
-                    + src_row1[col * 8 + 1] as i32
-                    + src_row1[col * 8 + 5] as i32
-                    + 2)
-                    / 4;
-                let b = (src_row0[col * 8 + 2] as i32
-                    + src_row0[col * 8 + 6] as i32
-                    + src_row1[col * 8 + 2] as i32
-                    + src_row1[col * 8 + 6] as i32
-                    + 2)
-                    / 4;
-                uv_row[col * 2] =
-                    ((128 + ((-38 * r - 74 * g + 112 * b + 128) >> 8)) as u8).clamp(16, 240);
-                uv_row[col * 2 + 1] =
-                    ((128 + ((112 * r - 94 * g - 18 * b + 128) >> 8)) as u8).clamp(16, 240);
-            }
-        }
-
-        Ok(Some(frame_pipeline::Nv12RenderedFrame {
-            data: self.nv12_buffer_pool.wrap(nv12_buf),
-            width,
-            height,
-            y_stride: width,
-            frame_number,
-            target_time_ns,
-            format: frame_pipeline::GpuOutputFormat::Nv12,
-        }))
+        Ok(Some(frame_pipeline::Nv12RenderedFrame::from_rgba(
+            &rgba_frame,
+            &self.nv12_buffer_pool,
+        )))
     }
 
     async fn render_nv12_gpu_path(
@@ -4066,9 +4453,11 @@ pub struct RendererLayers {
     camera: CameraLayer,
     camera_only: CameraLayer,
     mask: MaskLayer,
+    annotation: AnnotationLayer,
     text: TextLayer,
     captions: CaptionsLayer,
     keyboard: KeyboardLayer,
+    watermark: WatermarkLayer,
     camera_blur_processor: Option<cap_camera_effects::BlurProcessor>,
     camera_blur_init_failed: bool,
 }
@@ -4108,9 +4497,11 @@ impl RendererLayers {
                 shared_composite_pipeline,
             ),
             mask: MaskLayer::new(device),
+            annotation: AnnotationLayer::new(device),
             text: TextLayer::new(device, queue),
             captions: CaptionsLayer::new(device, queue),
             keyboard: KeyboardLayer::new(device, queue),
+            watermark: WatermarkLayer::new(device),
             camera_blur_processor: None,
             camera_blur_init_failed: false,
         }
@@ -4315,9 +4706,17 @@ impl RendererLayers {
             self.run_shared_camera_blur(&constants.device, &constants.queue, mode);
         }
 
+        self.annotation.prepare(
+            &constants.device,
+            &constants.queue,
+            XY::new(uniforms.output_size.0, uniforms.output_size.1),
+            &uniforms.annotations,
+        );
+
         self.text.prepare(
             &constants.device,
             &constants.queue,
+            &constants.recording_meta.project_path,
             uniforms.output_size,
             &uniforms.texts,
         );
@@ -4337,6 +4736,10 @@ impl RendererLayers {
             self.captions.active_layout(),
         );
 
+        self.watermark
+            .prepare(constants, uniforms.watermark.as_ref())
+            .await;
+
         Ok(())
     }
 
@@ -4473,10 +4876,18 @@ impl RendererLayers {
         }
         timings.camera_blur_prepare_duration = start.elapsed();
 
+        self.annotation.prepare(
+            &constants.device,
+            &constants.queue,
+            XY::new(uniforms.output_size.0, uniforms.output_size.1),
+            &uniforms.annotations,
+        );
+
         let start = Instant::now();
         self.text.prepare(
             &constants.device,
             &constants.queue,
+            &constants.recording_meta.project_path,
             uniforms.output_size,
             &uniforms.texts,
         );
@@ -4501,6 +4912,10 @@ impl RendererLayers {
         );
         timings.keyboard_prepare_duration = start.elapsed();
 
+        self.watermark
+            .prepare(constants, uniforms.watermark.as_ref())
+            .await;
+
         Ok(timings)
     }
 
@@ -4591,9 +5006,13 @@ impl RendererLayers {
             }
         }
 
-        if !uniforms.texts.is_empty() {
+        if self.annotation.has_content() {
             let mut pass = render_pass!(session.current_texture_view(), wgpu::LoadOp::Load);
-            self.text.render(&mut pass);
+            self.annotation.render(&mut pass);
+        }
+
+        if !uniforms.texts.is_empty() {
+            self.text.render(encoder, session.current_texture_view());
         }
 
         if self.keyboard.has_content() {
@@ -4605,6 +5024,11 @@ impl RendererLayers {
             let mut pass = render_pass!(session.current_texture_view(), wgpu::LoadOp::Load);
             self.captions.render(&mut pass);
         }
+
+        if self.watermark.has_content() {
+            let mut pass = render_pass!(session.current_texture_view(), wgpu::LoadOp::Load);
+            self.watermark.render(&mut pass);
+        }
     }
 }
 
@@ -78,6 +78,11 @@ impl Video {
     pub fn fps(&self) -> u32 {
         self.fps
     }
+
+    /// Source time of the last frame, the latest time a decoder can be asked for.
+    pub fn last_frame_time(&self) -> f64 {
+        (self.duration - 1.0 / self.fps.max(1) as f64).max(0.0)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Type)]
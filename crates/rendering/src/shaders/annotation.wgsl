@@ -0,0 +1,137 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+struct Uniforms {
+    output_size: vec2<f32>,
+    // 0: rectangle, 1: ellipse, 2: arrow.
+    kind: u32,
+    stroke_width: f32,
+    // Pixel rectangle the quad covers: left, top, right, bottom.
+    bounds: vec4<f32>,
+    // Rectangle and ellipse: centre and half size. Arrow: tail and tip.
+    geometry: vec4<f32>,
+    // Rotation (radians, clockwise), arrow head length, arrow head width, opacity.
+    params: vec4<f32>,
+    stroke_color: vec4<f32>,
+    fill_color: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0)
+    );
+
+    let screen_pos = mix(uniforms.bounds.xy, uniforms.bounds.zw, corners[vertex_index]);
+    let clip_pos = (screen_pos / uniforms.output_size) * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(clip_pos.x, -clip_pos.y, 0.0, 1.0);
+    return out;
+}
+
+fn coverage(distance: f32) -> f32 {
+    return clamp(0.5 - distance, 0.0, 1.0);
+}
+
+fn unrotate(p: vec2<f32>, center: vec2<f32>, rotation: f32) -> vec2<f32> {
+    let s = sin(rotation);
+    let c = cos(rotation);
+    let d = p - center;
+    return vec2<f32>(d.x * c + d.y * s, -d.x * s + d.y * c);
+}
+
+fn box_distance(p: vec2<f32>, half_size: vec2<f32>) -> f32 {
+    let q = abs(p) - half_size;
+    return length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0);
+}
+
+// Approximate signed distance to an axis-aligned ellipse, exact on its axes.
+fn ellipse_distance(p: vec2<f32>, radii_in: vec2<f32>) -> f32 {
+    let radii = max(radii_in, vec2<f32>(1e-3));
+    let k0 = length(p / radii);
+    let k1 = length(p / (radii * radii));
+    if k1 <= 1e-6 {
+        return -min(radii.x, radii.y);
+    }
+    return k0 * (k0 - 1.0) / k1;
+}
+
+fn segment_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let pa = p - a;
+    let ba = b - a;
+    let len2 = dot(ba, ba);
+    var h = 0.0;
+    if len2 > 0.0 {
+        h = clamp(dot(pa, ba) / len2, 0.0, 1.0);
+    }
+    return length(pa - ba * h);
+}
+
+fn edge_side(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    return (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x);
+}
+
+fn triangle_distance(p: vec2<f32>, a: vec2<f32>, b: vec2<f32>, c: vec2<f32>) -> f32 {
+    let distance = min(min(segment_distance(p, a, b), segment_distance(p, b, c)), segment_distance(p, c, a));
+    let c0 = edge_side(p, a, b);
+    let c1 = edge_side(p, b, c);
+    let c2 = edge_side(p, c, a);
+    let inside = (c0 >= 0.0 && c1 >= 0.0 && c2 >= 0.0) || (c0 <= 0.0 && c1 <= 0.0 && c2 <= 0.0);
+    return select(distance, -distance, inside);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.position.xy;
+    let half_stroke = uniforms.stroke_width / 2.0;
+    var fill = 0.0;
+    var stroke = 0.0;
+
+    if uniforms.kind == 2u {
+        let tail = uniforms.geometry.xy;
+        let tip = uniforms.geometry.zw;
+        let arrow_length = length(tip - tail);
+        if arrow_length < 1e-3 {
+            return vec4<f32>(0.0);
+        }
+
+        let direction = (tip - tail) / arrow_length;
+        let head_length = min(uniforms.params.y, arrow_length);
+        let base = tip - direction * head_length;
+        let normal = vec2<f32>(-direction.y, direction.x) * uniforms.params.z / 2.0;
+        let head = triangle_distance(p, tip, base + normal, base - normal);
+        let shaft = segment_distance(p, tail, base) - half_stroke;
+        stroke = coverage(min(shaft, head));
+    } else {
+        let local = unrotate(p, uniforms.geometry.xy, uniforms.params.x);
+        var d: f32;
+        if uniforms.kind == 1u {
+            d = ellipse_distance(local, uniforms.geometry.zw);
+        } else {
+            d = box_distance(local, uniforms.geometry.zw);
+        }
+        fill = coverage(d);
+        stroke = coverage(abs(d) - half_stroke);
+    }
+
+    // Stroke over fill, in straight alpha; the pipeline blends with ALPHA_BLENDING.
+    let fill_alpha = uniforms.fill_color.a * fill;
+    let stroke_alpha = uniforms.stroke_color.a * stroke;
+    let alpha = stroke_alpha + fill_alpha * (1.0 - stroke_alpha);
+    if alpha <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+
+    let rgb = (uniforms.stroke_color.rgb * stroke_alpha
+        + uniforms.fill_color.rgb * fill_alpha * (1.0 - stroke_alpha)) / alpha;
+    return vec4<f32>(rgb, alpha * uniforms.params.w);
+}
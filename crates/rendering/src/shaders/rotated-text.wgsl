@@ -0,0 +1,46 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+struct Uniforms {
+    output_size: vec2<f32>,
+    pivot: vec2<f32>,
+    // sin and cos of the clockwise rotation.
+    rotation: vec2<f32>,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0) var text_texture: texture_2d<f32>;
+@group(0) @binding(1) var text_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: Uniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // A single triangle that covers the whole target.
+    var positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(3.0, -1.0),
+        vec2<f32>(-1.0, 3.0)
+    );
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(positions[vertex_index], 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Undo the rotation to find where this pixel sits in the unrotated text.
+    let offset = in.position.xy - uniforms.pivot;
+    let s = uniforms.rotation.x;
+    let c = uniforms.rotation.y;
+    let source = uniforms.pivot + vec2<f32>(offset.x * c + offset.y * s, -offset.x * s + offset.y * c);
+    let uv = source / uniforms.output_size;
+
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return vec4<f32>(0.0);
+    }
+
+    // The offscreen text was blended over transparent black, so it is already premultiplied.
+    return textureSampleLevel(text_texture, text_sampler, uv, 0.0);
+}
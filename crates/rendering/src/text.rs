@@ -1,7 +1,9 @@
-use cap_project::{TextSegment, XY};
+use cap_project::{TextAlign, TextScalarKeyframe, TextSegment, TextVectorKeyframe, XY};
 
 const BASE_TEXT_HEIGHT: f64 = 0.2;
 const MAX_FONT_SIZE_PX: f32 = 256.0;
+/// Caps outline widths, shadow offsets and background padding, in output pixels.
+const MAX_EFFECT_PX: f32 = 128.0;
 
 #[derive(Debug, Clone)]
 pub struct PreparedText {
@@ -13,6 +15,55 @@ pub struct PreparedText {
     pub font_weight: f32,
     pub italic: bool,
     pub opacity: f32,
+    /// Clockwise, in radians, around the centre of `bounds`.
+    pub rotation: f32,
+    /// Font file inside the project bundle, relative to its root.
+    pub font_path: Option<String>,
+    pub align: TextAlign,
+    /// In ems.
+    pub letter_spacing: f32,
+    /// As a multiple of `font_size`.
+    pub line_height: f32,
+    pub outline: Option<PreparedTextOutline>,
+    pub shadow: Option<PreparedTextShadow>,
+    pub background: Option<PreparedTextBackground>,
+}
+
+/// Text styles with their pixel sizes scaled to the output. Colors are straight RGBA and
+/// don't include the text's `opacity` yet.
+#[derive(Debug, Clone, Copy)]
+pub struct PreparedTextOutline {
+    pub color: [f32; 4],
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PreparedTextShadow {
+    pub color: [f32; 4],
+    pub offset: [f32; 2],
+    pub blur: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PreparedTextBackground {
+    pub color: [f32; 4],
+    pub padding: [f32; 2],
+    pub radius: f32,
+}
+
+impl PreparedText {
+    /// How far outlines, shadows and the background box can reach past `bounds`.
+    pub fn overhang(&self) -> f32 {
+        let outline = self.outline.map_or(0.0, |outline| outline.width);
+        let shadow = self.shadow.map_or(0.0, |shadow| {
+            shadow.offset[0].abs().max(shadow.offset[1].abs()) + shadow.blur + outline
+        });
+        let background = self.background.map_or(0.0, |background| {
+            background.padding[0].max(background.padding[1])
+        });
+
+        outline.max(shadow).max(background).ceil()
+    }
 }
 
 fn parse_color(hex: &str) -> [f32; 4] {
@@ -30,6 +81,72 @@ fn parse_color(hex: &str) -> [f32; 4] {
     [1.0, 1.0, 1.0, 1.0]
 }
 
+fn parse_color_with_opacity(hex: &str, opacity: f64) -> [f32; 4] {
+    let [r, g, b, _] = parse_color(hex);
+    [r, g, b, opacity.clamp(0.0, 1.0) as f32]
+}
+
+fn keyframe_progress(prev_time: f64, next_time: f64, time: f64) -> f64 {
+    let span = (next_time - prev_time).max(1e-6);
+    ((time - prev_time) / span).clamp(0.0, 1.0)
+}
+
+fn interpolate_vector(base: XY<f64>, keys: &[TextVectorKeyframe], time: f64) -> XY<f64> {
+    if keys.is_empty() {
+        return base;
+    }
+
+    let mut sorted = keys.to_vec();
+    sorted.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    if time <= sorted[0].time {
+        return XY::new(sorted[0].x, sorted[0].y);
+    }
+
+    for window in sorted.windows(2) {
+        let prev = &window[0];
+        let next = &window[1];
+        if time <= next.time {
+            let t = prev
+                .easing
+                .apply(keyframe_progress(prev.time, next.time, time));
+            return XY::new(
+                prev.x + (next.x - prev.x) * t,
+                prev.y + (next.y - prev.y) * t,
+            );
+        }
+    }
+
+    let last = sorted.last().unwrap();
+    XY::new(last.x, last.y)
+}
+
+fn interpolate_scalar(base: f64, keys: &[TextScalarKeyframe], time: f64) -> f64 {
+    if keys.is_empty() {
+        return base;
+    }
+
+    let mut sorted = keys.to_vec();
+    sorted.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    if time <= sorted[0].time {
+        return sorted[0].value;
+    }
+
+    for window in sorted.windows(2) {
+        let prev = &window[0];
+        let next = &window[1];
+        if time <= next.time {
+            let t = prev
+                .easing
+                .apply(keyframe_progress(prev.time, next.time, time));
+            return prev.value + (next.value - prev.value) * t;
+        }
+    }
+
+    sorted.last().map(|k| k.value).unwrap_or(base)
+}
+
 pub fn prepare_texts(
     output_size: XY<u32>,
     frame_time: f64,
@@ -52,18 +169,20 @@ pub fn prepare_texts(
             continue;
         }
 
-        let center = XY::new(
-            segment.center.x.clamp(0.0, 1.0),
-            segment.center.y.clamp(0.0, 1.0),
-        );
-        let size = XY::new(
-            segment.size.x.clamp(0.01, 2.0),
-            segment.size.y.clamp(0.01, 2.0),
-        );
+        let keyframes = &segment.keyframes;
+        let relative_time = (frame_time - segment.start).max(0.0);
+        let center = interpolate_vector(segment.center, &keyframes.position, relative_time);
+        let size = interpolate_vector(segment.size, &keyframes.size, relative_time);
+        let scale = interpolate_scalar(1.0, &keyframes.scale, relative_time).clamp(0.05, 10.0);
+        let rotation = interpolate_scalar(0.0, &keyframes.rotation, relative_time).to_radians();
+        let keyframe_opacity = interpolate_scalar(1.0, &keyframes.opacity, relative_time);
+
+        let center = XY::new(center.x.clamp(0.0, 1.0), center.y.clamp(0.0, 1.0));
+        let size = XY::new(size.x.clamp(0.01, 2.0), size.y.clamp(0.01, 2.0));
         let size_scale = (size.y / BASE_TEXT_HEIGHT).clamp(0.25, 4.0) as f32;
 
-        let width = (size.x * output_size.x as f64).max(1.0) as f32;
-        let height = (size.y * output_size.y as f64).max(1.0) as f32;
+        let width = (size.x * scale * output_size.x as f64).max(1.0) as f32;
+        let height = (size.y * scale * output_size.y as f64).max(1.0) as f32;
         let half_w = width / 2.0;
         let half_h = height / 2.0;
 
@@ -73,30 +192,183 @@ pub fn prepare_texts(
         let bottom = (top + height).min(output_size.y as f32);
 
         let fade_duration = segment.fade_duration.max(0.0);
-        let opacity = if fade_duration > 0.0 {
+        let fade = if fade_duration > 0.0 {
             let time_since_start = (frame_time - segment.start).max(0.0);
             let time_until_end = (segment.end - frame_time).max(0.0);
 
             let fade_in = (time_since_start / fade_duration).min(1.0);
             let fade_out = (time_until_end / fade_duration).min(1.0);
 
-            (fade_in * fade_out) as f32
+            fade_in * fade_out
         } else {
             1.0
         };
+        let opacity = fade * keyframe_opacity.clamp(0.0, 1.0);
+        let pixel_scale = height_scale * scale as f32;
 
         prepared.push(PreparedText {
             content: segment.content.clone(),
             bounds: [left, top, right, bottom],
             color: parse_color(&segment.color),
             font_family: segment.font_family.clone(),
-            font_size: ((segment.font_size * size_scale).max(1.0) * height_scale)
+            font_size: ((segment.font_size * size_scale * scale as f32).max(1.0) * height_scale)
                 .min(MAX_FONT_SIZE_PX),
             font_weight: segment.font_weight,
             italic: segment.italic,
-            opacity,
+            opacity: opacity as f32,
+            rotation: rotation as f32,
+            font_path: segment.font_path.clone(),
+            align: segment.align,
+            letter_spacing: segment.letter_spacing.clamp(-0.5, 2.0),
+            line_height: segment.line_height.clamp(0.5, 4.0),
+            outline: segment
+                .outline
+                .as_ref()
+                .filter(|outline| outline.width > 0.0)
+                .map(|outline| PreparedTextOutline {
+                    color: parse_color(&outline.color),
+                    width: (outline.width as f32 * pixel_scale).min(MAX_EFFECT_PX),
+                }),
+            shadow: segment.shadow.as_ref().map(|shadow| PreparedTextShadow {
+                color: parse_color_with_opacity(&shadow.color, shadow.opacity),
+                offset: [
+                    (shadow.offset.x as f32 * pixel_scale).clamp(-MAX_EFFECT_PX, MAX_EFFECT_PX),
+                    (shadow.offset.y as f32 * pixel_scale).clamp(-MAX_EFFECT_PX, MAX_EFFECT_PX),
+                ],
+                blur: (shadow.blur.max(0.0) as f32 * pixel_scale).min(MAX_EFFECT_PX),
+            }),
+            background: segment
+                .background
+                .as_ref()
+                .map(|background| PreparedTextBackground {
+                    color: parse_color_with_opacity(&background.color, background.opacity),
+                    padding: [
+                        (background.padding.x.max(0.0) as f32 * pixel_scale).min(MAX_EFFECT_PX),
+                        (background.padding.y.max(0.0) as f32 * pixel_scale).min(MAX_EFFECT_PX),
+                    ],
+                    radius: background.corner_radius.max(0.0) as f32 * pixel_scale,
+                }),
         });
     }
 
     prepared
 }
+
+#[cfg(test)]
+mod tests {
+    use cap_project::KeyframeEasing;
+
+    use super::*;
+
+    fn segment(keyframes: cap_project::TextKeyframes) -> TextSegment {
+        let mut segment: TextSegment =
+            serde_json::from_value(serde_json::json!({ "start": 0.0, "end": 4.0 })).unwrap();
+        segment.fade_duration = 0.0;
+        segment.keyframes = keyframes;
+        segment
+    }
+
+    fn scalar(time: f64, value: f64, easing: KeyframeEasing) -> TextScalarKeyframe {
+        TextScalarKeyframe {
+            time,
+            value,
+            easing,
+        }
+    }
+
+    fn prepare_at(segment: &TextSegment, time: f64) -> PreparedText {
+        prepare_texts(
+            XY::new(1920, 1080),
+            time,
+            std::slice::from_ref(segment),
+            &[],
+        )
+        .pop()
+        .unwrap()
+    }
+
+    #[test]
+    fn position_keyframes_move_the_text() {
+        let segment = segment(cap_project::TextKeyframes {
+            position: vec![
+                TextVectorKeyframe {
+                    time: 0.0,
+                    x: 0.25,
+                    y: 0.5,
+                    easing: KeyframeEasing::Linear,
+                },
+                TextVectorKeyframe {
+                    time: 2.0,
+                    x: 0.75,
+                    y: 0.5,
+                    easing: KeyframeEasing::Linear,
+                },
+            ],
+            ..Default::default()
+        });
+
+        let text = prepare_at(&segment, 1.0);
+        let center_x = (text.bounds[0] + text.bounds[2]) / 2.0;
+        assert!((center_x - 960.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn easing_shapes_the_interpolation() {
+        let keys = [
+            scalar(0.0, 0.0, KeyframeEasing::EaseIn),
+            scalar(2.0, 100.0, KeyframeEasing::Linear),
+        ];
+        assert!(interpolate_scalar(0.0, &keys, 1.0) < 50.0);
+
+        let held = [
+            scalar(0.0, 10.0, KeyframeEasing::Hold),
+            scalar(2.0, 100.0, KeyframeEasing::Linear),
+        ];
+        assert_eq!(interpolate_scalar(0.0, &held, 1.9), 10.0);
+        assert_eq!(interpolate_scalar(0.0, &held, 2.0), 100.0);
+    }
+
+    #[test]
+    fn rotation_scale_and_opacity_keyframes_are_applied() {
+        let animated = segment(cap_project::TextKeyframes {
+            rotation: vec![scalar(0.0, 90.0, KeyframeEasing::Linear)],
+            scale: vec![scalar(0.0, 2.0, KeyframeEasing::Linear)],
+            opacity: vec![scalar(0.0, 0.5, KeyframeEasing::Linear)],
+            ..Default::default()
+        });
+        let plain = prepare_at(&segment(Default::default()), 1.0);
+        let text = prepare_at(&animated, 1.0);
+
+        assert!((text.rotation - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+        assert!((text.opacity - 0.5).abs() < 1e-5);
+        assert!((text.font_size - plain.font_size * 2.0).abs() < 1e-3);
+        assert!(
+            (text.bounds[3] - text.bounds[1] - (plain.bounds[3] - plain.bounds[1]) * 2.0).abs()
+                < 1.0
+        );
+    }
+
+    #[test]
+    fn text_styles_scale_with_the_output() {
+        let mut styled = segment(Default::default());
+        styled.outline = Some(cap_project::TextOutline {
+            width: 2.0,
+            ..Default::default()
+        });
+        styled.background = Some(cap_project::TextBackground {
+            opacity: 0.5,
+            ..Default::default()
+        });
+
+        let text = prepare_texts(XY::new(3840, 2160), 1.0, std::slice::from_ref(&styled), &[])
+            .pop()
+            .unwrap();
+
+        assert_eq!(text.outline.unwrap().width, 4.0);
+        let background = text.background.unwrap();
+        assert_eq!(background.padding, [48.0, 24.0]);
+        assert_eq!(background.color, [0.0, 0.0, 0.0, 0.5]);
+        assert_eq!(text.overhang(), 48.0);
+        assert!(text.shadow.is_none());
+    }
+}
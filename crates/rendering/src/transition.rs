@@ -0,0 +1,221 @@
+use std::sync::Arc;
+
+use cap_project::{SlideDirection, TransitionKind};
+use rayon::prelude::*;
+
+use crate::RenderedFrame;
+
+/// How far the outgoing clip is magnified (and the incoming clip starts magnified) in a
+/// zoom-through.
+const ZOOM_THROUGH_SCALE: f32 = 1.5;
+
+/// Blends the composited frames either side of a timeline transition. `progress` runs from 0
+/// (all `outgoing`) to 1 (all `incoming`); both frames must share the same size.
+pub fn blend_transition(
+    kind: TransitionKind,
+    progress: f32,
+    outgoing: &RenderedFrame,
+    incoming: &RenderedFrame,
+) -> RenderedFrame {
+    let width = outgoing.width;
+    let height = outgoing.height;
+    let stride = outgoing.padded_bytes_per_row as usize;
+    let progress = progress.clamp(0.0, 1.0);
+    let eased = ease_in_out(progress);
+
+    let mut data = vec![0u8; outgoing.data.len()];
+    data.par_chunks_mut(stride)
+        .take(height as usize)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for x in 0..width {
+                let pixel = match kind {
+                    TransitionKind::Crossfade => mix(
+                        texel(outgoing, x, y as u32),
+                        texel(incoming, x, y as u32),
+                        progress,
+                    ),
+                    TransitionKind::DipToColor { color } => {
+                        let color = [
+                            color[0].min(255) as f32,
+                            color[1].min(255) as f32,
+                            color[2].min(255) as f32,
+                            255.0,
+                        ];
+                        if progress < 0.5 {
+                            mix(texel(outgoing, x, y as u32), color, progress * 2.0)
+                        } else {
+                            mix(color, texel(incoming, x, y as u32), progress * 2.0 - 1.0)
+                        }
+                    }
+                    TransitionKind::Slide { direction } => {
+                        slide(outgoing, incoming, direction, eased, x, y as u32)
+                    }
+                    TransitionKind::ZoomThrough => {
+                        let u = (x as f32 + 0.5) / width as f32;
+                        let v = (y as f32 + 0.5) / height as f32;
+                        mix(
+                            sample_zoomed(outgoing, u, v, 1.0 + (ZOOM_THROUGH_SCALE - 1.0) * eased),
+                            sample_zoomed(
+                                incoming,
+                                u,
+                                v,
+                                ZOOM_THROUGH_SCALE - (ZOOM_THROUGH_SCALE - 1.0) * eased,
+                            ),
+                            eased,
+                        )
+                    }
+                };
+
+                let index = x as usize * 4;
+                for (channel, value) in row[index..index + 4].iter_mut().zip(pixel) {
+                    *channel = value.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        });
+
+    RenderedFrame {
+        data: Arc::new(data),
+        width,
+        height,
+        padded_bytes_per_row: outgoing.padded_bytes_per_row,
+        frame_number: outgoing.frame_number,
+        target_time_ns: outgoing.target_time_ns,
+    }
+}
+
+fn ease_in_out(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn mix(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+fn texel(frame: &RenderedFrame, x: u32, y: u32) -> [f32; 4] {
+    let x = x.min(frame.width - 1) as usize;
+    let y = y.min(frame.height - 1) as usize;
+    let index = y * frame.padded_bytes_per_row as usize + x * 4;
+    std::array::from_fn(|i| frame.data[index + i] as f32)
+}
+
+/// The incoming frame pushes the outgoing one off screen in `direction`.
+fn slide(
+    outgoing: &RenderedFrame,
+    incoming: &RenderedFrame,
+    direction: SlideDirection,
+    eased: f32,
+    x: u32,
+    y: u32,
+) -> [f32; 4] {
+    let (width, height) = (outgoing.width as i64, outgoing.height as i64);
+    let (dx, dy) = match direction {
+        SlideDirection::Left => ((width as f32 * eased) as i64, 0),
+        SlideDirection::Right => (-(width as f32 * eased) as i64, 0),
+        SlideDirection::Up => (0, (height as f32 * eased) as i64),
+        SlideDirection::Down => (0, -(height as f32 * eased) as i64),
+    };
+
+    // Where this output pixel lands in the outgoing frame; off the frame means the incoming
+    // frame, offset by one full frame the other way, covers it.
+    let (ox, oy) = (x as i64 + dx, y as i64 + dy);
+    if (0..width).contains(&ox) && (0..height).contains(&oy) {
+        return texel(outgoing, ox as u32, oy as u32);
+    }
+
+    let ix = ox - dx.signum() * width;
+    let iy = oy - dy.signum() * height;
+    texel(
+        incoming,
+        ix.clamp(0, width - 1) as u32,
+        iy.clamp(0, height - 1) as u32,
+    )
+}
+
+/// Bilinear sample of `frame` magnified by `scale` around its centre.
+fn sample_zoomed(frame: &RenderedFrame, u: f32, v: f32, scale: f32) -> [f32; 4] {
+    let x = ((u - 0.5) / scale + 0.5) * frame.width as f32 - 0.5;
+    let y = ((v - 0.5) / scale + 0.5) * frame.height as f32 - 0.5;
+    let (x0, y0) = (x.floor().max(0.0), y.floor().max(0.0));
+    let (fx, fy) = ((x - x0).clamp(0.0, 1.0), (y - y0).clamp(0.0, 1.0));
+    let (x0, y0) = (x0 as u32, y0 as u32);
+
+    let top = mix(texel(frame, x0, y0), texel(frame, x0 + 1, y0), fx);
+    let bottom = mix(texel(frame, x0, y0 + 1), texel(frame, x0 + 1, y0 + 1), fx);
+    mix(top, bottom, fy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, color: [u8; 4]) -> RenderedFrame {
+        RenderedFrame {
+            data: Arc::new(color.repeat((width * height) as usize)),
+            width,
+            height,
+            padded_bytes_per_row: width * 4,
+            frame_number: 0,
+            target_time_ns: 0,
+        }
+    }
+
+    fn pixel(frame: &RenderedFrame, x: u32, y: u32) -> [u8; 4] {
+        let index = (y * frame.padded_bytes_per_row + x * 4) as usize;
+        frame.data[index..index + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn crossfade_mixes_linearly() {
+        let outgoing = solid(4, 4, [0, 0, 0, 255]);
+        let incoming = solid(4, 4, [200, 100, 40, 255]);
+
+        let frame = blend_transition(TransitionKind::Crossfade, 0.25, &outgoing, &incoming);
+
+        assert_eq!(pixel(&frame, 1, 1), [50, 25, 10, 255]);
+    }
+
+    #[test]
+    fn dip_to_color_is_solid_at_the_cut() {
+        let outgoing = solid(4, 4, [0, 0, 0, 255]);
+        let incoming = solid(4, 4, [255, 255, 255, 255]);
+        let kind = TransitionKind::DipToColor { color: [255, 0, 0] };
+
+        assert_eq!(
+            pixel(&blend_transition(kind, 0.5, &outgoing, &incoming), 2, 2),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(
+            pixel(&blend_transition(kind, 0.0, &outgoing, &incoming), 2, 2),
+            [0, 0, 0, 255]
+        );
+        assert_eq!(
+            pixel(&blend_transition(kind, 1.0, &outgoing, &incoming), 2, 2),
+            [255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn slide_left_brings_the_incoming_frame_in_from_the_right() {
+        let outgoing = solid(8, 2, [10, 10, 10, 255]);
+        let incoming = solid(8, 2, [200, 200, 200, 255]);
+        let kind = TransitionKind::Slide {
+            direction: SlideDirection::Left,
+        };
+
+        let frame = blend_transition(kind, 0.5, &outgoing, &incoming);
+
+        assert_eq!(pixel(&frame, 0, 0), [10, 10, 10, 255]);
+        assert_eq!(pixel(&frame, 7, 0), [200, 200, 200, 255]);
+    }
+
+    #[test]
+    fn zoom_through_ends_on_the_incoming_frame() {
+        let outgoing = solid(4, 4, [10, 20, 30, 255]);
+        let incoming = solid(4, 4, [90, 80, 70, 255]);
+
+        let frame = blend_transition(TransitionKind::ZoomThrough, 1.0, &outgoing, &incoming);
+
+        assert_eq!(pixel(&frame, 0, 3), [90, 80, 70, 255]);
+    }
+}
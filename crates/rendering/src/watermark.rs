@@ -0,0 +1,122 @@
+use cap_project::{WatermarkAnchor, WatermarkConfiguration, XY};
+
+#[derive(Debug, Clone)]
+pub struct PreparedWatermark {
+    pub image_path: String,
+    pub anchor: WatermarkAnchor,
+    pub margin: f32,
+    pub scale: f32,
+    pub opacity: f32,
+    pub output_size: XY<u32>,
+}
+
+pub fn prepare_watermark(
+    output_size: XY<u32>,
+    frame_time: f64,
+    config: Option<&WatermarkConfiguration>,
+) -> Option<PreparedWatermark> {
+    let config = config.filter(|c| c.is_visible_at(frame_time))?;
+
+    Some(PreparedWatermark {
+        image_path: config.image_path.clone(),
+        anchor: config.anchor,
+        margin: config.margin.clamp(0.0, 0.5),
+        scale: config.scale.clamp(0.0, 1.0),
+        opacity: config.opacity.clamp(0.0, 1.0),
+        output_size,
+    })
+}
+
+impl PreparedWatermark {
+    /// Top-left position and size of the logo in output pixels, for an image of `image_size`.
+    pub fn rect(&self, image_size: XY<u32>) -> (XY<f32>, XY<f32>) {
+        let output = XY::new(self.output_size.x as f32, self.output_size.y as f32);
+
+        let width = output.x * self.scale;
+        let height = if image_size.x == 0 {
+            0.0
+        } else {
+            width * image_size.y as f32 / image_size.x as f32
+        };
+        let margin = output.x.min(output.y) * self.margin;
+
+        let x = match self.anchor {
+            WatermarkAnchor::TopLeft | WatermarkAnchor::BottomLeft => margin,
+            WatermarkAnchor::TopRight | WatermarkAnchor::BottomRight => output.x - margin - width,
+        };
+        let y = match self.anchor {
+            WatermarkAnchor::TopLeft | WatermarkAnchor::TopRight => margin,
+            WatermarkAnchor::BottomLeft | WatermarkAnchor::BottomRight => {
+                output.y - margin - height
+            }
+        };
+
+        (XY::new(x, y), XY::new(width, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cap_project::WatermarkTimeRange;
+
+    fn config(anchor: WatermarkAnchor) -> WatermarkConfiguration {
+        WatermarkConfiguration {
+            image_path: "/tmp/logo.png".to_string(),
+            anchor,
+            margin: 0.05,
+            scale: 0.1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bottom_right_is_inset_by_margin_of_shorter_side() {
+        let prepared = prepare_watermark(
+            XY::new(1920, 1080),
+            0.0,
+            Some(&config(WatermarkAnchor::BottomRight)),
+        )
+        .unwrap();
+
+        let (position, size) = prepared.rect(XY::new(400, 200));
+
+        assert_eq!(size, XY::new(192.0, 96.0));
+        assert_eq!(
+            position,
+            XY::new(1920.0 - 54.0 - 192.0, 1080.0 - 54.0 - 96.0)
+        );
+    }
+
+    #[test]
+    fn top_left_sits_at_margin() {
+        let prepared = prepare_watermark(
+            XY::new(1000, 2000),
+            0.0,
+            Some(&config(WatermarkAnchor::TopLeft)),
+        )
+        .unwrap();
+
+        let (position, _) = prepared.rect(XY::new(100, 100));
+
+        assert_eq!(position, XY::new(50.0, 50.0));
+    }
+
+    #[test]
+    fn hidden_outside_time_range_or_without_image() {
+        let mut watermark = config(WatermarkAnchor::TopRight);
+        watermark.time_range = Some(WatermarkTimeRange {
+            start: 2.0,
+            end: 4.0,
+        });
+
+        assert!(prepare_watermark(XY::new(1920, 1080), 1.0, Some(&watermark)).is_none());
+        assert!(prepare_watermark(XY::new(1920, 1080), 3.0, Some(&watermark)).is_some());
+        assert!(prepare_watermark(XY::new(1920, 1080), 4.5, Some(&watermark)).is_none());
+
+        watermark.time_range = None;
+        watermark.image_path.clear();
+        assert!(prepare_watermark(XY::new(1920, 1080), 3.0, Some(&watermark)).is_none());
+        assert!(prepare_watermark(XY::new(1920, 1080), 3.0, None).is_none());
+    }
+}
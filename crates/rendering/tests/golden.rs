@@ -0,0 +1,922 @@
+//! Golden-image tests for the compositor layers.
+//!
+//! Every scene writes a synthetic `.cap` project (recording meta, project config and cursor
+//! events, with generated screen and camera frames standing in for decoded video), renders it
+//! through `FrameRenderer::render` on the software wgpu adapter at fixed timestamps, and compares
+//! each frame against `tests/goldens/<os>/<scene>-<ms>ms.png` with a perceptual tolerance.
+//! The same frames are also composited by the CPU compositor and held to the same tolerance
+//! against the GPU output, so the two backends can't drift apart.
+//!
+//! After an intentional rendering change, regenerate the goldens with
+//!
+//! ```text
+//! cargo test -p cap-rendering --test golden -- --bless
+//! ```
+//!
+//! Any other argument is treated as a scene-name filter, for both checking and blessing.
+//!
+//! A platform without a `tests/goldens/<os>` directory fails the run rather than passing with
+//! nothing compared, and under CI (`CI` set) so does a machine without a wgpu adapter.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use cap_project::{
+    CursorEvents, Cursors, MultipleSegment, MultipleSegments, ProjectConfiguration, RecordingMeta,
+    RecordingMetaInner, StudioRecordingMeta, VideoMeta, XY,
+};
+use cap_rendering::{
+    CpuCompositor, CpuRenderConstants, DecodedFrame, DecodedSegmentFrames, FrameRenderer,
+    ProjectUniforms, RenderVideoConstants, RenderedFrame, RendererLayers, RenderingError,
+    SegmentRecordings, Video, ZoomFocusInterpolator, set_force_software_wgpu_adapter,
+    spring_mass_damper::SpringMassDamperSimulationConfig,
+};
+use image::RgbaImage;
+use relative_path::RelativePathBuf;
+use serde_json::{Value, json};
+
+const FPS: u32 = 30;
+const DURATION: f64 = 2.0;
+const OUTPUT_SIZE: XY<u32> = XY { x: 640, y: 360 };
+const SCREEN_SIZE: XY<u32> = XY { x: 640, y: 360 };
+const CAMERA_SIZE: XY<u32> = XY { x: 320, y: 240 };
+
+/// Per-pixel YIQ distance (as a fraction of the largest possible) above which a pixel counts as
+/// changed. Absorbs the rounding differences between software rasterizers.
+const PIXEL_THRESHOLD: f64 = 0.1;
+/// Fraction of changed pixels a frame may have and still match its golden.
+const MAX_CHANGED_RATIO: f64 = 0.002;
+
+struct Scene {
+    name: &'static str,
+    config: fn() -> Value,
+    camera: bool,
+    cursor: Option<fn() -> Value>,
+    times: &'static [f64],
+    /// Whether the CPU compositor draws every layer in the scene, so its output must match.
+    cpu: bool,
+}
+
+const SCENES: &[Scene] = &[
+    Scene {
+        name: "background-color",
+        config: || {
+            json!({
+                "background": {
+                    "source": { "type": "color", "value": [34, 139, 230] },
+                    "padding": 10.0,
+                    "shadow": 0.0,
+                    "advancedShadow": null,
+                },
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "background-gradient",
+        config: || {
+            json!({
+                "background": {
+                    "source": { "type": "gradient", "from": [255, 94, 77], "to": [72, 52, 212], "angle": 135 },
+                    "padding": 12.0,
+                    "rounding": 30.0,
+                },
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "background-blur",
+        config: || {
+            json!({
+                "background": {
+                    "source": {
+                        "type": "gradient",
+                        "from": [20, 20, 20],
+                        "to": [240, 240, 240],
+                        "noiseIntensity": 0.8,
+                        "noiseScale": 4.0,
+                    },
+                    "blur": 60.0,
+                    "padding": 15.0,
+                },
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "display-shadow-border",
+        config: || {
+            json!({
+                "background": {
+                    "source": { "type": "color", "value": [245, 245, 245] },
+                    "padding": 20.0,
+                    "rounding": 40.0,
+                    "roundingType": "squircle",
+                    "shadow": 80.0,
+                    "advancedShadow": { "size": 60.0, "opacity": 40.0, "blur": 60.0 },
+                    "border": { "enabled": true, "width": 6.0, "color": [255, 0, 128], "opacity": 100.0 },
+                },
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "camera",
+        config: || {
+            json!({
+                "background": { "padding": 8.0 },
+                "camera": { "size": 30.0, "rounding": 50.0, "shape": "square" },
+            })
+        },
+        camera: true,
+        cursor: None,
+        times: &[0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "cursor",
+        config: || {
+            json!({
+                "cursor": { "type": "circle", "size": 150, "raw": true, "motionBlur": 0.0 },
+            })
+        },
+        camera: false,
+        cursor: Some(|| {
+            json!({
+                "clicks": [
+                    { "active_modifiers": [], "cursor_num": 0, "cursor_id": "0", "time_ms": 900.0, "down": true },
+                    { "active_modifiers": [], "cursor_num": 0, "cursor_id": "0", "time_ms": 1000.0, "down": false },
+                ],
+                "moves": [
+                    { "active_modifiers": [], "cursor_id": "0", "time_ms": 0.0, "x": 0.25, "y": 0.3 },
+                    { "active_modifiers": [], "cursor_id": "0", "time_ms": 1000.0, "x": 0.7, "y": 0.6 },
+                ],
+            })
+        }),
+        times: &[0.0, 0.95],
+        cpu: true,
+    },
+    Scene {
+        name: "captions",
+        config: || {
+            json!({
+                "captions": {
+                    "segments": [],
+                    "settings": { "enabled": true, "size": 32 },
+                },
+                "timeline": timeline(json!({
+                    "captionSegments": [{
+                        "id": "caption-0",
+                        "start": 0.0,
+                        "end": 2.0,
+                        "text": "Golden captions stay put",
+                        "words": [
+                            { "text": "Golden", "start": 0.0, "end": 0.5 },
+                            { "text": "captions", "start": 0.5, "end": 1.0 },
+                            { "text": "stay", "start": 1.0, "end": 1.5 },
+                            { "text": "put", "start": 1.5, "end": 2.0 },
+                        ],
+                    }],
+                })),
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.05, 0.75],
+        cpu: true,
+    },
+    Scene {
+        name: "keyboard",
+        config: || {
+            json!({
+                "keyboard": { "settings": { "enabled": true, "size": 36 } },
+                "timeline": timeline(json!({
+                    "keyboardSegments": [{
+                        "id": "keys-0",
+                        "start": 0.2,
+                        "end": 1.8,
+                        "displayText": "Ctrl Shift P",
+                    }],
+                })),
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.25, 1.0],
+        // The keyboard overlay has no CPU implementation.
+        cpu: false,
+    },
+    Scene {
+        name: "masks",
+        config: || {
+            json!({
+                "timeline": timeline(json!({
+                    "maskSegments": [
+                        {
+                            "start": 0.0,
+                            "end": 2.0,
+                            "maskType": "sensitive",
+                            "center": { "x": 0.3, "y": 0.4 },
+                            "size": { "x": 0.25, "y": 0.3 },
+                            "pixelation": 18.0,
+                        },
+                        {
+                            "start": 0.0,
+                            "end": 2.0,
+                            "track": 1,
+                            "maskType": "highlight",
+                            "center": { "x": 0.7, "y": 0.6 },
+                            "size": { "x": 0.3, "y": 0.25 },
+                            "feather": 0.1,
+                            "darkness": 0.6,
+                        },
+                    ],
+                })),
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "text",
+        config: || {
+            json!({
+                "timeline": timeline(json!({
+                    "textSegments": [{
+                        "start": 0.0,
+                        "end": 2.0,
+                        "content": "Golden text",
+                        "center": { "x": 0.5, "y": 0.3 },
+                        "size": { "x": 0.6, "y": 0.2 },
+                        "fontSize": 56.0,
+                        "color": "#ffd400",
+                    }],
+                })),
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.05, 0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "text-keyframes",
+        config: || {
+            json!({
+                "timeline": timeline(json!({
+                    "textSegments": [{
+                        "start": 0.0,
+                        "end": 2.0,
+                        "content": "Moving text",
+                        "size": { "x": 0.5, "y": 0.2 },
+                        "fontSize": 48.0,
+                        "fadeDuration": 0.0,
+                        "keyframes": {
+                            "position": [
+                                { "time": 0.0, "x": 0.3, "y": 0.3 },
+                                { "time": 1.0, "x": 0.7, "y": 0.6, "easing": "easeInOut" },
+                            ],
+                            "rotation": [
+                                { "time": 0.0, "value": 0.0 },
+                                { "time": 1.0, "value": 30.0 },
+                            ],
+                            "scale": [
+                                { "time": 0.0, "value": 0.8 },
+                                { "time": 1.0, "value": 1.2 },
+                            ],
+                        },
+                    }],
+                })),
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.0, 0.5, 1.5],
+        cpu: true,
+    },
+    Scene {
+        name: "text-styles",
+        config: || {
+            json!({
+                "timeline": timeline(json!({
+                    "textSegments": [{
+                        "start": 0.0,
+                        "end": 2.0,
+                        "content": "Styled\ntext",
+                        "center": { "x": 0.5, "y": 0.45 },
+                        "size": { "x": 0.6, "y": 0.3 },
+                        "fontSize": 52.0,
+                        "fadeDuration": 0.0,
+                        "align": "left",
+                        "letterSpacing": 0.08,
+                        "lineHeight": 1.4,
+                        "outline": { "color": "#1030ff", "width": 3.0 },
+                        "shadow": { "offset": { "x": 6.0, "y": 6.0 }, "blur": 6.0 },
+                        "background": { "color": "#202020", "opacity": 0.8 },
+                    }],
+                })),
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.5],
+        cpu: true,
+    },
+    Scene {
+        name: "annotations",
+        config: || {
+            let annotation = |id: &str, kind: &str, rect: [f64; 4], fill: &str| {
+                json!({
+                    "id": id,
+                    "type": kind,
+                    "x": rect[0],
+                    "y": rect[1],
+                    "width": rect[2],
+                    "height": rect[3],
+                    "strokeColor": "#ff3b30",
+                    "strokeWidth": 6.0,
+                    "fillColor": fill,
+                    "opacity": 1.0,
+                    "rotation": 0.0,
+                    "text": null,
+                })
+            };
+            json!({
+                "timeline": timeline(json!({
+                    "annotationSegments": [
+                        {
+                            "start": 0.0,
+                            "end": 2.0,
+                            "fadeDuration": 0.0,
+                            "annotation": annotation("box", "rectangle", [0.1, 0.15, 0.3, 0.3], "#ff3b3040"),
+                        },
+                        {
+                            "start": 0.0,
+                            "end": 2.0,
+                            "fadeDuration": 0.0,
+                            "annotation": annotation("ring", "circle", [0.55, 0.2, 0.3, 0.25], "transparent"),
+                        },
+                        {
+                            "start": 0.0,
+                            "end": 2.0,
+                            "fadeDuration": 0.0,
+                            "drawOnDuration": 1.0,
+                            "annotation": annotation("arrow", "arrow", [0.2, 0.85, 0.5, -0.25], "transparent"),
+                        },
+                    ],
+                })),
+            })
+        },
+        camera: false,
+        cursor: None,
+        times: &[0.25, 1.5],
+        cpu: true,
+    },
+];
+
+/// A single full-length timeline segment with `tracks` merged in.
+fn timeline(tracks: Value) -> Value {
+    let mut timeline = json!({
+        "segments": [{ "recordingSegment": 0, "timescale": 1.0, "start": 0.0, "end": DURATION }],
+        "zoomSegments": [],
+    });
+    if let (Some(timeline), Value::Object(tracks)) = (timeline.as_object_mut(), tracks) {
+        timeline.extend(tracks);
+    }
+    timeline
+}
+
+struct Args {
+    bless: bool,
+    filter: Option<String>,
+}
+
+impl Args {
+    fn parse() -> Self {
+        let mut args = Self {
+            bless: false,
+            filter: None,
+        };
+
+        // libtest flags such as `--nocapture` are accepted and ignored.
+        for arg in std::env::args().skip(1) {
+            if arg == "--bless" {
+                args.bless = true;
+            } else if !arg.starts_with('-') {
+                args.filter = Some(arg);
+            }
+        }
+
+        args.bless |= std::env::var("CAP_GOLDEN_BLESS").is_ok_and(|value| value == "1");
+        args
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    set_force_software_wgpu_adapter(true);
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime");
+
+    let scenes = SCENES
+        .iter()
+        .filter(|scene| args.filter.as_ref().is_none_or(|f| scene.name.contains(f)))
+        .collect::<Vec<_>>();
+
+    println!("\nrunning {} golden scenes", scenes.len());
+
+    let goldens_dir = goldens_dir();
+    if !args.bless && !goldens_dir.is_dir() {
+        println!(
+            "\nno goldens for this platform in {}",
+            goldens_dir.display()
+        );
+        println!("Run `cargo test -p cap-rendering --test golden -- --bless` and commit the PNGs.");
+        std::process::exit(1);
+    }
+
+    let mut failures = Vec::new();
+    for scene in scenes {
+        match runtime.block_on(run_scene(scene, args.bless)) {
+            Ok(SceneOutcome::Passed) => println!("golden {} ... ok", scene.name),
+            Ok(SceneOutcome::Blessed) => println!("golden {} ... blessed", scene.name),
+            Ok(SceneOutcome::Skipped(reason)) => {
+                println!("golden {} ... skipped: {reason}", scene.name)
+            }
+            Err(error) => {
+                println!("golden {} ... FAILED", scene.name);
+                failures.push(format!("{}: {error}", scene.name));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for failure in &failures {
+            println!("    {failure}");
+        }
+        println!(
+            "\nIf the change is intentional, run `cargo test -p cap-rendering --test golden -- --bless`."
+        );
+        std::process::exit(1);
+    }
+}
+
+enum SceneOutcome {
+    Passed,
+    Blessed,
+    Skipped(String),
+}
+
+async fn run_scene(scene: &Scene, bless: bool) -> Result<SceneOutcome, String> {
+    let project_dir = tempfile::Builder::new()
+        .prefix(scene.name)
+        .suffix(".cap")
+        .tempdir()
+        .map_err(|e| format!("temp dir: {e}"))?;
+    write_fixture(project_dir.path(), scene)?;
+
+    let recording_meta = RecordingMeta::load_for_project(project_dir.path())
+        .map_err(|e| format!("load recording meta: {e}"))?;
+    let Some(studio_meta) = recording_meta.studio_meta().cloned() else {
+        return Err("fixture is not a studio recording".to_string());
+    };
+    let StudioRecordingMeta::MultipleSegments { inner } = &studio_meta else {
+        return Err("fixture is not a multi-segment recording".to_string());
+    };
+    let project = ProjectConfiguration::load(project_dir.path())
+        .map_err(|e| format!("load project config: {e}"))?;
+    let cursor = match &inner.segments[0].cursor {
+        Some(path) => CursorEvents::load_from_file(&path.to_path(project_dir.path()))?,
+        None => CursorEvents::default(),
+    };
+
+    let segments = [SegmentRecordings {
+        display: video(SCREEN_SIZE),
+        camera: scene.camera.then(|| video(CAMERA_SIZE)),
+        mic: None,
+        system_audio: None,
+    }];
+
+    let cpu_constants =
+        CpuRenderConstants::new(&segments, recording_meta.clone(), studio_meta.clone())
+            .map_err(|e| format!("CPU compositor setup: {e}"))?;
+
+    let constants = match RenderVideoConstants::new(&segments, recording_meta, studio_meta).await {
+        Ok(constants) => constants,
+        Err(RenderingError::NoAdapter) if std::env::var_os("CI").is_none() => {
+            return Ok(SceneOutcome::Skipped("no wgpu adapter".to_string()));
+        }
+        Err(e) => return Err(format!("renderer setup: {e}")),
+    };
+
+    let frames = render_frames(&constants, &project, &cursor, scene).await?;
+    let cpu_frames = scene
+        .cpu
+        .then(|| render_cpu_frames(&cpu_constants, &project, &cursor, scene));
+
+    let goldens_dir = goldens_dir();
+    let mut errors = Vec::new();
+    for (i, (&time, frame)) in scene.times.iter().zip(&frames).enumerate() {
+        let name = format!("{}-{}ms", scene.name, (time * 1000.0).round() as u32);
+        let actual = frame_to_image(frame);
+        let golden_path = goldens_dir.join(format!("{name}.png"));
+
+        if let Some(cpu_frames) = &cpu_frames
+            && let Err(error) = compare(
+                &actual,
+                &frame_to_image(&cpu_frames[i]),
+                &format!("{name}-cpu"),
+            )
+        {
+            errors.push(error);
+        }
+
+        if bless {
+            std::fs::create_dir_all(&goldens_dir).map_err(|e| format!("create goldens: {e}"))?;
+            actual
+                .save(&golden_path)
+                .map_err(|e| format!("write {}: {e}", golden_path.display()))?;
+            continue;
+        }
+
+        let Ok(expected) = image::open(&golden_path).map(|image| image.into_rgba8()) else {
+            errors.push(format!("missing golden {}", golden_path.display()));
+            continue;
+        };
+
+        if let Err(error) = compare(&expected, &actual, &name) {
+            errors.push(error);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    Ok(if bless {
+        SceneOutcome::Blessed
+    } else {
+        SceneOutcome::Passed
+    })
+}
+
+fn zoom_focus_interpolator(
+    project: &ProjectConfiguration,
+    cursor: &CursorEvents,
+) -> ZoomFocusInterpolator {
+    let cursor_smoothing = (!project.cursor.raw).then_some(SpringMassDamperSimulationConfig {
+        tension: project.cursor.tension,
+        mass: project.cursor.mass,
+        friction: project.cursor.friction,
+    });
+    let mut zoom_focus_interpolator = ZoomFocusInterpolator::new(
+        cursor,
+        cursor_smoothing,
+        project.cursor.click_spring_config(),
+        project.screen_movement_spring,
+        DURATION,
+        project
+            .timeline
+            .as_ref()
+            .map(|t| t.zoom_segments.as_slice())
+            .unwrap_or(&[]),
+    );
+    zoom_focus_interpolator.precompute();
+    zoom_focus_interpolator
+}
+
+/// The generated screen and camera frames every scene renders, as decoded at `time`.
+struct SceneFrames {
+    screen: std::sync::Arc<Vec<u8>>,
+    camera: std::sync::Arc<Vec<u8>>,
+}
+
+impl SceneFrames {
+    fn new() -> Self {
+        Self {
+            screen: test_pattern(SCREEN_SIZE, |x, y| [x, y, 0.5]),
+            camera: test_pattern(CAMERA_SIZE, |x, y| {
+                let d = ((x - 0.5).powi(2) + (y - 0.5).powi(2)).sqrt();
+                [1.0 - d, 0.6, d]
+            }),
+        }
+    }
+
+    fn at(&self, scene: &Scene, time: f64) -> DecodedSegmentFrames {
+        DecodedSegmentFrames {
+            screen_frame: Some(DecodedFrame::new_with_arc(
+                self.screen.clone(),
+                SCREEN_SIZE.x,
+                SCREEN_SIZE.y,
+            )),
+            camera_frame: scene.camera.then(|| {
+                DecodedFrame::new_with_arc(self.camera.clone(), CAMERA_SIZE.x, CAMERA_SIZE.y)
+            }),
+            segment_time: time as f32,
+            recording_time: time as f32,
+            segment_has_camera: scene.camera,
+        }
+    }
+}
+
+async fn render_frames(
+    constants: &RenderVideoConstants,
+    project: &ProjectConfiguration,
+    cursor: &CursorEvents,
+    scene: &Scene,
+) -> Result<Vec<RenderedFrame>, String> {
+    let zoom_focus_interpolator = zoom_focus_interpolator(project, cursor);
+
+    let mut renderer = FrameRenderer::new(constants);
+    let mut layers = RendererLayers::new_with_options(
+        &constants.device,
+        &constants.queue,
+        constants.is_software_adapter,
+    );
+    layers.prepare_for_video_dimensions(
+        &constants.device,
+        SCREEN_SIZE.x,
+        SCREEN_SIZE.y,
+        scene.camera.then_some(CAMERA_SIZE.x),
+        scene.camera.then_some(CAMERA_SIZE.y),
+    );
+
+    let scene_frames = SceneFrames::new();
+
+    // `render` hands back the previous frame once the readback pipeline is primed, so frames
+    // are matched to timestamps by number rather than by call order.
+    let mut rendered = BTreeMap::new();
+    for &time in scene.times {
+        let frame_number = (time * FPS as f64).round() as u32;
+        let segment_frames = scene_frames.at(scene, time);
+        let uniforms = ProjectUniforms::new(
+            constants,
+            project,
+            frame_number,
+            FPS,
+            OUTPUT_SIZE,
+            cursor,
+            &segment_frames,
+            DURATION,
+            &zoom_focus_interpolator,
+        );
+
+        if let Some(frame) = renderer
+            .render(segment_frames, uniforms, cursor, true, &mut layers)
+            .await
+            .map_err(|e| format!("render frame {frame_number}: {e}"))?
+        {
+            rendered.insert(frame.frame_number, frame);
+        }
+    }
+
+    if let Some(frame) = renderer.flush_pipeline().await {
+        let frame = frame.map_err(|e| format!("flush: {e}"))?;
+        rendered.insert(frame.frame_number, frame);
+    }
+
+    scene
+        .times
+        .iter()
+        .map(|&time| {
+            let frame_number = (time * FPS as f64).round() as u32;
+            rendered
+                .remove(&frame_number)
+                .ok_or_else(|| format!("frame {frame_number} was never rendered"))
+        })
+        .collect()
+}
+
+/// Composites the same frames as [`render_frames`] with the CPU compositor.
+fn render_cpu_frames(
+    constants: &CpuRenderConstants,
+    project: &ProjectConfiguration,
+    cursor: &CursorEvents,
+    scene: &Scene,
+) -> Vec<RenderedFrame> {
+    let zoom_focus_interpolator = zoom_focus_interpolator(project, cursor);
+    let scene_frames = SceneFrames::new();
+    let mut compositor = CpuCompositor::new(constants);
+
+    scene
+        .times
+        .iter()
+        .map(|&time| {
+            let segment_frames = scene_frames.at(scene, time);
+            let uniforms = ProjectUniforms::with_options(
+                &constants.options,
+                project,
+                (time * FPS as f64).round() as u32,
+                FPS,
+                OUTPUT_SIZE,
+                cursor,
+                &segment_frames,
+                DURATION,
+                &zoom_focus_interpolator,
+            );
+            compositor.render(segment_frames, uniforms, cursor, true)
+        })
+        .collect()
+}
+
+fn write_fixture(project_dir: &Path, scene: &Scene) -> Result<(), String> {
+    let segment_dir = RelativePathBuf::from("content/segments/segment-0");
+    std::fs::create_dir_all(segment_dir.to_path(project_dir))
+        .map_err(|e| format!("create fixture: {e}"))?;
+
+    let cursor = match scene.cursor {
+        Some(events) => {
+            let path = segment_dir.join("cursor.json");
+            std::fs::write(path.to_path(project_dir), events().to_string())
+                .map_err(|e| format!("write cursor events: {e}"))?;
+            Some(path)
+        }
+        None => None,
+    };
+
+    let video_meta = |name: &str| VideoMeta {
+        path: segment_dir.join(name),
+        fps: FPS,
+        start_time: None,
+        device_id: None,
+    };
+
+    RecordingMeta {
+        platform: None,
+        project_path: project_dir.to_path_buf(),
+        pretty_name: format!("Golden {}", scene.name),
+        sharing: None,
+        upload: None,
+        chapters: Vec::new(),
+        inner: RecordingMetaInner::Studio(Box::new(StudioRecordingMeta::MultipleSegments {
+            inner: MultipleSegments {
+                segments: vec![MultipleSegment {
+                    display: video_meta("display.mp4"),
+                    camera: scene.camera.then(|| video_meta("camera.mp4")),
+                    mic: None,
+                    system_audio: None,
+                    cursor,
+                    keyboard: None,
+                }],
+                cursors: Cursors::default(),
+                status: None,
+            },
+        })),
+    }
+    .save_for_project()
+    .map_err(|e| format!("write recording meta: {e:?}"))?;
+
+    let project: ProjectConfiguration =
+        serde_json::from_value((scene.config)()).map_err(|e| format!("project config: {e}"))?;
+    project
+        .write(project_dir)
+        .map_err(|e| format!("write project config: {e}"))
+}
+
+fn video(size: XY<u32>) -> Video {
+    Video {
+        duration: DURATION,
+        width: size.x,
+        height: size.y,
+        fps: FPS,
+        start_time: 0.0,
+    }
+}
+
+/// An opaque RGBA frame with an 8x8 checkerboard over `color(u, v)`, so crops, scales and blurs
+/// all show up in the output.
+fn test_pattern(size: XY<u32>, color: impl Fn(f32, f32) -> [f32; 3]) -> std::sync::Arc<Vec<u8>> {
+    let mut data = Vec::with_capacity((size.x * size.y * 4) as usize);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let [r, g, b] = color(x as f32 / size.x as f32, y as f32 / size.y as f32);
+            let checker = if ((x / 40) + (y / 40)) % 2 == 0 {
+                1.0
+            } else {
+                0.75
+            };
+            data.extend([r, g, b].map(|c| (c.clamp(0.0, 1.0) * checker * 255.0).round() as u8));
+            data.push(255);
+        }
+    }
+    std::sync::Arc::new(data)
+}
+
+fn goldens_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/goldens")
+        .join(std::env::consts::OS)
+}
+
+fn frame_to_image(frame: &RenderedFrame) -> RgbaImage {
+    let row_bytes = (frame.width * 4) as usize;
+    let data = frame
+        .data
+        .chunks(frame.padded_bytes_per_row as usize)
+        .take(frame.height as usize)
+        .flat_map(|row| &row[..row_bytes])
+        .copied()
+        .collect();
+    RgbaImage::from_raw(frame.width, frame.height, data).expect("frame buffer size")
+}
+
+/// Compares `actual` against `expected`, writing both plus a diff image to the target dir when
+/// they differ by more than the tolerance.
+fn compare(expected: &RgbaImage, actual: &RgbaImage, name: &str) -> Result<(), String> {
+    if expected.dimensions() != actual.dimensions() {
+        return Err(format!(
+            "{name}: size {:?} does not match golden {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        ));
+    }
+
+    let max_delta = MAX_YIQ_DELTA * PIXEL_THRESHOLD * PIXEL_THRESHOLD;
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut changed = 0u64;
+
+    for ((e, a), d) in expected
+        .pixels()
+        .zip(actual.pixels())
+        .zip(diff.pixels_mut())
+    {
+        if yiq_delta(e.0, a.0) > max_delta {
+            changed += 1;
+            *d = image::Rgba([255, 0, 0, 255]);
+        } else {
+            let gray = (luma(blend_on_white(e.0)) * 0.1 + 255.0 * 0.9) as u8;
+            *d = image::Rgba([gray, gray, gray, 255]);
+        }
+    }
+
+    let ratio = changed as f64 / (actual.width() as f64 * actual.height() as f64);
+    if ratio <= MAX_CHANGED_RATIO {
+        return Ok(());
+    }
+
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let _ = std::fs::create_dir_all(&out_dir);
+    let _ = actual.save(out_dir.join(format!("{name}.actual.png")));
+    let _ = expected.save(out_dir.join(format!("{name}.expected.png")));
+    let _ = diff.save(out_dir.join(format!("{name}.diff.png")));
+
+    Err(format!(
+        "{name}: {:.3}% of pixels changed (limit {:.3}%), see {}",
+        ratio * 100.0,
+        MAX_CHANGED_RATIO * 100.0,
+        out_dir.display()
+    ))
+}
+
+/// The largest `yiq_delta` two pixels can have (black against white).
+const MAX_YIQ_DELTA: f64 = 35215.0;
+
+/// Perceptual colour distance in YIQ space, weighted the way pixelmatch does.
+fn yiq_delta(a: [u8; 4], b: [u8; 4]) -> f64 {
+    let a = blend_on_white(a);
+    let b = blend_on_white(b);
+
+    let y = luma(a) - luma(b);
+    let i = in_phase(a) - in_phase(b);
+    let q = quadrature(a) - quadrature(b);
+
+    0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q
+}
+
+fn blend_on_white([r, g, b, a]: [u8; 4]) -> [f64; 3] {
+    let alpha = a as f64 / 255.0;
+    [r, g, b].map(|c| 255.0 + (c as f64 - 255.0) * alpha)
+}
+
+fn luma([r, g, b]: [f64; 3]) -> f64 {
+    r * 0.298_895_31 + g * 0.586_622_47 + b * 0.114_482_23
+}
+
+fn in_phase([r, g, b]: [f64; 3]) -> f64 {
+    r * 0.595_977_99 - g * 0.274_176_10 - b * 0.321_801_89
+}
+
+fn quadrature([r, g, b]: [f64; 3]) -> f64 {
+    r * 0.211_470_17 - g * 0.522_617_37 + b * 0.311_147_20
+}
@@ -0,0 +1,368 @@
+use std::path::{Path, PathBuf};
+
+use cap_audio::AudioData;
+use cap_project::{RecordingMeta, RecordingMetaInner, StudioRecordingMeta};
+use ffmpeg::{
+    ChannelLayout, codec as avcodec,
+    format::{self as avformat},
+    software::resampling,
+};
+
+pub(crate) const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+#[derive(Debug)]
+pub(crate) enum AudioExtractionSource {
+    ProjectDirectory(PathBuf),
+    MediaFile(PathBuf),
+}
+
+pub(crate) fn resolve_audio_extraction_source(
+    path: &Path,
+) -> Result<AudioExtractionSource, String> {
+    let metadata =
+        std::fs::metadata(path).map_err(|e| format!("Failed to read video path metadata: {e}"))?;
+
+    if metadata.is_dir() {
+        if !path.join("recording-meta.json").is_file() {
+            return Err("Recording directory is missing recording-meta.json".to_string());
+        }
+
+        return Ok(AudioExtractionSource::ProjectDirectory(path.to_path_buf()));
+    }
+
+    if metadata.is_file() {
+        return Ok(AudioExtractionSource::MediaFile(path.to_path_buf()));
+    }
+
+    Err("Video path is neither a file nor a recording directory".to_string())
+}
+
+/// Each recording segment's display duration, in seconds. Source time is these segments laid end
+/// to end, so transcription audio and caption projection both need them.
+pub fn recording_durations(meta: &RecordingMeta) -> Result<Vec<f64>, String> {
+    let Some(studio) = meta.studio_meta() else {
+        return Ok(Vec::new());
+    };
+
+    let displays = match studio {
+        StudioRecordingMeta::SingleSegment { segment } => vec![&segment.display],
+        StudioRecordingMeta::MultipleSegments { inner, .. } => {
+            inner.segments.iter().map(|s| &s.display).collect()
+        }
+    };
+
+    displays
+        .into_iter()
+        .map(|display| {
+            let path = meta.path(&display.path);
+            let input = avformat::input(&path)
+                .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            Ok(input.duration().max(0) as f64 / f64::from(ffmpeg::ffi::AV_TIME_BASE))
+        })
+        .collect()
+}
+
+/// Mic and system audio for one recording, mixed to mono 48kHz and laid out in source time:
+/// each segment is padded or trimmed to its display duration, so a segment recorded without
+/// audio still takes up its share of the timeline.
+fn project_samples(project_path: &Path) -> Result<Vec<f32>, String> {
+    let meta = RecordingMeta::load_for_project(project_path)
+        .map_err(|e| format!("Failed to read recording metadata: {e}"))?;
+
+    let studio = match &meta.inner {
+        RecordingMetaInner::Instant(_) => {
+            let audio = AudioData::from_file(meta.output_path())
+                .map_err(|e| format!("Failed to decode recording audio: {e}"))?;
+            return Ok(convert_to_mono(audio.samples(), audio.channels() as usize));
+        }
+        RecordingMetaInner::Studio(studio) => studio,
+    };
+
+    let segment_sources = match studio.as_ref() {
+        StudioRecordingMeta::SingleSegment { segment } => {
+            vec![segment.audio.iter().collect::<Vec<_>>()]
+        }
+        StudioRecordingMeta::MultipleSegments { inner, .. } => inner
+            .segments
+            .iter()
+            .map(|s| s.system_audio.iter().chain(s.mic.iter()).collect())
+            .collect(),
+    };
+    let durations = recording_durations(&meta).unwrap_or_else(|e| {
+        tracing::warn!("Couldn't probe segment durations, captions may drift: {e}");
+        Vec::new()
+    });
+
+    let mut final_samples = Vec::new();
+
+    for (segment_idx, sources) in segment_sources.iter().enumerate() {
+        let mut segment_samples: Vec<f32> = Vec::new();
+
+        for source in sources {
+            let path = meta.path(&source.path);
+            match AudioData::from_file(&path) {
+                Ok(audio) => {
+                    tracing::info!(
+                        "Processing audio source {:?}: {} channels, {} samples",
+                        path,
+                        audio.channels(),
+                        audio.sample_count()
+                    );
+
+                    let mono_samples = convert_to_mono(audio.samples(), audio.channels() as usize);
+                    if segment_samples.is_empty() {
+                        segment_samples = mono_samples;
+                    } else {
+                        mix_samples(&mut segment_samples, &mono_samples);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to process audio source {path:?}: {e}");
+                }
+            }
+        }
+
+        if let Some(duration) = durations.get(segment_idx) {
+            segment_samples.resize((duration * AudioData::SAMPLE_RATE as f64) as usize, 0.0);
+        }
+
+        tracing::info!(
+            "Segment {} produced {} samples",
+            segment_idx,
+            segment_samples.len()
+        );
+        final_samples.extend(segment_samples);
+    }
+
+    if segment_sources.iter().all(Vec::is_empty) {
+        return Err("No audio sources found in the recording metadata".to_string());
+    }
+
+    Ok(final_samples)
+}
+
+/// Writes the audio of a recording project or media file as 16kHz mono PCM WAV, the input both
+/// engines expect.
+pub(crate) fn extract_audio(source: &Path, output_path: &Path) -> Result<(), String> {
+    tracing::info!("Extracting transcription audio from {source:?} to {output_path:?}");
+
+    let mut samples = match resolve_audio_extraction_source(source)? {
+        AudioExtractionSource::ProjectDirectory(path) => project_samples(&path)?,
+        AudioExtractionSource::MediaFile(path) => {
+            let audio =
+                AudioData::from_file(&path).map_err(|e| format!("Failed to decode audio: {e}"))?;
+            convert_to_mono(audio.samples(), audio.channels() as usize)
+        }
+    };
+
+    if samples.is_empty() {
+        return Err("Failed to process any audio sources".to_string());
+    }
+
+    let gain = normalize_audio_for_transcription(&mut samples);
+    if (gain - 1.0).abs() > 0.01 {
+        tracing::info!("Applied transcription audio gain: {gain:.2}x");
+    }
+
+    let rms = (samples.iter().map(|&s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+    if rms < 0.001 {
+        tracing::warn!("Mixed audio RMS is very low ({rms:.6}) - audio may be nearly silent");
+    }
+
+    write_wav(&samples, output_path)
+}
+
+fn write_wav(samples: &[f32], output_path: &Path) -> Result<(), String> {
+    let mut output =
+        avformat::output(&output_path).map_err(|e| format!("Failed to create output file: {e}"))?;
+
+    let codec = avcodec::encoder::find_by_name("pcm_s16le")
+        .ok_or_else(|| "PCM encoder not found".to_string())?;
+
+    let mut encoder = avcodec::Context::new()
+        .encoder()
+        .audio()
+        .map_err(|e| format!("Failed to create encoder: {e}"))?;
+
+    encoder.set_rate(WHISPER_SAMPLE_RATE as i32);
+    let channel_layout = ChannelLayout::MONO;
+    encoder.set_channel_layout(channel_layout);
+    encoder.set_format(avformat::Sample::I16(avformat::sample::Type::Packed));
+
+    let mut encoder = encoder
+        .open_as(codec)
+        .map_err(|e| format!("Failed to open encoder: {e}"))?;
+
+    let mut stream = output
+        .add_stream(codec)
+        .map_err(|e| format!("Failed to add stream: {e}"))?;
+    stream.set_parameters(&encoder);
+
+    output
+        .write_header()
+        .map_err(|e| format!("Failed to write header: {e}"))?;
+
+    let mut resampler = resampling::Context::get(
+        avformat::Sample::F32(avformat::sample::Type::Packed),
+        channel_layout,
+        AudioData::SAMPLE_RATE,
+        avformat::Sample::I16(avformat::sample::Type::Packed),
+        channel_layout,
+        WHISPER_SAMPLE_RATE,
+    )
+    .map_err(|e| format!("Failed to create resampler: {e}"))?;
+
+    let frame_size = match encoder.frame_size() as usize {
+        0 => 1024,
+        frame_size => frame_size,
+    };
+
+    for (chunk_idx, chunk) in samples.chunks(frame_size).enumerate() {
+        let mut input_frame = ffmpeg::frame::Audio::new(
+            avformat::Sample::F32(avformat::sample::Type::Packed),
+            chunk.len(),
+            channel_layout,
+        );
+        input_frame.set_rate(AudioData::SAMPLE_RATE);
+
+        let bytes = unsafe {
+            std::slice::from_raw_parts(chunk.as_ptr() as *const u8, std::mem::size_of_val(chunk))
+        };
+        input_frame.data_mut(0)[0..bytes.len()].copy_from_slice(bytes);
+
+        let mut output_frame = ffmpeg::frame::Audio::new(
+            avformat::Sample::I16(avformat::sample::Type::Packed),
+            frame_size,
+            channel_layout,
+        );
+        output_frame.set_rate(WHISPER_SAMPLE_RATE);
+
+        if let Err(e) = resampler.run(&input_frame, &mut output_frame) {
+            tracing::error!("Failed to resample chunk {chunk_idx}: {e}");
+            continue;
+        }
+
+        if let Err(e) = encoder.send_frame(&output_frame) {
+            tracing::error!("Failed to send frame to encoder: {e}");
+            continue;
+        }
+
+        let mut packet = ffmpeg::Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            if let Err(e) = packet.write_interleaved(&mut output) {
+                tracing::error!("Failed to write packet: {e}");
+            }
+        }
+    }
+
+    encoder
+        .send_eof()
+        .map_err(|e| format!("Failed to send EOF: {e}"))?;
+
+    let mut packet = ffmpeg::Packet::empty();
+    while encoder.receive_packet(&mut packet).is_ok() {
+        packet
+            .write_interleaved(&mut output)
+            .map_err(|e| format!("Failed to write final packet: {e}"))?;
+    }
+
+    output
+        .write_trailer()
+        .map_err(|e| format!("Failed to write trailer: {e}"))
+}
+
+/// Raises quiet recordings towards a speech-like level without clipping; returns the gain used.
+pub(crate) fn normalize_audio_for_transcription(samples: &mut [f32]) -> f32 {
+    if samples.is_empty() {
+        return 1.0;
+    }
+
+    let peak = samples
+        .iter()
+        .fold(0.0_f32, |max, sample| max.max(sample.abs()));
+    if peak <= f32::EPSILON {
+        return 1.0;
+    }
+
+    let rms =
+        (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt();
+    if rms <= f32::EPSILON {
+        return 1.0;
+    }
+
+    let target_rms = 0.08_f32;
+    let desired_gain = (target_rms / rms).clamp(1.0, 8.0);
+    let peak_limited_gain = 0.98 / peak;
+    let gain = desired_gain.min(peak_limited_gain);
+
+    if (gain - 1.0).abs() > 0.01 {
+        for sample in samples {
+            *sample = (*sample * gain).clamp(-0.98, 0.98);
+        }
+    }
+
+    gain
+}
+
+fn convert_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+fn mix_samples(dest: &mut [f32], source: &[f32]) {
+    for (dest, source) in dest.iter_mut().zip(source) {
+        *dest = (*dest + source) * 0.5;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn audio_extraction_source_accepts_project_directory_without_cap_extension() {
+        let dir = tempdir().unwrap();
+        let project_dir = dir.path().join("recording");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(project_dir.join("recording-meta.json"), "{}").unwrap();
+
+        match resolve_audio_extraction_source(&project_dir).unwrap() {
+            AudioExtractionSource::ProjectDirectory(path) => assert_eq!(path, project_dir),
+            AudioExtractionSource::MediaFile(_) => panic!("expected project directory"),
+        }
+    }
+
+    #[test]
+    fn audio_extraction_source_rejects_directory_without_recording_metadata() {
+        let dir = tempdir().unwrap();
+
+        match resolve_audio_extraction_source(dir.path()) {
+            Ok(_) => panic!("expected missing metadata error"),
+            Err(error) => assert_eq!(error, "Recording directory is missing recording-meta.json"),
+        }
+    }
+
+    #[test]
+    fn audio_extraction_source_accepts_media_file() {
+        let dir = tempdir().unwrap();
+        let media_file = dir.path().join("recording.mp4");
+        std::fs::write(&media_file, []).unwrap();
+
+        match resolve_audio_extraction_source(&media_file).unwrap() {
+            AudioExtractionSource::MediaFile(path) => assert_eq!(path, media_file),
+            AudioExtractionSource::ProjectDirectory(_) => panic!("expected media file"),
+        }
+    }
+
+    #[test]
+    fn stereo_is_averaged_to_mono() {
+        assert_eq!(convert_to_mono(&[1.0, 0.0, 0.5, 0.5], 2), vec![0.5, 0.5]);
+    }
+}
@@ -0,0 +1,230 @@
+//! Speech-to-text for Cap recordings with a local Whisper or Parakeet model. Turns a recording
+//! project or media file into word-timed caption segments in source time, the layout
+//! `CaptionsData::source_timed` describes.
+
+mod audio;
+mod parakeet;
+mod whisper;
+mod words;
+
+use std::{path::Path, sync::Arc};
+
+use cap_project::{
+    CaptionSegment, CaptionSettings, CaptionsData, ProjectConfiguration, RecordingMeta,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tempfile::tempdir;
+
+pub use audio::recording_durations;
+pub use parakeet::invalidate_parakeet_model;
+
+#[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+pub const PARAKEET_UNSUPPORTED_MESSAGE: &str =
+    "Parakeet transcription is not available on Intel macOS";
+
+/// Only one model runs at a time; each can use most of the machine's memory and cores.
+static TRANSCRIPTION_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptionEngine {
+    Whisper,
+    Parakeet,
+}
+
+impl TranscriptionEngine {
+    /// Parakeet models are directories of ONNX files; Whisper models are a single `.bin` file.
+    pub fn for_model(model_path: &Path) -> Self {
+        if model_path.is_dir() {
+            Self::Parakeet
+        } else {
+            Self::Whisper
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriptionOptions {
+    pub engine: TranscriptionEngine,
+    /// A Whisper language code, or `auto` to detect it. Parakeet only transcribes English.
+    pub language: String,
+    /// Names and terms whose spelling Whisper should prefer.
+    pub hints: Vec<String>,
+}
+
+impl Default for TranscriptionOptions {
+    fn default() -> Self {
+        Self {
+            engine: TranscriptionEngine::Whisper,
+            language: "auto".to_string(),
+            hints: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(tag = "stage", rename_all = "camelCase")]
+pub enum TranscriptionProgress {
+    ExtractingAudio,
+    LoadingModel,
+    /// `progress` runs from 0 to 1. Parakeet doesn't report it, so it only sends 1 when done.
+    Transcribing {
+        progress: f64,
+    },
+}
+
+fn lock_transcription_worker_slot() -> std::sync::MutexGuard<'static, ()> {
+    TRANSCRIPTION_LOCK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Frees the cached Whisper and Parakeet models.
+pub async fn release_models() {
+    whisper::release_context().await;
+    parakeet::release_context().await;
+}
+
+/// Transcribes a recording project directory or a media file with the model at `model_path`.
+/// For projects, segment times are in source time: every recording segment's audio laid end to
+/// end, each taking up its display duration.
+pub async fn transcribe(
+    source: &Path,
+    model_path: &Path,
+    options: &TranscriptionOptions,
+    on_progress: impl Fn(TranscriptionProgress) + Send + Sync + 'static,
+) -> Result<Vec<CaptionSegment>, String> {
+    if !source.exists() {
+        return Err(format!(
+            "Video file not found at path: {}",
+            source.display()
+        ));
+    }
+    if !model_path.exists() {
+        return Err(format!(
+            "Model file not found at path: {}",
+            model_path.display()
+        ));
+    }
+
+    let on_progress: Arc<dyn Fn(TranscriptionProgress) + Send + Sync> = Arc::new(on_progress);
+
+    let temp_dir = tempdir().map_err(|e| format!("Failed to create temporary directory: {e}"))?;
+    let audio_path = temp_dir.path().join("audio.wav");
+
+    on_progress(TranscriptionProgress::ExtractingAudio);
+    {
+        let source = source.to_path_buf();
+        let audio_path = audio_path.clone();
+        tokio::task::spawn_blocking(move || audio::extract_audio(&source, &audio_path))
+            .await
+            .map_err(|e| format!("Audio extraction task panicked: {e}"))?
+            .map_err(|e| format!("Failed to extract audio from video: {e}"))?;
+    }
+
+    if !audio_path.exists() {
+        return Err("Failed to create audio file for transcription".to_string());
+    }
+
+    let model_path = model_path.to_path_buf();
+    let segments = match options.engine {
+        TranscriptionEngine::Parakeet => tokio::task::spawn_blocking(move || {
+            let _guard = lock_transcription_worker_slot();
+            on_progress(TranscriptionProgress::LoadingModel);
+            let result = parakeet::transcribe(&audio_path, &model_path);
+            on_progress(TranscriptionProgress::Transcribing { progress: 1.0 });
+            result
+        })
+        .await
+        .map_err(|e| format!("Parakeet task panicked: {e}"))?,
+        TranscriptionEngine::Whisper => {
+            let language = options.language.clone();
+            let hints = options.hints.clone();
+            tokio::task::spawn_blocking(move || {
+                let _guard = lock_transcription_worker_slot();
+                whisper::transcribe(&audio_path, &model_path, &language, &hints, on_progress)
+            })
+            .await
+            .map_err(|e| format!("Whisper task panicked: {e}"))?
+        }
+    }
+    .map_err(|e| format!("Failed to transcribe audio: {e}"))?;
+
+    if segments.is_empty() {
+        return Err("No speech detected in the audio".to_string());
+    }
+
+    Ok(segments)
+}
+
+/// Transcribes a recording project and saves the result as its captions with
+/// [`save_project_captions`].
+pub async fn generate_project_captions(
+    project_path: &Path,
+    model_path: &Path,
+    options: &TranscriptionOptions,
+    on_progress: impl Fn(TranscriptionProgress) + Send + Sync + 'static,
+) -> Result<CaptionsData, String> {
+    // Fail on a broken project before spending minutes on transcription.
+    load_project(project_path)?;
+
+    let segments = transcribe(project_path, model_path, options, on_progress).await?;
+    save_project_captions(project_path, segments)
+}
+
+/// Saves source-time caption segments as a project's captions, replacing any existing ones but
+/// keeping their style. The project's caption track is re-derived from them so an export picks
+/// them up without opening the editor.
+pub fn save_project_captions(
+    project_path: &Path,
+    segments: Vec<CaptionSegment>,
+) -> Result<CaptionsData, String> {
+    let (meta, mut config) = load_project(project_path)?;
+
+    let settings = config
+        .captions
+        .as_ref()
+        .map(|captions| captions.settings.clone())
+        .unwrap_or_else(|| CaptionSettings {
+            enabled: true,
+            ..Default::default()
+        });
+    let captions = CaptionsData {
+        segments,
+        settings,
+        source_timed: true,
+    };
+
+    if let Some(timeline) = config.timeline.as_mut() {
+        let durations = recording_durations(&meta)?;
+        timeline.caption_segments = captions.derive_track(timeline, &durations);
+    }
+    config.captions = Some(captions.clone());
+    config
+        .write(project_path)
+        .map_err(|e| format!("Failed to write project config: {e}"))?;
+
+    Ok(captions)
+}
+
+fn load_project(project_path: &Path) -> Result<(RecordingMeta, ProjectConfiguration), String> {
+    let meta = RecordingMeta::load_for_project(project_path)
+        .map_err(|e| format!("Failed to load recording meta: {e}"))?;
+    let config = match ProjectConfiguration::load(project_path) {
+        Ok(config) => config,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => ProjectConfiguration::default(),
+        Err(e) => return Err(format!("Failed to load project config: {e}")),
+    };
+    Ok((meta, config))
+}
+
+/// Writes captions as pretty-printed JSON, the layout the desktop app keeps per recording.
+pub fn write_captions_file(path: &Path, captions: &CaptionsData) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create captions directory: {e}"))?;
+    }
+    let json = serde_json::to_string_pretty(captions)
+        .map_err(|e| format!("Failed to serialize captions: {e}"))?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write captions file: {e}"))
+}
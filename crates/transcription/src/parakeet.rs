@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use cap_project::CaptionSegment;
+
+#[cfg(not(all(target_os = "macos", target_arch = "x86_64")))]
+mod imp {
+    use std::{path::Path, sync::Arc};
+
+    use cap_project::{CaptionSegment, CaptionWord};
+    use parakeet_rs::{ParakeetTDT, TimestampMode, Transcriber};
+    use tokio::sync::Mutex;
+
+    use crate::words::{caption_segments, normalize_caption_words};
+
+    struct CachedParakeetContext {
+        model_dir: String,
+        model: Arc<std::sync::Mutex<ParakeetTDT>>,
+    }
+
+    static PARAKEET_CONTEXT: Mutex<Option<CachedParakeetContext>> = Mutex::const_new(None);
+
+    pub(super) fn parakeet_model_dir_matches(cached_model_dir: &str, model_dir: &Path) -> bool {
+        cached_model_dir == model_dir.to_string_lossy()
+    }
+
+    pub async fn invalidate_model(model_dir: &Path) {
+        let mut ctx = PARAKEET_CONTEXT.lock().await;
+        if ctx
+            .as_ref()
+            .is_some_and(|cached| parakeet_model_dir_matches(&cached.model_dir, model_dir))
+        {
+            tracing::info!(
+                "Invalidating cached Parakeet context for {}",
+                model_dir.display()
+            );
+            *ctx = None;
+        }
+    }
+
+    pub async fn release_context() {
+        let mut ctx = PARAKEET_CONTEXT.lock().await;
+        if ctx.is_some() {
+            tracing::info!("Releasing Parakeet context to free memory");
+            *ctx = None;
+        }
+    }
+
+    pub fn transcribe(audio_path: &Path, model_dir: &Path) -> Result<Vec<CaptionSegment>, String> {
+        let model_dir = model_dir.to_string_lossy().to_string();
+        tracing::info!("Parakeet transcription of {audio_path:?} with {model_dir}");
+
+        let cached_model = {
+            let guard = PARAKEET_CONTEXT.blocking_lock();
+            guard
+                .as_ref()
+                .filter(|cached| cached.model_dir == model_dir)
+                .map(|cached| Arc::clone(&cached.model))
+        };
+
+        let model_arc = if let Some(model) = cached_model {
+            tracing::info!("Reusing cached Parakeet TDT model");
+            model
+        } else {
+            tracing::info!("Loading Parakeet TDT model from: {model_dir}");
+            let model =
+                ParakeetTDT::from_pretrained(&model_dir, None).map_err(|e| format!("{e}"))?;
+            let loaded_model = Arc::new(std::sync::Mutex::new(model));
+
+            let mut guard = PARAKEET_CONTEXT.blocking_lock();
+            if let Some(cached) = guard
+                .as_ref()
+                .filter(|cached| cached.model_dir == model_dir)
+            {
+                Arc::clone(&cached.model)
+            } else {
+                *guard = Some(CachedParakeetContext {
+                    model_dir: model_dir.clone(),
+                    model: Arc::clone(&loaded_model),
+                });
+                loaded_model
+            }
+        };
+
+        let result = {
+            let mut parakeet = model_arc
+                .lock()
+                .map_err(|e| format!("Failed to lock Parakeet model: {e}"))?;
+            parakeet
+                .transcribe_file(audio_path, Some(TimestampMode::Words))
+                .map_err(|e| format!("Parakeet transcription failed: {e}"))?
+        };
+
+        let words = normalize_caption_words(
+            result
+                .tokens
+                .iter()
+                .filter(|t| !t.text.trim().is_empty())
+                .map(|t| CaptionWord {
+                    text: t.text.trim().to_string(),
+                    start: t.start,
+                    end: t.end,
+                })
+                .collect(),
+        );
+
+        if words.is_empty() {
+            tracing::warn!("Parakeet produced no words");
+            return Err("No speech detected in the audio".to_string());
+        }
+
+        let segments = caption_segments(&words, "segment-");
+        tracing::info!(
+            "Parakeet produced {} segments, {} words",
+            segments.len(),
+            words.len()
+        );
+
+        Ok(segments)
+    }
+}
+
+#[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+mod imp {
+    use std::path::Path;
+
+    use cap_project::CaptionSegment;
+
+    pub async fn invalidate_model(_model_dir: &Path) {}
+
+    pub async fn release_context() {}
+
+    pub fn transcribe(
+        _audio_path: &Path,
+        _model_dir: &Path,
+    ) -> Result<Vec<CaptionSegment>, String> {
+        Err(crate::PARAKEET_UNSUPPORTED_MESSAGE.to_string())
+    }
+}
+
+/// Drops the cached Parakeet model if it was loaded from `model_dir`, so a re-downloaded or
+/// deleted model isn't reused.
+pub async fn invalidate_parakeet_model(model_dir: &Path) {
+    imp::invalidate_model(model_dir).await;
+}
+
+pub(crate) async fn release_context() {
+    imp::release_context().await;
+}
+
+/// Blocking; run it off the async runtime.
+pub(crate) fn transcribe(
+    audio_path: &Path,
+    model_dir: &Path,
+) -> Result<Vec<CaptionSegment>, String> {
+    imp::transcribe(audio_path, model_dir)
+}
+
+#[cfg(all(test, not(all(target_os = "macos", target_arch = "x86_64"))))]
+mod tests {
+    use super::imp::parakeet_model_dir_matches;
+    use tempfile::tempdir;
+
+    #[test]
+    fn parakeet_model_dir_match_uses_full_directory_path() {
+        let dir = tempdir().unwrap();
+        let model_dir = dir.path().join("models").join("parakeet-best");
+
+        assert!(parakeet_model_dir_matches(
+            model_dir.to_string_lossy().as_ref(),
+            &model_dir
+        ));
+        assert!(!parakeet_model_dir_matches(
+            dir.path()
+                .join("models")
+                .join("parakeet-best-max")
+                .to_string_lossy()
+                .as_ref(),
+            &model_dir
+        ));
+    }
+}
@@ -0,0 +1,242 @@
+use std::{fs::File, io::Read, path::Path, sync::Arc};
+
+use cap_project::{CaptionSegment, CaptionWord};
+use tokio::sync::Mutex;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use crate::{
+    TranscriptionProgress,
+    audio::{WHISPER_SAMPLE_RATE, normalize_audio_for_transcription},
+    words::{caption_segments, is_special_token, normalize_caption_words},
+};
+
+static WHISPER_CONTEXT: Mutex<Option<Arc<WhisperContext>>> = Mutex::const_new(None);
+
+fn get_whisper_context_blocking(model_path: &Path) -> Result<Arc<WhisperContext>, String> {
+    let mut context_guard = WHISPER_CONTEXT.blocking_lock();
+
+    if let Some(ref existing) = *context_guard {
+        tracing::info!("Reusing cached Whisper context");
+        return Ok(existing.clone());
+    }
+
+    tracing::info!("Initializing Whisper context with model: {model_path:?}");
+    let ctx = WhisperContext::new_with_params(
+        &model_path.to_string_lossy(),
+        WhisperContextParameters::default(),
+    )
+    .map_err(|e| format!("Failed to load Whisper model: {e}"))?;
+
+    let ctx_arc = Arc::new(ctx);
+    *context_guard = Some(ctx_arc.clone());
+
+    Ok(ctx_arc)
+}
+
+#[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+fn release_whisper_context_after_transcription() {
+    let mut ctx = WHISPER_CONTEXT.blocking_lock();
+    *ctx = None;
+}
+
+#[cfg(not(all(target_os = "macos", target_arch = "aarch64")))]
+fn release_whisper_context_after_transcription() {}
+
+pub(crate) async fn release_context() {
+    let mut ctx = WHISPER_CONTEXT.lock().await;
+    if ctx.is_some() {
+        tracing::info!("Releasing Whisper context to free memory");
+        *ctx = None;
+    }
+}
+
+fn build_initial_prompt(transcription_hints: &[String]) -> Option<String> {
+    let mut normalized = Vec::new();
+
+    for hint in transcription_hints {
+        let value = hint.replace('\0', "").trim().to_string();
+        if value.is_empty() || normalized.contains(&value) {
+            continue;
+        }
+        normalized.push(value);
+    }
+
+    if normalized.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "Preferred spellings, names, and capitalization for this transcript: {}",
+            normalized.join("; ")
+        ))
+    }
+}
+
+/// Blocking; run it off the async runtime.
+pub(crate) fn transcribe(
+    audio_path: &Path,
+    model_path: &Path,
+    language: &str,
+    transcription_hints: &[String],
+    on_progress: Arc<dyn Fn(TranscriptionProgress) + Send + Sync>,
+) -> Result<Vec<CaptionSegment>, String> {
+    on_progress(TranscriptionProgress::LoadingModel);
+    let context = get_whisper_context_blocking(model_path)
+        .map_err(|e| format!("Failed to initialize transcription model: {e}"))?;
+
+    let result = process_with_whisper(
+        audio_path,
+        &context,
+        language,
+        transcription_hints,
+        on_progress,
+    );
+    release_whisper_context_after_transcription();
+    result
+}
+
+fn process_with_whisper(
+    audio_path: &Path,
+    context: &WhisperContext,
+    language: &str,
+    transcription_hints: &[String],
+    on_progress: Arc<dyn Fn(TranscriptionProgress) + Send + Sync>,
+) -> Result<Vec<CaptionSegment>, String> {
+    tracing::info!("Whisper transcription of {audio_path:?}, language: {language}");
+
+    let mut params = FullParams::new(SamplingStrategy::BeamSearch {
+        beam_size: 5,
+        patience: 1.0,
+    });
+
+    params.set_translate(false);
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_token_timestamps(true);
+    params.set_language(Some(if language == "auto" { "auto" } else { language }));
+    params.set_max_len(i32::MAX);
+    params.set_progress_callback_safe(move |percent: i32| {
+        on_progress(TranscriptionProgress::Transcribing {
+            progress: f64::from(percent.clamp(0, 100)) / 100.0,
+        });
+    });
+
+    let initial_prompt = build_initial_prompt(transcription_hints);
+    if let Some(initial_prompt) = &initial_prompt {
+        params.set_initial_prompt(initial_prompt);
+    }
+
+    let mut audio_file = File::open(audio_path)
+        .map_err(|e| format!("Failed to open audio file: {e} at path: {audio_path:?}"))?;
+    let mut audio_data = Vec::new();
+    audio_file
+        .read_to_end(&mut audio_data)
+        .map_err(|e| format!("Failed to read audio file: {e}"))?;
+
+    let mut audio_data_f32 = audio_data
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]) as f32 / 32768.0)
+        .collect::<Vec<_>>();
+
+    let gain = normalize_audio_for_transcription(&mut audio_data_f32);
+    if (gain - 1.0).abs() > 0.01 {
+        tracing::info!("Applied Whisper input gain: {gain:.2}x");
+    }
+
+    tracing::info!(
+        "Transcribing {:.2}s of audio",
+        audio_data_f32.len() as f32 / WHISPER_SAMPLE_RATE as f32
+    );
+
+    let mut state = context
+        .create_state()
+        .map_err(|e| format!("Failed to create Whisper state: {e}"))?;
+
+    state
+        .full(params, &audio_data_f32[..])
+        .map_err(|e| format!("Failed to run Whisper transcription: {e}"))?;
+
+    let num_segments = state
+        .full_n_segments()
+        .map_err(|e| format!("Failed to get number of segments: {e}"))?;
+
+    let mut segments = Vec::new();
+
+    for i in 0..num_segments {
+        let start_i64 = state
+            .full_get_segment_t0(i)
+            .map_err(|e| format!("Failed to get segment start time: {e}"))?;
+        let start_time = (start_i64 as f32) / 100.0;
+
+        let num_tokens = state
+            .full_n_tokens(i)
+            .map_err(|e| format!("Failed to get token count: {e}"))?;
+
+        let mut words = Vec::new();
+        let mut current_word = String::new();
+        let mut word_start: Option<f32> = None;
+        let mut word_end: f32 = start_time;
+
+        for t in 0..num_tokens {
+            let token_text = state.full_get_token_text(i, t).unwrap_or_default();
+
+            if is_special_token(&token_text) {
+                continue;
+            }
+
+            let Ok(data) = state.full_get_token_data(i, t) else {
+                tracing::warn!("Token {t} of segment {i} ({token_text:?}) has no timing data");
+                continue;
+            };
+
+            let token_start = (data.t0 as f32) / 100.0;
+            let token_end = (data.t1 as f32) / 100.0;
+
+            if token_text.starts_with(' ') || token_text.starts_with('\n') {
+                if !current_word.is_empty()
+                    && let Some(ws) = word_start
+                {
+                    words.push(CaptionWord {
+                        text: current_word.trim().to_string(),
+                        start: ws,
+                        end: word_end,
+                    });
+                }
+                current_word = token_text.trim().to_string();
+                word_start = Some(token_start);
+            } else {
+                if word_start.is_none() {
+                    word_start = Some(token_start);
+                }
+                current_word.push_str(&token_text);
+            }
+            word_end = token_end;
+        }
+
+        if !current_word.trim().is_empty()
+            && let Some(ws) = word_start
+        {
+            words.push(CaptionWord {
+                text: current_word.trim().to_string(),
+                start: ws,
+                end: word_end,
+            });
+        }
+
+        let words = normalize_caption_words(words);
+        if words.is_empty() {
+            tracing::warn!("Segment {i} has no words, skipping");
+            continue;
+        }
+
+        segments.extend(caption_segments(&words, &format!("segment-{i}-")));
+    }
+
+    tracing::info!(
+        "Whisper produced {} segments, {} words",
+        segments.len(),
+        segments.iter().map(|s| s.words.len()).sum::<usize>()
+    );
+
+    Ok(segments)
+}